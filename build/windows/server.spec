@@ -34,6 +34,9 @@ hiddenimports += collect_submodules("sniffio")
 hiddenimports += collect_submodules("websockets")
 hiddenimports += collect_submodules("wsproto")
 hiddenimports += collect_submodules("h11")
+hiddenimports += collect_submodules("msgpack")
+hiddenimports += collect_submodules("zeroconf")
+hiddenimports += collect_submodules("cryptography")
 
 # Windows 托盘后端
 hiddenimports += ["pystray._win32"]
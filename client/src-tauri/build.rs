@@ -1,7 +1,15 @@
 fn main() {
     #[cfg(target_os = "windows")]
     ensure_windows_icon();
-    tauri_build::build()
+
+    let mut attributes = tauri_build::Attributes::new();
+    // ui-access 版本需要签名并安装到 Program Files 等受信任目录，否则 Windows 拒绝启动
+    if std::env::var_os("CARGO_FEATURE_UI_ACCESS").is_some() {
+        attributes = attributes.windows_attributes(
+            tauri_build::WindowsAttributes::new().app_manifest(include_str!("windows-uiaccess.manifest")),
+        );
+    }
+    tauri_build::try_build(attributes).expect("failed to run tauri build");
 }
 
 #[cfg(target_os = "windows")]
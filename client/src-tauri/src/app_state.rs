@@ -1,27 +1,378 @@
-use std::sync::Mutex;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use tauri::async_runtime::JoinHandle;
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use tokio::sync::Mutex as AsyncMutex;
 
+use crate::asr::AsrContext;
 use crate::audio::AudioRecorder;
-use crate::pipeline::Pipeline;
+use crate::config::{self, ClientConfig};
+use crate::error::{AppError, ErrorCode};
+use crate::events::{EventSender, LateCorrection};
+use crate::meeting::{self, MeetingRecorder};
+use crate::pipeline::{Pipeline, PreemptHandle};
+use crate::rules::{RuleSet, TextRule};
+
+/// 已松开热键、等待识别的会话上限，超出后新的热键按下提示忙碌
+const MAX_PENDING_SESSIONS: usize = 2;
+
+/// 上一次会话还在识别时按下热键的处理方式；两种方式都会立即开始录音
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyPolicy {
+    /// 排队：上一次的结果照常输出，新会话在其后识别
+    #[default]
+    Queue,
+    /// 抢占：丢弃上一次尚未输出的结果
+    Preempt,
+}
+
+/// 一次录音的用途
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionMode {
+    /// 听写：识别结果输入到当前焦点
+    #[default]
+    Dictation,
+    /// 改写选中的文字：说出的是改写指令
+    Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionPhase {
+    Idle,
+    /// 正在录音（之前的会话可能仍在识别）
+    Recording,
+    /// 没有录音，仍有会话在识别
+    Processing,
+}
+
+/// 热键按下时状态机给出的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartAction {
+    /// 空闲，直接开始
+    Begin,
+    /// 排在识别中的会话之后
+    Queue,
+    /// 丢弃识别中的会话结果后开始
+    Preempt,
+    /// 已在录音（按键重复），忽略
+    Ignore,
+    /// 等待识别的会话太多，拒绝
+    Busy,
+}
+
+/// 会话状态机：热键事件与会话 worker 都只通过它改变状态，录音与识别可以重叠但不会乱序
+#[derive(Debug)]
+pub struct SessionMachine {
+    policy: BusyPolicy,
+    recording: bool,
+    /// 已结束录音、尚未识别完成的会话数
+    pending: usize,
+}
+
+impl SessionMachine {
+    pub fn new(policy: BusyPolicy) -> Self {
+        Self {
+            policy,
+            recording: false,
+            pending: 0,
+        }
+    }
+
+    pub fn phase(&self) -> SessionPhase {
+        if self.recording {
+            SessionPhase::Recording
+        } else if self.pending > 0 {
+            SessionPhase::Processing
+        } else {
+            SessionPhase::Idle
+        }
+    }
+
+    pub fn start(&mut self) -> StartAction {
+        let action = match (self.recording, self.pending, self.policy) {
+            (true, _, _) => StartAction::Ignore,
+            (false, 0, _) => StartAction::Begin,
+            (false, pending, _) if pending >= MAX_PENDING_SESSIONS => StartAction::Busy,
+            (false, _, BusyPolicy::Queue) => StartAction::Queue,
+            (false, _, BusyPolicy::Preempt) => StartAction::Preempt,
+        };
+        if matches!(action, StartAction::Begin | StartAction::Queue | StartAction::Preempt) {
+            self.recording = true;
+        }
+        action
+    }
+
+    /// 录音结束（松开热键，或会话未能开始）；没有在录音时返回 false
+    pub fn stop(&mut self) -> bool {
+        if !self.recording {
+            return false;
+        }
+        self.recording = false;
+        self.pending += 1;
+        true
+    }
+
+    /// 录音未能开始（例如麦克风失败），回到按下热键之前的状态
+    pub fn abort(&mut self) {
+        self.recording = false;
+    }
+
+    /// worker 处理完一个会话
+    pub fn finished(&mut self) -> SessionPhase {
+        self.pending = self.pending.saturating_sub(1);
+        self.phase()
+    }
+}
+
+/// 交给会话 worker 的一次录音；worker 按热键顺序逐个识别，`frames` 在松开热键后关闭
+pub struct SessionJob {
+    pub trace_id: String,
+    pub sample_rate: u32,
+    pub context: AsrContext,
+    pub mode: SessionMode,
+    pub frames: mpsc::UnboundedReceiver<Vec<i16>>,
+    /// 录音被取消：worker 不请求识别结果，也不输出
+    pub cancelled: Arc<AtomicBool>,
+}
 
 pub struct AppState {
     pub audio: Mutex<Option<AudioRecorder>>,
-    pub audio_task: Mutex<Option<JoinHandle<()>>>,
-    pub session_gen: Mutex<Option<u64>>,
+    /// 正在录音的会话的取消标记，松开热键后清空
+    pub recording_cancel: Mutex<Option<Arc<AtomicBool>>>,
+    pub session: Mutex<SessionMachine>,
+    pub sessions: mpsc::UnboundedSender<SessionJob>,
+    pub preempt: PreemptHandle,
     pub pipeline: AsyncMutex<Pipeline>,
     pub audio_device: Option<String>,
+    pub events: EventSender,
+    /// 禁止听写的应用模式，保存配置后立即生效
+    blocked_apps: Mutex<Vec<String>>,
+    /// 暂停时忽略热键，不会开始新的录音
+    paused: AtomicBool,
+    /// 识别超时（毫秒），0 表示不限；保存配置后立即生效
+    processing_timeout_ms: AtomicU64,
+    /// 正在进行的会议转写，与热键听写互不影响
+    meeting: AsyncMutex<Option<MeetingRecorder>>,
 }
 
 impl AppState {
-    pub fn new(pipeline: Pipeline, audio_device: Option<String>) -> Self {
+    pub fn new(
+        pipeline: Pipeline,
+        audio_device: Option<String>,
+        events: EventSender,
+        busy_policy: BusyPolicy,
+        processing_timeout_ms: u64,
+        blocked_apps: Vec<String>,
+        sessions: mpsc::UnboundedSender<SessionJob>,
+    ) -> Self {
         Self {
             audio: Mutex::new(None),
-            audio_task: Mutex::new(None),
-            session_gen: Mutex::new(None),
+            recording_cancel: Mutex::new(None),
+            session: Mutex::new(SessionMachine::new(busy_policy)),
+            sessions,
+            preempt: pipeline.preempt_handle(),
             pipeline: AsyncMutex::new(pipeline),
             audio_device,
+            events,
+            blocked_apps: Mutex::new(blocked_apps),
+            paused: AtomicBool::new(false),
+            processing_timeout_ms: AtomicU64::new(processing_timeout_ms),
+            meeting: AsyncMutex::new(None),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// 一个会话从松开热键到识别完成的时限；为空时不限
+    pub fn processing_timeout(&self) -> Option<Duration> {
+        match self.processing_timeout_ms.load(Ordering::SeqCst) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+
+    /// 会议转写是否在进行；正在开始或结束时也算在进行
+    pub fn is_meeting_active(&self) -> bool {
+        match self.meeting.try_lock() {
+            Ok(meeting) => meeting.as_ref().is_some_and(|recorder| !recorder.is_finished()),
+            Err(_) => true,
         }
     }
+
+    /// 开始或结束会议转写；开始时返回转写文件，结束时返回 `None`
+    pub async fn toggle_meeting(&self, config: &ClientConfig) -> anyhow::Result<Option<PathBuf>> {
+        let mut meeting = self.meeting.lock().await;
+        if let Some(recorder) = meeting.take() {
+            let finished = recorder.is_finished();
+            recorder.stop().await;
+            // 录音中断等原因已自行结束的，这次重新开始
+            if !finished {
+                return Ok(None);
+            }
+        }
+
+        let recorder = meeting::start(
+            &config.meeting,
+            &config.asr,
+            self.audio_device.clone(),
+            self.events.clone(),
+        )
+        .await?;
+        let path = recorder.path.clone();
+        *meeting = Some(recorder);
+        Ok(Some(path))
+    }
+
+    /// 退出前调用：不再开始新的录音，丢弃正在进行的录音，结束会议转写（已转写的内容照常保存），
+    /// 取消识别中的会话
+    pub async fn shutdown(&self) {
+        self.set_paused(true);
+        if let Some(cancelled) = self.recording_cancel.lock().expect("recording cancel lock").take() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+        if let Some(recorder) = self.audio.lock().expect("audio lock").take() {
+            recorder.stop();
+        }
+        if let Some(recorder) = self.meeting.lock().await.take() {
+            recorder.stop().await;
+        }
+        self.preempt.preempt();
+        let mut pipeline = self.pipeline.lock().await;
+        if pipeline.is_active() {
+            pipeline.cancel().await;
+        }
+    }
+
+    /// 目标应用在 `blocked_apps` 中时不开始录音
+    pub fn is_app_blocked(&self, app_name: &str) -> bool {
+        config::is_app_blocked(&self.blocked_apps.lock().expect("blocked apps lock"), app_name)
+    }
+
+    /// 切换 ASR 配置并立即替换引擎，成功后写回配置文件
+    pub async fn switch_asr_profile(&self, name: &str) -> anyhow::Result<ClientConfig> {
+        if self.audio.lock().expect("audio lock").is_some() {
+            return Err(AppError::new(ErrorCode::Busy, "录音中，无法切换 ASR 配置").into());
+        }
+
+        let (mut config, path) = config::load_with_path();
+        config::select_asr_profile(&mut config, name).map_err(AppError::with_code(ErrorCode::NotFound))?;
+        self.pipeline
+            .lock()
+            .await
+            .replace_asr(&config.asr)
+            .map_err(AppError::with_code(ErrorCode::Config))?;
+        config::save_to_path(&config, path).map_err(AppError::with_code(ErrorCode::Config))?;
+        Ok(config)
+    }
+
+    /// 保存后处理规则并立即生效；有无效规则时整组不保存
+    pub async fn save_rules(&self, rules: Vec<TextRule>) -> anyhow::Result<()> {
+        let compiled = RuleSet::compile(&rules).map_err(AppError::with_code(ErrorCode::Config))?;
+        let (mut config, path) = config::load_with_path();
+        config.rules = rules;
+        config::save_to_path(&config, path).map_err(AppError::with_code(ErrorCode::Config))?;
+        self.pipeline.lock().await.set_rules(compiled);
+        Ok(())
+    }
+
+    /// 把保存的配置应用到运行中的 Pipeline；录音中时返回 false，会话结束后自动应用
+    pub async fn apply_config(&self, config: &ClientConfig) -> anyhow::Result<bool> {
+        self.session.lock().expect("session lock").policy = config.busy_policy;
+        self.processing_timeout_ms.store(config.processing_timeout_ms, Ordering::SeqCst);
+        *self.blocked_apps.lock().expect("blocked apps lock") = config.blocked_apps.clone();
+        self.pipeline
+            .lock()
+            .await
+            .reconfigure(config)
+            .context("配置已保存，但未能应用（重启客户端后生效）")
+    }
+
+    /// 把上一次的结果重新输入到当前焦点窗口
+    pub async fn retype_last(&self) -> anyhow::Result<String> {
+        if self.audio.lock().expect("audio lock").is_some() {
+            return Err(AppError::new(ErrorCode::Busy, "录音中，无法重新输入").into());
+        }
+
+        let app_name = active_win_pos_rs::get_active_window()
+            .map(|window| window.app_name)
+            .unwrap_or_default();
+        self.pipeline.lock().await.retype_last(&app_name).await
+    }
+
+    /// 回删当前焦点应用中 GhostType 最近 `count` 次输入的文字，返回被删掉的文字
+    pub async fn undo_last(&self, count: usize) -> anyhow::Result<String> {
+        if self.audio.lock().expect("audio lock").is_some() {
+            return Err(AppError::new(ErrorCode::Busy, "录音中，无法撤销").into());
+        }
+
+        let app_name = active_win_pos_rs::get_active_window()
+            .map(|window| window.app_name)
+            .unwrap_or_default();
+        self.pipeline.lock().await.undo_last(&app_name, count).await
+    }
+
+    /// 在用户确认后应用迟到的校正；焦点已不在原应用时不输入，由调用方改为复制
+    pub async fn apply_late_correction(&self, late: &LateCorrection) -> anyhow::Result<()> {
+        if self.audio.lock().expect("audio lock").is_some() {
+            return Err(AppError::new(ErrorCode::Busy, "录音中，无法替换").into());
+        }
+
+        let app_name = active_win_pos_rs::get_active_window()
+            .map(|window| window.app_name)
+            .unwrap_or_default();
+        if app_name != late.app_name {
+            return Err(AppError::new(ErrorCode::NotFound, format!("焦点已切换到 {app_name}")).into());
+        }
+        self.pipeline.lock().await.apply_late_correction(late).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_machine_queues_or_preempts_while_processing() {
+        let mut machine = SessionMachine::new(BusyPolicy::Queue);
+        assert_eq!(machine.phase(), SessionPhase::Idle);
+        assert!(!machine.stop());
+        assert_eq!(machine.start(), StartAction::Begin);
+        assert_eq!(machine.start(), StartAction::Ignore);
+        assert!(machine.stop());
+        assert_eq!(machine.phase(), SessionPhase::Processing);
+
+        // 识别中再次按下：立即录音，排在后面
+        assert_eq!(machine.start(), StartAction::Queue);
+        assert_eq!(machine.phase(), SessionPhase::Recording);
+        assert!(machine.stop());
+        assert_eq!(machine.start(), StartAction::Busy);
+        assert_eq!(machine.phase(), SessionPhase::Processing);
+        assert_eq!(machine.finished(), SessionPhase::Processing);
+        assert_eq!(machine.finished(), SessionPhase::Idle);
+
+        // 录音中识别完成时仍保持录音
+        machine.policy = BusyPolicy::Preempt;
+        assert_eq!(machine.start(), StartAction::Begin);
+        assert!(machine.stop());
+        assert_eq!(machine.start(), StartAction::Preempt);
+        assert_eq!(machine.finished(), SessionPhase::Recording);
+        assert!(machine.stop());
+        assert_eq!(machine.finished(), SessionPhase::Idle);
+
+        machine.start();
+        machine.abort();
+        assert_eq!(machine.phase(), SessionPhase::Idle);
+    }
 }
@@ -0,0 +1,207 @@
+use std::fmt;
+
+use anyhow::Context as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use crate::network::ServerCapabilities;
+
+/// 密钥派生的 HKDF info，协议不兼容地变更时换版本号
+const HKDF_INFO: &[u8] = b"ghosttype-e2e-v1";
+
+/// 加密帧：8 字节大端计数器 + 密文（含 16 字节 tag）
+const COUNTER_LEN: usize = 8;
+
+/// 端到端加密设置，来自 ASR 配置
+#[derive(Debug, Clone, Default)]
+pub struct E2eConfig {
+    pub enabled: bool,
+    /// 固定的服务器公钥（base64）；设置后可防止中间的代理替换公钥
+    pub pinned_key: Option<String>,
+}
+
+/// 开启了端到端加密但无法安全地建立：会话直接失败，不退回明文
+#[derive(Debug)]
+pub(super) struct Refused(&'static str);
+
+impl fmt::Display for Refused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Refused {}
+
+/// 从服务器能力中取出用于交换的公钥，并按配置校验固定公钥
+pub(super) fn server_key<'a>(config: &E2eConfig, capabilities: &'a ServerCapabilities) -> anyhow::Result<&'a str> {
+    let key = match capabilities.e2e_key.as_deref() {
+        Some(key) if capabilities.supports(ServerCapabilities::E2E) => key,
+        _ => {
+            return Err(Refused("服务器不支持端到端加密 | Server does not support end-to-end encryption").into());
+        }
+    };
+    match config.pinned_key.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        Some(pinned) if pinned != key.trim() => {
+            Err(Refused("服务器公钥与固定值不符 | Server e2e key does not match the pinned key").into())
+        }
+        _ => Ok(key),
+    }
+}
+
+/// 客户端发往服务器方向的加密器（音频帧、start 中的上下文）
+pub(super) struct Sealer {
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+}
+
+/// 服务器发来的会话事件的解密器；计数器必须递增，拒绝重放
+pub(super) struct Opener {
+    cipher: ChaCha20Poly1305,
+    last: Option<u64>,
+}
+
+/// 与服务器公钥（pong 中的 `e2e_key`）做一次 X25519 交换，返回本端公钥（base64）和两个方向的密钥。
+/// 每个会话都用新的临时密钥，`trace_id` 作为 HKDF salt。
+pub(super) fn handshake(server_key: &str, trace_id: &str) -> anyhow::Result<(String, Sealer, Opener)> {
+    let server_key: [u8; 32] = BASE64
+        .decode(server_key.trim())
+        .context("decode server e2e key")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("server e2e key must be 32 bytes"))?;
+
+    let secret = EphemeralSecret::random();
+    let public = PublicKey::from(&secret);
+    let shared = secret.diffie_hellman(&PublicKey::from(server_key));
+    if !shared.was_contributory() {
+        anyhow::bail!("invalid server e2e key");
+    }
+
+    let (to_server, to_client) = derive_keys(shared.as_bytes(), trace_id);
+    Ok((
+        BASE64.encode(public.as_bytes()),
+        Sealer {
+            cipher: ChaCha20Poly1305::new(&to_server),
+            counter: 0,
+        },
+        Opener {
+            cipher: ChaCha20Poly1305::new(&to_client),
+            last: None,
+        },
+    ))
+}
+
+/// 前 32 字节用于客户端→服务器，后 32 字节用于服务器→客户端，两个方向的计数器互不冲突
+fn derive_keys(shared: &[u8], trace_id: &str) -> (Key, Key) {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha256>::new(Some(trace_id.as_bytes()), shared)
+        .expand(HKDF_INFO, &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA256 output length");
+    (*Key::from_slice(&okm[..32]), *Key::from_slice(&okm[32..]))
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce.into()
+}
+
+impl Sealer {
+    pub fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let counter = self.counter;
+        self.counter += 1;
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce(counter), plaintext)
+            .expect("chacha20poly1305 encryption is infallible for in-memory buffers");
+        let mut frame = Vec::with_capacity(COUNTER_LEN + ciphertext.len());
+        frame.extend_from_slice(&counter.to_be_bytes());
+        frame.extend_from_slice(&ciphertext);
+        frame
+    }
+}
+
+impl Opener {
+    pub fn open(&mut self, frame: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if frame.len() < COUNTER_LEN {
+            anyhow::bail!("e2e frame too short");
+        }
+        let (counter, ciphertext) = frame.split_at(COUNTER_LEN);
+        let counter = u64::from_be_bytes(counter.try_into().expect("8-byte counter"));
+        if self.last.is_some_and(|last| counter <= last) {
+            anyhow::bail!("e2e frame replayed (counter {counter})");
+        }
+        let plaintext = self
+            .cipher
+            .decrypt(&nonce(counter), ciphertext)
+            .map_err(|_| anyhow::anyhow!("e2e frame authentication failed"))?;
+        self.last = Some(counter);
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_keys_match_server_side_derivation() {
+        let server_secret = EphemeralSecret::random();
+        let server_public = BASE64.encode(PublicKey::from(&server_secret).as_bytes());
+
+        let (client_public, mut sealer, mut opener) = handshake(&server_public, "t1").expect("handshake");
+
+        // 服务端：同样的交换与派生，方向相反
+        let client_public: [u8; 32] = BASE64.decode(client_public).expect("b64").try_into().expect("32");
+        let shared = server_secret.diffie_hellman(&PublicKey::from(client_public));
+        let (to_server, to_client) = derive_keys(shared.as_bytes(), "t1");
+        let mut server_opener = Opener {
+            cipher: ChaCha20Poly1305::new(&to_server),
+            last: None,
+        };
+        let mut server_sealer = Sealer {
+            cipher: ChaCha20Poly1305::new(&to_client),
+            counter: 0,
+        };
+
+        let first = sealer.seal(b"opus-1");
+        let second = sealer.seal(b"opus-2");
+        assert_eq!(&first[..COUNTER_LEN], &[0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(server_opener.open(&first).expect("open"), b"opus-1");
+        assert_eq!(server_opener.open(&second).expect("open"), b"opus-2");
+        // 重放与篡改都被拒绝
+        assert!(server_opener.open(&second).is_err());
+        let mut tampered = sealer.seal(b"opus-3");
+        *tampered.last_mut().expect("tag") ^= 1;
+        assert!(server_opener.open(&tampered).is_err());
+
+        let event = server_sealer.seal(br#"{"type":"fast_text","content":"hi"}"#);
+        assert_eq!(opener.open(&event).expect("open"), br#"{"type":"fast_text","content":"hi"}"#);
+
+        assert!(handshake("AAAA", "t1").is_err());
+    }
+
+    #[test]
+    fn server_key_requires_feature_and_matching_pin() {
+        let mut caps = ServerCapabilities {
+            features: vec![ServerCapabilities::E2E.to_string()],
+            e2e_key: Some("a2V5".to_string()),
+            ..ServerCapabilities::default()
+        };
+        let mut config = E2eConfig {
+            enabled: true,
+            pinned_key: None,
+        };
+        assert_eq!(server_key(&config, &caps).expect("key"), "a2V5");
+        config.pinned_key = Some(" a2V5 ".to_string());
+        assert!(server_key(&config, &caps).is_ok());
+        config.pinned_key = Some("b3RoZXI=".to_string());
+        assert!(server_key(&config, &caps).expect_err("pin").is::<Refused>());
+        caps.features.clear();
+        assert!(server_key(&E2eConfig::default(), &caps).expect_err("feature").is::<Refused>());
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+/// 收到 stop 后的答复
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    Text {
+        content: String,
+        confidence: Option<f32>,
+        language: Option<String>,
+    },
+    Error(String),
+    /// 先回识别结果，再回 LLM 校正（`correction`）
+    Corrected {
+        content: String,
+        corrected: String,
+    },
+    /// 不答复，用于测试等待结果超时
+    Silent,
+}
+
+impl MockReply {
+    pub fn text(content: impl Into<String>) -> Self {
+        MockReply::Text {
+            content: content.into(),
+            confidence: None,
+            language: None,
+        }
+    }
+}
+
+/// 服务端收到的控制消息与音频帧
+#[derive(Debug, Default)]
+pub struct Received {
+    pub starts: Vec<Value>,
+    /// 每个会话收到的音频帧数，与 `starts` 一一对应
+    pub frames: Vec<usize>,
+    pub stops: Vec<Value>,
+    pub cancels: Vec<Value>,
+}
+
+/// 测试用 ASR 服务端：在 127.0.0.1 的随机端口上实现 GhostType 协议
+/// （ping/pong、start/stop/cancel、fast_text、correction、error），供测试驱动真实的 WebSocket 引擎
+pub struct MockAsrServer {
+    pub endpoint: String,
+    reply: Arc<Mutex<MockReply>>,
+    received: Arc<Mutex<Received>>,
+}
+
+impl MockAsrServer {
+    /// `features` 随 pong 声明，例如 `ServerCapabilities::SEQ_FRAMES`
+    pub async fn spawn(features: &[&str], reply: MockReply) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock asr server");
+        let endpoint = format!("ws://{}/ws", listener.local_addr().expect("mock asr addr"));
+        let pong = json!({ "type": "pong", "features": features }).to_string();
+        let reply = Arc::new(Mutex::new(reply));
+        let received = Arc::new(Mutex::new(Received::default()));
+
+        let (reply_for_task, received_for_task) = (reply.clone(), received.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(
+                    stream,
+                    pong.clone(),
+                    reply_for_task.clone(),
+                    received_for_task.clone(),
+                ));
+            }
+        });
+        Self {
+            endpoint,
+            reply,
+            received,
+        }
+    }
+
+    /// 之后的会话改用新的答复
+    pub fn set_reply(&self, reply: MockReply) {
+        *self.reply.lock().expect("mock reply lock") = reply;
+    }
+
+    pub fn received(&self) -> MutexGuard<'_, Received> {
+        self.received.lock().expect("mock received lock")
+    }
+}
+
+async fn serve(stream: TcpStream, pong: String, reply: Arc<Mutex<MockReply>>, received: Arc<Mutex<Received>>) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let mut trace_id: Option<String> = None;
+    let mut msgpack = false;
+    while let Some(Ok(message)) = ws.next().await {
+        let events = match message {
+            Message::Text(text) => {
+                let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                match value["type"].as_str() {
+                    // pong 始终是 JSON 文本
+                    Some("ping") => {
+                        let _ = ws.send(Message::Text(pong.clone())).await;
+                        continue;
+                    }
+                    Some("start") => {
+                        trace_id = value["trace_id"].as_str().map(str::to_string);
+                        msgpack = value["wire_format"] == "msgpack";
+                        let mut received = received.lock().expect("mock received lock");
+                        received.starts.push(value);
+                        received.frames.push(0);
+                        Vec::new()
+                    }
+                    Some("stop") => {
+                        received.lock().expect("mock received lock").stops.push(value);
+                        let reply = reply.lock().expect("mock reply lock").clone();
+                        match reply {
+                            MockReply::Text {
+                                content,
+                                confidence,
+                                language,
+                            } => vec![json!({
+                                "type": "fast_text",
+                                "trace_id": trace_id,
+                                "content": content,
+                                "is_final": true,
+                                "confidence": confidence,
+                                "language": language,
+                            })],
+                            MockReply::Error(message) => vec![json!({
+                                "type": "error",
+                                "trace_id": trace_id,
+                                "message": message,
+                            })],
+                            MockReply::Corrected { content, corrected } => vec![
+                                json!({
+                                    "type": "fast_text",
+                                    "trace_id": trace_id,
+                                    "content": content,
+                                    "is_final": true,
+                                }),
+                                json!({
+                                    "type": "correction",
+                                    "trace_id": trace_id,
+                                    "original_text": content,
+                                    "replaced_text": corrected,
+                                    "delete_count": content.chars().count(),
+                                }),
+                            ],
+                            MockReply::Silent => Vec::new(),
+                        }
+                    }
+                    Some("cancel") => {
+                        received.lock().expect("mock received lock").cancels.push(value);
+                        Vec::new()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            Message::Binary(_) => {
+                if let Some(frames) = received.lock().expect("mock received lock").frames.last_mut() {
+                    *frames += 1;
+                }
+                Vec::new()
+            }
+            Message::Close(_) => break,
+            _ => Vec::new(),
+        };
+        for event in events {
+            let message = if msgpack {
+                Message::Binary(rmp_serde::to_vec_named(&event).expect("msgpack"))
+            } else {
+                Message::Text(event.to_string())
+            };
+            if ws.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
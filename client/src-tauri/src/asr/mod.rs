@@ -1,8 +1,19 @@
+mod e2e;
+#[cfg(test)]
+pub(crate) mod mock_server;
+mod race;
+mod selector;
 mod websocket;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use tokio::sync::mpsc;
 
+use crate::input_field::InputField;
+use crate::network::{ConnectionManager, TrafficStats};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -11,13 +22,26 @@ pub struct AsrContext {
     pub app_name: String,
     #[serde(default)]
     pub window_title: String,
+    /// 期望识别语言（来自按应用覆盖），为空时由服务端自动判断
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 请求服务器做 LLM 校正（服务器声明支持 `llm` 时才生效）
+    #[serde(default)]
+    pub server_correction: bool,
+    /// 焦点输入控件（角色、占位文字），拿不到时为空
+    #[serde(default)]
+    pub field: Option<InputField>,
+    /// 快慢两路识别时同时启动质量引擎；分段输出等用不上替换结果的会话不启动
+    #[serde(default)]
+    pub quality_pass: bool,
 }
 
-/// ASR 事件（为未来流式识别预留）
+/// ASR 事件（为未来流式识别预留）；`Correction` 是服务端 LLM 校正后的文字，在 `Final` 之后到达
 #[derive(Debug, Clone)]
 pub enum AsrEvent {
     Partial { text: String },
     Final { text: String },
+    Correction { text: String },
     Error { message: String },
 }
 
@@ -26,11 +50,110 @@ pub trait AsrEngine: Send {
     async fn start(&mut self, trace_id: String, sample_rate: u32, context: AsrContext) -> anyhow::Result<()>;
     async fn feed_audio(&mut self, pcm: &[i16]) -> anyhow::Result<()>;
     async fn stop(&mut self) -> anyhow::Result<String>;
+    /// 放弃本次会话：不请求识别结果，也不留下待补交的会话
+    async fn cancel(&mut self);
 
     fn events(&mut self) -> &mut mpsc::Receiver<AsrEvent>;
+
+    /// 需要长连接的引擎返回其连接管理器，供界面显示连接状态
+    fn connection(&self) -> Option<Arc<ConnectionManager>> {
+        None
+    }
+
+    /// `stop` 因服务器不可达失败时，取出暂存的会话留待连接恢复后提交
+    fn take_deferred(&mut self) -> Option<Box<dyn DeferredSession>> {
+        None
+    }
+
+    /// 取出上一次识别结果的置信度（0~1）；后端不提供时返回 `None`
+    fn take_confidence(&mut self) -> Option<f32> {
+        None
+    }
+
+    /// 取出上一次识别结果的语言代码（如 "zh"、"en"）；后端不提供时返回 `None`
+    fn take_language(&mut self) -> Option<String> {
+        None
+    }
+
+    /// 取出上一次会话的网络用量；不走网络的引擎返回 `None`
+    fn take_traffic(&mut self) -> Option<TrafficStats> {
+        None
+    }
+
+    /// 取出上一次会话待到达的服务端校正；服务器未负责校正、也未开启收听窗口时返回 `None`
+    fn take_server_correction(&mut self) -> Option<Box<dyn ServerCorrection>> {
+        None
+    }
+
+    /// 取出上一次会话质量引擎尚未返回的识别结果；没有配置或未启动第二路识别时返回 `None`
+    fn take_quality_result(&mut self) -> Option<Box<dyn QualityResult>> {
+        None
+    }
+}
+
+/// 快慢两路识别中质量引擎的识别结果，在快速结果输出之后到达
+#[async_trait]
+pub trait QualityResult: Send {
+    async fn wait(self: Box<Self>) -> anyhow::Result<String>;
+}
+
+/// 服务器在识别结果之后发来的 LLM 校正；未取走前占用服务器连接上的会话
+#[async_trait]
+pub trait ServerCorrection: Send {
+    /// 等待校正后的文字；只是在收听窗口内没有等到时返回 `None`，请求过的校正报错、断线或超时时返回错误
+    async fn wait(self: Box<Self>) -> anyhow::Result<Option<String>>;
+}
+
+/// 服务器不可达时暂存在本地的会话（已编码的音频）
+#[async_trait]
+pub trait DeferredSession: Send {
+    fn trace_id(&self) -> &str;
+
+    /// 等待连接恢复后提交，返回识别结果与补交产生的网络用量；中途断线会在重连后重新提交
+    async fn submit(self: Box<Self>) -> (anyhow::Result<String>, TrafficStats);
+}
+
+/// 服务端会话事件（ack、识别结果、错误等）的编码；客户端发出的控制消息始终是 JSON 文本
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    /// 服务器声明支持时使用 MessagePack 二进制帧，体积更小
+    Msgpack,
+}
+
+impl WireFormat {
+    pub fn is_json(&self) -> bool {
+        *self == WireFormat::Json
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// 音频帧的编码
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    #[default]
+    Opus,
+    /// 不编码，直接发送 16 位小端 PCM（千兆局域网中省去编码的 CPU 与延迟）；服务器不支持时退回 Opus
+    PcmS16le,
+}
+
+impl AudioCodec {
+    pub fn is_opus(&self) -> bool {
+        *self == AudioCodec::Opus
+    }
+
+    /// 协议中（`pong.codecs` 与 `start.codec`）使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "opus",
+            AudioCodec::PcmS16le => "pcm_s16le",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AsrConfig {
     /// 系统原生 ASR（不同平台使用不同实现）
@@ -44,18 +167,90 @@ pub enum AsrConfig {
     },
     /// 自建服务端（WebSocket）
     #[serde(rename = "websocket", alias = "web_socket")]
-    WebSocket { endpoint: String },
+    WebSocket {
+        endpoint: String,
+        /// 备用服务器；配置后按延迟为新会话选择最快的可用服务器
+        #[serde(default)]
+        extra_endpoints: Vec<String>,
+        /// 会话开始时等待连接可用的超时
+        #[serde(default = "default_connect_timeout_ms")]
+        connect_timeout_ms: u64,
+        /// 等待结果时两条服务端消息之间的最长间隔
+        #[serde(default = "default_read_timeout_ms")]
+        read_timeout_ms: u64,
+        /// 发送 stop 后等待最终结果的总时长，超时则本次会话失败
+        #[serde(default = "default_stop_timeout_ms")]
+        stop_timeout_ms: u64,
+        /// 识别结果之后继续接收同一会话的校正或更新结果的时长，0 为不等待
+        #[serde(default)]
+        late_event_window_ms: u64,
+        #[serde(default)]
+        wire_format: WireFormat,
+        /// 音频编码，`pcm_s16le` 适合局域网部署
+        #[serde(default)]
+        codec: AudioCodec,
+        /// 应用层端到端加密音频与识别结果（经不可信的反向代理中转时使用）
+        #[serde(default)]
+        e2e_encryption: bool,
+        /// 固定的服务器加密公钥（服务端启动日志中打印）；为空时信任 pong 中的公钥
+        #[serde(default)]
+        e2e_server_key: Option<String>,
+    },
+    /// 快慢两路：两个引擎同时识别同一段音频，先输入 `fast` 的结果，`quality` 的结果到达后替换
+    Race {
+        fast: Box<AsrConfig>,
+        quality: Box<AsrConfig>,
+    },
 }
 
-impl Default for AsrConfig {
-    fn default() -> Self {
+impl AsrConfig {
+    /// 指定地址、其余使用默认超时的 WebSocket 配置
+    pub fn websocket(endpoint: String) -> Self {
         Self::WebSocket {
-            endpoint: default_websocket_endpoint(),
+            endpoint,
+            extra_endpoints: Vec::new(),
+            connect_timeout_ms: default_connect_timeout_ms(),
+            read_timeout_ms: default_read_timeout_ms(),
+            stop_timeout_ms: default_stop_timeout_ms(),
+            late_event_window_ms: 0,
+            wire_format: WireFormat::default(),
+            codec: AudioCodec::default(),
+            e2e_encryption: false,
+            e2e_server_key: None,
         }
     }
+
+    /// WebSocket 配置的全部服务器地址（主地址在前，去空去重，至少一个）
+    pub fn websocket_endpoints(&self) -> Vec<String> {
+        let Self::WebSocket {
+            endpoint,
+            extra_endpoints,
+            ..
+        } = self
+        else {
+            return Vec::new();
+        };
+        let mut endpoints: Vec<String> = Vec::new();
+        for candidate in std::iter::once(endpoint).chain(extra_endpoints) {
+            let candidate = candidate.trim();
+            if !candidate.is_empty() && !endpoints.iter().any(|e| e == candidate) {
+                endpoints.push(candidate.to_string());
+            }
+        }
+        if endpoints.is_empty() {
+            endpoints.push(endpoint.clone());
+        }
+        endpoints
+    }
+}
+
+impl Default for AsrConfig {
+    fn default() -> Self {
+        Self::websocket(default_websocket_endpoint())
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum CloudProvider {
     Deepgram,
@@ -67,9 +262,53 @@ pub fn default_websocket_endpoint() -> String {
     "ws://127.0.0.1:8000/ws".to_string()
 }
 
+pub fn default_connect_timeout_ms() -> u64 {
+    5000
+}
+
+pub fn default_read_timeout_ms() -> u64 {
+    20_000
+}
+
+pub fn default_stop_timeout_ms() -> u64 {
+    30_000
+}
+
 pub fn create_engine(config: &AsrConfig) -> anyhow::Result<Box<dyn AsrEngine>> {
     match config {
-        AsrConfig::WebSocket { endpoint } => Ok(Box::new(websocket::WebSocketAsrEngine::new(endpoint.clone()))),
+        AsrConfig::WebSocket {
+            connect_timeout_ms,
+            read_timeout_ms,
+            stop_timeout_ms,
+            late_event_window_ms,
+            wire_format,
+            codec,
+            e2e_encryption,
+            e2e_server_key,
+            ..
+        } => Ok(Box::new(websocket::WebSocketAsrEngine::new(
+            &config.websocket_endpoints(),
+            websocket::SessionTimeouts {
+                connect: Duration::from_millis(*connect_timeout_ms),
+                read: Duration::from_millis(*read_timeout_ms),
+                stop: Duration::from_millis(*stop_timeout_ms),
+                late_events: Duration::from_millis(*late_event_window_ms),
+            },
+            *wire_format,
+            *codec,
+            e2e::E2eConfig {
+                enabled: *e2e_encryption,
+                pinned_key: e2e_server_key.clone(),
+            },
+        ))),
+        AsrConfig::Race { fast, quality } => {
+            // 同一服务器上的两个引擎共用一条连接，一次只能有一个会话
+            anyhow::ensure!(fast != quality, "快速与质量 ASR 的配置相同");
+            Ok(Box::new(race::RaceAsrEngine::new(
+                create_engine(fast)?,
+                create_engine(quality)?,
+            )))
+        }
         AsrConfig::Native => anyhow::bail!("系统原生 ASR 尚未实现"),
         AsrConfig::Cloud { provider, .. } => anyhow::bail!("云端 ASR 尚未实现: {provider:?}"),
     }
@@ -81,9 +320,7 @@ mod tests {
 
     #[test]
     fn asr_config_serializes_websocket_tag() {
-        let cfg = AsrConfig::WebSocket {
-            endpoint: "ws://example/ws".to_string(),
-        };
+        let cfg = AsrConfig::websocket("ws://example/ws".to_string());
         let value = serde_json::to_value(cfg).expect("serialize");
         assert_eq!(value.get("type").and_then(|v| v.as_str()), Some("websocket"));
     }
@@ -93,7 +330,16 @@ mod tests {
         let raw = r#"{ "type": "web_socket", "endpoint": "ws://legacy/ws" }"#;
         let cfg = serde_json::from_str::<AsrConfig>(raw).expect("deserialize legacy");
         match cfg {
-            AsrConfig::WebSocket { endpoint } => assert_eq!(endpoint, "ws://legacy/ws"),
+            AsrConfig::WebSocket {
+                endpoint,
+                connect_timeout_ms,
+                stop_timeout_ms,
+                ..
+            } => {
+                assert_eq!(endpoint, "ws://legacy/ws");
+                assert_eq!(connect_timeout_ms, default_connect_timeout_ms());
+                assert_eq!(stop_timeout_ms, default_stop_timeout_ms());
+            }
             other => panic!("unexpected: {other:?}"),
         }
     }
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::warn;
+
+use crate::asr::{AsrContext, AsrEngine, AsrEvent, DeferredSession, QualityResult, ServerCorrection};
+use crate::network::{ConnectionManager, TrafficStats};
+
+/// 快慢两路识别：音频同时送给两个引擎，`stop` 返回快速引擎的结果，质量引擎在后台收尾，
+/// 结果由 `take_quality_result` 取走后用于替换。快速引擎失败时改用质量结果；
+/// 置信度、语言、网络用量与离线暂存只来自快速引擎
+pub(super) struct RaceAsrEngine {
+    fast: Box<dyn AsrEngine>,
+    /// 上一次会话的质量识别在后台结束前一直持有锁
+    quality: Arc<Mutex<Box<dyn AsrEngine>>>,
+    quality_connection: Option<Arc<ConnectionManager>>,
+    /// 本次会话的质量引擎已开始且没有出错
+    racing: bool,
+    /// 后台等待质量结果的任务，新会话开始或取消时中止
+    pending: Option<JoinHandle<()>>,
+    result: Option<oneshot::Receiver<anyhow::Result<String>>>,
+}
+
+impl RaceAsrEngine {
+    pub fn new(fast: Box<dyn AsrEngine>, quality: Box<dyn AsrEngine>) -> Self {
+        Self {
+            fast,
+            quality_connection: quality.connection(),
+            quality: Arc::new(Mutex::new(quality)),
+            racing: false,
+            pending: None,
+            result: None,
+        }
+    }
+
+    fn abort_pending(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.abort();
+        }
+        self.result = None;
+    }
+}
+
+#[async_trait]
+impl AsrEngine for RaceAsrEngine {
+    async fn start(&mut self, trace_id: String, sample_rate: u32, context: AsrContext) -> anyhow::Result<()> {
+        self.abort_pending();
+        self.racing = false;
+        if !context.quality_pass {
+            return self.fast.start(trace_id, sample_rate, context).await;
+        }
+        let mut quality = self.quality.lock().await;
+        let (fast_started, quality_started) = tokio::join!(
+            self.fast.start(trace_id.clone(), sample_rate, context.clone()),
+            quality.start(trace_id.clone(), sample_rate, context),
+        );
+        if let Err(err) = fast_started {
+            quality.cancel().await;
+            return Err(err);
+        }
+        match quality_started {
+            Ok(()) => self.racing = true,
+            Err(err) => warn!(
+                target: "asr",
+                trace_id = trace_id.as_str(),
+                error = %format!("{err:#}"),
+                "质量 ASR 启动失败，本次只用快速结果 | Quality ASR failed to start, using the fast result only"
+            ),
+        }
+        Ok(())
+    }
+
+    async fn feed_audio(&mut self, pcm: &[i16]) -> anyhow::Result<()> {
+        self.fast.feed_audio(pcm).await?;
+        if self.racing {
+            let mut quality = self.quality.lock().await;
+            if let Err(err) = quality.feed_audio(pcm).await {
+                warn!(
+                    target: "asr",
+                    error = %format!("{err:#}"),
+                    "质量 ASR 发送音频失败，本次只用快速结果 | Quality ASR failed to take audio, using the fast result only"
+                );
+                quality.cancel().await;
+                self.racing = false;
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> anyhow::Result<String> {
+        if std::mem::take(&mut self.racing) {
+            let quality = self.quality.clone();
+            let (tx, rx) = oneshot::channel();
+            self.pending = Some(tauri::async_runtime::spawn(async move {
+                let mut quality = quality.lock().await;
+                let _ = tx.send(quality.stop().await);
+            }));
+            self.result = Some(rx);
+        }
+        let err = match self.fast.stop().await {
+            Ok(text) => return Ok(text),
+            Err(err) => err,
+        };
+        let Some(result) = self.result.take() else {
+            return Err(err);
+        };
+        match result.await {
+            Ok(Ok(text)) => {
+                warn!(
+                    target: "asr",
+                    error = %format!("{err:#}"),
+                    "快速 ASR 失败，改用质量结果 | Fast ASR failed, using the quality result"
+                );
+                // 已有结果，不再补交快速引擎暂存的会话
+                let _ = self.fast.take_deferred();
+                Ok(text)
+            }
+            _ => Err(err),
+        }
+    }
+
+    async fn cancel(&mut self) {
+        self.abort_pending();
+        self.fast.cancel().await;
+        if std::mem::take(&mut self.racing) {
+            self.quality.lock().await.cancel().await;
+        }
+    }
+
+    fn events(&mut self) -> &mut mpsc::Receiver<AsrEvent> {
+        self.fast.events()
+    }
+
+    fn connection(&self) -> Option<Arc<ConnectionManager>> {
+        self.fast.connection().or_else(|| self.quality_connection.clone())
+    }
+
+    fn take_deferred(&mut self) -> Option<Box<dyn DeferredSession>> {
+        self.fast.take_deferred()
+    }
+
+    fn take_confidence(&mut self) -> Option<f32> {
+        self.fast.take_confidence()
+    }
+
+    fn take_language(&mut self) -> Option<String> {
+        self.fast.take_language()
+    }
+
+    fn take_traffic(&mut self) -> Option<TrafficStats> {
+        self.fast.take_traffic()
+    }
+
+    fn take_server_correction(&mut self) -> Option<Box<dyn ServerCorrection>> {
+        self.fast.take_server_correction()
+    }
+
+    fn take_quality_result(&mut self) -> Option<Box<dyn QualityResult>> {
+        self.result
+            .take()
+            .map(|rx| Box::new(PendingQuality { rx }) as Box<dyn QualityResult>)
+    }
+}
+
+struct PendingQuality {
+    rx: oneshot::Receiver<anyhow::Result<String>>,
+}
+
+#[async_trait]
+impl QualityResult for PendingQuality {
+    async fn wait(self: Box<Self>) -> anyhow::Result<String> {
+        self.rx.await.map_err(|_| anyhow::anyhow!("quality asr aborted"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asr::mock_server::{MockAsrServer, MockReply};
+    use crate::asr::{create_engine, AsrConfig};
+
+    #[tokio::test]
+    async fn fast_result_returns_first_and_quality_result_follows() {
+        let fast = MockAsrServer::spawn(&[], MockReply::text("今天天汽不错")).await;
+        let quality = MockAsrServer::spawn(&[], MockReply::text("今天天气不错。")).await;
+        let config = AsrConfig::Race {
+            fast: Box::new(AsrConfig::websocket(fast.endpoint.clone())),
+            quality: Box::new(AsrConfig::websocket(quality.endpoint.clone())),
+        };
+        let mut engine = create_engine(&config).expect("race engine");
+
+        let context = AsrContext {
+            quality_pass: true,
+            ..AsrContext::default()
+        };
+        engine.start("t1".to_string(), 16000, context).await.expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天汽不错");
+        let pending = engine.take_quality_result().expect("quality result");
+        assert_eq!(pending.wait().await.expect("quality"), "今天天气不错。");
+        assert!(quality.received().frames[0] > 0);
+
+        // 未请求第二路识别时只用快速引擎
+        engine
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天汽不错");
+        assert!(engine.take_quality_result().is_none());
+        assert_eq!(quality.received().starts.len(), 1);
+        assert_eq!(fast.received().starts.len(), 2);
+
+        // 快速引擎出错时改用质量结果
+        fast.set_reply(MockReply::Error("model crashed".to_string()));
+        let context = AsrContext {
+            quality_pass: true,
+            ..AsrContext::default()
+        };
+        engine.start("t3".to_string(), 16000, context).await.expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天气不错。");
+
+        let same = AsrConfig::Race {
+            fast: Box::new(AsrConfig::websocket(fast.endpoint.clone())),
+            quality: Box::new(AsrConfig::websocket(fast.endpoint.clone())),
+        };
+        assert!(create_engine(&same).is_err());
+    }
+}
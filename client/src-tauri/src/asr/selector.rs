@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::network::{ConnectionManager, LinkState};
+
+/// 当前服务器比最快的服务器慢不到这么多时继续使用，避免延迟抖动导致来回切换
+const STICKY_RATIO_PERCENT: u64 = 150;
+const STICKY_MARGIN_MS: u64 = 20;
+
+/// 配置了多个 ASR 服务器时按延迟选路：每个地址各保持一条长连接（心跳即测延迟），
+/// 新会话开始时重新评估，当前服务器断线或明显变慢才切换。
+pub(super) struct ServerSelector {
+    servers: Vec<Arc<ConnectionManager>>,
+    current: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    connected: bool,
+    rtt_ms: Option<u64>,
+}
+
+impl ServerSelector {
+    /// `endpoints` 不能为空；第一个地址为初始选择
+    pub fn new(endpoints: &[String]) -> Self {
+        Self {
+            servers: endpoints.iter().map(|e| ConnectionManager::shared(e)).collect(),
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> Arc<ConnectionManager> {
+        self.servers[self.current].clone()
+    }
+
+    /// 为新会话选择服务器
+    pub fn select(&mut self) -> Arc<ConnectionManager> {
+        let candidates: Vec<Candidate> = self
+            .servers
+            .iter()
+            .map(|server| {
+                let status = server.status();
+                Candidate {
+                    connected: status.state == LinkState::Connected,
+                    rtt_ms: status.rtt_ms,
+                }
+            })
+            .collect();
+        let chosen = choose(self.current, &candidates);
+        if chosen != self.current {
+            info!(
+                target: "asr",
+                from = self.servers[self.current].endpoint(),
+                to = self.servers[chosen].endpoint(),
+                from_rtt_ms = ?candidates[self.current].rtt_ms,
+                to_rtt_ms = ?candidates[chosen].rtt_ms,
+                "切换 ASR 服务器 | Switching ASR server"
+            );
+            self.current = chosen;
+        }
+        self.current()
+    }
+}
+
+fn choose(current: usize, candidates: &[Candidate]) -> usize {
+    let best = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.connected)
+        .min_by_key(|(_, c)| c.rtt_ms.unwrap_or(u64::MAX))
+        .map(|(index, _)| index);
+    let Some(best) = best else {
+        // 全部不可用：保持原选择，由会话走离线暂存
+        return current;
+    };
+
+    let now = candidates[current];
+    if !now.connected {
+        return best;
+    }
+    match (now.rtt_ms, candidates[best].rtt_ms) {
+        (Some(now_rtt), Some(best_rtt)) if now_rtt > best_rtt * STICKY_RATIO_PERCENT / 100 + STICKY_MARGIN_MS => best,
+        // 延迟未知（刚连上还没收到 pong）或差距不大时保持不变
+        _ => current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn up(rtt_ms: u64) -> Candidate {
+        Candidate {
+            connected: true,
+            rtt_ms: Some(rtt_ms),
+        }
+    }
+
+    const DOWN: Candidate = Candidate {
+        connected: false,
+        rtt_ms: Some(5),
+    };
+
+    #[test]
+    fn choose_prefers_fast_servers_with_stickiness() {
+        // 当前服务器略慢：保持
+        assert_eq!(choose(0, &[up(40), up(30)]), 0);
+        // 明显变慢：切换到最快的
+        assert_eq!(choose(0, &[up(200), up(30), up(60)]), 1);
+        // 当前断线：切到可用的里最快的，断线服务器的旧延迟不算数
+        assert_eq!(choose(0, &[DOWN, up(80), up(50)]), 2);
+        assert_eq!(choose(1, &[DOWN, up(80)]), 1);
+        // 全部不可用：不动
+        assert_eq!(choose(1, &[DOWN, DOWN]), 1);
+        // 当前延迟未知：不急着切换
+        let fresh = Candidate {
+            connected: true,
+            rtt_ms: None,
+        };
+        assert_eq!(choose(0, &[fresh, up(10)]), 0);
+    }
+}
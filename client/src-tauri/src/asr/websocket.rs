@@ -1,19 +1,84 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Context as _;
 use async_trait::async_trait;
-use futures_util::{SinkExt, StreamExt};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
 use serde::Serialize;
 use tokio::sync::mpsc;
-use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use crate::asr::e2e::{self, E2eConfig, Opener, Sealer};
+use crate::asr::selector::ServerSelector;
+use crate::asr::{AsrContext, AsrEngine, AsrEvent, AudioCodec, DeferredSession, ServerCorrection, WireFormat};
+use crate::input_field::InputField;
+use crate::network::{ConnectionManager, LinkState, ServerCapabilities, ServerMessage, Session, TrafficStats};
+use crate::opus::{EncoderPool, OpusEncoder};
+
+/// 已知断线时只短暂尝试重连，不让用户等满超时才开始录音
+const OFFLINE_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 本地最多保留的音频包（20ms 一包，约 10 分钟），超出部分不再暂存
+const MAX_BUFFERED_PACKETS: usize = 30_000;
+
+const DEFERRED_RETRY_DELAY: Duration = Duration::from_secs(1);
 
-use crate::asr::{AsrContext, AsrEngine, AsrEvent};
-use crate::opus::OpusEncoder;
+/// 原始 PCM 帧头的版本号
+const PCM_FRAME_VERSION: u8 = 1;
+
+/// 会话各阶段的超时，来自 ASR 配置
+#[derive(Debug, Clone, Copy)]
+pub struct SessionTimeouts {
+    /// 会话开始时等待连接可用
+    pub connect: Duration,
+    /// 等结果时两条服务端消息之间的最长间隔
+    pub read: Duration,
+    /// 发送 stop 后等待最终结果的总时长
+    pub stop: Duration,
+    /// 识别结果之后继续收听同一会话的校正或更新结果；为零时不收听
+    pub late_events: Duration,
+}
 
 pub struct WebSocketAsrEngine {
-    endpoint: String,
-    ws: Option<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>,
+    selector: ServerSelector,
+    /// 本次会话使用的服务器，每次 start 时由 `selector` 重新选择
+    connection: Arc<ConnectionManager>,
+    timeouts: SessionTimeouts,
+    /// 希望服务端使用的事件编码，服务器不支持时退回 JSON
+    wire_format: WireFormat,
+    /// 希望使用的音频编码，服务器不支持时退回 Opus
+    codec: AudioCodec,
+    e2e: E2eConfig,
+    /// 本次会话的音频加密器（启用端到端加密时）
+    sealer: Option<Sealer>,
+    /// 当前会话的服务端消息；离线暂存时为空
+    session: Option<LiveSession>,
+    /// 本次会话的 start 消息与已编码音频，服务器不可达时用于稍后补交
+    start_payload: Option<StartPayload>,
+    packets: Vec<Vec<u8>>,
+    /// 本次实时会话是否使用帧序号，以及已发出的帧数
+    seq_frames: bool,
+    frames_sent: u32,
+    /// 服务器支持续传：录音中断线时先暂停发送，连接恢复后接着传
+    resumable: bool,
+    suspended: bool,
+    offline: bool,
+    deferred: Option<Box<dyn DeferredSession>>,
+    /// 识别结果之后仍在等待的校正（服务器负责校正，或开启了收听窗口）
+    correction: Option<PendingCorrection>,
+    /// 本次会话的网络用量，`take_traffic` 取走
+    traffic: TrafficStats,
+    /// 本次识别结果的置信度，`take_confidence` 取走
+    confidence: Option<f32>,
+    /// 服务器判断的语言，`take_language` 取走
+    language: Option<String>,
     trace_id: Option<String>,
     sample_rate: u32,
     encoder: Option<OpusEncoder>,
+    /// 会话结束后归还的编码器，下次会话复用
+    encoders: EncoderPool,
     frame_size: usize,
     pcm_buf: Vec<i16>,
     out_buf: Vec<u8>,
@@ -21,24 +86,59 @@ pub struct WebSocketAsrEngine {
     rx: mpsc::Receiver<AsrEvent>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 struct ClientContextPayload {
     app_name: String,
     window_title: String,
+    /// 焦点输入控件；旧服务器忽略这个字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<InputField>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StartPayload {
+    trace_id: String,
+    sample_rate: u32,
+    context: ClientContextPayload,
+    /// 请求服务器做 LLM 校正，仅当服务器声明 `llm` 时为真
+    use_cloud_api: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    seq_frames: bool,
+    #[serde(skip_serializing_if = "WireFormat::is_json")]
+    wire_format: WireFormat,
+    /// 音频帧的编码；已编码的音频在续传与补交时沿用，不再重新协商
+    #[serde(skip_serializing_if = "AudioCodec::is_opus")]
+    codec: AudioCodec,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    e2e: Option<E2eStartPayload>,
+    /// 断线后续传同一 trace_id 的会话
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    resume: bool,
+}
+
+/// 端到端加密的握手：本次会话的临时公钥，以及加密后的上下文（明文 `context` 留空）
+#[derive(Debug, Clone, Serialize)]
+struct E2eStartPayload {
+    public_key: String,
+    context: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 enum ClientControlPayload {
-    Start {
-        trace_id: String,
-        sample_rate: u32,
-        context: ClientContextPayload,
-        use_cloud_api: bool,
-    },
+    Start(StartPayload),
     Stop {
         #[serde(skip_serializing_if = "Option::is_none")]
         trace_id: Option<String>,
+        /// 启用帧序号时的总帧数，服务器据此发现末尾丢失的帧
+        #[serde(skip_serializing_if = "Option::is_none")]
+        frames: Option<u32>,
+    },
+    Cancel {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace_id: Option<String>,
     },
 }
 
@@ -46,10 +146,29 @@ enum ClientControlPayload {
 #[serde(tag = "type", rename_all = "snake_case")]
 enum ServerEventPayload {
     Pong,
+    Ack {
+        trace_id: Option<String>,
+        received: u64,
+        missing: u64,
+    },
+    /// 对续传 start 的答复：服务器已有的帧数（会话已过期时为 0）
+    Resumed {
+        trace_id: Option<String>,
+        received: u64,
+    },
     FastText {
         trace_id: Option<String>,
         content: String,
         is_final: Option<bool>,
+        /// 识别结果的置信度（0~1），服务器不提供时为空
+        confidence: Option<f32>,
+        /// 识别出的语言代码，服务器不提供时为空
+        language: Option<String>,
+    },
+    /// 服务端 LLM 校正后的文字，在 `fast_text` 之后到达
+    Correction {
+        trace_id: Option<String>,
+        replaced_text: String,
     },
     Error {
         trace_id: Option<String>,
@@ -58,14 +177,40 @@ enum ServerEventPayload {
 }
 
 impl WebSocketAsrEngine {
-    pub fn new(endpoint: String) -> Self {
+    pub fn new(
+        endpoints: &[String],
+        timeouts: SessionTimeouts,
+        wire_format: WireFormat,
+        codec: AudioCodec,
+        e2e: E2eConfig,
+    ) -> Self {
         let (tx, rx) = mpsc::channel::<AsrEvent>(64);
+        let selector = ServerSelector::new(endpoints);
         Self {
-            endpoint,
-            ws: None,
+            connection: selector.current(),
+            selector,
+            timeouts,
+            wire_format,
+            codec,
+            e2e,
+            sealer: None,
+            session: None,
+            start_payload: None,
+            packets: Vec::new(),
+            seq_frames: false,
+            frames_sent: 0,
+            resumable: false,
+            suspended: false,
+            offline: false,
+            deferred: None,
+            correction: None,
+            traffic: TrafficStats::default(),
+            confidence: None,
+            language: None,
             trace_id: None,
             sample_rate: 0,
             encoder: None,
+            encoders: EncoderPool::default(),
             frame_size: 0,
             pcm_buf: Vec::new(),
             out_buf: vec![0u8; 4096],
@@ -74,63 +219,164 @@ impl WebSocketAsrEngine {
         }
     }
 
-    async fn disconnect(&mut self) {
-        if let Some(mut ws) = self.ws.take() {
-            let _ = ws.close(None).await;
+    /// 连接服务器并发送 start；失败时由调用方转入离线暂存。
+    /// `resume` 时发送续传 start，并补发服务器尚未收到的音频。
+    async fn open_live_session(&mut self, resume: bool) -> anyhow::Result<()> {
+        let timeout = if self.connection.status().state == LinkState::Disconnected {
+            OFFLINE_CONNECT_TIMEOUT.min(self.timeouts.connect)
+        } else {
+            self.timeouts.connect
+        };
+        self.connection.wait_connected(timeout).await?;
+        // 连接由 ConnectionManager 长期保持；每次会话换一个新的消息接收端，
+        // 上一会话残留的消息不会串进来（另外还按 trace_id 过滤）。
+        let session = tokio::time::timeout(timeout, self.connection.open_session())
+            .await
+            .context("wait for previous session")?;
+        let mut session = LiveSession::new(session);
+        // 续传必须知道服务器是否支持；刚重连时等首个 pong
+        let capabilities = if resume {
+            Some(self.connection.wait_capabilities(timeout).await?)
+        } else {
+            self.connection.capabilities()
+        };
+        let start = self.start_payload.as_mut().context("start payload missing")?;
+        negotiate(start, capabilities.as_ref(), self.wire_format);
+        if !resume {
+            start.codec = negotiate_codec(start, capabilities.as_ref(), self.codec);
+        }
+        let (mut start, keys) = seal_start(start, capabilities.as_ref(), &self.e2e)?;
+        self.resumable = capabilities.is_some_and(|caps| caps.supports(ServerCapabilities::RESUME));
+        if resume && !self.resumable {
+            anyhow::bail!("server does not support resume");
         }
+        start.resume = resume;
+        self.seq_frames = start.seq_frames;
+        session.wire_format = start.wire_format;
+        let (sealer, opener) = keys.unzip();
+        session.opener = opener;
+        self.sealer = sealer;
+        self.session = Some(session);
+        let text = serde_json::to_string(&ClientControlPayload::Start(start)).context("serialize start payload")?;
+        let len = text.len();
+        self.connection.send_text(text).await.context("ws send start")?;
+        self.traffic.record_text(len);
+        if resume {
+            let session = self.session.as_mut().context("session missing")?;
+            let trace_id = self.trace_id.as_deref();
+            let received = wait_resumed(session, trace_id, &mut self.traffic, self.timeouts.read).await?;
+            self.resend_from(received as usize).await?;
+        }
+        Ok(())
     }
 
-    async fn ensure_connected(&mut self) -> anyhow::Result<()> {
-        if self.ws.is_some() {
-            return Ok(());
+    /// 续传：从服务器已有的帧之后补发本地缓存的音频
+    async fn resend_from(&mut self, received: usize) -> anyhow::Result<()> {
+        let sent_before = self.frames_sent as usize;
+        for index in received.min(self.packets.len())..self.packets.len() {
+            let mut frame = audio_frame(self.seq_frames, index as u32, &self.packets[index]);
+            if let Some(sealer) = self.sealer.as_mut() {
+                frame = sealer.seal(&frame);
+            }
+            let len = frame.len();
+            self.connection.send_binary(frame).await.context("ws resend binary")?;
+            self.traffic.record_audio(len, index < sent_before);
         }
-
-        let (ws, _) = tokio_tungstenite::connect_async(&self.endpoint)
-            .await
-            .context("connect websocket")?;
-        self.ws = Some(ws);
+        self.frames_sent = self.packets.len() as u32;
         Ok(())
     }
 
-    async fn send_text(&mut self, text: String) -> anyhow::Result<()> {
-        let Some(ws) = self.ws.as_mut() else {
-            anyhow::bail!("websocket not connected");
-        };
-        ws.send(Message::Text(text)).await.context("ws send text")?;
-        Ok(())
+    /// 录音中发送失败：服务器支持续传时先暂停，等连接恢复；否则转入离线暂存
+    fn suspend(&mut self, err: anyhow::Error) {
+        // 本地缓存已满时无法补发，只能整段补交
+        if !self.resumable || self.packets.len() >= MAX_BUFFERED_PACKETS {
+            self.go_offline(&err);
+            return;
+        }
+        warn!(
+            target: "asr",
+            trace_id = self.trace_id.as_deref().unwrap_or(""),
+            error = %format!("{err:#}"),
+            "录音中连接中断，恢复后续传 | Connection lost mid-session, will resume after reconnect"
+        );
+        self.suspended = true;
+        self.session = None;
+        self.sealer = None;
     }
 
-    async fn send_binary(&mut self, bytes: Vec<u8>) -> anyhow::Result<()> {
-        let Some(ws) = self.ws.as_mut() else {
-            anyhow::bail!("websocket not connected");
-        };
-        ws.send(Message::Binary(bytes))
-            .await
-            .context("ws send binary")?;
-        Ok(())
+    /// 连接已恢复：续传本次会话，失败则整段转为离线暂存
+    async fn resume(&mut self) {
+        self.suspended = false;
+        match self.open_live_session(true).await {
+            Ok(()) => info!(
+                target: "asr",
+                trace_id = self.trace_id.as_deref().unwrap_or(""),
+                frames = self.frames_sent,
+                "会话已续传 | Session resumed"
+            ),
+            Err(err) => self.go_offline(&err),
+        }
     }
 
-    async fn recv_event(&mut self) -> anyhow::Result<ServerEventPayload> {
-        let Some(ws) = self.ws.as_mut() else {
-            anyhow::bail!("websocket not connected");
-        };
+    fn go_offline(&mut self, err: &anyhow::Error) {
+        if self.offline {
+            return;
+        }
+        warn!(
+            target: "asr",
+            trace_id = self.trace_id.as_deref().unwrap_or(""),
+            error = %format!("{err:#}"),
+            "服务器不可达，音频暂存本地 | Server unreachable, buffering audio locally"
+        );
+        self.offline = true;
+        self.session = None;
+    }
 
-        loop {
-            let Some(msg) = ws.next().await else {
-                anyhow::bail!("websocket closed");
-            };
-            let msg = msg.context("ws recv")?;
-            match msg {
-                Message::Text(text) => {
-                    if let Ok(event) = serde_json::from_str::<ServerEventPayload>(&text) {
-                        return Ok(event);
-                    }
-                    continue;
-                }
-                Message::Close(_) => anyhow::bail!("websocket closed"),
-                _ => continue,
-            }
+    /// 把本次会话转为待补交；返回给 `stop` 调用方的错误
+    fn defer(&mut self, err: anyhow::Error) -> anyhow::Error {
+        if let (Some(trace_id), Some(start)) = (self.trace_id.clone(), self.start_payload.take()) {
+            info!(
+                target: "asr",
+                trace_id = trace_id.as_str(),
+                packets = self.packets.len(),
+                "会话已暂存，等待连接恢复 | Session deferred until server is reachable"
+            );
+            let packets = std::mem::take(&mut self.packets);
+            // 补交的结果晚于输入时机，不再请求服务端校正
+            let mut start = start;
+            start.use_cloud_api = false;
+            self.deferred = Some(Box::new(PendingSession {
+                connection: self.connection.clone(),
+                timeouts: self.timeouts,
+                wire_format: self.wire_format,
+                e2e: self.e2e.clone(),
+                trace_id,
+                start,
+                sent_before: (self.traffic.audio_packets as usize).min(packets.len()),
+                packets,
+            }));
         }
+        self.end_session();
+        err.context("ASR 服务器不可达 | ASR server unreachable")
+    }
+
+    fn end_session(&mut self) {
+        self.trace_id = None;
+        if let Some(encoder) = self.encoder.take() {
+            self.encoders.put(encoder);
+        }
+        self.frame_size = 0;
+        self.pcm_buf.clear();
+        self.session = None;
+        self.correction = None;
+        self.sealer = None;
+        self.start_payload = None;
+        self.packets.clear();
+        self.seq_frames = false;
+        self.frames_sent = 0;
+        self.resumable = false;
+        self.suspended = false;
+        self.offline = false;
     }
 
     fn push_pcm_and_drain_frames(&mut self, pcm: &[i16]) -> Vec<Vec<u8>> {
@@ -144,7 +390,9 @@ impl WebSocketAsrEngine {
         while self.pcm_buf.len() >= self.frame_size {
             let frame: Vec<i16> = self.pcm_buf.drain(..self.frame_size).collect();
             let Some(encoder) = self.encoder.as_mut() else {
-                break;
+                // 协商为原始 PCM 时不使用编码器
+                out_packets.push(pcm_packet(&frame));
+                continue;
             };
             let Ok(len) = encoder.encode(&frame, &mut self.out_buf) else {
                 continue;
@@ -162,84 +410,1015 @@ impl WebSocketAsrEngine {
 #[async_trait]
 impl AsrEngine for WebSocketAsrEngine {
     async fn start(&mut self, trace_id: String, sample_rate: u32, context: AsrContext) -> anyhow::Result<()> {
-        // 为了避免跨会话残留消息导致混淆，每次会话都重新建立连接。
-        self.disconnect().await;
-        self.ensure_connected().await?;
-
+        self.end_session();
+        self.deferred = None;
+        self.connection = self.selector.select();
+        self.traffic = TrafficStats::default();
+        self.confidence = None;
+        self.language = None;
         self.trace_id = Some(trace_id.clone());
         self.sample_rate = sample_rate;
-        self.encoder = Some(OpusEncoder::new(sample_rate)?);
+        self.encoder = Some(self.encoders.take(sample_rate)?);
         self.frame_size = (sample_rate / 50) as usize;
-        self.pcm_buf.clear();
 
-        let payload = ClientControlPayload::Start {
+        self.start_payload = Some(StartPayload {
             trace_id,
             sample_rate,
             context: ClientContextPayload {
                 app_name: context.app_name,
                 window_title: context.window_title,
+                field: context.field,
             },
-            use_cloud_api: false,
-        };
-        let text = serde_json::to_string(&payload).context("serialize start payload")?;
-        self.send_text(text).await?;
+            use_cloud_api: context.server_correction,
+            language: context.language,
+            seq_frames: false,
+            wire_format: WireFormat::Json,
+            codec: AudioCodec::Opus,
+            e2e: None,
+            resume: false,
+        });
+
+        // 服务器不可达时照常录音，音频暂存本地，结束时转为待补交
+        if let Err(err) = self.open_live_session(false).await {
+            // 要求加密但服务器不支持（或公钥不符）：直接失败，不以明文补交
+            if err.is::<e2e::Refused>() {
+                self.end_session();
+                return Err(err);
+            }
+            self.go_offline(&err);
+        }
+        // 协商为原始 PCM 时编码器留回池中；离线开始的会话仍按 Opus 暂存
+        if self.start_payload.as_ref().is_some_and(|start| !start.codec.is_opus()) {
+            if let Some(encoder) = self.encoder.take() {
+                self.encoders.put(encoder);
+            }
+        }
         Ok(())
     }
 
     async fn feed_audio(&mut self, pcm: &[i16]) -> anyhow::Result<()> {
         let packets = self.push_pcm_and_drain_frames(pcm);
+        // 两次发送之间断开又重连时发送不会报错，按会话是否已关闭判断
+        if self.session.as_ref().is_some_and(|live| live.session.is_closed()) {
+            self.suspend(anyhow::anyhow!("websocket closed"));
+        }
+        // 断线后连接一恢复就续传，期间的音频已在本地缓存
+        if self.suspended && self.connection.status().state == LinkState::Connected {
+            self.resume().await;
+        }
         for pkt in packets {
-            self.send_binary(pkt).await?;
+            if !self.offline && !self.suspended {
+                let mut frame = audio_frame(self.seq_frames, self.frames_sent, &pkt);
+                if let Some(sealer) = self.sealer.as_mut() {
+                    frame = sealer.seal(&frame);
+                }
+                let len = frame.len();
+                match self.connection.send_binary(frame).await {
+                    Ok(()) => {
+                        self.frames_sent += 1;
+                        self.traffic.record_audio(len, false);
+                    }
+                    Err(err) => self.suspend(err.context("ws send binary")),
+                }
+            }
+            if self.packets.len() < MAX_BUFFERED_PACKETS {
+                self.packets.push(pkt);
+            }
+        }
+        // 录音期间就处理 ack，长会话不会把会话消息队列塞满
+        if let Some(session) = self.session.as_mut() {
+            session.poll_acks(&mut self.traffic);
         }
         Ok(())
     }
 
     async fn stop(&mut self) -> anyhow::Result<String> {
-        let trace_id = self.trace_id.clone();
+        if self.suspended {
+            // 松开热键时连接仍未恢复：再等一次，仍不行就整段补交
+            match self.connection.wait_connected(self.timeouts.connect).await {
+                Ok(()) => self.resume().await,
+                Err(err) => {
+                    self.suspended = false;
+                    self.go_offline(&err);
+                }
+            }
+        }
+        if self.offline {
+            return Err(self.defer(anyhow::anyhow!("websocket not connected")));
+        }
 
-        let payload = ClientControlPayload::Stop { trace_id };
+        let trace_id = self.trace_id.clone();
+        let payload = ClientControlPayload::Stop {
+            trace_id: trace_id.clone(),
+            frames: self.seq_frames.then_some(self.frames_sent),
+        };
         let text = serde_json::to_string(&payload).context("serialize stop payload")?;
-        self.send_text(text).await?;
+        let len = text.len();
+        if let Err(err) = self.connection.send_text(text).await {
+            return Err(self.defer(err.context("ws send stop")));
+        }
+        self.traffic.record_text(len);
 
-        loop {
-            let event = self.recv_event().await?;
-            match event {
-                ServerEventPayload::Pong => continue,
-                ServerEventPayload::FastText { trace_id, content, .. } => {
-                    if let (Some(expected), Some(got)) = (self.trace_id.as_deref(), trace_id.as_deref()) {
-                        if got != expected {
-                            continue;
-                        }
-                    }
-                    let _ = self.tx.try_send(AsrEvent::Final { text: content.clone() });
-                    self.trace_id = None;
-                    self.encoder = None;
-                    self.frame_size = 0;
-                    self.pcm_buf.clear();
-                    self.disconnect().await;
-                    return Ok(content);
-                }
-                ServerEventPayload::Error { trace_id, message } => {
-                    if let (Some(expected), Some(got)) = (self.trace_id.as_deref(), trace_id.as_deref()) {
-                        if got != expected {
-                            continue;
-                        }
-                    }
-                    let _ = self.tx.try_send(AsrEvent::Error {
-                        message: message.clone(),
-                    });
-                    self.trace_id = None;
-                    self.encoder = None;
-                    self.frame_size = 0;
-                    self.pcm_buf.clear();
-                    self.disconnect().await;
-                    anyhow::bail!(message);
+        let Some(session) = self.session.as_mut() else {
+            anyhow::bail!("websocket session not started");
+        };
+        match wait_result(session, trace_id.as_deref(), &mut self.traffic, self.timeouts).await {
+            Ok(SessionResult::Text {
+                content,
+                confidence,
+                language,
+            }) => {
+                let _ = self.tx.try_send(AsrEvent::Final { text: content.clone() });
+                self.confidence = confidence;
+                self.language = language;
+                // 服务器负责校正时保留本次会话等之后的 correction；否则在收听窗口内接收服务器主动发来的更新
+                let requested = self.start_payload.as_ref().is_some_and(|start| start.use_cloud_api);
+                let timeout = if requested {
+                    self.timeouts.stop
+                } else {
+                    self.timeouts.late_events
+                };
+                let correction = match (self.session.take(), trace_id) {
+                    (Some(session), Some(trace_id)) if !timeout.is_zero() => Some(PendingCorrection {
+                        session,
+                        trace_id,
+                        requested,
+                        timeout,
+                        tx: self.tx.clone(),
+                    }),
+                    _ => None,
+                };
+                self.end_session();
+                self.correction = correction;
+                Ok(content)
+            }
+            Ok(SessionResult::ServerError(message)) => {
+                let _ = self.tx.try_send(AsrEvent::Error {
+                    message: message.clone(),
+                });
+                self.end_session();
+                anyhow::bail!(message);
+            }
+            // 服务器仍在线但迟迟不回结果：本次会话直接失败，不再补交
+            Ok(SessionResult::TimedOut(reason)) => {
+                warn!(
+                    target: "asr",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    reason,
+                    "等待识别结果超时 | Timed out waiting for ASR result"
+                );
+                let message = format!("等待识别结果超时 | ASR result timeout ({reason})");
+                let _ = self.tx.try_send(AsrEvent::Error {
+                    message: message.clone(),
+                });
+                self.end_session();
+                anyhow::bail!(message);
+            }
+            // 等结果时断线：服务器未必处理完，整段重新提交
+            Err(err) => Err(self.defer(err)),
+        }
+    }
+
+    async fn cancel(&mut self) {
+        // 旧版服务器不认识 cancel：不通知，下次 start 时服务器自然丢弃这段音频
+        let notify = self.session.is_some()
+            && !self.offline
+            && !self.suspended
+            && self.connection.supports(ServerCapabilities::CANCEL);
+        if notify {
+            let payload = ClientControlPayload::Cancel {
+                trace_id: self.trace_id.clone(),
+            };
+            if let Ok(text) = serde_json::to_string(&payload) {
+                let len = text.len();
+                match self.connection.send_text(text).await {
+                    Ok(()) => self.traffic.record_text(len),
+                    Err(err) => debug!(
+                        target: "asr",
+                        trace_id = self.trace_id.as_deref().unwrap_or(""),
+                        error = %err,
+                        "取消消息发送失败 | Failed to send cancel"
+                    ),
                 }
             }
         }
+        self.end_session();
+        self.deferred = None;
+        self.confidence = None;
+        self.language = None;
     }
 
     fn events(&mut self) -> &mut mpsc::Receiver<AsrEvent> {
         &mut self.rx
     }
+
+    fn connection(&self) -> Option<Arc<ConnectionManager>> {
+        Some(self.connection.clone())
+    }
+
+    fn take_deferred(&mut self) -> Option<Box<dyn DeferredSession>> {
+        self.deferred.take()
+    }
+
+    fn take_confidence(&mut self) -> Option<f32> {
+        self.confidence.take()
+    }
+
+    fn take_language(&mut self) -> Option<String> {
+        self.language.take()
+    }
+
+    fn take_traffic(&mut self) -> Option<TrafficStats> {
+        Some(std::mem::take(&mut self.traffic))
+    }
+
+    fn take_server_correction(&mut self) -> Option<Box<dyn ServerCorrection>> {
+        self.correction
+            .take()
+            .map(|correction| Box::new(correction) as Box<dyn ServerCorrection>)
+    }
+}
+
+enum SessionResult {
+    Text {
+        content: String,
+        confidence: Option<f32>,
+        language: Option<String>,
+    },
+    ServerError(String),
+    /// 超过单条消息或整体等待时限
+    TimedOut(&'static str),
+}
+
+/// 服务器最近一次 ack 的累计值
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameAcks {
+    received: u64,
+    missing: u64,
+}
+
+/// 一次会话的服务端消息：录音期间先处理 ack，其它消息留到等结果时再看
+struct LiveSession {
+    session: Session,
+    acks: FrameAcks,
+    backlog: VecDeque<ServerMessage>,
+    /// 协商后的事件编码，解密后的事件按它解析
+    wire_format: WireFormat,
+    /// 启用端到端加密时，服务器的会话事件为加密的二进制帧
+    opener: Option<Opener>,
+}
+
+impl LiveSession {
+    fn new(session: Session) -> Self {
+        Self {
+            session,
+            acks: FrameAcks::default(),
+            backlog: VecDeque::new(),
+            wire_format: WireFormat::Json,
+            opener: None,
+        }
+    }
+
+    fn decode(&mut self, message: &ServerMessage) -> Option<ServerEventPayload> {
+        let (ServerMessage::Binary(frame), Some(opener)) = (message, self.opener.as_mut()) else {
+            return parse_event(message);
+        };
+        let plaintext = match opener.open(frame) {
+            Ok(plaintext) => plaintext,
+            Err(err) => {
+                warn!(target: "asr", error = %err, "服务端事件解密失败，已丢弃 | Failed to decrypt server event, dropped");
+                return None;
+            }
+        };
+        match self.wire_format {
+            WireFormat::Json => serde_json::from_slice(&plaintext).ok(),
+            WireFormat::Msgpack => rmp_serde::from_slice(&plaintext).ok(),
+        }
+    }
+
+    fn poll_acks(&mut self, traffic: &mut TrafficStats) {
+        while let Some(message) = self.session.try_recv() {
+            traffic.record_received(message.byte_len());
+            match self.decode(&message) {
+                Some(ServerEventPayload::Ack { received, missing, .. }) => self.acks = FrameAcks { received, missing },
+                _ => self.backlog.push_back(message),
+            }
+        }
+    }
+
+    async fn next(&mut self, traffic: &mut TrafficStats) -> Option<ServerMessage> {
+        if let Some(message) = self.backlog.pop_front() {
+            return Some(message);
+        }
+        let message = self.session.recv().await?;
+        traffic.record_received(message.byte_len());
+        Some(message)
+    }
+}
+
+/// 解析服务端事件：文本为 JSON，二进制帧为协商后的 MessagePack
+fn parse_event(message: &ServerMessage) -> Option<ServerEventPayload> {
+    match message {
+        ServerMessage::Text(text) => serde_json::from_str(text).ok(),
+        ServerMessage::Binary(bytes) => rmp_serde::from_slice(bytes).ok(),
+    }
+}
+
+/// 按服务器声明的能力调整 start：支持时启用帧序号、所选事件编码与服务端校正，去掉服务器不认识的识别语言
+fn negotiate(start: &mut StartPayload, capabilities: Option<&ServerCapabilities>, wire_format: WireFormat) {
+    start.seq_frames = capabilities.is_some_and(|caps| caps.supports(ServerCapabilities::SEQ_FRAMES));
+    start.use_cloud_api &= capabilities.is_some_and(|caps| caps.supports(ServerCapabilities::LLM));
+    start.wire_format = match wire_format {
+        WireFormat::Msgpack if capabilities.is_some_and(|caps| caps.supports(ServerCapabilities::MSGPACK)) => {
+            WireFormat::Msgpack
+        }
+        _ => WireFormat::Json,
+    };
+    let Some(language) = start.language.as_deref() else {
+        return;
+    };
+    match capabilities {
+        Some(caps) if !caps.supports_language(language) => {
+            if !caps.languages.is_empty() {
+                warn!(
+                    target: "asr",
+                    trace_id = start.trace_id.as_str(),
+                    language,
+                    supported = %caps.languages.join(","),
+                    "服务器不支持该识别语言，改为自动判断 | Language not supported by server, falling back to auto"
+                );
+            }
+            start.language = None;
+        }
+        _ => {}
+    }
+}
+
+/// 新会话的音频编码：服务器在 `pong.codecs` 中声明支持时才使用配置的编码，否则退回 Opus
+fn negotiate_codec(start: &StartPayload, capabilities: Option<&ServerCapabilities>, codec: AudioCodec) -> AudioCodec {
+    if codec.is_opus() || capabilities.is_some_and(|caps| caps.codecs.iter().any(|c| c == codec.name())) {
+        return codec;
+    }
+    warn!(
+        target: "asr",
+        trace_id = start.trace_id.as_str(),
+        codec = codec.name(),
+        "服务器不支持该音频编码，改用 Opus | Codec not supported by server, falling back to Opus"
+    );
+    AudioCodec::Opus
+}
+
+/// 启用端到端加密时：与服务器交换密钥，start 中的上下文改为密文。
+/// 返回实际发送的 start 与本会话的密钥；未启用时原样返回。
+fn seal_start(
+    start: &StartPayload,
+    capabilities: Option<&ServerCapabilities>,
+    config: &E2eConfig,
+) -> anyhow::Result<(StartPayload, Option<(Sealer, Opener)>)> {
+    if !config.enabled {
+        return Ok((start.clone(), None));
+    }
+    // 刚连上还没收到 pong：按暂时不可用处理，稍后补交
+    let capabilities = capabilities.context("server capabilities not received yet")?;
+    let server_key = e2e::server_key(config, capabilities)?;
+    let (public_key, mut sealer, opener) = e2e::handshake(server_key, &start.trace_id)?;
+    let context = serde_json::to_vec(&start.context).context("serialize context")?;
+    let mut sealed = start.clone();
+    sealed.context = ClientContextPayload::default();
+    sealed.e2e = Some(E2eStartPayload {
+        public_key,
+        context: BASE64.encode(sealer.seal(&context)),
+    });
+    Ok((sealed, Some((sealer, opener))))
+}
+
+/// 原始 PCM 帧：1 字节版本、1 字节声道数、2 字节大端采样数，其后为 16 位小端采样
+fn pcm_packet(samples: &[i16]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(4 + samples.len() * 2);
+    packet.push(PCM_FRAME_VERSION);
+    packet.push(1);
+    packet.extend_from_slice(&(samples.len() as u16).to_be_bytes());
+    for sample in samples {
+        packet.extend_from_slice(&sample.to_le_bytes());
+    }
+    packet
+}
+
+/// 带序号时在音频包前加 4 字节大端帧序号
+fn audio_frame(seq_frames: bool, seq: u32, packet: &[u8]) -> Vec<u8> {
+    if !seq_frames {
+        return packet.to_vec();
+    }
+    let mut frame = Vec::with_capacity(4 + packet.len());
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(packet);
+    frame
+}
+
+/// 等待服务器对续传 start 的答复，返回服务器已有的帧数
+async fn wait_resumed(
+    session: &mut LiveSession,
+    trace_id: Option<&str>,
+    traffic: &mut TrafficStats,
+    timeout: Duration,
+) -> anyhow::Result<u64> {
+    loop {
+        let message = tokio::time::timeout(timeout, session.next(traffic))
+            .await
+            .context("wait resumed timeout")?
+            .context("websocket closed")?;
+        match session.decode(&message) {
+            Some(ServerEventPayload::Resumed {
+                trace_id: got,
+                received,
+            }) if got.as_deref() == trace_id => return Ok(received),
+            Some(ServerEventPayload::Error { message, .. }) => anyhow::bail!(message),
+            _ => continue,
+        }
+    }
+}
+
+/// 等待本会话的识别结果（忽略 pong 与其它会话的消息）；连接断开时返回错误。
+/// 服务器最后一次 ack 报告的缺失帧计入本会话的 `dropped_packets`。
+async fn wait_result(
+    session: &mut LiveSession,
+    trace_id: Option<&str>,
+    traffic: &mut TrafficStats,
+    timeouts: SessionTimeouts,
+) -> anyhow::Result<SessionResult> {
+    let deadline = tokio::time::Instant::now() + timeouts.stop;
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let (limit, reason) = if remaining <= timeouts.read {
+            (remaining, "stop")
+        } else {
+            (timeouts.read, "read")
+        };
+        let Ok(received) = tokio::time::timeout(limit, session.next(traffic)).await else {
+            return Ok(SessionResult::TimedOut(reason));
+        };
+        let Some(message) = received else {
+            anyhow::bail!("websocket closed");
+        };
+        let Some(event) = session.decode(&message) else {
+            continue;
+        };
+        let (event_trace_id, result) = match event {
+            ServerEventPayload::Pong | ServerEventPayload::Resumed { .. } | ServerEventPayload::Correction { .. } => {
+                continue
+            }
+            ServerEventPayload::Ack {
+                trace_id: ack_trace_id,
+                received,
+                missing,
+            } => {
+                if ack_trace_id.as_deref() == trace_id {
+                    session.acks = FrameAcks { received, missing };
+                }
+                continue;
+            }
+            ServerEventPayload::FastText {
+                trace_id,
+                content,
+                confidence,
+                language,
+                ..
+            } => (
+                trace_id,
+                SessionResult::Text {
+                    content,
+                    confidence,
+                    language,
+                },
+            ),
+            ServerEventPayload::Error { trace_id, message } => (trace_id, SessionResult::ServerError(message)),
+        };
+        if let (Some(expected), Some(got)) = (trace_id, event_trace_id.as_deref()) {
+            if got != expected {
+                continue;
+            }
+        }
+        if session.acks.missing > 0 {
+            warn!(
+                target: "asr",
+                trace_id = trace_id.unwrap_or(""),
+                received = session.acks.received,
+                missing = session.acks.missing,
+                "服务器未收到部分音频帧，识别结果可能不完整 | Server missed audio frames, transcript may be truncated"
+            );
+            traffic.dropped_packets += session.acks.missing;
+        }
+        return Ok(result);
+    }
+}
+
+/// 识别结果已返回、等待服务端校正的会话；持有会话期间同一连接上的新会话需等它结束
+struct PendingCorrection {
+    session: LiveSession,
+    trace_id: String,
+    /// 是否在 start 中请求过校正；只是收听窗口时没等到不算错误
+    requested: bool,
+    /// 从识别结果到校正的最长等待
+    timeout: Duration,
+    tx: mpsc::Sender<AsrEvent>,
+}
+
+#[async_trait]
+impl ServerCorrection for PendingCorrection {
+    async fn wait(mut self: Box<Self>) -> anyhow::Result<Option<String>> {
+        // 网络用量已在识别结果时上报，校正消息不再计入
+        let mut traffic = TrafficStats::default();
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            let message = match tokio::time::timeout_at(deadline, self.session.next(&mut traffic)).await {
+                Ok(Some(message)) => message,
+                // 收听窗口内服务器没有再发消息属于正常情况
+                _ if !self.requested => return Ok(None),
+                Ok(None) => anyhow::bail!("websocket closed"),
+                Err(_) => anyhow::bail!("wait correction timeout"),
+            };
+            // 同一会话再次发来的 fast_text 是更新后的识别结果，与校正同样处理
+            let (trace_id, result) = match self.session.decode(&message) {
+                Some(ServerEventPayload::Correction {
+                    trace_id,
+                    replaced_text,
+                })
+                | Some(ServerEventPayload::FastText {
+                    trace_id,
+                    content: replaced_text,
+                    ..
+                }) => (trace_id, Ok(replaced_text)),
+                Some(ServerEventPayload::Error { trace_id, message }) => (trace_id, Err(message)),
+                _ => continue,
+            };
+            if trace_id.as_deref().is_some_and(|got| got != self.trace_id) {
+                continue;
+            }
+            let event = match &result {
+                Ok(text) => AsrEvent::Correction { text: text.clone() },
+                Err(message) => AsrEvent::Error {
+                    message: message.clone(),
+                },
+            };
+            let _ = self.tx.try_send(event);
+            return result.map(Some).map_err(|message| anyhow::anyhow!(message));
+        }
+    }
+}
+
+/// 离线暂存的会话：按原顺序重放 start、音频与 stop
+struct PendingSession {
+    connection: Arc<ConnectionManager>,
+    timeouts: SessionTimeouts,
+    wire_format: WireFormat,
+    e2e: E2eConfig,
+    trace_id: String,
+    start: StartPayload,
+    packets: Vec<Vec<u8>>,
+    /// 此前已发出过的音频包数（断线前的实时发送或中断的补交），再次发送计为重传
+    sent_before: usize,
+}
+
+impl PendingSession {
+    async fn replay(&mut self, traffic: &mut TrafficStats) -> anyhow::Result<SessionResult> {
+        let mut session = LiveSession::new(self.connection.open_session().await);
+        let capabilities = self.connection.capabilities();
+        negotiate(&mut self.start, capabilities.as_ref(), self.wire_format);
+        let seq_frames = self.start.seq_frames;
+        // 每次补交都重新交换密钥（服务器可能已重启）
+        let (start, keys) = seal_start(&self.start, capabilities.as_ref(), &self.e2e)?;
+        session.wire_format = start.wire_format;
+        let (mut sealer, opener) = keys.unzip();
+        session.opener = opener;
+        let start = serde_json::to_string(&ClientControlPayload::Start(start)).context("serialize start payload")?;
+        let len = start.len();
+        self.connection.send_text(start).await?;
+        traffic.record_text(len);
+        for (index, pkt) in self.packets.iter().enumerate() {
+            let mut frame = audio_frame(seq_frames, index as u32, pkt);
+            if let Some(sealer) = sealer.as_mut() {
+                frame = sealer.seal(&frame);
+            }
+            let len = frame.len();
+            self.connection.send_binary(frame).await?;
+            traffic.record_audio(len, index < self.sent_before);
+            self.sent_before = self.sent_before.max(index + 1);
+            session.poll_acks(traffic);
+        }
+        let stop = ClientControlPayload::Stop {
+            trace_id: Some(self.trace_id.clone()),
+            frames: seq_frames.then_some(self.packets.len() as u32),
+        };
+        let stop = serde_json::to_string(&stop).context("serialize stop payload")?;
+        let len = stop.len();
+        self.connection.send_text(stop).await?;
+        traffic.record_text(len);
+        wait_result(&mut session, Some(&self.trace_id), traffic, self.timeouts).await
+    }
+}
+
+#[async_trait]
+impl DeferredSession for PendingSession {
+    fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    async fn submit(mut self: Box<Self>) -> (anyhow::Result<String>, TrafficStats) {
+        let mut traffic = TrafficStats::default();
+        loop {
+            if self.connection.wait_connected(self.timeouts.connect).await.is_err() {
+                continue;
+            }
+            let err = match self.replay(&mut traffic).await {
+                Ok(SessionResult::Text { content, .. }) => return (Ok(content), traffic),
+                Ok(SessionResult::ServerError(message)) => return (Err(anyhow::anyhow!(message)), traffic),
+                Ok(SessionResult::TimedOut(reason)) => anyhow::anyhow!("wait deferred result timeout ({reason})"),
+                // 加密要求无法满足，重试也没用
+                Err(err) if err.is::<e2e::Refused>() => return (Err(err), traffic),
+                Err(err) => err,
+            };
+            warn!(
+                target: "asr",
+                trace_id = self.trace_id.as_str(),
+                error = %format!("{err:#}"),
+                "离线会话补交中断，稍后重试 | Deferred submission interrupted, retrying"
+            );
+            tokio::time::sleep(DEFERRED_RETRY_DELAY).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_is_negotiated_from_server_capabilities() {
+        assert_eq!(audio_frame(false, 7, &[1, 2]), vec![1, 2]);
+        assert_eq!(audio_frame(true, 258, &[1, 2]), vec![0, 0, 1, 2, 1, 2]);
+        assert_eq!(pcm_packet(&[1, -2]), vec![1, 1, 0, 2, 1, 0, 0xfe, 0xff]);
+
+        let mut start = StartPayload {
+            trace_id: "t1".to_string(),
+            sample_rate: 48000,
+            context: ClientContextPayload::default(),
+            use_cloud_api: false,
+            language: None,
+            seq_frames: false,
+            wire_format: WireFormat::Json,
+            codec: AudioCodec::Opus,
+            e2e: None,
+            resume: false,
+        };
+        // 旧服务器不认识的字段不发送
+        let text = serde_json::to_string(&ClientControlPayload::Start(start.clone())).expect("serialize");
+        assert!(text.starts_with(r#"{"type":"start","trace_id":"t1""#));
+        assert!(!text.contains("seq_frames"));
+        assert!(!text.contains("wire_format"));
+        assert!(!text.contains("codec"));
+        let caps = ServerCapabilities {
+            features: vec![
+                ServerCapabilities::SEQ_FRAMES.to_string(),
+                ServerCapabilities::MSGPACK.to_string(),
+            ],
+            codecs: vec!["opus".to_string()],
+            languages: vec!["auto".to_string(), "zh".to_string()],
+            ..ServerCapabilities::default()
+        };
+        start.language = Some("en".to_string());
+        start.use_cloud_api = true;
+        negotiate(&mut start, Some(&caps), WireFormat::Msgpack);
+        assert!(start.seq_frames);
+        assert_eq!(start.language, None);
+        // 服务器没有声明 llm 时不请求服务端校正
+        assert!(!start.use_cloud_api);
+        let text = serde_json::to_string(&ClientControlPayload::Start(start.clone())).expect("serialize");
+        assert!(text.contains(r#""seq_frames":true"#));
+        assert!(text.contains(r#""wire_format":"msgpack""#));
+
+        // 原始 PCM 只在服务器声明支持时使用
+        assert_eq!(
+            negotiate_codec(&start, Some(&caps), AudioCodec::PcmS16le),
+            AudioCodec::Opus
+        );
+        assert_eq!(negotiate_codec(&start, None, AudioCodec::PcmS16le), AudioCodec::Opus);
+        let lan = ServerCapabilities {
+            codecs: vec!["opus".to_string(), "pcm_s16le".to_string()],
+            ..caps.clone()
+        };
+        start.codec = negotiate_codec(&start, Some(&lan), AudioCodec::PcmS16le);
+        assert_eq!(start.codec, AudioCodec::PcmS16le);
+        let text = serde_json::to_string(&ClientControlPayload::Start(start.clone())).expect("serialize");
+        assert!(text.contains(r#""codec":"pcm_s16le""#));
+
+        // 旧版服务器（pong 不带能力）：不带序号，也不发送语言，事件仍为 JSON
+        start.language = Some("zh".to_string());
+        negotiate(&mut start, Some(&caps), WireFormat::Json);
+        assert_eq!(start.language.as_deref(), Some("zh"));
+        assert_eq!(start.wire_format, WireFormat::Json);
+        negotiate(&mut start, Some(&ServerCapabilities::default()), WireFormat::Msgpack);
+        assert!(!start.seq_frames);
+        assert_eq!(start.language, None);
+        assert_eq!(start.wire_format, WireFormat::Json);
+
+        let ack = r#"{"type":"ack","trace_id":"t1","seq":24,"received":24,"missing":1}"#;
+        assert!(matches!(
+            parse_event(&ServerMessage::Text(ack.to_string())),
+            Some(ServerEventPayload::Ack { missing: 1, .. })
+        ));
+        let value: serde_json::Value = serde_json::from_str(ack).expect("json");
+        let packed = rmp_serde::to_vec_named(&value).expect("msgpack");
+        assert!(packed.len() < ack.len());
+        assert!(matches!(
+            parse_event(&ServerMessage::Binary(packed)),
+            Some(ServerEventPayload::Ack { missing: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn stop_fails_when_server_never_answers() {
+        use crate::asr::mock_server::{MockAsrServer, MockReply};
+
+        // 测试服务端：只回应 ping，收到 stop 后不返回结果
+        let server = MockAsrServer::spawn(&[], MockReply::Silent).await;
+        let endpoint = server.endpoint.clone();
+
+        let timeouts = SessionTimeouts {
+            connect: Duration::from_secs(3),
+            read: Duration::from_millis(200),
+            stop: Duration::from_millis(500),
+            late_events: Duration::ZERO,
+        };
+        let mut engine = WebSocketAsrEngine::new(
+            &[endpoint],
+            timeouts,
+            WireFormat::Json,
+            AudioCodec::Opus,
+            E2eConfig::default(),
+        );
+        engine
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+
+        let err = tokio::time::timeout(Duration::from_secs(3), engine.stop())
+            .await
+            .expect("stop returns")
+            .expect_err("server never answers");
+        assert!(format!("{err:#}").contains("timeout"));
+        assert!(engine.take_deferred().is_none());
+    }
+
+    #[tokio::test]
+    async fn resumes_session_after_connection_drop() {
+        use futures_util::{SinkExt, StreamExt};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let endpoint = format!("ws://{}/ws", listener.local_addr().expect("addr"));
+        // 测试服务端：第一条连接收到 2 帧后断开；第二条连接答复续传，结束时返回收到的帧序号
+        tokio::spawn(async move {
+            let connections = Arc::new(AtomicUsize::new(0));
+            while let Ok((stream, _)) = listener.accept().await {
+                let first = connections.fetch_add(1, Ordering::SeqCst) == 0;
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.expect("accept");
+                    let mut seqs: Vec<String> = Vec::new();
+                    while let Some(Ok(msg)) = ws.next().await {
+                        match msg {
+                            Message::Text(text) if text.contains("ping") => {
+                                let pong = r#"{"type":"pong","features":["seq_frames","resume"]}"#;
+                                let _ = ws.send(Message::Text(pong.to_string())).await;
+                            }
+                            Message::Text(text) if text.contains(r#""resume":true"#) => {
+                                let resumed = r#"{"type":"resumed","trace_id":"t1","received":2}"#;
+                                let _ = ws.send(Message::Text(resumed.to_string())).await;
+                            }
+                            Message::Text(text) if text.contains(r#""type":"stop""#) => {
+                                let content = seqs.join(",");
+                                let reply = format!(r#"{{"type":"fast_text","trace_id":"t1","content":"{content}"}}"#);
+                                let _ = ws.send(Message::Text(reply)).await;
+                            }
+                            Message::Binary(frame) => {
+                                seqs.push(u32::from_be_bytes(frame[..4].try_into().expect("seq")).to_string());
+                                if first && seqs.len() == 2 {
+                                    return;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+            }
+        });
+
+        let timeouts = SessionTimeouts {
+            connect: Duration::from_secs(3),
+            read: Duration::from_secs(3),
+            stop: Duration::from_secs(3),
+            late_events: Duration::ZERO,
+        };
+        let mut engine = WebSocketAsrEngine::new(
+            &[endpoint],
+            timeouts,
+            WireFormat::Json,
+            AudioCodec::Opus,
+            E2eConfig::default(),
+        );
+        engine
+            .connection
+            .wait_capabilities(Duration::from_secs(3))
+            .await
+            .expect("capabilities");
+        engine
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        // 等服务器断开后继续录音：这两帧只能在续传时补发
+        tokio::time::timeout(Duration::from_secs(3), async {
+            while engine.connection.status().state == LinkState::Connected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("server drops connection");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert!(engine.suspended);
+
+        let text = tokio::time::timeout(Duration::from_secs(5), engine.stop())
+            .await
+            .expect("stop returns")
+            .expect("resumed session");
+        assert_eq!(text, "2,3");
+        assert!(engine.take_deferred().is_none());
+    }
+
+    #[tokio::test]
+    async fn engine_round_trips_sessions_with_mock_server() {
+        use crate::asr::mock_server::{MockAsrServer, MockReply};
+
+        let features = [
+            ServerCapabilities::SEQ_FRAMES,
+            ServerCapabilities::CANCEL,
+            ServerCapabilities::MSGPACK,
+        ];
+        let server = MockAsrServer::spawn(
+            &features,
+            MockReply::Text {
+                content: "你好世界".to_string(),
+                confidence: Some(0.9),
+                language: Some("zh".to_string()),
+            },
+        )
+        .await;
+        let timeouts = SessionTimeouts {
+            connect: Duration::from_secs(3),
+            read: Duration::from_secs(3),
+            stop: Duration::from_secs(3),
+            late_events: Duration::ZERO,
+        };
+        let endpoints = [server.endpoint.clone()];
+        let mut engine = WebSocketAsrEngine::new(
+            &endpoints,
+            timeouts,
+            WireFormat::Msgpack,
+            AudioCodec::Opus,
+            E2eConfig::default(),
+        );
+        engine
+            .connection
+            .wait_capabilities(Duration::from_secs(3))
+            .await
+            .expect("capabilities");
+
+        let context = AsrContext {
+            app_name: "Notes".to_string(),
+            ..AsrContext::default()
+        };
+        engine.start("t1".to_string(), 16000, context).await.expect("start");
+        for _ in 0..5 {
+            engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        }
+        assert_eq!(engine.stop().await.expect("stop"), "你好世界");
+        assert_eq!(engine.take_confidence(), Some(0.9));
+        assert_eq!(engine.take_language().as_deref(), Some("zh"));
+        assert!(engine.take_traffic().is_some_and(|traffic| traffic.audio_packets > 0));
+        {
+            let received = server.received();
+            assert_eq!(received.starts[0]["trace_id"], "t1");
+            assert_eq!(received.starts[0]["context"]["app_name"], "Notes");
+            assert_eq!(received.starts[0]["seq_frames"], true);
+            assert_eq!(received.starts[0]["wire_format"], "msgpack");
+            // 服务端收到的帧数与 stop 中声明的一致
+            assert!(received.frames[0] > 0);
+            assert_eq!(received.stops[0]["frames"], received.frames[0]);
+        }
+
+        // 服务端报错：stop 返回错误，不转入补交
+        server.set_reply(MockReply::Error("model busy".to_string()));
+        engine
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        let err = engine.stop().await.expect_err("server error");
+        assert!(format!("{err:#}").contains("model busy"));
+        assert!(engine.take_deferred().is_none());
+
+        // 服务端声明支持 cancel 时通知服务端
+        engine
+            .start("t3".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.cancel().await;
+        tokio::time::timeout(Duration::from_secs(3), async {
+            while server.received().cancels.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("cancel reaches server");
+        assert_eq!(server.received().cancels[0]["trace_id"], "t3");
+    }
+
+    #[tokio::test]
+    async fn server_corrections_are_accepted_after_fast_text() {
+        use crate::asr::mock_server::{MockAsrServer, MockReply};
+
+        let server = MockAsrServer::spawn(
+            &[ServerCapabilities::LLM],
+            MockReply::Corrected {
+                content: "今天天汽不错".to_string(),
+                corrected: "今天天气不错。".to_string(),
+            },
+        )
+        .await;
+        let timeouts = SessionTimeouts {
+            connect: Duration::from_secs(3),
+            read: Duration::from_secs(3),
+            stop: Duration::from_secs(3),
+            late_events: Duration::from_millis(300),
+        };
+        let endpoints = [server.endpoint.clone()];
+        let mut engine = WebSocketAsrEngine::new(
+            &endpoints,
+            timeouts,
+            WireFormat::Json,
+            AudioCodec::Opus,
+            E2eConfig::default(),
+        );
+        engine
+            .connection
+            .wait_capabilities(Duration::from_secs(3))
+            .await
+            .expect("capabilities");
+
+        let context = AsrContext {
+            server_correction: true,
+            ..AsrContext::default()
+        };
+        engine.start("t1".to_string(), 16000, context).await.expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天汽不错");
+        assert_eq!(server.received().starts[0]["use_cloud_api"], true);
+        let correction = engine.take_server_correction().expect("pending correction");
+        assert_eq!(
+            correction.wait().await.expect("correction").as_deref(),
+            Some("今天天气不错。")
+        );
+        // 校正也作为 ASR 事件发出，排在识别结果之后
+        let events: Vec<_> = std::iter::from_fn(|| engine.events().try_recv().ok()).collect();
+        assert!(matches!(
+            events.as_slice(),
+            [AsrEvent::Final { .. }, AsrEvent::Correction { text }] if text == "今天天气不错。"
+        ));
+
+        // 未请求时在收听窗口内仍接收服务器主动发来的校正
+        engine
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天汽不错");
+        assert_eq!(server.received().starts[1]["use_cloud_api"], false);
+        let correction = engine.take_server_correction().expect("listening window");
+        assert_eq!(
+            correction.wait().await.expect("correction").as_deref(),
+            Some("今天天气不错。")
+        );
+
+        // 窗口内没有后续消息不算错误
+        server.set_reply(MockReply::text("今天天气不错"));
+        engine
+            .start("t3".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        engine.feed_audio(&[0i16; 640]).await.expect("feed");
+        assert_eq!(engine.stop().await.expect("stop"), "今天天气不错");
+        let correction = engine.take_server_correction().expect("listening window");
+        assert_eq!(correction.wait().await.expect("no correction"), None);
+    }
 }
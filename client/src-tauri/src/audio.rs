@@ -6,6 +6,17 @@ use std::time::Instant;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info};
 
+/// 录音来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioSource {
+    #[default]
+    Microphone,
+    /// 系统正在播放的声音（输出设备回环录音），目前只支持 Windows；
+    /// 其他平台可安装虚拟声卡（BlackHole、PulseAudio monitor 等）并作为麦克风选择
+    Loopback,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct InputDeviceInfo {
     pub name: String,
@@ -31,6 +42,15 @@ impl AudioRecorder {
 pub fn start_audio(
     trace_id: String,
     device_name: Option<String>,
+) -> anyhow::Result<(AudioRecorder, mpsc::Receiver<Vec<i16>>)> {
+    start_capture(trace_id, AudioSource::Microphone, device_name)
+}
+
+/// 从指定来源录音；`device_name` 为空时使用默认麦克风或默认输出设备
+pub fn start_capture(
+    trace_id: String,
+    source: AudioSource,
+    device_name: Option<String>,
 ) -> anyhow::Result<(AudioRecorder, mpsc::Receiver<Vec<i16>>)> {
     let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(1);
     let (ready_tx, ready_rx) = crossbeam_channel::bounded::<anyhow::Result<u32>>(1);
@@ -42,10 +62,13 @@ pub fn start_audio(
         let start_result =
             (|| -> anyhow::Result<(Stream, crossbeam_channel::Receiver<Vec<f32>>, u32, String)> {
             let host = cpal::default_host();
-            let device = select_input_device(&host, requested_device.as_deref())?;
+            let device = match source {
+                AudioSource::Microphone => select_input_device(&host, requested_device.as_deref())?,
+                AudioSource::Loopback => select_loopback_device(&host, requested_device.as_deref())?,
+            };
 
             let device_name = device.name().unwrap_or_else(|_| "default".to_string());
-            let (config, sample_format, sample_rate) = pick_stream_config(&device)?;
+            let (config, sample_format, sample_rate) = pick_stream_config(&device, source)?;
             let channels = config.channels as usize;
 
             let (raw_tx, raw_rx) = crossbeam_channel::bounded::<Vec<f32>>(16);
@@ -211,11 +234,50 @@ fn select_input_device(host: &cpal::Host, requested: Option<&str>) -> anyhow::Re
         .ok_or_else(|| anyhow!("no input device"))
 }
 
-fn pick_stream_config(device: &Device) -> anyhow::Result<(StreamConfig, SampleFormat, u32)> {
+/// 回环录音：在输出设备上打开输入流（WASAPI loopback）
+fn select_loopback_device(host: &cpal::Host, requested: Option<&str>) -> anyhow::Result<Device> {
+    if !cfg!(target_os = "windows") {
+        return Err(anyhow!(
+            "当前平台不支持直接录制系统声音，请安装虚拟声卡并作为麦克风选择 | \
+             Loopback capture is Windows-only, select a virtual audio device as the microphone instead"
+        ));
+    }
+
+    if let Some(want) = requested.map(str::trim).filter(|v| !v.is_empty()) {
+        if let Ok(devices) = host.output_devices() {
+            for device in devices {
+                if device.name().is_ok_and(|name| name == want) {
+                    info!(
+                        target: "audio",
+                        device = want,
+                        "选择回环录音设备 | Loopback device selected"
+                    );
+                    return Ok(device);
+                }
+            }
+        }
+
+        tracing::warn!(
+            target: "audio",
+            device = want,
+            "未找到指定输出设备，回退默认设备 | Requested output device not found, falling back to default"
+        );
+    }
+
+    host.default_output_device()
+        .ok_or_else(|| anyhow!("no output device"))
+}
+
+fn pick_stream_config(device: &Device, source: AudioSource) -> anyhow::Result<(StreamConfig, SampleFormat, u32)> {
     let target_rates: [u32; 5] = [48000, 16000, 24000, 12000, 8000];
 
     let mut ranges = Vec::new();
-    if let Ok(configs) = device.supported_input_configs() {
+    // 回环录音只能使用输出设备当前的格式
+    let configs = match source {
+        AudioSource::Microphone => device.supported_input_configs().ok(),
+        AudioSource::Loopback => None,
+    };
+    if let Some(configs) = configs {
         for cfg in configs {
             debug!(
                 target: "audio",
@@ -249,7 +311,10 @@ fn pick_stream_config(device: &Device) -> anyhow::Result<(StreamConfig, SampleFo
         }
     }
 
-    let default_config = device.default_input_config().context("default_input_config")?;
+    let default_config = match source {
+        AudioSource::Microphone => device.default_input_config().context("default_input_config")?,
+        AudioSource::Loopback => device.default_output_config().context("default_output_config")?,
+    };
     let sample_rate = default_config.sample_rate().0;
     let sample_format = default_config.sample_format();
 
@@ -341,3 +406,72 @@ fn f32_to_i16(sample: f32) -> i16 {
     let sample = sample.clamp(-1.0, 1.0);
     (sample * i16::MAX as f32) as i16
 }
+
+/// 录音电平采样：把 PCM 按固定窗口切成若干桶，输出每桶的 RMS（0.0~1.0），供前端画波形。
+pub struct LevelMeter {
+    bucket_len: usize,
+    buckets_per_update: usize,
+    sum_squares: f64,
+    filled: usize,
+    levels: Vec<f32>,
+}
+
+impl LevelMeter {
+    /// 每 50ms 输出一次，每次 5 个桶（每桶 10ms）
+    const UPDATE_MS: u32 = 50;
+    const BUCKETS_PER_UPDATE: usize = 5;
+
+    pub fn new(sample_rate: u32) -> Self {
+        let update_len = (sample_rate * Self::UPDATE_MS / 1000).max(Self::BUCKETS_PER_UPDATE as u32) as usize;
+        Self {
+            bucket_len: update_len / Self::BUCKETS_PER_UPDATE,
+            buckets_per_update: Self::BUCKETS_PER_UPDATE,
+            sum_squares: 0.0,
+            filled: 0,
+            levels: Vec::with_capacity(Self::BUCKETS_PER_UPDATE),
+        }
+    }
+
+    /// 喂入一帧 PCM；攒满一次更新的桶数时返回电平
+    pub fn push(&mut self, pcm: &[i16]) -> Option<Vec<f32>> {
+        let mut ready = None;
+        for &sample in pcm {
+            let value = sample as f64 / i16::MAX as f64;
+            self.sum_squares += value * value;
+            self.filled += 1;
+            if self.filled < self.bucket_len {
+                continue;
+            }
+
+            let rms = (self.sum_squares / self.filled as f64).sqrt().min(1.0);
+            self.levels.push(rms as f32);
+            self.sum_squares = 0.0;
+            self.filled = 0;
+            if self.levels.len() >= self.buckets_per_update {
+                ready = Some(std::mem::replace(
+                    &mut self.levels,
+                    Vec::with_capacity(self.buckets_per_update),
+                ));
+            }
+        }
+        ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_meter_emits_buckets_every_50ms() {
+        let mut meter = LevelMeter::new(16000);
+        // 20ms 一帧：前两帧不足 50ms
+        assert!(meter.push(&[i16::MAX; 320]).is_none());
+        assert!(meter.push(&[0; 320]).is_none());
+
+        let levels = meter.push(&[0; 320]).expect("levels");
+        assert_eq!(levels.len(), 5);
+        assert!((levels[0] - 1.0).abs() < 1e-3);
+        assert_eq!(levels[4], 0.0);
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+use tracing::{info, warn};
+
+use crate::cli;
+use crate::config;
+use crate::error::{AppError, ErrorCode};
+use crate::notify::Notifier;
+use crate::url_scheme;
+
+/// 正在识别文件；同一时间只识别一个，避免重复点击占满 ASR 连接
+static TRANSCRIBING: AtomicBool = AtomicBool::new(false);
+
+/// 用当前配置的 ASR 与 LLM 识别音频文件（WAV/MP3/OGG），结果写入剪贴板；不经过录音与注入
+pub async fn transcribe_to_clipboard(path: &Path) -> Result<String, AppError> {
+    if TRANSCRIBING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::new(ErrorCode::Busy, "正在识别另一个文件"));
+    }
+    let result = transcribe(path).await;
+    TRANSCRIBING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn transcribe(path: &Path) -> Result<String, AppError> {
+    let (config, _) = config::load_with_path();
+    let text = cli::transcribe(&config, path, true)
+        .await
+        .map_err(AppError::with_code(ErrorCode::AsrFailed))?;
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text.clone()))
+        .map_err(|err| AppError::new(ErrorCode::Io, format!("写入剪贴板失败: {err}")))?;
+    info!(
+        target: "app",
+        path = %path.display(),
+        len = text.chars().count(),
+        "录音文件已识别 | Audio file transcribed"
+    );
+    Ok(text)
+}
+
+/// 识别剪贴板中路径指向的音频文件，结果写回剪贴板
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub async fn transcribe_clipboard() -> Result<String, AppError> {
+    let clipboard = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|err| AppError::new(ErrorCode::Io, format!("读取剪贴板失败: {err}")))?;
+    let path = url_scheme::clipboard_audio_path(&clipboard)
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, "剪贴板中没有音频文件路径"))?;
+    transcribe_to_clipboard(&path).await
+}
+
+/// 托盘"识别音频文件…"：选择文件后在后台识别，完成或失败时发通知
+pub fn pick_and_transcribe(app: &tauri::AppHandle) {
+    let app_for_result = app.clone();
+    app.dialog()
+        .file()
+        .set_title("选择要识别的音频 | Choose an audio file")
+        .add_filter("Audio", &cli::AUDIO_EXTENSIONS)
+        .pick_file(move |picked| {
+            let Some(path) = picked.and_then(|picked| picked.into_path().ok()) else {
+                return;
+            };
+            spawn_transcription(&app_for_result, path);
+        });
+}
+
+fn spawn_transcription(app: &tauri::AppHandle, path: PathBuf) {
+    let notifier = app
+        .try_state::<Arc<Notifier>>()
+        .map(|notifier| notifier.inner().clone());
+    tauri::async_runtime::spawn(async move {
+        let result = transcribe_to_clipboard(&path).await;
+        if let Err(error) = &result {
+            warn!(
+                target: "app",
+                path = %path.display(),
+                error = %error,
+                "录音文件识别失败 | Audio file transcription failed"
+            );
+        }
+        let Some(notifier) = notifier else {
+            return;
+        };
+        match result {
+            Ok(text) => notifier.audio_transcribed(&text),
+            Err(error) => notifier.failure(&error),
+        }
+    });
+}
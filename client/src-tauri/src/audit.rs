@@ -0,0 +1,151 @@
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Context as _;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// 审计记录：每次会话最终输入的文字、目标应用与时间，每行一个 JSON 对象追加到单独的文件，
+/// 供需要留存输入记录的场景（医疗、法律听写）使用。记录的是原文，不受隐私模式影响
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 审计文件路径；为空时使用程序目录下的 `audit/ghosttype_audit.jsonl`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 记录的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// 听写结果（校正后以校正结果为准）
+    Dictation,
+    /// 服务器恢复后补交的离线录音，输入到补交时的焦点
+    Deferred,
+    /// 按指令改写选中的文字
+    Rewrite,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    trace_id: Option<&'a str>,
+    app: &'a str,
+    kind: AuditKind,
+    corrected: bool,
+    text: &'a str,
+}
+
+/// 当前审计文件，未启用时为空；写入时持有锁，保证每条记录完整且按顺序
+fn current() -> &'static Mutex<Option<PathBuf>> {
+    static CURRENT: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    CURRENT.get_or_init(|| Mutex::new(None))
+}
+
+/// 启动和保存配置时调用
+pub fn install(config: &AuditConfig) {
+    *current().lock().expect("audit lock") = config.enabled.then(|| resolve_audit_path(config));
+}
+
+/// 记录一次输入；写入失败只记录日志
+pub fn record(trace_id: Option<&str>, app: &str, kind: AuditKind, corrected: bool, text: &str) {
+    let current = current().lock().expect("audit lock");
+    let Some(path) = current.as_deref() else {
+        return;
+    };
+    let entry = AuditEntry {
+        timestamp: String::new(),
+        trace_id,
+        app,
+        kind,
+        corrected,
+        text,
+    };
+    if let Err(err) = append(path, entry, Local::now()) {
+        warn!(
+            target: "audit",
+            trace_id = trace_id.unwrap_or(""),
+            path = %path.display(),
+            error = %format!("{err:#}"),
+            "审计记录写入失败 | Failed to write audit record"
+        );
+    }
+}
+
+fn append(path: &Path, mut entry: AuditEntry<'_>, now: DateTime<Local>) -> anyhow::Result<()> {
+    entry.timestamp = now.to_rfc3339_opts(SecondsFormat::Millis, false);
+    let mut line = serde_json::to_string(&entry).context("serialize audit entry")?;
+    line.push('\n');
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("write {}", path.display()))
+}
+
+fn resolve_audit_path(config: &AuditConfig) -> PathBuf {
+    if let Some(path) = config.path.as_deref().map(str::trim).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("audit").join("ghosttype_audit.jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn entries_are_appended_as_json_lines() {
+        let dir = std::env::temp_dir().join(format!("ghosttype-audit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("audit.jsonl");
+        let time = Local.with_ymd_and_hms(2024, 5, 1, 9, 30, 5).single().expect("time");
+        let entry = |kind, text| AuditEntry {
+            timestamp: String::new(),
+            trace_id: Some("t1"),
+            app: "Epic",
+            kind,
+            corrected: false,
+            text,
+        };
+        append(&path, entry(AuditKind::Dictation, "血压 120/80"), time).expect("append");
+        append(&path, entry(AuditKind::Rewrite, "第二行\n续"), time).expect("append");
+
+        let content = std::fs::read_to_string(&path).expect("read");
+        let _ = std::fs::remove_dir_all(&dir);
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["app"], "Epic");
+        assert_eq!(lines[0]["kind"], "dictation");
+        assert_eq!(lines[0]["text"], "血压 120/80");
+        assert!(lines[0]["timestamp"]
+            .as_str()
+            .expect("timestamp")
+            .starts_with("2024-05-01T09:30:05.000"));
+        assert_eq!(lines[1]["kind"], "rewrite");
+        assert_eq!(lines[1]["text"], "第二行\n续");
+
+        assert!(resolve_audit_path(&AuditConfig::default()).ends_with("audit/ghosttype_audit.jsonl"));
+    }
+}
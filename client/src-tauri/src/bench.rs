@@ -0,0 +1,226 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as _};
+
+use crate::cli::{self, StageTimings, Transcriber};
+use crate::config::{self, ClientConfig};
+use crate::language;
+use crate::opus::OpusEncoder;
+use crate::privacy;
+use crate::proxy;
+use crate::tls;
+
+const HEADER: &str = "file\taudio_s\tencode_ms\tfeed_ms\tasr_ms\tllm_ms\ttotal_ms\twer\ttext";
+
+/// 一个 WAV 文件的评测结果
+struct Sample {
+    name: String,
+    audio: Duration,
+    /// 只编码不发送的耗时，与网络无关
+    encode: Duration,
+    timings: StageTimings,
+    total: Duration,
+    /// 与参考文本的编辑距离及参考文本的词数；没有参考文本时为空
+    errors: Option<(usize, usize)>,
+    text: String,
+}
+
+impl Sample {
+    fn row(&self) -> String {
+        let wer = self.errors.map(|(errors, words)| percent(errors, words));
+        format!(
+            "{}\t{:.2}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.audio.as_secs_f64(),
+            ms(self.encode),
+            ms(self.timings.feed),
+            ms(self.timings.asr),
+            self.timings.llm.map(ms).unwrap_or_else(|| "-".to_string()),
+            ms(self.total),
+            wer.unwrap_or_else(|| "-".to_string()),
+            self.text.replace(['\t', '\n'], " ")
+        )
+    }
+}
+
+/// `ghosttype bench <dir>`：逐个识别目录中的 WAV，结果按制表符分隔输出到 stdout；
+/// 同名的 `.txt` 为参考文本。有文件失败时返回非零退出码
+pub fn run(dir: &Path, use_llm: bool, realtime: bool) -> i32 {
+    let (config, _) = config::load_with_path();
+    privacy::install(&config.privacy);
+    proxy::install(config.proxy.clone());
+    tls::install(config.tls.clone());
+    match tauri::async_runtime::block_on(bench(&config, dir, use_llm, realtime)) {
+        Ok(failed) => i32::from(failed > 0),
+        Err(err) => {
+            eprintln!("评测失败 | Benchmark failed: {err:#}");
+            1
+        }
+    }
+}
+
+/// 返回失败的文件数
+async fn bench(config: &ClientConfig, dir: &Path, use_llm: bool, realtime: bool) -> anyhow::Result<usize> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("读取 {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav")))
+        .collect();
+    if paths.is_empty() {
+        bail!("目录中没有 WAV 文件 | No WAV files in {}", dir.display());
+    }
+    paths.sort();
+
+    let mut transcriber = Transcriber::new(config, use_llm)?;
+    transcriber.realtime = realtime;
+    println!("{HEADER}");
+    let mut samples = Vec::new();
+    let mut failed = 0;
+    for (index, path) in paths.iter().enumerate() {
+        match bench_file(&mut transcriber, path, index).await {
+            Ok(sample) => {
+                println!("{}", sample.row());
+                samples.push(sample);
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("{}: {err:#}", path.display());
+            }
+        }
+    }
+    println!("{}", summary(&samples, failed));
+    Ok(failed)
+}
+
+async fn bench_file(transcriber: &mut Transcriber, path: &Path, index: usize) -> anyhow::Result<Sample> {
+    let (sample_rate, pcm) = cli::load_audio(path)?;
+    let reference_path = path.with_extension("txt");
+    let reference = match std::fs::read_to_string(&reference_path) {
+        Ok(reference) => Some(reference),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).with_context(|| format!("读取 {}", reference_path.display())),
+    };
+    let encode = encode_time(sample_rate, &pcm)?;
+
+    let started = Instant::now();
+    let (text, timings) = transcriber.run(format!("bench-{index}"), sample_rate, &pcm).await?;
+    let total = started.elapsed();
+    Ok(Sample {
+        name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+        audio: Duration::from_secs_f64(pcm.len() as f64 / f64::from(sample_rate)),
+        encode,
+        timings,
+        total,
+        errors: reference.map(|reference| word_errors(&reference, &text)),
+        text,
+    })
+}
+
+/// 单独编码一遍，得到不含网络的 Opus 编码耗时
+fn encode_time(sample_rate: u32, pcm: &[i16]) -> anyhow::Result<Duration> {
+    let mut encoder = OpusEncoder::new(sample_rate)?;
+    let mut out = vec![0u8; 4096];
+    let started = Instant::now();
+    for frame in pcm.chunks_exact((sample_rate / 50) as usize) {
+        encoder.encode(frame, &mut out)?;
+    }
+    Ok(started.elapsed())
+}
+
+/// 各阶段平均耗时；WER 按全部参考文本的总词数计算
+fn summary(samples: &[Sample], failed: usize) -> String {
+    let mean = |durations: Vec<Duration>| {
+        if durations.is_empty() {
+            return "-".to_string();
+        }
+        ms(durations.iter().sum::<Duration>() / durations.len() as u32)
+    };
+    let (errors, words) = samples
+        .iter()
+        .filter_map(|sample| sample.errors)
+        .fold((0, 0), |(errors, words), (e, w)| (errors + e, words + w));
+    let wer = if words > 0 { percent(errors, words) } else { "-".to_string() };
+    format!(
+        "mean\t{:.2}\t{}\t{}\t{}\t{}\t{}\t{wer}\t{} ok, {failed} failed",
+        samples.iter().fold(0.0, |sum, sample| sum + sample.audio.as_secs_f64()) / samples.len().max(1) as f64,
+        mean(samples.iter().map(|sample| sample.encode).collect()),
+        mean(samples.iter().map(|sample| sample.timings.feed).collect()),
+        mean(samples.iter().map(|sample| sample.timings.asr).collect()),
+        mean(samples.iter().filter_map(|sample| sample.timings.llm).collect()),
+        mean(samples.iter().map(|sample| sample.total).collect()),
+        samples.len()
+    )
+}
+
+fn ms(duration: Duration) -> String {
+    format!("{:.0}", duration.as_secs_f64() * 1000.0)
+}
+
+fn percent(errors: usize, words: usize) -> String {
+    format!("{:.1}%", errors as f64 * 100.0 / words.max(1) as f64)
+}
+
+/// 返回（编辑距离，参考文本词数）。汉字、假名、谚文按字计，其它按词计；忽略大小写与标点
+fn word_errors(reference: &str, hypothesis: &str) -> (usize, usize) {
+    let reference = tokens(reference);
+    let hypothesis = tokens(hypothesis);
+    (edit_distance(&reference, &hypothesis), reference.len())
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        let cjk = language::is_cjk_letter(c);
+        if !cjk && (c.is_alphanumeric() || c == '\'') {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        if cjk {
+            tokens.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+fn edit_distance(a: &[String], b: &[String]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_errors_count_cjk_by_character() {
+        assert_eq!(word_errors("今天天气很好。", "今天天汽很好"), (1, 6));
+        assert_eq!(word_errors("Hello, world!", "hello word"), (1, 2));
+        assert_eq!(word_errors("打开 GitHub 页面", "打开github页面"), (0, 5));
+        assert_eq!(word_errors("send it now", "send it"), (1, 3));
+        assert_eq!(word_errors("", "extra"), (1, 0));
+
+        assert_eq!(percent(1, 6), "16.7%");
+        assert_eq!(summary(&[], 2), "mean\t0.00\t-\t-\t-\t-\t-\t-\t0 ok, 2 failed");
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tracing::warn;
+
+const CAPTION_LABEL: &str = "captions";
+
+/// 与 tauri.conf.json 中 captions 窗口尺寸保持一致
+const CAPTION_WIDTH: f64 = 720.0;
+const CAPTION_HEIGHT: f64 = 96.0;
+
+/// 字幕条距离屏幕边缘的距离
+const CAPTION_MARGIN: f64 = 72.0;
+
+/// 实时字幕：置顶的字幕条实时显示识别中的文字（只显示、不输入），用于无障碍或在输出前确认识别效果
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CaptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub position: CaptionPosition,
+    /// 说完后字幕保留的时长；会议转写期间一直显示
+    #[serde(default = "default_linger_ms")]
+    pub linger_ms: u64,
+}
+
+impl Default for CaptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: CaptionPosition::default(),
+            linger_ms: default_linger_ms(),
+        }
+    }
+}
+
+fn default_linger_ms() -> u64 {
+    3_000
+}
+
+/// 字幕条在主屏幕上的位置（水平居中）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPosition {
+    #[default]
+    Bottom,
+    Top,
+}
+
+pub struct CaptionController {
+    app: tauri::AppHandle,
+    config: Mutex<CaptionConfig>,
+    /// 每次显示或安排隐藏都递增，只有最后一次安排的隐藏生效
+    generation: Arc<AtomicU64>,
+    shown: Arc<AtomicBool>,
+    /// 会议转写期间字幕常驻
+    pinned: AtomicBool,
+}
+
+impl CaptionController {
+    pub fn new(app: tauri::AppHandle, config: CaptionConfig) -> Self {
+        Self {
+            app,
+            config: Mutex::new(config),
+            generation: Arc::new(AtomicU64::new(0)),
+            shown: Arc::new(AtomicBool::new(false)),
+            pinned: AtomicBool::new(false),
+        }
+    }
+
+    /// 保存配置后立即生效；关闭时马上隐藏
+    pub fn set_config(&self, config: &CaptionConfig) {
+        *self.config.lock().expect("caption config lock") = config.clone();
+        // 位置可能变了，下次显示时重新定位
+        self.hide_now();
+        if config.enabled && self.pinned.load(Ordering::SeqCst) {
+            self.show();
+        }
+    }
+
+    /// 有识别内容时调用；已显示时只取消待执行的隐藏
+    pub fn show(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let position = {
+            let config = self.config.lock().expect("caption config lock");
+            if !config.enabled {
+                return;
+            }
+            config.position
+        };
+        if self.shown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let Some(window) = self.app.get_webview_window(CAPTION_LABEL) else {
+            return;
+        };
+
+        if let Ok(Some(monitor)) = window.primary_monitor() {
+            let origin = monitor.position();
+            let size = monitor.size();
+            let (x, y) = caption_position(
+                (f64::from(origin.x), f64::from(origin.y)),
+                (f64::from(size.width), f64::from(size.height)),
+                monitor.scale_factor(),
+                position,
+            );
+            let position = tauri::Position::Physical(tauri::PhysicalPosition::new(x as i32, y as i32));
+            if let Err(err) = window.set_position(position) {
+                warn!(target: "caption", error = %err, "字幕条定位失败 | Caption position failed");
+            }
+        }
+
+        let _ = window.set_ignore_cursor_events(true);
+        if let Err(err) = window.show() {
+            warn!(target: "caption", error = %err, "字幕条显示失败 | Caption show failed");
+        }
+    }
+
+    /// 一次识别结束：保留 `linger_ms` 后隐藏，期间有新的识别内容则继续显示
+    pub fn hide_later(&self) {
+        if self.pinned.load(Ordering::SeqCst) {
+            return;
+        }
+        let gen = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let linger = Duration::from_millis(self.config.lock().expect("caption config lock").linger_ms);
+        let generation = self.generation.clone();
+        let shown = self.shown.clone();
+        let app = self.app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(linger).await;
+            if generation.load(Ordering::SeqCst) != gen {
+                return;
+            }
+            shown.store(false, Ordering::SeqCst);
+            if let Some(window) = app.get_webview_window(CAPTION_LABEL) {
+                let _ = window.hide();
+            }
+        });
+    }
+
+    /// 会议转写开始时常驻显示，结束后按正常方式隐藏
+    pub fn set_pinned(&self, pinned: bool) {
+        self.pinned.store(pinned, Ordering::SeqCst);
+        if pinned {
+            self.show();
+        } else {
+            self.hide_later();
+        }
+    }
+
+    fn hide_now(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.shown.store(false, Ordering::SeqCst);
+        if let Some(window) = self.app.get_webview_window(CAPTION_LABEL) {
+            let _ = window.hide();
+        }
+    }
+}
+
+/// 屏幕内水平居中，距上/下边缘留出边距（物理像素）
+fn caption_position(origin: (f64, f64), size: (f64, f64), scale: f64, position: CaptionPosition) -> (f64, f64) {
+    let width = CAPTION_WIDTH * scale;
+    let height = CAPTION_HEIGHT * scale;
+    let margin = CAPTION_MARGIN * scale;
+    let x = origin.0 + ((size.0 - width) / 2.0).max(0.0);
+    let y = match position {
+        CaptionPosition::Bottom => origin.1 + (size.1 - height - margin).max(0.0),
+        CaptionPosition::Top => origin.1 + margin,
+    };
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caption_bar_is_centered_on_the_monitor_edge() {
+        assert_eq!(
+            caption_position((0.0, 0.0), (1920.0, 1080.0), 1.0, CaptionPosition::Bottom),
+            (600.0, 912.0)
+        );
+        assert_eq!(
+            caption_position((1920.0, 0.0), (2880.0, 1800.0), 2.0, CaptionPosition::Top),
+            (2640.0, 144.0)
+        );
+        // 屏幕比字幕条还窄时贴着左边
+        assert_eq!(
+            caption_position((0.0, 0.0), (640.0, 480.0), 1.0, CaptionPosition::Bottom),
+            (0.0, 312.0)
+        );
+    }
+}
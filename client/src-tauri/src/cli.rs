@@ -0,0 +1,467 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as _};
+use tracing::warn;
+
+use crate::asr::{self, AsrContext, AsrEngine};
+use crate::config::{self, ClientConfig};
+use crate::dictionary::LearnedDictionary;
+use crate::language::{self, Language, LanguageConfig};
+use crate::llm::{self, LlmEngine};
+use crate::privacy;
+use crate::proxy;
+use crate::punctuation::PunctuationConfig;
+use crate::rules::RuleSet;
+use crate::tls;
+
+pub const USAGE: &str = "\
+用法 | Usage:
+  ghosttype                          启动托盘应用 | Start the tray app
+  ghosttype --headless               不创建任何窗口，只运行托盘、热键与识别 | Tray, hotkey and pipeline only, no windows
+  ghosttype transcribe <file>        用当前配置的 ASR 与 LLM 识别 WAV/MP3/OGG 文件并输出文字 | Transcribe an audio file
+      --no-llm                       跳过 LLM 校正 | Skip LLM correction
+  ghosttype bench <dir>              逐个识别目录中的 WAV 文件，统计各阶段耗时与 WER | Benchmark a directory of WAV files
+      --no-llm                       跳过 LLM 校正 | Skip LLM correction
+      --realtime                     按录音节奏送入音频 | Feed audio at recording pace
+  ghosttype --help";
+
+/// 可识别的音频文件扩展名
+pub const AUDIO_EXTENSIONS: [&str; 4] = ["wav", "mp3", "ogg", "oga"];
+
+/// 编码器支持的采样率；其他采样率先重采样到 16 kHz
+const SUPPORTED_RATES: [u32; 5] = [8000, 12000, 16000, 24000, 48000];
+
+const FALLBACK_RATE: u32 = 16000;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run { headless: bool },
+    Transcribe { path: PathBuf, use_llm: bool },
+    Bench { dir: PathBuf, use_llm: bool, realtime: bool },
+    Help,
+}
+
+/// 解析命令行（不含程序名）；参数有误时返回错误说明
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    // macOS 从 Finder 启动时会带上 -psn_ 参数
+    let mut args = args.into_iter().filter(|arg| !arg.starts_with("-psn_"));
+    let Some(first) = args.next() else {
+        return Ok(Command::Run { headless: false });
+    };
+    match first.as_str() {
+        "--headless" => match args.next() {
+            None => Ok(Command::Run { headless: true }),
+            Some(arg) => Err(format!("未知参数 | Unknown argument: {arg}")),
+        },
+        "-h" | "--help" | "help" => Ok(Command::Help),
+        "transcribe" => {
+            let mut path = None;
+            let mut use_llm = true;
+            for arg in args {
+                match arg.as_str() {
+                    "--no-llm" => use_llm = false,
+                    _ if arg.starts_with('-') => return Err(format!("未知参数 | Unknown argument: {arg}")),
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("只能识别一个文件 | Only one file can be transcribed: {arg}")),
+                }
+            }
+            let path = path.ok_or_else(|| "缺少音频文件路径 | Missing audio file path".to_string())?;
+            Ok(Command::Transcribe { path, use_llm })
+        }
+        "bench" => {
+            let mut dir = None;
+            let mut use_llm = true;
+            let mut realtime = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--no-llm" => use_llm = false,
+                    "--realtime" => realtime = true,
+                    _ if arg.starts_with('-') => return Err(format!("未知参数 | Unknown argument: {arg}")),
+                    _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("只能指定一个目录 | Only one directory can be given: {arg}")),
+                }
+            }
+            let dir = dir.ok_or_else(|| "缺少 WAV 目录 | Missing WAV directory".to_string())?;
+            Ok(Command::Bench { dir, use_llm, realtime })
+        }
+        _ => Err(format!("未知命令 | Unknown command: {first}")),
+    }
+}
+
+/// `ghosttype transcribe`：结果输出到 stdout，日志在 stderr；返回进程退出码
+pub fn run_transcribe(path: &Path, use_llm: bool) -> i32 {
+    let (config, _) = config::load_with_path();
+    privacy::install(&config.privacy);
+    proxy::install(config.proxy.clone());
+    tls::install(config.tls.clone());
+    match tauri::async_runtime::block_on(transcribe(&config, path, use_llm)) {
+        Ok(text) => {
+            println!("{text}");
+            0
+        }
+        Err(err) => {
+            eprintln!("识别失败 | Transcription failed: {err:#}");
+            1
+        }
+    }
+}
+
+pub async fn transcribe(config: &ClientConfig, path: &Path, use_llm: bool) -> anyhow::Result<String> {
+    let (sample_rate, samples) = load_audio(path)?;
+    let mut transcriber = Transcriber::new(config, use_llm)?;
+    let trace_id = format!("cli-{}", chrono::Local::now().format("%Y%m%d%H%M%S"));
+    let (text, _) = transcriber.run(trace_id, sample_rate, &samples).await?;
+    Ok(text)
+}
+
+/// 读取 WAV/MP3/OGG 文件并混为单声道；采样率不受编码器支持时重采样到 16 kHz
+pub fn load_audio(path: &Path) -> anyhow::Result<(u32, Vec<i16>)> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        bail!("不支持的音频格式 | Unsupported audio format: {}", path.display());
+    }
+    let bytes = std::fs::read(path).with_context(|| format!("读取 {}", path.display()))?;
+    let decoded = if extension == "wav" {
+        read_wav(&bytes)
+    } else {
+        decode_compressed(bytes, &extension)
+    };
+    let (sample_rate, samples) = decoded.with_context(|| format!("解析 {}", path.display()))?;
+    if SUPPORTED_RATES.contains(&sample_rate) {
+        Ok((sample_rate, samples))
+    } else {
+        Ok((FALLBACK_RATE, resample(&samples, sample_rate, FALLBACK_RATE)))
+    }
+}
+
+/// 一次识别各阶段的耗时
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageTimings {
+    /// 音频编码为 Opus 并发送
+    pub feed: Duration,
+    /// 发送 stop 到收到识别结果
+    pub asr: Duration,
+    /// LLM 校正；跳过时为空
+    pub llm: Option<Duration>,
+}
+
+/// 与听写相同的处理：ASR → 语言整理 → LLM 校正 → 个人词典与后处理规则 → 中英混排标点
+pub struct Transcriber {
+    engine: Box<dyn AsrEngine>,
+    llm: Option<Box<dyn LlmEngine>>,
+    language_config: LanguageConfig,
+    punctuation: PunctuationConfig,
+    dictionary: LearnedDictionary,
+    rules: RuleSet,
+    /// 按录音的节奏（每 20ms 一帧）送入音频，否则尽快送完
+    pub realtime: bool,
+}
+
+impl Transcriber {
+    pub fn new(config: &ClientConfig, use_llm: bool) -> anyhow::Result<Self> {
+        let dictionary = LearnedDictionary::open_default();
+        dictionary.configure(&config.dictionary);
+        Ok(Self {
+            engine: asr::create_engine(&config.asr)?,
+            llm: use_llm.then(|| llm::create_engine(&config.llm)).transpose()?,
+            language_config: config.language_detection,
+            punctuation: config.punctuation,
+            dictionary,
+            rules: RuleSet::compile(&config.rules)?,
+            realtime: false,
+        })
+    }
+
+    pub async fn run(
+        &mut self,
+        trace_id: String,
+        sample_rate: u32,
+        samples: &[i16],
+    ) -> anyhow::Result<(String, StageTimings)> {
+        let mut timings = StageTimings::default();
+        let context = AsrContext {
+            quality_pass: true,
+            ..AsrContext::default()
+        };
+        self.engine.start(trace_id, sample_rate, context).await?;
+        // 与录音相同的 20ms 帧
+        let frame_len = (sample_rate / 50) as usize;
+        let mut ticker = tokio::time::interval(FRAME_INTERVAL);
+        for frame in samples.chunks(frame_len) {
+            if self.realtime {
+                ticker.tick().await;
+            }
+            let started = Instant::now();
+            self.engine.feed_audio(frame).await?;
+            timings.feed += started.elapsed();
+        }
+        let started = Instant::now();
+        let mut text = self.engine.stop().await?.trim().to_string();
+        // 快慢两路识别时文件识别不急于出字，等质量引擎的结果
+        if let Some(quality) = self.engine.take_quality_result() {
+            match quality.wait().await {
+                Ok(quality) => text = quality.trim().to_string(),
+                Err(err) => warn!(
+                    target: "cli",
+                    error = %format!("{err:#}"),
+                    "质量 ASR 失败，使用快速结果 | Quality ASR failed, using the fast result"
+                ),
+            }
+        }
+        timings.asr = started.elapsed();
+
+        let language = if self.language_config.enabled {
+            self.engine
+                .take_language()
+                .as_deref()
+                .and_then(Language::from_code)
+                .or_else(|| language::detect(&text))
+        } else {
+            None
+        };
+        if let Some(language) = language {
+            text = self.language_config.polish(&text, language);
+        }
+
+        if let Some(llm) = self.llm.as_ref().filter(|_| !text.is_empty()) {
+            let hints = llm::CorrectionHints {
+                language,
+                glossary: self.dictionary.glossary(),
+                ..llm::CorrectionHints::default()
+            };
+            let started = Instant::now();
+            let result = llm.correct(&text, hints).await;
+            timings.llm = Some(started.elapsed());
+            match result {
+                Ok(correction) if correction.changed => {
+                    text = correction.corrected.trim().to_string();
+                    if let Some(language) = language {
+                        text = self.language_config.polish(&text, language);
+                    }
+                }
+                Ok(_) => {}
+                // 与听写一致：校正失败时输出识别原文
+                Err(err) => warn!(
+                    target: "cli",
+                    error = %format!("{err:#}"),
+                    "LLM 校正失败，输出识别原文 | LLM correction failed, printing the ASR text"
+                ),
+            }
+        }
+        let text = self.rules.apply(&self.dictionary.apply(&text));
+        Ok((self.punctuation.fix(&text), timings))
+    }
+}
+
+/// 读取 PCM（8/16/24/32 位整数或 32 位浮点）WAV，多声道混为单声道
+fn read_wav(bytes: &[u8]) -> anyhow::Result<(u32, Vec<i16>)> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        bail!("不是 WAV 文件 | Not a WAV file");
+    }
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let len = u32_at(offset + 4) as usize;
+        let body = offset + 8;
+        let end = body.saturating_add(len).min(bytes.len());
+        match id {
+            b"fmt " if end - body >= 16 => {
+                let mut tag = u16_at(body);
+                // WAVE_FORMAT_EXTENSIBLE：真正的格式在子格式 GUID 的前两个字节
+                if tag == 0xFFFE && end - body >= 26 {
+                    tag = u16_at(body + 24);
+                }
+                format = Some((tag, u16_at(body + 2), u32_at(body + 4), u16_at(body + 14)));
+            }
+            b"data" => data = Some(&bytes[body..end]),
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        offset = body.saturating_add(len + (len & 1));
+    }
+
+    let Some((tag, channels, sample_rate, bits)) = format else {
+        bail!("缺少 fmt 块 | Missing fmt chunk");
+    };
+    let data = data.context("缺少 data 块 | Missing data chunk")?;
+    if channels == 0 || sample_rate == 0 {
+        bail!("无效的声道数或采样率 | Invalid channel count or sample rate");
+    }
+    let width = usize::from(bits / 8);
+    let sample = |chunk: &[u8]| -> Option<f32> {
+        match (tag, bits) {
+            (1, 8) => Some((f32::from(chunk[0]) - 128.0) / 128.0),
+            (1, 16) => Some(f32::from(i16::from_le_bytes([chunk[0], chunk[1]])) / 32768.0),
+            (1, 24) => Some(i32::from_le_bytes([0, chunk[0], chunk[1], chunk[2]]) as f32 / 2_147_483_648.0),
+            (1, 32) => Some(i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f32 / 2_147_483_648.0),
+            (3, 32) => Some(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+            _ => None,
+        }
+    };
+    if width == 0 || sample(&[0; 4]).is_none() {
+        bail!("不支持的 WAV 格式 | Unsupported WAV format: tag {tag}, {bits} bits");
+    }
+
+    let frame_len = width * usize::from(channels);
+    let samples = data
+        .chunks_exact(frame_len)
+        .map(|frame| {
+            let sum: f32 = frame.chunks_exact(width).filter_map(sample).sum();
+            let mono = sum / f32::from(channels);
+            (mono.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16
+        })
+        .collect();
+    Ok((sample_rate, samples))
+}
+
+/// 用 symphonia 解码 MP3 / Ogg Vorbis，多声道混为单声道
+fn decode_compressed(bytes: Vec<u8>, extension: &str) -> anyhow::Result<(u32, Vec<i16>)> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+    let probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .context("无法识别的音频格式 | Unrecognized audio format")?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .context("没有音轨 | No audio track")?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("不支持的音频编码 | Unsupported audio codec")?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err).context("读取音频数据失败 | Failed to read audio data"),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 个别损坏的帧跳过，不影响其余部分
+            Err(Error::DecodeError(_)) => continue,
+            Err(err) => return Err(err).context("音频解码失败 | Audio decoding failed"),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        sample_rate.get_or_insert(spec.rate);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(buffer.samples().chunks_exact(channels).map(|frame| {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            (mono.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16
+        }));
+    }
+    let sample_rate = sample_rate.filter(|rate| *rate > 0).context("缺少采样率 | Missing sample rate")?;
+    Ok((sample_rate, samples))
+}
+
+/// 线性插值重采样；只用于离线识别文件，音质足够
+fn resample(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if samples.is_empty() || from == to {
+        return samples.to_vec();
+    }
+    let len = (samples.len() as u64 * u64::from(to) / u64::from(from)) as usize;
+    let step = f64::from(from) / f64::from(to);
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            let fraction = position - index as f64;
+            (f64::from(samples[index]) + (f64::from(next) - f64::from(samples[index])) * fraction).round() as i16
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(channels: u16, sample_rate: u32, pcm: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = pcm.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&channels.to_le_bytes());
+        out.extend_from_slice(&sample_rate.to_le_bytes());
+        out.extend_from_slice(&(sample_rate * u32::from(channels) * 2).to_le_bytes());
+        out.extend_from_slice(&(channels * 2).to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        // 未知块应被跳过
+        out.extend_from_slice(b"LIST");
+        out.extend_from_slice(&3u32.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&data);
+        out
+    }
+
+    #[test]
+    fn cli_parses_commands_and_reads_wav() {
+        let args = |list: &[&str]| parse(list.iter().map(|arg| arg.to_string()));
+        assert_eq!(args(&[]), Ok(Command::Run { headless: false }));
+        assert_eq!(args(&["-psn_0_12345", "--headless"]), Ok(Command::Run { headless: true }));
+        assert_eq!(
+            args(&["transcribe", "--no-llm", "a.wav"]),
+            Ok(Command::Transcribe {
+                path: PathBuf::from("a.wav"),
+                use_llm: false
+            })
+        );
+        assert!(args(&["transcribe"]).is_err());
+        assert!(args(&["transcribe", "a.wav", "b.wav"]).is_err());
+        assert!(args(&["--verbose"]).is_err());
+        assert_eq!(
+            args(&["bench", "fixtures", "--realtime"]),
+            Ok(Command::Bench {
+                dir: PathBuf::from("fixtures"),
+                use_llm: true,
+                realtime: true
+            })
+        );
+
+        // 立体声混为单声道
+        let (rate, samples) = read_wav(&wav(2, 16000, &[1000, 3000, -2000, -4000])).expect("wav");
+        assert_eq!(rate, 16000);
+        assert_eq!(samples, vec![1999, -2999]);
+        assert!(read_wav(b"RIFF\0\0\0\0WAVE").is_err());
+        assert!(read_wav(b"not a wav").is_err());
+        assert!(load_audio(Path::new("notes.flac")).is_err());
+
+        assert_eq!(resample(&[0, 100, 200, 300], 32000, 16000), vec![0, 200]);
+        assert_eq!(resample(&[0, 100], 8000, 16000), vec![0, 50, 100, 100]);
+    }
+}
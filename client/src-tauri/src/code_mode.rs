@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// ASR 自动加的标点；代码里的标点需要说出来
+const CJK_PUNCTUATION: &[char] = &['，', '。', '、', '？', '！', '；', '：'];
+
+/// 代码听写：在 IDE / 终端中按代码的习惯输出——不自动加标点，把说出的符号名转成符号，
+/// 可选转换标识符的命名风格；LLM 校正改用面向标识符的提示词。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CodeModeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 自动进入代码听写的应用，模式规则同 `per_app`；`per_app` 中的 `code_mode` 优先
+    #[serde(default = "default_apps")]
+    pub apps: Vec<String>,
+    /// 整句都是单词时转换成的命名风格；为空时保持原样（仍可用 "camel case …" 等前缀临时指定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier_case: Option<IdentifierCase>,
+    /// 额外的符号说法，键为说法（大小写不敏感），值为输出的符号（前后保留空格）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, String>,
+}
+
+impl Default for CodeModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            apps: default_apps(),
+            identifier_case: None,
+            symbols: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_apps() -> Vec<String> {
+    [
+        "code",
+        "visual studio code",
+        "cursor",
+        "zed",
+        "xcode",
+        "android studio",
+        "sublime text",
+        "intellij*",
+        "pycharm*",
+        "webstorm*",
+        "goland*",
+        "clion*",
+        "rider*",
+        "terminal",
+        "iterm*",
+        "warp",
+        "alacritty",
+        "kitty",
+        "wezterm*",
+        "windowsterminal",
+        "windows terminal",
+        "powershell",
+        "cmd",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierCase {
+    /// userName
+    Camel,
+    /// UserName
+    Pascal,
+    /// user_name
+    Snake,
+    /// user-name
+    Kebab,
+    /// USER_NAME
+    Constant,
+}
+
+impl IdentifierCase {
+    fn apply(self, words: &[String]) -> String {
+        let lower = words.iter().map(|word| word.to_lowercase());
+        match self {
+            IdentifierCase::Camel => lower
+                .enumerate()
+                .map(|(index, word)| if index == 0 { word } else { capitalize(&word) })
+                .collect(),
+            IdentifierCase::Pascal => lower.map(|word| capitalize(&word)).collect(),
+            IdentifierCase::Snake => lower.collect::<Vec<_>>().join("_"),
+            IdentifierCase::Kebab => lower.collect::<Vec<_>>().join("-"),
+            IdentifierCase::Constant => lower.map(|word| word.to_uppercase()).collect::<Vec<_>>().join("_"),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// 符号与前后单词之间是否留空格
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Spacing {
+    /// `a + b`
+    Space,
+    /// `user_name`、`src/main`、`f(x`
+    Join,
+    /// 贴在前一个词后：`f(x), y`
+    Left,
+    /// 贴在后一个词前：`--force`、`#include`
+    Right,
+}
+
+const SYMBOLS: &[(&str, &str, Spacing)] = &[
+    ("underscore", "_", Spacing::Join),
+    ("dot", ".", Spacing::Join),
+    ("double colon", "::", Spacing::Join),
+    ("slash", "/", Spacing::Join),
+    ("backslash", "\\", Spacing::Join),
+    ("open paren", "(", Spacing::Join),
+    ("open bracket", "[", Spacing::Join),
+    ("comma", ",", Spacing::Left),
+    ("semicolon", ";", Spacing::Left),
+    ("colon", ":", Spacing::Left),
+    ("question mark", "?", Spacing::Left),
+    ("close paren", ")", Spacing::Left),
+    ("close bracket", "]", Spacing::Left),
+    ("dash", "-", Spacing::Right),
+    ("bang", "!", Spacing::Right),
+    ("at sign", "@", Spacing::Right),
+    ("hash", "#", Spacing::Right),
+    ("dollar", "$", Spacing::Right),
+    ("tilde", "~", Spacing::Right),
+    ("open brace", "{", Spacing::Space),
+    ("close brace", "}", Spacing::Space),
+    ("arrow", "->", Spacing::Space),
+    ("fat arrow", "=>", Spacing::Space),
+    ("equals", "=", Spacing::Space),
+    ("double equals", "==", Spacing::Space),
+    ("triple equals", "===", Spacing::Space),
+    ("not equals", "!=", Spacing::Space),
+    ("plus equals", "+=", Spacing::Space),
+    ("minus equals", "-=", Spacing::Space),
+    ("plus", "+", Spacing::Space),
+    ("minus", "-", Spacing::Space),
+    ("star", "*", Spacing::Space),
+    ("percent", "%", Spacing::Space),
+    ("caret", "^", Spacing::Space),
+    ("ampersand", "&", Spacing::Space),
+    ("double ampersand", "&&", Spacing::Space),
+    ("pipe", "|", Spacing::Space),
+    ("double pipe", "||", Spacing::Space),
+    ("less than", "<", Spacing::Space),
+    ("greater than", ">", Spacing::Space),
+    ("quote", "\"", Spacing::Space),
+    ("single quote", "'", Spacing::Space),
+    ("backtick", "`", Spacing::Space),
+];
+
+/// 中文说法先换成英文说法再统一匹配；长的在前，"反斜杠"不会被拆成"反" + "斜杠"
+const CHINESE_SYMBOLS: &[(&str, &str)] = &[
+    ("反斜杠", "backslash"),
+    ("左括号", "open paren"),
+    ("右括号", "close paren"),
+    ("下划线", "underscore"),
+    ("箭头", "arrow"),
+    ("等号", "equals"),
+    ("等于", "equals"),
+    ("冒号", "colon"),
+    ("分号", "semicolon"),
+    ("逗号", "comma"),
+    ("点号", "dot"),
+    ("斜杠", "slash"),
+    ("井号", "hash"),
+    ("星号", "star"),
+    ("加号", "plus"),
+    ("减号", "minus"),
+];
+
+const CASE_PREFIXES: &[(&str, IdentifierCase)] = &[
+    ("camel case", IdentifierCase::Camel),
+    ("pascal case", IdentifierCase::Pascal),
+    ("snake case", IdentifierCase::Snake),
+    ("kebab case", IdentifierCase::Kebab),
+    ("constant case", IdentifierCase::Constant),
+];
+
+enum Item {
+    Word(String),
+    Symbol(String, Spacing),
+}
+
+/// 按配置整理代码听写的输出
+#[derive(Debug, Clone, Default)]
+pub struct CodeFormatter {
+    enabled: bool,
+    apps: Vec<String>,
+    identifier_case: Option<IdentifierCase>,
+    /// 用户说法（小写、单空格分隔）→ 符号
+    custom: Vec<(String, String)>,
+}
+
+impl CodeFormatter {
+    pub fn new(config: &CodeModeConfig) -> Self {
+        let custom = config
+            .symbols
+            .iter()
+            .map(|(phrase, symbol)| (normalize_phrase(phrase), symbol.trim().to_string()))
+            .filter(|(phrase, symbol)| !phrase.is_empty() && !symbol.is_empty())
+            .collect();
+        Self {
+            enabled: config.enabled,
+            apps: config.apps.clone(),
+            identifier_case: config.identifier_case,
+            custom,
+        }
+    }
+
+    /// 未在 `per_app` 中指定时，按应用名决定是否进入代码听写
+    pub fn auto_enabled_for(&self, app_name: &str) -> bool {
+        self.enabled && config::app_matches_any(&self.apps, app_name)
+    }
+
+    pub fn format(&self, text: &str) -> String {
+        let mut spoken = strip_asr_punctuation(text);
+        for (chinese, english) in CHINESE_SYMBOLS {
+            spoken = spoken.replace(chinese, &format!(" {english} "));
+        }
+        let tokens: Vec<&str> = spoken.split_whitespace().collect();
+
+        let mut items = Vec::new();
+        let mut cased: Option<(IdentifierCase, Vec<String>)> = None;
+        let mut explicit = false;
+        let mut index = 0;
+        while index < tokens.len() {
+            if let Some((case, used)) = self.match_case_prefix(&tokens[index..]) {
+                flush_cased(&mut items, cased.take());
+                cased = Some((case, Vec::new()));
+                explicit = true;
+                index += used;
+            } else if let Some((symbol, spacing, used)) = self.match_symbol(&tokens[index..]) {
+                flush_cased(&mut items, cased.take());
+                items.push(Item::Symbol(symbol, spacing));
+                explicit = true;
+                index += used;
+            } else {
+                match cased.as_mut() {
+                    Some((_, words)) => words.push(tokens[index].to_string()),
+                    None => items.push(Item::Word(tokens[index].to_string())),
+                }
+                index += 1;
+            }
+        }
+        flush_cased(&mut items, cased);
+
+        // 只是一串普通单词时按默认命名风格合成一个标识符
+        if let Some(case) = self.identifier_case.filter(|_| !explicit && items.len() > 1) {
+            let words: Vec<String> = items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Word(word) if word.chars().all(|c| c.is_ascii_alphanumeric()) => Some(word.clone()),
+                    _ => None,
+                })
+                .collect();
+            if words.len() == items.len() {
+                return case.apply(&words);
+            }
+        }
+        join(&items)
+    }
+
+    fn match_case_prefix(&self, tokens: &[&str]) -> Option<(IdentifierCase, usize)> {
+        CASE_PREFIXES.iter().find_map(|(phrase, case)| {
+            let used = phrase_matches(phrase, tokens)?;
+            Some((*case, used))
+        })
+    }
+
+    /// 最长匹配：用户说法优先于内置说法
+    fn match_symbol(&self, tokens: &[&str]) -> Option<(String, Spacing, usize)> {
+        let custom = self.custom.iter().filter_map(|(phrase, symbol)| {
+            phrase_matches(phrase, tokens).map(|used| (symbol.clone(), Spacing::Space, used))
+        });
+        let builtin = SYMBOLS.iter().filter_map(|(phrase, symbol, spacing)| {
+            phrase_matches(phrase, tokens).map(|used| (symbol.to_string(), *spacing, used))
+        });
+        // max_by_key 取最后一个最大值，把用户说法放在后面
+        builtin.chain(custom).max_by_key(|(_, _, used)| *used)
+    }
+}
+
+/// `tokens` 以 `phrase` 开头时返回占用的单词数
+fn phrase_matches(phrase: &str, tokens: &[&str]) -> Option<usize> {
+    let words: Vec<&str> = phrase.split(' ').collect();
+    if tokens.len() < words.len() {
+        return None;
+    }
+    words
+        .iter()
+        .zip(tokens)
+        .all(|(word, token)| token.eq_ignore_ascii_case(word))
+        .then_some(words.len())
+}
+
+fn normalize_phrase(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// 去掉 ASR 加的标点：中文标点全部去掉，英文句读只去掉词尾的（保留 `3.14`、`a.b`）
+fn strip_asr_punctuation(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if CJK_PUNCTUATION.contains(&c) {
+            out.push(' ');
+            continue;
+        }
+        let at_word_end = !matches!(chars.peek(), Some(next) if !next.is_whitespace());
+        if matches!(c, ',' | '.' | '?' | '!') && at_word_end {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn flush_cased(items: &mut Vec<Item>, cased: Option<(IdentifierCase, Vec<String>)>) {
+    if let Some((case, words)) = cased.filter(|(_, words)| !words.is_empty()) {
+        items.push(Item::Word(case.apply(&words)));
+    }
+}
+
+fn join(items: &[Item]) -> String {
+    let mut out = String::new();
+    let mut previous: Option<Spacing> = None;
+    for item in items {
+        let (text, spacing) = match item {
+            Item::Word(word) => (word.as_str(), None),
+            Item::Symbol(symbol, spacing) => (symbol.as_str(), Some(*spacing)),
+        };
+        let glued_to_previous = matches!(previous, Some(Spacing::Join | Spacing::Right))
+            || matches!(spacing, Some(Spacing::Join | Spacing::Left));
+        if !out.is_empty() && !glued_to_previous {
+            out.push(' ');
+        }
+        out.push_str(text);
+        previous = Some(spacing.unwrap_or(Spacing::Space));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_formatter_maps_spoken_symbols_and_cases() {
+        let formatter = CodeFormatter::new(&CodeModeConfig {
+            enabled: true,
+            symbols: BTreeMap::from([("walrus".to_string(), ":=".to_string())]),
+            ..CodeModeConfig::default()
+        });
+        assert_eq!(formatter.format("user underscore name equals 3.14."), "user_name = 3.14");
+        assert_eq!(
+            formatter.format("snake case get user open paren id comma true close paren"),
+            "get_user(id, true)"
+        );
+        assert_eq!(formatter.format("git push dash dash force"), "git push --force");
+        assert_eq!(formatter.format("fn main open paren close paren arrow result"), "fn main() -> result");
+        assert_eq!(formatter.format("x walrus 1"), "x := 1");
+        assert_eq!(formatter.format("用户下划线名字等于零。"), "用户_名字 = 零");
+        assert_eq!(formatter.format("Camel Case http request."), "httpRequest");
+        assert_eq!(formatter.format("not equals"), "!=");
+
+        let camel = CodeFormatter::new(&CodeModeConfig {
+            identifier_case: Some(IdentifierCase::Camel),
+            ..CodeModeConfig::default()
+        });
+        assert_eq!(camel.format("user name count"), "userNameCount");
+        assert_eq!(camel.format("count plus one"), "count + one");
+        assert_eq!(IdentifierCase::Constant.apply(&["max".to_string(), "size".to_string()]), "MAX_SIZE");
+
+        assert!(!camel.auto_enabled_for("Code"));
+        assert!(formatter.auto_enabled_for("PyCharm Community"));
+        assert!(!formatter.auto_enabled_for("Notes"));
+    }
+}
@@ -1,7 +1,37 @@
 use anyhow::Context as _;
+use crate::app_state;
 use crate::asr;
+use crate::audit;
+use crate::caption;
+use crate::code_mode;
+use crate::content_filter;
+use crate::control;
+use crate::dictionary;
+use crate::elevation;
+use crate::hooks;
+use crate::input;
+use crate::language;
 use crate::llm;
+use crate::meeting;
+use crate::metrics;
+use crate::numbers;
+use crate::plugin;
+use crate::privacy;
+use crate::proxy;
+use crate::punctuation;
+use crate::reliability;
+use crate::rules;
+use crate::segment;
+use crate::sink;
+use crate::snippet;
+use crate::telemetry;
+use crate::throttle;
+use crate::tls;
+use crate::update;
+use crate::voice_command;
+use crate::wake;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -11,18 +41,124 @@ pub struct ClientConfig {
     pub schema_version: u32,
     #[serde(default = "default_hotkey")]
     pub hotkey: String,
+    /// 改写选中文字的按住说话热键：按住说出指令（例如"改得正式一些"），松开后用 LLM 改写并替换选中的文字。
+    /// 为空时不启用，修改后重启客户端生效
+    #[serde(default)]
+    pub transform_hotkey: Option<String>,
+    /// 上一次会话还在识别时又按下热键：排队（默认）或抢占
+    #[serde(default)]
+    pub busy_policy: app_state::BusyPolicy,
+    /// 松开热键后超过这么久（毫秒）仍没有识别完成（服务器或 LLM 卡住）时放弃这次会话并恢复空闲；0 表示不限
+    #[serde(default = "default_processing_timeout_ms")]
+    pub processing_timeout_ms: u64,
     #[serde(default)]
     pub audio_device: Option<String>,
     #[serde(default)]
     pub asr: asr::AsrConfig,
+    /// 可在托盘中切换的 ASR 配置，键为显示名称（例如 "本地服务" / "云端"）
+    #[serde(default)]
+    pub asr_profiles: BTreeMap<String, asr::AsrConfig>,
+    /// 当前启用的 ASR 配置名；为空表示直接使用 `asr`
+    #[serde(default)]
+    pub active_asr_profile: Option<String>,
     #[serde(default)]
     pub llm: llm::LlmConfig,
+    /// ASR 提供置信度时，按置信度跳过或强制 LLM 校正
+    #[serde(default)]
+    pub correction_gate: llm::CorrectionGate,
+    /// 校正结果替换已输入文字的最短等待与最长时限
+    #[serde(default)]
+    pub correction_timing: llm::CorrectionTiming,
+    /// 使用电池或散热受限时改用的轻量 ASR/LLM 后端
+    #[serde(default)]
+    pub throttle: throttle::ThrottleConfig,
+    /// 按应用覆盖：键为应用名模式（大小写不敏感，支持 `*` 通配）
+    #[serde(default)]
+    pub per_app: BTreeMap<String, AppOverride>,
+    /// 禁止听写的应用（例如网银、虚拟机控制台），模式规则同 `per_app`
+    #[serde(default)]
+    pub blocked_apps: Vec<String>,
+    /// 输入前的后处理规则（用户词典、正则替换、大小写），按顺序应用
+    #[serde(default)]
+    pub rules: Vec<rules::TextRule>,
+    /// 敏感词过滤：输入前遮盖或删掉词表中的词
+    #[serde(default)]
+    pub content_filter: content_filter::ContentFilterConfig,
+    /// 识别结果的输出端，可同时启用多个
+    #[serde(default = "sink::default_sinks")]
+    pub sinks: Vec<sink::SinkConfig>,
+    /// 模拟键盘输入后回读校验，出错时改用剪贴板，并按应用累计可靠度自动选择注入方式
+    #[serde(default)]
+    pub injection_check: reliability::InjectionCheckConfig,
+    /// 代码听写：IDE / 终端中口述符号、转换标识符命名风格
+    #[serde(default)]
+    pub code_mode: code_mode::CodeModeConfig,
+    /// 多语言：检测每次识别结果的语言，调整 LLM 提示词、标点宽度与中英文间距
+    #[serde(default)]
+    pub language_detection: language::LanguageConfig,
+    /// 中英混排：按规则统一标点宽度与中英文之间的空格，LLM 校正后的文字同样处理
+    #[serde(default)]
+    pub punctuation: punctuation::PunctuationConfig,
+    /// 数字规范化：口述的数字、日期、时间、金额与单位改成书面写法
+    #[serde(default)]
+    pub numbers: numbers::NumberConfig,
+    /// 个人词典：学习用户对输出的修改
+    #[serde(default)]
+    pub dictionary: dictionary::DictionaryConfig,
+    /// 语音编辑命令（"scratch that"、"换行"等）
+    #[serde(default)]
+    pub voice_commands: voice_command::VoiceCommandConfig,
+    /// 语音片段：说"插入 <片段名>"输入预设的文字模板
+    #[serde(default)]
+    pub snippets: snippet::SnippetConfig,
+    /// WASM 后处理插件
+    #[serde(default)]
+    pub plugins: plugin::PluginConfig,
+    /// 长时间听写时按停顿/时长分段输出
+    #[serde(default)]
+    pub segments: segment::SegmentConfig,
+    /// 实时字幕：置顶字幕条显示识别中的文字
+    #[serde(default)]
+    pub captions: caption::CaptionConfig,
+    /// 会议转写：从托盘开启，持续录音并写入带时间戳的文件
+    #[serde(default)]
+    pub meeting: meeting::MeetingConfig,
+    /// 唤醒词免按键听写，修改后重启客户端生效
+    #[serde(default)]
+    pub wake: wake::WakeConfig,
+    #[serde(default)]
+    pub update: update::UpdateConfig,
+    #[serde(default)]
+    pub proxy: proxy::ProxyConfig,
+    #[serde(default)]
+    pub tls: tls::TlsConfig,
+    #[serde(default)]
+    pub metrics: metrics::MetricsConfig,
+    /// 隐私模式：日志与会话历史只记录文字的长度与哈希
+    #[serde(default)]
+    pub privacy: privacy::PrivacyConfig,
+    /// 审计文件：记录每次会话输入的文字、目标应用与时间
+    #[serde(default)]
+    pub audit: audit::AuditConfig,
+    /// Windows：如何与管理员权限运行的窗口交互
+    #[serde(default)]
+    pub elevation: elevation::ElevationConfig,
+    /// OpenTelemetry 链路导出（OTLP/HTTP）
+    #[serde(default)]
+    pub telemetry: telemetry::TelemetryConfig,
+    /// 本地控制接口：外部工具通过 HTTP 开始/结束听写，修改后重启客户端生效
+    #[serde(default)]
+    pub control: control::ControlConfig,
+    /// 脚本钩子：会话开始、得到结果、出错时执行 shell 命令
+    #[serde(default)]
+    pub hooks: hooks::HooksConfig,
+    /// 由服务器做 LLM 校正（服务器声明支持 `llm` 时），不支持时仍在本地校正
+    #[serde(default)]
+    pub use_cloud_api: bool,
 
     // === legacy fields (兼容旧版 config.json) ===
     #[serde(default, skip_serializing)]
     pub server_endpoints: Vec<String>,
-    #[serde(default, skip_serializing)]
-    pub use_cloud_api: bool,
 }
 
 impl Default for ClientConfig {
@@ -30,11 +166,47 @@ impl Default for ClientConfig {
         Self {
             schema_version: default_schema_version(),
             hotkey: default_hotkey(),
+            transform_hotkey: None,
+            busy_policy: app_state::BusyPolicy::default(),
+            processing_timeout_ms: default_processing_timeout_ms(),
             audio_device: None,
             asr: asr::AsrConfig::default(),
+            asr_profiles: BTreeMap::new(),
+            active_asr_profile: None,
             llm: llm::LlmConfig::default(),
-            server_endpoints: Vec::new(),
+            correction_gate: llm::CorrectionGate::default(),
+            correction_timing: llm::CorrectionTiming::default(),
+            throttle: throttle::ThrottleConfig::default(),
+            per_app: BTreeMap::new(),
+            blocked_apps: Vec::new(),
+            rules: Vec::new(),
+            content_filter: content_filter::ContentFilterConfig::default(),
+            sinks: sink::default_sinks(),
+            injection_check: reliability::InjectionCheckConfig::default(),
+            code_mode: code_mode::CodeModeConfig::default(),
+            language_detection: language::LanguageConfig::default(),
+            punctuation: punctuation::PunctuationConfig::default(),
+            numbers: numbers::NumberConfig::default(),
+            dictionary: dictionary::DictionaryConfig::default(),
+            voice_commands: voice_command::VoiceCommandConfig::default(),
+            snippets: snippet::SnippetConfig::default(),
+            plugins: plugin::PluginConfig::default(),
+            segments: segment::SegmentConfig::default(),
+            captions: caption::CaptionConfig::default(),
+            meeting: meeting::MeetingConfig::default(),
+            wake: wake::WakeConfig::default(),
+            update: update::UpdateConfig::default(),
+            proxy: proxy::ProxyConfig::default(),
+            tls: tls::TlsConfig::default(),
+            metrics: metrics::MetricsConfig::default(),
+            privacy: privacy::PrivacyConfig::default(),
+            audit: audit::AuditConfig::default(),
+            elevation: elevation::ElevationConfig::default(),
+            telemetry: telemetry::TelemetryConfig::default(),
+            control: control::ControlConfig::default(),
+            hooks: hooks::HooksConfig::default(),
             use_cloud_api: false,
+            server_endpoints: Vec::new(),
         }
     }
 }
@@ -43,6 +215,155 @@ fn default_schema_version() -> u32 {
     210
 }
 
+/// 单个应用的覆盖项，未填写的字段沿用全局设置。
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AppOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub injection: Option<input::InjectionStrategy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_punctuation: Option<bool>,
+    /// 强制开启或关闭代码听写，未填写时按 `code_mode.apps` 判断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_mode: Option<bool>,
+    /// 强制开启或关闭敏感词过滤，未填写时按 `content_filter` 的设置判断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_filter: Option<bool>,
+}
+
+/// 会话开始时根据目标应用解析出的最终设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionOverrides {
+    pub llm_enabled: bool,
+    pub language: Option<String>,
+    pub injection: input::InjectionStrategy,
+    pub auto_punctuation: bool,
+    /// 按应用覆盖中指定的代码听写开关；为空时由 `code_mode.apps` 决定
+    pub code_mode: Option<bool>,
+    /// 按应用覆盖中指定的敏感词过滤开关；为空时由 `content_filter` 的设置决定
+    pub content_filter: Option<bool>,
+}
+
+impl Default for SessionOverrides {
+    fn default() -> Self {
+        Self {
+            llm_enabled: true,
+            language: None,
+            injection: input::InjectionStrategy::default(),
+            auto_punctuation: true,
+            code_mode: None,
+            content_filter: None,
+        }
+    }
+}
+
+/// 合并所有匹配 `app_name` 的覆盖项：通配模式先应用，精确匹配最后应用；
+/// 同类模式中越长（越具体）的越晚应用。
+pub fn resolve_app_overrides(per_app: &BTreeMap<String, AppOverride>, app_name: &str) -> SessionOverrides {
+    let mut resolved = SessionOverrides::default();
+    let app_name = app_name.trim();
+    if app_name.is_empty() {
+        return resolved;
+    }
+
+    let mut matches: Vec<(&String, &AppOverride)> = per_app
+        .iter()
+        .filter(|(pattern, _)| app_pattern_matches(pattern, app_name))
+        .collect();
+    matches.sort_by_key(|(pattern, _)| (!pattern.contains('*'), pattern.len()));
+
+    for (_, entry) in matches {
+        if let Some(v) = entry.llm_enabled {
+            resolved.llm_enabled = v;
+        }
+        if let Some(v) = entry.language.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            resolved.language = Some(v.to_string());
+        }
+        if let Some(v) = entry.injection {
+            resolved.injection = v;
+        }
+        if let Some(v) = entry.auto_punctuation {
+            resolved.auto_punctuation = v;
+        }
+        if let Some(v) = entry.code_mode {
+            resolved.code_mode = Some(v);
+        }
+        if let Some(v) = entry.content_filter {
+            resolved.content_filter = Some(v);
+        }
+    }
+
+    resolved
+}
+
+/// 匹配 `app_name` 的按应用覆盖中是否指定了注入方式
+pub fn injection_overridden(per_app: &BTreeMap<String, AppOverride>, app_name: &str) -> bool {
+    let app_name = app_name.trim();
+    !app_name.is_empty()
+        && per_app
+            .iter()
+            .any(|(pattern, entry)| entry.injection.is_some() && app_pattern_matches(pattern, app_name))
+}
+
+/// `app_name` 是否匹配 `blocked_apps` 中的任一模式
+pub fn is_app_blocked(blocked_apps: &[String], app_name: &str) -> bool {
+    app_matches_any(blocked_apps, app_name)
+}
+
+/// `app_name` 是否匹配任一应用名模式（规则同 `per_app`）
+pub fn app_matches_any(patterns: &[String], app_name: &str) -> bool {
+    let app_name = app_name.trim();
+    !app_name.is_empty() && patterns.iter().any(|pattern| app_pattern_matches(pattern, app_name))
+}
+
+fn app_pattern_matches(pattern: &str, app_name: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let name = app_name.to_lowercase();
+    if pattern.is_empty() {
+        return false;
+    }
+    if !pattern.contains('*') {
+        return pattern == name;
+    }
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut rest = name.as_str();
+    for (idx, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if idx == 0 {
+            let Some(stripped) = rest.strip_prefix(part) else {
+                return false;
+            };
+            rest = stripped;
+        } else if idx == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            let Some(pos) = rest.find(part) else {
+                return false;
+            };
+            rest = &rest[pos + part.len()..];
+        }
+    }
+    true
+}
+
+/// 切换到指定 ASR 配置：同步更新 `asr` 与 `active_asr_profile`
+pub fn select_asr_profile(config: &mut ClientConfig, name: &str) -> anyhow::Result<()> {
+    let profile = config
+        .asr_profiles
+        .get(name)
+        .cloned()
+        .with_context(|| format!("ASR 配置不存在: {name}"))?;
+    config.asr = profile;
+    config.active_asr_profile = Some(name.to_string());
+    Ok(())
+}
+
 pub fn load_with_path() -> (ClientConfig, Option<PathBuf>) {
     for path in candidate_paths() {
         if let Ok(content) = std::fs::read_to_string(&path) {
@@ -69,6 +390,10 @@ pub fn save_to_path(config: &ClientConfig, path: Option<PathBuf>) -> anyhow::Res
     Ok(path)
 }
 
+fn default_processing_timeout_ms() -> u64 {
+    60_000
+}
+
 fn default_hotkey() -> String {
     if cfg!(target_os = "macos") {
         "f8".to_string()
@@ -119,13 +444,21 @@ fn default_save_path() -> PathBuf {
 }
 
 fn normalize_legacy_config(mut config: ClientConfig) -> ClientConfig {
-    // 旧版字段：server_endpoints → asr.websocket.endpoint
-    if let asr::AsrConfig::WebSocket { endpoint } = &config.asr {
+    // 旧版字段：server_endpoints → asr.websocket.endpoint（其余地址作为备用服务器）
+    if let asr::AsrConfig::WebSocket {
+        endpoint,
+        extra_endpoints,
+        ..
+    } = &mut config.asr
+    {
         let is_default = endpoint.trim().is_empty() || endpoint.trim() == asr::default_websocket_endpoint();
         if is_default && !config.server_endpoints.is_empty() {
-            let endpoint = config.server_endpoints[0].trim().to_string();
-            if !endpoint.is_empty() {
-                config.asr = asr::AsrConfig::WebSocket { endpoint };
+            let legacy = config.server_endpoints[0].trim().to_string();
+            if !legacy.is_empty() {
+                *endpoint = legacy;
+            }
+            if extra_endpoints.is_empty() {
+                *extra_endpoints = config.server_endpoints[1..].to_vec();
             }
         }
     }
@@ -139,40 +472,117 @@ mod tests {
     #[test]
     fn legacy_server_endpoints_overrides_default_asr_endpoint() {
         let config = ClientConfig {
-            schema_version: default_schema_version(),
             hotkey: "f8".to_string(),
-            audio_device: None,
-            asr: asr::AsrConfig::default(),
-            llm: llm::LlmConfig::default(),
             server_endpoints: vec!["ws://10.0.0.1:8000/ws".to_string()],
-            use_cloud_api: false,
+            ..ClientConfig::default()
         };
 
         let normalized = normalize_legacy_config(config);
         match normalized.asr {
-            asr::AsrConfig::WebSocket { endpoint } => assert_eq!(endpoint, "ws://10.0.0.1:8000/ws"),
+            asr::AsrConfig::WebSocket { endpoint, .. } => assert_eq!(endpoint, "ws://10.0.0.1:8000/ws"),
             other => panic!("unexpected asr config: {other:?}"),
         }
     }
 
+    #[test]
+    fn legacy_server_endpoints_become_backup_servers() {
+        let config = ClientConfig {
+            hotkey: "f8".to_string(),
+            server_endpoints: vec![
+                "ws://10.0.0.1:8000/ws".to_string(),
+                "ws://10.0.0.2:8000/ws".to_string(),
+                " ws://10.0.0.1:8000/ws ".to_string(),
+            ],
+            ..ClientConfig::default()
+        };
+
+        let normalized = normalize_legacy_config(config);
+        assert_eq!(
+            normalized.asr.websocket_endpoints(),
+            vec!["ws://10.0.0.1:8000/ws".to_string(), "ws://10.0.0.2:8000/ws".to_string()]
+        );
+    }
+
     #[test]
     fn legacy_does_not_override_custom_asr_endpoint() {
         let config = ClientConfig {
-            schema_version: default_schema_version(),
             hotkey: "f8".to_string(),
-            audio_device: None,
-            asr: asr::AsrConfig::WebSocket {
-                endpoint: "ws://192.168.1.8:8000/ws".to_string(),
-            },
-            llm: llm::LlmConfig::default(),
+            asr: asr::AsrConfig::websocket("ws://192.168.1.8:8000/ws".to_string()),
             server_endpoints: vec!["ws://10.0.0.1:8000/ws".to_string()],
-            use_cloud_api: false,
+            ..ClientConfig::default()
         };
 
         let normalized = normalize_legacy_config(config);
         match normalized.asr {
-            asr::AsrConfig::WebSocket { endpoint } => assert_eq!(endpoint, "ws://192.168.1.8:8000/ws"),
+            asr::AsrConfig::WebSocket { endpoint, .. } => assert_eq!(endpoint, "ws://192.168.1.8:8000/ws"),
             other => panic!("unexpected asr config: {other:?}"),
         }
     }
+
+    #[test]
+    fn per_app_overrides_merge_wildcard_then_exact() {
+        let raw = r#"
+        {
+          "per_app": {
+            "*code*": { "llm_enabled": false, "auto_punctuation": false },
+            "Visual Studio Code": { "injection": "clipboard", "language": "en" }
+          }
+        }
+        "#;
+        let config = serde_json::from_str::<ClientConfig>(raw).expect("deserialize");
+
+        let resolved = resolve_app_overrides(&config.per_app, "visual studio code");
+        assert!(!resolved.llm_enabled);
+        assert!(!resolved.auto_punctuation);
+        assert_eq!(resolved.language.as_deref(), Some("en"));
+        assert_eq!(resolved.injection, input::InjectionStrategy::Clipboard);
+
+        let other = resolve_app_overrides(&config.per_app, "Slack");
+        assert_eq!(other, SessionOverrides::default());
+        assert!(injection_overridden(&config.per_app, "Visual Studio Code"));
+        assert!(!injection_overridden(&config.per_app, "vscode"));
+    }
+
+    #[test]
+    fn app_pattern_matches_globs_case_insensitively() {
+        assert!(app_pattern_matches("Code*", "code - insiders"));
+        assert!(app_pattern_matches("*Term*", "iTerm2"));
+        assert!(app_pattern_matches("*.exe", "WINWORD.EXE"));
+        assert!(!app_pattern_matches("Code", "Code - Insiders"));
+        assert!(!app_pattern_matches("*word", "WordPad"));
+    }
+
+    #[test]
+    fn blocked_apps_match_patterns() {
+        let config = serde_json::from_str::<ClientConfig>(r#"{ "blocked_apps": ["VirtualBox*", "KeePassXC"] }"#)
+            .expect("deserialize");
+        assert!(is_app_blocked(&config.blocked_apps, "virtualbox vm"));
+        assert!(is_app_blocked(&config.blocked_apps, " KeePassXC "));
+        assert!(!is_app_blocked(&config.blocked_apps, "Safari"));
+        assert!(!is_app_blocked(&config.blocked_apps, ""));
+        assert!(!is_app_blocked(&ClientConfig::default().blocked_apps, "KeePassXC"));
+    }
+
+    #[test]
+    fn select_asr_profile_switches_active_backend() {
+        let raw = r#"
+        {
+          "asr_profiles": {
+            "local": { "type": "websocket", "endpoint": "ws://127.0.0.1:8000/ws" },
+            "lab": { "type": "websocket", "endpoint": "ws://10.0.0.2:8000/ws" }
+          }
+        }
+        "#;
+        let mut config = serde_json::from_str::<ClientConfig>(raw).expect("deserialize");
+
+        select_asr_profile(&mut config, "lab").expect("select");
+        assert_eq!(config.active_asr_profile.as_deref(), Some("lab"));
+        match &config.asr {
+            asr::AsrConfig::WebSocket { endpoint, .. } => assert_eq!(endpoint, "ws://10.0.0.2:8000/ws"),
+            other => panic!("unexpected asr config: {other:?}"),
+        }
+
+        assert!(select_asr_profile(&mut config, "missing").is_err());
+        assert_eq!(config.active_asr_profile.as_deref(), Some("lab"));
+    }
 }
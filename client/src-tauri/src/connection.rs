@@ -0,0 +1,273 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::async_runtime::JoinHandle;
+use tauri::Emitter;
+use tracing::{debug, info, warn};
+
+use crate::metrics::Metrics;
+use crate::network::{ConnectionManager, LinkState, LinkStatus, ServerCapabilities};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// ASR 服务器连接状态，推送给前端显示状态点与延迟（心跳与重连见 `network.rs`）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectionStatus {
+    /// 当前 ASR 为 WebSocket 时的服务器地址；其它后端为空
+    pub endpoint: Option<String>,
+    pub connected: bool,
+    pub last_ping_rtt_ms: Option<u64>,
+    pub last_ping_at: Option<String>,
+    /// 最近一次成功完成识别的会话时间
+    pub last_session_at: Option<String>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// 服务器声明的能力，设置界面据此提示可用功能
+    pub capabilities: Option<ServerCapabilities>,
+}
+
+impl ConnectionStatus {
+    fn apply_ping(&mut self, endpoint: &str, result: Result<u64, String>, now: &str) {
+        if self.endpoint.as_deref() != Some(endpoint) {
+            // 地址变了，旧地址的记录不再有参考意义
+            *self = ConnectionStatus {
+                endpoint: Some(endpoint.to_string()),
+                ..ConnectionStatus::default()
+            };
+        }
+        self.last_ping_at = Some(now.to_string());
+        match result {
+            Ok(rtt_ms) => {
+                self.connected = true;
+                self.last_ping_rtt_ms = Some(rtt_ms);
+                self.last_error = None;
+                self.consecutive_failures = 0;
+            }
+            Err(err) => {
+                self.connected = false;
+                self.last_error = Some(err);
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+
+    /// 同步长连接状态；延迟与最近 ping 时间来自心跳
+    fn apply_link(&mut self, link: &LinkStatus) {
+        if self.endpoint.as_deref() != Some(link.endpoint.as_str()) {
+            *self = ConnectionStatus {
+                endpoint: Some(link.endpoint.clone()),
+                ..ConnectionStatus::default()
+            };
+        }
+        self.connected = link.state == LinkState::Connected;
+        if link.rtt_ms.is_some() {
+            self.last_ping_rtt_ms = link.rtt_ms;
+            self.last_ping_at = link.last_pong_at.clone();
+        }
+        if let Some(err) = &link.last_error {
+            self.last_error = Some(err.clone());
+        } else if self.connected {
+            self.last_error = None;
+        }
+        self.consecutive_failures = link.reconnect_attempts;
+        // 断线期间保留上一次的能力，界面不必闪烁
+        if link.capabilities.is_some() {
+            self.capabilities = link.capabilities.clone();
+        }
+    }
+
+    fn apply_session(&mut self, result: Result<(), String>, now: &str) {
+        match result {
+            Ok(()) => {
+                self.connected = true;
+                self.last_session_at = Some(now.to_string());
+                self.last_error = None;
+                self.consecutive_failures = 0;
+            }
+            Err(err) => {
+                self.connected = false;
+                self.last_error = Some(err);
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+}
+
+/// 汇总长连接状态与实际会话结果，每次更新都发送 `connection-status` 事件
+pub struct ConnectionMonitor {
+    app: tauri::AppHandle,
+    status: Mutex<ConnectionStatus>,
+    metrics: Arc<Metrics>,
+    /// 当前 ASR 引擎的连接及其状态转发任务
+    attached: Mutex<Option<(Arc<ConnectionManager>, JoinHandle<()>)>>,
+}
+
+impl ConnectionMonitor {
+    pub fn new(app: tauri::AppHandle, metrics: Arc<Metrics>) -> Self {
+        Self {
+            app,
+            status: Mutex::new(ConnectionStatus::default()),
+            metrics,
+            attached: Mutex::new(None),
+        }
+    }
+
+    /// 跟随 ASR 引擎的连接状态；切换引擎后需重新调用，非 WebSocket 后端传 `None`
+    pub fn attach(self: &Arc<Self>, connection: Option<Arc<ConnectionManager>>) {
+        let mut attached = self.attached.lock().expect("connection attached lock");
+        if let Some((_, task)) = attached.take() {
+            task.abort();
+        }
+
+        let Some(connection) = connection else {
+            self.update(|status, _| *status = ConnectionStatus::default());
+            return;
+        };
+
+        let monitor = self.clone();
+        let mut rx = connection.watch();
+        let task = tauri::async_runtime::spawn(async move {
+            loop {
+                let link = rx.borrow_and_update().clone();
+                let was_connected = monitor.snapshot().connected;
+                let status = monitor.update(|status, _| status.apply_link(&link));
+                if was_connected != status.connected {
+                    monitor.metrics.record_link_change(status.connected);
+                    if status.connected {
+                        info!(
+                            target: "connection",
+                            endpoint = %link.endpoint,
+                            "服务器连接已恢复 | Server connection restored"
+                        );
+                    } else {
+                        warn!(
+                            target: "connection",
+                            endpoint = %link.endpoint,
+                            error = link.last_error.as_deref().unwrap_or(""),
+                            "服务器连接已断开 | Server connection lost"
+                        );
+                    }
+                }
+                if rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+        *attached = Some((connection, task));
+    }
+
+    pub fn snapshot(&self) -> ConnectionStatus {
+        self.status.lock().expect("connection status lock").clone()
+    }
+
+    pub fn record_session_ok(&self) {
+        self.update(|status, now| status.apply_session(Ok(()), now));
+    }
+
+    pub fn record_session_failed(&self, detail: &str) {
+        self.update(|status, now| status.apply_session(Err(detail.to_string()), now));
+    }
+
+    /// 通过当前连接立即 ping 一次，刷新延迟
+    pub async fn ping_now(&self) -> ConnectionStatus {
+        let connection = self
+            .attached
+            .lock()
+            .expect("connection attached lock")
+            .as_ref()
+            .map(|(connection, _)| connection.clone());
+        let Some(connection) = connection else {
+            return self.snapshot();
+        };
+
+        let result = connection.ping(PING_TIMEOUT).await.map_err(|err| format!("{err:#}"));
+        if let Err(err) = &result {
+            debug!(
+                target: "connection",
+                endpoint = connection.endpoint(),
+                error = %err,
+                "服务器 ping 失败 | Server ping failed"
+            );
+        }
+        self.update(|status, now| status.apply_ping(connection.endpoint(), result, now))
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut ConnectionStatus, &str)) -> ConnectionStatus {
+        let now = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        let snapshot = {
+            let mut status = self.status.lock().expect("connection status lock");
+            apply(&mut status, &now);
+            status.clone()
+        };
+        let _ = self.app.emit("connection-status", &snapshot);
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_tracks_pings_sessions_and_endpoint_changes() {
+        let mut status = ConnectionStatus::default();
+        status.apply_ping("ws://a/ws", Ok(12), "t1");
+        assert!(status.connected);
+        assert_eq!(status.last_ping_rtt_ms, Some(12));
+
+        status.apply_ping("ws://a/ws", Err("refused".to_string()), "t2");
+        status.apply_ping("ws://a/ws", Err("refused".to_string()), "t3");
+        assert!(!status.connected);
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(status.last_ping_rtt_ms, Some(12));
+
+        status.apply_session(Ok(()), "t4");
+        assert!(status.connected);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_session_at.as_deref(), Some("t4"));
+
+        status.apply_ping("ws://b/ws", Ok(30), "t5");
+        assert_eq!(status.endpoint.as_deref(), Some("ws://b/ws"));
+        assert_eq!(status.last_session_at, None);
+        assert_eq!(status.last_ping_rtt_ms, Some(30));
+    }
+
+    #[test]
+    fn status_follows_link_state() {
+        let mut link = LinkStatus {
+            endpoint: "ws://a/ws".to_string(),
+            state: LinkState::Disconnected,
+            rtt_ms: None,
+            last_pong_at: None,
+            last_error: Some("refused".to_string()),
+            reconnect_attempts: 3,
+            capabilities: None,
+        };
+        let mut status = ConnectionStatus::default();
+        status.apply_link(&link);
+        assert!(!status.connected);
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.last_error.as_deref(), Some("refused"));
+
+        link.state = LinkState::Connected;
+        link.last_error = None;
+        link.reconnect_attempts = 0;
+        link.rtt_ms = Some(8);
+        link.last_pong_at = Some("t1".to_string());
+        link.capabilities = Some(ServerCapabilities {
+            features: vec!["seq_frames".to_string()],
+            ..ServerCapabilities::default()
+        });
+        status.apply_link(&link);
+        assert!(status.connected);
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.last_ping_rtt_ms, Some(8));
+        assert_eq!(status.last_ping_at.as_deref(), Some("t1"));
+
+        link.state = LinkState::Disconnected;
+        link.capabilities = None;
+        status.apply_link(&link);
+        assert!(status.capabilities.as_ref().is_some_and(|caps| caps.supports("seq_frames")));
+    }
+}
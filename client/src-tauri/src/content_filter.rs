@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// 正则编译后的大小上限，词表很大时拒绝而不是占用过多内存
+const REGEX_SIZE_LIMIT: usize = 4 << 20;
+
+/// 敏感词过滤：输入前遮盖或删掉词表中的词，适合在共享屏幕、正式场合听写
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContentFilterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub action: FilterAction,
+    /// 要过滤的词，大小写不敏感；英文只匹配完整的单词
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<String>,
+    /// 词表文件，每行一个词，`#` 开头的行为注释
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub word_lists: Vec<PathBuf>,
+    /// 只在这些应用中过滤，模式规则同 `per_app`；为空时所有应用都过滤。`per_app` 中的 `content_filter` 优先
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// 每个字符换成 `*`
+    #[default]
+    Mask,
+    /// 整个删掉
+    Drop,
+}
+
+/// 编译后的过滤器；词表读取失败时整体拒绝
+#[derive(Debug, Default)]
+pub struct ContentFilter {
+    enabled: bool,
+    apps: Vec<String>,
+    action: FilterAction,
+    regex: Option<Regex>,
+}
+
+impl ContentFilter {
+    pub fn compile(config: &ContentFilterConfig) -> anyhow::Result<Self> {
+        let mut words: Vec<String> = config.words.iter().map(|word| word.trim().to_string()).collect();
+        for path in &config.word_lists {
+            let list = std::fs::read_to_string(path).with_context(|| format!("无法读取词表: {}", path.display()))?;
+            words.extend(
+                list.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        words.retain(|word| !word.is_empty());
+        // 长的词优先，"shithead" 不会只遮住 "shit"
+        words.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then_with(|| a.cmp(b)));
+        words.dedup();
+
+        let regex = if words.is_empty() {
+            None
+        } else {
+            let pattern = words
+                .iter()
+                .map(|word| regex::escape(word))
+                .collect::<Vec<_>>()
+                .join("|");
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .context("词表过大 | Word list too large")?,
+            )
+        };
+        Ok(Self {
+            enabled: config.enabled,
+            apps: config.apps.clone(),
+            action: config.action,
+            regex,
+        })
+    }
+
+    /// 未在按应用覆盖中指定时，`app_name` 是否过滤
+    pub fn enabled_for(&self, app_name: &str) -> bool {
+        self.enabled && self.regex.is_some() && (self.apps.is_empty() || config::app_matches_any(&self.apps, app_name))
+    }
+
+    /// 遮盖或删掉词表中的词，返回处理后的文字与命中次数
+    pub fn apply(&self, text: &str) -> (String, usize) {
+        let Some(regex) = &self.regex else {
+            return (text.to_string(), 0);
+        };
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut hits = 0;
+        for found in regex.find_iter(text) {
+            if !whole_word(text, found.start(), found.end()) {
+                continue;
+            }
+            hits += 1;
+            match self.action {
+                FilterAction::Mask => {
+                    out.push_str(&text[copied..found.start()]);
+                    out.extend(found.as_str().chars().map(|_| '*'));
+                }
+                FilterAction::Drop => {
+                    // 删掉英文单词时连同前面的一个空格，不留下两个连续的空格
+                    let before = &text[copied..found.start()];
+                    let next = text[found.end()..].chars().next();
+                    let joined = next.is_none_or(|c| c == ' ' || c.is_ascii_punctuation());
+                    out.push_str(if joined {
+                        before.strip_suffix(' ').unwrap_or(before)
+                    } else {
+                        before
+                    });
+                }
+            }
+            copied = found.end();
+        }
+        out.push_str(&text[copied..]);
+        (out, hits)
+    }
+}
+
+/// 英文词的两端不能紧挨着字母或数字（"class" 中的 "ass" 不算）；中文没有词边界，不检查
+fn whole_word(text: &str, start: usize, end: usize) -> bool {
+    let matched = &text[start..end];
+    let first = matched.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+    let last = matched.chars().next_back().is_some_and(|c| c.is_ascii_alphanumeric());
+    let before = text[..start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_ascii_alphanumeric());
+    let after = text[end..].chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+    !(first && before || last && after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_words_are_masked_or_dropped_as_whole_words() {
+        let dir = std::env::temp_dir().join(format!("ghosttype-filter-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let list = dir.join("words.txt");
+        std::fs::write(&list, "# 注释\nshithead\n\n他妈的\n").expect("write list");
+        let mut config = ContentFilterConfig {
+            enabled: true,
+            words: vec!["shit".to_string(), "Damn".to_string()],
+            word_lists: vec![list],
+            ..ContentFilterConfig::default()
+        };
+
+        let filter = ContentFilter::compile(&config).expect("compile");
+        assert!(filter.enabled_for("Slack"));
+        assert_eq!(
+            filter.apply("Damn, this shithead broke the class, shit."),
+            ("****, this ******** broke the class, ****.".to_string(), 3)
+        );
+        assert_eq!(filter.apply("他妈的又下雨了"), ("***又下雨了".to_string(), 1));
+
+        config.action = FilterAction::Drop;
+        config.apps = vec!["zoom*".to_string()];
+        let filter = ContentFilter::compile(&config).expect("compile");
+        assert!(!filter.enabled_for("Slack"));
+        assert!(filter.enabled_for("zoom.us"));
+        assert_eq!(
+            filter.apply("Oh damn, that is shit. damn"),
+            ("Oh, that is.".to_string(), 3)
+        );
+        assert_eq!(filter.apply("他妈的又下雨了"), ("又下雨了".to_string(), 1));
+
+        config.word_lists.push(dir.join("missing.txt"));
+        assert!(ContentFilter::compile(&config).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,206 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use crate::app_state::SessionPhase;
+use crate::error::AppError;
+use crate::metrics;
+
+/// 本地控制接口（只监听 127.0.0.1），供 Stream Deck、AutoHotkey、Raycast 等外部工具开始/结束听写；
+/// 修改后重启客户端生效
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControlConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// 设置后请求须带 `Authorization: Bearer <token>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+            token: None,
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    9465
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAction {
+    /// 开始听写，与按下热键相同
+    Start,
+    /// 结束录音并识别，与松开热键相同
+    Stop,
+    /// 录音中则结束，否则开始
+    Toggle,
+    /// 取消当前会话，不输入
+    Cancel,
+    Status,
+}
+
+/// 由主程序执行动作后回复；控制接口本身不持有会话状态
+pub struct ControlRequest {
+    pub action: ControlAction,
+    pub reply: oneshot::Sender<ControlReply>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlReply {
+    /// 动作是否生效（例如没有进行中的会话时取消不生效）
+    pub ok: bool,
+    /// 执行动作后的会话状态
+    pub phase: SessionPhase,
+    pub paused: bool,
+    pub meeting: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+/// 在 127.0.0.1 上提供 `GET /status` 与 `POST /start|/stop|/toggle|/cancel`；端口被占用时只记录警告
+pub fn spawn_server(config: &ControlConfig, requests: mpsc::Sender<ControlRequest>) {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, config.port));
+    let token = config.token.clone().filter(|token| !token.is_empty());
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = serve(addr, token, requests).await {
+            warn!(
+                target: "control",
+                addr = %addr,
+                error = %format!("{err:#}"),
+                "控制端口启动失败 | Control listener failed"
+            );
+        }
+    });
+}
+
+async fn serve(addr: SocketAddr, token: Option<String>, requests: mpsc::Sender<ControlRequest>) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr).await.with_context(|| format!("bind {addr}"))?;
+    info!(target: "control", addr = %addr, "控制端口已开启 | Control listener started");
+    loop {
+        let (stream, peer) = listener.accept().await.context("accept")?;
+        let token = token.clone();
+        let requests = requests.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = handle(stream, token.as_deref(), &requests).await {
+                debug!(
+                    target: "control",
+                    peer = %peer,
+                    error = %err,
+                    "控制请求处理失败 | Control request failed"
+                );
+            }
+        });
+    }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    token: Option<&str>,
+    requests: &mpsc::Sender<ControlRequest>,
+) -> anyhow::Result<()> {
+    let head = tokio::time::timeout(metrics::READ_TIMEOUT, metrics::read_head(&mut stream))
+        .await
+        .context("read timeout")??;
+    let (status, body) = match parse_request(&head, token) {
+        Ok(action) => {
+            let (reply, rx) = oneshot::channel();
+            requests
+                .send(ControlRequest { action, reply })
+                .await
+                .map_err(|_| anyhow::anyhow!("控制通道已关闭"))?;
+            let reply = rx.await.context("control reply")?;
+            info!(target: "control", action = ?action, ok = reply.ok, "外部控制请求 | External control request");
+            let status = if reply.error.is_some() { "409 Conflict" } else { "200 OK" };
+            (status, serde_json::to_string(&reply)?)
+        }
+        Err(status) => (status, format!("{{\"ok\":false,\"error\":\"{status}\"}}")),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 解析请求行与请求头；失败时返回 HTTP 状态
+fn parse_request(head: &str, token: Option<&str>) -> Result<ControlAction, &'static str> {
+    let mut lines = head.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or(target);
+    let headers: Vec<(String, &str)> = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+    let header = |name: &str| headers.iter().find(|(key, _)| key == name).map(|(_, value)| *value);
+
+    // 浏览器里的网页也能访问 127.0.0.1，带 Origin 的请求一律拒绝，避免网页触发录音
+    if header("origin").is_some() {
+        return Err("403 Forbidden");
+    }
+    if let Some(token) = token {
+        let authorized = header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|value| value.trim() == token);
+        if !authorized {
+            return Err("401 Unauthorized");
+        }
+    }
+    let action = match path {
+        "/status" => ControlAction::Status,
+        "/start" => ControlAction::Start,
+        "/stop" => ControlAction::Stop,
+        "/toggle" => ControlAction::Toggle,
+        "/cancel" => ControlAction::Cancel,
+        _ => return Err("404 Not Found"),
+    };
+    // 查询状态用 GET，改变状态的动作只接受 POST
+    match (method, action) {
+        ("GET", ControlAction::Status) | ("POST", _) => Ok(action),
+        _ => Err("405 Method Not Allowed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_requests_need_post_and_token() {
+        assert_eq!(parse_request("GET /status HTTP/1.1\r\n\r\n", None), Ok(ControlAction::Status));
+        assert_eq!(parse_request("POST /toggle?x=1 HTTP/1.1\r\n\r\n", None), Ok(ControlAction::Toggle));
+        assert_eq!(parse_request("GET /start HTTP/1.1\r\n\r\n", None), Err("405 Method Not Allowed"));
+        assert_eq!(parse_request("POST /quit HTTP/1.1\r\n\r\n", None), Err("404 Not Found"));
+        assert_eq!(
+            parse_request("POST /start HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n", None),
+            Err("403 Forbidden")
+        );
+
+        let token = Some("s3cret");
+        assert_eq!(parse_request("POST /stop HTTP/1.1\r\n\r\n", token), Err("401 Unauthorized"));
+        assert_eq!(
+            parse_request("POST /stop HTTP/1.1\r\nauthorization: Bearer wrong\r\n\r\n", token),
+            Err("401 Unauthorized")
+        );
+        assert_eq!(
+            parse_request("POST /stop HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n", token),
+            Ok(ControlAction::Stop)
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::backtrace::Backtrace;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::logging;
+use crate::platform;
+
+/// 崩溃报告附带的最近日志行数
+const LOG_LINES: usize = 200;
+
+/// 最多保留的崩溃报告数，超出后删除最旧的
+const MAX_REPORTS: usize = 20;
+
+/// 记录已经提示过的最新报告的文件名
+const NOTIFIED_MARKER: &str = ".notified";
+
+/// 托盘应用崩溃时没有任何界面：panic 时把消息、调用栈、版本与最近的日志写入程序目录下的 `crashes`，
+/// 下次启动时通知用户。之前的 hook 照常执行（输出到 stderr）
+pub fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "<non-string panic payload>".to_string());
+        let location = info.location().map(ToString::to_string).unwrap_or_default();
+        let thread = std::thread::current();
+        let now = Local::now();
+        let report = render_report(
+            now,
+            &message,
+            &location,
+            thread.name().unwrap_or("<unnamed>"),
+            &Backtrace::force_capture(),
+            &logging::try_recent_lines(LOG_LINES),
+        );
+        match write_report(&crash_dir(), now, &report) {
+            Ok(path) => eprintln!("[crash] 崩溃报告已保存: {}", path.display()),
+            Err(err) => eprintln!("[crash] 崩溃报告写入失败: {err}"),
+        }
+        previous(info);
+    }));
+}
+
+/// 上次提示之后新产生的崩溃报告（从旧到新），并记为已提示
+pub fn take_unnotified() -> Vec<PathBuf> {
+    take_unnotified_in(&crash_dir())
+}
+
+pub fn has_reports() -> bool {
+    !list_reports(&crash_dir()).is_empty()
+}
+
+/// 在文件管理器中打开崩溃报告目录
+pub fn open_folder() -> Result<(), String> {
+    let dir = crash_dir();
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    platform::open_path(&dir)
+}
+
+fn crash_dir() -> PathBuf {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("crashes")
+}
+
+fn render_report(
+    now: DateTime<Local>,
+    message: &str,
+    location: &str,
+    thread: &str,
+    backtrace: &dyn Display,
+    log_lines: &[String],
+) -> String {
+    let mut report = format!(
+        "GhostType {} crash report\n\
+         time: {}\n\
+         platform: {} {}\n\
+         thread: {thread}\n\
+         location: {location}\n\n\
+         panic:\n{message}\n\n\
+         backtrace:\n{backtrace}\n\n\
+         recent log ({} lines):\n",
+        env!("CARGO_PKG_VERSION"),
+        now.to_rfc3339(),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        log_lines.len()
+    );
+    for line in log_lines {
+        report.push_str(line);
+        report.push('\n');
+    }
+    report
+}
+
+/// 写入 `crash_<时间>.txt`，并删除超出数量的旧报告
+fn write_report(dir: &Path, now: DateTime<Local>, report: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash_{}.txt", now.format("%Y%m%d_%H%M%S%.3f")));
+    std::fs::write(&path, report)?;
+
+    let reports = list_reports(dir);
+    for old in reports.iter().take(reports.len().saturating_sub(MAX_REPORTS)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// 目录中的崩溃报告，文件名中的时间可按字典序比较，最旧的在前
+fn list_reports(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash_") && name.ends_with(".txt"))
+        })
+        .collect();
+    reports.sort();
+    reports
+}
+
+fn take_unnotified_in(dir: &Path) -> Vec<PathBuf> {
+    let marker = dir.join(NOTIFIED_MARKER);
+    let notified = std::fs::read_to_string(&marker).unwrap_or_default();
+    let notified = notified.trim();
+    let reports: Vec<PathBuf> = list_reports(dir)
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name > notified)
+        })
+        .collect();
+    if let Some(name) = reports.last().and_then(|path| path.file_name()) {
+        let _ = std::fs::write(&marker, name.to_string_lossy().as_bytes());
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_are_written_and_notified_once() {
+        let dir = std::env::temp_dir().join(format!("ghosttype-crashes-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let now = Local::now();
+        let lines = vec!["INFO app: starting".to_string()];
+        let report = render_report(now, "boom", "src/main.rs:1:1", "main", &"<backtrace>", &lines);
+        assert!(report.contains("panic:\nboom\n"));
+        assert!(report.contains("location: src/main.rs:1:1"));
+        assert!(report.ends_with("recent log (1 lines):\nINFO app: starting\n"));
+
+        let first = write_report(&dir, now, &report).expect("write report");
+        assert_eq!(take_unnotified_in(&dir), vec![first]);
+        assert!(take_unnotified_in(&dir).is_empty());
+
+        let second = write_report(&dir, now + chrono::Duration::seconds(1), &report).expect("write report");
+        assert_eq!(take_unnotified_in(&dir), vec![second]);
+
+        for offset in 2..(MAX_REPORTS as i64 + 5) {
+            write_report(&dir, now + chrono::Duration::seconds(offset), &report).expect("write report");
+        }
+        assert_eq!(list_reports(&dir).len(), MAX_REPORTS);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
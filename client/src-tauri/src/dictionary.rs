@@ -0,0 +1,353 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::rules::{RuleKind, RuleSet, TextRule};
+
+/// 一次学到的说法最长的字数；改动超过这个长度多半是改写而不是纠正识别错误
+const MAX_TERM_CHARS: usize = 32;
+
+/// 个人词典：用 "记住这个" 等语音命令学习用户对输出的修改，
+/// 之后的输出先按学到的说法替换，LLM 校正时也作为术语表
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DictionaryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 交给 LLM 的术语数上限，按学习次数取最常用的；0 表示不加术语表
+    #[serde(default = "default_glossary_size")]
+    pub glossary_size: usize,
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            glossary_size: default_glossary_size(),
+        }
+    }
+}
+
+fn default_glossary_size() -> usize {
+    30
+}
+
+/// 学到的一条说法：`from` 是 GhostType 输出的写法，`to` 是用户改成的写法
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LearnedTerm {
+    pub from: String,
+    pub to: String,
+    /// 学习（被纠正）的次数
+    pub count: u32,
+    pub learned_at: String,
+}
+
+struct Learned {
+    config: DictionaryConfig,
+    terms: Vec<LearnedTerm>,
+    rules: Arc<RuleSet>,
+}
+
+/// 学到的说法，保存在本地 JSON 文件中
+pub struct LearnedDictionary {
+    path: Option<PathBuf>,
+    inner: Mutex<Learned>,
+}
+
+impl LearnedDictionary {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let terms = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).context("parse dictionary")?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).context("read dictionary"),
+        };
+        Ok(Self::with_terms(Some(path.to_path_buf()), terms))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_terms(None, Vec::new())
+    }
+
+    /// 打开默认位置的词典；失败时使用空的内存词典，保证应用照常运行
+    pub fn open_default() -> Self {
+        let path = resolve_dictionary_path();
+        match Self::open(&path) {
+            Ok(dictionary) => dictionary,
+            Err(err) => {
+                warn!(
+                    target: "dictionary",
+                    path = %path.display(),
+                    error = %format!("{err:#}"),
+                    "个人词典读取失败，使用空词典 | Dictionary load failed, starting empty"
+                );
+                Self::in_memory()
+            }
+        }
+    }
+
+    fn with_terms(path: Option<PathBuf>, terms: Vec<LearnedTerm>) -> Self {
+        let rules = Arc::new(compile_terms(&terms));
+        Self {
+            path,
+            inner: Mutex::new(Learned {
+                config: DictionaryConfig::default(),
+                terms,
+                rules,
+            }),
+        }
+    }
+
+    pub fn configure(&self, config: &DictionaryConfig) {
+        self.inner.lock().expect("dictionary lock").config = config.clone();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.lock().expect("dictionary lock").config.enabled
+    }
+
+    pub fn terms(&self) -> Vec<LearnedTerm> {
+        self.inner.lock().expect("dictionary lock").terms.clone()
+    }
+
+    /// 按学到的说法替换；未启用时原样返回
+    pub fn apply(&self, text: &str) -> String {
+        let rules = {
+            let inner = self.inner.lock().expect("dictionary lock");
+            if !inner.config.enabled {
+                return text.to_string();
+            }
+            inner.rules.clone()
+        };
+        rules.apply(text)
+    }
+
+    /// 交给 LLM 的术语表：学习次数最多的写法
+    pub fn glossary(&self) -> Vec<String> {
+        let inner = self.inner.lock().expect("dictionary lock");
+        if !inner.config.enabled {
+            return Vec::new();
+        }
+        let mut terms: Vec<&LearnedTerm> = inner.terms.iter().collect();
+        terms.sort_by(|a, b| b.count.cmp(&a.count));
+        let mut glossary: Vec<String> = Vec::new();
+        for term in terms {
+            if glossary.len() >= inner.config.glossary_size {
+                break;
+            }
+            if !glossary.contains(&term.to) {
+                glossary.push(term.to.clone());
+            }
+        }
+        glossary
+    }
+
+    /// 比较输出的文字与用户修改后的文字，记下改动的说法；同一说法再次学习时更新写法并累加次数
+    pub fn learn(&self, before: &str, after: &str) -> anyhow::Result<LearnedTerm> {
+        let Some((from, to)) = correction_pair(before, after) else {
+            anyhow::bail!("没有找到可以学习的修改（一次只改一处，改动不超过 {MAX_TERM_CHARS} 个字）");
+        };
+        let mut inner = self.inner.lock().expect("dictionary lock");
+        let learned_at = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        let index = match inner.terms.iter().position(|term| term.from.eq_ignore_ascii_case(&from)) {
+            Some(index) => {
+                let term = &mut inner.terms[index];
+                term.to = to;
+                term.count += 1;
+                term.learned_at = learned_at;
+                index
+            }
+            None => {
+                inner.terms.push(LearnedTerm {
+                    from,
+                    to,
+                    count: 1,
+                    learned_at,
+                });
+                inner.terms.len() - 1
+            }
+        };
+        let term = inner.terms[index].clone();
+        self.commit(&mut inner);
+        Ok(term)
+    }
+
+    /// 删除学到的说法，返回删除的条数
+    pub fn forget(&self, from: &[String]) -> usize {
+        let mut inner = self.inner.lock().expect("dictionary lock");
+        let before = inner.terms.len();
+        inner.terms.retain(|term| !from.contains(&term.from));
+        let removed = before - inner.terms.len();
+        if removed > 0 {
+            self.commit(&mut inner);
+        }
+        removed
+    }
+
+    /// 重新编译替换规则并写回文件；写入失败只记录日志，内存中的词典照常生效
+    fn commit(&self, inner: &mut Learned) {
+        inner.rules = Arc::new(compile_terms(&inner.terms));
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(err) = save_terms(path, &inner.terms) {
+            warn!(
+                target: "dictionary",
+                path = %path.display(),
+                error = %format!("{err:#}"),
+                "个人词典保存失败 | Dictionary save failed"
+            );
+        }
+    }
+}
+
+fn save_terms(path: &Path, terms: &[LearnedTerm]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).context("create dictionary dir")?;
+        }
+    }
+    let content = serde_json::to_string_pretty(terms).context("serialize dictionary")?;
+    std::fs::write(path, content).context("write dictionary")
+}
+
+/// 学到的说法按原样替换（忽略大小写）；首尾是英文字母或数字的说法只匹配完整的单词
+fn compile_terms(terms: &[LearnedTerm]) -> RuleSet {
+    let rules: Vec<TextRule> = terms
+        .iter()
+        .map(|term| {
+            let edges = [term.from.chars().next(), term.from.chars().next_back()];
+            TextRule {
+                enabled: true,
+                note: String::new(),
+                kind: RuleKind::Exact {
+                    from: term.from.clone(),
+                    to: term.to.clone(),
+                    ignore_case: true,
+                    whole_word: edges.iter().all(|c| c.is_some_and(|c| c.is_ascii_alphanumeric())),
+                },
+            }
+        })
+        .collect();
+    RuleSet::compile(&rules).unwrap_or_default()
+}
+
+/// 切分成比较单位：连续的英文字母、数字为一个词，其余每个字符单独一个
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        if c.is_ascii_alphanumeric() {
+            start.get_or_insert(index);
+            continue;
+        }
+        if let Some(word_start) = start.take() {
+            tokens.push(&text[word_start..index]);
+        }
+        tokens.push(&text[index..index + c.len_utf8()]);
+    }
+    if let Some(word_start) = start {
+        tokens.push(&text[word_start..]);
+    }
+    tokens
+}
+
+/// 去掉前后相同的部分，剩下的一处改动就是学到的说法。
+/// 只改了一个汉字时带上前一个字（没有时带后一个），避免单字替换误伤其他词
+fn correction_pair(before: &str, after: &str) -> Option<(String, String)> {
+    let before_tokens = tokenize(before.trim());
+    let after_tokens = tokenize(after.trim());
+    let mut prefix = before_tokens
+        .iter()
+        .zip(&after_tokens)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let max_suffix = before_tokens.len().min(after_tokens.len()) - prefix;
+    let mut suffix = before_tokens
+        .iter()
+        .rev()
+        .zip(after_tokens.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let is_single_cjk = |tokens: &[&str]| tokens.len() == 1 && !tokens[0].is_ascii();
+    let changed = |prefix: usize, suffix: usize| {
+        (
+            &before_tokens[prefix..before_tokens.len() - suffix],
+            &after_tokens[prefix..after_tokens.len() - suffix],
+        )
+    };
+    let (from, to) = changed(prefix, suffix);
+    if is_single_cjk(from) || is_single_cjk(to) {
+        if prefix > 0 && !before_tokens[prefix - 1].is_ascii() {
+            prefix -= 1;
+        } else if suffix > 0 && !before_tokens[before_tokens.len() - suffix].is_ascii() {
+            suffix -= 1;
+        }
+    }
+
+    let (from, to) = changed(prefix, suffix);
+    let from = from.concat().trim().to_string();
+    let to = to.concat().trim().to_string();
+    let valid = |text: &str| !text.is_empty() && text.chars().count() <= MAX_TERM_CHARS;
+    (valid(&from) && valid(&to) && from != to).then_some((from, to))
+}
+
+fn resolve_dictionary_path() -> PathBuf {
+    if let Ok(explicit) = std::env::var("GHOSTTYPE_DICTIONARY") {
+        if !explicit.trim().is_empty() {
+            return PathBuf::from(explicit.trim());
+        }
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("data").join("dictionary.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dictionary_learns_single_edit_and_applies_it() {
+        assert_eq!(
+            correction_pair("我们在 get hub 上提交", "我们在 GitHub 上提交"),
+            Some(("get hub".to_string(), "GitHub".to_string()))
+        );
+        // 单个汉字的改动带上前一个字
+        assert_eq!(
+            correction_pair("今天天汽很好。", "今天天气很好。"),
+            Some(("天汽".to_string(), "天气".to_string()))
+        );
+        assert_eq!(correction_pair("汽车", "气车"), Some(("汽车".to_string(), "气车".to_string())));
+        assert_eq!(correction_pair("same text", "same text"), None);
+        assert_eq!(correction_pair("hello", "hello world"), None);
+
+        let dir = std::env::temp_dir().join(format!("ghosttype-dictionary-{}", std::process::id()));
+        let path = dir.join("dictionary.json");
+        let dictionary = LearnedDictionary::open(&path).expect("open");
+        dictionary.configure(&DictionaryConfig {
+            enabled: true,
+            ..DictionaryConfig::default()
+        });
+        dictionary.learn("push to get hub", "push to GitHub").expect("learn");
+        dictionary.learn("open get hub", "open GitHub").expect("learn again");
+        assert!(dictionary.learn("abc", "abc").is_err());
+        assert_eq!(dictionary.apply("Get hub actions, not forget hubs"), "GitHub actions, not forget hubs");
+        assert_eq!(dictionary.glossary(), vec!["GitHub".to_string()]);
+
+        let reopened = LearnedDictionary::open(&path).expect("reopen");
+        assert_eq!(reopened.terms()[0].count, 2);
+        assert_eq!(reopened.apply("get hub"), "get hub", "未启用时不替换");
+        assert_eq!(reopened.forget(&["get hub".to_string()]), 1);
+        assert!(LearnedDictionary::open(&path).expect("reopen").terms().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use serde::Serialize;
+use tracing::{debug, info};
+
+/// 服务端通过 mDNS 广播的服务类型
+const SERVICE_TYPE: &str = "_ghosttype._tcp.local.";
+
+/// TXT 记录未声明路径时使用的 WebSocket 路径
+const DEFAULT_WS_PATH: &str = "/ws";
+
+/// 局域网内发现的服务器
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiscoveredServer {
+    /// 服务实例名（默认是服务端的主机名）
+    pub name: String,
+    pub endpoint: String,
+}
+
+/// 在局域网中浏览 `_ghosttype._tcp` 服务，`timeout` 内收集到的结果按名称排序返回
+pub async fn discover(timeout: Duration) -> anyhow::Result<Vec<DiscoveredServer>> {
+    // mdns-sd 的接收端是同步通道，放到阻塞线程里等待
+    tokio::task::spawn_blocking(move || browse(timeout))
+        .await
+        .context("mdns browse task")?
+}
+
+fn browse(timeout: Duration) -> anyhow::Result<Vec<DiscoveredServer>> {
+    let daemon = ServiceDaemon::new().context("start mdns daemon")?;
+    let receiver = daemon.browse(SERVICE_TYPE).context("mdns browse")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let Ok(event) = receiver.recv_timeout(remaining) else {
+            break;
+        };
+        let ServiceEvent::ServiceResolved(service) = event else {
+            continue;
+        };
+        let path = service.get_property_val_str("path").unwrap_or(DEFAULT_WS_PATH);
+        let Some(endpoint) = endpoint_for(service.get_addresses(), service.get_port(), path) else {
+            continue;
+        };
+        debug!(
+            target: "discovery",
+            name = service.get_fullname(),
+            endpoint = endpoint.as_str(),
+            "发现服务器 | Server discovered"
+        );
+        if servers.iter().any(|s| s.endpoint == endpoint) {
+            continue;
+        }
+        servers.push(DiscoveredServer {
+            name: instance_name(service.get_fullname()),
+            endpoint,
+        });
+    }
+
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.shutdown();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    info!(
+        target: "discovery",
+        count = servers.len(),
+        "局域网服务器搜索完成 | LAN server discovery finished"
+    );
+    Ok(servers)
+}
+
+/// 由解析到的地址拼出 WebSocket 地址：优先 IPv4，IPv6 仅在没有 IPv4 时使用
+fn endpoint_for(addresses: &HashSet<IpAddr>, port: u16, path: &str) -> Option<String> {
+    let address = addresses
+        .iter()
+        .filter(|ip| !ip.is_loopback())
+        .min_by_key(|ip| (ip.is_ipv6(), **ip))?;
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    };
+    Some(match address {
+        IpAddr::V4(ip) => format!("ws://{ip}:{port}{path}"),
+        IpAddr::V6(ip) => format!("ws://[{ip}]:{port}{path}"),
+    })
+}
+
+/// `GhostType on desk._ghosttype._tcp.local.` → `GhostType on desk`
+fn instance_name(fullname: &str) -> String {
+    fullname
+        .strip_suffix(SERVICE_TYPE)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(fullname)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_prefers_ipv4_and_normalizes_path() {
+        let addresses: HashSet<IpAddr> = ["fe80::1", "192.168.1.20", "127.0.0.1"]
+            .iter()
+            .map(|ip| ip.parse().expect("ip"))
+            .collect();
+        assert_eq!(
+            endpoint_for(&addresses, 8000, "ws").as_deref(),
+            Some("ws://192.168.1.20:8000/ws")
+        );
+
+        let v6: HashSet<IpAddr> = ["fd00::2".parse().expect("ip")].into_iter().collect();
+        assert_eq!(endpoint_for(&v6, 9000, "/ws").as_deref(), Some("ws://[fd00::2]:9000/ws"));
+        assert_eq!(endpoint_for(&HashSet::new(), 8000, "/ws"), None);
+
+        assert_eq!(instance_name("desk._ghosttype._tcp.local."), "desk");
+    }
+}
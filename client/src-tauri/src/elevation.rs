@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::notify::Notifier;
+use crate::platform;
+
+/// 检查前台窗口的间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Windows 的 UIPI 不允许普通权限的程序向管理员权限的窗口发送按键，也收不到其中的全局热键
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationStrategy {
+    /// 保持普通权限，前台是管理员窗口时提示
+    #[default]
+    None,
+    /// 启动时通过 UAC 以管理员权限重新启动
+    RunElevated,
+    /// 使用带 uiAccess 清单的版本（`ui-access` feature 构建，签名后安装到 Program Files）
+    UiAccess,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ElevationConfig {
+    #[serde(default)]
+    pub strategy: ElevationStrategy,
+    /// 前台切换到无法操作的管理员窗口时发送系统通知
+    #[serde(default = "default_warn")]
+    pub warn: bool,
+}
+
+impl Default for ElevationConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ElevationStrategy::None,
+            warn: default_warn(),
+        }
+    }
+}
+
+fn default_warn() -> bool {
+    true
+}
+
+static WARN: AtomicBool = AtomicBool::new(true);
+
+/// 启动和保存配置时调用
+pub fn install(config: &ElevationConfig) {
+    WARN.store(config.warn, Ordering::SeqCst);
+}
+
+/// 进程启动时调用：按策略以管理员权限重新启动，或检查 UIAccess 是否生效。
+/// 返回 `true` 表示新进程已启动，当前进程应退出
+pub fn apply_at_startup(config: &ElevationConfig) -> bool {
+    if !cfg!(windows) {
+        return false;
+    }
+    match config.strategy {
+        ElevationStrategy::RunElevated if !platform::is_elevated() => match platform::relaunch_elevated() {
+            Ok(()) => {
+                info!(
+                    target: "elevation",
+                    "已请求以管理员权限重新启动 | Relaunching with administrator rights"
+                );
+                true
+            }
+            Err(err) => {
+                warn!(
+                    target: "elevation",
+                    error = %err,
+                    "以管理员权限重新启动失败，继续以普通权限运行 | Elevated relaunch failed, running unelevated"
+                );
+                false
+            }
+        },
+        ElevationStrategy::UiAccess if !platform::has_ui_access() => {
+            warn!(
+                target: "elevation",
+                "未获得 UIAccess，需要签名并安装到 Program Files 的 ui-access 版本 | UIAccess not granted; \
+                 requires a signed ui-access build installed under Program Files"
+            );
+            false
+        }
+        _ => false,
+    }
+}
+
+/// 当前进程能否操作管理员权限的窗口
+fn can_reach_elevated() -> bool {
+    platform::is_elevated() || platform::has_ui_access()
+}
+
+/// 后台检查前台窗口：切换到管理员权限的窗口而本进程无法操作时，每个应用提示一次
+pub fn spawn_watcher(notifier: Arc<Notifier>) {
+    if !cfg!(windows) || can_reach_elevated() {
+        return;
+    }
+    std::thread::spawn(move || {
+        let mut last_pid = None;
+        let mut warned = HashSet::new();
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let Ok(window) = active_win_pos_rs::get_active_window() else {
+                continue;
+            };
+            if last_pid.replace(window.process_id) == Some(window.process_id) {
+                continue;
+            }
+            if platform::process_elevated(window.process_id) != Some(true) {
+                continue;
+            }
+            warn!(
+                target: "elevation",
+                app = %window.app_name,
+                "前台窗口以管理员权限运行，热键和文字输入无法到达 | Foreground window is elevated, \
+                 hotkey and injection cannot reach it"
+            );
+            if WARN.load(Ordering::SeqCst) && warned.insert(window.app_name.clone()) {
+                notifier.elevated_window(&window.app_name);
+            }
+        }
+    });
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// 错误码：前端按错误码展示提示，并给出对应的处理入口
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ErrorCode {
+    #[serde(rename = "E_MIC_PERMISSION")]
+    MicPermission,
+    #[serde(rename = "E_AUDIO_DEVICE")]
+    AudioDevice,
+    #[serde(rename = "E_ACCESSIBILITY")]
+    Accessibility,
+    #[serde(rename = "E_SERVER_UNREACHABLE")]
+    ServerUnreachable,
+    #[serde(rename = "E_ASR_FAILED")]
+    AsrFailed,
+    #[serde(rename = "E_LLM_FAILED")]
+    LlmFailed,
+    #[serde(rename = "E_INJECTION_FAILED")]
+    InjectionFailed,
+    #[serde(rename = "E_CONFIG")]
+    Config,
+    #[serde(rename = "E_BUSY")]
+    Busy,
+    #[serde(rename = "E_PAUSED")]
+    Paused,
+    #[serde(rename = "E_APP_BLOCKED")]
+    AppBlocked,
+    #[serde(rename = "E_NO_SELECTION")]
+    NoSelection,
+    #[serde(rename = "E_NOT_FOUND")]
+    NotFound,
+    #[serde(rename = "E_IO")]
+    Io,
+    #[serde(rename = "E_INTERNAL")]
+    Internal,
+}
+
+/// 建议的处理方式，前端据此渲染按钮（例如「打开设置」）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestedAction {
+    OpenMicrophoneSettings,
+    OpenSoundSettings,
+    OpenAccessibilitySettings,
+    CheckServer,
+    CheckLlm,
+    OpenSettings,
+    Resume,
+    Retry,
+    ViewLogs,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::MicPermission => "E_MIC_PERMISSION",
+            ErrorCode::AudioDevice => "E_AUDIO_DEVICE",
+            ErrorCode::Accessibility => "E_ACCESSIBILITY",
+            ErrorCode::ServerUnreachable => "E_SERVER_UNREACHABLE",
+            ErrorCode::AsrFailed => "E_ASR_FAILED",
+            ErrorCode::LlmFailed => "E_LLM_FAILED",
+            ErrorCode::InjectionFailed => "E_INJECTION_FAILED",
+            ErrorCode::Config => "E_CONFIG",
+            ErrorCode::Busy => "E_BUSY",
+            ErrorCode::Paused => "E_PAUSED",
+            ErrorCode::AppBlocked => "E_APP_BLOCKED",
+            ErrorCode::NoSelection => "E_NO_SELECTION",
+            ErrorCode::NotFound => "E_NOT_FOUND",
+            ErrorCode::Io => "E_IO",
+            ErrorCode::Internal => "E_INTERNAL",
+        }
+    }
+
+    /// 中英双语的概要说明（具体原因放在 `detail`）
+    pub fn message(self) -> &'static str {
+        match self {
+            ErrorCode::MicPermission => "麦克风不可用或未授权 | Microphone unavailable or permission denied",
+            ErrorCode::AudioDevice => "音频设备不可用 | Audio device unavailable",
+            ErrorCode::Accessibility => "缺少辅助功能权限 | Accessibility permission missing",
+            ErrorCode::ServerUnreachable => "无法连接 ASR 服务器 | ASR server unreachable",
+            ErrorCode::AsrFailed => "语音识别失败 | Speech recognition failed",
+            ErrorCode::LlmFailed => "LLM 校正失败 | LLM correction failed",
+            ErrorCode::InjectionFailed => "文字输入失败 | Text injection failed",
+            ErrorCode::Config => "配置无效或无法保存 | Invalid config or save failed",
+            ErrorCode::Busy => "正在录音，请稍后再试 | Busy recording, try again later",
+            ErrorCode::Paused => "GhostType 已暂停 | GhostType is paused",
+            ErrorCode::AppBlocked => "当前应用已禁用听写 | Dictation is disabled in this app",
+            ErrorCode::NoSelection => "没有选中的文字 | No text selected",
+            ErrorCode::NotFound => "找不到请求的内容 | Not found",
+            ErrorCode::Io => "文件读写失败 | File I/O failed",
+            ErrorCode::Internal => "内部错误 | Internal error",
+        }
+    }
+
+    pub fn action(self) -> Option<SuggestedAction> {
+        match self {
+            ErrorCode::MicPermission => Some(SuggestedAction::OpenMicrophoneSettings),
+            ErrorCode::AudioDevice => Some(SuggestedAction::OpenSoundSettings),
+            ErrorCode::Accessibility | ErrorCode::InjectionFailed => Some(SuggestedAction::OpenAccessibilitySettings),
+            ErrorCode::ServerUnreachable | ErrorCode::AsrFailed => Some(SuggestedAction::CheckServer),
+            ErrorCode::LlmFailed => Some(SuggestedAction::CheckLlm),
+            ErrorCode::Config | ErrorCode::AppBlocked => Some(SuggestedAction::OpenSettings),
+            ErrorCode::Busy => Some(SuggestedAction::Retry),
+            ErrorCode::Paused => Some(SuggestedAction::Resume),
+            ErrorCode::NoSelection | ErrorCode::NotFound | ErrorCode::Io => None,
+            ErrorCode::Internal => Some(SuggestedAction::ViewLogs),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 命令与 Pipeline 统一使用的错误类型，序列化后直接交给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: &'static str,
+    pub detail: String,
+    pub action: Option<SuggestedAction>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: code.message(),
+            detail: detail.into(),
+            action: code.action(),
+        }
+    }
+
+    /// 把底层错误归到指定错误码；已是 `AppError` 的保留原错误码
+    pub fn with_code(code: ErrorCode) -> impl FnOnce(anyhow::Error) -> Self {
+        move |err| match err.downcast::<AppError>() {
+            Ok(err) => err,
+            Err(err) => AppError::new(code, format!("{err:#}")),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)?;
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        AppError::with_code(ErrorCode::Internal)(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_error_serializes_code_message_and_action() {
+        let err = AppError::new(ErrorCode::MicPermission, "denied");
+        let value = serde_json::to_value(&err).expect("serialize");
+        assert_eq!(value["code"], "E_MIC_PERMISSION");
+        assert_eq!(value["action"], "open_microphone_settings");
+        assert_eq!(value["detail"], "denied");
+        assert!(value["message"].as_str().unwrap_or("").contains(" | "));
+    }
+
+    #[test]
+    fn anyhow_conversion_keeps_existing_code() {
+        let inner = anyhow::Error::new(AppError::new(ErrorCode::Busy, "recording"));
+        assert_eq!(AppError::from(inner).code, ErrorCode::Busy);
+
+        let plain = anyhow::anyhow!("boom").context("load");
+        let err = AppError::with_code(ErrorCode::Config)(plain);
+        assert_eq!(err.code, ErrorCode::Config);
+        assert_eq!(err.detail, "load: boom");
+    }
+}
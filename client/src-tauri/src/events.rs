@@ -0,0 +1,310 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::error::AppError;
+use crate::network::TrafficStats;
+use crate::voice_command::EditAction;
+
+/// Pipeline 对外发布的状态事件（托盘/前端等订阅方统一从这里消费）
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PipelineEvent {
+    SessionStarted {
+        trace_id: String,
+        app_name: String,
+        window_title: String,
+    },
+    /// 录音电平（0.0~1.0），约每 50ms 一次
+    AudioLevel { trace_id: Option<String>, levels: Vec<f32> },
+    Partial { trace_id: Option<String>, text: String },
+    /// 分段输出时录音中已输入的一段（从 1 开始编号），最后一段仍走 `AsrFinal`
+    SegmentFinal {
+        trace_id: Option<String>,
+        index: u32,
+        text: String,
+    },
+    AsrFinal {
+        trace_id: Option<String>,
+        text: String,
+        asr_ms: u64,
+        audio_ms: u64,
+    },
+    Correcting { trace_id: Option<String> },
+    CorrectionApplied {
+        trace_id: Option<String>,
+        text: String,
+        latency_ms: u64,
+    },
+    CorrectionSkipped { trace_id: Option<String> },
+    /// 校正超出替换时限才就绪，没有自动替换；随后仍会发送 `CorrectionSkipped`
+    CorrectionLate(LateCorrection),
+    /// 会话被取消，没有请求识别结果，也没有输出
+    SessionCancelled { trace_id: Option<String> },
+    /// 选中的文字已按语音指令改写并替换
+    TransformApplied {
+        trace_id: Option<String>,
+        instruction: String,
+        text: String,
+        latency_ms: u64,
+    },
+    /// 整句识别为编辑命令；`applied` 为假表示当前应用没有可操作的输入
+    VoiceCommand {
+        trace_id: Option<String>,
+        action: EditAction,
+        applied: bool,
+    },
+    /// "记住这个"：从用户的修改中学到一条说法，已记入个人词典
+    TermLearned {
+        trace_id: Option<String>,
+        from: String,
+        to: String,
+    },
+    /// 服务器不可达，会话已暂存本地；`pending` 为队列中的会话数
+    SessionQueued { trace_id: Option<String>, pending: usize },
+    /// 暂存的会话在连接恢复后补交成功，结果已输出
+    DeferredDelivered {
+        trace_id: Option<String>,
+        text: String,
+        pending: usize,
+    },
+    /// 配置了多个服务器时，本次会话改用了另一台（延迟更低或原服务器不可用）
+    ServerSelected {
+        trace_id: Option<String>,
+        endpoint: String,
+        rtt_ms: Option<u64>,
+    },
+    /// 保存的配置已应用到运行中的 Pipeline（录音中保存的在会话结束后应用）
+    Reconfigured {
+        asr_replaced: bool,
+        llm_replaced: bool,
+        /// 重建 ASR 引擎后使用的服务器（非 WebSocket 后端为空）
+        endpoint: Option<String>,
+    },
+    /// 会话结束时的汇总，一次会话只发一次
+    SessionSummary(SessionSummary),
+    /// 一次会话（含离线补交）的网络用量
+    NetworkUsage {
+        trace_id: Option<String>,
+        traffic: TrafficStats,
+    },
+    /// 会议转写已开始，转写内容逐段追加到 `path`
+    MeetingStarted { trace_id: Option<String>, path: String },
+    /// 会议转写写入了一段；`offset_ms` 为这一段开始时距会议开始的时长
+    MeetingSegment {
+        trace_id: Option<String>,
+        offset_ms: u64,
+        text: String,
+    },
+    /// 会议转写已结束，文件已保存
+    MeetingSaved {
+        trace_id: Option<String>,
+        path: String,
+        segments: u32,
+        duration_ms: u64,
+    },
+    /// 错误码/说明/建议操作平铺在事件里，前端与命令错误共用一套展示逻辑
+    Failed {
+        trace_id: Option<String>,
+        #[serde(flatten)]
+        error: AppError,
+    },
+}
+
+impl PipelineEvent {
+    /// 推送给前端时使用的 Tauri 事件名
+    pub fn name(&self) -> &'static str {
+        match self {
+            PipelineEvent::SessionStarted { .. } => "session-started",
+            PipelineEvent::AudioLevel { .. } => "audio-level",
+            PipelineEvent::Partial { .. } => "partial",
+            PipelineEvent::SegmentFinal { .. } => "asr-segment",
+            PipelineEvent::AsrFinal { .. } => "asr-final",
+            PipelineEvent::Correcting { .. } => "correcting",
+            PipelineEvent::CorrectionApplied { .. } => "correction-applied",
+            PipelineEvent::CorrectionSkipped { .. } => "correction-skipped",
+            PipelineEvent::CorrectionLate(_) => "correction-late",
+            PipelineEvent::SessionCancelled { .. } => "session-cancelled",
+            PipelineEvent::TransformApplied { .. } => "transform-applied",
+            PipelineEvent::VoiceCommand { .. } => "voice-command",
+            PipelineEvent::TermLearned { .. } => "term-learned",
+            PipelineEvent::SessionQueued { .. } => "session-queued",
+            PipelineEvent::DeferredDelivered { .. } => "deferred-delivered",
+            PipelineEvent::ServerSelected { .. } => "server-selected",
+            PipelineEvent::Reconfigured { .. } => "pipeline-reconfigured",
+            PipelineEvent::SessionSummary(_) => "session-summary",
+            PipelineEvent::NetworkUsage { .. } => "network-usage",
+            PipelineEvent::MeetingStarted { .. } => "meeting-started",
+            PipelineEvent::MeetingSegment { .. } => "meeting-segment",
+            PipelineEvent::MeetingSaved { .. } => "meeting-saved",
+            PipelineEvent::Failed { .. } => "error",
+        }
+    }
+
+    /// 历史记录、统计、钩子等需要完整收到的事件；录音电平与中间结果只用于展示
+    pub fn is_record(&self) -> bool {
+        !matches!(self, PipelineEvent::AudioLevel { .. } | PipelineEvent::Partial { .. })
+    }
+}
+
+/// 单次会话的汇总指标，按 `trace_id` 与历史记录对应
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionSummary {
+    pub trace_id: Option<String>,
+    pub audio_ms: u64,
+    /// 送入 ASR 引擎的音频帧数
+    pub frames_sent: u64,
+    /// 松开热键到拿到识别结果
+    pub asr_ms: u64,
+    /// 未调用 LLM 校正时为空
+    pub llm_ms: Option<u64>,
+    /// 最终留在输入框里的字数（校正替换后以校正结果为准）
+    pub chars_injected: usize,
+    pub corrected: bool,
+}
+
+/// 超出替换时限的校正结果，由用户决定应用（回删后输入）还是复制
+#[derive(Debug, Clone, Serialize)]
+pub struct LateCorrection {
+    pub trace_id: Option<String>,
+    pub app_name: String,
+    /// 替换时回删的字数，即本次会话最后输入的一段
+    pub replace_chars: usize,
+    /// 回删后输入的文字
+    pub replacement: String,
+    /// 校正后的完整结果
+    pub text: String,
+    pub latency_ms: u64,
+}
+
+#[derive(Clone)]
+pub struct EventSender {
+    tx: mpsc::Sender<PipelineEvent>,
+    records: Option<RecordSender>,
+}
+
+#[derive(Clone)]
+struct RecordSender {
+    tx: mpsc::UnboundedSender<PipelineEvent>,
+    /// 已发出但还没有处理完的记录事件
+    pending: Arc<AtomicUsize>,
+}
+
+impl EventSender {
+    /// 展示用的事件在订阅方处理不过来时直接丢弃，不阻塞会话；记录事件另走不丢弃的通道。
+    pub fn send(&self, event: PipelineEvent) {
+        if let Some(records) = self.records.as_ref().filter(|_| event.is_record()) {
+            records.pending.fetch_add(1, Ordering::SeqCst);
+            if records.tx.send(event.clone()).is_err() {
+                records.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+        let _ = self.tx.try_send(event);
+    }
+
+    /// 等待已发出的记录事件都处理完（历史记录、统计随之写入），最多等 `timeout`
+    pub async fn drained(&self, timeout: Duration) {
+        let Some(records) = &self.records else {
+            return;
+        };
+        let deadline = Instant::now() + timeout;
+        while records.pending.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+/// 记录事件的接收端：按发出顺序逐条处理，处理完一条后调用 `done`
+pub struct RecordReceiver {
+    rx: mpsc::UnboundedReceiver<PipelineEvent>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl RecordReceiver {
+    pub async fn recv(&mut self) -> Option<PipelineEvent> {
+        self.rx.recv().await
+    }
+
+    pub fn done(&self) {
+        self.pending.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 只有展示通道，测试中直接读取全部事件
+pub fn channel() -> (EventSender, mpsc::Receiver<PipelineEvent>) {
+    let (tx, rx) = mpsc::channel::<PipelineEvent>(64);
+    (EventSender { tx, records: None }, rx)
+}
+
+/// 展示通道之外再建一条记录通道，供历史记录、统计、指标与钩子使用
+pub fn channel_with_records() -> (EventSender, mpsc::Receiver<PipelineEvent>, RecordReceiver) {
+    let (tx, rx) = mpsc::channel::<PipelineEvent>(64);
+    let (record_tx, record_rx) = mpsc::unbounded_channel();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let records = RecordSender {
+        tx: record_tx,
+        pending: pending.clone(),
+    };
+    let sender = EventSender {
+        tx,
+        records: Some(records),
+    };
+    (sender, rx, RecordReceiver { rx: record_rx, pending })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn pipeline_event_serializes_with_type_tag() {
+        let event = PipelineEvent::Failed {
+            trace_id: Some("t1".to_string()),
+            error: AppError::new(ErrorCode::ServerUnreachable, "refused"),
+        };
+        assert_eq!(event.name(), "error");
+        let value = serde_json::to_value(&event).expect("serialize");
+        assert_eq!(value.get("type").and_then(|v| v.as_str()), Some("failed"));
+        assert_eq!(value.get("code").and_then(|v| v.as_str()), Some("E_SERVER_UNREACHABLE"));
+        assert_eq!(value.get("detail").and_then(|v| v.as_str()), Some("refused"));
+    }
+
+    #[tokio::test]
+    async fn record_events_survive_a_full_display_channel() {
+        let (events, mut display, mut records) = channel_with_records();
+        for _ in 0..100 {
+            events.send(PipelineEvent::AudioLevel {
+                trace_id: None,
+                levels: vec![0.5],
+            });
+        }
+        events.send(PipelineEvent::AsrFinal {
+            trace_id: Some("t1".to_string()),
+            text: "你好".to_string(),
+            asr_ms: 120,
+            audio_ms: 900,
+        });
+        // 展示通道已满，识别结果只能从记录通道收到
+        let mut displayed = 0;
+        while display.try_recv().is_ok() {
+            displayed += 1;
+        }
+        assert_eq!(displayed, 64);
+        let record = records.recv().await.expect("record");
+        assert!(matches!(record, PipelineEvent::AsrFinal { .. }));
+        assert!(records.rx.try_recv().is_err());
+
+        // 处理完之前 drained 一直等到超时
+        let started = Instant::now();
+        events.drained(Duration::from_millis(100)).await;
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        records.done();
+        let started = Instant::now();
+        events.drained(Duration::from_secs(5)).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
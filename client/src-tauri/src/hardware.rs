@@ -0,0 +1,159 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::platform::{self, Hardware};
+
+/// 本机的推理能力，设置界面据此推荐本地 Whisper 与 LLM（Ollama）的模型大小
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemCapabilities {
+    pub os: String,
+    pub arch: String,
+    pub cpu_cores: usize,
+    pub memory_mb: Option<u64>,
+    pub accelerators: Vec<Accelerator>,
+    pub gpu_name: Option<String>,
+    pub gpu_memory_mb: Option<u64>,
+    pub recommended: Recommendation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Accelerator {
+    Metal,
+    Cuda,
+    #[serde(rename = "directml")]
+    DirectMl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Recommendation {
+    /// Whisper 模型名（`tiny`…`large-v3`），在本机运行服务端时使用
+    pub whisper_model: &'static str,
+    /// Ollama 模型名；内存不足以在识别的同时运行 LLM 时为 `None`
+    pub llm_model: Option<&'static str>,
+}
+
+/// 首次调用时探测，之后返回缓存（硬件在运行期间不会变化）
+pub fn capabilities() -> SystemCapabilities {
+    static CACHED: OnceLock<SystemCapabilities> = OnceLock::new();
+    CACHED.get_or_init(probe).clone()
+}
+
+fn probe() -> SystemCapabilities {
+    let hardware = platform::hardware();
+    let cpu_cores = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut accelerators = Vec::new();
+    if hardware.metal {
+        accelerators.push(Accelerator::Metal);
+    }
+    if hardware.cuda {
+        accelerators.push(Accelerator::Cuda);
+    }
+    if hardware.directml {
+        accelerators.push(Accelerator::DirectMl);
+    }
+    SystemCapabilities {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpu_cores,
+        memory_mb: hardware.memory_mb,
+        accelerators,
+        recommended: recommend(&hardware, cpu_cores),
+        gpu_name: hardware.gpu_name,
+        gpu_memory_mb: hardware.gpu_memory_mb,
+    }
+}
+
+/// 有 CUDA 时按显存、Apple Silicon 按一半内存选模型；DirectML 与只有 CPU 时按核数与内存选小模型
+fn recommend(hardware: &Hardware, cpu_cores: usize) -> Recommendation {
+    let memory_mb = hardware.memory_mb.unwrap_or(0);
+    let gpu_budget_mb = if hardware.cuda {
+        hardware.gpu_memory_mb
+    } else if hardware.metal && hardware.unified_memory {
+        Some(memory_mb / 2)
+    } else {
+        None
+    };
+    let Some(budget) = gpu_budget_mb else {
+        let whisper_model = match (cpu_cores, memory_mb) {
+            (8.., 8_000..) => "small",
+            (4.., _) => "base",
+            _ => "tiny",
+        };
+        let llm_model = match (cpu_cores, memory_mb) {
+            (8.., 16_000..) => Some("qwen2.5:1.5b"),
+            (_, 8_000..) => Some("qwen2.5:0.5b"),
+            _ => None,
+        };
+        return Recommendation {
+            whisper_model,
+            llm_model,
+        };
+    };
+    let whisper_model = match budget {
+        10_000.. => "large-v3",
+        6_000.. => "large-v3-turbo",
+        5_000.. => "medium",
+        2_000.. => "small",
+        _ => "base",
+    };
+    let llm_model = match budget {
+        8_000.. => "qwen2.5:7b",
+        4_000.. => "qwen2.5:3b",
+        2_000.. => "qwen2.5:1.5b",
+        _ => "qwen2.5:0.5b",
+    };
+    Recommendation {
+        whisper_model,
+        llm_model: Some(llm_model),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn models_are_sized_to_the_accelerator() {
+        let models = |hardware: Hardware, cores| {
+            let recommended = recommend(&hardware, cores);
+            (recommended.whisper_model, recommended.llm_model)
+        };
+        let apple_silicon = Hardware {
+            memory_mb: Some(16_384),
+            metal: true,
+            unified_memory: true,
+            ..Hardware::default()
+        };
+        assert_eq!(models(apple_silicon, 8), ("large-v3-turbo", Some("qwen2.5:7b")));
+        let rtx = Hardware {
+            memory_mb: Some(32_768),
+            cuda: true,
+            gpu_memory_mb: Some(12_282),
+            directml: true,
+            ..Hardware::default()
+        };
+        assert_eq!(models(rtx.clone(), 16), ("large-v3", Some("qwen2.5:7b")));
+        let small_gpu = Hardware {
+            gpu_memory_mb: Some(4_096),
+            ..rtx
+        };
+        assert_eq!(models(small_gpu, 16), ("small", Some("qwen2.5:3b")));
+
+        // Intel Mac 与只有 DirectML 的机器按 CPU 推荐
+        let intel_mac = Hardware {
+            memory_mb: Some(16_384),
+            metal: true,
+            ..Hardware::default()
+        };
+        assert_eq!(models(intel_mac, 8), ("small", Some("qwen2.5:1.5b")));
+        let laptop = Hardware {
+            memory_mb: Some(8_192),
+            directml: true,
+            ..Hardware::default()
+        };
+        assert_eq!(models(laptop, 4), ("base", Some("qwen2.5:0.5b")));
+        assert_eq!(models(Hardware::default(), 2), ("tiny", None));
+    }
+}
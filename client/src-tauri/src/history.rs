@@ -0,0 +1,606 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::language;
+use crate::privacy;
+use crate::stats::UNKNOWN_APP;
+
+/// 已完成的会话记录
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub trace_id: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub app_name: String,
+    pub window_title: String,
+    pub asr_text: String,
+    pub corrected_text: Option<String>,
+    pub asr_ms: u64,
+    pub llm_ms: Option<u64>,
+}
+
+/// 某个应用一天的听写用量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyUsage {
+    /// `YYYY-MM-DD`（本地日期）
+    pub day: String,
+    pub sessions: u64,
+    pub words: u64,
+    pub failures: u64,
+}
+
+/// 按应用汇总的听写用量；清空会话历史不影响这里的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUsage {
+    pub app_name: String,
+    /// 有识别结果的会话数
+    pub sessions: u64,
+    /// 最终输入的字数：汉字按字、英文按词计
+    pub words: u64,
+    /// 没有产生识别结果的失败会话数（识别后 LLM 校正失败不计入）
+    pub failures: u64,
+    /// 失败会话占全部会话的比例
+    pub error_rate: f64,
+    /// 按日期正序
+    pub daily: Vec<DailyUsage>,
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// 导出范围：指定 id 时忽略日期；日期为 `YYYY-MM-DD` 或完整 RFC3339，按开始时间筛选（含两端）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportSelection {
+    #[serde(default)]
+    pub ids: Vec<i64>,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+struct PendingSession {
+    started_at: String,
+    app_name: String,
+    window_title: String,
+}
+
+/// 本地 SQLite 会话历史
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+    pending: Mutex<HashMap<String, PendingSession>>,
+    /// 最近落库的会话及其字数：开启隐私模式时库中只有脱敏文字，校正时据此计算字数变化
+    last_words: Mutex<Option<(String, usize)>>,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    trace_id TEXT NOT NULL UNIQUE,
+    started_at TEXT NOT NULL,
+    ended_at TEXT NOT NULL,
+    app_name TEXT NOT NULL DEFAULT '',
+    window_title TEXT NOT NULL DEFAULT '',
+    asr_text TEXT NOT NULL,
+    corrected_text TEXT,
+    asr_ms INTEGER NOT NULL DEFAULT 0,
+    llm_ms INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_sessions_started_at ON sessions(started_at);
+CREATE TABLE IF NOT EXISTS app_usage (
+    day TEXT NOT NULL,
+    app_name TEXT NOT NULL,
+    sessions INTEGER NOT NULL DEFAULT 0,
+    words INTEGER NOT NULL DEFAULT 0,
+    failures INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, app_name)
+);
+";
+
+const SELECT_COLUMNS: &str =
+    "id, trace_id, started_at, ended_at, app_name, window_title, asr_text, corrected_text, asr_ms, llm_ms";
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent).context("create history dir")?;
+            }
+        }
+        let conn = Connection::open(path).context("open history db")?;
+        Self::with_connection(conn)
+    }
+
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        let conn = Connection::open_in_memory().context("open in-memory history db")?;
+        Self::with_connection(conn)
+    }
+
+    /// 打开默认位置的历史库；失败时回退内存库，保证应用照常运行。
+    pub fn open_default() -> Self {
+        let path = resolve_history_path();
+        match Self::open(&path) {
+            Ok(store) => store,
+            Err(err) => {
+                warn!(
+                    target: "history",
+                    path = %path.display(),
+                    error = %err,
+                    "历史数据库打开失败，使用内存库 | History db open failed, using in-memory store"
+                );
+                Self::open_in_memory().expect("in-memory history db")
+            }
+        }
+    }
+
+    fn with_connection(conn: Connection) -> anyhow::Result<Self> {
+        conn.execute_batch(SCHEMA).context("init history schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            pending: Mutex::new(HashMap::new()),
+            last_words: Mutex::new(None),
+        })
+    }
+
+    /// 会话开始：暂存上下文，等识别结果出来后再落库。
+    pub fn begin(&self, trace_id: &str, app_name: &str, window_title: &str) {
+        let mut pending = self.pending.lock().expect("history pending lock");
+        // 同一时刻只有一个录音会话，未落库的旧会话（识别为空/失败）直接丢弃。
+        pending.clear();
+        pending.insert(
+            trace_id.to_string(),
+            PendingSession {
+                started_at: now_rfc3339(),
+                app_name: app_name.to_string(),
+                window_title: window_title.to_string(),
+            },
+        );
+    }
+
+    pub fn finalize(&self, trace_id: &str, asr_text: &str, asr_ms: u64) -> anyhow::Result<()> {
+        let pending = self.pending.lock().expect("history pending lock").remove(trace_id);
+        let ended_at = now_rfc3339();
+        let (started_at, app_name, window_title) = match pending {
+            Some(p) => (p.started_at, p.app_name, p.window_title),
+            None => (ended_at.clone(), String::new(), String::new()),
+        };
+
+        let conn = self.conn.lock().expect("history conn lock");
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions (trace_id, started_at, ended_at, app_name, window_title, asr_text, asr_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                trace_id,
+                started_at,
+                ended_at,
+                app_name,
+                privacy::redact(&window_title),
+                privacy::redact(asr_text),
+                asr_ms as i64
+            ],
+        )
+        .context("insert history")?;
+        let words = count_words(asr_text);
+        add_usage(&conn, &started_at, &app_name, 1, words as i64, 0)?;
+        *self.last_words.lock().expect("history words lock") = Some((trace_id.to_string(), words));
+        Ok(())
+    }
+
+    pub fn apply_correction(&self, trace_id: &str, corrected_text: &str, llm_ms: u64) -> anyhow::Result<()> {
+        let conn = self.conn.lock().expect("history conn lock");
+        let previous = conn
+            .query_row(
+                "SELECT started_at, app_name, COALESCE(corrected_text, asr_text) FROM sessions WHERE trace_id = ?1",
+                params![trace_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            )
+            .ok();
+        conn.execute(
+            "UPDATE sessions SET corrected_text = ?2, llm_ms = ?3 WHERE trace_id = ?1",
+            params![trace_id, privacy::redact(corrected_text), llm_ms as i64],
+        )
+        .context("update history correction")?;
+        // 用量按最终留在输入框里的文字计字数
+        if let Some((started_at, app_name, previous)) = previous {
+            let words = count_words(corrected_text);
+            let mut last_words = self.last_words.lock().expect("history words lock");
+            let previous_words = match last_words.as_ref() {
+                Some((last_trace, words)) if last_trace == trace_id => *words,
+                _ => count_words(&previous),
+            };
+            *last_words = Some((trace_id.to_string(), words));
+            let delta = words as i64 - previous_words as i64;
+            add_usage(&conn, &started_at, &app_name, 0, delta, 0)?;
+        }
+        Ok(())
+    }
+
+    /// 会话没有产生识别结果就失败了：计入该应用当天的失败数
+    pub fn record_failure(&self, trace_id: &str) -> anyhow::Result<()> {
+        let Some(pending) = self.pending.lock().expect("history pending lock").remove(trace_id) else {
+            return Ok(());
+        };
+        let conn = self.conn.lock().expect("history conn lock");
+        add_usage(&conn, &pending.started_at, &pending.app_name, 0, 0, 1)
+    }
+
+    /// `since` 当天（`YYYY-MM-DD`）及之后按应用汇总的用量，字数多的在前
+    pub fn app_usage(&self, since: &str) -> anyhow::Result<Vec<AppUsage>> {
+        let conn = self.conn.lock().expect("history conn lock");
+        let mut stmt = conn
+            .prepare(
+                "SELECT day, app_name, sessions, words, failures FROM app_usage
+                 WHERE day >= ?1 ORDER BY day ASC",
+            )
+            .context("prepare app usage")?;
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok((
+                    row.get::<_, String>(1)?,
+                    DailyUsage {
+                        day: row.get(0)?,
+                        sessions: row.get::<_, i64>(2)?.max(0) as u64,
+                        words: row.get::<_, i64>(3)?.max(0) as u64,
+                        failures: row.get::<_, i64>(4)?.max(0) as u64,
+                    },
+                ))
+            })
+            .context("query app usage")?;
+
+        let mut by_app: BTreeMap<String, Vec<DailyUsage>> = BTreeMap::new();
+        for row in rows {
+            let (app_name, daily) = row.context("read app usage row")?;
+            by_app.entry(app_name).or_default().push(daily);
+        }
+        let mut usage: Vec<AppUsage> = by_app
+            .into_iter()
+            .map(|(app_name, daily)| {
+                let sessions = daily.iter().map(|d| d.sessions).sum::<u64>();
+                let failures = daily.iter().map(|d| d.failures).sum::<u64>();
+                let total = sessions + failures;
+                AppUsage {
+                    app_name,
+                    sessions,
+                    words: daily.iter().map(|d| d.words).sum(),
+                    failures,
+                    error_rate: if total == 0 { 0.0 } else { failures as f64 / total as f64 },
+                    daily,
+                }
+            })
+            .collect();
+        usage.sort_by(|a, b| b.words.cmp(&a.words).then_with(|| a.app_name.cmp(&b.app_name)));
+        Ok(usage)
+    }
+
+    pub fn list(&self, limit: u32, offset: u32) -> anyhow::Result<Vec<HistoryEntry>> {
+        let conn = self.conn.lock().expect("history conn lock");
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM sessions ORDER BY started_at DESC, id DESC LIMIT ?1 OFFSET ?2");
+        let mut stmt = conn.prepare(&sql).context("prepare history list")?;
+        let rows = stmt
+            .query_map(params![limit, offset], row_to_entry)
+            .context("query history list")?;
+        rows.collect::<Result<Vec<_>, _>>().context("read history rows")
+    }
+
+    pub fn search(&self, query: &str, limit: u32) -> anyhow::Result<Vec<HistoryEntry>> {
+        let pattern = format!("%{}%", escape_like(query.trim()));
+        let conn = self.conn.lock().expect("history conn lock");
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM sessions
+             WHERE asr_text LIKE ?1 ESCAPE '\\' OR corrected_text LIKE ?1 ESCAPE '\\' OR app_name LIKE ?1 ESCAPE '\\'
+             ORDER BY started_at DESC, id DESC LIMIT ?2"
+        );
+        let mut stmt = conn.prepare(&sql).context("prepare history search")?;
+        let rows = stmt
+            .query_map(params![pattern, limit], row_to_entry)
+            .context("query history search")?;
+        rows.collect::<Result<Vec<_>, _>>().context("read history rows")
+    }
+
+    /// 按选择范围取出会话（时间正序，便于阅读）
+    pub fn select(&self, selection: &ExportSelection) -> anyhow::Result<Vec<HistoryEntry>> {
+        let conn = self.conn.lock().expect("history conn lock");
+        if !selection.ids.is_empty() {
+            let sql = format!("SELECT {SELECT_COLUMNS} FROM sessions WHERE id = ?1");
+            let mut stmt = conn.prepare(&sql).context("prepare history select")?;
+            let mut entries = Vec::with_capacity(selection.ids.len());
+            for id in &selection.ids {
+                let mut rows = stmt.query_map(params![id], row_to_entry).context("query history select")?;
+                if let Some(entry) = rows.next() {
+                    entries.push(entry.context("read history row")?);
+                }
+            }
+            entries.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+            return Ok(entries);
+        }
+
+        let from = selection.from.as_deref().map(str::trim).filter(|v| !v.is_empty()).unwrap_or("");
+        // 只给日期时包含当天全部记录
+        let to = match selection.to.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            Some(to) if to.len() == 10 => format!("{to}T99"),
+            Some(to) => to.to_string(),
+            None => "9999".to_string(),
+        };
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM sessions WHERE started_at >= ?1 AND started_at <= ?2 ORDER BY started_at ASC, id ASC"
+        );
+        let mut stmt = conn.prepare(&sql).context("prepare history select")?;
+        let rows = stmt
+            .query_map(params![from, to], row_to_entry)
+            .context("query history select")?;
+        rows.collect::<Result<Vec<_>, _>>().context("read history rows")
+    }
+
+    pub fn delete(&self, ids: &[i64]) -> anyhow::Result<usize> {
+        let conn = self.conn.lock().expect("history conn lock");
+        let mut deleted = 0;
+        for id in ids {
+            deleted += conn
+                .execute("DELETE FROM sessions WHERE id = ?1", params![id])
+                .context("delete history")?;
+        }
+        Ok(deleted)
+    }
+
+    pub fn clear(&self) -> anyhow::Result<usize> {
+        let conn = self.conn.lock().expect("history conn lock");
+        conn.execute("DELETE FROM sessions", []).context("clear history")
+    }
+}
+
+/// 把会话渲染为导出文件内容
+pub fn render_export(entries: &[HistoryEntry], format: ExportFormat) -> anyhow::Result<String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries).context("serialize history export"),
+        ExportFormat::Csv => {
+            let mut out = String::from("id,started_at,app_name,window_title,text,asr_text,asr_ms,llm_ms\n");
+            for entry in entries {
+                let fields = [
+                    entry.id.to_string(),
+                    entry.started_at.clone(),
+                    entry.app_name.clone(),
+                    entry.window_title.clone(),
+                    final_text(entry).to_string(),
+                    entry.asr_text.clone(),
+                    entry.asr_ms.to_string(),
+                    entry.llm_ms.map(|v| v.to_string()).unwrap_or_default(),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&line.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        ExportFormat::Markdown => {
+            let mut out = String::from("# GhostType 会话记录\n");
+            let mut current_day = "";
+            for entry in entries {
+                let day = entry.started_at.get(..10).unwrap_or(&entry.started_at);
+                if day != current_day {
+                    out.push_str(&format!("\n## {day}\n"));
+                    current_day = day;
+                }
+                let time = entry.started_at.get(11..16).unwrap_or("");
+                if entry.app_name.is_empty() {
+                    out.push_str(&format!("\n**{time}**\n\n"));
+                } else {
+                    out.push_str(&format!("\n**{time}** · {}\n\n", entry.app_name));
+                }
+                out.push_str(final_text(entry));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+fn final_text(entry: &HistoryEntry) -> &str {
+    entry.corrected_text.as_deref().unwrap_or(&entry.asr_text)
+}
+
+fn csv_field(raw: &str) -> String {
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw.to_string()
+    }
+}
+
+fn row_to_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        trace_id: row.get(1)?,
+        started_at: row.get(2)?,
+        ended_at: row.get(3)?,
+        app_name: row.get(4)?,
+        window_title: row.get(5)?,
+        asr_text: row.get(6)?,
+        corrected_text: row.get(7)?,
+        asr_ms: row.get::<_, i64>(8)?.max(0) as u64,
+        llm_ms: row.get::<_, Option<i64>>(9)?.map(|v| v.max(0) as u64),
+    })
+}
+
+/// 累加某个应用在会话开始当天的用量
+fn add_usage(
+    conn: &Connection,
+    started_at: &str,
+    app_name: &str,
+    sessions: i64,
+    words: i64,
+    failures: i64,
+) -> anyhow::Result<()> {
+    let day = started_at.get(..10).unwrap_or(started_at);
+    let app_name = match app_name.trim() {
+        "" => UNKNOWN_APP,
+        app => app,
+    };
+    conn.execute(
+        "INSERT INTO app_usage (day, app_name, sessions, words, failures) VALUES (?1, ?2, ?3, MAX(?4, 0), ?5)
+         ON CONFLICT(day, app_name) DO UPDATE SET
+             sessions = sessions + excluded.sessions,
+             words = MAX(words + ?4, 0),
+             failures = failures + excluded.failures",
+        params![day, app_name, sessions, words, failures],
+    )
+    .context("update app usage")?;
+    Ok(())
+}
+
+/// 汉字（含假名、谚文）每个字算一个词，其他文字按连续的字母数字算一个词
+fn count_words(text: &str) -> usize {
+    let mut words = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if language::is_cjk_letter(c) {
+            words += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else {
+            in_word = c == '\'' && in_word;
+        }
+    }
+    words
+}
+
+fn escape_like(raw: &str) -> String {
+    raw.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn now_rfc3339() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+}
+
+fn resolve_history_path() -> PathBuf {
+    if let Ok(explicit) = std::env::var("GHOSTTYPE_HISTORY_DB") {
+        if !explicit.trim().is_empty() {
+            return PathBuf::from(explicit.trim());
+        }
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("data").join("history.sqlite3")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_records_session_and_correction() {
+        let store = HistoryStore::open_in_memory().expect("open");
+        store.begin("t1", "Slack", "general");
+        store.finalize("t1", "你好 世界", 120).expect("finalize");
+        store.apply_correction("t1", "你好，世界", 300).expect("correct");
+
+        let entries = store.list(10, 0).expect("list");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].app_name, "Slack");
+        assert_eq!(entries[0].corrected_text.as_deref(), Some("你好，世界"));
+        assert_eq!(entries[0].llm_ms, Some(300));
+    }
+
+    #[test]
+    fn history_search_and_delete() {
+        let store = HistoryStore::open_in_memory().expect("open");
+        store.finalize("t1", "meeting notes", 10).expect("t1");
+        store.finalize("t2", "100% done", 10).expect("t2");
+
+        assert_eq!(store.search("notes", 10).expect("search").len(), 1);
+        assert_eq!(store.search("100%", 10).expect("search").len(), 1);
+
+        let id = store.search("meeting", 10).expect("search")[0].id;
+        assert_eq!(store.delete(&[id]).expect("delete"), 1);
+        assert_eq!(store.list(10, 0).expect("list").len(), 1);
+        assert_eq!(store.clear().expect("clear"), 1);
+    }
+
+    #[test]
+    fn history_exports_selection_in_each_format() {
+        let store = HistoryStore::open_in_memory().expect("open");
+        store.begin("t1", "Notes", "journal");
+        store.finalize("t1", "first, draft", 10).expect("t1");
+        store.apply_correction("t1", "First, \"draft\"", 20).expect("correct");
+        store.finalize("t2", "second", 10).expect("t2");
+
+        let all = store.select(&ExportSelection::default()).expect("select");
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].trace_id, "t1");
+
+        let only = store
+            .select(&ExportSelection {
+                ids: vec![all[1].id],
+                ..ExportSelection::default()
+            })
+            .expect("select ids");
+        assert_eq!(only.len(), 1);
+        assert_eq!(only[0].asr_text, "second");
+
+        let csv = render_export(&all, ExportFormat::Csv).expect("csv");
+        assert!(csv.contains("\"First, \"\"draft\"\"\""));
+        let markdown = render_export(&all, ExportFormat::Markdown).expect("markdown");
+        assert!(markdown.contains("· Notes"));
+        assert!(markdown.contains("second"));
+        let json = render_export(&all, ExportFormat::Json).expect("json");
+        assert!(json.contains("\"trace_id\": \"t2\""));
+    }
+
+    #[test]
+    fn history_tracks_usage_per_app_and_day() {
+        let store = HistoryStore::open_in_memory().expect("open");
+        store.begin("t1", "Slack", "general");
+        store.finalize("t1", "ship it today", 10).expect("t1");
+        store.apply_correction("t1", "Ship it today, don't wait", 20).expect("correct");
+        store.begin("t2", "Slack", "random");
+        store.finalize("t2", "你好世界", 10).expect("t2");
+        store.begin("t3", "Slack", "random");
+        store.record_failure("t3").expect("failure");
+        store.begin("t4", "", "");
+        store.finalize("t4", "notes", 10).expect("t4");
+        // 已落库的会话之后再失败（例如校正失败）不计入
+        store.record_failure("t4").expect("late failure");
+        store.clear().expect("clear");
+
+        let usage = store.app_usage("2000-01-01").expect("usage");
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].app_name, "Slack");
+        assert_eq!((usage[0].sessions, usage[0].words, usage[0].failures), (2, 9, 1));
+        assert!((usage[0].error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(usage[0].daily.len(), 1);
+        assert_eq!(usage[1].app_name, UNKNOWN_APP);
+        assert_eq!(usage[1].words, 1);
+        assert!(store.app_usage("9999-01-01").expect("usage").is_empty());
+    }
+}
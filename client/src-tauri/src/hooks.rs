@@ -0,0 +1,354 @@
+use std::collections::VecDeque;
+use std::io::{Read as _, Write as _};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+
+/// 最多跟踪的未结束会话；事件丢失时不会无限增长
+const MAX_PENDING: usize = 8;
+
+/// stderr 只在日志里保留末尾这么多字
+const STDERR_TAIL_CHARS: usize = 500;
+
+/// 等待命令结束时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 脚本钩子：会话开始、得到最终结果、出错时执行 shell 命令（Windows 用 cmd /C，其它用 sh -c）。
+/// 文字同时通过 stdin 与 `GHOSTTYPE_*` 环境变量传入，命令在后台执行，不影响输出
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<String>,
+    /// 最终结果（LLM 校正后以校正结果为准）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_final: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<String>,
+    /// 超时后结束命令
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            on_start: None,
+            on_final: None,
+            on_error: None,
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HookEvent {
+    Start {
+        trace_id: String,
+        app: String,
+    },
+    Final {
+        trace_id: Option<String>,
+        app: String,
+        text: String,
+        corrected: bool,
+    },
+    Error {
+        trace_id: Option<String>,
+        code: String,
+        message: String,
+    },
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            HookEvent::Start { .. } => "start",
+            HookEvent::Final { .. } => "final",
+            HookEvent::Error { .. } => "error",
+        }
+    }
+
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![("GHOSTTYPE_EVENT", self.name().to_string())];
+        match self {
+            HookEvent::Start { trace_id, app } => {
+                env.push(("GHOSTTYPE_TRACE_ID", trace_id.clone()));
+                env.push(("GHOSTTYPE_APP", app.clone()));
+            }
+            HookEvent::Final {
+                trace_id,
+                app,
+                text,
+                corrected,
+            } => {
+                env.push(("GHOSTTYPE_TRACE_ID", trace_id.clone().unwrap_or_default()));
+                env.push(("GHOSTTYPE_APP", app.clone()));
+                env.push(("GHOSTTYPE_TEXT", text.clone()));
+                env.push(("GHOSTTYPE_CORRECTED", if *corrected { "1" } else { "0" }.to_string()));
+            }
+            HookEvent::Error {
+                trace_id,
+                code,
+                message,
+            } => {
+                env.push(("GHOSTTYPE_TRACE_ID", trace_id.clone().unwrap_or_default()));
+                env.push(("GHOSTTYPE_ERROR_CODE", code.clone()));
+                env.push(("GHOSTTYPE_ERROR", message.clone()));
+            }
+        }
+        env
+    }
+
+    fn stdin(&self) -> &str {
+        match self {
+            HookEvent::Start { .. } => "",
+            HookEvent::Final { text, .. } => text,
+            HookEvent::Error { message, .. } => message,
+        }
+    }
+}
+
+/// 还没结束的会话：目标应用与最新的结果
+#[derive(Debug)]
+struct PendingSession {
+    trace_id: String,
+    app: String,
+    text: String,
+}
+
+/// 订阅 Pipeline 事件并执行对应的钩子
+pub struct HookRunner {
+    config: Mutex<HooksConfig>,
+    pending: Mutex<VecDeque<PendingSession>>,
+}
+
+impl HookRunner {
+    pub fn new(config: HooksConfig) -> Self {
+        Self {
+            config: Mutex::new(config),
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 保存配置后立即生效
+    pub fn set_config(&self, config: &HooksConfig) {
+        *self.config.lock().expect("hooks config lock") = config.clone();
+    }
+
+    pub fn session_started(&self, trace_id: &str, app: &str) {
+        {
+            let mut pending = self.pending.lock().expect("hooks pending lock");
+            if pending.len() >= MAX_PENDING {
+                pending.pop_front();
+            }
+            pending.push_back(PendingSession {
+                trace_id: trace_id.to_string(),
+                app: app.to_string(),
+                text: String::new(),
+            });
+        }
+        self.run(HookEvent::Start {
+            trace_id: trace_id.to_string(),
+            app: app.to_string(),
+        });
+    }
+
+    /// 识别结果或替换它的校正结果
+    pub fn update_text(&self, trace_id: Option<&str>, text: &str) {
+        let mut pending = self.pending.lock().expect("hooks pending lock");
+        if let Some(session) = pending.iter_mut().find(|session| Some(session.trace_id.as_str()) == trace_id) {
+            session.text = text.to_string();
+        }
+    }
+
+    /// 会话汇总：结果不会再变，有文字时执行 `on_final`
+    pub fn session_finished(&self, trace_id: Option<&str>, corrected: bool) {
+        if let Some(event) = self.finish(trace_id, corrected) {
+            self.run(event);
+        }
+    }
+
+    /// 离线暂存的会话补交成功；它的开始事件早已结束跟踪
+    pub fn deferred_delivered(&self, trace_id: Option<&str>, text: &str) {
+        self.run(HookEvent::Final {
+            trace_id: trace_id.map(str::to_string),
+            app: String::new(),
+            text: text.to_string(),
+            corrected: false,
+        });
+    }
+
+    /// 会话被取消或只执行了编辑命令，没有结果
+    pub fn forget(&self, trace_id: Option<&str>) {
+        self.take(trace_id);
+    }
+
+    pub fn failed(&self, trace_id: Option<&str>, error: &AppError) {
+        self.take(trace_id);
+        let message = if error.detail.trim().is_empty() {
+            error.message.to_string()
+        } else {
+            format!("{}: {}", error.message, error.detail.trim())
+        };
+        self.run(HookEvent::Error {
+            trace_id: trace_id.map(str::to_string),
+            code: error.code.as_str().to_string(),
+            message,
+        });
+    }
+
+    fn take(&self, trace_id: Option<&str>) -> Option<PendingSession> {
+        let mut pending = self.pending.lock().expect("hooks pending lock");
+        let index = pending.iter().position(|session| Some(session.trace_id.as_str()) == trace_id)?;
+        pending.remove(index)
+    }
+
+    fn finish(&self, trace_id: Option<&str>, corrected: bool) -> Option<HookEvent> {
+        let session = self.take(trace_id)?;
+        if session.text.trim().is_empty() {
+            return None;
+        }
+        Some(HookEvent::Final {
+            trace_id: Some(session.trace_id),
+            app: session.app,
+            text: session.text,
+            corrected,
+        })
+    }
+
+    fn run(&self, event: HookEvent) {
+        let (command, timeout) = {
+            let config = self.config.lock().expect("hooks config lock");
+            let command = match &event {
+                HookEvent::Start { .. } => &config.on_start,
+                HookEvent::Final { .. } => &config.on_final,
+                HookEvent::Error { .. } => &config.on_error,
+            };
+            let Some(command) = command.as_deref().map(str::trim).filter(|command| !command.is_empty()) else {
+                return;
+            };
+            (command.to_string(), Duration::from_millis(config.timeout_ms))
+        };
+        tauri::async_runtime::spawn_blocking(move || {
+            let name = event.name();
+            match execute(&command, &event, timeout) {
+                Ok(()) => debug!(target: "hooks", event = name, "钩子已执行 | Hook finished"),
+                Err(err) => warn!(
+                    target: "hooks",
+                    event = name,
+                    error = %format!("{err:#}"),
+                    "钩子执行失败 | Hook failed"
+                ),
+            }
+        });
+    }
+}
+
+fn execute(command: &str, event: &HookEvent, timeout: Duration) -> anyhow::Result<()> {
+    let mut child = shell(command)
+        .envs(event.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // stdin/stderr 在单独的线程里读写，命令不读 stdin 或输出很多时也不会卡住等待
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = event.stdin().to_string();
+        std::thread::spawn(move || {
+            // 命令不读 stdin 时写入会失败，不算错误
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stderr = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            String::from_utf8_lossy(&buf).into_owned()
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("超时 {}ms，已结束 | Timed out and killed", timeout.as_millis());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    if !status.success() {
+        let stderr = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+        let skip = stderr.chars().count().saturating_sub(STDERR_TAIL_CHARS);
+        let tail: String = stderr.chars().skip(skip).collect();
+        anyhow::bail!("{status}: {}", tail.trim());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt as _;
+    // 不弹出控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_report_the_final_text_of_each_session() {
+        let runner = HookRunner::new(HooksConfig::default());
+        runner.session_started("t1", "Notes");
+        runner.session_started("t2", "Mail");
+        runner.update_text(Some("t1"), "hello");
+        runner.update_text(Some("t1"), "Hello.");
+
+        // 校正结果替换识别结果
+        let event = runner.finish(Some("t1"), true).expect("final");
+        assert_eq!(
+            event,
+            HookEvent::Final {
+                trace_id: Some("t1".to_string()),
+                app: "Notes".to_string(),
+                text: "Hello.".to_string(),
+                corrected: true,
+            }
+        );
+        assert_eq!(event.stdin(), "Hello.");
+        assert!(event.env().contains(&("GHOSTTYPE_TEXT", "Hello.".to_string())));
+        assert!(event.env().contains(&("GHOSTTYPE_CORRECTED", "1".to_string())));
+
+        // 只结束一次；没有文字的会话不执行
+        assert!(runner.finish(Some("t1"), true).is_none());
+        assert!(runner.finish(Some("t2"), false).is_none());
+
+        for index in 0..MAX_PENDING + 2 {
+            runner.session_started(&format!("s{index}"), "Notes");
+        }
+        assert_eq!(runner.pending.lock().unwrap().len(), MAX_PENDING);
+        assert!(runner.take(Some("s0")).is_none());
+    }
+}
@@ -1,12 +1,67 @@
 use active_win_pos_rs::ActiveWindow;
 use enigo::{Direction, Enigo, Key, Keyboard, Settings};
-use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, field, info, info_span, warn};
+
+use crate::error::{AppError, ErrorCode};
+use crate::events::{EventSender, PipelineEvent};
+use crate::platform;
+use crate::reliability::{self, InjectionReliability, Insertion};
+
+/// 回读焦点文字的最多次数与间隔：目标程序处理按键需要一点时间，回读稳定后提前结束
+const READ_BACK_ATTEMPTS: u32 = 10;
+const READ_BACK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 文字注入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionStrategy {
+    /// 模拟键盘逐字输入
+    #[default]
+    Typing,
+    /// 写入剪贴板后粘贴（对 IME/终端更可靠），完成后恢复原剪贴板
+    Clipboard,
+}
 
 #[derive(Debug)]
 pub enum InjectCommand {
     TypeText { trace_id: Option<String>, text: String },
+    PasteText { trace_id: Option<String>, text: String },
     Backspace { trace_id: Option<String>, count: usize },
+    /// 按住 Shift 向左选中 `count` 个字
+    SelectBack { trace_id: Option<String>, count: usize },
+    /// 回车
+    Newline { trace_id: Option<String> },
+    /// 只写入剪贴板，不粘贴
+    CopyText { trace_id: Option<String>, text: String },
+    /// 复制当前选中的文字（之后恢复原剪贴板）；没有选中内容时回复空串
+    CopySelection {
+        trace_id: Option<String>,
+        reply: oneshot::Sender<Result<String, String>>,
+    },
+    /// 读取剪贴板中的文字（不按任何键）；剪贴板为空或不是文字时回复空串
+    ReadClipboard {
+        trace_id: Option<String>,
+        reply: oneshot::Sender<Result<String, String>>,
+    },
+    /// 退出前恢复按键状态：松开修饰键；`caps_lock` 时再按一次 CapsLock，撤销按住热键时切换的大写锁定
+    RestoreKeys {
+        caps_lock: bool,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+}
+
+impl InjectCommand {
+    /// 按注入方式构造输出文字的命令
+    pub fn text(strategy: InjectionStrategy, trace_id: Option<String>, text: String) -> Self {
+        match strategy {
+            InjectionStrategy::Typing => InjectCommand::TypeText { trace_id, text },
+            InjectionStrategy::Clipboard => InjectCommand::PasteText { trace_id, text },
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -14,32 +69,135 @@ pub struct Injector {
     pub tx: mpsc::Sender<InjectCommand>,
 }
 
-pub fn spawn_injector() -> Injector {
+impl Injector {
+    /// 读取当前焦点中选中的文字；与其它注入命令排队执行，不会和进行中的输入交错
+    pub async fn copy_selection(&self, trace_id: Option<String>) -> anyhow::Result<String> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(InjectCommand::CopySelection { trace_id, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("键盘注入初始化失败"))?
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// 读取剪贴板文字；排在之前的注入命令之后，读到的是粘贴恢复后的内容
+    pub async fn read_clipboard(&self, trace_id: Option<String>) -> anyhow::Result<String> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(InjectCommand::ReadClipboard { trace_id, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("键盘注入初始化失败"))?
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// 恢复按键状态；排在之前的注入命令之后执行
+    pub async fn restore_keys(&self, caps_lock: bool) -> anyhow::Result<()> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(InjectCommand::RestoreKeys { caps_lock, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("键盘注入初始化失败"))?
+            .map_err(anyhow::Error::msg)
+    }
+}
+
+pub fn spawn_injector(events: EventSender, reliability: Arc<InjectionReliability>) -> Injector {
     let (tx, mut rx) = mpsc::channel::<InjectCommand>(256);
 
     tauri::async_runtime::spawn(async move {
         while let Some(cmd) = rx.recv().await {
-            let _ = tokio::task::spawn_blocking(move || apply_command(cmd)).await;
+            let trace_id = cmd.trace_id_for_log().to_string();
+            let reliability = reliability.clone();
+            let result = tokio::task::spawn_blocking(move || apply_command(cmd, Some(&reliability))).await;
+            if let Ok(Err(message)) = result {
+                events.send(PipelineEvent::Failed {
+                    trace_id: Some(trace_id).filter(|v| !v.is_empty()),
+                    error: AppError::new(ErrorCode::InjectionFailed, message),
+                });
+            }
         }
     });
 
     Injector { tx }
 }
 
-fn apply_command(cmd: InjectCommand) {
+/// 注入自检用的 trace_id，便于在日志中区分
+const INJECTION_TEST_TRACE: &str = "inject-test";
+
+/// 注入自检的步骤：先输入草稿，再退格删掉末尾并补上新文字，
+/// 与真实的「ASR 结果 → LLM 校正替换」顺序一致。返回步骤与期望的最终文字。
+fn injection_test_steps(strategy: InjectionStrategy) -> (Vec<InjectCommand>, String) {
+    const DRAFT: &str = "GhostType 注入测试 abc";
+    const REPLACED: &str = "abc";
+    const REPLACEMENT: &str = "OK";
+
+    let trace_id = Some(INJECTION_TEST_TRACE.to_string());
+    let steps = vec![
+        InjectCommand::text(strategy, trace_id.clone(), DRAFT.to_string()),
+        InjectCommand::Backspace {
+            trace_id: trace_id.clone(),
+            count: REPLACED.chars().count(),
+        },
+        InjectCommand::text(strategy, trace_id, REPLACEMENT.to_string()),
+    ];
+    let expected = format!("{}{REPLACEMENT}", DRAFT.strip_suffix(REPLACED).unwrap_or(DRAFT));
+    (steps, expected)
+}
+
+/// 在当前焦点位置执行一遍完整的注入流程，返回期望输入框中出现的文字
+pub async fn run_injection_test(strategy: InjectionStrategy) -> Result<String, String> {
+    let (steps, expected) = injection_test_steps(strategy);
+    for step in steps {
+        tokio::task::spawn_blocking(move || apply_command(step, None))
+            .await
+            .map_err(|err| err.to_string())??;
+        // 给目标输入框一点时间处理上一步的按键
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(expected)
+}
+
+/// 在 `inject` span 中执行，链路导出时作为输入阶段。`reliability` 启用时校验模拟键盘输入的结果
+fn apply_command(cmd: InjectCommand, reliability: Option<&InjectionReliability>) -> Result<(), String> {
+    let span = info_span!("inject", trace_id = cmd.trace_id_for_log(), error = field::Empty).entered();
+    let result = execute(cmd, reliability.filter(|reliability| reliability.is_enabled()));
+    if let Err(err) = &result {
+        span.record("error", err.as_str());
+    }
+    result
+}
+
+fn execute(cmd: InjectCommand, reliability: Option<&InjectionReliability>) -> Result<(), String> {
+    // 只读写剪贴板时不需要键盘权限
+    let cmd = match cmd {
+        InjectCommand::CopyText { trace_id, text } => return copy_text(trace_id.as_deref(), text),
+        InjectCommand::ReadClipboard { trace_id, reply } => {
+            let _ = reply.send(read_clipboard(trace_id.as_deref()));
+            return Ok(());
+        }
+        cmd => cmd,
+    };
     let Ok(mut enigo) = Enigo::new(&Settings::default()) else {
         error!(
             target: "input",
             trace_id = cmd.trace_id_for_log(),
             "键盘注入初始化失败 | Keyboard injection failed"
         );
-        return;
+        return Err("键盘注入初始化失败 | Keyboard injection init failed".to_string());
     };
 
     match cmd {
         InjectCommand::TypeText { trace_id, text } => {
             let target_app = get_active_app_name();
             let len = text.chars().count();
+            // 拿不到焦点文字（不支持的控件、没有权限）时不校验
+            let before = reliability.and_then(|_| platform::focused_text());
             match enigo.text(&text) {
                 Ok(()) => {
                     if let Some(tid) = trace_id.as_deref() {
@@ -74,8 +232,46 @@ fn apply_command(cmd: InjectCommand) {
                             "文字注入失败 | Text injection failed"
                         );
                     }
+                    return Err(err.to_string());
                 }
             }
+            if let (Some(reliability), Some(before)) = (reliability, before) {
+                verify_typed(
+                    &mut enigo,
+                    reliability,
+                    trace_id.as_deref(),
+                    target_app.as_deref(),
+                    &before,
+                    &text,
+                )?;
+            }
+        }
+        InjectCommand::PasteText { trace_id, text } => {
+            let len = text.chars().count();
+            match paste_via_clipboard(&mut enigo, &text) {
+                Ok(()) => {
+                    info!(
+                        target: "input",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        len = len,
+                        strategy = "clipboard",
+                        "文字已注入 | Text injected"
+                    );
+                }
+                Err(err) => {
+                    error!(
+                        target: "input",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        error = %err,
+                        strategy = "clipboard",
+                        "文字注入失败 | Text injection failed"
+                    );
+                    return Err(err.to_string());
+                }
+            }
+        }
+        InjectCommand::CopyText { .. } | InjectCommand::ReadClipboard { .. } => {
+            unreachable!("剪贴板命令不经过键盘注入")
         }
         InjectCommand::Backspace { trace_id, count } => {
             if let Some(tid) = trace_id.as_deref() {
@@ -95,10 +291,208 @@ fn apply_command(cmd: InjectCommand) {
                         error = %err,
                         "退格注入失败 | Backspace injection failed"
                     );
-                    break;
+                    return Err(err.to_string());
                 }
             }
         }
+        InjectCommand::SelectBack { trace_id, count } => {
+            debug!(
+                target: "input",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                count = count,
+                "选中注入 | Selection injected"
+            );
+            enigo.key(Key::Shift, Direction::Press).map_err(|err| err.to_string())?;
+            let selected = (0..count).try_for_each(|_| enigo.key(Key::LeftArrow, Direction::Click));
+            enigo.key(Key::Shift, Direction::Release).map_err(|err| err.to_string())?;
+            if let Err(err) = selected {
+                error!(
+                    target: "input",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %err,
+                    "选中注入失败 | Selection injection failed"
+                );
+                return Err(err.to_string());
+            }
+        }
+        InjectCommand::CopySelection { trace_id, reply } => {
+            let copied = copy_selection(&mut enigo).map_err(|err| {
+                error!(
+                    target: "input",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %err,
+                    "复制选中文字失败 | Copying the selection failed"
+                );
+                err.to_string()
+            });
+            let _ = reply.send(copied);
+        }
+        InjectCommand::RestoreKeys { caps_lock, reply } => {
+            let _ = reply.send(restore_keys(&mut enigo, caps_lock).map_err(|err| err.to_string()));
+        }
+        InjectCommand::Newline { trace_id } => {
+            if let Err(err) = enigo.key(Key::Return, Direction::Click) {
+                error!(
+                    target: "input",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %err,
+                    "换行注入失败 | Newline injection failed"
+                );
+                return Err(err.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 松开输入时可能按住的修饰键（注入中途退出时不会留下按住的 Shift/Ctrl）
+fn restore_keys(enigo: &mut Enigo, caps_lock: bool) -> anyhow::Result<()> {
+    for key in [Key::Shift, Key::Control, Key::Alt, Key::Meta] {
+        enigo.key(key, Direction::Release)?;
+    }
+    if caps_lock {
+        enigo.key(Key::CapsLock, Direction::Click)?;
+    }
+    info!(target: "input", caps_lock = caps_lock, "按键状态已恢复 | Key state restored");
+    Ok(())
+}
+
+/// 回读焦点文字核对模拟键盘输入的结果；回读稳定后仍不一致时退格删掉输入的内容，
+/// 改用剪贴板重新输入，结果计入目标应用的可靠度
+fn verify_typed(
+    enigo: &mut Enigo,
+    reliability: &InjectionReliability,
+    trace_id: Option<&str>,
+    target_app: Option<&str>,
+    before: &str,
+    text: &str,
+) -> Result<(), String> {
+    let mut previous: Option<String> = None;
+    let mut settled = false;
+    for _ in 0..READ_BACK_ATTEMPTS {
+        std::thread::sleep(READ_BACK_INTERVAL);
+        let Some(current) = platform::focused_text() else {
+            return Ok(());
+        };
+        settled = reliability::read_back_settled(before, previous.as_deref(), &current, text);
+        previous = Some(current);
+        if settled {
+            break;
+        }
+    }
+    let Some(after) = previous else {
+        return Ok(());
+    };
+    let target_app = target_app.unwrap_or_default();
+    // 一直在变化说明应用还没处理完，不能据此判断，也不重新输入
+    if !settled && after != before {
+        debug!(
+            target: "input",
+            trace_id = trace_id.unwrap_or(""),
+            target_app = target_app,
+            "回读一直未稳定，跳过核对 | Read-back never settled, skipping verification"
+        );
+        return Ok(());
+    }
+    let Insertion::Mismatch { typed } = reliability::check_insertion(before, &after, text) else {
+        reliability.record(target_app, true);
+        return Ok(());
+    };
+    reliability.record(target_app, false);
+    warn!(
+        target: "input",
+        trace_id = trace_id.unwrap_or(""),
+        target_app = target_app,
+        typed = typed,
+        expected = text.chars().count(),
+        "输入结果与发送的文字不符，改用剪贴板重新输入 | Typed text mismatch, retrying via clipboard"
+    );
+    for _ in 0..typed {
+        enigo.key(Key::Backspace, Direction::Click).map_err(|err| err.to_string())?;
+    }
+    paste_via_clipboard(enigo, text).map_err(|err| err.to_string())
+}
+
+fn copy_text(trace_id: Option<&str>, text: String) -> Result<(), String> {
+    let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+    if let Err(err) = copied {
+        error!(
+            target: "input",
+            trace_id = trace_id.unwrap_or(""),
+            error = %err,
+            "写入剪贴板失败 | Clipboard copy failed"
+        );
+        return Err(err.to_string());
+    }
+    debug!(
+        target: "input",
+        trace_id = trace_id.unwrap_or(""),
+        "已复制到剪贴板 | Copied to clipboard"
+    );
+    Ok(())
+}
+
+fn read_clipboard(trace_id: Option<&str>) -> Result<String, String> {
+    match arboard::Clipboard::new() {
+        // 剪贴板为空或内容不是文字时按空串处理
+        Ok(mut clipboard) => Ok(clipboard.get_text().unwrap_or_default()),
+        Err(err) => {
+            error!(
+                target: "input",
+                trace_id = trace_id.unwrap_or(""),
+                error = %err,
+                "读取剪贴板失败 | Clipboard read failed"
+            );
+            Err(err.to_string())
+        }
+    }
+}
+
+fn paste_via_clipboard(enigo: &mut Enigo, text: &str) -> anyhow::Result<()> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    let previous = clipboard.get_text().ok();
+    clipboard.set_text(text.to_string())?;
+
+    press_shortcut(enigo, 'v')?;
+
+    // 目标应用读取剪贴板是异步的，稍等再恢复，避免粘贴出旧内容。
+    std::thread::sleep(Duration::from_millis(150));
+    restore_clipboard(&mut clipboard, previous);
+    Ok(())
+}
+
+fn copy_selection(enigo: &mut Enigo) -> anyhow::Result<String> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    let previous = clipboard.get_text().ok();
+    // 先清空：没有选中内容时复制不生效，不能把旧的剪贴板内容当成选中的文字
+    clipboard.clear()?;
+
+    press_shortcut(enigo, 'c')?;
+    std::thread::sleep(Duration::from_millis(150));
+    let selection = clipboard.get_text().unwrap_or_default();
+    restore_clipboard(&mut clipboard, previous);
+    Ok(selection)
+}
+
+/// Cmd（macOS）或 Ctrl 加一个字母
+fn press_shortcut(enigo: &mut Enigo, key: char) -> anyhow::Result<()> {
+    let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+    enigo.key(modifier, Direction::Press)?;
+    let pressed = enigo.key(Key::Unicode(key), Direction::Click);
+    enigo.key(modifier, Direction::Release)?;
+    pressed?;
+    Ok(())
+}
+
+fn restore_clipboard(clipboard: &mut arboard::Clipboard, previous: Option<String>) {
+    if let Some(previous) = previous {
+        if let Err(err) = clipboard.set_text(previous) {
+            warn!(
+                target: "input",
+                error = %err,
+                "剪贴板恢复失败 | Clipboard restore failed"
+            );
+        }
     }
 }
 
@@ -111,7 +505,28 @@ impl InjectCommand {
     fn trace_id_for_log(&self) -> &str {
         match self {
             InjectCommand::TypeText { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::PasteText { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
             InjectCommand::Backspace { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::SelectBack { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::Newline { trace_id } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::CopySelection { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::ReadClipboard { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::CopyText { trace_id, .. } => trace_id.as_deref().unwrap_or(""),
+            InjectCommand::RestoreKeys { .. } => "",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injection_test_replaces_draft_suffix() {
+        let (steps, expected) = injection_test_steps(InjectionStrategy::Clipboard);
+        assert_eq!(expected, "GhostType 注入测试 OK");
+        assert!(matches!(&steps[0], InjectCommand::PasteText { text, .. } if text == "GhostType 注入测试 abc"));
+        assert!(matches!(steps[1], InjectCommand::Backspace { count: 3, .. }));
+        assert!(matches!(&steps[2], InjectCommand::PasteText { text, .. } if text == "OK"));
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+use crate::platform::{self, FocusedField};
+
+/// 占位文字或标签中出现这些词时按对应种类处理（小写比较）
+const EMAIL_WORDS: &[&str] = &["email", "e-mail", "邮箱", "邮件地址", "电子邮件"];
+const URL_WORDS: &[&str] = &["url", "http", "website", "网址", "链接地址"];
+const SEARCH_WORDS: &[&str] = &["search", "搜索", "查找"];
+
+/// 焦点输入控件的种类，服务端与 LLM 据此调整输出（邮箱地址不加标点、聊天框保留口语等）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    /// 单行文本框，或无法判断
+    #[default]
+    Text,
+    /// 多行文本（聊天框、文档、代码编辑器）
+    MultiLine,
+    Search,
+    Email,
+    Url,
+    Password,
+}
+
+/// 随 ASR 上下文发出的焦点输入控件信息
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InputField {
+    #[serde(default)]
+    pub kind: FieldKind,
+    /// 平台原始角色，如 `AXTextField`、`AXTextArea`、`Edit`
+    #[serde(default)]
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl InputField {
+    /// 读取当前焦点控件；拿不到（无权限、平台不支持）时返回 `None`
+    pub fn focused() -> Option<Self> {
+        platform::focused_field().map(Self::classify)
+    }
+
+    /// 按平台角色、样式与提示文字归类：密码框优先，其次是提示文字中的邮箱、网址、搜索
+    pub fn classify(field: FocusedField) -> Self {
+        let hints = [field.placeholder.as_deref(), field.label.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let mentions = |words: &[&str]| hints.iter().any(|hint| words.iter().any(|word| hint.contains(word)));
+        let kind = if field.password {
+            FieldKind::Password
+        } else if mentions(EMAIL_WORDS) {
+            FieldKind::Email
+        } else if mentions(URL_WORDS) {
+            FieldKind::Url
+        } else if field.search || mentions(SEARCH_WORDS) {
+            FieldKind::Search
+        } else if field.multi_line {
+            FieldKind::MultiLine
+        } else {
+            FieldKind::Text
+        };
+        Self {
+            kind,
+            role: field.role,
+            placeholder: field.placeholder,
+            label: field.label,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_classified_from_role_and_hints() {
+        let field = |role: &str, placeholder: Option<&str>| FocusedField {
+            role: role.to_string(),
+            placeholder: placeholder.map(str::to_string),
+            ..FocusedField::default()
+        };
+        let kind = |field: FocusedField| InputField::classify(field).kind;
+
+        assert_eq!(kind(field("AXTextField", Some("Email address"))), FieldKind::Email);
+        assert_eq!(kind(field("Edit", Some("请输入邮箱"))), FieldKind::Email);
+        assert_eq!(kind(field("AXTextField", Some("https://"))), FieldKind::Url);
+        assert_eq!(kind(field("AXTextField", Some("Search mail"))), FieldKind::Search);
+        assert_eq!(kind(field("AXTextField", None)), FieldKind::Text);
+        let search = FocusedField {
+            search: true,
+            ..field("AXTextField/AXSearchField", None)
+        };
+        assert_eq!(kind(search), FieldKind::Search);
+        let chat = FocusedField {
+            multi_line: true,
+            label: Some("Message #general".to_string()),
+            ..field("AXTextArea", None)
+        };
+        let chat = InputField::classify(chat);
+        assert_eq!(chat.kind, FieldKind::MultiLine);
+        assert_eq!(chat.label.as_deref(), Some("Message #general"));
+        // 密码框即使提示文字提到邮箱也按密码处理
+        let password = FocusedField {
+            password: true,
+            ..field("AXTextField/AXSecureTextField", Some("Email password"))
+        };
+        assert_eq!(kind(password), FieldKind::Password);
+
+        let value = serde_json::to_value(InputField::classify(field("Edit", None))).expect("serialize");
+        assert_eq!(value, serde_json::json!({ "kind": "text", "role": "Edit" }));
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+
+/// 可互换的半角、全角标点
+pub const PUNCTUATION_PAIRS: &[(char, char)] = &[
+    (',', '，'),
+    ('.', '。'),
+    ('?', '？'),
+    ('!', '！'),
+    (';', '；'),
+    (':', '：'),
+];
+
+/// 识别结果的语言，决定 LLM 校正的提示词以及标点、空格的整理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese,
+    English,
+    /// 中英混说
+    Mixed,
+}
+
+impl Language {
+    /// ASR 返回或会话指定的语言代码（"zh"、"en-US"、"yue" 等）；"auto" 等无法对应时返回 `None`
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        if code == "mixed" {
+            return Some(Language::Mixed);
+        }
+        match code.split(['-', '_']).next().unwrap_or_default() {
+            "zh" | "cmn" | "yue" | "wuu" => Some(Language::Chinese),
+            "en" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::Chinese => "zh",
+            Language::English => "en",
+            Language::Mixed => "mixed",
+        }
+    }
+}
+
+/// 按文字判断语言：统计汉字（含假名、谚文）与英文单词；中文里夹几个英文词（或反过来）仍按主要语言处理
+pub fn detect(text: &str) -> Option<Language> {
+    let cjk = text.chars().filter(|c| is_cjk_letter(*c)).count();
+    let latin = text
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .count();
+    match (cjk, latin) {
+        (0, 0) => None,
+        (_, 0) => Some(Language::Chinese),
+        (0, _) => Some(Language::English),
+        (cjk, latin) if cjk >= latin * 3 => Some(Language::Chinese),
+        (cjk, latin) if latin >= cjk * 2 => Some(Language::English),
+        _ => Some(Language::Mixed),
+    }
+}
+
+/// 多语言：按每次识别结果的语言选择 LLM 提示词，并统一标点宽度与中英文间距
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct LanguageConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 中文与英文、数字之间加空格
+    #[serde(default = "default_enabled")]
+    pub cjk_latin_spacing: bool,
+    /// 中文用全角标点、英文用半角标点；中英混说时跟随标点前的文字
+    #[serde(default = "default_enabled")]
+    pub punctuation_width: bool,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cjk_latin_spacing: true,
+            punctuation_width: true,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl LanguageConfig {
+    pub fn polish(&self, text: &str, language: Language) -> String {
+        let mut text = text.to_string();
+        if self.punctuation_width {
+            text = normalize_punctuation(&text, language);
+        }
+        if self.cjk_latin_spacing {
+            text = space_cjk_latin(&text);
+        }
+        text
+    }
+}
+
+fn normalize_punctuation(text: &str, language: Language) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut drop_space = false;
+    for (index, &c) in chars.iter().enumerate() {
+        if drop_space && c == ' ' {
+            continue;
+        }
+        drop_space = false;
+        let Some(&(half, full)) = PUNCTUATION_PAIRS.iter().find(|(half, full)| c == *half || c == *full) else {
+            out.push(c);
+            continue;
+        };
+        let previous = out.chars().next_back();
+        let next = chars.get(index + 1).copied();
+        // 3.14、a.b、10:30 这类词内的半角符号保持原样
+        let in_token =
+            previous.is_some_and(|p| p.is_ascii_alphanumeric()) && next.is_some_and(|n| n.is_ascii_alphanumeric());
+        if c == half && in_token {
+            out.push(c);
+            continue;
+        }
+        let wide = match language {
+            Language::Chinese => true,
+            Language::English => false,
+            Language::Mixed => match previous {
+                Some(p) if is_cjk_letter(p) => true,
+                Some(p) if p.is_ascii_alphanumeric() => false,
+                _ => c == full,
+            },
+        };
+        if wide {
+            // 全角标点前后不留空格
+            while out.ends_with(' ') {
+                out.pop();
+            }
+            out.push(full);
+            drop_space = true;
+        } else {
+            out.push(half);
+            if c == full && next.is_some_and(char::is_alphanumeric) {
+                out.push(' ');
+            }
+        }
+    }
+    out
+}
+
+pub fn space_cjk_latin(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        if let Some(p) = previous {
+            let boundary =
+                (is_cjk_letter(p) && c.is_ascii_alphanumeric()) || (p.is_ascii_alphanumeric() && is_cjk_letter(c));
+            if boundary {
+                out.push(' ');
+            }
+        }
+        out.push(c);
+        previous = Some(c);
+    }
+    out
+}
+
+/// 汉字、假名、谚文（不含全角标点）
+pub fn is_cjk_letter(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_detection_drives_punctuation_and_spacing() {
+        assert_eq!(detect("我用GitHub提交代码"), Some(Language::Chinese));
+        assert_eq!(detect("Hello world, how are you"), Some(Language::English));
+        assert_eq!(detect("Please 帮我 review 一下 this PR"), Some(Language::Mixed));
+        assert_eq!(detect("123。"), None);
+        assert_eq!(Language::from_code("en-US"), Some(Language::English));
+        assert_eq!(Language::from_code("yue"), Some(Language::Chinese));
+        assert_eq!(Language::from_code("auto"), None);
+
+        let config = LanguageConfig {
+            enabled: true,
+            ..LanguageConfig::default()
+        };
+        assert_eq!(config.polish("我用GitHub, 版本3.14.", Language::Chinese), "我用 GitHub，版本 3.14。");
+        assert_eq!(config.polish("Hello，world。It is 10:30！", Language::English), "Hello, world. It is 10:30!");
+        assert_eq!(config.polish("好的, thanks. 明天见.", Language::Mixed), "好的，thanks. 明天见。");
+
+        let width_only = LanguageConfig {
+            cjk_latin_spacing: false,
+            ..config
+        };
+        assert_eq!(width_only.polish("我用GitHub.", Language::Chinese), "我用GitHub。");
+    }
+}
@@ -3,12 +3,102 @@ mod openai_compat;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use crate::input_field::FieldKind;
+use crate::language::Language;
+
+/// 携带会话 trace_id 的请求头
+const TRACE_HEADER: &str = "X-Trace-Id";
 
 pub fn default_timeout_ms() -> u64 {
     3000
 }
 
+/// 改写选中文字时的最短超时：输入可能很长，校正用的超时通常不够
+const REWRITE_MIN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 改写时使用的系统提示词
+const REWRITE_PROMPT: &str = "你是文本改写助手。按照用户的语音指令改写给出的原文，保持原文的语言，\
+除非指令要求翻译。只输出改写后的文本，不要解释，不要加引号。";
+
+/// 校正提示词：中文（未检测语言时也用这一条）
+const SYSTEM_PROMPT: &str = "你是中文文本校正助手。修正语音识别文本的错别字和语法错误，保持原意。只输出修正后的文本，无需解释。若无需修正则原样输出。";
+
+const ENGLISH_PROMPT: &str = "You are an English text correction assistant. Fix misrecognized words, spelling and \
+grammar in the speech recognition text while keeping its meaning. Output only the corrected text without any \
+explanation. If nothing needs fixing, output the text unchanged.";
+
+const MIXED_PROMPT: &str = "你是中英混合文本校正助手。修正语音识别文本的错别字和语法错误，保持原意。\
+中文和英文部分各自保持原来的语言，不要翻译，英文单词和术语使用正确的拼写与大小写。\
+只输出修正后的文本，无需解释。若无需修正则原样输出。";
+
+/// 代码听写时的校正提示词：只修正识别错的标识符和关键字，不加标点、不改写成句子
+const CODE_PROMPT: &str = "你是代码听写校正助手。输入是语音识别出的代码片段、命令或标识符。\
+只修正识别错误的标识符、关键字和命令名，保留原有的符号和大小写，不要添加标点，不要改写成自然语言，\
+不要用代码块包裹。只输出修正后的文本，无需解释。若无需修正则原样输出。";
+
+/// 校正的文本类型，决定使用的提示词
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CorrectionStyle {
+    #[default]
+    Prose,
+    Code,
+}
+
+/// 校正时的附加信息，决定使用的提示词
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorrectionHints {
+    pub style: CorrectionStyle,
+    /// 检测到的语言；为空时按中文校正
+    pub language: Option<Language>,
+    /// 个人词典中用户偏好的写法
+    pub glossary: Vec<String>,
+    /// 焦点输入框的种类；邮箱、网址与搜索框按对应的格式输出
+    pub field: Option<FieldKind>,
+    /// 会话的 trace_id，以 `X-Trace-Id` 请求头发给 LLM 服务，便于对照两边的日志
+    pub trace_id: Option<String>,
+}
+
+impl CorrectionHints {
+    fn prompt(&self) -> Cow<'static, str> {
+        let prompt = match (self.style, self.language) {
+            (CorrectionStyle::Code, _) => CODE_PROMPT,
+            (CorrectionStyle::Prose, Some(Language::English)) => ENGLISH_PROMPT,
+            (CorrectionStyle::Prose, Some(Language::Mixed)) => MIXED_PROMPT,
+            (CorrectionStyle::Prose, Some(Language::Chinese) | None) => SYSTEM_PROMPT,
+        };
+        let field = self.field.and_then(field_prompt);
+        if self.glossary.is_empty() && field.is_none() {
+            return Cow::Borrowed(prompt);
+        }
+        let mut prompt = prompt.to_string();
+        if let Some(field) = field {
+            prompt.push('\n');
+            prompt.push_str(field);
+        }
+        if !self.glossary.is_empty() {
+            prompt.push_str("\n用户常用的写法（文本中出现相近的说法时优先改成这些写法）：");
+            prompt.push_str(&self.glossary.join("、"));
+        }
+        Cow::Owned(prompt)
+    }
+}
+
+/// 输入框种类对输出格式的要求；普通文本框不加要求
+fn field_prompt(field: FieldKind) -> Option<&'static str> {
+    match field {
+        FieldKind::Email => Some(
+            "文本要填入邮箱地址栏：输出为邮箱地址（如 name@example.com），\
+把说出的\"at\"\"艾特\"\"点\"换成 @ 和 .，不要空格和句末标点。",
+        ),
+        FieldKind::Url => Some("文本要填入网址栏：输出为网址，把说出的\"点\"\"斜杠\"换成符号，不要空格和句末标点。"),
+        FieldKind::Search => Some("文本要填入搜索框：输出简短的搜索词，不要句末标点。"),
+        FieldKind::Text | FieldKind::MultiLine | FieldKind::Password => None,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CorrectionResult {
     pub original: String,
@@ -19,11 +109,13 @@ pub struct CorrectionResult {
 
 #[async_trait]
 pub trait LlmEngine: Send + Sync {
-    async fn correct(&self, text: &str) -> anyhow::Result<CorrectionResult>;
+    async fn correct(&self, text: &str, hints: CorrectionHints) -> anyhow::Result<CorrectionResult>;
+    /// 按语音指令（例如"改得正式一些"）改写一段文字
+    async fn rewrite(&self, instruction: &str, text: &str) -> anyhow::Result<CorrectionResult>;
     async fn health_check(&self) -> bool;
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum LlmConfig {
     /// 禁用 LLM 校正（只输出 ASR）
@@ -52,6 +144,97 @@ impl Default for LlmConfig {
     }
 }
 
+/// 按 ASR 置信度决定是否校正：高置信度的短句直接输出，低置信度的总是校正。
+/// 只在 ASR 后端提供置信度时生效。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CorrectionGate {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 置信度不低于此值的短句跳过校正
+    #[serde(default = "default_skip_above")]
+    pub skip_above: f32,
+    /// 不超过这个字数才算短句
+    #[serde(default = "default_skip_max_chars")]
+    pub skip_max_chars: usize,
+    /// 置信度低于此值时总是校正，即使当前应用关闭了校正
+    #[serde(default = "default_force_below")]
+    pub force_below: f32,
+}
+
+impl Default for CorrectionGate {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            skip_above: default_skip_above(),
+            skip_max_chars: default_skip_max_chars(),
+            force_below: default_force_below(),
+        }
+    }
+}
+
+fn default_skip_above() -> f32 {
+    0.9
+}
+
+fn default_skip_max_chars() -> usize {
+    20
+}
+
+fn default_force_below() -> f32 {
+    0.6
+}
+
+/// 校正结果替换已输入文字的时机
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CorrectionTiming {
+    /// 识别结果输入后至少等这么久再替换，避免文字刚出现就跳动
+    #[serde(default = "default_min_delay_ms")]
+    pub min_delay_ms: u64,
+    /// 输入后超过这么久才就绪的校正直接丢弃（用户可能已经接着输入了）；0 表示不限
+    #[serde(default = "default_replace_window_ms")]
+    pub replace_window_ms: u64,
+}
+
+impl Default for CorrectionTiming {
+    fn default() -> Self {
+        Self {
+            min_delay_ms: default_min_delay_ms(),
+            replace_window_ms: default_replace_window_ms(),
+        }
+    }
+}
+
+fn default_min_delay_ms() -> u64 {
+    500
+}
+
+fn default_replace_window_ms() -> u64 {
+    10_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateDecision {
+    /// 按应用设置决定
+    Default,
+    Skip,
+    Force,
+}
+
+impl CorrectionGate {
+    pub fn decide(&self, confidence: Option<f32>, text: &str) -> GateDecision {
+        let Some(confidence) = confidence.filter(|c| self.enabled && c.is_finite()) else {
+            return GateDecision::Default;
+        };
+        if confidence < self.force_below {
+            GateDecision::Force
+        } else if confidence >= self.skip_above && text.chars().count() <= self.skip_max_chars {
+            GateDecision::Skip
+        } else {
+            GateDecision::Default
+        }
+    }
+}
+
 pub fn create_engine(config: &LlmConfig) -> anyhow::Result<Box<dyn LlmEngine>> {
     match config {
         LlmConfig::Disabled => Ok(Box::new(DisabledEngine)),
@@ -82,7 +265,7 @@ struct DisabledEngine;
 
 #[async_trait]
 impl LlmEngine for DisabledEngine {
-    async fn correct(&self, text: &str) -> anyhow::Result<CorrectionResult> {
+    async fn correct(&self, text: &str, _hints: CorrectionHints) -> anyhow::Result<CorrectionResult> {
         Ok(CorrectionResult {
             original: text.to_string(),
             corrected: text.to_string(),
@@ -91,19 +274,77 @@ impl LlmEngine for DisabledEngine {
         })
     }
 
+    async fn rewrite(&self, _instruction: &str, _text: &str) -> anyhow::Result<CorrectionResult> {
+        anyhow::bail!("未配置 LLM，无法改写选中的文字")
+    }
+
     async fn health_check(&self) -> bool {
         true
     }
 }
 
+/// 有 trace_id 时加上 `X-Trace-Id` 请求头
+fn with_trace_id(request: reqwest::RequestBuilder, trace_id: Option<&str>) -> reqwest::RequestBuilder {
+    match trace_id.map(str::trim).filter(|trace_id| !trace_id.is_empty()) {
+        Some(trace_id) => request.header(TRACE_HEADER, trace_id),
+        None => request,
+    }
+}
+
 fn elapsed_ms(started: Instant) -> u64 {
     started.elapsed().as_millis() as u64
 }
 
+fn rewrite_timeout(timeout: Duration) -> Duration {
+    timeout.max(REWRITE_MIN_TIMEOUT)
+}
+
+/// 改写请求的用户消息：指令在前，原文放在标记之间，避免原文里的内容被当成指令
+fn rewrite_message(instruction: &str, text: &str) -> String {
+    format!("指令：{}\n\n原文：\n<<<\n{text}\n>>>", instruction.trim())
+}
+
+/// 模型偶尔会原样带上原文的标记，去掉它们
+fn strip_rewrite_markers(output: &str) -> &str {
+    let output = output.trim();
+    output
+        .strip_prefix("<<<")
+        .and_then(|rest| rest.strip_suffix(">>>"))
+        .map(str::trim)
+        .unwrap_or(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn rewrite_output_drops_echoed_markers() {
+        assert_eq!(strip_rewrite_markers("<<<\nDear team,\n>>>"), "Dear team,");
+        assert_eq!(strip_rewrite_markers("  Dear team, >>> ok "), "Dear team, >>> ok");
+        assert!(rewrite_message(" make this formal ", "hi").starts_with("指令：make this formal\n"));
+    }
+
+    #[test]
+    fn prompt_mentions_field_format_and_glossary() {
+        let hints = CorrectionHints::default();
+        assert_eq!(hints.prompt(), SYSTEM_PROMPT);
+        let hints = CorrectionHints {
+            field: Some(FieldKind::Email),
+            glossary: vec!["GhostType".to_string()],
+            ..CorrectionHints::default()
+        };
+        let prompt = hints.prompt();
+        assert!(prompt.starts_with(SYSTEM_PROMPT));
+        assert!(prompt.contains("邮箱地址栏"));
+        assert!(prompt.ends_with("GhostType"));
+        let hints = CorrectionHints {
+            field: Some(FieldKind::MultiLine),
+            ..CorrectionHints::default()
+        };
+        assert_eq!(hints.prompt(), SYSTEM_PROMPT);
+    }
+
     #[test]
     fn llm_config_serializes_openai_compat_tag() {
         let cfg = LlmConfig::OpenAiCompat {
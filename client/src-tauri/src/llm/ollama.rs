@@ -4,7 +4,12 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
-use crate::llm::{elapsed_ms, CorrectionResult, LlmEngine};
+use crate::llm::{
+    elapsed_ms, rewrite_message, rewrite_timeout, strip_rewrite_markers, with_trace_id, CorrectionHints,
+    CorrectionResult, LlmEngine, REWRITE_PROMPT,
+};
+use crate::privacy;
+use crate::proxy;
 
 pub struct OllamaEngine {
     client: Client,
@@ -34,7 +39,7 @@ struct VersionResponse {
 
 #[async_trait]
 impl LlmEngine for OllamaEngine {
-    async fn correct(&self, text: &str) -> anyhow::Result<CorrectionResult> {
+    async fn correct(&self, text: &str, hints: CorrectionHints) -> anyhow::Result<CorrectionResult> {
         let started = Instant::now();
         let input = text.trim();
         if input.is_empty() {
@@ -46,31 +51,8 @@ impl LlmEngine for OllamaEngine {
             });
         }
 
-        let url = format!("{}/api/generate", self.endpoint.trim_end_matches('/'));
-        let prompt = format!("{SYSTEM_PROMPT}\n\n{input}");
-        let request = GenerateRequest {
-            model: self.model.clone(),
-            prompt,
-            stream: false,
-        };
-
-        let resp = self
-            .client
-            .post(url)
-            .json(&request)
-            .timeout(self.timeout)
-            .send()
-            .await
-            .context("send ollama request")?;
-
-        let status = resp.status();
-        let body = resp.text().await.context("read ollama response")?;
-        if !status.is_success() {
-            anyhow::bail!("ollama http error: status={status} body={body}");
-        }
-
-        let parsed = serde_json::from_str::<GenerateResponse>(&body).context("parse ollama json")?;
-        let corrected = parsed.response.trim().to_string();
+        let prompt = format!("{}\n\n{input}", hints.prompt());
+        let corrected = self.generate(prompt, self.timeout, hints.trace_id.as_deref()).await?;
         let corrected = if corrected.is_empty() { input.to_string() } else { corrected };
 
         Ok(CorrectionResult {
@@ -81,6 +63,23 @@ impl LlmEngine for OllamaEngine {
         })
     }
 
+    async fn rewrite(&self, instruction: &str, text: &str) -> anyhow::Result<CorrectionResult> {
+        let started = Instant::now();
+        let prompt = format!("{REWRITE_PROMPT}\n\n{}", rewrite_message(instruction, text));
+        let output = self.generate(prompt, rewrite_timeout(self.timeout), None).await?;
+        let rewritten = strip_rewrite_markers(&output).to_string();
+        if rewritten.is_empty() {
+            anyhow::bail!("LLM 没有返回改写结果");
+        }
+
+        Ok(CorrectionResult {
+            original: text.to_string(),
+            changed: rewritten != text,
+            corrected: rewritten,
+            latency_ms: elapsed_ms(started),
+        })
+    }
+
     async fn health_check(&self) -> bool {
         let url = format!("{}/api/version", self.endpoint.trim_end_matches('/'));
         let resp = self.client.get(url).timeout(self.timeout).send().await;
@@ -98,6 +97,32 @@ impl LlmEngine for OllamaEngine {
 }
 
 impl OllamaEngine {
+    /// 非流式生成，返回去掉首尾空白的回复（可能为空）
+    async fn generate(&self, prompt: String, timeout: Duration, trace_id: Option<&str>) -> anyhow::Result<String> {
+        let url = format!("{}/api/generate", self.endpoint.trim_end_matches('/'));
+        let request = GenerateRequest {
+            model: self.model.clone(),
+            prompt,
+            stream: false,
+        };
+
+        let resp = with_trace_id(self.client.post(url), trace_id)
+            .json(&request)
+            .timeout(timeout)
+            .send()
+            .await
+            .context("send ollama request")?;
+
+        let status = resp.status();
+        let body = resp.text().await.context("read ollama response")?;
+        if !status.is_success() {
+            anyhow::bail!("ollama http error: status={status} body={}", privacy::redact(&body));
+        }
+
+        let parsed = serde_json::from_str::<GenerateResponse>(&body).context("parse ollama json")?;
+        Ok(parsed.response.trim().to_string())
+    }
+
     pub fn new(endpoint: String, model: String, timeout_ms: u64) -> anyhow::Result<Self> {
         let endpoint = endpoint.trim().trim_end_matches('/').to_string();
         if endpoint.is_empty() {
@@ -109,7 +134,7 @@ impl OllamaEngine {
             anyhow::bail!("LLM model 不能为空");
         }
 
-        let client = Client::builder().build().context("build reqwest client")?;
+        let client = proxy::http_client_builder().build().context("build reqwest client")?;
         Ok(Self {
             client,
             endpoint,
@@ -119,8 +144,6 @@ impl OllamaEngine {
     }
 }
 
-const SYSTEM_PROMPT: &str = "你是中文文本校正助手。修正语音识别文本的错别字和语法错误，保持原意。只输出修正后的文本，无需解释。若无需修正则原样输出。";
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,7 +5,12 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
-use crate::llm::{elapsed_ms, CorrectionResult, LlmEngine};
+use crate::llm::{
+    elapsed_ms, rewrite_message, rewrite_timeout, strip_rewrite_markers, with_trace_id, CorrectionHints,
+    CorrectionResult, LlmEngine, REWRITE_PROMPT,
+};
+use crate::privacy;
+use crate::proxy;
 
 pub struct OpenAiCompatEngine {
     client: Client,
@@ -46,7 +51,7 @@ struct MessageContent {
 
 #[async_trait]
 impl LlmEngine for OpenAiCompatEngine {
-    async fn correct(&self, text: &str) -> anyhow::Result<CorrectionResult> {
+    async fn correct(&self, text: &str, hints: CorrectionHints) -> anyhow::Result<CorrectionResult> {
         let started = Instant::now();
         let input = text.trim();
         if input.is_empty() {
@@ -58,28 +63,91 @@ impl LlmEngine for OpenAiCompatEngine {
             });
         }
 
+        let corrected = self
+            .chat(
+                &hints.prompt(),
+                input.to_string(),
+                200,
+                self.timeout,
+                hints.trace_id.as_deref(),
+            )
+            .await?;
+        let corrected = if corrected.is_empty() { input.to_string() } else { corrected };
+
+        Ok(CorrectionResult {
+            original: input.to_string(),
+            changed: corrected != input,
+            corrected,
+            latency_ms: elapsed_ms(started),
+        })
+    }
+
+    async fn rewrite(&self, instruction: &str, text: &str) -> anyhow::Result<CorrectionResult> {
+        let started = Instant::now();
+        let output = self
+            .chat(
+                REWRITE_PROMPT,
+                rewrite_message(instruction, text),
+                REWRITE_MAX_TOKENS,
+                rewrite_timeout(self.timeout),
+                None,
+            )
+            .await?;
+        let rewritten = strip_rewrite_markers(&output).to_string();
+        if rewritten.is_empty() {
+            anyhow::bail!("LLM 没有返回改写结果");
+        }
+
+        Ok(CorrectionResult {
+            original: text.to_string(),
+            changed: rewritten != text,
+            corrected: rewritten,
+            latency_ms: elapsed_ms(started),
+        })
+    }
+
+    async fn health_check(&self) -> bool {
+        let url = format!("{}/models", self.endpoint.trim_end_matches('/'));
+        let resp = self
+            .client
+            .get(url)
+            .timeout(self.timeout)
+            .send()
+            .await;
+        resp.map(|r| r.status().is_success()).unwrap_or(false)
+    }
+}
+
+impl OpenAiCompatEngine {
+    /// 发送一轮对话，返回去掉首尾空白的回复（可能为空）
+    async fn chat(
+        &self,
+        system: &str,
+        user: String,
+        max_tokens: u32,
+        timeout: Duration,
+        trace_id: Option<&str>,
+    ) -> anyhow::Result<String> {
         let url = format!("{}/chat/completions", self.endpoint.trim_end_matches('/'));
         let request = ChatRequest {
             model: self.model.clone(),
             messages: vec![
                 Message {
                     role: "system".to_string(),
-                    content: SYSTEM_PROMPT.to_string(),
+                    content: system.to_string(),
                 },
                 Message {
                     role: "user".to_string(),
-                    content: input.to_string(),
+                    content: user,
                 },
             ],
             temperature: 0.1,
-            max_tokens: 200,
+            max_tokens,
         };
 
-        let resp = self
-            .client
-            .post(url)
+        let resp = with_trace_id(self.client.post(url), trace_id)
             .json(&request)
-            .timeout(self.timeout)
+            .timeout(timeout)
             .send()
             .await
             .context("send openai compat request")?;
@@ -87,38 +155,17 @@ impl LlmEngine for OpenAiCompatEngine {
         let status = resp.status();
         let body = resp.text().await.context("read openai compat response")?;
         if !status.is_success() {
-            anyhow::bail!("openai compat http error: status={status} body={body}");
+            anyhow::bail!("openai compat http error: status={status} body={}", privacy::redact(&body));
         }
 
         let parsed = serde_json::from_str::<ChatResponse>(&body).context("parse openai compat json")?;
-        let corrected = parsed
+        Ok(parsed
             .choices
             .first()
             .map(|c| c.message.content.trim().to_string())
-            .filter(|v| !v.is_empty())
-            .unwrap_or_else(|| input.to_string());
-
-        Ok(CorrectionResult {
-            original: input.to_string(),
-            changed: corrected != input,
-            corrected,
-            latency_ms: elapsed_ms(started),
-        })
+            .unwrap_or_default())
     }
 
-    async fn health_check(&self) -> bool {
-        let url = format!("{}/models", self.endpoint.trim_end_matches('/'));
-        let resp = self
-            .client
-            .get(url)
-            .timeout(self.timeout)
-            .send()
-            .await;
-        resp.map(|r| r.status().is_success()).unwrap_or(false)
-    }
-}
-
-impl OpenAiCompatEngine {
     pub fn new(endpoint: String, api_key: String, model: String, timeout_ms: u64) -> anyhow::Result<Self> {
         let endpoint = endpoint.trim().trim_end_matches('/').to_string();
         if endpoint.is_empty() {
@@ -139,7 +186,7 @@ impl OpenAiCompatEngine {
         let value = HeaderValue::from_str(&format!("Bearer {api_key}")).context("invalid api key header")?;
         headers.insert(AUTHORIZATION, value);
 
-        let client = Client::builder()
+        let client = proxy::http_client_builder()
             .default_headers(headers)
             .build()
             .context("build reqwest client")?;
@@ -154,7 +201,8 @@ impl OpenAiCompatEngine {
     }
 }
 
-const SYSTEM_PROMPT: &str = "你是中文文本校正助手。修正语音识别文本的错别字和语法错误，保持原意。只输出修正后的文本，无需解释。若无需修正则原样输出。";
+/// 改写的输出可能比校正长得多
+const REWRITE_MAX_TOKENS: u32 = 2048;
 
 #[cfg(test)]
 mod tests {
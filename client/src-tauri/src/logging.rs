@@ -1,21 +1,77 @@
+use std::collections::VecDeque;
 use std::fmt;
 use std::fs::OpenOptions;
+use std::io::{Read as _, Seek as _, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
 
+use tokio::sync::broadcast;
 use tracing::{Event, Level, Subscriber};
 use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
 use tracing_subscriber::fmt::FmtContext;
 use tracing_subscriber::fmt::writer::MakeWriter;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::{telemetry, timeline};
+
+/// 内存中保留的最近日志行数（未开启日志文件时供应用内查看）
+const MEMORY_LOG_LINES: usize = 2000;
+
+/// 日志文件的轮转与保留策略，来自环境变量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RotationPolicy {
+    /// 启动时日志文件超过这个大小就轮转（`GHOSTTYPE_LOG_MAX_MB`）
+    max_bytes: u64,
+    /// 最多保留的轮转文件数，0 为不限（`GHOSTTYPE_LOG_MAX_FILES`）
+    max_files: usize,
+    /// 轮转文件的保留天数，0 为不限（`GHOSTTYPE_LOG_RETENTION_DAYS`）
+    retention_days: u64,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 5 * 1024 * 1024,
+            max_files: 10,
+            retention_days: 30,
+        }
+    }
+}
+
+impl RotationPolicy {
+    fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_bytes: env_number("GHOSTTYPE_LOG_MAX_MB")
+                .filter(|mb| *mb > 0)
+                .map_or(default.max_bytes, |mb| mb.saturating_mul(1024 * 1024)),
+            max_files: env_number("GHOSTTYPE_LOG_MAX_FILES").map_or(default.max_files, |n| n as usize),
+            retention_days: env_number("GHOSTTYPE_LOG_RETENTION_DAYS").unwrap_or(default.retention_days),
+        }
+    }
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    let raw = std::env::var(name).ok()?;
+    match raw.trim().parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("[logging] {name} 不是有效的数字，使用默认值: {raw}");
+            None
+        }
+    }
+}
 
 pub fn init() {
     let filter = env_filter();
+    let format = GhostTypeFormat { json: wants_json() };
     let fmt_stderr = tracing_subscriber::fmt::layer()
-        .event_format(GhostTypeFormat)
-        .with_writer(std::io::stderr);
+        .event_format(format)
+        .with_writer(std::io::stderr)
+        .and_then(memory_layer());
 
     let wants_file = match std::env::var("GHOSTTYPE_LOG_FILE") {
         Ok(v) => !v.trim().is_empty(),
@@ -26,24 +82,219 @@ pub fn init() {
         match build_file_writer() {
             Ok(writer) => {
                 let fmt_file = tracing_subscriber::fmt::layer()
-                    .event_format(GhostTypeFormat)
+                    .event_format(format)
                     .with_writer(writer);
 
                 let _ = tracing_subscriber::registry()
                     .with(filter)
                     .with(fmt_stderr)
                     .with(fmt_file)
+                    .with(telemetry::layer())
+                    .with(timeline::layer())
                     .try_init();
             }
             Err(err) => {
                 eprintln!("[logging] 日志文件初始化失败，回退 stderr: {err}");
-                let _ = tracing_subscriber::registry().with(filter).with(fmt_stderr).try_init();
+                let _ = tracing_subscriber::registry()
+                    .with(filter)
+                    .with(fmt_stderr)
+                    .with(telemetry::layer())
+                    .with(timeline::layer())
+                    .try_init();
             }
         }
         return;
     }
 
-    let _ = tracing_subscriber::registry().with(filter).with(fmt_stderr).try_init();
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_stderr)
+        .with(telemetry::layer())
+        .with(timeline::layer())
+        .try_init();
+}
+
+fn memory_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // 应用内查看的日志始终是文本格式
+    tracing_subscriber::fmt::layer()
+        .event_format(GhostTypeFormat { json: false })
+        .with_ansi(false)
+        .with_writer(MemoryWriter)
+}
+
+/// 当前日志文件路径（仅在设置 `GHOSTTYPE_LOG_FILE` 后存在）
+pub fn log_file_path() -> Option<PathBuf> {
+    LOG_FILE_PATH.get().cloned()
+}
+
+/// 最近的日志行：优先读日志文件末尾（JSON 格式时除外），否则取内存缓冲
+pub fn recent_lines(limit: usize) -> Vec<String> {
+    if let Some(path) = log_file_path().filter(|_| !wants_json()) {
+        match read_file_tail(&path, limit) {
+            Ok(lines) => return lines,
+            Err(err) => eprintln!("[logging] 读取日志文件失败: {err}"),
+        }
+    }
+
+    let lines = log_buffer().lines.lock().expect("log buffer lock");
+    let skip = lines.len().saturating_sub(limit);
+    lines.iter().skip(skip).cloned().collect()
+}
+
+/// 崩溃报告用的 `recent_lines`：内存缓冲正被占用（可能就是崩溃的线程）时不等待，返回空
+pub fn try_recent_lines(limit: usize) -> Vec<String> {
+    if let Some(path) = log_file_path().filter(|_| !wants_json()) {
+        if let Ok(lines) = read_file_tail(&path, limit) {
+            return lines;
+        }
+    }
+
+    let Ok(lines) = log_buffer().lines.try_lock() else {
+        return Vec::new();
+    };
+    let skip = lines.len().saturating_sub(limit);
+    lines.iter().skip(skip).cloned().collect()
+}
+
+/// 订阅新产生的日志行（用于实时 tail）
+pub fn subscribe() -> broadcast::Receiver<String> {
+    log_buffer().tx.subscribe()
+}
+
+/// 把新日志行以 `log-line` 事件推送给设置窗口
+pub struct LogTail {
+    task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl LogTail {
+    pub fn new() -> Self {
+        Self { task: Mutex::new(None) }
+    }
+
+    pub fn start(&self, app: tauri::AppHandle) {
+        use tauri::Emitter as _;
+
+        let mut task = self.task.lock().expect("log tail lock");
+        if task.is_some() {
+            return;
+        }
+        let mut rx = subscribe();
+        *task = Some(tauri::async_runtime::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(line) => {
+                        let _ = app.emit("log-line", line);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    pub fn stop(&self) {
+        if let Some(task) = self.task.lock().expect("log tail lock").take() {
+            task.abort();
+        }
+    }
+}
+
+impl Default for LogTail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+static LOG_FILE: OnceLock<Arc<Mutex<std::fs::File>>> = OnceLock::new();
+
+/// 退出前调用：把日志文件写入磁盘
+pub fn flush() {
+    if let Some(file) = LOG_FILE.get() {
+        if let Ok(file) = file.lock() {
+            let _ = file.sync_data();
+        }
+    }
+}
+
+struct LogBuffer {
+    lines: Mutex<VecDeque<String>>,
+    tx: broadcast::Sender<String>,
+}
+
+fn log_buffer() -> &'static LogBuffer {
+    static BUFFER: OnceLock<LogBuffer> = OnceLock::new();
+    BUFFER.get_or_init(|| LogBuffer {
+        lines: Mutex::new(VecDeque::with_capacity(MEMORY_LOG_LINES)),
+        tx: broadcast::channel(256).0,
+    })
+}
+
+#[derive(Clone, Copy)]
+struct MemoryWriter;
+
+impl<'a> MakeWriter<'a> for MemoryWriter {
+    type Writer = MemoryWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        MemoryWriter
+    }
+}
+
+impl std::io::Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let buffer = log_buffer();
+        let mut lines = buffer.lines.lock().expect("log buffer lock");
+        for line in push_lines(&mut lines, &String::from_utf8_lossy(buf), MEMORY_LOG_LINES) {
+            let _ = buffer.tx.send(line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 按行写入环形缓冲，超出容量时丢弃最旧的行；返回新写入的行
+fn push_lines(lines: &mut VecDeque<String>, text: &str, capacity: usize) -> Vec<String> {
+    let added: Vec<String> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    for line in &added {
+        if lines.len() >= capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.clone());
+    }
+    added
+}
+
+/// 只读取文件末尾一段，避免日志文件很大时整个读入
+fn read_file_tail(path: &Path, limit: usize) -> std::io::Result<Vec<String>> {
+    const TAIL_BYTES: u64 = 512 * 1024;
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw)?;
+    let text = String::from_utf8_lossy(&raw);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        // 第一行可能被截断
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(limit);
+    Ok(lines.into_iter().skip(skip).map(str::to_string).collect())
 }
 
 fn env_filter() -> EnvFilter {
@@ -71,7 +322,15 @@ fn env_filter() -> EnvFilter {
     EnvFilter::try_new(normalized).unwrap_or_else(|_| EnvFilter::new(default_level))
 }
 
-struct GhostTypeFormat;
+/// `GHOSTTYPE_LOG_FORMAT=json`：stderr 与日志文件每行输出一个 JSON 对象，便于 Loki/ELK 等采集
+fn wants_json() -> bool {
+    std::env::var("GHOSTTYPE_LOG_FORMAT").is_ok_and(|v| v.trim().eq_ignore_ascii_case("json"))
+}
+
+#[derive(Clone, Copy)]
+struct GhostTypeFormat {
+    json: bool,
+}
 
 impl<S, N> FormatEvent<S, N> for GhostTypeFormat
 where
@@ -80,10 +339,15 @@ where
 {
     fn format_event(
         &self,
-        _ctx: &FmtContext<'_, S, N>,
+        ctx: &FmtContext<'_, S, N>,
         mut writer: Writer<'_>,
         event: &Event<'_>,
     ) -> fmt::Result {
+        // 日志本身没带 trace_id 时，用所在 span（如 `llm`、`inject`）的会话 trace_id
+        let span_trace_id = || timeline::scope_trace_id(ctx.event_scope());
+        if self.json {
+            return format_json(writer, event, span_trace_id);
+        }
         let now = chrono::Local::now();
         let ts = now.format("%Y-%m-%d %H:%M:%S%.3f");
 
@@ -92,6 +356,9 @@ where
 
         let mut fields = FieldVisitor::default();
         event.record(&mut fields);
+        if fields.trace_id.as_deref().unwrap_or_default().is_empty() {
+            fields.trace_id = span_trace_id();
+        }
 
         write!(writer, "[{ts}] [{level:<5}] [{module:<8}] ")?;
         if let Some(trace_id) = fields.trace_id.as_deref().filter(|v| !v.is_empty()) {
@@ -116,6 +383,27 @@ where
     }
 }
 
+fn format_json(
+    mut writer: Writer<'_>,
+    event: &Event<'_>,
+    span_trace_id: impl FnOnce() -> Option<String>,
+) -> fmt::Result {
+    let mut fields = JsonVisitor::default();
+    event.record(&mut fields);
+    if fields.trace_id.as_deref().unwrap_or_default().is_empty() {
+        fields.trace_id = span_trace_id();
+    }
+    let line = serde_json::json!({
+        "timestamp": chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        "level": level_str(event.metadata().level()),
+        "target": event.metadata().target(),
+        "trace_id": fields.trace_id.filter(|v| !v.is_empty()),
+        "message": fields.message,
+        "fields": fields.fields,
+    });
+    writeln!(writer, "{line}")
+}
+
 fn level_str(level: &Level) -> &'static str {
     match *level {
         Level::ERROR => "ERROR",
@@ -187,6 +475,67 @@ impl tracing::field::Visit for FieldVisitor {
     }
 }
 
+/// 与 `FieldVisitor` 相同，但数字与布尔值保留原类型
+#[derive(Default)]
+struct JsonVisitor {
+    message: Option<String>,
+    trace_id: Option<String>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &tracing::field::Field, value: serde_json::Value) {
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl tracing::field::Visit for JsonVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        match field.name() {
+            "message" => self.message = Some(value.to_string()),
+            "trace_id" => self.trace_id = Some(value.to_string()),
+            _ => self.insert(field, value.into()),
+        }
+    }
+
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i128(&mut self, field: &tracing::field::Field, value: i128) {
+        self.insert(field, value.to_string().into());
+    }
+
+    fn record_u128(&mut self, field: &tracing::field::Field, value: u128) {
+        self.insert(field, value.to_string().into());
+    }
+
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        // NaN 与无穷大不是合法的 JSON 数字
+        let value = serde_json::Number::from_f64(value)
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|| value.to_string().into());
+        self.insert(field, value);
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        let rendered = format!("{value:?}");
+        match field.name() {
+            "message" => self.message = Some(unquote_debug_string(&rendered)),
+            "trace_id" => self.trace_id = Some(unquote_debug_string(&rendered)),
+            _ => self.insert(field, rendered.into()),
+        }
+    }
+}
+
 fn unquote_debug_string(raw: &str) -> String {
     let trimmed = raw.trim();
     if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
@@ -249,16 +598,21 @@ fn build_file_writer() -> std::io::Result<SharedFileWriter> {
         std::fs::create_dir_all(parent)?;
     }
 
-    rotate_if_too_large(&log_path, 5 * 1024 * 1024)?;
+    let policy = RotationPolicy::from_env();
+    rotate_if_too_large(&log_path, policy.max_bytes)?;
+    if let Err(err) = prune_rotated(&log_path, policy, SystemTime::now()) {
+        eprintln!("[logging] 清理旧日志失败: {err}");
+    }
+    let _ = LOG_FILE_PATH.set(log_path.clone());
 
     let file = OpenOptions::new()
         .create(true)
         .append(true)
         .open(&log_path)?;
 
-    Ok(SharedFileWriter {
-        file: Arc::new(Mutex::new(file)),
-    })
+    let file = Arc::new(Mutex::new(file));
+    let _ = LOG_FILE.set(file.clone());
+    Ok(SharedFileWriter { file })
 }
 
 fn resolve_log_path() -> PathBuf {
@@ -290,3 +644,113 @@ fn rotate_if_too_large(path: &Path, max_bytes: u64) -> std::io::Result<()> {
     let _ = std::fs::rename(path, rotated);
     Ok(())
 }
+
+/// 删除超出数量或保留天数的轮转文件（`<stem>_<时间>.log`），返回删除的文件
+fn prune_rotated(path: &Path, policy: RotationPolicy, now: SystemTime) -> std::io::Result<Vec<PathBuf>> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("ghosttype_client");
+    let prefix = format!("{stem}_");
+    let mut rotated: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|candidate| {
+            candidate
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        })
+        .collect();
+    // 文件名中的时间可按字典序比较，最新的在前
+    rotated.sort_by(|a, b| b.cmp(a));
+
+    let max_age = Duration::from_secs(policy.retention_days.saturating_mul(24 * 60 * 60));
+    let mut removed = Vec::new();
+    for (index, candidate) in rotated.into_iter().enumerate() {
+        let too_many = policy.max_files > 0 && index >= policy.max_files;
+        let too_old = policy.retention_days > 0
+            && std::fs::metadata(&candidate)
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|age| age > max_age));
+        if (too_many || too_old) && std::fs::remove_file(&candidate).is_ok() {
+            removed.push(candidate);
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_lines_keeps_only_latest_lines() {
+        let mut lines = VecDeque::new();
+        let added = push_lines(&mut lines, "a\nb\n\nc\n", 2);
+        assert_eq!(added, vec!["a", "b", "c"]);
+        assert_eq!(lines, VecDeque::from(vec!["b".to_string(), "c".to_string()]));
+    }
+
+    #[test]
+    fn read_file_tail_returns_last_lines() {
+        let path = std::env::temp_dir().join(format!("ghosttype_tail_{}.log", std::process::id()));
+        std::fs::write(&path, "one\ntwo\nthree\n").expect("write log");
+        let lines = read_file_tail(&path, 2).expect("read tail");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(lines, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn prune_rotated_keeps_newest_files_within_retention() {
+        let dir = std::env::temp_dir().join(format!("ghosttype_prune_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let log = dir.join("ghosttype_client.log");
+        let names = [
+            "ghosttype_client.log",
+            "ghosttype_client_20250101_000000.000.log",
+            "ghosttype_client_20250102_000000.000.log",
+            "ghosttype_client_20250103_000000.000.log",
+            "other_20250101_000000.000.log",
+        ];
+        for name in names {
+            std::fs::write(dir.join(name), "x").expect("write log");
+        }
+
+        let policy = RotationPolicy {
+            max_files: 2,
+            ..RotationPolicy::default()
+        };
+        let removed = prune_rotated(&log, policy, SystemTime::now()).expect("prune");
+        assert_eq!(removed, vec![dir.join(names[1])]);
+
+        // 超过保留天数的轮转文件全部删除，当前日志与其它文件不动
+        let later = SystemTime::now() + Duration::from_secs(31 * 24 * 60 * 60);
+        let removed = prune_rotated(&log, policy, later).expect("prune");
+        assert_eq!(removed.len(), 2);
+        assert!(log.exists());
+        assert!(dir.join(names[4]).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn json_format_writes_one_object_per_line() {
+        let path = std::env::temp_dir().join(format!("ghosttype_json_{}.log", std::process::id()));
+        let file = std::fs::File::create(&path).expect("create log");
+        let layer = tracing_subscriber::fmt::layer()
+            .event_format(GhostTypeFormat { json: true })
+            .with_writer(SharedFileWriter {
+                file: Arc::new(Mutex::new(file)),
+            });
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!(target: "pipeline", trace_id = "t1", chars = 5, ok = true, error = %"boom", "完成 | Done");
+        });
+        let raw = std::fs::read_to_string(&path).expect("read log");
+        let _ = std::fs::remove_file(&path);
+
+        let line: serde_json::Value = serde_json::from_str(raw.trim()).expect("one json object");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "pipeline");
+        assert_eq!(line["trace_id"], "t1");
+        assert_eq!(line["message"], "完成 | Done");
+        assert_eq!(line["fields"], serde_json::json!({ "chars": 5, "ok": true, "error": "boom" }));
+        assert!(line["timestamp"].as_str().is_some_and(|ts| ts.contains('T')));
+    }
+}
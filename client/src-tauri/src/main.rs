@@ -3,138 +3,92 @@
 mod app_state;
 mod asr;
 mod audio;
+mod audio_file;
+mod audit;
+mod bench;
+mod caption;
+mod cli;
+mod code_mode;
 mod config;
+mod connection;
+mod content_filter;
+mod control;
+mod crash;
+mod dictionary;
+mod discovery;
+mod elevation;
+mod error;
+mod events;
+mod hardware;
+mod history;
+mod hooks;
 mod input;
+mod input_field;
+mod language;
 mod llm;
 mod logging;
+mod meeting;
+mod metrics;
+mod network;
+mod numbers;
+mod notify;
 mod opus;
+mod overlay;
 mod pipeline;
 mod platform;
+mod plugin;
+mod power;
+mod privacy;
+mod proxy;
+mod punctuation;
+mod reliability;
+mod rules;
+mod segment;
+mod setup;
+mod shutdown;
+mod sink;
+mod snippet;
+mod stats;
+mod telemetry;
+mod throttle;
+mod timeline;
+mod tls;
+mod tray;
+mod update;
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod url_scheme;
+mod voice_command;
+mod wake;
+mod warmup;
 
 use active_win_pos_rs::ActiveWindow;
+use error::{AppError, ErrorCode};
 use rdev::{EventType, Key};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tray::TrayController;
 use tracing::{debug, error, info};
 
 #[derive(Debug)]
 enum HotkeyEvent {
     Start,
     Stop,
-}
-
-const TRAY_ID: &str = "ghosttype-tray";
-
-#[cfg(target_os = "macos")]
-const TRAY_IDLE: &[u8] = include_bytes!("../icons/tray_idle@2x.png");
-#[cfg(target_os = "macos")]
-const TRAY_RECORDING: &[u8] = include_bytes!("../icons/tray_recording@2x.png");
-#[cfg(target_os = "macos")]
-const TRAY_PROCESSING: &[u8] = include_bytes!("../icons/tray_processing@2x.png");
-#[cfg(target_os = "macos")]
-const TRAY_ERROR: &[u8] = include_bytes!("../icons/tray_error@2x.png");
-
-#[cfg(not(target_os = "macos"))]
-const TRAY_IDLE: &[u8] = include_bytes!("../icons/tray_idle.png");
-#[cfg(not(target_os = "macos"))]
-const TRAY_RECORDING: &[u8] = include_bytes!("../icons/tray_recording.png");
-#[cfg(not(target_os = "macos"))]
-const TRAY_PROCESSING: &[u8] = include_bytes!("../icons/tray_processing.png");
-#[cfg(not(target_os = "macos"))]
-const TRAY_ERROR: &[u8] = include_bytes!("../icons/tray_error.png");
-
-#[derive(Debug, Clone, Copy)]
-enum TrayMode {
-    Idle,
-    Recording,
-    Processing,
-}
-
-#[derive(Debug)]
-struct TrayControllerState {
-    mode: TrayMode,
-    error: bool,
-}
-
-#[derive(Debug)]
-struct TrayController {
-    app: tauri::AppHandle,
-    state: Mutex<TrayControllerState>,
-}
-
-impl TrayController {
-    fn new(app: tauri::AppHandle) -> Self {
-        Self {
-            app,
-            state: Mutex::new(TrayControllerState {
-                mode: TrayMode::Idle,
-                error: false,
-            }),
-        }
-    }
-
-    fn set_idle(&self) {
-        self.set_mode(TrayMode::Idle);
-        self.clear_error();
-    }
-
-    fn set_recording(&self) {
-        self.set_mode(TrayMode::Recording);
-        self.clear_error();
-    }
-
-    fn set_processing(&self) {
-        self.set_mode(TrayMode::Processing);
-        self.clear_error();
-    }
-
-    fn set_error(&self) {
-        let mut guard = self.state.lock().expect("tray state lock");
-        guard.error = true;
-        drop(guard);
-        self.apply();
-    }
-
-    fn clear_error(&self) {
-        let mut guard = self.state.lock().expect("tray state lock");
-        guard.error = false;
-        drop(guard);
-        self.apply();
-    }
-
-    fn set_mode(&self, mode: TrayMode) {
-        let mut guard = self.state.lock().expect("tray state lock");
-        guard.mode = mode;
-        drop(guard);
-        self.apply();
-    }
-
-    fn apply(&self) {
-        let guard = self.state.lock().expect("tray state lock");
-        let bytes = match (guard.mode, guard.error) {
-            (TrayMode::Recording, _) => TRAY_RECORDING,
-            (_, true) => TRAY_ERROR,
-            (TrayMode::Processing, false) => TRAY_PROCESSING,
-            (TrayMode::Idle, false) => TRAY_IDLE,
-        };
-        drop(guard);
-
-        let Some(tray) = self.app.tray_by_id(TRAY_ID) else {
-            return;
-        };
-
-        let icon = tauri::image::Image::from_bytes(bytes).expect("tray icon");
-        if let Err(err) = tray.set_icon(Some(icon)) {
-            tracing::warn!(target: "tray", error = %err, "tray icon set failed");
-        }
-    }
+    /// 按下改写选中文字的热键
+    TransformStart,
+    /// 听到唤醒词；只在空闲时开始
+    Wake,
+    /// 唤醒后说完停顿；只结束由唤醒词开始的会话
+    WakeEnded,
 }
 
 #[derive(serde::Serialize)]
 struct ClientConfigResponse {
     config: config::ClientConfig,
     path: Option<String>,
+    /// 已应用到运行中的 Pipeline；为 false 时在本次录音结束后生效
+    applied: bool,
 }
 
 #[derive(serde::Serialize)]
@@ -146,6 +100,7 @@ struct RuntimeInfo {
 #[derive(serde::Serialize)]
 struct PermissionStatus {
     accessibility: bool,
+    input_monitoring: bool,
     microphone: bool,
 }
 
@@ -155,19 +110,53 @@ fn load_client_config() -> ClientConfigResponse {
     ClientConfigResponse {
         config,
         path: path.map(|p| p.display().to_string()),
+        applied: true,
     }
 }
 
 #[tauri::command]
-fn save_client_config(config: config::ClientConfig) -> Result<ClientConfigResponse, String> {
+async fn save_client_config(
+    app: tauri::AppHandle,
+    config: config::ClientConfig,
+) -> Result<ClientConfigResponse, AppError> {
+    rules::RuleSet::compile(&config.rules).map_err(AppError::with_code(ErrorCode::Config))?;
+    content_filter::ContentFilter::compile(&config.content_filter).map_err(AppError::with_code(ErrorCode::Config))?;
+    voice_command::CommandSet::compile(&config.voice_commands).map_err(AppError::with_code(ErrorCode::Config))?;
+    snippet::SnippetLibrary::compile(&config.snippets).map_err(AppError::with_code(ErrorCode::Config))?;
     let (_, path) = config::load_with_path();
-    let saved = config::save_to_path(&config, path).map_err(|err| err.to_string())?;
+    let saved = config::save_to_path(&config, path).map_err(AppError::with_code(ErrorCode::Config))?;
+    privacy::install(&config.privacy);
+    audit::install(&config.audit);
+    elevation::install(&config.elevation);
+    proxy::install(config.proxy.clone());
+    tls::install(config.tls.clone());
+    telemetry::install(config.telemetry.clone());
+    tray::refresh_menu(&app, &config);
+    let applied = apply_saved_config(&app, &config).await?;
     Ok(ClientConfigResponse {
         config,
         path: Some(saved.display().to_string()),
+        applied,
     })
 }
 
+/// 热键和麦克风在启动时确定，仍需重启；引擎、规则等立即（录音中则在本次录音结束后）生效
+async fn apply_saved_config(app: &tauri::AppHandle, config: &config::ClientConfig) -> Result<bool, AppError> {
+    if let Some(captions) = app.try_state::<Arc<caption::CaptionController>>() {
+        captions.set_config(&config.captions);
+    }
+    if let Some(hooks) = app.try_state::<Arc<hooks::HookRunner>>() {
+        hooks.set_config(&config.hooks);
+    }
+    let Some(state) = app.try_state::<Arc<app_state::AppState>>() else {
+        return Ok(false);
+    };
+    state
+        .apply_config(config)
+        .await
+        .map_err(AppError::with_code(ErrorCode::Config))
+}
+
 #[tauri::command]
 fn get_runtime_info() -> RuntimeInfo {
     RuntimeInfo {
@@ -176,95 +165,440 @@ fn get_runtime_info() -> RuntimeInfo {
     }
 }
 
+/// 探测 GPU、内存与核数（首次需要启动外部命令，放到阻塞线程上）
+#[tauri::command]
+async fn get_system_capabilities() -> Result<hardware::SystemCapabilities, AppError> {
+    tauri::async_runtime::spawn_blocking(hardware::capabilities)
+        .await
+        .map_err(|err| AppError::new(ErrorCode::Internal, err.to_string()))
+}
+
 #[tauri::command]
-fn list_audio_devices() -> Result<Vec<audio::InputDeviceInfo>, String> {
-    audio::list_input_devices().map_err(|err| err.to_string())
+fn list_audio_devices() -> Result<Vec<audio::InputDeviceInfo>, AppError> {
+    audio::list_input_devices().map_err(AppError::with_code(ErrorCode::AudioDevice))
 }
 
 #[tauri::command]
 fn check_permissions(state: tauri::State<'_, Arc<app_state::AppState>>) -> PermissionStatus {
-    let accessibility = if cfg!(target_os = "macos") {
-        platform::ensure_accessibility(false)
-    } else {
-        true
-    };
+    let accessibility = platform::ensure_accessibility(false);
+    let input_monitoring = platform::input_monitoring_granted();
 
     let microphone = audio::check_microphone_access(state.audio_device.as_deref());
 
     PermissionStatus {
         accessibility,
+        input_monitoring,
         microphone,
     }
 }
 
 #[tauri::command]
-fn open_accessibility_settings() -> Result<(), String> {
-    platform::open_accessibility_settings()
+fn open_accessibility_settings() -> Result<(), AppError> {
+    platform::open_accessibility_settings().map_err(|err| AppError::new(ErrorCode::Internal, err))
+}
+
+#[tauri::command]
+fn open_input_monitoring_settings() -> Result<(), AppError> {
+    platform::open_input_monitoring_settings().map_err(|err| AppError::new(ErrorCode::Internal, err))
 }
 
 #[tauri::command]
-fn open_microphone_settings() -> Result<(), String> {
-    platform::open_microphone_settings()
+fn open_microphone_settings() -> Result<(), AppError> {
+    platform::open_microphone_settings().map_err(|err| AppError::new(ErrorCode::Internal, err))
 }
 
 #[tauri::command]
-fn open_sound_settings() -> Result<(), String> {
-    platform::open_sound_settings()
+fn open_sound_settings() -> Result<(), AppError> {
+    platform::open_sound_settings().map_err(|err| AppError::new(ErrorCode::Internal, err))
 }
 
 #[tauri::command]
-async fn test_server_connection(endpoint: String) -> Result<bool, String> {
-    use futures_util::{SinkExt, StreamExt};
-    use std::time::Duration;
-    use tokio_tungstenite::tungstenite::Message;
+fn get_autostart() -> Result<bool, AppError> {
+    platform::autostart_enabled().map_err(|err| AppError::new(ErrorCode::Io, err))
+}
 
-    let endpoint = endpoint.trim().to_string();
-    if endpoint.is_empty() {
-        return Err("服务器地址为空 | Endpoint is empty".to_string());
+#[tauri::command]
+fn set_autostart(enabled: bool) -> Result<(), AppError> {
+    platform::set_autostart(enabled).map_err(|err| AppError::new(ErrorCode::Io, err))?;
+    info!(target: "app", enabled = enabled, "开机启动已更新 | Autostart updated");
+    Ok(())
+}
+
+#[tauri::command]
+async fn test_server_connection(endpoint: String) -> Result<bool, AppError> {
+    setup::ping_server(&endpoint, std::time::Duration::from_secs(3))
+        .await
+        .map_err(|err| AppError::new(ErrorCode::ServerUnreachable, err))
+}
+
+#[tauri::command]
+async fn discover_servers(timeout_ms: Option<u64>) -> Result<Vec<discovery::DiscoveredServer>, AppError> {
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(3000).clamp(500, 15_000));
+    discovery::discover(timeout)
+        .await
+        .map_err(AppError::with_code(ErrorCode::Io))
+}
+
+#[tauri::command]
+async fn test_llm_health(llm_config: llm::LlmConfig) -> Result<bool, AppError> {
+    let engine = llm::create_engine(&llm_config).map_err(AppError::with_code(ErrorCode::Config))?;
+    Ok(engine.health_check().await)
+}
+
+#[derive(serde::Serialize)]
+struct InjectionTestResult {
+    strategy: input::InjectionStrategy,
+    expected: String,
+}
+
+/// 注入自检：前端先聚焦设置页中的测试输入框，再调用本命令；
+/// 返回期望出现的文字，由前端与输入框内容比对。
+#[tauri::command]
+async fn test_injection(
+    app: tauri::AppHandle,
+    strategy: Option<input::InjectionStrategy>,
+) -> Result<InjectionTestResult, AppError> {
+    if !platform::ensure_accessibility(false) {
+        return Err(AppError::new(ErrorCode::Accessibility, "未授予辅助功能权限，无法模拟键盘输入"));
     }
 
-    let connect_result = tokio::time::timeout(Duration::from_secs(3), tokio_tungstenite::connect_async(&endpoint))
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_focus();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+    let strategy = strategy.unwrap_or_default();
+    let expected = input::run_injection_test(strategy)
         .await
-        .map_err(|_| "连接超时 | Connect timeout".to_string())?;
+        .map_err(|err| AppError::new(ErrorCode::InjectionFailed, err))?;
+    info!(target: "input", strategy = ?strategy, "注入自检已执行 | Injection test executed");
+    Ok(InjectionTestResult { strategy, expected })
+}
+
+#[tauri::command]
+fn get_setup_status() -> setup::SetupStatus {
+    setup::status()
+}
+
+#[tauri::command]
+async fn probe_default_server(endpoint: Option<String>) -> setup::ServerProbe {
+    setup::probe_server(endpoint).await
+}
+
+#[tauri::command]
+async fn request_setup_permission(
+    permission: setup::SetupPermission,
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+) -> Result<bool, AppError> {
+    Ok(setup::request_permission(permission, state.audio_device.clone()).await)
+}
+
+/// 引导式权限流程：`prompt=true` 时按顺序触发系统弹窗
+#[tauri::command]
+async fn run_permission_flow(
+    prompt: bool,
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+) -> Result<setup::PermissionFlow, AppError> {
+    Ok(setup::permission_flow(state.audio_device.clone(), prompt).await)
+}
+
+#[tauri::command]
+async fn test_microphone(device: Option<String>, duration_ms: Option<u64>) -> setup::MicrophoneTest {
+    setup::test_microphone(device, duration_ms.unwrap_or(1500)).await
+}
+
+#[tauri::command]
+async fn complete_setup(app: tauri::AppHandle, config: config::ClientConfig) -> Result<ClientConfigResponse, AppError> {
+    let saved = setup::complete(&config).map_err(AppError::with_code(ErrorCode::Config))?;
+    let applied = apply_saved_config(&app, &config).await?;
+    Ok(ClientConfigResponse {
+        config,
+        path: Some(saved.display().to_string()),
+        applied,
+    })
+}
+
+#[tauri::command]
+fn list_history(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    history: tauri::State<'_, Arc<history::HistoryStore>>,
+) -> Result<Vec<history::HistoryEntry>, AppError> {
+    history
+        .list(limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(AppError::with_code(ErrorCode::Io))
+}
+
+#[tauri::command]
+fn search_history(
+    query: String,
+    limit: Option<u32>,
+    history: tauri::State<'_, Arc<history::HistoryStore>>,
+) -> Result<Vec<history::HistoryEntry>, AppError> {
+    history
+        .search(&query, limit.unwrap_or(50))
+        .map_err(AppError::with_code(ErrorCode::Io))
+}
+
+#[tauri::command]
+fn delete_history(ids: Vec<i64>, history: tauri::State<'_, Arc<history::HistoryStore>>) -> Result<usize, AppError> {
+    history.delete(&ids).map_err(AppError::with_code(ErrorCode::Io))
+}
+
+#[tauri::command]
+fn list_learned_terms(
+    dictionary: tauri::State<'_, Arc<dictionary::LearnedDictionary>>,
+) -> Vec<dictionary::LearnedTerm> {
+    dictionary.terms()
+}
+
+#[tauri::command]
+fn forget_learned_terms(from: Vec<String>, dictionary: tauri::State<'_, Arc<dictionary::LearnedDictionary>>) -> usize {
+    dictionary.forget(&from)
+}
+
+#[derive(serde::Serialize)]
+struct ExportResult {
+    path: String,
+    count: usize,
+}
+
+/// 导出历史会话；未指定路径时写到「文稿」目录
+#[tauri::command]
+fn export_history(
+    app: tauri::AppHandle,
+    format: history::ExportFormat,
+    selection: Option<history::ExportSelection>,
+    path: Option<String>,
+    history: tauri::State<'_, Arc<history::HistoryStore>>,
+) -> Result<ExportResult, AppError> {
+    let entries = history
+        .select(&selection.unwrap_or_default())
+        .map_err(AppError::with_code(ErrorCode::Io))?;
+    if entries.is_empty() {
+        return Err(AppError::new(ErrorCode::NotFound, "没有可导出的记录"));
+    }
+    let content = history::render_export(&entries, format)?;
+
+    let path = match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let dir = app
+                .path()
+                .document_dir()
+                .ok()
+                .or_else(|| std::env::current_dir().ok())
+                .ok_or_else(|| AppError::new(ErrorCode::Io, "无法确定导出目录"))?;
+            let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            dir.join(format!("ghosttype-{stamp}.{}", format.extension()))
+        }
+    };
+    std::fs::write(&path, content).map_err(|err| AppError::new(ErrorCode::Io, err.to_string()))?;
+
+    info!(
+        target: "history",
+        path = %path.display(),
+        count = entries.len(),
+        "历史记录已导出 | History exported"
+    );
+    Ok(ExportResult {
+        path: path.display().to_string(),
+        count: entries.len(),
+    })
+}
 
-    let (ws, _) = connect_result.map_err(|err| err.to_string())?;
-    let (mut write, mut read) = ws.split();
+#[tauri::command]
+fn clear_history(history: tauri::State<'_, Arc<history::HistoryStore>>) -> Result<usize, AppError> {
+    history.clear().map_err(AppError::with_code(ErrorCode::Io))
+}
 
-    let payload = serde_json::json!({ "type": "ping" }).to_string();
-    write
-        .send(Message::Text(payload))
+#[tauri::command]
+async fn switch_asr_profile(app: tauri::AppHandle, name: String) -> Result<(), AppError> {
+    tray::switch_asr_profile(&app, &name).await
+}
+
+#[tauri::command]
+async fn retype_last_transcript(state: tauri::State<'_, Arc<app_state::AppState>>) -> Result<String, AppError> {
+    state.retype_last().await.map_err(AppError::with_code(ErrorCode::InjectionFailed))
+}
+
+/// 回删当前应用中最近几次输入（默认 1 次），返回被删掉的文字
+#[tauri::command]
+async fn undo_last_injections(
+    count: Option<usize>,
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+) -> Result<String, AppError> {
+    state
+        .undo_last(count.unwrap_or(1))
         .await
-        .map_err(|err| err.to_string())?;
+        .map_err(AppError::with_code(ErrorCode::InjectionFailed))
+}
+
+/// 识别音频文件（WAV/MP3/OGG），结果写入剪贴板并返回
+#[tauri::command]
+async fn transcribe_audio_file(path: String) -> Result<String, AppError> {
+    audio_file::transcribe_to_clipboard(std::path::Path::new(&path)).await
+}
 
-    let incoming = tokio::time::timeout(Duration::from_secs(3), read.next())
+#[tauri::command]
+fn list_text_rules() -> Vec<rules::TextRule> {
+    config::load_with_path().0.rules
+}
+
+#[tauri::command]
+async fn save_text_rules(
+    rules: Vec<rules::TextRule>,
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+) -> Result<(), AppError> {
+    state.save_rules(rules).await.map_err(AppError::from)
+}
+
+/// 用尚未保存的规则试算一段文字，供设置界面预览
+#[tauri::command]
+fn preview_text_rules(rules: Vec<rules::TextRule>, text: String) -> Result<String, AppError> {
+    let compiled = rules::RuleSet::compile(&rules).map_err(AppError::with_code(ErrorCode::Config))?;
+    Ok(compiled.apply(&text))
+}
+
+#[tauri::command]
+async fn check_for_updates() -> Result<update::UpdateInfo, AppError> {
+    let (config, _) = config::load_with_path();
+    update::check(&config.update.feed_url)
         .await
-        .map_err(|_| "等待响应超时 | Wait timeout".to_string())?;
+        .map_err(AppError::with_code(ErrorCode::ServerUnreachable))
+}
 
-    let Some(incoming) = incoming else {
-        return Ok(false);
-    };
-    let Ok(incoming) = incoming else {
-        return Ok(false);
-    };
+#[tauri::command]
+async fn start_dictation(
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+    tray: tauri::State<'_, Arc<TrayController>>,
+    overlay: tauri::State<'_, Arc<overlay::OverlayController>>,
+) -> Result<(), AppError> {
+    if state.is_paused() {
+        return Err(AppError::new(ErrorCode::Paused, ""));
+    }
+    handle_start(state.inner(), tray.inner(), overlay.inner(), app_state::SessionMode::Dictation)
+}
 
-    let Message::Text(text) = incoming else {
-        return Ok(false);
-    };
+#[tauri::command]
+async fn stop_dictation(
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+    tray: tauri::State<'_, Arc<TrayController>>,
+) -> Result<(), AppError> {
+    handle_stop(state.inner(), tray.inner());
+    Ok(())
+}
 
-    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
-        return Ok(false);
-    };
-    Ok(value.get("type").and_then(|v| v.as_str()) == Some("pong"))
+/// 取消当前会话：不识别、不输入；没有进行中的会话时返回 `false`
+#[tauri::command]
+async fn cancel_session(
+    state: tauri::State<'_, Arc<app_state::AppState>>,
+    tray: tauri::State<'_, Arc<TrayController>>,
+) -> Result<bool, AppError> {
+    Ok(handle_cancel(state.inner(), tray.inner()))
 }
 
 #[tauri::command]
-async fn test_llm_health(llm_config: llm::LlmConfig) -> Result<bool, String> {
-    let engine = llm::create_engine(&llm_config).map_err(|err| err.to_string())?;
-    Ok(engine.health_check().await)
+fn get_stats(stats: tauri::State<'_, Arc<stats::StatsCollector>>) -> stats::StatsSnapshot {
+    stats.snapshot()
+}
+
+#[tauri::command]
+fn reset_stats(stats: tauri::State<'_, Arc<stats::StatsCollector>>) {
+    stats.reset();
+}
+
+/// 最近 `days` 天（含今天，默认 7 天）按应用汇总的听写用量，保存在本地历史库中
+#[tauri::command]
+fn get_app_stats(
+    days: Option<u32>,
+    history: tauri::State<'_, Arc<history::HistoryStore>>,
+) -> Result<Vec<history::AppUsage>, AppError> {
+    let days = days.unwrap_or(7).max(1);
+    let since = chrono::Local::now() - chrono::Duration::days(i64::from(days - 1));
+    history
+        .app_usage(&since.format("%Y-%m-%d").to_string())
+        .map_err(AppError::with_code(ErrorCode::Io))
+}
+
+/// ASR 服务器连接状态；`refresh=true` 时立即重新探测
+#[tauri::command]
+async fn get_connection_status(
+    refresh: Option<bool>,
+    connection: tauri::State<'_, Arc<connection::ConnectionMonitor>>,
+) -> Result<connection::ConnectionStatus, AppError> {
+    if refresh.unwrap_or(false) {
+        return Ok(connection.ping_now().await);
+    }
+    Ok(connection.snapshot())
+}
+
+/// 最近一次预热（启动、切换配置后自动连接 ASR 服务器并检查 LLM）的结果
+#[tauri::command]
+fn get_warmup_status(warmup: tauri::State<'_, Arc<warmup::Warmup>>) -> warmup::WarmupStatus {
+    warmup.snapshot()
+}
+
+#[derive(serde::Serialize)]
+struct RecentLogs {
+    path: Option<String>,
+    lines: Vec<String>,
+}
+
+/// 最近的日志行，供设置窗口查看与复制诊断信息
+#[tauri::command]
+fn get_recent_logs(limit: Option<usize>) -> RecentLogs {
+    RecentLogs {
+        path: logging::log_file_path().map(|p| p.display().to_string()),
+        lines: logging::recent_lines(limit.unwrap_or(500).clamp(1, 2000)),
+    }
+}
+
+/// 某次听写按顺序排列的日志事件（录音、识别、校正、注入），用于排查单次听写的问题
+#[tauri::command]
+fn get_session_timeline(trace_id: String) -> Vec<timeline::TimelineEvent> {
+    timeline::session(&trace_id)
+}
+
+/// 开启/关闭实时日志：开启后每条新日志以 `log-line` 事件推送
+#[tauri::command]
+fn tail_logs(app: tauri::AppHandle, enabled: bool, tail: tauri::State<'_, Arc<logging::LogTail>>) {
+    if enabled {
+        tail.start(app);
+    } else {
+        tail.stop();
+    }
 }
 
 fn main() {
+    let headless = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Run { headless }) => headless,
+        Ok(cli::Command::Help) => {
+            platform::attach_console();
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Ok(cli::Command::Transcribe { path, use_llm }) => {
+            platform::attach_console();
+            logging::init();
+            std::process::exit(cli::run_transcribe(&path, use_llm));
+        }
+        Ok(cli::Command::Bench { dir, use_llm, realtime }) => {
+            platform::attach_console();
+            logging::init();
+            std::process::exit(bench::run(&dir, use_llm, realtime));
+        }
+        Err(err) => {
+            platform::attach_console();
+            eprintln!("{err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
     logging::init();
+    crash::install_hook();
+    if elevation::apply_at_startup(&config::load_with_path().0.elevation) {
+        return;
+    }
 
     info!(
         target: "app",
@@ -275,25 +609,80 @@ fn main() {
     );
 
     tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             load_client_config,
             save_client_config,
             get_runtime_info,
+            get_system_capabilities,
             list_audio_devices,
             check_permissions,
             open_accessibility_settings,
+            open_input_monitoring_settings,
             open_microphone_settings,
             open_sound_settings,
+            get_autostart,
+            set_autostart,
             test_server_connection,
-            test_llm_health
+            discover_servers,
+            test_llm_health,
+            test_injection,
+            get_setup_status,
+            probe_default_server,
+            request_setup_permission,
+            run_permission_flow,
+            test_microphone,
+            complete_setup,
+            list_history,
+            search_history,
+            delete_history,
+            clear_history,
+            export_history,
+            switch_asr_profile,
+            retype_last_transcript,
+            undo_last_injections,
+            transcribe_audio_file,
+            list_text_rules,
+            save_text_rules,
+            preview_text_rules,
+            list_learned_terms,
+            forget_learned_terms,
+            start_dictation,
+            stop_dictation,
+            cancel_session,
+            check_for_updates,
+            get_stats,
+            reset_stats,
+            get_app_stats,
+            get_recent_logs,
+            get_session_timeline,
+            tail_logs,
+            get_connection_status,
+            get_warmup_status
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            // 无界面模式：关闭所有窗口（设置、浮层、字幕），只保留托盘、热键与识别
+            if headless {
+                for window in app.webview_windows().into_values() {
+                    let _ = window.destroy();
+                }
+                info!(target: "app", "无界面模式 | Headless mode");
+            }
             let (config, config_path) = config::load_with_path();
+            let first_run = config_path.is_none();
+            privacy::install(&config.privacy);
+            audit::install(&config.audit);
+            elevation::install(&config.elevation);
+            proxy::install(config.proxy.clone());
+            tls::install(config.tls.clone());
+            telemetry::install(config.telemetry.clone());
             let hotkey = config.hotkey.clone();
+            let transform_hotkey = config.transform_hotkey.clone();
             let audio_device = config.audio_device.clone();
 
             let server_endpoints = match &config.asr {
-                asr::AsrConfig::WebSocket { endpoint } => vec![endpoint.clone()],
+                asr::AsrConfig::WebSocket { .. } => config.asr.websocket_endpoints(),
                 _ => vec![asr::default_websocket_endpoint()],
             };
             let config_path = config_path
@@ -322,52 +711,287 @@ fn main() {
                 llm = %format!("{:?}", config.llm),
                 "配置已加载 | Config loaded"
             );
-            setup_tray(app)?;
+            tray::setup_tray(app, &config)?;
             let tray = Arc::new(TrayController::new(app.handle().clone()));
             tray.set_idle();
+            tray::watch_theme(app, tray.clone());
 
-            let injector = input::spawn_injector();
-            let pipeline = pipeline::Pipeline::new(&config.asr, &config.llm, injector.clone()).unwrap_or_else(|err| {
+            let notifier = Arc::new(notify::Notifier::new(app.handle().clone()));
+            let crashes = crash::take_unnotified();
+            if let Some(latest) = crashes.last() {
+                tracing::warn!(
+                    target: "app",
+                    count = crashes.len(),
+                    path = %latest.display(),
+                    "上次运行时崩溃，报告已保存 | Previous run crashed, report saved"
+                );
+                notifier.crash_reported(crashes.len(), latest);
+            }
+            elevation::spawn_watcher(notifier.clone());
+            if config.update.auto_check {
+                update::spawn_background_check(config.update.clone(), notifier.clone());
+            }
+            let history = Arc::new(history::HistoryStore::open_default());
+            let dictionary = Arc::new(dictionary::LearnedDictionary::open_default());
+            dictionary.configure(&config.dictionary);
+            let reliability = Arc::new(reliability::InjectionReliability::open_default());
+            reliability.configure(&config.injection_check);
+            let stats = Arc::new(stats::StatsCollector::new());
+            let overlay = Arc::new(overlay::OverlayController::new(app.handle().clone()));
+            let captions = Arc::new(caption::CaptionController::new(app.handle().clone(), config.captions.clone()));
+            let hooks = Arc::new(hooks::HookRunner::new(config.hooks.clone()));
+            let (event_tx, event_rx, record_rx) = events::channel_with_records();
+            let metrics = Arc::new(metrics::Metrics::new());
+            if config.metrics.enabled {
+                metrics::spawn_server(metrics.clone(), &config.metrics);
+            }
+            let connection = Arc::new(connection::ConnectionMonitor::new(app.handle().clone(), metrics.clone()));
+            let warmup = Arc::new(warmup::Warmup::new(app.handle().clone(), tray.clone()));
+            spawn_event_forwarder(
+                app.handle().clone(),
+                event_rx,
+                EventSubscribers {
+                    tray: tray.clone(),
+                    overlay: overlay.clone(),
+                    captions: captions.clone(),
+                    notifier: notifier.clone(),
+                },
+            );
+            spawn_record_writer(
+                app.handle().clone(),
+                record_rx,
+                RecordSubscribers {
+                    hooks: hooks.clone(),
+                    history: history.clone(),
+                    stats: stats.clone(),
+                    connection: connection.clone(),
+                    warmup: warmup.clone(),
+                    metrics,
+                },
+            );
+            let injector = input::spawn_injector(event_tx.clone(), reliability.clone());
+            let pipeline_result = pipeline::Pipeline::new(&config.asr, &config.llm, injector.clone(), event_tx.clone());
+            let mut pipeline = pipeline_result.unwrap_or_else(|err| {
                 tracing::error!(
                     target: "pipeline",
                     error = %err,
                     "Pipeline 初始化失败，回退默认配置 | Pipeline init failed, falling back to defaults"
                 );
-                pipeline::Pipeline::new(&asr::AsrConfig::default(), &llm::LlmConfig::default(), injector.clone())
-                    .expect("pipeline fallback")
+                pipeline::Pipeline::new(
+                    &asr::AsrConfig::default(),
+                    &llm::LlmConfig::default(),
+                    injector.clone(),
+                    event_tx.clone(),
+                )
+                .expect("pipeline fallback")
             });
-
-            let state = Arc::new(app_state::AppState::new(pipeline, audio_device.clone()));
+            pipeline.set_app_overrides(config.per_app.clone());
+            pipeline.set_segment_config(config.segments.clone());
+            pipeline.set_server_correction(config.use_cloud_api);
+            pipeline.set_correction_gate(config.correction_gate.clone());
+            pipeline.set_correction_timing(config.correction_timing.clone());
+            pipeline.set_throttle(config.throttle.clone());
+            match rules::RuleSet::compile(&config.rules) {
+                Ok(rules) => pipeline.set_rules(rules),
+                Err(err) => tracing::warn!(
+                    target: "config",
+                    error = %format!("{err:#}"),
+                    "后处理规则无效，已全部忽略 | Invalid post-processing rules, all ignored"
+                ),
+            }
+            match content_filter::ContentFilter::compile(&config.content_filter) {
+                Ok(filter) => pipeline.set_content_filter(filter),
+                Err(err) => tracing::warn!(
+                    target: "config",
+                    error = %format!("{err:#}"),
+                    "敏感词过滤配置无效，已停用 | Invalid content filter config, filter disabled"
+                ),
+            }
+            match voice_command::CommandSet::compile(&config.voice_commands) {
+                Ok(commands) => pipeline.set_voice_commands(commands),
+                Err(err) => tracing::warn!(
+                    target: "config",
+                    error = %format!("{err:#}"),
+                    "语音编辑命令配置无效，已停用 | Invalid voice command config, commands disabled"
+                ),
+            }
+            match snippet::SnippetLibrary::compile(&config.snippets) {
+                Ok(snippets) => pipeline.set_snippets(snippets),
+                Err(err) => tracing::warn!(
+                    target: "config",
+                    error = %format!("{err:#}"),
+                    "语音片段配置无效，已停用 | Invalid snippet config, snippets disabled"
+                ),
+            }
+            let plugins = plugin::PluginSet::load(&config.plugins);
+            if config.plugins.enabled && plugins.is_empty() {
+                tracing::warn!(target: "plugin", "插件已启用但没有加载任何插件 | Plugins enabled but none loaded");
+            }
+            pipeline.set_plugins(&config.plugins, plugins);
+            pipeline.set_code_mode(code_mode::CodeFormatter::new(&config.code_mode));
+            pipeline.set_numbers(numbers::NumberFormatter::new(&config.numbers));
+            pipeline.set_language_config(config.language_detection);
+            pipeline.set_punctuation(config.punctuation);
+            pipeline.set_dictionary(dictionary.clone());
+            pipeline.set_reliability(reliability);
+            match sink::create_sinks(&config.sinks, &injector) {
+                Ok(sinks) => pipeline.set_sinks(sinks),
+                Err(err) => tracing::warn!(
+                    target: "config",
+                    error = %format!("{err:#}"),
+                    "输出端配置无效，只输入到当前焦点 | Invalid sinks, typing only"
+                ),
+            }
+            connection.attach(pipeline.asr_connection());
+
+            let (session_tx, session_rx) = mpsc::unbounded_channel::<app_state::SessionJob>();
+            let state = Arc::new(app_state::AppState::new(
+                pipeline,
+                audio_device.clone(),
+                event_tx.clone(),
+                config.busy_policy,
+                config.processing_timeout_ms,
+                config.blocked_apps.clone(),
+                session_tx,
+            ));
+            spawn_session_worker(state.clone(), tray.clone(), session_rx);
+            warmup.refresh(state.clone());
 
             let (hk_tx, mut hk_rx) = mpsc::channel::<HotkeyEvent>(32);
-            spawn_hotkey_listener(hk_tx, hotkey);
+            if config.wake.enabled {
+                let wake_tx = hk_tx.clone();
+                wake::spawn_listener(config.wake.clone(), audio_device.clone(), move |event| {
+                    let _ = wake_tx.blocking_send(match event {
+                        wake::WakeEvent::Detected => HotkeyEvent::Wake,
+                        wake::WakeEvent::Ended => HotkeyEvent::WakeEnded,
+                    });
+                });
+            }
+            // 休眠或锁屏时可能收不到热键的松开事件，由电源事件让监听线程忘掉按住的键
+            let hotkeys_released = Arc::new(AtomicBool::new(false));
+            spawn_hotkey_listener(hk_tx, hotkey, transform_hotkey, hotkeys_released.clone());
+            if config.control.enabled {
+                let (control_tx, control_rx) = mpsc::channel::<control::ControlRequest>(8);
+                control::spawn_server(&config.control, control_tx);
+                spawn_control_handler(state.clone(), tray.clone(), overlay.clone(), control_rx);
+            }
+            {
+                // 休眠或锁屏时结束进行中的会话，避免唤醒后留下仍在“录音”的会话；唤醒后旧连接多半已失效，立即重连
+                let state = state.clone();
+                let tray = tray.clone();
+                power::spawn_watcher(move |event| match event {
+                    power::PowerEvent::Resumed { .. } => {
+                        hotkeys_released.store(true, Ordering::SeqCst);
+                        handle_cancel(&state, &tray);
+                        // 空闲时 handle_cancel 什么也不做，上一次会话还在等的校正同样作废
+                        state.preempt.preempt();
+                        network::reconnect_all();
+                    }
+                    power::PowerEvent::Locked => {
+                        hotkeys_released.store(true, Ordering::SeqCst);
+                        handle_cancel(&state, &tray);
+                        state.preempt.preempt();
+                    }
+                    power::PowerEvent::Unlocked => {}
+                });
+            }
 
             let state_for_task = state.clone();
             let tray_for_task = tray.clone();
+            let overlay_for_task = overlay.clone();
             tauri::async_runtime::spawn(async move {
+                // 当前录音是否由唤醒词开始；按热键接管后由热键结束
+                let mut wake_session = false;
                 while let Some(evt) = hk_rx.recv().await {
                     match evt {
                         HotkeyEvent::Start => {
-                            handle_start(&state_for_task, &tray_for_task).await;
+                            if state_for_task.is_paused() {
+                                debug!(target: "hotkey", "已暂停，忽略热键 | Paused, hotkey ignored");
+                                continue;
+                            }
+                            // 失败已通过事件通知
+                            let _ = handle_start(
+                                &state_for_task,
+                                &tray_for_task,
+                                &overlay_for_task,
+                                app_state::SessionMode::Dictation,
+                            );
+                        }
+                        HotkeyEvent::TransformStart => {
+                            if state_for_task.is_paused() {
+                                debug!(target: "hotkey", "已暂停，忽略热键 | Paused, hotkey ignored");
+                                continue;
+                            }
+                            let _ = handle_start(
+                                &state_for_task,
+                                &tray_for_task,
+                                &overlay_for_task,
+                                app_state::SessionMode::Transform,
+                            );
                         }
                         HotkeyEvent::Stop => {
-                            handle_stop(&state_for_task, &tray_for_task).await;
+                            wake_session = false;
+                            handle_stop(&state_for_task, &tray_for_task);
+                        }
+                        HotkeyEvent::Wake => {
+                            let phase = state_for_task.session.lock().expect("session lock").phase();
+                            if state_for_task.is_paused() || phase != app_state::SessionPhase::Idle {
+                                debug!(
+                                    target: "wake",
+                                    phase = ?phase,
+                                    "忙碌或已暂停，忽略唤醒词 | Busy or paused, wake word ignored"
+                                );
+                                continue;
+                            }
+                            wake_session = handle_start(
+                                &state_for_task,
+                                &tray_for_task,
+                                &overlay_for_task,
+                                app_state::SessionMode::Dictation,
+                            )
+                            .is_ok();
+                        }
+                        HotkeyEvent::WakeEnded => {
+                            if std::mem::take(&mut wake_session) {
+                                handle_stop(&state_for_task, &tray_for_task);
+                            }
                         }
                     }
                 }
             });
 
             app.manage(state);
+            app.manage(injector);
+            app.manage(tray);
+            app.manage(overlay);
+            app.manage(captions);
+            app.manage(hooks);
+            app.manage(notifier);
+            app.manage(history);
+            app.manage(dictionary);
+            app.manage(stats);
+            app.manage(connection);
+            app.manage(warmup);
+            app.manage(Arc::new(logging::LogTail::new()));
             info!(target: "tray", "托盘已就绪 | Tray ready");
 
             // 如果权限缺失，自动弹出窗口提示（否则托盘模式下用户可能不知道）。
-            let accessibility_ok = if cfg!(target_os = "macos") {
-                platform::ensure_accessibility(false)
-            } else {
-                true
-            };
+            let accessibility_ok = platform::ensure_accessibility(false);
+            let input_monitoring_ok = platform::input_monitoring_granted();
+            if !input_monitoring_ok {
+                tracing::warn!(
+                    target: "hotkey",
+                    "未授予输入监控权限，全局热键不会触发 | Input Monitoring not granted, hotkey will not fire"
+                );
+            }
             let microphone_ok = audio::check_microphone_access(audio_device.as_deref());
-            if !accessibility_ok || !microphone_ok {
+            if first_run {
+                tracing::warn!(
+                    target: "config",
+                    "未找到配置文件，进入首次设置向导 | No config found, starting setup wizard"
+                );
+            }
+            if !headless && (first_run || !accessibility_ok || !input_monitoring_ok || !microphone_ok) {
                 if let Some(window) = app.get_webview_window("main") {
                     let _ = window.show();
                     let _ = window.set_focus();
@@ -376,70 +1000,294 @@ fn main() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, _event| {
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                for url in urls {
+                    handle_url(_app, url.as_str());
+                }
+            }
+        });
 }
 
-fn setup_tray(app: &tauri::App) -> tauri::Result<()> {
-    use tauri::menu::{Menu, MenuItem};
-    use tauri::tray::TrayIconBuilder;
+/// Pipeline 事件的界面订阅方，事件可能被丢弃
+struct EventSubscribers {
+    tray: Arc<TrayController>,
+    overlay: Arc<overlay::OverlayController>,
+    captions: Arc<caption::CaptionController>,
+    notifier: Arc<notify::Notifier>,
+}
 
-    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-    let hide = MenuItem::with_id(app, "hide", "Hide", true, None::<&str>)?;
-    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&show, &hide, &quit])?;
+/// 需要完整收到每个事件的订阅方：历史记录、统计、指标、钩子与连接状态
+struct RecordSubscribers {
+    hooks: Arc<hooks::HookRunner>,
+    history: Arc<history::HistoryStore>,
+    stats: Arc<stats::StatsCollector>,
+    connection: Arc<connection::ConnectionMonitor>,
+    warmup: Arc<warmup::Warmup>,
+    metrics: Arc<metrics::Metrics>,
+}
 
-    TrayIconBuilder::with_id(TRAY_ID)
-        .icon(tauri::image::Image::from_bytes(TRAY_IDLE).expect("tray icon"))
-        .menu(&menu)
-        .on_menu_event(|app, event| match event.id().as_ref() {
-            "quit" => app.exit(0),
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
+fn spawn_event_forwarder(
+    app: tauri::AppHandle,
+    mut rx: mpsc::Receiver<events::PipelineEvent>,
+    subscribers: EventSubscribers,
+) {
+    let EventSubscribers {
+        tray,
+        overlay,
+        captions,
+        notifier,
+    } = subscribers;
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            if let Err(err) = app.emit(event.name(), &event) {
+                tracing::debug!(target: "app", error = %err, "前端事件推送失败 | Frontend event emit failed");
+            }
+
+            match event {
+                events::PipelineEvent::SessionStarted { .. } | events::PipelineEvent::Partial { .. } => captions.show(),
+                events::PipelineEvent::AsrFinal { text, .. } => {
+                    tray.set_last_transcript(&text);
+                    captions.hide_later();
+                }
+                events::PipelineEvent::SegmentFinal { text, .. } => tray.set_last_transcript(&text),
+                events::PipelineEvent::Correcting { .. } => tray.set_status_text(Some("校正中… | Correcting…")),
+                events::PipelineEvent::CorrectionApplied { text, .. }
+                | events::PipelineEvent::TransformApplied { text, .. } => {
+                    tray.set_last_transcript(&text);
+                    tray.set_status_text(None);
+                    overlay.hide_later();
+                    captions.hide_later();
+                }
+                events::PipelineEvent::SessionCancelled { .. }
+                | events::PipelineEvent::VoiceCommand { .. }
+                | events::PipelineEvent::CorrectionSkipped { .. }
+                | events::PipelineEvent::TermLearned { .. } => {
+                    tray.set_status_text(None);
+                    overlay.hide_later();
+                    captions.hide_later();
+                }
+                events::PipelineEvent::CorrectionLate(late) => offer_late_correction(&app, notifier.clone(), late),
+                events::PipelineEvent::SessionQueued { pending, .. } => {
+                    overlay.hide_later();
+                    captions.hide_later();
+                    notifier.session_queued(pending);
+                }
+                events::PipelineEvent::DeferredDelivered { text, .. } => notifier.deferred_delivered(&text),
+                events::PipelineEvent::MeetingStarted { .. } => captions.set_pinned(true),
+                events::PipelineEvent::MeetingSegment { text, .. } => tray.set_last_transcript(&text),
+                events::PipelineEvent::MeetingSaved { path, segments, .. } => {
+                    captions.set_pinned(false);
+                    notifier.meeting_saved(&path, segments);
+                    // 录音中断时会议会自行结束，托盘勾选需要跟着更新
+                    tray::refresh_menu(&app, &config::load_with_path().0);
+                }
+                events::PipelineEvent::Failed { error, .. } if error.code == ErrorCode::AppBlocked => {
+                    tray.set_blocked(&error.detail);
+                    notifier.failure(&error);
+                }
+                events::PipelineEvent::Failed { error, .. } => {
+                    tray.set_error();
+                    overlay.hide_later();
+                    captions.hide_later();
+                    notifier.failure(&error);
                 }
+                events::PipelineEvent::AudioLevel { .. }
+                | events::PipelineEvent::ServerSelected { .. }
+                | events::PipelineEvent::Reconfigured { .. }
+                | events::PipelineEvent::SessionSummary(_)
+                | events::PipelineEvent::NetworkUsage { .. } => {}
             }
-            "hide" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.hide();
+        }
+    });
+}
+
+/// 按发出顺序处理记录事件；SQLite 写入放到阻塞线程上，写完再处理下一条
+fn spawn_record_writer(app: tauri::AppHandle, mut records: events::RecordReceiver, subscribers: RecordSubscribers) {
+    let RecordSubscribers {
+        hooks,
+        history,
+        stats,
+        connection,
+        warmup,
+        metrics,
+    } = subscribers;
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = records.recv().await {
+            match event {
+                events::PipelineEvent::SessionStarted {
+                    trace_id,
+                    app_name,
+                    window_title,
+                } => {
+                    hooks.session_started(&trace_id, &app_name);
+                    stats.record_start(&app_name);
+                    metrics.record_start();
+                    history.begin(&trace_id, &app_name, &window_title);
+                }
+                events::PipelineEvent::AsrFinal {
+                    trace_id,
+                    text,
+                    asr_ms,
+                    audio_ms,
+                } => {
+                    hooks.update_text(trace_id.as_deref(), &text);
+                    stats.record_final(audio_ms, asr_ms, text.chars().count());
+                    connection.record_session_ok();
+                    if let Some(trace_id) = trace_id {
+                        write_history(&history, move |history| history.finalize(&trace_id, &text, asr_ms)).await;
+                    }
                 }
+                events::PipelineEvent::SessionSummary(summary) => {
+                    hooks.session_finished(summary.trace_id.as_deref(), summary.corrected);
+                    metrics.record_summary(&summary);
+                }
+                events::PipelineEvent::CorrectionApplied {
+                    trace_id,
+                    text,
+                    latency_ms,
+                } => {
+                    stats.record_correction(latency_ms, text.chars().count());
+                    warmup.record_llm_ok();
+                    hooks.update_text(trace_id.as_deref(), &text);
+                    if let Some(trace_id) = trace_id {
+                        write_history(&history, move |history| {
+                            history.apply_correction(&trace_id, &text, latency_ms)
+                        })
+                        .await;
+                    }
+                }
+                events::PipelineEvent::SessionCancelled { trace_id }
+                | events::PipelineEvent::VoiceCommand { trace_id, .. } => hooks.forget(trace_id.as_deref()),
+                events::PipelineEvent::DeferredDelivered { trace_id, text, .. } => {
+                    hooks.deferred_delivered(trace_id.as_deref(), &text)
+                }
+                events::PipelineEvent::ServerSelected { endpoint, .. } => {
+                    // 连接状态跟随当前会话使用的服务器
+                    connection.attach(Some(network::ConnectionManager::shared(&endpoint)));
+                }
+                events::PipelineEvent::Reconfigured {
+                    asr_replaced,
+                    llm_replaced,
+                    endpoint,
+                } => {
+                    if asr_replaced {
+                        connection.attach(endpoint.map(|endpoint| network::ConnectionManager::shared(&endpoint)));
+                    }
+                    if asr_replaced || llm_replaced {
+                        if let Some(state) = app.try_state::<Arc<app_state::AppState>>() {
+                            warmup.refresh(state.inner().clone());
+                        }
+                    }
+                }
+                events::PipelineEvent::NetworkUsage { traffic, .. } => stats.record_traffic(&traffic),
+                events::PipelineEvent::Failed { error, .. } if error.code == ErrorCode::AppBlocked => {}
+                events::PipelineEvent::Failed { trace_id, error } => {
+                    hooks.failed(trace_id.as_deref(), &error);
+                    if let Some(trace_id) = trace_id {
+                        write_history(&history, move |history| history.record_failure(&trace_id)).await;
+                    }
+                    stats.record_failure();
+                    metrics.record_error(error.code.as_str());
+                    if matches!(error.code, ErrorCode::ServerUnreachable | ErrorCode::AsrFailed) {
+                        connection.record_session_failed(&error.detail);
+                    }
+                }
+                _ => {}
             }
-            _ => {}
-        })
-        .build(app)?;
+            records.done();
+        }
+    });
+}
 
-    Ok(())
+async fn write_history(
+    history: &Arc<history::HistoryStore>,
+    write: impl FnOnce(&history::HistoryStore) -> anyhow::Result<()> + Send + 'static,
+) {
+    let history = history.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || write(&history))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    if let Err(err) = result {
+        tracing::warn!(target: "history", error = %err, "历史记录写入失败 | History write failed");
+    }
 }
 
-fn spawn_hotkey_listener(tx: mpsc::Sender<HotkeyEvent>, hotkey: String) {
+fn spawn_hotkey_listener(
+    tx: mpsc::Sender<HotkeyEvent>,
+    hotkey: String,
+    transform_hotkey: Option<String>,
+    released: Arc<AtomicBool>,
+) {
     std::thread::spawn(move || {
         let hotkey = parse_hotkey(&hotkey);
+        let transform_hotkey = transform_hotkey.as_deref().filter(|raw| !raw.trim().is_empty());
+        let transform_hotkey = transform_hotkey.and_then(|raw| match hotkey_key(raw) {
+            Some(key) if key != hotkey => Some(key),
+            _ => {
+                tracing::warn!(
+                    target: "config",
+                    hotkey = %raw,
+                    "改写热键无效或与听写热键相同，已忽略 | Transform hotkey unknown or same as dictation hotkey, ignored"
+                );
+                None
+            }
+        });
         info!(
             target: "hotkey",
             key = ?hotkey,
+            transform_key = ?transform_hotkey,
             "热键监听器已启动 | Hotkey listener started"
         );
-        let listen_result = rdev::listen(move |event| match event.event_type {
-            EventType::KeyPress(key) if key == hotkey => {
-                debug!(
-                    target: "hotkey",
-                    action = "press",
-                    key = ?key,
-                    "热键事件 | Hotkey event"
-                );
-                let _ = tx.blocking_send(HotkeyEvent::Start);
+        // 按住热键时系统会重复发送 KeyPress：每次按下只发一次开始，松开只发一次结束
+        let mut held: Vec<Key> = Vec::new();
+        let listen_result = rdev::listen(move |event| {
+            // 松开事件丢失（锁屏、休眠）后不清掉的话，这个键再也触发不了开始
+            if released.swap(false, Ordering::SeqCst) {
+                for key in held.drain(..) {
+                    shutdown::track_hotkey(key, false);
+                }
             }
-            EventType::KeyRelease(key) if key == hotkey => {
-                debug!(
-                    target: "hotkey",
-                    action = "release",
-                    key = ?key,
-                    "热键事件 | Hotkey event"
-                );
-                let _ = tx.blocking_send(HotkeyEvent::Stop);
+            match event.event_type {
+                EventType::KeyPress(key) if key == hotkey || Some(key) == transform_hotkey => {
+                    if held.contains(&key) {
+                        return;
+                    }
+                    held.push(key);
+                    shutdown::track_hotkey(key, true);
+                    debug!(
+                        target: "hotkey",
+                        action = "press",
+                        key = ?key,
+                        "热键事件 | Hotkey event"
+                    );
+                    let _ = tx.blocking_send(if key == hotkey {
+                        HotkeyEvent::Start
+                    } else {
+                        HotkeyEvent::TransformStart
+                    });
+                }
+                EventType::KeyRelease(key) if key == hotkey || Some(key) == transform_hotkey => {
+                    // 监听开始前就按住的键，松开时没有对应的开始
+                    let Some(index) = held.iter().position(|held| *held == key) else {
+                        return;
+                    };
+                    held.swap_remove(index);
+                    shutdown::track_hotkey(key, false);
+                    debug!(
+                        target: "hotkey",
+                        action = "release",
+                        key = ?key,
+                        "热键事件 | Hotkey event"
+                    );
+                    let _ = tx.blocking_send(HotkeyEvent::Stop);
+                }
+                _ => {}
             }
-            _ => {}
         });
 
         if let Err(err) = listen_result {
@@ -453,7 +1301,22 @@ fn spawn_hotkey_listener(tx: mpsc::Sender<HotkeyEvent>, hotkey: String) {
 }
 
 fn parse_hotkey(raw: &str) -> Key {
-    match raw.trim().to_ascii_lowercase().as_str() {
+    hotkey_key(raw).unwrap_or_else(|| {
+        tracing::warn!(
+            target: "config",
+            hotkey = %raw,
+            "未知热键，使用默认 | Unknown hotkey, using default"
+        );
+        if cfg!(target_os = "macos") {
+            Key::F8
+        } else {
+            Key::CapsLock
+        }
+    })
+}
+
+fn hotkey_key(raw: &str) -> Option<Key> {
+    Some(match raw.trim().to_ascii_lowercase().as_str() {
         // === 推荐热键 (业界已验证) ===
         "capslock" | "caps_lock" | "caps lock" | "caps" => Key::CapsLock,
         "f5" => Key::F5,
@@ -473,31 +1336,62 @@ fn parse_hotkey(raw: &str) -> Key {
         "right_shift" | "right shift" | "rshift" => Key::ShiftRight,
         "left_shift" | "left shift" | "lshift" => Key::ShiftLeft,
 
-        _ => {
-            tracing::warn!(
-                target: "config",
-                hotkey = %raw,
-                "未知热键，使用默认 | Unknown hotkey, using default"
-            );
-            if cfg!(target_os = "macos") {
-                Key::F8
-            } else {
-                Key::CapsLock
-            }
+        _ => return None,
+    })
+}
+
+/// 按下热键：立即开始录音；上一次会话仍在识别时按 `busy_policy` 排队或抢占
+fn handle_start(
+    state: &Arc<app_state::AppState>,
+    tray: &Arc<TrayController>,
+    overlay: &Arc<overlay::OverlayController>,
+    mode: app_state::SessionMode,
+) -> Result<(), AppError> {
+    let action = state.session.lock().expect("session lock").start();
+    match action {
+        app_state::StartAction::Ignore => return Ok(()),
+        app_state::StartAction::Busy => {
+            info!(target: "pipeline", "待识别的会话过多，忽略热键 | Too many sessions pending, hotkey ignored");
+            let error = AppError::new(ErrorCode::Busy, "上一次录音仍在识别中");
+            state.events.send(events::PipelineEvent::Failed {
+                trace_id: None,
+                error: error.clone(),
+            });
+            return Err(error);
         }
+        _ => {}
     }
-}
 
-async fn handle_start(state: &Arc<app_state::AppState>, tray: &Arc<TrayController>) {
-    {
-        let guard = state.audio.lock().expect("audio lock");
-        if guard.is_some() {
-            return;
+    let active_window = active_win_pos_rs::get_active_window().ok();
+    if let Some(window) = active_window.as_ref().filter(|window| state.is_app_blocked(&window.app_name)) {
+        info!(
+            target: "pipeline",
+            app = %window.app_name,
+            "当前应用已禁用听写，忽略热键 | Dictation disabled in the active app, hotkey ignored"
+        );
+        state.session.lock().expect("session lock").abort();
+        let error = AppError::new(ErrorCode::AppBlocked, window.app_name.clone());
+        state.events.send(events::PipelineEvent::Failed {
+            trace_id: None,
+            error: error.clone(),
+        });
+        return Err(error);
+    }
+
+    // 确认可以开始后再处理识别中的会话，被拒绝的热键不会抢占上一次的结果
+    match action {
+        app_state::StartAction::Preempt => {
+            info!(target: "pipeline", "新录音抢占识别中的会话 | New recording preempts the pending session");
+            state.preempt.preempt();
+        }
+        app_state::StartAction::Queue => {
+            info!(target: "pipeline", "新录音排在识别中的会话之后 | New recording queued behind the pending session");
         }
+        _ => {}
     }
 
     let trace_id = generate_trace_id();
-    let context = get_active_context().unwrap_or_default();
+    let context = active_window.as_ref().map(active_context).unwrap_or_default();
     let (recorder, mut pcm_rx) = match audio::start_audio(trace_id.clone(), state.audio_device.clone()) {
         Ok(parts) => parts,
         Err(err) => {
@@ -506,95 +1400,392 @@ async fn handle_start(state: &Arc<app_state::AppState>, tray: &Arc<TrayControlle
                 error = %err,
                 "麦克风访问失败 | Microphone access failed"
             );
+            state.session.lock().expect("session lock").abort();
             tray.set_error();
-            return;
+            let error = AppError::new(ErrorCode::MicPermission, err.to_string());
+            state.events.send(events::PipelineEvent::Failed {
+                trace_id: Some(trace_id),
+                error: error.clone(),
+            });
+            return Err(error);
         }
     };
 
-    let sample_rate = recorder.sample_rate;
-    let session_gen = {
-        let mut pipeline = state.pipeline.lock().await;
-        match pipeline.start(trace_id.clone(), sample_rate, context).await {
-            Ok(gen) => gen,
-            Err(err) => {
-                error!(
-                    target: "pipeline",
-                    trace_id = trace_id.as_str(),
-                    error = %err,
-                    "ASR 会话启动失败 | ASR session start failed"
-                );
+    // 识别前一个会话时 worker 暂不读取音频，这里先转存，避免录音线程阻塞丢帧
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (frames_tx, frames) = mpsc::unbounded_channel();
+    tauri::async_runtime::spawn(async move {
+        while let Some(frame) = pcm_rx.recv().await {
+            if frames_tx.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+    let job = app_state::SessionJob {
+        trace_id,
+        sample_rate: recorder.sample_rate,
+        context,
+        mode,
+        frames,
+        cancelled: cancelled.clone(),
+    };
+    *state.audio.lock().expect("audio lock") = Some(recorder);
+    *state.recording_cancel.lock().expect("recording cancel lock") = Some(cancelled);
+    let _ = state.sessions.send(job);
+
+    tray.set_recording();
+    overlay.show_near(
+        active_window.as_ref().map(|window| &window.position),
+        platform::caret_position().as_ref(),
+    );
+    Ok(())
+}
+
+/// 松开热键：结束录音，识别交给会话 worker，不阻塞下一次热键；没有正在进行的录音时返回 `false`
+fn handle_stop(state: &Arc<app_state::AppState>, tray: &Arc<TrayController>) -> bool {
+    if !state.session.lock().expect("session lock").stop() {
+        // 没有正在进行的录音，不发送 Stop
+        return false;
+    }
+    state.recording_cancel.lock().expect("recording cancel lock").take();
+    if let Some(recorder) = state.audio.lock().expect("audio lock").take() {
+        recorder.stop();
+    }
+    tray.set_processing();
+    true
+}
+
+/// 取消当前会话：录音中则停止录音并丢弃，不请求识别结果；已松开热键的会话丢弃其结果
+fn handle_cancel(state: &Arc<app_state::AppState>, tray: &Arc<TrayController>) -> bool {
+    let phase = {
+        let mut machine = state.session.lock().expect("session lock");
+        let phase = machine.phase();
+        if phase == app_state::SessionPhase::Recording {
+            // worker 仍会处理这个会话（只是不识别），处理完后计入 finished
+            machine.stop();
+        }
+        phase
+    };
+    match phase {
+        app_state::SessionPhase::Idle => return false,
+        app_state::SessionPhase::Recording => {
+            if let Some(cancelled) = state.recording_cancel.lock().expect("recording cancel lock").take() {
+                cancelled.store(true, Ordering::SeqCst);
+            }
+            if let Some(recorder) = state.audio.lock().expect("audio lock").take() {
                 recorder.stop();
-                tray.set_error();
-                return;
             }
+            tray.set_processing();
         }
+        app_state::SessionPhase::Processing => state.preempt.preempt(),
+    }
+    info!(target: "pipeline", phase = ?phase, "已取消当前会话 | Current session cancelled");
+    true
+}
+
+/// 执行本地控制接口的请求，与热键走同一套开始/结束逻辑
+fn spawn_control_handler(
+    state: Arc<app_state::AppState>,
+    tray: Arc<TrayController>,
+    overlay: Arc<overlay::OverlayController>,
+    mut requests: mpsc::Receiver<control::ControlRequest>,
+) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(control::ControlRequest { action, reply }) = requests.recv().await {
+            let result = run_control_action(&state, &tray, &overlay, action);
+            let (ok, error) = match result {
+                Ok(ok) => (ok, None),
+                Err(error) => (false, Some(error)),
+            };
+            let _ = reply.send(control::ControlReply {
+                ok,
+                phase: state.session.lock().expect("session lock").phase(),
+                paused: state.is_paused(),
+                meeting: state.is_meeting_active(),
+                error,
+            });
+        }
+    });
+}
+
+/// 执行控制动作（本地控制接口与 `ghosttype://` 链接共用）；返回动作是否生效
+fn run_control_action(
+    state: &Arc<app_state::AppState>,
+    tray: &Arc<TrayController>,
+    overlay: &Arc<overlay::OverlayController>,
+    action: control::ControlAction,
+) -> Result<bool, AppError> {
+    let phase = state.session.lock().expect("session lock").phase();
+    let start = || {
+        if state.is_paused() {
+            return Err(AppError::new(ErrorCode::Paused, ""));
+        }
+        handle_start(state, tray, overlay, app_state::SessionMode::Dictation)
     };
+    match action {
+        control::ControlAction::Status => Ok(true),
+        control::ControlAction::Start if phase == app_state::SessionPhase::Recording => Ok(false),
+        control::ControlAction::Start => start().map(|()| true),
+        control::ControlAction::Stop => Ok(handle_stop(state, tray)),
+        control::ControlAction::Toggle if phase == app_state::SessionPhase::Recording => Ok(handle_stop(state, tray)),
+        control::ControlAction::Toggle => start().map(|()| true),
+        control::ControlAction::Cancel => Ok(handle_cancel(state, tray)),
+    }
+}
 
-    {
-        let mut guard = state.audio.lock().expect("audio lock");
-        if guard.is_some() {
-            // 竞态：另一个 Start 已经抢先，停止当前 recorder 避免泄漏
-            drop(guard);
-            recorder.stop();
+/// macOS 通过 `ghosttype://` 链接触发的动作（快捷指令、Raycast、AppleScript 的 `open location`、终端的 `open`）
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn handle_url(app: &tauri::AppHandle, url: &str) {
+    let action = match url_scheme::parse(url) {
+        Ok(action) => action,
+        Err(err) => {
+            tracing::warn!(target: "app", url = %url, error = %err, "无法处理的链接 | Unhandled URL");
             return;
         }
-        *guard = Some(recorder);
+    };
+    info!(target: "app", action = ?action, "收到链接请求 | URL action received");
+    let (Some(state), Some(tray), Some(overlay), Some(notifier)) = (
+        app.try_state::<Arc<app_state::AppState>>(),
+        app.try_state::<Arc<TrayController>>(),
+        app.try_state::<Arc<overlay::OverlayController>>(),
+        app.try_state::<Arc<notify::Notifier>>(),
+    ) else {
+        tracing::warn!(target: "app", "应用尚未就绪，忽略链接 | App not ready, URL ignored");
+        return;
+    };
+    match action {
+        url_scheme::UrlAction::Control(action) => {
+            if let Err(error) = run_control_action(state.inner(), tray.inner(), overlay.inner(), action) {
+                notifier.failure(&error);
+            }
+        }
+        url_scheme::UrlAction::TranscribeClipboard => {
+            let notifier = notifier.inner().clone();
+            tauri::async_runtime::spawn(async move {
+                match audio_file::transcribe_clipboard().await {
+                    Ok(text) => notifier.audio_transcribed(&text),
+                    Err(error) => notifier.failure(&error),
+                }
+            });
+        }
     }
+}
 
-    tray.set_recording();
-
-    *state.session_gen.lock().expect("session gen lock") = Some(session_gen);
+/// 校正超出替换时限：在通知中让用户选择应用或复制（仅 Windows 支持带按钮的通知）
+fn offer_late_correction(app: &tauri::AppHandle, notifier: Arc<notify::Notifier>, late: events::LateCorrection) {
+    if !cfg!(windows) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let toast_notifier = notifier.clone();
+        let text = late.text.clone();
+        let choice = tauri::async_runtime::spawn_blocking(move || toast_notifier.late_correction(&text))
+            .await
+            .ok()
+            .flatten();
+        let applied = match choice {
+            None => return,
+            Some(notify::LateCorrectionChoice::Copy) => None,
+            Some(notify::LateCorrectionChoice::Apply) => {
+                let Some(state) = app.try_state::<Arc<app_state::AppState>>() else {
+                    return;
+                };
+                let result = state.apply_late_correction(&late).await;
+                if let Err(err) = &result {
+                    info!(
+                        target: "app",
+                        trace_id = late.trace_id.as_deref().unwrap_or(""),
+                        reason = %format!("{err:#}"),
+                        "迟到的校正无法替换，改为复制 | Late correction not applied, copying instead"
+                    );
+                }
+                Some(result.is_ok())
+            }
+        };
+        if applied == Some(true) {
+            return;
+        }
+        if let Err(err) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(late.text.clone())) {
+            error!(target: "app", error = %err, "写入剪贴板失败 | Clipboard write failed");
+            return;
+        }
+        if applied == Some(false) {
+            notifier.late_correction_copied();
+        }
+    });
+}
 
-    let state_for_task = state.clone();
-    let task = tauri::async_runtime::spawn(async move {
-        while let Some(frame) = pcm_rx.recv().await {
-            let mut pipeline = state_for_task.pipeline.lock().await;
-            if let Err(err) = pipeline.feed_audio(&frame).await {
-                tracing::warn!(
-                    target: "audio",
-                    error = %err,
-                    "ASR 音频发送失败 | ASR feed_audio failed"
-                );
-                break;
+/// 按热键顺序逐个处理会话：开始 → 送入音频 → 识别，同一时间 Pipeline 只有一个会话
+fn spawn_session_worker(
+    state: Arc<app_state::AppState>,
+    tray: Arc<TrayController>,
+    mut jobs: mpsc::UnboundedReceiver<app_state::SessionJob>,
+) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(job) = jobs.recv().await {
+            let outcome = run_session(&state, job).await;
+            let phase = state.session.lock().expect("session lock").finished();
+            if phase == app_state::SessionPhase::Idle {
+                tray.set_idle();
+            }
+            match outcome {
+                SessionOutcome::Done => {}
+                SessionOutcome::Failed => tray.set_error(),
+                SessionOutcome::TimedOut => {
+                    tray.set_error();
+                    let tray = tray.clone();
+                    tauri::async_runtime::spawn(async move {
+                        tokio::time::sleep(STUCK_ERROR_HOLD).await;
+                        tray.clear_error();
+                    });
+                }
             }
         }
     });
-    *state.audio_task.lock().expect("audio task lock") = Some(task);
 }
 
-async fn handle_stop(state: &Arc<app_state::AppState>, tray: &Arc<TrayController>) {
-    let recorder = state.audio.lock().expect("audio lock").take();
-    let Some(recorder) = recorder else {
-        // 没有正在进行的录音，不发送 Stop
-        return;
-    };
+/// 识别超时后托盘显示错误的时长，之后回到空闲
+const STUCK_ERROR_HOLD: std::time::Duration = std::time::Duration::from_secs(5);
 
-    let task = state.audio_task.lock().expect("audio task lock").take();
-    let session_gen = state.session_gen.lock().expect("session gen lock").take().unwrap_or(0);
+/// 超时后取消会话的时限：取消时也可能卡在同一个连接上
+const STUCK_CANCEL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
 
-    recorder.stop();
+/// 一个会话的处理结果，决定托盘状态
+enum SessionOutcome {
+    Done,
+    Failed,
+    /// 超过 `processing_timeout_ms` 仍未识别完成，会话已放弃
+    TimedOut,
+}
 
-    tray.set_processing();
+async fn run_session(state: &app_state::AppState, job: app_state::SessionJob) -> SessionOutcome {
+    let app_state::SessionJob {
+        trace_id,
+        sample_rate,
+        context,
+        mode,
+        mut frames,
+        cancelled,
+    } = job;
+    // 排队期间已被取消：不开始 ASR 会话
+    if cancelled.load(Ordering::SeqCst) {
+        while frames.recv().await.is_some() {}
+        return SessionOutcome::Done;
+    }
+
+    let started = {
+        let mut pipeline = state.pipeline.lock().await;
+        match mode {
+            app_state::SessionMode::Dictation => pipeline.start(trace_id.clone(), sample_rate, context).await,
+            app_state::SessionMode::Transform => pipeline.start_transform(trace_id.clone(), sample_rate, context).await,
+        }
+    };
+    let session_gen = match started {
+        Ok(gen) => gen,
+        Err(err) => {
+            error!(
+                target: "pipeline",
+                trace_id = trace_id.as_str(),
+                error = %format!("{err:#}"),
+                "ASR 会话启动失败 | ASR session start failed"
+            );
+            state.events.send(events::PipelineEvent::Failed {
+                trace_id: Some(trace_id.clone()),
+                error: AppError::with_code(ErrorCode::ServerUnreachable)(err),
+            });
+            // 仍在录音时直接结束，松开热键时不再有会话要处理
+            let recorder = {
+                let mut audio = state.audio.lock().expect("audio lock");
+                if audio.as_ref().is_some_and(|recorder| recorder.trace_id == trace_id) {
+                    state.session.lock().expect("session lock").stop();
+                    audio.take()
+                } else {
+                    None
+                }
+            };
+            if let Some(recorder) = recorder {
+                recorder.stop();
+            }
+            return SessionOutcome::Failed;
+        }
+    };
 
-    if let Some(task) = task {
-        let _ = task.await;
+    while let Some(frame) = frames.recv().await {
+        if cancelled.load(Ordering::SeqCst) {
+            break;
+        }
+        let mut pipeline = state.pipeline.lock().await;
+        if let Err(err) = pipeline.feed_audio(&frame).await {
+            tracing::warn!(
+                target: "audio",
+                error = %err,
+                "ASR 音频发送失败 | ASR feed_audio failed"
+            );
+            break;
+        }
     }
+    // 发送失败后仍等到松开热键再结束会话
+    while frames.recv().await.is_some() {}
 
-    let mut pipeline = state.pipeline.lock().await;
-    let stop_result = pipeline.stop(session_gen).await;
+    if cancelled.load(Ordering::SeqCst) {
+        state.pipeline.lock().await.cancel().await;
+        return SessionOutcome::Done;
+    }
+    let stop = async { state.pipeline.lock().await.stop(session_gen).await };
+    let stop_result = match state.processing_timeout() {
+        Some(limit) => match tokio::time::timeout(limit, stop).await {
+            Ok(result) => result,
+            Err(_) => {
+                abandon_stuck_session(state, &trace_id, limit).await;
+                return SessionOutcome::TimedOut;
+            }
+        },
+        None => stop.await,
+    };
     match stop_result {
-        Ok(()) => tray.set_idle(),
+        Ok(()) => SessionOutcome::Done,
         Err(err) => {
             error!(
                 target: "pipeline",
-                error = %err,
+                trace_id = trace_id.as_str(),
+                error = %format!("{err:#}"),
                 "会话处理失败 | Session failed"
             );
-            tray.set_error();
+            state.events.send(events::PipelineEvent::Failed {
+                trace_id: Some(trace_id),
+                error: AppError::with_code(ErrorCode::AsrFailed)(err),
+            });
+            SessionOutcome::Failed
         }
     }
 }
 
+/// 识别卡住（服务器无响应、LLM 挂起）：放弃这个会话并重置 Pipeline，通知用户；之后的会话照常进行
+async fn abandon_stuck_session(state: &app_state::AppState, trace_id: &str, limit: std::time::Duration) {
+    error!(
+        target: "pipeline",
+        trace_id = trace_id,
+        limit_ms = limit.as_millis() as u64,
+        "识别超时，已放弃本次会话 | Processing timed out, session abandoned"
+    );
+    let cancel = async { state.pipeline.lock().await.cancel().await };
+    if tokio::time::timeout(STUCK_CANCEL_TIMEOUT, cancel).await.is_err() {
+        tracing::warn!(
+            target: "pipeline",
+            trace_id = trace_id,
+            "超时会话的取消也未完成 | Cancelling the timed-out session did not finish"
+        );
+    }
+    state.events.send(events::PipelineEvent::Failed {
+        trace_id: Some(trace_id.to_string()),
+        error: AppError::new(
+            ErrorCode::AsrFailed,
+            format!("{} 秒内没有识别结果，已放弃本次听写", limit.as_secs_f32()),
+        ),
+    });
+}
+
 fn generate_trace_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -615,15 +1806,11 @@ fn generate_trace_id() -> String {
     String::from_utf8_lossy(&out).to_string()
 }
 
-fn get_active_context() -> Option<asr::AsrContext> {
-    let ActiveWindow {
-        app_name,
-        title,
-        ..
-    } = active_win_pos_rs::get_active_window().ok()?;
-
-    Some(asr::AsrContext {
-        app_name,
-        window_title: title,
-    })
+fn active_context(window: &ActiveWindow) -> asr::AsrContext {
+    asr::AsrContext {
+        app_name: window.app_name.clone(),
+        window_title: window.title.clone(),
+        field: input_field::InputField::focused(),
+        ..Default::default()
+    }
 }
@@ -0,0 +1,429 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+use crate::asr::{self, AsrConfig, AsrContext, AsrEngine};
+use crate::audio::{self, AudioRecorder, AudioSource};
+use crate::error::{AppError, ErrorCode};
+use crate::events::{EventSender, PipelineEvent};
+use crate::pipeline;
+use crate::segment::{SegmentConfig, Segmenter};
+
+/// 一段至少这么长才在停顿处切分，避免转写文件里全是零碎的短句
+const MIN_SEGMENT_MS: u64 = 10_000;
+
+/// 会议转写：从托盘开启后持续录音，按停顿或固定时长分段识别，逐段追加到带时间戳的文件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MeetingConfig {
+    /// 转写文件目录；为空时使用程序目录下的 `data/meetings`
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    #[serde(default)]
+    pub source: AudioSource,
+    /// 录音设备；为空时使用听写的麦克风（回环录音时为默认输出设备）
+    #[serde(default)]
+    pub device: Option<String>,
+    /// 期望识别语言，为空时由服务端自动判断
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 一段最长的时长（秒），到达后强制识别并写入文件
+    #[serde(default = "default_chunk_secs")]
+    pub chunk_secs: u64,
+    /// 停顿多久视为一段话结束
+    #[serde(default = "default_pause_ms")]
+    pub pause_ms: u64,
+}
+
+impl Default for MeetingConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            source: AudioSource::default(),
+            device: None,
+            language: None,
+            chunk_secs: default_chunk_secs(),
+            pause_ms: default_pause_ms(),
+        }
+    }
+}
+
+fn default_chunk_secs() -> u64 {
+    60
+}
+
+fn default_pause_ms() -> u64 {
+    1_200
+}
+
+/// 正在进行的会议转写；`stop` 结束录音、识别最后一段并返回保存的文件
+pub struct MeetingRecorder {
+    pub path: PathBuf,
+    stop_tx: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MeetingRecorder {
+    pub async fn stop(self) -> PathBuf {
+        let _ = self.stop_tx.send(());
+        if let Err(err) = self.task.await {
+            warn!(
+                target: "meeting",
+                error = %err,
+                "会议转写任务异常退出 | Meeting task aborted"
+            );
+        }
+        self.path
+    }
+
+    /// 录音中断或识别无法继续时任务会自行结束
+    pub fn is_finished(&self) -> bool {
+        self.task.inner().is_finished()
+    }
+}
+
+/// 开始会议转写：使用独立的 ASR 引擎与录音，不占用热键听写的会话
+pub async fn start(
+    config: &MeetingConfig,
+    asr_config: &AsrConfig,
+    fallback_device: Option<String>,
+    events: EventSender,
+) -> anyhow::Result<MeetingRecorder> {
+    let started_at = Local::now();
+    let dir = resolve_output_dir(config);
+    fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(file_name(started_at));
+    let transcript = Transcript::create(&path, started_at)?;
+
+    let mut engine = asr::create_engine(asr_config).context("创建 ASR 引擎失败")?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "meeting".to_string());
+    let device = match config.source {
+        AudioSource::Microphone => config.device.clone().or(fallback_device),
+        AudioSource::Loopback => config.device.clone(),
+    };
+    let (recorder, frames) = audio::start_capture(name.clone(), config.source, device)
+        .map_err(|err| AppError::new(ErrorCode::AudioDevice, format!("{err:#}")))?;
+
+    let context = AsrContext {
+        app_name: "meeting".to_string(),
+        language: config.language.clone(),
+        ..AsrContext::default()
+    };
+    let sample_rate = recorder.sample_rate;
+    if let Err(err) = engine.start(format!("{name}.1"), sample_rate, context.clone()).await {
+        recorder.stop();
+        return Err(err.context("开始识别失败"));
+    }
+
+    let segment_config = SegmentConfig {
+        enabled: true,
+        max_segment_ms: config.chunk_secs.max(5) * 1000,
+        pause_ms: config.pause_ms,
+        min_segment_ms: MIN_SEGMENT_MS,
+    };
+    info!(
+        target: "meeting",
+        path = %path.display(),
+        source = ?config.source,
+        sample_rate = sample_rate,
+        "会议转写开始 | Meeting transcription started"
+    );
+    events.send(PipelineEvent::MeetingStarted {
+        trace_id: Some(name.clone()),
+        path: path.display().to_string(),
+    });
+
+    let driver = MeetingDriver {
+        name,
+        engine,
+        context,
+        sample_rate,
+        segmenter: Segmenter::new(&segment_config, sample_rate),
+        max_chunk_samples: segment_config.max_segment_ms * u64::from(sample_rate) / 1000,
+        transcript,
+        events,
+        fed_samples: 0,
+        chunk_start: 0,
+        chunk_index: 1,
+        segments: 0,
+    };
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let task = tauri::async_runtime::spawn(driver.run(recorder, frames, stop_rx));
+
+    Ok(MeetingRecorder { path, stop_tx, task })
+}
+
+/// 会议的会话驱动：录音不停，每段结束时停止 ASR 会话、写入文件，再开始下一段
+struct MeetingDriver {
+    name: String,
+    engine: Box<dyn AsrEngine>,
+    context: AsrContext,
+    sample_rate: u32,
+    segmenter: Segmenter,
+    max_chunk_samples: u64,
+    transcript: Transcript,
+    events: EventSender,
+    /// 会议开始以来送入的样本数，用来计算时间戳
+    fed_samples: u64,
+    /// 当前段开始时的 `fed_samples`
+    chunk_start: u64,
+    chunk_index: u32,
+    /// 已写入文件的段数
+    segments: u32,
+}
+
+impl MeetingDriver {
+    async fn run(
+        mut self,
+        recorder: AudioRecorder,
+        mut frames: mpsc::Receiver<Vec<i16>>,
+        mut stop_rx: oneshot::Receiver<()>,
+    ) {
+        let mut running = true;
+        while running {
+            tokio::select! {
+                _ = &mut stop_rx => break,
+                frame = frames.recv() => {
+                    let Some(frame) = frame else {
+                        warn!(target: "meeting", "录音已中断 | Recording interrupted");
+                        break;
+                    };
+                    running = self.push(&frame).await;
+                }
+            }
+        }
+
+        recorder.stop();
+        if running {
+            // 录音停止后通道里剩下的帧仍属于最后一段
+            while let Ok(frame) = frames.try_recv() {
+                if self.engine.feed_audio(&frame).await.is_ok() {
+                    self.fed_samples += frame.len() as u64;
+                }
+            }
+            self.finish_chunk().await;
+        }
+
+        let duration_ms = self.offset_ms(self.fed_samples);
+        info!(
+            target: "meeting",
+            path = %self.transcript.path.display(),
+            segments = self.segments,
+            duration_ms = duration_ms,
+            "会议转写已保存 | Meeting transcript saved"
+        );
+        self.events.send(PipelineEvent::MeetingSaved {
+            trace_id: Some(self.name.clone()),
+            path: self.transcript.path.display().to_string(),
+            segments: self.segments,
+            duration_ms,
+        });
+    }
+
+    /// 送入一帧；返回 false 表示无法继续识别，会议结束
+    async fn push(&mut self, pcm: &[i16]) -> bool {
+        if let Err(err) = self.engine.feed_audio(pcm).await {
+            warn!(
+                target: "meeting",
+                trace_id = %self.trace_id(),
+                error = %format!("{err:#}"),
+                "音频发送失败，提前结束这一段 | Audio feed failed, closing the segment early"
+            );
+            self.finish_chunk().await;
+            return self.next_chunk().await;
+        }
+        self.fed_samples += pcm.len() as u64;
+        // 流式引擎的中间结果转发给实时字幕
+        let trace_id = self.trace_id();
+        pipeline::forward_partials(self.engine.events(), &self.events, Some(&trace_id));
+
+        if self.segmenter.push(pcm) {
+            self.finish_chunk().await;
+            return self.next_chunk().await;
+        }
+        if self.fed_samples - self.chunk_start >= self.max_chunk_samples {
+            // 整段没有说话：不请求识别，直接开始下一段，避免 ASR 会话无限增长
+            self.engine.cancel().await;
+            return self.next_chunk().await;
+        }
+        true
+    }
+
+    async fn finish_chunk(&mut self) {
+        let trace_id = self.trace_id();
+        let stopped = self.engine.stop().await;
+        if let Some(traffic) = self.engine.take_traffic() {
+            self.events.send(PipelineEvent::NetworkUsage {
+                trace_id: Some(trace_id.clone()),
+                traffic,
+            });
+        }
+        let text = match stopped {
+            Ok(text) => text,
+            Err(err) => {
+                // 会议转写不补交离线会话：连接恢复时录音早已继续，补交的段落会错位
+                drop(self.engine.take_deferred());
+                warn!(
+                    target: "meeting",
+                    trace_id = %trace_id,
+                    error = %format!("{err:#}"),
+                    "会议分段识别失败 | Meeting segment recognition failed"
+                );
+                self.events.send(PipelineEvent::Failed {
+                    trace_id: Some(trace_id),
+                    error: AppError::new(ErrorCode::AsrFailed, format!("{err:#}")),
+                });
+                return;
+            }
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+
+        let offset_ms = self.offset_ms(self.chunk_start);
+        if let Err(err) = self.transcript.append(Duration::from_millis(offset_ms), text) {
+            warn!(
+                target: "meeting",
+                path = %self.transcript.path.display(),
+                error = %format!("{err:#}"),
+                "会议转写写入失败 | Meeting transcript write failed"
+            );
+            self.events.send(PipelineEvent::Failed {
+                trace_id: Some(trace_id),
+                error: AppError::new(ErrorCode::Io, format!("{err:#}")),
+            });
+            return;
+        }
+        self.segments += 1;
+        self.events.send(PipelineEvent::MeetingSegment {
+            trace_id: Some(trace_id),
+            offset_ms,
+            text: text.to_string(),
+        });
+    }
+
+    async fn next_chunk(&mut self) -> bool {
+        self.chunk_index += 1;
+        self.chunk_start = self.fed_samples;
+        let trace_id = self.trace_id();
+        match self.engine.start(trace_id.clone(), self.sample_rate, self.context.clone()).await {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(
+                    target: "meeting",
+                    trace_id = %trace_id,
+                    error = %format!("{err:#}"),
+                    "无法开始下一段，会议转写结束 | Next segment failed to start, meeting stopped"
+                );
+                self.events.send(PipelineEvent::Failed {
+                    trace_id: Some(trace_id),
+                    error: AppError::new(ErrorCode::ServerUnreachable, format!("{err:#}")),
+                });
+                false
+            }
+        }
+    }
+
+    /// 每段使用独立的 trace_id，服务端按它区分会话
+    fn trace_id(&self) -> String {
+        format!("{}.{}", self.name, self.chunk_index)
+    }
+
+    fn offset_ms(&self, samples: u64) -> u64 {
+        samples * 1000 / u64::from(self.sample_rate.max(1))
+    }
+}
+
+/// 转写文件：每写入一段就落盘，会议中途退出也不会丢失已识别的内容
+struct Transcript {
+    path: PathBuf,
+    file: File,
+}
+
+impl Transcript {
+    fn create(path: &Path, started_at: DateTime<Local>) -> anyhow::Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open {}", path.display()))?;
+        writeln!(
+            file,
+            "# 会议转写 | Meeting transcript · {}\n",
+            started_at.format("%Y-%m-%d %H:%M:%S")
+        )?;
+        file.sync_data()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    fn append(&mut self, offset: Duration, text: &str) -> anyhow::Result<()> {
+        self.file.write_all(transcript_line(offset, text).as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// 每段一行：`[hh:mm:ss] 文本`，时间为这一段开始时距会议开始的时长
+fn transcript_line(offset: Duration, text: &str) -> String {
+    let secs = offset.as_secs();
+    format!("[{:02}:{:02}:{:02}] {text}\n", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn file_name(started_at: DateTime<Local>) -> String {
+    format!("meeting-{}.md", started_at.format("%Y%m%d-%H%M%S"))
+}
+
+fn resolve_output_dir(config: &MeetingConfig) -> PathBuf {
+    if let Some(dir) = config.output_dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("data").join("meetings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn transcript_lines_carry_offsets_from_meeting_start() {
+        assert_eq!(
+            transcript_line(Duration::from_millis(65_400), "我们先过一下排期"),
+            "[00:01:05] 我们先过一下排期\n"
+        );
+        assert_eq!(
+            transcript_line(Duration::from_secs(3 * 3600 + 7), "we ship it"),
+            "[03:00:07] we ship it\n"
+        );
+
+        let started = Local.with_ymd_and_hms(2026, 3, 9, 14, 5, 0).unwrap();
+        assert_eq!(file_name(started), "meeting-20260309-140500.md");
+
+        let config = MeetingConfig {
+            output_dir: Some(" /tmp/meetings ".to_string()),
+            ..MeetingConfig::default()
+        };
+        assert_eq!(resolve_output_dir(&config), PathBuf::from("/tmp/meetings"));
+    }
+}
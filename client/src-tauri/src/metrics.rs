@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::events::SessionSummary;
+
+/// 请求头的上限，超出后直接断开
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+pub const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 延迟类直方图的桶（秒）
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0];
+
+/// 录音时长直方图的桶（秒）
+const AUDIO_BUCKETS: &[f64] = &[1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// 本地 Prometheus 指标端口（只监听 127.0.0.1），修改后重启客户端生效
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    9464
+}
+
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// 每个桶的计数（不累加），最后一个是 +Inf
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let index = self.bounds.iter().position(|bound| value <= *bound).unwrap_or(self.bounds.len());
+        self.counts[index] += 1;
+        self.sum += value;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        cumulative += self.counts[self.bounds.len()];
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}");
+        let _ = writeln!(out, "{name}_sum {}", self.sum);
+        let _ = writeln!(out, "{name}_count {cumulative}");
+    }
+}
+
+#[derive(Debug)]
+struct MetricsInner {
+    sessions_started: u64,
+    sessions_completed: u64,
+    corrections: u64,
+    chars_injected: u64,
+    /// 按错误码统计
+    errors: BTreeMap<&'static str, u64>,
+    reconnects: u64,
+    disconnects: u64,
+    audio_seconds: Histogram,
+    asr_latency: Histogram,
+    llm_latency: Histogram,
+}
+
+/// 会话、延迟、错误与重连计数，进程内累计，重启后清零
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(MetricsInner {
+                sessions_started: 0,
+                sessions_completed: 0,
+                corrections: 0,
+                chars_injected: 0,
+                errors: BTreeMap::new(),
+                reconnects: 0,
+                disconnects: 0,
+                audio_seconds: Histogram::new(AUDIO_BUCKETS),
+                asr_latency: Histogram::new(LATENCY_BUCKETS),
+                llm_latency: Histogram::new(LATENCY_BUCKETS),
+            }),
+        }
+    }
+
+    pub fn record_start(&self) {
+        self.inner.lock().expect("metrics lock").sessions_started += 1;
+    }
+
+    pub fn record_summary(&self, summary: &SessionSummary) {
+        let mut inner = self.inner.lock().expect("metrics lock");
+        inner.sessions_completed += 1;
+        inner.chars_injected += summary.chars_injected as u64;
+        if summary.corrected {
+            inner.corrections += 1;
+        }
+        inner.audio_seconds.observe(summary.audio_ms as f64 / 1000.0);
+        inner.asr_latency.observe(summary.asr_ms as f64 / 1000.0);
+        if let Some(llm_ms) = summary.llm_ms {
+            inner.llm_latency.observe(llm_ms as f64 / 1000.0);
+        }
+    }
+
+    pub fn record_error(&self, code: &'static str) {
+        *self.inner.lock().expect("metrics lock").errors.entry(code).or_insert(0) += 1;
+    }
+
+    /// 长连接状态变化；首次连上不算重连
+    pub fn record_link_change(&self, connected: bool) {
+        let mut inner = self.inner.lock().expect("metrics lock");
+        if !connected {
+            inner.disconnects += 1;
+        } else if inner.disconnects > inner.reconnects {
+            inner.reconnects += 1;
+        }
+    }
+
+    /// Prometheus 文本格式（0.0.4）
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().expect("metrics lock");
+        let mut out = String::new();
+        counter(&mut out, "ghosttype_sessions_started_total", "Dictation sessions started.", inner.sessions_started);
+        counter(
+            &mut out,
+            "ghosttype_sessions_completed_total",
+            "Sessions that produced a transcript.",
+            inner.sessions_completed,
+        );
+        counter(&mut out, "ghosttype_corrections_total", "LLM corrections applied.", inner.corrections);
+        counter(
+            &mut out,
+            "ghosttype_chars_injected_total",
+            "Characters typed into the focused app.",
+            inner.chars_injected,
+        );
+        let _ = writeln!(out, "# HELP ghosttype_errors_total Session errors by code.");
+        let _ = writeln!(out, "# TYPE ghosttype_errors_total counter");
+        for (code, count) in &inner.errors {
+            let _ = writeln!(out, "ghosttype_errors_total{{code=\"{code}\"}} {count}");
+        }
+        counter(&mut out, "ghosttype_reconnects_total", "ASR server connections restored.", inner.reconnects);
+        counter(&mut out, "ghosttype_disconnects_total", "ASR server connections lost.", inner.disconnects);
+        inner
+            .audio_seconds
+            .render(&mut out, "ghosttype_audio_duration_seconds", "Recorded audio per session.");
+        inner
+            .asr_latency
+            .render(&mut out, "ghosttype_asr_latency_seconds", "Time from key release to transcript.");
+        inner
+            .llm_latency
+            .render(&mut out, "ghosttype_llm_latency_seconds", "LLM correction latency.");
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// 在 127.0.0.1 上提供 `GET /metrics`；端口被占用时只记录警告
+pub fn spawn_server(metrics: Arc<Metrics>, config: &MetricsConfig) {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, config.port));
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = serve(metrics, addr).await {
+            warn!(
+                target: "metrics",
+                addr = %addr,
+                error = %format!("{err:#}"),
+                "指标端口启动失败 | Metrics listener failed"
+            );
+        }
+    });
+}
+
+async fn serve(metrics: Arc<Metrics>, addr: SocketAddr) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr).await.with_context(|| format!("bind {addr}"))?;
+    info!(target: "metrics", addr = %addr, "指标端口已开启 | Metrics listener started");
+    loop {
+        let (stream, peer) = listener.accept().await.context("accept")?;
+        let metrics = metrics.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = handle(stream, &metrics).await {
+                debug!(
+                    target: "metrics",
+                    peer = %peer,
+                    error = %err,
+                    "指标请求处理失败 | Metrics request failed"
+                );
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, metrics: &Metrics) -> anyhow::Result<()> {
+    let head = tokio::time::timeout(READ_TIMEOUT, read_head(&mut stream))
+        .await
+        .context("read timeout")??;
+    let (status, content_type, body) = match request_path(&head) {
+        Some("/metrics") => ("200 OK", "text/plain; version=0.0.4; charset=utf-8", metrics.render()),
+        Some(_) => ("404 Not Found", "text/plain; charset=utf-8", "not found\n".to_string()),
+        None => ("405 Method Not Allowed", "text/plain; charset=utf-8", "method not allowed\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 读到空行为止（只需要请求行，忽略请求体）
+pub async fn read_head(stream: &mut TcpStream) -> anyhow::Result<String> {
+    let mut buf = Vec::with_capacity(512);
+    let mut chunk = [0u8; 512];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.len() > MAX_REQUEST_BYTES {
+            anyhow::bail!("request header too large");
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// GET 请求的路径（去掉查询串）；其它方法返回 `None`
+fn request_path(head: &str) -> Option<&str> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let target = parts.next()?;
+    Some(target.split('?').next().unwrap_or(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_render_counters_and_histograms() {
+        let metrics = Metrics::new();
+        metrics.record_start();
+        metrics.record_summary(&SessionSummary {
+            audio_ms: 3000,
+            asr_ms: 400,
+            llm_ms: Some(1200),
+            chars_injected: 5,
+            corrected: true,
+            ..SessionSummary::default()
+        });
+        metrics.record_error("E_ASR_FAILED");
+        metrics.record_link_change(true);
+        metrics.record_link_change(false);
+        metrics.record_link_change(true);
+
+        let text = metrics.render();
+        assert!(text.contains("ghosttype_sessions_started_total 1\n"));
+        assert!(text.contains("ghosttype_corrections_total 1\n"));
+        assert!(text.contains("ghosttype_errors_total{code=\"E_ASR_FAILED\"} 1\n"));
+        assert!(text.contains("ghosttype_reconnects_total 1\n"));
+        assert!(text.contains("ghosttype_asr_latency_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(text.contains("ghosttype_asr_latency_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(text.contains("ghosttype_llm_latency_seconds_bucket{le=\"+Inf\"} 1\n"));
+        assert!(text.contains("ghosttype_audio_duration_seconds_sum 3\n"));
+
+        assert_eq!(request_path("GET /metrics?x=1 HTTP/1.1\r\nHost: a\r\n\r\n"), Some("/metrics"));
+        assert_eq!(request_path("POST /metrics HTTP/1.1\r\n\r\n"), None);
+    }
+}
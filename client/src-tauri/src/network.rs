@@ -1,234 +1,637 @@
-use crate::input::{InjectCommand, Injector};
-use crate::TrayController;
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
 
+use anyhow::Context as _;
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot, watch, Notify, OwnedMutexGuard};
 use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use crate::proxy::{self, WsStream};
+
+/// 建立连接的超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 心跳间隔；上一次心跳超过一个间隔仍未收到 pong 视为断线
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// 与 ASR 服务器之间长连接的状态
 #[derive(Debug, Clone, Serialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum ClientControl {
-    Start {
-        trace_id: String,
-        sample_rate: u32,
-        context: ClientContext,
-        use_cloud_api: bool,
-    },
-    Stop {
-        #[serde(skip_serializing_if = "Option::is_none")]
-        trace_id: Option<String>,
-    },
-}
-
-#[derive(Debug, Clone, Serialize, Default)]
-pub struct ClientContext {
-    pub app_name: String,
-    pub window_title: String,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-enum ServerEvent {
-    Pong,
-    FastText {
-        trace_id: Option<String>,
-        content: String,
-        is_final: Option<bool>,
-    },
-    Correction {
-        trace_id: Option<String>,
-        original_text: String,
-        replaced_text: String,
-        delete_count: usize,
-    },
-    Error { trace_id: Option<String>, message: String },
-}
-
-#[derive(Debug)]
-pub enum NetworkCommand {
-    SendControl(ClientControl),
-    SendAudio { trace_id: String, seq: u64, bytes: Vec<u8> },
-}
-
-#[derive(Clone)]
-pub struct NetworkHandle {
-    pub tx: mpsc::Sender<NetworkCommand>,
-}
-
-pub fn spawn_network(endpoints: Vec<String>, injector: Injector, tray: Arc<TrayController>) -> NetworkHandle {
-    let (tx, mut rx) = mpsc::channel::<NetworkCommand>(1024);
-
-    tauri::async_runtime::spawn(async move {
-        let endpoints = if endpoints.is_empty() {
-            vec!["ws://127.0.0.1:8000/ws".to_string()]
-        } else {
-            endpoints
-        };
+pub struct LinkStatus {
+    pub endpoint: String,
+    pub state: LinkState,
+    pub rtt_ms: Option<u64>,
+    pub last_pong_at: Option<String>,
+    pub last_error: Option<String>,
+    /// 连续失败的连接次数，连接成功后清零
+    pub reconnect_attempts: u32,
+    /// 服务器在 pong 中声明的能力；连接后首个 pong 之前与断线后为空
+    pub capabilities: Option<ServerCapabilities>,
+}
 
-        let mut backoff_ms: u64 = 200;
-        loop {
-            for endpoint in &endpoints {
-                info!(
-                    target: "network",
-                    endpoint = %endpoint,
-                    "正在连接服务器 | Connecting to server"
-                );
-                let connect_result = tokio_tungstenite::connect_async(endpoint).await;
-                let Ok((ws, _)) = connect_result else {
-                    warn!(
-                        target: "network",
-                        endpoint = %endpoint,
-                        "服务器连接失败 | Server connect failed"
-                    );
-                    tray.set_error();
-                    continue;
-                };
+/// 服务器能力声明（随 pong 返回，旧版服务器没有这些字段）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerCapabilities {
+    /// 协议扩展：`seq_frames`、`partials`（流式中间结果）、`llm`（服务端校正）等
+    pub features: Vec<String>,
+    /// 支持的音频编码
+    pub codecs: Vec<String>,
+    /// 可按会话指定的识别语言；为空表示不支持指定
+    pub languages: Vec<String>,
+    /// 端到端加密用的服务器 X25519 公钥（base64），声明 `e2e` 时提供
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e2e_key: Option<String>,
+}
+
+impl ServerCapabilities {
+    /// 音频帧带序号并回 ack
+    pub const SEQ_FRAMES: &'static str = "seq_frames";
+    /// 可在识别结果之后发送 LLM 校正（`start.use_cloud_api` 请求时）
+    pub const LLM: &'static str = "llm";
+    /// 会话事件可用 MessagePack 二进制帧发送
+    pub const MSGPACK: &'static str = "msgpack";
+    /// 音频帧与会话事件可在应用层加密（X25519 + ChaCha20-Poly1305）
+    pub const E2E: &'static str = "e2e";
+    /// 录音中断线后可用同一 trace_id 续传
+    pub const RESUME: &'static str = "resume";
+    /// 可取消进行中的会话，服务器丢弃已收到的音频且不回结果
+    pub const CANCEL: &'static str = "cancel";
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    pub fn supports_language(&self, language: &str) -> bool {
+        self.languages.iter().any(|l| l.eq_ignore_ascii_case(language))
+    }
+}
+
+/// 单次会话的网络用量（心跳不计入）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TrafficStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// 发送的音频包数，含重传
+    pub audio_packets: u64,
+    pub audio_bytes: u64,
+    /// 断线后补交时重复发送的音频包
+    pub retransmitted_packets: u64,
+    /// 服务器按帧序号确认缺失的音频包
+    pub dropped_packets: u64,
+}
+
+impl TrafficStats {
+    pub fn record_text(&mut self, bytes: usize) {
+        self.bytes_sent += bytes as u64;
+    }
+
+    pub fn record_audio(&mut self, bytes: usize, retransmit: bool) {
+        self.bytes_sent += bytes as u64;
+        self.audio_packets += 1;
+        self.audio_bytes += bytes as u64;
+        if retransmit {
+            self.retransmitted_packets += 1;
+        }
+    }
+
+    pub fn record_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes as u64;
+    }
+
+    /// 平均每个 Opus 包的字节数
+    pub fn avg_packet_bytes(&self) -> u64 {
+        self.audio_bytes.checked_div(self.audio_packets).unwrap_or(0)
+    }
+}
+
+/// 服务端发给会话的消息：JSON 文本，或协商 MessagePack 后的二进制帧
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl ServerMessage {
+    /// 计入网络用量的字节数
+    pub fn byte_len(&self) -> usize {
+        match self {
+            ServerMessage::Text(text) => text.len(),
+            ServerMessage::Binary(bytes) => bytes.len(),
+        }
+    }
+}
+
+enum Outgoing {
+    Message(Message),
+    Ping(oneshot::Sender<u64>),
+    /// 发送关闭帧后断开，完成时回复
+    Close(oneshot::Sender<()>),
+}
+
+struct Shared {
+    endpoint: String,
+    status: watch::Sender<LinkStatus>,
+    /// 当前会话的服务端消息接收方；断线时丢弃，会话随之结束
+    session: Mutex<Option<mpsc::Sender<ServerMessage>>>,
+    /// 同一时间只允许一个会话使用连接
+    session_lock: Arc<tokio::sync::Mutex<()>>,
+    /// 需要连接时跳过退避等待，立即重连
+    reconnect: Notify,
+    /// 主动断开当前连接（休眠唤醒后旧连接多半已失效）
+    reset: Notify,
+    /// 已关闭（退出时），不再重连
+    closed: AtomicBool,
+}
+
+impl Shared {
+    fn update(&self, apply: impl FnOnce(&mut LinkStatus)) {
+        self.status.send_modify(apply);
+    }
+}
+
+/// ASR 服务器长连接：自动重连（指数退避）、心跳保活并上报状态。
+/// Pipeline 的 WebSocket 引擎与界面的连接状态共用同一个实例。
+pub struct ConnectionManager {
+    shared: Arc<Shared>,
+    outgoing: mpsc::Sender<Outgoing>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+fn managers() -> &'static Mutex<HashMap<String, Weak<ConnectionManager>>> {
+    static MANAGERS: OnceLock<Mutex<HashMap<String, Weak<ConnectionManager>>>> = OnceLock::new();
+    MANAGERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 系统休眠唤醒后调用：断开所有现有连接并立即重连，不等心跳超时
+pub fn reconnect_all() {
+    let managers: Vec<Arc<ConnectionManager>> = managers()
+        .lock()
+        .expect("connection managers lock")
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect();
+    for manager in managers {
+        manager.reconnect();
+    }
+}
+
+/// 退出前调用：关闭所有连接，服务器随即结束未完成的会话
+pub async fn close_all() {
+    let managers: Vec<Arc<ConnectionManager>> = managers()
+        .lock()
+        .expect("connection managers lock")
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect();
+    for manager in managers {
+        manager.close().await;
+    }
+}
+
+impl ConnectionManager {
+    /// 同一地址只保持一条连接
+    pub fn shared(endpoint: &str) -> Arc<ConnectionManager> {
+        let endpoint = endpoint.trim();
+        let mut managers = managers().lock().expect("connection managers lock");
+        managers.retain(|_, manager| manager.strong_count() > 0);
+        if let Some(manager) = managers.get(endpoint).and_then(Weak::upgrade) {
+            return manager;
+        }
+
+        let manager = Arc::new(ConnectionManager::spawn(endpoint.to_string()));
+        managers.insert(endpoint.to_string(), Arc::downgrade(&manager));
+        manager
+    }
+
+    fn spawn(endpoint: String) -> Self {
+        let (status, _) = watch::channel(LinkStatus {
+            endpoint: endpoint.clone(),
+            state: LinkState::Connecting,
+            rtt_ms: None,
+            last_pong_at: None,
+            last_error: None,
+            reconnect_attempts: 0,
+            capabilities: None,
+        });
+        let shared = Arc::new(Shared {
+            endpoint,
+            status,
+            session: Mutex::new(None),
+            session_lock: Arc::new(tokio::sync::Mutex::new(())),
+            reconnect: Notify::new(),
+            reset: Notify::new(),
+            closed: AtomicBool::new(false),
+        });
+        let (outgoing, rx) = mpsc::channel::<Outgoing>(1024);
+        let task = tauri::async_runtime::spawn(run(shared.clone(), rx));
+        Self { shared, outgoing, task }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.shared.endpoint
+    }
+
+    pub fn status(&self) -> LinkStatus {
+        self.shared.status.borrow().clone()
+    }
+
+    pub fn watch(&self) -> watch::Receiver<LinkStatus> {
+        self.shared.status.subscribe()
+    }
+
+    /// 服务器最近一次声明的能力；尚未收到 pong 时为 `None`
+    pub fn capabilities(&self) -> Option<ServerCapabilities> {
+        self.shared.status.borrow().capabilities.clone()
+    }
+
+    /// 服务器是否声明支持某项协议扩展（连接后首个 pong 之前为 `false`）
+    pub fn supports(&self, feature: &str) -> bool {
+        self.shared
+            .status
+            .borrow()
+            .capabilities
+            .as_ref()
+            .is_some_and(|caps| caps.supports(feature))
+    }
+
+    /// 等待服务器声明能力（连接后的首个 pong）
+    pub async fn wait_capabilities(&self, timeout: Duration) -> anyhow::Result<ServerCapabilities> {
+        let mut rx = self.watch();
+        let status = tokio::time::timeout(timeout, rx.wait_for(|status| status.capabilities.is_some()))
+            .await
+            .context("wait server capabilities timeout")?
+            .map_err(|_| anyhow::anyhow!("connection manager stopped"))?;
+        Ok(status.capabilities.clone().unwrap_or_default())
+    }
+
+    /// 等待连接可用；当前处于退避等待时立即重连
+    pub async fn wait_connected(&self, timeout: Duration) -> anyhow::Result<()> {
+        let mut rx = self.watch();
+        if rx.borrow_and_update().state == LinkState::Connected {
+            return Ok(());
+        }
+        self.shared.reconnect.notify_one();
+
+        let connected = tokio::time::timeout(timeout, rx.wait_for(|status| status.state == LinkState::Connected))
+            .await
+            .map(|result| result.is_ok());
+        match connected {
+            Ok(true) => Ok(()),
+            Ok(false) => anyhow::bail!("connection manager stopped"),
+            Err(_) => {
+                let reason = self.status().last_error.unwrap_or_else(|| "timeout".to_string());
+                anyhow::bail!("connect websocket {}: {reason}", self.shared.endpoint)
+            }
+        }
+    }
+
+    /// 断开当前连接（如果有）并跳过退避立即重连
+    pub fn reconnect(&self) {
+        info!(
+            target: "network",
+            endpoint = %self.shared.endpoint,
+            "重新建立服务器连接 | Re-establishing server connection"
+        );
+        if self.shared.status.borrow().state == LinkState::Connected {
+            self.shared.reset.notify_one();
+        }
+        self.shared.reconnect.notify_one();
+    }
+
+    /// 发送关闭帧并断开，之后不再重连；未连接时直接停止重连
+    pub async fn close(&self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+        self.shared.reconnect.notify_one();
+        let (done, rx) = oneshot::channel();
+        if self.outgoing.send(Outgoing::Close(done)).await.is_ok() {
+            // 连接任务退出时会丢弃未处理的请求，同样视为完成
+            let _ = rx.await;
+        }
+    }
+
+    /// 开始新的会话：之后收到的服务端消息（pong 除外）都交给返回的会话。
+    /// 上一个会话未结束时等待，补交的离线会话与实时会话不会互相串扰。
+    pub async fn open_session(&self) -> Session {
+        let exclusive = self.shared.session_lock.clone().lock_owned().await;
+        let (tx, rx) = mpsc::channel::<ServerMessage>(64);
+        *self.shared.session.lock().expect("session lock") = Some(tx);
+        Session {
+            rx,
+            _exclusive: exclusive,
+        }
+    }
+
+    pub async fn send_text(&self, text: String) -> anyhow::Result<()> {
+        self.send(Outgoing::Message(Message::Text(text))).await
+    }
+
+    pub async fn send_binary(&self, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.send(Outgoing::Message(Message::Binary(bytes))).await
+    }
+
+    /// 主动发送一次 ping，返回往返耗时（毫秒）
+    pub async fn ping(&self, timeout: Duration) -> anyhow::Result<u64> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Outgoing::Ping(tx)).await?;
+        tokio::time::timeout(timeout, rx)
+            .await
+            .context("wait pong timeout")?
+            .context("connection lost before pong")
+    }
+
+    async fn send(&self, outgoing: Outgoing) -> anyhow::Result<()> {
+        if self.shared.status.borrow().state != LinkState::Connected {
+            anyhow::bail!("websocket not connected");
+        }
+        self.outgoing
+            .send(outgoing)
+            .await
+            .map_err(|_| anyhow::anyhow!("connection manager stopped"))
+    }
+}
+
+/// 独占连接的一次会话，丢弃后下一个会话才能开始
+pub struct Session {
+    rx: mpsc::Receiver<ServerMessage>,
+    _exclusive: OwnedMutexGuard<()>,
+}
+
+impl Session {
+    /// 下一条服务端消息；连接断开时返回 `None`
+    pub async fn recv(&mut self) -> Option<ServerMessage> {
+        self.rx.recv().await
+    }
+
+    /// 不等待，取出已到达的消息
+    pub fn try_recv(&mut self) -> Option<ServerMessage> {
+        self.rx.try_recv().ok()
+    }
+
+    /// 连接已断开（即使随后自动重连，本会话也不会再收到消息）
+    pub fn is_closed(&self) -> bool {
+        self.rx.is_closed()
+    }
+}
+
+impl Drop for ConnectionManager {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(shared: Arc<Shared>, mut outgoing: mpsc::Receiver<Outgoing>) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempts: u32 = 0;
 
+    loop {
+        if shared.closed.load(Ordering::SeqCst) {
+            shared.update(|status| status.state = LinkState::Disconnected);
+            info!(target: "network", endpoint = %shared.endpoint, "服务器连接已关闭 | Server connection closed");
+            return;
+        }
+        shared.update(|status| status.state = LinkState::Connecting);
+        let connect = tokio::time::timeout(CONNECT_TIMEOUT, proxy::connect_websocket(&shared.endpoint)).await;
+        let (reason, served) = match connect {
+            Ok(Ok(ws)) => {
                 info!(
                     target: "network",
-                    endpoint = %endpoint,
+                    endpoint = %shared.endpoint,
                     "服务器已连接 | Server connected"
                 );
-                tray.clear_error();
-                backoff_ms = 200;
-                let (mut write, mut read) = ws.split();
-
-                loop {
-                    tokio::select! {
-                        Some(cmd) = rx.recv() => {
-                            let msg = match cmd {
-                                NetworkCommand::SendControl(control) => {
-                                    let Ok(text) = serde_json::to_string(&control) else { continue; };
-                                    Message::Text(text)
-                                }
-                                NetworkCommand::SendAudio { trace_id, seq, bytes } => {
-                                    debug!(
-                                        target: "network",
-                                        trace_id = %trace_id,
-                                        bytes = bytes.len(),
-                                        seq = seq,
-                                        "音频包已发送 | Audio packet sent"
-                                    );
-                                    Message::Binary(bytes)
-                                }
-                            };
-
-                            if write.send(msg).await.is_err() {
-                                break;
-                            }
-                        }
-                        incoming = read.next() => {
-                            let Some(incoming) = incoming else { break; };
-                            let Ok(incoming) = incoming else { break; };
-
-                            match incoming {
-                                Message::Text(text) => handle_server_text(&text, &injector, &tray).await,
-                                Message::Close(_) => break,
-                                _ => {}
-                            }
-                        }
-                    }
-                }
+                attempts = 0;
+                backoff = INITIAL_BACKOFF;
+                shared.update(|status| {
+                    status.state = LinkState::Connected;
+                    status.last_error = None;
+                    status.reconnect_attempts = 0;
+                });
 
+                let reason = serve(&shared, ws, &mut outgoing).await;
+                if shared.closed.load(Ordering::SeqCst) {
+                    continue;
+                }
                 warn!(
                     target: "network",
-                    endpoint = %endpoint,
+                    endpoint = %shared.endpoint,
+                    reason = %reason,
                     "服务器连接断开 | Server disconnected"
                 );
-                tray.set_error();
+                (reason, true)
             }
+            Ok(Err(err)) => (format!("{err:#}"), false),
+            Err(_) => ("连接超时 | Connect timeout".to_string(), false),
+        };
 
-            while rx.try_recv().is_ok() {}
-
-            info!(
+        attempts += 1;
+        if !served && attempts == 1 {
+            // 服务器长时间离线时只记录第一次失败，避免日志刷屏
+            warn!(
                 target: "network",
-                delay_ms = backoff_ms,
-                "正在重连 | Reconnecting"
+                endpoint = %shared.endpoint,
+                error = %reason,
+                "服务器连接失败 | Server connect failed"
             );
-            tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-            backoff_ms = (backoff_ms * 2).min(5000);
-        }
-    });
-
-    NetworkHandle { tx }
-}
-
-async fn handle_server_text(text: &str, injector: &Injector, tray: &Arc<TrayController>) {
-    let Ok(event) = serde_json::from_str::<ServerEvent>(text) else {
-        return;
-    };
-
-    match event {
-        ServerEvent::Pong => {}
-        ServerEvent::FastText {
-            trace_id,
-            content,
-            is_final,
-        } => {
-            let _ = is_final;
-            let trace_id = trace_id.filter(|v| !v.is_empty());
-            if let Some(tid) = trace_id.as_deref() {
-                info!(
-                    target: "network",
-                    trace_id = %tid,
-                    text_len = content.len(),
-                    "收到识别结果 | Recognition result received"
-                );
-            } else {
-                info!(target: "network", text_len = content.len(), "收到识别结果 | Recognition result received");
-            }
-            let _ = injector
-                .tx
-                .send(InjectCommand::TypeText {
-                    trace_id,
-                    text: content,
-                })
-                .await;
-            tray.set_idle();
         }
-        ServerEvent::Correction {
-            trace_id,
-            original_text,
-            replaced_text,
-            delete_count,
-        } => {
-            let _ = original_text;
-            let _ = injector
-                .tx
-                .send(InjectCommand::Backspace {
-                    trace_id: trace_id.clone(),
-                    count: delete_count,
-                })
-                .await;
-            let _ = injector
-                .tx
-                .send(InjectCommand::TypeText {
-                    trace_id,
-                    text: replaced_text,
-                })
-                .await;
-            tray.set_idle();
+
+        shared.update(|status| {
+            status.state = LinkState::Disconnected;
+            status.last_error = Some(reason);
+            status.reconnect_attempts = attempts;
+            status.capabilities = None;
+        });
+        // 断线后结束当前会话，并丢弃尚未发出的旧数据，避免重连后串到新会话
+        shared.session.lock().expect("session lock").take();
+        while outgoing.try_recv().is_ok() {}
+
+        debug!(
+            target: "network",
+            endpoint = %shared.endpoint,
+            delay_ms = backoff.as_millis() as u64,
+            "正在重连 | Reconnecting"
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = shared.reconnect.notified() => {}
         }
-        ServerEvent::Error { trace_id, message } => {
-            tray.set_error();
-            let trace_id = trace_id.filter(|v| !v.is_empty());
-            if let Some(tid) = trace_id.as_deref() {
-                error!(
-                    target: "network",
-                    trace_id = %tid,
-                    message = %message,
-                    "服务端错误 | Server error"
-                );
-                return;
+        backoff = next_backoff(backoff);
+    }
+}
+
+/// 处理一条已建立的连接，返回断开原因
+async fn serve(
+    shared: &Shared,
+    ws: WsStream,
+    outgoing: &mut mpsc::Receiver<Outgoing>,
+) -> String {
+    let (mut write, mut read) = ws.split();
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    let mut pending_pings: VecDeque<(Instant, Option<oneshot::Sender<u64>>)> = VecDeque::new();
+
+    loop {
+        tokio::select! {
+            _ = shared.reset.notified() => {
+                return "已请求重连 | Reconnect requested".to_string();
+            }
+            cmd = outgoing.recv() => {
+                let Some(cmd) = cmd else {
+                    return "connection manager stopped".to_string();
+                };
+                let msg = match cmd {
+                    Outgoing::Message(msg) => msg,
+                    Outgoing::Ping(waiter) => {
+                        pending_pings.push_back((Instant::now(), Some(waiter)));
+                        ping_message()
+                    }
+                    Outgoing::Close(done) => {
+                        let _ = write.close().await;
+                        let _ = done.send(());
+                        return "客户端关闭连接 | Closed by client".to_string();
+                    }
+                };
+                if let Err(err) = write.send(msg).await {
+                    return err.to_string();
+                }
+            }
+            _ = keepalive.tick() => {
+                if pending_pings.front().is_some_and(|(sent, _)| sent.elapsed() >= KEEPALIVE_INTERVAL) {
+                    return "心跳超时 | Keep-alive timeout".to_string();
+                }
+                pending_pings.push_back((Instant::now(), None));
+                if let Err(err) = write.send(ping_message()).await {
+                    return err.to_string();
+                }
+            }
+            incoming = read.next() => {
+                let message = match incoming {
+                    None | Some(Ok(Message::Close(_))) => return "连接已关闭 | Connection closed".to_string(),
+                    Some(Err(err)) => return err.to_string(),
+                    Some(Ok(Message::Text(text))) => ServerMessage::Text(text),
+                    Some(Ok(Message::Binary(bytes))) => ServerMessage::Binary(bytes),
+                    Some(Ok(_)) => continue,
+                };
+
+                // pong 始终是 JSON 文本
+                if let Some(capabilities) = match &message {
+                    ServerMessage::Text(text) => parse_pong(text),
+                    ServerMessage::Binary(_) => None,
+                } {
+                    if let Some((sent, waiter)) = pending_pings.pop_front() {
+                        let rtt_ms = sent.elapsed().as_millis() as u64;
+                        let now = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+                        shared.update(|status| {
+                            status.rtt_ms = Some(rtt_ms);
+                            status.last_pong_at = Some(now);
+                            status.capabilities = Some(capabilities);
+                        });
+                        if let Some(waiter) = waiter {
+                            let _ = waiter.send(rtt_ms);
+                        }
+                    }
+                    continue;
+                }
+
+                if let Some(session) = shared.session.lock().expect("session lock").as_ref() {
+                    let _ = session.try_send(message);
+                }
             }
-            error!(
-                target: "network",
-                message = %message,
-                "服务端错误 | Server error"
-            );
         }
     }
 }
+
+fn ping_message() -> Message {
+    Message::Text(serde_json::json!({ "type": "ping" }).to_string())
+}
+
+/// pong 消息返回服务器声明的能力（旧版服务器没有这些字段，视为全空）
+fn parse_pong(text: &str) -> Option<ServerCapabilities> {
+    let value = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    if value.get("type").and_then(|v| v.as_str()) != Some("pong") {
+        return None;
+    }
+    Some(serde_json::from_value(value).unwrap_or_default())
+}
+
+fn next_backoff(current: Duration) -> Duration {
+    (current * 2).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        assert_eq!(next_backoff(INITIAL_BACKOFF), Duration::from_millis(400));
+        assert_eq!(next_backoff(Duration::from_secs(20)), MAX_BACKOFF);
+        assert_eq!(next_backoff(MAX_BACKOFF), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn parse_pong_only_matches_pong_messages() {
+        assert_eq!(parse_pong(r#"{"type":"pong"}"#), Some(ServerCapabilities::default()));
+        let caps = parse_pong(r#"{"type":"pong","features":["seq_frames"],"codecs":["opus"],"languages":["auto","zh"]}"#)
+            .expect("pong");
+        assert!(caps.supports("seq_frames"));
+        assert!(!caps.supports("llm"));
+        assert_eq!(caps.codecs, vec!["opus".to_string()]);
+        assert!(caps.supports_language("ZH"));
+        assert!(!caps.supports_language("fr"));
+        assert_eq!(parse_pong(r#"{"type":"fast_text","content":"pong"}"#), None);
+        assert_eq!(parse_pong("pong"), None);
+    }
+
+    #[tokio::test]
+    async fn manager_reconnects_and_routes_session_messages() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let endpoint = format!("ws://{}/ws", listener.local_addr().expect("addr"));
+
+        // 测试服务端：回应 ping；收到 stop 时返回识别结果，收到 drop 时断开连接
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.expect("accept");
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let reply = match text.as_str() {
+                            t if t.contains("ping") => r#"{"type":"pong"}"#,
+                            t if t.contains("stop") => r#"{"type":"fast_text","content":"hi"}"#,
+                            _ => return,
+                        };
+                        let _ = ws.send(Message::Text(reply.to_string())).await;
+                    }
+                });
+            }
+        });
+
+        let manager = ConnectionManager::shared(&endpoint);
+        assert!(Arc::ptr_eq(&manager, &ConnectionManager::shared(&endpoint)));
+        manager.wait_connected(Duration::from_secs(3)).await.expect("connect");
+        manager.ping(Duration::from_secs(3)).await.expect("ping");
+
+        let mut session = manager.open_session().await;
+        manager.send_text(r#"{"type":"stop"}"#.to_string()).await.expect("send");
+        let reply = tokio::time::timeout(Duration::from_secs(3), session.recv()).await.expect("reply");
+        assert!(matches!(reply, Some(ServerMessage::Text(text)) if text.contains("fast_text")));
+
+        // 服务端断开后，当前会话结束，管理器自动重连
+        manager.send_text("drop".to_string()).await.expect("send drop");
+        let closed = tokio::time::timeout(Duration::from_secs(3), session.recv()).await.expect("session end");
+        assert!(closed.is_none());
+        manager.wait_connected(Duration::from_secs(3)).await.expect("reconnect");
+        assert_eq!(manager.status().state, LinkState::Connected);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::Emitter;
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+
+use crate::error::{AppError, ErrorCode};
+use crate::platform;
+use crate::update::UpdateInfo;
+
+/// 同类失败的通知冷却时间，避免连续失败时刷屏
+const NOTIFY_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 迟到校正的通知等待用户选择的时长
+const LATE_CORRECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 用户对迟到校正的选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LateCorrectionChoice {
+    /// 回删原文并输入校正结果
+    Apply,
+    /// 复制到剪贴板
+    Copy,
+}
+
+pub struct Notifier {
+    app: tauri::AppHandle,
+    last_sent: Mutex<HashMap<ErrorCode, Instant>>,
+    /// 最近一次失败对应的设置页，用户打开主窗口时跳转过去
+    pending_settings: Mutex<Option<&'static str>>,
+}
+
+impl Notifier {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self {
+            app,
+            last_sent: Mutex::new(HashMap::new()),
+            pending_settings: Mutex::new(None),
+        }
+    }
+
+    pub fn failure(&self, error: &AppError) {
+        let kind = error.code;
+        if let Some(section) = settings_section(kind) {
+            *self.pending_settings.lock().expect("notify pending lock") = Some(section);
+        }
+
+        let now = Instant::now();
+        {
+            let mut last_sent = self.last_sent.lock().expect("notify last sent lock");
+            if !should_notify(last_sent.get(&kind).copied(), now, NOTIFY_COOLDOWN) {
+                return;
+            }
+            last_sent.insert(kind, now);
+        }
+
+        let title = failure_title(kind);
+        let body = if error.detail.trim().is_empty() {
+            format!("{}\n点击托盘图标 → Show 查看设置 | Open settings from the tray", error.message)
+        } else {
+            format!("{} [{}]\n{}", error.message, kind, error.detail.trim())
+        };
+
+        if let Err(err) = self.app.notification().builder().title(title).body(body).show() {
+            warn!(
+                target: "notify",
+                error = %err,
+                "系统通知发送失败 | Notification failed"
+            );
+        }
+    }
+
+    /// 发现新版本：通知用户，并在打开主窗口时跳到更新页
+    pub fn update_available(&self, info: &UpdateInfo) {
+        *self.pending_settings.lock().expect("notify pending lock") = Some("update");
+
+        let body = format!(
+            "新版本 {} 已发布（当前 {}）| Version {} is available\n点击托盘图标 → Show 查看 | Open from the tray",
+            info.latest_version, info.current_version, info.latest_version
+        );
+        if let Err(err) = self
+            .app
+            .notification()
+            .builder()
+            .title("GhostType 有可用更新")
+            .body(body)
+            .show()
+        {
+            warn!(
+                target: "notify",
+                error = %err,
+                "系统通知发送失败 | Notification failed"
+            );
+        }
+    }
+
+    /// 服务器不可达，录音已暂存，连接恢复后自动提交
+    pub fn session_queued(&self, pending: usize) {
+        let body = format!(
+            "录音已暂存（{pending} 条待提交），连接恢复后自动识别并输入 | Recording saved, will submit when the server is back"
+        );
+        self.show("GhostType 服务器不可达", body);
+    }
+
+    /// 暂存的录音已补交，结果已输入到当前焦点
+    pub fn deferred_delivered(&self, text: &str) {
+        let preview: String = text.chars().take(40).collect();
+        let ellipsis = if text.chars().count() > 40 { "…" } else { "" };
+        self.show(
+            "GhostType 已补交暂存的录音",
+            format!("已输入 | Typed: {preview}{ellipsis}"),
+        );
+    }
+
+    /// 会议转写已结束，文件已保存
+    pub fn meeting_saved(&self, path: &str, segments: u32) {
+        self.show(
+            "GhostType 会议转写已保存",
+            format!("共 {segments} 段 | {segments} segments\n{path}"),
+        );
+    }
+
+    /// 录音文件识别完成（托盘菜单或 `ghosttype://transcribe-clipboard`），结果已写入剪贴板
+    pub fn audio_transcribed(&self, text: &str) {
+        let preview: String = text.chars().take(40).collect();
+        let ellipsis = if text.chars().count() > 40 { "…" } else { "" };
+        self.show(
+            "GhostType 已识别录音文件",
+            format!("结果已复制 | Copied: {preview}{ellipsis}"),
+        );
+    }
+
+    /// 前台是管理员权限的窗口：热键和文字输入都无法到达
+    pub fn elevated_window(&self, app_name: &str) {
+        self.show(
+            "GhostType 无法在此窗口中输入",
+            format!(
+                "{app_name} 以管理员权限运行，GhostType 需要同样的权限才能接收热键并输入文字 | \
+                 {app_name} runs as administrator; set elevation.strategy to run_elevated or ui_access"
+            ),
+        );
+    }
+
+    /// 校正超出替换时限：显示带“应用”“复制”按钮的通知，阻塞到用户选择、关闭或超时。
+    /// 只有 Windows 支持带按钮的通知，其他平台直接返回 `None`
+    pub fn late_correction(&self, text: &str) -> Option<LateCorrectionChoice> {
+        let preview: String = text.chars().take(80).collect();
+        let ellipsis = if text.chars().count() > 80 { "…" } else { "" };
+        let xml = toast_xml(
+            "GhostType 校正结果已就绪 | Correction ready",
+            &format!("{}{ellipsis}", preview.trim()),
+            &[("apply", "应用 | Apply"), ("copy", "复制 | Copy")],
+        );
+        // 安装版的快捷方式注册了应用标识，开发版借用 PowerShell 的
+        let app_id = (!tauri::is_dev()).then(|| self.app.config().identifier.clone());
+        match platform::action_toast(app_id.as_deref(), &xml, LATE_CORRECTION_TIMEOUT)?.as_str() {
+            "apply" => Some(LateCorrectionChoice::Apply),
+            "copy" => Some(LateCorrectionChoice::Copy),
+            _ => None,
+        }
+    }
+
+    /// 迟到的校正无法替换（焦点已切换或之后有新的输入），已改为复制
+    pub fn late_correction_copied(&self) {
+        self.show(
+            "GhostType 校正结果已复制",
+            "无法替换原文，已复制到剪贴板，请手动粘贴 | Could not replace the text, copied instead".to_string(),
+        );
+    }
+
+    /// 上次运行时崩溃：报告已写入崩溃目录，可从托盘菜单打开
+    pub fn crash_reported(&self, count: usize, latest: &Path) {
+        self.show(
+            "GhostType 上次意外退出",
+            format!(
+                "已保存 {count} 份崩溃报告，可从托盘菜单打开 | Crash report saved, open it from the tray\n{}",
+                latest.display()
+            ),
+        );
+    }
+
+    fn show(&self, title: &str, body: String) {
+        if let Err(err) = self.app.notification().builder().title(title).body(body).show() {
+            warn!(
+                target: "notify",
+                error = %err,
+                "系统通知发送失败 | Notification failed"
+            );
+        }
+    }
+
+    /// 主窗口被打开时调用：把待处理的设置页告诉前端。
+    pub fn open_pending_settings(&self) {
+        let section = self.pending_settings.lock().expect("notify pending lock").take();
+        if let Some(section) = section {
+            let _ = self.app.emit("open-settings", section);
+        }
+    }
+}
+
+fn should_notify(last: Option<Instant>, now: Instant, cooldown: Duration) -> bool {
+    match last {
+        Some(last) => now.saturating_duration_since(last) >= cooldown,
+        None => true,
+    }
+}
+
+fn failure_title(kind: ErrorCode) -> &'static str {
+    match kind {
+        ErrorCode::ServerUnreachable => "GhostType 无法连接服务器",
+        ErrorCode::MicPermission | ErrorCode::AudioDevice => "GhostType 无法使用麦克风",
+        ErrorCode::AsrFailed => "GhostType 识别失败",
+        ErrorCode::LlmFailed => "GhostType 校正失败",
+        ErrorCode::InjectionFailed | ErrorCode::Accessibility => "GhostType 无法输入文字",
+        ErrorCode::AppBlocked => "GhostType 未开始录音",
+        _ => "GhostType 出错了",
+    }
+}
+
+/// 带按钮的 Windows toast 内容；按钮点击后通知消失，`arguments` 原样返回
+fn toast_xml(title: &str, body: &str, actions: &[(&str, &str)]) -> String {
+    let mut xml = format!(
+        r#"<toast duration="long"><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text>"#,
+        xml_escape(title),
+        xml_escape(body)
+    );
+    xml.push_str("</binding></visual><actions>");
+    for (id, label) in actions {
+        xml.push_str(&format!(
+            r#"<action content="{}" arguments="{}" activationType="foreground"/>"#,
+            xml_escape(label),
+            xml_escape(id)
+        ));
+    }
+    xml.push_str("</actions></toast>");
+    xml
+}
+
+/// 转义 XML 文本与属性值中的特殊字符
+pub(crate) fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn settings_section(kind: ErrorCode) -> Option<&'static str> {
+    match kind {
+        ErrorCode::ServerUnreachable | ErrorCode::AsrFailed => Some("server"),
+        ErrorCode::MicPermission | ErrorCode::AudioDevice => Some("microphone"),
+        ErrorCode::LlmFailed => Some("llm"),
+        ErrorCode::InjectionFailed | ErrorCode::Accessibility => Some("accessibility"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_notify_respects_cooldown() {
+        let now = Instant::now();
+        assert!(should_notify(None, now, NOTIFY_COOLDOWN));
+        assert!(!should_notify(Some(now), now + Duration::from_secs(5), NOTIFY_COOLDOWN));
+        assert!(should_notify(Some(now), now + NOTIFY_COOLDOWN, NOTIFY_COOLDOWN));
+    }
+
+    #[test]
+    fn toast_xml_escapes_text_and_lists_actions() {
+        let xml = toast_xml("t", r#"if a < b && c > "d""#, &[("apply", "应用"), ("copy", "复制")]);
+        assert!(xml.contains("<text>if a &lt; b &amp;&amp; c &gt; &quot;d&quot;</text>"));
+        assert!(xml.contains(r#"<action content="应用" arguments="apply" activationType="foreground"/>"#));
+        assert!(xml.contains(r#"arguments="copy""#));
+        assert!(xml.starts_with("<toast") && xml.ends_with("</actions></toast>"));
+    }
+}
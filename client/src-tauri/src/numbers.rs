@@ -0,0 +1,923 @@
+use serde::{Deserialize, Serialize};
+
+/// 数字规范化：把口述的数字、日期、时间、金额、百分比和单位改成书面写法
+/// （"三百二十五" → "325"，"five thirty pm" → "5:30 PM"），按语言分别开关
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NumberConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 处理中文数字（"三百二十五"、"下午三点半"、"百分之五十"）
+    #[serde(default = "default_enabled")]
+    pub chinese: bool,
+    /// 处理英文数字（"twenty five"、"five thirty pm"、"ten dollars"）
+    #[serde(default = "default_enabled")]
+    pub english: bool,
+}
+
+impl Default for NumberConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chinese: true,
+            english: true,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 按配置规范化识别结果中的数字
+#[derive(Debug, Clone, Default)]
+pub struct NumberFormatter {
+    config: NumberConfig,
+}
+
+impl NumberFormatter {
+    pub fn new(config: &NumberConfig) -> Self {
+        Self { config: config.clone() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled && (self.config.chinese || self.config.english)
+    }
+
+    pub fn format(&self, text: &str) -> String {
+        if !self.config.enabled {
+            return text.to_string();
+        }
+        let mut text = text.to_string();
+        if self.config.chinese {
+            text = format_chinese(&text);
+        }
+        if self.config.english {
+            text = format_english(&text);
+        }
+        text
+    }
+}
+
+// ---- 中文 ----
+
+/// 单独一个数字（"两公里"、"十岁"）只在后面跟着这些单位时转换；按长度从长到短匹配
+const CHINESE_UNITS: &[&str] = &[
+    "个小时",
+    "个月",
+    "块钱",
+    "公里",
+    "千米",
+    "厘米",
+    "毫米",
+    "公斤",
+    "千克",
+    "毫升",
+    "分钟",
+    "小时",
+    "美元",
+    "欧元",
+    "英镑",
+    "摄氏度",
+    "米",
+    "克",
+    "斤",
+    "吨",
+    "升",
+    "元",
+    "块",
+    "毛",
+    "秒",
+    "岁",
+    "度",
+    "倍",
+    "%",
+];
+
+/// 前面是这些词时，"三点"、"三点五"按钟点理解
+const CHINESE_TIME_PREFIXES: &[&str] = &["上午", "下午", "早上", "晚上", "中午", "凌晨", "傍晚", "夜里"];
+
+fn chinese_digit(c: char) -> Option<u64> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    }
+}
+
+fn chinese_unit(c: char) -> Option<u64> {
+    match c {
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1_000),
+        '万' => Some(10_000),
+        '亿' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+fn is_chinese_numeral(c: char) -> bool {
+    chinese_digit(c).is_some() || chinese_unit(c).is_some()
+}
+
+/// 按位值读的中文数字（"三百二十五"、"一万零五"，以及口语的"一万五" = 15000）
+fn parse_chinese_value(chars: &[char]) -> Option<u64> {
+    let (mut total, mut section, mut current) = (0u64, 0u64, 0u64);
+    let mut digit: Option<u64> = None;
+    let mut last_unit = 1;
+    let mut after_zero = false;
+    for (index, &c) in chars.iter().enumerate() {
+        if let Some(value) = chinese_digit(c) {
+            if digit.is_some() {
+                return None;
+            }
+            if value == 0 {
+                after_zero = true;
+            } else {
+                digit = Some(value);
+            }
+            continue;
+        }
+        let unit = chinese_unit(c)?;
+        if unit < 10_000 {
+            // 只有开头的"十"可以省略前面的"一"
+            let value = match digit.take() {
+                Some(value) => value,
+                None if unit == 10 && index == 0 => 1,
+                None => return None,
+            };
+            current += value * unit;
+        } else {
+            let value = current + digit.take().unwrap_or(0);
+            if value == 0 && section == 0 {
+                return None;
+            }
+            if unit == 10_000 {
+                section = value * unit;
+            } else {
+                total = (section + value) * unit;
+                section = 0;
+            }
+            current = 0;
+        }
+        last_unit = unit;
+        after_zero = false;
+    }
+    let tail = match digit {
+        Some(value) if !after_zero && last_unit >= 10 => value * last_unit / 10,
+        Some(value) => value,
+        None => 0,
+    };
+    Some(total + section + current + tail)
+}
+
+/// 一串中文数字的阿拉伯数字写法：带"十百千万亿"的按位值读，否则逐位读（"二零二四" → "2024"）。
+/// 以"万""亿"结尾的保留这个字（"三万" → "3万"）
+fn chinese_number(chars: &[char]) -> Option<String> {
+    if !chars.iter().any(|c| chinese_unit(*c).is_some()) {
+        return chars.iter().map(|c| chinese_digit(*c).map(|d| d.to_string())).collect();
+    }
+    let value = parse_chinese_value(chars)?;
+    match chars.last() {
+        Some('亿') if value % 100_000_000 == 0 => Some(format!("{}亿", value / 100_000_000)),
+        Some('万') if value % 10_000 == 0 && value < 100_000_000 => Some(format!("{}万", value / 10_000)),
+        _ => Some(value.to_string()),
+    }
+}
+
+fn run_end(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() && is_chinese_numeral(chars[end]) {
+        end += 1;
+    }
+    end
+}
+
+fn starts_with(chars: &[char], at: usize, word: &str) -> bool {
+    (at..).zip(word.chars()).all(|(index, c)| chars.get(index) == Some(&c))
+}
+
+fn chinese_unit_at(chars: &[char], at: usize) -> Option<&'static str> {
+    CHINESE_UNITS.iter().copied().find(|unit| starts_with(chars, at, unit))
+}
+
+fn format_chinese(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        if starts_with(&chars, index, "百分之") {
+            let end = run_end(&chars, index + 3);
+            if let Some((number, next)) = chinese_decimal(&chars, index + 3, end).or_else(|| {
+                let run = &chars[index + 3..end];
+                let number = if run == ['百'] {
+                    Some("100".to_string())
+                } else {
+                    chinese_number(run)
+                };
+                number.map(|number| (number, end))
+            }) {
+                out.push_str(&number);
+                out.push('%');
+                index = next;
+                continue;
+            }
+        }
+
+        let c = chars[index];
+        let starts_run = chinese_digit(c).is_some() || c == '十';
+        if !starts_run || index > 0 && is_chinese_numeral(chars[index - 1]) {
+            out.push(c);
+            index += 1;
+            continue;
+        }
+        let end = run_end(&chars, index);
+        match convert_chinese_run(&chars, index, end, &out) {
+            Some((number, next)) => {
+                if out.ends_with('负') {
+                    out.pop();
+                    out.push('-');
+                }
+                out.push_str(&number);
+                index = next;
+            }
+            None => {
+                out.extend(&chars[index..end]);
+                index = end;
+            }
+        }
+    }
+    out
+}
+
+/// "三点五" → "3.5"：点后面只能是逐位读的数字
+fn chinese_decimal(chars: &[char], start: usize, end: usize) -> Option<(String, usize)> {
+    if start == end || chars.get(end) != Some(&'点') {
+        return None;
+    }
+    let fraction_end = run_end(chars, end + 1);
+    let fraction = &chars[end + 1..fraction_end];
+    if fraction.is_empty() || fraction.iter().any(|c| chinese_unit(*c).is_some()) {
+        return None;
+    }
+    let integer = chinese_number(&chars[start..end])?;
+    let fraction = chinese_number(fraction)?;
+    Some((format!("{integer}.{fraction}"), fraction_end))
+}
+
+/// 转换从 `start` 开始的一串中文数字，返回写法与下一个未处理的位置；不宜转换时返回 `None`
+fn convert_chinese_run(chars: &[char], start: usize, end: usize, before: &str) -> Option<(String, usize)> {
+    let run = &chars[start..end];
+    let value = if run.iter().any(|c| chinese_unit(*c).is_some()) {
+        parse_chinese_value(run)
+    } else {
+        None
+    };
+
+    if chars.get(end) == Some(&'点') {
+        if let Some(time) = chinese_time(chars, end, value.or_else(|| single_digit(run)), before) {
+            return Some(time);
+        }
+        if let Some(decimal) = chinese_decimal(chars, start, end) {
+            return Some(decimal);
+        }
+    }
+
+    let number = chinese_number(run)?;
+    let next = chars.get(end).copied();
+    let digitwise = value.is_none();
+    let single = run.len() == 1;
+    let convert = match next {
+        Some('年') => !single || run[0] != '一',
+        Some('月') => value
+            .or_else(|| single_digit(run))
+            .is_some_and(|month| (1..=12).contains(&month)),
+        Some('日' | '号') => {
+            value
+                .or_else(|| single_digit(run))
+                .is_some_and(|day| (1..=31).contains(&day))
+                && (before.ends_with('月') || run != ['一'])
+        }
+        _ if chinese_unit_at(chars, end).is_some() => run != ['一'],
+        // 逐位读的数字至少三位才转换，"一一对应"、"三三两两"之类保持原样
+        _ if digitwise => run.len() >= 3 && !run.contains(&'两'),
+        _ => !single,
+    };
+    convert.then_some((number, end))
+}
+
+fn single_digit(run: &[char]) -> Option<u64> {
+    match run {
+        [c] => chinese_digit(*c).filter(|value| *value > 0),
+        _ => None,
+    }
+}
+
+/// "三点半" → "3:30"，"三点十五分" → "3:15"，"下午三点" → "下午3点"；`dot` 为"点"的位置
+fn chinese_time(chars: &[char], dot: usize, hour: Option<u64>, before: &str) -> Option<(String, usize)> {
+    let hour = hour.filter(|hour| *hour <= 24)?;
+    let prefixed = CHINESE_TIME_PREFIXES.iter().any(|prefix| before.ends_with(prefix));
+    let after = dot + 1;
+    match chars.get(after) {
+        Some('半') => return Some((format!("{hour}:30"), after + 1)),
+        Some('钟') => return Some((format!("{hour}点钟"), after + 1)),
+        _ if starts_with(chars, after, "一刻") => return Some((format!("{hour}:15"), after + 2)),
+        _ if starts_with(chars, after, "三刻") => return Some((format!("{hour}:45"), after + 2)),
+        _ => {}
+    }
+    let minute_end = run_end(chars, after);
+    if minute_end == after {
+        return prefixed.then(|| (format!("{hour}点"), after));
+    }
+    let minute_run = &chars[after..minute_end];
+    let has_unit = minute_run.iter().any(|c| chinese_unit(*c).is_some());
+    let minute = if has_unit {
+        parse_chinese_value(minute_run)
+    } else {
+        chinese_number(minute_run).and_then(|minute| minute.parse::<u64>().ok())
+    }
+    .filter(|minute| *minute < 60)?;
+    let with_fen = chars.get(minute_end) == Some(&'分') && chars.get(minute_end + 1) != Some(&'之');
+    // 没有"分"也没有时段词时，"三点五"按小数理解，"三点二十"按钟点理解
+    if !with_fen && !prefixed && !has_unit {
+        return None;
+    }
+    let next = if with_fen { minute_end + 1 } else { minute_end };
+    Some((format!("{hour}:{minute:02}"), next))
+}
+
+// ---- 英文 ----
+
+const ENGLISH_UNITS: &[(&str, u64)] = &[
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+const ENGLISH_TEENS: &[(&str, u64)] = &[
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+];
+
+const ENGLISH_TENS: &[(&str, u64)] = &[
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+const ENGLISH_SCALES: &[(&str, u64)] = &[("thousand", 1_000), ("million", 1_000_000), ("billion", 1_000_000_000)];
+
+const ENGLISH_ORDINALS: &[(&str, u64)] = &[
+    ("first", 1),
+    ("second", 2),
+    ("third", 3),
+    ("fourth", 4),
+    ("fifth", 5),
+    ("sixth", 6),
+    ("seventh", 7),
+    ("eighth", 8),
+    ("ninth", 9),
+    ("tenth", 10),
+    ("eleventh", 11),
+    ("twelfth", 12),
+    ("thirteenth", 13),
+    ("fourteenth", 14),
+    ("fifteenth", 15),
+    ("sixteenth", 16),
+    ("seventeenth", 17),
+    ("eighteenth", 18),
+    ("nineteenth", 19),
+    ("twentieth", 20),
+    ("thirtieth", 30),
+];
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// 数字后的单位说法与书面写法；写法为空时只把数字改成阿拉伯数字，单位保持原样
+const ENGLISH_MEASURES: &[(&str, &str)] = &[
+    ("degrees celsius", "°C"),
+    ("degrees fahrenheit", "°F"),
+    ("degrees", "°"),
+    ("kilometers per hour", " km/h"),
+    ("kilometres per hour", " km/h"),
+    ("miles per hour", " mph"),
+    ("kilometers", " km"),
+    ("kilometres", " km"),
+    ("meters", " m"),
+    ("metres", " m"),
+    ("centimeters", " cm"),
+    ("centimetres", " cm"),
+    ("millimeters", " mm"),
+    ("millimetres", " mm"),
+    ("kilograms", " kg"),
+    ("grams", " g"),
+    ("milliliters", " ml"),
+    ("millilitres", " ml"),
+    ("liters", " L"),
+    ("litres", " L"),
+    ("kilobytes", " KB"),
+    ("megabytes", " MB"),
+    ("gigabytes", " GB"),
+    ("terabytes", " TB"),
+    ("percent", "%"),
+    ("per cent", "%"),
+    ("miles", ""),
+    ("feet", ""),
+    ("inches", ""),
+    ("pounds", ""),
+    ("ounces", ""),
+    ("hours", ""),
+    ("minutes", ""),
+    ("seconds", ""),
+    ("cents", ""),
+];
+
+/// 数字后的货币说法与符号
+const CURRENCIES: &[(&str, &str)] = &[
+    ("dollars", "$"),
+    ("dollar", "$"),
+    ("bucks", "$"),
+    ("euros", "€"),
+    ("euro", "€"),
+    ("yuan", "¥"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberWord {
+    Unit(u64),
+    Teen(u64),
+    Tens(u64),
+    Hundred,
+    Scale(u64),
+}
+
+fn lookup(table: &[(&str, u64)], word: &str) -> Option<u64> {
+    table.iter().find(|(name, _)| *name == word).map(|(_, value)| *value)
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    lookup(ENGLISH_UNITS, word)
+        .map(NumberWord::Unit)
+        .or_else(|| lookup(ENGLISH_TEENS, word).map(NumberWord::Teen))
+        .or_else(|| lookup(ENGLISH_TENS, word).map(NumberWord::Tens))
+        .or_else(|| (word == "hundred").then_some(NumberWord::Hundred))
+        .or_else(|| lookup(ENGLISH_SCALES, word).map(NumberWord::Scale))
+}
+
+/// 原文中的一个英文单词
+struct Word<'a> {
+    text: &'a str,
+    lower: String,
+    start: usize,
+    end: usize,
+}
+
+fn split_words(text: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let bytes = text.as_bytes();
+    for (index, c) in text.char_indices() {
+        // "o'clock" 中的撇号算在单词里
+        let inner_apostrophe =
+            c == '\'' && start.is_some() && bytes.get(index + 1).is_some_and(|b| b.is_ascii_alphabetic());
+        if c.is_ascii_alphabetic() || inner_apostrophe {
+            start.get_or_insert(index);
+        } else if let Some(begin) = start.take() {
+            words.push(Word {
+                text: &text[begin..index],
+                lower: text[begin..index].to_lowercase(),
+                start: begin,
+                end: index,
+            });
+        }
+    }
+    if let Some(begin) = start {
+        words.push(Word {
+            text: &text[begin..],
+            lower: text[begin..].to_lowercase(),
+            start: begin,
+            end: text.len(),
+        });
+    }
+    words
+}
+
+/// 英文单词序列上的游标
+struct Cursor<'t, 'w> {
+    text: &'t str,
+    words: &'w [Word<'t>],
+}
+
+impl Cursor<'_, '_> {
+    fn lower(&self, index: usize) -> Option<&str> {
+        self.words.get(index).map(|word| word.lower.as_str())
+    }
+
+    /// 第 `index` 个单词与前一个之间只隔着一个空格或连字符
+    fn joined(&self, index: usize) -> bool {
+        index > 0
+            && index < self.words.len()
+            && matches!(
+                &self.text[self.words[index - 1].end..self.words[index].start],
+                " " | "-"
+            )
+    }
+
+    /// 从 `index` 起连续的 `phrase`（空格分隔的小写单词），返回其后的位置
+    fn phrase(&self, index: usize, phrase: &str) -> Option<usize> {
+        let mut at = index;
+        for part in phrase.split(' ') {
+            if self.lower(at) != Some(part) || (at > index && !self.joined(at)) {
+                return None;
+            }
+            at += 1;
+        }
+        Some(at)
+    }
+
+    /// 从 `index` 起读一个英文基数词，返回数值与其后的位置
+    fn cardinal(&self, index: usize) -> Option<(u64, usize)> {
+        let (mut total, mut current) = (0u64, 0u64);
+        let mut last: Option<NumberWord> = None;
+        let mut end = index;
+        let mut at = index;
+        while let Some(word) = self.lower(at) {
+            if at > index && !self.joined(at) {
+                break;
+            }
+            // "three hundred and five"
+            if word == "and" && matches!(last, Some(NumberWord::Hundred | NumberWord::Scale(_))) {
+                let next = self.lower(at + 1).and_then(number_word);
+                if matches!(
+                    next,
+                    Some(NumberWord::Unit(_) | NumberWord::Teen(_) | NumberWord::Tens(_))
+                ) && self.joined(at + 1)
+                {
+                    at += 1;
+                    continue;
+                }
+                break;
+            }
+            let Some(kind) = number_word(word) else {
+                break;
+            };
+            let accepted = match kind {
+                NumberWord::Unit(value) => {
+                    let after_tens = matches!(last, Some(NumberWord::Tens(_)));
+                    let fresh = matches!(last, None | Some(NumberWord::Hundred | NumberWord::Scale(_)));
+                    (after_tens || fresh) && (value > 0 || last.is_none())
+                }
+                NumberWord::Teen(_) | NumberWord::Tens(_) => {
+                    matches!(last, None | Some(NumberWord::Hundred | NumberWord::Scale(_)))
+                }
+                NumberWord::Hundred => current > 0 && current < 100,
+                NumberWord::Scale(_) => current > 0,
+            };
+            if !accepted || (last.is_none() && matches!(kind, NumberWord::Hundred | NumberWord::Scale(_))) {
+                break;
+            }
+            match kind {
+                NumberWord::Unit(value) | NumberWord::Teen(value) | NumberWord::Tens(value) => current += value,
+                NumberWord::Hundred => current *= 100,
+                NumberWord::Scale(scale) => {
+                    total += current * scale;
+                    current = 0;
+                }
+            }
+            last = Some(kind);
+            at += 1;
+            end = at;
+        }
+        (end > index).then_some((total + current, end))
+    }
+
+    /// 连续的数字单词个数（不论能否组成一个数）
+    fn number_run(&self, index: usize) -> usize {
+        let mut at = index;
+        while self.lower(at).and_then(number_word).is_some() && (at == index || self.joined(at)) {
+            at += 1;
+        }
+        at - index
+    }
+
+    fn ordinal(&self, index: usize) -> Option<(u64, usize)> {
+        if let Some(value) = self.lower(index).and_then(|word| lookup(ENGLISH_ORDINALS, word)) {
+            return Some((value, index + 1));
+        }
+        let tens = self.lower(index).and_then(|word| lookup(ENGLISH_TENS, word))?;
+        let unit = self
+            .lower(index + 1)
+            .filter(|_| self.joined(index + 1))
+            .and_then(|word| lookup(ENGLISH_ORDINALS, word))
+            .filter(|unit| *unit < 10)?;
+        Some((tens + unit, index + 2))
+    }
+
+    /// "am"、"pm"、"a.m."、"p m"，返回 "AM"/"PM" 与结束的字节位置
+    fn meridiem(&self, index: usize) -> Option<(&'static str, usize)> {
+        let word = self.words.get(index)?;
+        if !matches!(&self.text[self.words[index - 1].end..word.start], " ") {
+            return None;
+        }
+        let label = |word: &str| match word {
+            "am" | "a" => Some("AM"),
+            "pm" | "p" => Some("PM"),
+            _ => None,
+        };
+        let label_for = label(&word.lower)?;
+        if word.lower.len() == 2 {
+            return Some((label_for, word.end));
+        }
+        let m = self.words.get(index + 1).filter(|m| m.lower == "m")?;
+        if !matches!(&self.text[word.end..m.start], "." | " ") {
+            return None;
+        }
+        let end = if self.text[m.end..].starts_with('.') {
+            m.end + 1
+        } else {
+            m.end
+        };
+        Some((label_for, end))
+    }
+}
+
+/// 一处替换：原文字节范围与新的写法
+struct Replacement {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+fn format_english(text: &str) -> String {
+    let words = split_words(text);
+    let cursor = Cursor { text, words: &words };
+    let mut replacements = Vec::new();
+    let mut index = 0;
+    while index < words.len() {
+        let found = english_date(&cursor, index).or_else(|| english_number(&cursor, index));
+        match found {
+            Some((replacement, next)) => {
+                replacements.push(replacement);
+                index = next;
+            }
+            // 组不成一个数的整串跳过，不从中间重新开始
+            None => index += cursor.number_run(index).max(1),
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for replacement in replacements {
+        out.push_str(&text[copied..replacement.start]);
+        out.push_str(&replacement.text);
+        copied = replacement.end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// "March fifth twenty twenty four" → "March 5, 2024"
+fn english_date(cursor: &Cursor, index: usize) -> Option<(Replacement, usize)> {
+    let month = cursor.words.get(index)?;
+    let month_index = MONTHS.iter().position(|name| *name == month.lower)?;
+    // "may"、"march" 也是常用词，只认首字母大写的
+    if matches!(month_index, 2 | 4) && !month.text.starts_with(char::is_uppercase) {
+        return None;
+    }
+    if !cursor.joined(index + 1) {
+        return None;
+    }
+    let (day, mut next) = cursor
+        .ordinal(index + 1)
+        .or_else(|| cursor.cardinal(index + 1))
+        .filter(|(day, _)| (1..=31).contains(day))?;
+    let mut written = format!("{} {day}", month.text);
+    if let Some((year, after)) = english_year(cursor, next) {
+        written.push_str(&format!(", {year}"));
+        next = after;
+    }
+    let end = cursor.words[next - 1].end;
+    Some((
+        Replacement {
+            start: month.start,
+            end,
+            text: written,
+        },
+        next,
+    ))
+}
+
+/// 日期后的年份："twenty twenty four"、"two thousand five"、"nineteen ninety nine"
+fn english_year(cursor: &Cursor, index: usize) -> Option<(u64, usize)> {
+    let gap = cursor
+        .words
+        .get(index)
+        .map(|word| &cursor.text[cursor.words[index - 1].end..word.start])?;
+    if !matches!(gap, " " | ", ") {
+        return None;
+    }
+    let (first, after_first) = cursor.cardinal(index)?;
+    if (1000..=2999).contains(&first) {
+        return Some((first, after_first));
+    }
+    if !(10..=99).contains(&first) || !cursor.joined(after_first) {
+        return None;
+    }
+    let (second, after_second) = if cursor.lower(after_first) == Some("oh") {
+        let (unit, after) = cursor.cardinal(after_first + 1).filter(|(unit, _)| *unit < 10)?;
+        (unit, after)
+    } else {
+        cursor
+            .cardinal(after_first)
+            .filter(|(second, _)| (10..=99).contains(second))?
+    };
+    Some((first * 100 + second, after_second))
+}
+
+/// 从 `index` 开始的数字及其后的时间、小数、货币、单位说法
+fn english_number(cursor: &Cursor, index: usize) -> Option<(Replacement, usize)> {
+    let (value, after) = cursor.cardinal(index)?;
+    let start = cursor.words[index].start;
+    let replace = |text: String, next: usize, end: usize| Some((Replacement { start, end, text }, next));
+
+    // 时间："five thirty pm"、"ten oh five a.m."、"seven pm"、"five o'clock"
+    if (1..=12).contains(&value) {
+        let (minute, after_minute) = if cursor.lower(after) == Some("oh") && cursor.joined(after) {
+            cursor
+                .cardinal(after + 1)
+                .filter(|(minute, _)| *minute < 10)
+                .map_or((None, after), |(minute, next)| (Some(minute), next))
+        } else if cursor.joined(after) {
+            cursor
+                .cardinal(after)
+                .filter(|(minute, _)| (10..60).contains(minute))
+                .map_or((None, after), |(minute, next)| (Some(minute), next))
+        } else {
+            (None, after)
+        };
+        if let Some((label, end)) = cursor.meridiem(after_minute) {
+            let next = if cursor.lower(after_minute + 1) == Some("m") && end > cursor.words[after_minute].end {
+                after_minute + 2
+            } else {
+                after_minute + 1
+            };
+            let time = match minute {
+                Some(minute) => format!("{value}:{minute:02} {label}"),
+                None => format!("{value} {label}"),
+            };
+            return replace(time, next, end);
+        }
+        if minute.is_none() && cursor.lower(after) == Some("o'clock") && cursor.joined(after) {
+            return replace(format!("{value} o'clock"), after + 1, cursor.words[after].end);
+        }
+    }
+
+    // 小数："three point one four"
+    let mut number = value.to_string();
+    let mut next = after;
+    if cursor.lower(after) == Some("point") && cursor.joined(after) {
+        let mut digits = String::new();
+        let mut at = after + 1;
+        while let Some(digit) = cursor.lower(at).filter(|_| cursor.joined(at)).and_then(|word| {
+            if word == "oh" {
+                Some(0)
+            } else {
+                lookup(ENGLISH_UNITS, word)
+            }
+        }) {
+            digits.push_str(&digit.to_string());
+            at += 1;
+        }
+        if !digits.is_empty() {
+            number = format!("{value}.{digits}");
+            next = at;
+        }
+    }
+    let decimal = next != after;
+
+    // 货币："ten dollars"、"five dollars and fifty cents"
+    if cursor.joined(next) {
+        if let Some((_, symbol)) = CURRENCIES.iter().find(|(name, _)| cursor.lower(next) == Some(*name)) {
+            let mut written = format!("{symbol}{number}");
+            let mut after_currency = next + 1;
+            if !decimal && cursor.phrase(after_currency, "and").is_some() && cursor.joined(after_currency + 1) {
+                if let Some((cents, after_cents)) = cursor.cardinal(after_currency + 1).filter(|(c, _)| *c < 100) {
+                    if matches!(cursor.lower(after_cents), Some("cents" | "cent")) && cursor.joined(after_cents) {
+                        written = format!("{symbol}{value}.{cents:02}");
+                        after_currency = after_cents + 1;
+                    }
+                }
+            }
+            return replace(written, after_currency, cursor.words[after_currency - 1].end);
+        }
+        // 单位："five kilometers"、"twenty degrees celsius"、"fifty percent"
+        for (name, written) in ENGLISH_MEASURES {
+            if let Some(after_unit) = cursor.phrase(next, name) {
+                if written.is_empty() {
+                    return replace(number, next, cursor.words[next - 1].end);
+                }
+                return replace(
+                    format!("{number}{written}"),
+                    after_unit,
+                    cursor.words[after_unit - 1].end,
+                );
+            }
+        }
+    }
+
+    // 其它数字：十以上或多个单词组成的才改写，"one"、"two" 这类常用词保持原样；
+    // 连在一起却组不成一个数的（"five thirty"）整串保持原样
+    if !decimal && cursor.joined(after) && cursor.lower(after).and_then(number_word).is_some() {
+        return None;
+    }
+    if decimal || value >= 10 || after - index > 1 {
+        return replace(number, next, cursor.words[next - 1].end);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spoken_numbers_become_written_forms() {
+        let formatter = NumberFormatter::new(&NumberConfig {
+            enabled: true,
+            ..NumberConfig::default()
+        });
+        let cases = [
+            ("一共三百二十五个", "一共325个"),
+            ("预算一万五，涨了百分之五十", "预算15000，涨了50%"),
+            ("二零二四年三月五日下午三点半开会", "2024年3月5日下午3:30开会"),
+            ("三点十五分出发，跑了三点五公里", "3:15出发，跑了3.5公里"),
+            ("零下负五度，两块钱，三万人", "零下-5度，2块钱，3万人"),
+            ("一起十分好，千万别一一对应", "一起十分好，千万别一一对应"),
+            (
+                "I have twenty five apples and one pear",
+                "I have 25 apples and one pear",
+            ),
+            (
+                "Meet at five thirty pm, or ten oh five a.m.",
+                "Meet at 5:30 PM, or 10:05 AM",
+            ),
+            ("It costs twenty dollars and fifty cents", "It costs $20.50"),
+            ("Three hundred and twenty five kilometers", "325 km"),
+            ("fifty percent at twenty degrees celsius", "50% at 20°C"),
+            ("pi is three point one four", "pi is 3.14"),
+            ("Due March fifth twenty twenty four", "Due March 5, 2024"),
+            ("you may go, five thirty is fine", "you may go, five thirty is fine"),
+        ];
+        for (spoken, written) in cases {
+            assert_eq!(formatter.format(spoken), written, "{spoken}");
+        }
+
+        let chinese_only = NumberFormatter::new(&NumberConfig {
+            enabled: true,
+            english: false,
+            ..NumberConfig::default()
+        });
+        assert_eq!(chinese_only.format("二十个 twenty five"), "20个 twenty five");
+        assert_eq!(NumberFormatter::default().format("三百"), "三百");
+    }
+}
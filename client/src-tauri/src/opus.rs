@@ -1,7 +1,13 @@
-use anyhow::Context as _;
+use std::collections::HashMap;
 
+use anyhow::{anyhow, Context as _};
+use audiopus::coder::Encoder;
+use audiopus::{Application, Channels, SampleRate};
+
+/// 单声道 Opus 编码器；各平台统一使用 audiopus 链接的 libopus
 pub struct OpusEncoder {
-    inner: imp::OpusEncoder,
+    encoder: Encoder,
+    sample_rate: u32,
 }
 
 // Opus encoder 只要不并发使用（我们保证单线程/单任务顺序调用），跨线程移动是安全的。
@@ -9,142 +15,137 @@ unsafe impl Send for OpusEncoder {}
 
 impl OpusEncoder {
     pub fn new(sample_rate: u32) -> anyhow::Result<Self> {
-        Ok(Self {
-            inner: imp::OpusEncoder::new(sample_rate).context("init opus encoder")?,
-        })
+        let rate = opus_sample_rate(sample_rate)?;
+        let encoder = Encoder::new(rate, Channels::Mono, Application::Voip).context("init opus encoder")?;
+        Ok(Self { encoder, sample_rate })
     }
 
+    /// 编码一帧 PCM，返回写入 `out` 的字节数；空输入返回 0
     pub fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> anyhow::Result<usize> {
-        self.inner.encode(pcm, out)
+        if pcm.is_empty() {
+            return Ok(0);
+        }
+        if out.is_empty() {
+            return Err(anyhow!("opus output buffer is empty"));
+        }
+        if !is_valid_frame(self.sample_rate, pcm.len()) {
+            return Err(anyhow!(
+                "不支持的 Opus 帧长 | Unsupported Opus frame size: {} samples at {} Hz \
+                 (支持 2.5/5/10/20/40/60/80/100/120 ms)",
+                pcm.len(),
+                self.sample_rate
+            ));
+        }
+        self.encoder.encode(pcm, out).context("opus_encode")
     }
-}
-
-#[cfg(target_os = "windows")]
-mod imp {
-    use anyhow::anyhow;
-    use audiopus::{coder::Encoder, Application, Channels, SampleRate};
 
-    pub struct OpusEncoder {
-        encoder: Encoder,
+    /// 清空编码状态，下一帧像新建的编码器一样开始
+    fn reset(&mut self) -> anyhow::Result<()> {
+        self.encoder.reset_state().context("opus reset state")
     }
+}
+
+/// 按采样率缓存用过的编码器：会话结束时归还并重置，下次同采样率的会话直接取用，
+/// 开始录音时不再分配和初始化编码器
+#[derive(Default)]
+pub struct EncoderPool {
+    idle: HashMap<u32, OpusEncoder>,
+}
 
-    impl OpusEncoder {
-        pub fn new(sample_rate: u32) -> anyhow::Result<Self> {
-            let sr = match sample_rate {
-                8000 => SampleRate::Hz8000,
-                12000 => SampleRate::Hz12000,
-                16000 => SampleRate::Hz16000,
-                24000 => SampleRate::Hz24000,
-                48000 => SampleRate::Hz48000,
-                _ => return Err(anyhow!("unsupported sample rate for opus: {sample_rate}")),
-            };
-
-            Ok(Self {
-                encoder: Encoder::new(sr, Channels::Mono, Application::Voip)?,
-            })
+impl EncoderPool {
+    pub fn take(&mut self, sample_rate: u32) -> anyhow::Result<OpusEncoder> {
+        match self.idle.remove(&sample_rate) {
+            Some(encoder) => Ok(encoder),
+            None => OpusEncoder::new(sample_rate),
         }
+    }
 
-        pub fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> anyhow::Result<usize> {
-            Ok(self.encoder.encode(pcm, out)?)
+    /// 归还编码器；重置失败的直接丢弃
+    pub fn put(&mut self, mut encoder: OpusEncoder) {
+        if encoder.reset().is_ok() {
+            self.idle.insert(encoder.sample_rate, encoder);
         }
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-mod imp {
-    use std::ffi::CStr;
-    use std::ptr::NonNull;
-
-    use anyhow::{anyhow, Context as _};
-    use opus_sys as opus;
-
-    const OPUS_APPLICATION_VOIP: i32 = 2048;
-
-    pub struct OpusEncoder {
-        encoder: NonNull<opus::OpusEncoder>,
-        channels: i32,
+fn opus_sample_rate(sample_rate: u32) -> anyhow::Result<SampleRate> {
+    match sample_rate {
+        8000 => Ok(SampleRate::Hz8000),
+        12000 => Ok(SampleRate::Hz12000),
+        16000 => Ok(SampleRate::Hz16000),
+        24000 => Ok(SampleRate::Hz24000),
+        48000 => Ok(SampleRate::Hz48000),
+        _ => Err(anyhow!(
+            "不支持的 Opus 采样率 | Unsupported Opus sample rate: {sample_rate} (支持 8000/12000/16000/24000/48000)"
+        )),
     }
+}
 
-    impl OpusEncoder {
-        pub fn new(sample_rate: u32) -> anyhow::Result<Self> {
-            if !matches!(sample_rate, 8000 | 12000 | 16000 | 24000 | 48000) {
-                return Err(anyhow!(
-                    "不支持的 Opus 采样率 | Unsupported Opus sample rate: {} (支持 8000/12000/16000/24000/48000)",
-                    sample_rate
-                ));
-            }
-
-            let channels = 1i32;
-            let mut err = 0i32;
-            let encoder = unsafe {
-                opus::opus_encoder_create(
-                    sample_rate as i32,
-                    channels,
-                    OPUS_APPLICATION_VOIP,
-                    &mut err as *mut i32,
-                )
-            };
-
-            let encoder = NonNull::new(encoder).ok_or_else(|| anyhow!("opus encoder create returned null"))?;
-            if err != opus::OPUS_OK {
-                return Err(anyhow!("opus encoder create failed: {}", opus_error(err)));
-            }
-
-            Ok(Self { encoder, channels })
-        }
-
-        pub fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> anyhow::Result<usize> {
-            if out.is_empty() {
-                return Err(anyhow!("opus output buffer is empty"));
-            }
+/// libopus 只接受 2.5 ms 的整数倍中的固定几档帧长
+fn is_valid_frame(sample_rate: u32, samples: usize) -> bool {
+    let unit = sample_rate as usize / 400;
+    [1, 2, 4, 8, 16, 24, 32, 40, 48]
+        .iter()
+        .any(|units| units * unit == samples)
+}
 
-            let channels = self.channels as usize;
-            if channels == 0 {
-                return Err(anyhow!("opus channels invalid"));
-            }
-            if pcm.len() % channels != 0 {
-                return Err(anyhow!("opus pcm length {} not divisible by channels {}", pcm.len(), channels));
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let frame_size = (pcm.len() / channels) as i32;
-            if frame_size <= 0 {
-                return Ok(0);
-            }
+    fn tone(samples: usize) -> Vec<i16> {
+        (0..samples)
+            .map(|i| ((i as f32 * 0.05).sin() * 8000.0) as i16)
+            .collect()
+    }
 
-            let encoded = unsafe {
-                opus::opus_encode(
-                    self.encoder.as_ptr(),
-                    pcm.as_ptr(),
-                    frame_size,
-                    out.as_mut_ptr(),
-                    out.len() as i32,
-                )
-            };
-
-            if encoded < 0 {
-                return Err(anyhow!("opus encode failed: {}", opus_error(encoded as i32)))
-                    .context("opus_encode");
+    #[test]
+    fn supported_frame_sizes_encode() {
+        let mut out = [0u8; 4000];
+        for sample_rate in [8000, 16000, 48000] {
+            let mut encoder = OpusEncoder::new(sample_rate).expect("encoder");
+            // 2.5/5/10/20/40/60 ms
+            for tenth_ms in [25, 50, 100, 200, 400, 600] {
+                let samples = sample_rate as usize * tenth_ms / 10_000;
+                let written = encoder.encode(&tone(samples), &mut out).expect("encode");
+                assert!(
+                    written > 0 && written <= out.len(),
+                    "{sample_rate} Hz, {samples} samples"
+                );
             }
-
-            Ok(encoded as usize)
         }
     }
 
-    impl Drop for OpusEncoder {
-        fn drop(&mut self) {
-            unsafe {
-                opus::opus_encoder_destroy(self.encoder.as_ptr());
-            }
-        }
+    #[test]
+    fn pooled_encoders_are_reused_per_sample_rate() {
+        let mut pool = EncoderPool::default();
+        let mut out = [0u8; 4000];
+        let mut encoder = pool.take(16000).expect("encoder");
+        let first = encoder.encode(&tone(320), &mut out).expect("encode");
+        let first = out[..first].to_vec();
+        pool.put(encoder);
+        pool.put(OpusEncoder::new(48000).expect("encoder"));
+        assert_eq!(pool.idle.len(), 2);
+
+        // 重置后的编码器与新建的输出相同
+        let mut encoder = pool.take(16000).expect("reused");
+        assert_eq!(pool.idle.len(), 1);
+        let again = encoder.encode(&tone(320), &mut out).expect("encode");
+        assert_eq!(out[..again], first[..]);
+        assert!(pool.take(24000).is_ok());
+        assert_eq!(pool.idle.len(), 1);
     }
 
-    fn opus_error(code: i32) -> String {
-        unsafe {
-            let ptr = opus::opus_strerror(code);
-            if ptr.is_null() {
-                return format!("code={code}");
-            }
-            CStr::from_ptr(ptr).to_string_lossy().to_string()
-        }
+    #[test]
+    fn invalid_input_is_rejected() {
+        assert!(OpusEncoder::new(44100).is_err());
+
+        let mut encoder = OpusEncoder::new(16000).expect("encoder");
+        let mut out = [0u8; 4000];
+        assert_eq!(encoder.encode(&[], &mut out).expect("empty"), 0);
+        assert!(encoder.encode(&tone(321), &mut out).is_err());
+        assert!(encoder.encode(&tone(320), &mut []).is_err());
+        // 出错后编码器仍可继续使用
+        assert!(encoder.encode(&tone(320), &mut out).expect("encode") > 0);
     }
 }
@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use active_win_pos_rs::WindowPosition;
+use tauri::Manager;
+use tracing::warn;
+
+use crate::platform::CaretRect;
+
+const OVERLAY_LABEL: &str = "overlay";
+
+/// 与 tauri.conf.json 中 overlay 窗口尺寸保持一致
+const OVERLAY_WIDTH: f64 = 360.0;
+const OVERLAY_HEIGHT: f64 = 48.0;
+
+/// 悬浮窗距离目标窗口底边的距离
+const OVERLAY_MARGIN: f64 = 56.0;
+
+/// 悬浮窗与文本光标之间的距离
+const CARET_GAP: f64 = 8.0;
+
+/// 注入完成后保留片刻再隐藏，让用户看清最终结果
+const HIDE_DELAY: Duration = Duration::from_millis(800);
+
+/// 录音/识别期间的悬浮提示窗
+pub struct OverlayController {
+    app: tauri::AppHandle,
+    /// 每次显示递增，避免延迟隐藏把新会话的悬浮窗关掉
+    generation: Arc<AtomicU64>,
+}
+
+impl OverlayController {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self {
+            app,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 能拿到文本光标时显示在光标旁，否则在目标窗口底部居中；都拿不到时保持上次的位置。
+    pub fn show_near(&self, target: Option<&WindowPosition>, caret: Option<&CaretRect>) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let Some(window) = self.app.get_webview_window(OVERLAY_LABEL) else {
+            return;
+        };
+
+        let position = match (caret, target) {
+            (Some(caret), target) => Some(caret_overlay_position(caret, target)),
+            (None, Some(target)) => Some(overlay_position(target)),
+            (None, None) => None,
+        };
+        if let Some((x, y)) = position {
+            #[cfg(target_os = "macos")]
+            let position = tauri::Position::Logical(tauri::LogicalPosition::new(x, y));
+            #[cfg(not(target_os = "macos"))]
+            let position = tauri::Position::Physical(tauri::PhysicalPosition::new(x as i32, y as i32));
+            if let Err(err) = window.set_position(position) {
+                warn!(target: "overlay", error = %err, "悬浮窗定位失败 | Overlay position failed");
+            }
+        }
+
+        let _ = window.set_ignore_cursor_events(true);
+        if let Err(err) = window.show() {
+            warn!(target: "overlay", error = %err, "悬浮窗显示失败 | Overlay show failed");
+        }
+    }
+
+    pub fn hide_later(&self) {
+        let gen = self.generation.load(Ordering::SeqCst);
+        let generation = self.generation.clone();
+        let app = self.app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(HIDE_DELAY).await;
+            if generation.load(Ordering::SeqCst) != gen {
+                return;
+            }
+            if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+                let _ = window.hide();
+            }
+        });
+    }
+}
+
+/// 目标窗口底部居中、向上留出边距；窗口太小时贴着顶部
+fn overlay_position(target: &WindowPosition) -> (f64, f64) {
+    let x = target.x + (target.width - OVERLAY_WIDTH) / 2.0;
+    let y = (target.y + target.height - OVERLAY_HEIGHT - OVERLAY_MARGIN).max(target.y);
+    (x.max(0.0), y.max(0.0))
+}
+
+/// 光标下方、与光标左对齐；会超出目标窗口底边时改放到光标上方
+fn caret_overlay_position(caret: &CaretRect, target: Option<&WindowPosition>) -> (f64, f64) {
+    let below = caret.y + caret.height + CARET_GAP;
+    let overflows = target.is_some_and(|target| below + OVERLAY_HEIGHT > target.y + target.height);
+    let y = if overflows {
+        caret.y - CARET_GAP - OVERLAY_HEIGHT
+    } else {
+        below
+    };
+    (caret.x.max(0.0), y.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_is_centered_near_bottom_of_target() {
+        let target = WindowPosition {
+            x: 100.0,
+            y: 50.0,
+            width: 1000.0,
+            height: 800.0,
+        };
+        assert_eq!(overlay_position(&target), (420.0, 746.0));
+
+        let tiny = WindowPosition {
+            x: 0.0,
+            y: 10.0,
+            width: 200.0,
+            height: 60.0,
+        };
+        assert_eq!(overlay_position(&tiny), (0.0, 10.0));
+
+        let caret = CaretRect {
+            x: 300.0,
+            y: 200.0,
+            width: 2.0,
+            height: 20.0,
+        };
+        assert_eq!(caret_overlay_position(&caret, Some(&target)), (300.0, 228.0));
+        assert_eq!(caret_overlay_position(&caret, None), (300.0, 228.0));
+        let near_bottom = CaretRect { y: 780.0, ..caret };
+        assert_eq!(caret_overlay_position(&near_bottom, Some(&target)), (300.0, 724.0));
+    }
+}
@@ -1,44 +1,399 @@
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use anyhow::Context as _;
 use tokio::sync::{mpsc, watch};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, field, info, info_span, warn, Instrument as _, Span};
 
-use crate::asr::{self, AsrContext, AsrEngine};
+use crate::asr::{self, AsrContext, AsrEngine, DeferredSession};
+use crate::audio::LevelMeter;
+use crate::audit::{self, AuditKind};
+use crate::code_mode::CodeFormatter;
+use crate::config::{self, AppOverride, ClientConfig, SessionOverrides};
+use crate::content_filter::ContentFilter;
+use crate::dictionary::LearnedDictionary;
+use crate::error::{AppError, ErrorCode};
+use crate::events::{EventSender, LateCorrection, PipelineEvent, SessionSummary};
 use crate::input::{InjectCommand, Injector};
+use crate::language::{self, Language, LanguageConfig};
 use crate::llm::{self, LlmEngine};
+use crate::network::{ConnectionManager, TrafficStats};
+use crate::numbers::NumberFormatter;
+use crate::plugin::{PluginConfig, PluginSet, PluginStage};
+use crate::power;
+use crate::privacy;
+use crate::punctuation::PunctuationConfig;
+use crate::reliability::InjectionReliability;
+use crate::rules::RuleSet;
+use crate::segment::{self, SegmentConfig, Segmenter};
+use crate::sink::{self, SinkContext, Sinks, Transcript};
+use crate::snippet::{Snippet, SnippetLibrary};
+use crate::throttle::{self, ThrottleConfig};
+use crate::voice_command::{Command, CommandSet, EditAction, InjectionHistory};
+
+/// 最多暂存的离线会话数，超出后按普通失败处理
+const MAX_DEFERRED_SESSIONS: usize = 10;
+
+/// 离线会话的有效期：太久之后焦点早已变化，补交的文字不再有意义
+const DEFERRED_MAX_AGE: Duration = Duration::from_secs(10 * 60);
 
 pub struct Pipeline {
     asr: Box<dyn AsrEngine>,
+    asr_config: asr::AsrConfig,
     llm: Arc<dyn LlmEngine>,
+    llm_config: llm::LlmConfig,
     injector: Injector,
+    /// 识别结果的输出端（默认只输入到当前焦点）
+    sinks: Sinks,
+    events: EventSender,
     generation: Arc<AtomicU64>,
     cancel_tx: watch::Sender<u64>,
     _cancel_rx: watch::Receiver<u64>,
     trace_id: Option<String>,
+    app_name: String,
     injected_len: usize,
+    /// 本次会话已送入引擎的采样数，用于统计录音时长
+    fed_samples: u64,
+    fed_frames: u64,
+    sample_rate: u32,
+    level_meter: LevelMeter,
+    /// 最近一次最终输出的文字（校正后以校正结果为准），用于"重新输入"
+    last_output: Arc<Mutex<Option<String>>>,
+    app_overrides: BTreeMap<String, AppOverride>,
+    /// 输入前应用的后处理规则
+    rules: Arc<RuleSet>,
+    /// 敏感词过滤，在所有后处理之后应用
+    content_filter: Arc<ContentFilter>,
+    /// 语音编辑命令（"删掉"、"换行"等）
+    commands: CommandSet,
+    /// 语音片段（"插入 <片段名>"）
+    snippets: SnippetLibrary,
+    /// WASM 后处理插件
+    plugins: Arc<PluginSet>,
+    plugin_config: PluginConfig,
+    /// 代码听写的符号映射与命名风格
+    code_formatter: Arc<CodeFormatter>,
+    /// 口述数字、日期、时间、金额与单位的书面写法
+    numbers: Arc<NumberFormatter>,
+    /// 按识别结果的语言整理标点与间距
+    language_config: LanguageConfig,
+    /// 中英混排的标点宽度与空格，LLM 校正后同样整理
+    punctuation: PunctuationConfig,
+    /// 个人词典：学到的说法在规则之前替换，并作为 LLM 术语表
+    dictionary: Arc<LearnedDictionary>,
+    /// 按目标应用记录的输入，编辑命令据此回删或选中
+    injection_history: Arc<Mutex<InjectionHistory>>,
+    /// 各应用模拟键盘输入的可靠度，未指定注入方式的应用据此选择
+    reliability: Arc<InjectionReliability>,
+    session: SessionOverrides,
+    deferred: DeferredQueue,
+    /// ASR 会话已开始、尚未结束
+    active: bool,
+    /// 录音中保存的配置，会话结束后再应用
+    pending_config: Option<ClientConfig>,
+    segment_config: SegmentConfig,
+    /// 服务器声明支持时由服务器做 LLM 校正（配置中的 `use_cloud_api`）
+    server_correction: bool,
+    /// 按 ASR 置信度跳过或强制 LLM 校正
+    correction_gate: llm::CorrectionGate,
+    correction_timing: llm::CorrectionTiming,
+    /// 使用电池或散热受限时改用的后端
+    throttle: ThrottleConfig,
+    /// 因电源状态正在使用的 ASR/LLM 后端；为空表示使用配置的后端
+    asr_fallback: Option<asr::AsrConfig>,
+    llm_fallback: Option<llm::LlmConfig>,
+    /// 分段输出时判断分段位置，未启用时为空
+    segmenter: Option<Segmenter>,
+    /// 本次会话中间段重新开始 ASR 时使用
+    context: AsrContext,
+    /// 本次会话已输出的中间段（含分隔空格）
+    committed: String,
+    segment_index: u32,
+    /// 本次会话是改写选中文字：录下的话是改写指令，不输入
+    transform: bool,
+    /// 本次会话的链路 span
+    spans: SessionSpans,
 }
 
 impl Pipeline {
-    pub fn new(asr_config: &asr::AsrConfig, llm_config: &llm::LlmConfig, injector: Injector) -> anyhow::Result<Self> {
+    pub fn new(
+        asr_config: &asr::AsrConfig,
+        llm_config: &llm::LlmConfig,
+        injector: Injector,
+        events: EventSender,
+    ) -> anyhow::Result<Self> {
         let asr = asr::create_engine(asr_config)?;
         let llm_engine = llm::create_engine(llm_config)?;
         let llm: Arc<dyn LlmEngine> = Arc::from(llm_engine);
         let (cancel_tx, cancel_rx) = watch::channel::<u64>(0);
+        let last_output = Arc::new(Mutex::new(None));
+        let injection_history = Arc::new(Mutex::new(InjectionHistory::default()));
+        let deferred = DeferredQueue::spawn(events.clone(), last_output.clone(), injection_history.clone());
 
         Ok(Self {
             asr,
+            asr_config: asr_config.clone(),
             llm,
+            llm_config: llm_config.clone(),
+            sinks: Sinks::keyboard(injector.clone()),
             injector,
+            events,
             generation: Arc::new(AtomicU64::new(0)),
             cancel_tx,
             _cancel_rx: cancel_rx,
             trace_id: None,
+            app_name: String::new(),
             injected_len: 0,
+            fed_samples: 0,
+            fed_frames: 0,
+            sample_rate: 16000,
+            level_meter: LevelMeter::new(16000),
+            last_output,
+            app_overrides: BTreeMap::new(),
+            rules: Arc::new(RuleSet::default()),
+            content_filter: Arc::new(ContentFilter::default()),
+            commands: CommandSet::default(),
+            snippets: SnippetLibrary::default(),
+            plugins: Arc::new(PluginSet::default()),
+            plugin_config: PluginConfig::default(),
+            code_formatter: Arc::new(CodeFormatter::default()),
+            numbers: Arc::new(NumberFormatter::default()),
+            language_config: LanguageConfig::default(),
+            punctuation: PunctuationConfig::default(),
+            dictionary: Arc::new(LearnedDictionary::in_memory()),
+            injection_history,
+            reliability: Arc::new(InjectionReliability::in_memory()),
+            session: SessionOverrides::default(),
+            deferred,
+            active: false,
+            pending_config: None,
+            segment_config: SegmentConfig::default(),
+            server_correction: false,
+            correction_gate: llm::CorrectionGate::default(),
+            correction_timing: llm::CorrectionTiming::default(),
+            throttle: ThrottleConfig::default(),
+            asr_fallback: None,
+            llm_fallback: None,
+            segmenter: None,
+            context: AsrContext::default(),
+            committed: String::new(),
+            segment_index: 0,
+            transform: false,
+            spans: SessionSpans::none(),
         })
     }
 
+    /// 替换 ASR 引擎（切换后端/配置时使用），只应在没有进行中的会话时调用
+    pub fn replace_asr(&mut self, asr_config: &asr::AsrConfig) -> anyhow::Result<()> {
+        self.asr = asr::create_engine(asr_config)?;
+        self.asr_config = asr_config.clone();
+        self.asr_fallback = None;
+        self.trace_id = None;
+        self.injected_len = 0;
+        info!(target: "pipeline", "ASR 引擎已切换 | ASR engine replaced");
+        Ok(())
+    }
+
+    /// 用于抢占识别中的会话，不需要持有 Pipeline 的锁
+    pub fn preempt_handle(&self) -> PreemptHandle {
+        PreemptHandle(self.generation.clone())
+    }
+
+    /// 是否有开始后尚未结束的会话
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// 当前 ASR 引擎的长连接（非 WebSocket 后端为空）
+    pub fn asr_connection(&self) -> Option<Arc<ConnectionManager>> {
+        self.asr.connection()
+    }
+
+    /// 当前使用的 LLM 引擎，用于在会话之外做健康检查
+    pub fn llm_engine(&self) -> Arc<dyn LlmEngine> {
+        self.llm.clone()
+    }
+
+    pub fn set_app_overrides(&mut self, app_overrides: BTreeMap<String, AppOverride>) {
+        self.app_overrides = app_overrides;
+    }
+
+    /// 替换后处理规则，从下一次输出开始生效
+    pub fn set_rules(&mut self, rules: RuleSet) {
+        self.rules = Arc::new(rules);
+    }
+
+    /// 替换敏感词过滤，从下一次会话开始生效
+    pub fn set_content_filter(&mut self, filter: ContentFilter) {
+        self.content_filter = Arc::new(filter);
+    }
+
+    /// 替换语音编辑命令，从下一次会话开始生效
+    pub fn set_voice_commands(&mut self, commands: CommandSet) {
+        self.commands = commands;
+    }
+
+    /// 替换语音片段库，从下一次会话开始生效
+    pub fn set_snippets(&mut self, snippets: SnippetLibrary) {
+        self.snippets = snippets;
+    }
+
+    /// 替换已加载的插件，从下一次输出开始生效
+    pub fn set_plugins(&mut self, config: &PluginConfig, plugins: PluginSet) {
+        self.plugins = Arc::new(plugins);
+        self.plugin_config = config.clone();
+    }
+
+    /// 替换代码听写设置，从下一次会话开始生效
+    pub fn set_code_mode(&mut self, formatter: CodeFormatter) {
+        self.code_formatter = Arc::new(formatter);
+    }
+
+    /// 替换数字规范化设置，从下一次输出开始生效
+    pub fn set_numbers(&mut self, formatter: NumberFormatter) {
+        self.numbers = Arc::new(formatter);
+    }
+
+    /// 多语言设置，从下一次输出开始生效
+    pub fn set_language_config(&mut self, config: LanguageConfig) {
+        self.language_config = config;
+    }
+
+    /// 中英混排标点规则，从下一次输出开始生效
+    pub fn set_punctuation(&mut self, config: PunctuationConfig) {
+        self.punctuation = config;
+    }
+
+    /// 共用应用中的个人词典（设置界面可查看、删除学到的说法）
+    pub fn set_dictionary(&mut self, dictionary: Arc<LearnedDictionary>) {
+        self.dictionary = dictionary;
+    }
+
+    /// 与注入线程共用的模拟输入可靠度
+    pub fn set_reliability(&mut self, reliability: Arc<InjectionReliability>) {
+        self.reliability = reliability;
+    }
+
+    /// 按应用覆盖解析设置；覆盖中没有指定注入方式时按模拟输入的可靠度选择
+    fn resolve_overrides(&self, app_name: &str) -> SessionOverrides {
+        let mut overrides = config::resolve_app_overrides(&self.app_overrides, app_name);
+        if !config::injection_overridden(&self.app_overrides, app_name) {
+            overrides.injection = self.reliability.strategy(app_name, overrides.injection);
+        }
+        overrides
+    }
+
+    /// 应用保存后的配置：只重建有变化的引擎，规则和按应用覆盖直接替换。
+    /// 会话进行中时先暂存，返回 `Ok(false)`，会话结束后再应用。
+    pub fn reconfigure(&mut self, config: &ClientConfig) -> anyhow::Result<bool> {
+        let rules = RuleSet::compile(&config.rules)?;
+        let content_filter = ContentFilter::compile(&config.content_filter)?;
+        let commands = CommandSet::compile(&config.voice_commands)?;
+        let snippets = SnippetLibrary::compile(&config.snippets)?;
+        let sinks = sink::create_sinks(&config.sinks, &self.injector)?;
+        if self.active {
+            self.pending_config = Some(config.clone());
+            info!(target: "pipeline", "录音中，配置将在会话结束后生效 | Recording, config deferred until session ends");
+            return Ok(false);
+        }
+
+        // 两个引擎都创建成功后再替换，避免只生效一半；因电源状态换用的后端先换回，下次会话重新选择
+        let asr_replaced = config.asr != self.asr_config || self.asr_fallback.is_some();
+        let llm_replaced = config.llm != self.llm_config || self.llm_fallback.is_some();
+        let asr_engine = if asr_replaced {
+            Some(asr::create_engine(&config.asr)?)
+        } else {
+            None
+        };
+        let llm_engine = if llm_replaced {
+            Some(llm::create_engine(&config.llm)?)
+        } else {
+            None
+        };
+        if let Some(engine) = asr_engine {
+            self.asr = engine;
+            self.asr_config = config.asr.clone();
+            self.asr_fallback = None;
+            self.trace_id = None;
+            self.injected_len = 0;
+        }
+        if let Some(engine) = llm_engine {
+            self.llm = Arc::from(engine);
+            self.llm_config = config.llm.clone();
+            self.llm_fallback = None;
+        }
+        self.app_overrides = config.per_app.clone();
+        self.rules = Arc::new(rules);
+        self.content_filter = Arc::new(content_filter);
+        self.commands = commands;
+        self.snippets = snippets;
+        // 重新加载要读文件、编译模块，只在插件配置变化时进行
+        if config.plugins != self.plugin_config {
+            self.plugins = Arc::new(PluginSet::load(&config.plugins));
+            self.plugin_config = config.plugins.clone();
+        }
+        self.code_formatter = Arc::new(CodeFormatter::new(&config.code_mode));
+        self.numbers = Arc::new(NumberFormatter::new(&config.numbers));
+        self.language_config = config.language_detection;
+        self.punctuation = config.punctuation;
+        self.dictionary.configure(&config.dictionary);
+        self.reliability.configure(&config.injection_check);
+        self.sinks = sinks;
+        self.segment_config = config.segments.clone();
+        self.server_correction = config.use_cloud_api;
+        self.correction_gate = config.correction_gate.clone();
+        self.correction_timing = config.correction_timing.clone();
+        self.throttle = config.throttle.clone();
+        self.pending_config = None;
+
+        info!(
+            target: "pipeline",
+            asr_replaced = asr_replaced,
+            llm_replaced = llm_replaced,
+            "配置已应用 | Config applied"
+        );
+        self.events.send(PipelineEvent::Reconfigured {
+            asr_replaced,
+            llm_replaced,
+            endpoint: asr_replaced
+                .then(|| self.asr.connection().map(|c| c.endpoint().to_string()))
+                .flatten(),
+        });
+        Ok(true)
+    }
+
+    /// 分段输出设置，从下一次会话开始生效
+    pub fn set_segment_config(&mut self, config: SegmentConfig) {
+        self.segment_config = config;
+    }
+
+    /// 是否请求服务器做 LLM 校正，从下一次会话开始生效
+    pub fn set_server_correction(&mut self, enabled: bool) {
+        self.server_correction = enabled;
+    }
+
+    /// 置信度门限，从下一次会话开始生效
+    pub fn set_correction_gate(&mut self, gate: llm::CorrectionGate) {
+        self.correction_gate = gate;
+    }
+
+    /// 校正替换的等待与时限，从下一次会话开始生效
+    pub fn set_correction_timing(&mut self, timing: llm::CorrectionTiming) {
+        self.correction_timing = timing;
+    }
+
+    /// 电源受限时的后端切换策略，从下一次会话开始生效
+    pub fn set_throttle(&mut self, throttle: ThrottleConfig) {
+        self.throttle = throttle;
+    }
+
+    /// 替换输出端，从下一次会话开始生效
+    pub fn set_sinks(&mut self, sinks: Sinks) {
+        self.sinks = sinks;
+    }
+
     pub fn trace_id(&self) -> Option<&str> {
         self.trace_id.as_deref()
     }
@@ -47,72 +402,537 @@ impl Pipeline {
         self.asr.events()
     }
 
-    pub async fn start(&mut self, trace_id: String, sample_rate: u32, context: AsrContext) -> anyhow::Result<u64> {
+    pub async fn start(&mut self, trace_id: String, sample_rate: u32, mut context: AsrContext) -> anyhow::Result<u64> {
         let gen = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
         let _ = self.cancel_tx.send(gen);
         self.trace_id = Some(trace_id.clone());
         self.injected_len = 0;
+        self.fed_samples = 0;
+        self.fed_frames = 0;
+        self.sample_rate = sample_rate;
+        self.level_meter = LevelMeter::new(sample_rate);
+        self.session = self.resolve_overrides(&context.app_name);
+        let code_mode = self
+            .session
+            .code_mode
+            .unwrap_or_else(|| self.code_formatter.auto_enabled_for(&context.app_name));
+        self.session.code_mode = Some(code_mode);
+        let content_filter = self
+            .session
+            .content_filter
+            .unwrap_or_else(|| self.content_filter.enabled_for(&context.app_name));
+        self.session.content_filter = Some(content_filter);
+        if code_mode {
+            // 代码里的标点需要说出来，不保留 ASR 自动加的标点
+            self.session.auto_punctuation = false;
+        }
+        self.app_name = context.app_name.clone();
+        if context.language.is_none() {
+            context.language = self.session.language.clone();
+        }
+        self.segmenter = self
+            .segment_config
+            .enabled
+            .then(|| Segmenter::new(&self.segment_config, sample_rate));
+        // 代码听写与分段输出的文字不适合整段交给服务器校正
+        context.server_correction =
+            self.server_correction && self.session.llm_enabled && !code_mode && self.segmenter.is_none();
+        // 分段输出时中间段已经输入，用不上快慢两路识别的替换
+        context.quality_pass = self.segmenter.is_none();
+        self.context = context.clone();
+        self.committed.clear();
+        self.segment_index = 0;
+        self.transform = false;
 
         info!(
             target: "pipeline",
             trace_id = trace_id.as_str(),
             sample_rate = sample_rate,
             gen = gen,
+            llm_enabled = self.session.llm_enabled,
+            injection = ?self.session.injection,
+            code_mode = code_mode,
             "ASR 会话开始 | ASR session started"
         );
+        self.events.send(PipelineEvent::SessionStarted {
+            trace_id: trace_id.clone(),
+            app_name: context.app_name.clone(),
+            window_title: context.window_title.clone(),
+        });
+        self.spans = SessionSpans::start(&trace_id, &context.app_name);
+
+        let previous = self.asr.connection().map(|c| c.endpoint().to_string());
+        self.apply_throttle(&trace_id);
+        if let Err(err) = self.asr.start(trace_id.clone(), sample_rate, context).await {
+            std::mem::replace(&mut self.spans, SessionSpans::none()).fail(&err);
+            return Err(err);
+        }
+        self.active = true;
+        // 配置了多个服务器时，start 可能按延迟换了一台
+        if let Some(connection) = self.asr.connection() {
+            if previous.as_deref() != Some(connection.endpoint()) {
+                self.events.send(PipelineEvent::ServerSelected {
+                    trace_id: Some(trace_id),
+                    endpoint: connection.endpoint().to_string(),
+                    rtt_ms: connection.status().rtt_ms,
+                });
+            }
+        }
+        Ok(gen)
+    }
+
+    /// 按电源状态选择本次会话的后端：使用电池或散热受限时依次尝试 `throttle` 中的后端，
+    /// 恢复后换回配置的后端。启用时每次会话都记录选择结果
+    fn apply_throttle(&mut self, trace_id: &str) {
+        let reason = self.throttle.reason(power::supply());
+        if reason.is_some() {
+            if self.asr_fallback.is_none() {
+                for candidate in throttle::candidates(&self.throttle.asr, &self.asr_config) {
+                    match asr::create_engine(candidate) {
+                        Ok(engine) => {
+                            self.asr = engine;
+                            self.asr_fallback = Some(candidate.clone());
+                            break;
+                        }
+                        Err(err) => warn!(
+                            target: "pipeline",
+                            trace_id = trace_id,
+                            asr = throttle::asr_name(candidate),
+                            error = %format!("{err:#}"),
+                            "轻量 ASR 后端不可用，尝试下一个 | Lighter ASR backend unavailable, trying the next"
+                        ),
+                    }
+                }
+            }
+            if self.llm_fallback.is_none() {
+                for candidate in throttle::candidates(&self.throttle.llm, &self.llm_config) {
+                    match llm::create_engine(candidate) {
+                        Ok(engine) => {
+                            self.llm = Arc::from(engine);
+                            self.llm_fallback = Some(candidate.clone());
+                            break;
+                        }
+                        Err(err) => warn!(
+                            target: "pipeline",
+                            trace_id = trace_id,
+                            llm = throttle::llm_name(candidate),
+                            error = %format!("{err:#}"),
+                            "轻量 LLM 后端不可用，尝试下一个 | Lighter LLM backend unavailable, trying the next"
+                        ),
+                    }
+                }
+            }
+        } else {
+            if self.asr_fallback.is_some() {
+                match asr::create_engine(&self.asr_config) {
+                    Ok(engine) => {
+                        self.asr = engine;
+                        self.asr_fallback = None;
+                    }
+                    Err(err) => warn!(
+                        target: "pipeline",
+                        trace_id = trace_id,
+                        error = %format!("{err:#}"),
+                        "换回配置的 ASR 后端失败 | Restoring the configured ASR backend failed"
+                    ),
+                }
+            }
+            if self.llm_fallback.is_some() {
+                match llm::create_engine(&self.llm_config) {
+                    Ok(engine) => {
+                        self.llm = Arc::from(engine);
+                        self.llm_fallback = None;
+                    }
+                    Err(err) => warn!(
+                        target: "pipeline",
+                        trace_id = trace_id,
+                        error = %format!("{err:#}"),
+                        "换回配置的 LLM 后端失败 | Restoring the configured LLM backend failed"
+                    ),
+                }
+            }
+        }
+
+        if self.throttle.enabled {
+            info!(
+                target: "pipeline",
+                trace_id = trace_id,
+                reason = ?reason,
+                asr = throttle::asr_name(self.asr_fallback.as_ref().unwrap_or(&self.asr_config)),
+                llm = throttle::llm_name(self.llm_fallback.as_ref().unwrap_or(&self.llm_config)),
+                "按电源状态选择后端 | Backends chosen for power state"
+            );
+        }
+    }
 
-        self.asr.start(trace_id, sample_rate, context).await?;
+    /// 开始一次改写选中文字的会话：说出的指令（例如"改得正式一些"）在结束时连同选中的文字交给 LLM，
+    /// 结果替换选中的内容。指令不分段输出。
+    pub async fn start_transform(
+        &mut self,
+        trace_id: String,
+        sample_rate: u32,
+        context: AsrContext,
+    ) -> anyhow::Result<u64> {
+        let gen = self.start(trace_id, sample_rate, context).await?;
+        self.transform = true;
+        self.segmenter = None;
         Ok(gen)
     }
 
     pub async fn feed_audio(&mut self, pcm: &[i16]) -> anyhow::Result<()> {
-        self.asr.feed_audio(pcm).await
+        self.asr.feed_audio(pcm).await?;
+        self.fed_samples += pcm.len() as u64;
+        self.fed_frames += 1;
+        if let Some(levels) = self.level_meter.push(pcm) {
+            self.events.send(PipelineEvent::AudioLevel {
+                trace_id: self.trace_id.clone(),
+                levels,
+            });
+        }
+        forward_partials(self.asr.events(), &self.events, self.trace_id.as_deref());
+        if self.segmenter.as_mut().is_some_and(|segmenter| segmenter.push(pcm)) {
+            self.finalize_segment().await?;
+        }
+        Ok(())
+    }
+
+    /// 分段输出：结束当前 ASR 会话并输入已识别的部分，再开始下一段，录音不中断。
+    /// 中间段不做 LLM 校正（之后还会继续输入，无法安全地回删替换）。
+    async fn finalize_segment(&mut self) -> anyhow::Result<()> {
+        let trace_id = self.trace_id.clone();
+        let started = Instant::now();
+        let stopped = self.asr.stop().await;
+        if let Some(traffic) = self.asr.take_traffic() {
+            report_traffic(&self.events, trace_id.clone(), traffic);
+        }
+        self.segment_index += 1;
+        match stopped {
+            Ok(text) => {
+                let mut text = text.trim().to_string();
+                if !self.session.auto_punctuation {
+                    text = strip_trailing_punctuation(&text).to_string();
+                }
+                if let Some(formatter) = self.session_code_formatter(&self.session) {
+                    text = formatter.format(&text);
+                }
+                if let Some(numbers) = self.session_numbers(&self.session) {
+                    text = numbers.format(&text);
+                }
+                let reported = self.asr.take_language();
+                if let Some(language) = self.resolve_language(&self.session, reported, &text) {
+                    text = self.language_config.polish(&text, language);
+                }
+                let text = self.plugins.apply(PluginStage::Asr, &text);
+                let output = self.rules.apply(&self.dictionary.apply(&text));
+                let output = fix_punctuation(self.session_punctuation(&self.session), output);
+                let output = self.plugins.apply(PluginStage::Output, &output);
+                let output = filter_content(self.session_content_filter(&self.session), output, trace_id.as_deref());
+                if !output.trim().is_empty() {
+                    let output = format!("{}{output}", segment::separator(&self.committed, &output));
+                    let context = SinkContext {
+                        trace_id: trace_id.clone(),
+                        app_name: self.app_name.clone(),
+                        injection: self.session.injection,
+                    };
+                    if let Err(err) = self.sinks.draft(&context, &output).await {
+                        error!(
+                            target: "pipeline",
+                            trace_id = trace_id.as_deref().unwrap_or(""),
+                            error = %format!("{err:#}"),
+                            "文字输出失败 | Output failed"
+                        );
+                    }
+                    self.committed.push_str(&output);
+                    info!(
+                        target: "pipeline",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        segment = self.segment_index,
+                        len = output.chars().count(),
+                        asr_ms = started.elapsed().as_millis() as u64,
+                        "分段已输出 | Segment injected"
+                    );
+                    self.events.send(PipelineEvent::SegmentFinal {
+                        trace_id: trace_id.clone(),
+                        index: self.segment_index,
+                        text: output.trim_start().to_string(),
+                    });
+                }
+            }
+            Err(err) => {
+                // 服务器不可达时这一段转入离线队列；失败只影响这一段，之后的录音照常分段
+                let failed = match self.asr.take_deferred() {
+                    Some(deferred) => self.queue_deferred(deferred, err).err(),
+                    None => Some(err),
+                };
+                if let Some(err) = failed {
+                    warn!(
+                        target: "pipeline",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        segment = self.segment_index,
+                        error = %format!("{err:#}"),
+                        "分段识别失败 | Segment recognition failed"
+                    );
+                    self.events.send(PipelineEvent::Failed {
+                        trace_id: trace_id.clone(),
+                        error: AppError::new(ErrorCode::AsrFailed, format!("{err:#}")),
+                    });
+                }
+            }
+        }
+
+        // 每段使用独立的 trace_id，服务端按它区分会话
+        let segment_trace = format!("{}.{}", trace_id.as_deref().unwrap_or(""), self.segment_index);
+        self.asr
+            .start(segment_trace, self.sample_rate, self.context.clone())
+            .await
+            .context("开始下一段失败")
+    }
+
+    fn audio_ms(&self) -> u64 {
+        if self.sample_rate == 0 {
+            return 0;
+        }
+        self.fed_samples * 1000 / u64::from(self.sample_rate)
     }
 
     pub async fn stop(&mut self, session_gen: u64) -> anyhow::Result<()> {
+        let result = self.finish(session_gen).await;
+        self.active = false;
+        self.apply_pending_config();
+        result
+    }
+
+    /// 放弃当前会话：不请求识别结果，不输出文字，也不暂存待补交（分段输出时已输入的段保留）
+    pub async fn cancel(&mut self) {
+        let trace_id = self.trace_id.take();
+        self.spans = SessionSpans::none();
+        self.asr.cancel().await;
+        if let Some(traffic) = self.asr.take_traffic() {
+            report_traffic(&self.events, trace_id.clone(), traffic);
+        }
+        self.injected_len = 0;
+        self.segmenter = None;
+        self.committed.clear();
+        self.session = SessionOverrides::default();
+        self.active = false;
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            "会话已取消 | Session cancelled"
+        );
+        self.events.send(PipelineEvent::SessionCancelled { trace_id });
+        self.apply_pending_config();
+    }
+
+    fn apply_pending_config(&mut self) {
+        if let Some(config) = self.pending_config.take() {
+            if let Err(err) = self.reconfigure(&config) {
+                warn!(
+                    target: "pipeline",
+                    error = %format!("{err:#}"),
+                    "暂存的配置应用失败 | Failed to apply deferred config"
+                );
+            }
+        }
+    }
+
+    async fn finish(&mut self, session_gen: u64) -> anyhow::Result<()> {
         let trace_id = self.trace_id.clone();
         let started = Instant::now();
         let cancel_rx = self.cancel_tx.subscribe();
+        // 录音已结束；session span 到会话收尾（含之后的 LLM 校正）时结束
+        let SessionSpans {
+            session: session_span,
+            capture,
+            asr: asr_span,
+        } = std::mem::replace(&mut self.spans, SessionSpans::none());
+        drop(capture);
 
-        let asr_text = self.asr.stop().await?;
-        let asr_text = asr_text.trim().to_string();
+        let stopped = self.asr.stop().instrument(asr_span.clone()).await;
+        if let Err(err) = &stopped {
+            asr_span.record("error", format!("{err:#}").as_str());
+        }
+        drop(asr_span);
+        if let Some(traffic) = self.asr.take_traffic() {
+            report_traffic(&self.events, trace_id.clone(), traffic);
+        }
+        let confidence = self.asr.take_confidence();
+        let reported_language = self.asr.take_language();
+        // 不使用时随之丢弃，释放服务器连接上的会话
+        let server_correction = self.asr.take_server_correction();
+        let quality = self.asr.take_quality_result();
+        if self.generation.load(Ordering::SeqCst) != session_gen {
+            info!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                gen = session_gen,
+                "会话已被新的录音抢占，丢弃结果 | Session preempted by a new recording, result discarded"
+            );
+            let _ = self.asr.take_deferred();
+            self.events.send(PipelineEvent::CorrectionSkipped { trace_id });
+            self.trace_id = None;
+            self.injected_len = 0;
+            return Ok(());
+        }
+        let asr_text = match stopped {
+            Ok(text) => text,
+            Err(err) => match self.asr.take_deferred() {
+                Some(deferred) => {
+                    let queued = self.queue_deferred(deferred, err);
+                    self.trace_id = None;
+                    return queued;
+                }
+                None => return Err(err),
+            },
+        };
+        let mut asr_text = asr_text.trim().to_string();
+        if !self.session.auto_punctuation {
+            asr_text = strip_trailing_punctuation(&asr_text).to_string();
+        }
+        let session = std::mem::take(&mut self.session);
+        self.segmenter = None;
+        if std::mem::take(&mut self.transform) {
+            self.trace_id = None;
+            self.injected_len = 0;
+            self.finish_transform(session_gen, trace_id, asr_text, &session).await;
+            return Ok(());
+        }
+        // 整句是编辑命令时只执行命令；分段输出已输入过中间段时不识别，避免误删
+        let command = if self.committed.is_empty() {
+            self.commands.recognize(&asr_text)
+        } else {
+            None
+        };
+        if let Some(command) = command {
+            self.run_command(command, trace_id).await;
+            self.trace_id = None;
+            self.injected_len = 0;
+            return Ok(());
+        }
+        // 整句是"插入 <片段名>"时输入展开后的片段，不做格式化、规则替换与 LLM 校正
+        let snippet = if self.committed.is_empty() {
+            self.snippets.recognize(&asr_text).cloned()
+        } else {
+            None
+        };
+        let snippet_expanded = snippet.is_some();
+        // 代码听写：先把符号说法转成符号，LLM 校正的也是格式化后的文字
+        let code_formatter = self.session_code_formatter(&session).filter(|_| !snippet_expanded);
+        if let Some(formatter) = &code_formatter {
+            asr_text = formatter.format(&asr_text);
+        }
+        let numbers = self.session_numbers(&session).filter(|_| !snippet_expanded);
+        if let Some(numbers) = &numbers {
+            asr_text = numbers.format(&asr_text);
+        }
+        let language = if snippet_expanded {
+            None
+        } else {
+            self.resolve_language(&session, reported_language, &asr_text)
+        };
+        if let Some(language) = language {
+            asr_text = self.language_config.polish(&asr_text, language);
+        }
+        if !snippet_expanded {
+            asr_text = self.plugins.apply(PluginStage::Asr, &asr_text);
+        }
+        // LLM 校正基于识别原文，输入的是应用规则后的文字
+        let output = match &snippet {
+            Some(snippet) => self.expand_snippet(snippet, trace_id.clone()).await,
+            None => {
+                let output = self.rules.apply(&self.dictionary.apply(&asr_text));
+                let output = fix_punctuation(self.session_punctuation(&session), output);
+                let output = self.plugins.apply(PluginStage::Output, &output);
+                filter_content(self.session_content_filter(&session), output, trace_id.as_deref())
+            }
+        };
+        // 分段输出时之前的段已经输入，这里只输入最后一段，整段文字用于事件与输出端
+        let committed = std::mem::take(&mut self.committed);
+        let separator = if output.trim().is_empty() {
+            ""
+        } else {
+            segment::separator(&committed, &output)
+        };
+        let output = format!("{separator}{output}");
+        let context = SinkContext {
+            trace_id: trace_id.clone(),
+            app_name: self.app_name.clone(),
+            injection: session.injection,
+        };
+        let mut report = SummaryReport::new(
+            self.events.clone(),
+            self.sinks.clone(),
+            &context,
+            SessionSummary {
+                trace_id: trace_id.clone(),
+                audio_ms: self.audio_ms(),
+                frames_sent: self.fed_frames,
+                asr_ms: started.elapsed().as_millis() as u64,
+                ..SessionSummary::default()
+            },
+        );
 
         debug!(
             target: "pipeline",
             trace_id = trace_id.as_deref().unwrap_or(""),
             gen = session_gen,
             text_len = asr_text.chars().count(),
+            language = language.map(Language::code),
             "ASR 完成 | ASR completed"
         );
 
-        if asr_text.is_empty() {
+        if output.trim().is_empty() {
+            if !committed.is_empty() {
+                report.text = committed.clone();
+                report.summary.chars_injected = committed.chars().count();
+                if self.sinks.types_into_focus() {
+                    self.injection_history.lock().expect("injection history lock").record(
+                        &self.app_name,
+                        trace_id.clone(),
+                        &committed,
+                    );
+                }
+                *self.last_output.lock().expect("last output lock") = Some(committed.clone());
+                self.events.send(PipelineEvent::AsrFinal {
+                    trace_id: trace_id.clone(),
+                    text: committed,
+                    asr_ms: started.elapsed().as_millis() as u64,
+                    audio_ms: self.audio_ms(),
+                });
+            }
+            self.events.send(PipelineEvent::CorrectionSkipped { trace_id });
             self.trace_id = None;
             self.injected_len = 0;
             return Ok(());
         }
 
-        let injected_at = Instant::now();
-        let injected_len = asr_text.chars().count();
+        // 用 tokio 的时钟计时，测试中可以快进
+        let injected_at = tokio::time::Instant::now();
+        let injected_len = output.chars().count();
+        let full_text = format!("{committed}{output}");
         self.injected_len = injected_len;
+        report.summary.chars_injected = full_text.chars().count();
 
-        let _ = self
-            .injector
-            .tx
-            .send(InjectCommand::TypeText {
-                trace_id: trace_id.clone(),
-                text: asr_text.clone(),
-            })
-            .await
-            .map_err(|err| {
-                error!(
-                    target: "pipeline",
-                    trace_id = trace_id.as_deref().unwrap_or(""),
-                    gen = session_gen,
-                    error = %err,
-                    "文字注入失败：注入通道已关闭 | Injection channel closed"
-                );
-            })
-            .ok();
+        if let Err(err) = self.sinks.draft(&context, &output).await {
+            error!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                gen = session_gen,
+                error = %format!("{err:#}"),
+                "文字输出失败 | Output failed"
+            );
+        }
+        report.text = full_text.clone();
+        let types_into_focus = self.sinks.types_into_focus();
+        if types_into_focus {
+            self.injection_history.lock().expect("injection history lock").record(
+                &self.app_name,
+                trace_id.clone(),
+                &full_text,
+            );
+        }
+
+        *self.last_output.lock().expect("last output lock") = Some(full_text.clone());
 
         info!(
             target: "pipeline",
@@ -122,17 +942,113 @@ impl Pipeline {
             asr_ms = started.elapsed().as_millis() as u64,
             "ASR 已输出 | ASR injected"
         );
+        self.events.send(PipelineEvent::AsrFinal {
+            trace_id: trace_id.clone(),
+            text: full_text,
+            asr_ms: started.elapsed().as_millis() as u64,
+            audio_ms: self.audio_ms(),
+        });
+
+        let gate = self.correction_gate.decide(confidence, &asr_text);
+        // 语音片段不替换；其余情况下即使跳过 LLM 校正，也等质量 ASR 的结果替换
+        let quality = quality.filter(|_| !snippet_expanded);
+        let skip_reason = if snippet_expanded {
+            Some("已输入语音片段，跳过 LLM 校正 | Snippet inserted, skipping LLM correction")
+        } else if gate == llm::GateDecision::Skip {
+            Some("识别置信度高，跳过 LLM 校正 | High ASR confidence, skipping LLM correction")
+        } else if !session.llm_enabled && gate != llm::GateDecision::Force {
+            Some("当前应用禁用 LLM 校正 | LLM correction disabled for this app")
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            debug!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                gen = session_gen,
+                confidence = ?confidence,
+                "{reason}"
+            );
+        }
+        let llm = skip_reason.is_none().then(|| self.llm.clone());
+        if llm.is_none() && quality.is_none() {
+            self.events.send(PipelineEvent::CorrectionSkipped { trace_id });
+            self.trace_id = None;
+            self.injected_len = 0;
+            return Ok(());
+        }
+        if gate == llm::GateDecision::Force {
+            debug!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                gen = session_gen,
+                confidence = ?confidence,
+                "识别置信度低，强制 LLM 校正 | Low ASR confidence, forcing LLM correction"
+            );
+        }
 
         let generation = self.generation.clone();
-        let llm = self.llm.clone();
-        let injector = self.injector.clone();
+        let sinks = self.sinks.clone();
+        let events = self.events.clone();
+        let last_output = self.last_output.clone();
+        let rules = self.rules.clone();
+        let dictionary = self.dictionary.clone();
+        let plugins = self.plugins.clone();
+        let content_filter = self.session_content_filter(&session);
+        // 服务器校正的是未经代码格式化的识别结果，代码听写只用本地 LLM
+        let server_correction = server_correction.filter(|_| code_formatter.is_none());
+        // 质量结果与快速结果一样先去标点、代码格式化并经过 ASR 阶段插件，再交给 LLM
+        let prepare_quality = {
+            let auto_punctuation = session.auto_punctuation;
+            let code_formatter = code_formatter.clone();
+            let plugins = self.plugins.clone();
+            move |text: String| {
+                let mut text = text.trim().to_string();
+                if !auto_punctuation {
+                    text = strip_trailing_punctuation(&text).to_string();
+                }
+                if let Some(formatter) = &code_formatter {
+                    text = formatter.format(&text);
+                }
+                plugins.apply(PluginStage::Asr, &text)
+            }
+        };
+        let injection_history = types_into_focus.then(|| self.injection_history.clone());
         let original = asr_text;
+        let hints = llm::CorrectionHints {
+            style: if code_formatter.is_some() {
+                llm::CorrectionStyle::Code
+            } else {
+                llm::CorrectionStyle::Prose
+            },
+            language,
+            glossary: self.dictionary.glossary(),
+            field: self.context.field.as_ref().map(|field| field.kind),
+            trace_id: trace_id.clone(),
+        };
+        let language_config = self.language_config;
+        let punctuation = self.session_punctuation(&session);
         let trace_id_for_task = trace_id.clone();
         let injected_at_for_task = injected_at;
+        let min_delay = Duration::from_millis(self.correction_timing.min_delay_ms);
+        let replace_window = Duration::from_millis(self.correction_timing.replace_window_ms);
         let mut cancel_rx = cancel_rx;
 
+        self.events.send(PipelineEvent::Correcting {
+            trace_id: trace_id.clone(),
+        });
+
         tauri::async_runtime::spawn(async move {
+            let skipped = PipelineEvent::CorrectionSkipped {
+                trace_id: trace_id_for_task.clone(),
+            };
             let llm_started = Instant::now();
+            let llm_span = info_span!(
+                parent: &session_span,
+                "llm",
+                trace_id = trace_id_for_task.as_deref().unwrap_or(""),
+                error = field::Empty
+            );
             let correction = tokio::select! {
                 _ = cancel_rx.changed() => {
                     warn!(
@@ -141,12 +1057,18 @@ impl Pipeline {
                         gen = session_gen,
                         "LLM 校正已取消：检测到新会话 | LLM cancelled: new session started"
                     );
+                    events.send(skipped);
                     return;
                 }
-                res = llm.correct(&original) => res,
+                res = refine(llm, quality, server_correction, prepare_quality, &original, hints)
+                    .instrument(llm_span.clone()) => res,
             };
+            if let Err(err) = &correction {
+                llm_span.record("error", format!("{err:#}").as_str());
+            }
+            drop(llm_span);
+            report.summary.llm_ms = Some(llm_started.elapsed().as_millis() as u64);
 
-            let min_delay = Duration::from_millis(500);
             let since_injected = injected_at_for_task.elapsed();
             if since_injected < min_delay {
                 let remaining = min_delay - since_injected;
@@ -158,6 +1080,7 @@ impl Pipeline {
                             gen = session_gen,
                             "LLM 校正已取消：检测到新会话 | LLM cancelled: new session started"
                         );
+                        events.send(skipped);
                         return;
                     }
                     _ = tokio::time::sleep(remaining) => {}
@@ -171,19 +1094,42 @@ impl Pipeline {
                     gen = session_gen,
                     "跳过校正：已有新会话 | Skip correction: new session started"
                 );
+                events.send(skipped);
                 return;
             }
 
-            let Ok(correction) = correction else {
+            // 超出替换时限时不再自动替换，校正成功则交给用户决定是否应用
+            let late = !replace_window.is_zero() && injected_at_for_task.elapsed() > replace_window;
+            if late && correction.is_err() {
                 warn!(
                     target: "pipeline",
                     trace_id = trace_id_for_task.as_deref().unwrap_or(""),
                     gen = session_gen,
-                    error = %format!("{correction:?}"),
                     latency_ms = llm_started.elapsed().as_millis() as u64,
-                    "LLM 校正失败 | LLM correction failed"
+                    "校正超出替换时限，已丢弃 | Correction arrived after the replacement window, dropped"
                 );
+                events.send(skipped);
                 return;
+            }
+
+            let correction = match correction {
+                Ok(correction) => correction,
+                Err(err) => {
+                    warn!(
+                        target: "pipeline",
+                        trace_id = trace_id_for_task.as_deref().unwrap_or(""),
+                        gen = session_gen,
+                        error = %format!("{err:#}"),
+                        latency_ms = llm_started.elapsed().as_millis() as u64,
+                        "LLM 校正失败 | LLM correction failed"
+                    );
+                    events.send(PipelineEvent::Failed {
+                        trace_id: trace_id_for_task.clone(),
+                        error: AppError::new(ErrorCode::LlmFailed, format!("{err:#}")),
+                    });
+                    events.send(skipped);
+                    return;
+                }
             };
 
             if !correction.changed {
@@ -194,11 +1140,53 @@ impl Pipeline {
                     latency_ms = correction.latency_ms,
                     "LLM 无需校正 | LLM no change"
                 );
+                events.send(skipped);
+                return;
+            }
+
+            let mut corrected = correction.corrected.trim().to_string();
+            if !session.auto_punctuation {
+                corrected = strip_trailing_punctuation(&corrected).to_string();
+            }
+            if let Some(numbers) = &numbers {
+                corrected = numbers.format(&corrected);
+            }
+            if let Some(language) = language {
+                corrected = language_config.polish(&corrected, language);
+            }
+            let corrected = fix_punctuation(punctuation, rules.apply(&dictionary.apply(&corrected)));
+            let corrected = plugins.apply(PluginStage::Output, &corrected);
+            let corrected = filter_content(content_filter, corrected, trace_id_for_task.as_deref());
+            if corrected.trim().is_empty() {
+                events.send(skipped);
+                return;
+            }
+            let corrected = format!("{separator}{corrected}");
+            // 规则可能已经做了同样的修改
+            if corrected == output {
+                events.send(skipped);
                 return;
             }
 
-            let corrected = correction.corrected.trim().to_string();
-            if corrected.is_empty() {
+            let full_text = format!("{committed}{corrected}");
+            if late {
+                info!(
+                    target: "pipeline",
+                    trace_id = trace_id_for_task.as_deref().unwrap_or(""),
+                    gen = session_gen,
+                    latency_ms = correction.latency_ms,
+                    "校正超出替换时限，交由用户决定是否应用 | Correction arrived after the replacement window, \
+                     offering it to the user"
+                );
+                events.send(PipelineEvent::CorrectionLate(LateCorrection {
+                    trace_id: trace_id_for_task,
+                    app_name: context.app_name.clone(),
+                    replace_chars: injected_len,
+                    replacement: corrected,
+                    text: full_text,
+                    latency_ms: correction.latency_ms,
+                }));
+                events.send(skipped);
                 return;
             }
 
@@ -210,143 +1198,1510 @@ impl Pipeline {
                 "LLM 校正就绪，开始替换 | LLM correction ready, replacing"
             );
 
-            if injector
-                .tx
-                .send(InjectCommand::Backspace {
-                    trace_id: trace_id_for_task.clone(),
-                    count: injected_len,
-                })
-                .await
-                .is_err()
-            {
+            *last_output.lock().expect("last output lock") = Some(full_text.clone());
+            if let Err(err) = sinks.replace(&context, injected_len, &corrected).await {
                 warn!(
                     target: "pipeline",
                     trace_id = trace_id_for_task.as_deref().unwrap_or(""),
                     gen = session_gen,
-                    "退格注入失败：注入通道已关闭 | Backspace injection failed (channel closed)"
+                    error = %format!("{err:#}"),
+                    "校正结果替换失败 | Replacing with correction failed"
                 );
+                events.send(skipped);
                 return;
             }
 
-            if injector
-                .tx
-                .send(InjectCommand::TypeText {
-                    trace_id: trace_id_for_task.clone(),
-                    text: corrected,
-                })
-                .await
-                .is_err()
-            {
-                warn!(
-                    target: "pipeline",
-                    trace_id = trace_id_for_task.as_deref().unwrap_or(""),
-                    gen = session_gen,
-                    "文字注入失败：注入通道已关闭 | Injection failed (channel closed)"
+            report.summary.chars_injected = full_text.chars().count();
+            if let Some(history) = injection_history {
+                history.lock().expect("injection history lock").update(
+                    &context.app_name,
+                    trace_id_for_task.as_deref(),
+                    &full_text,
                 );
             }
+            report.text = full_text.clone();
+            report.summary.corrected = true;
+            events.send(PipelineEvent::CorrectionApplied {
+                trace_id: trace_id_for_task,
+                text: full_text,
+                latency_ms: correction.latency_ms,
+            });
         });
 
         self.trace_id = None;
         self.injected_len = 0;
         Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use async_trait::async_trait;
 
-    struct MockAsrEngine {
-        final_text: String,
+    /// 改写选中文字：复制当前选中的内容，按识别出的指令交给 LLM 改写，再输入结果替换选中的内容。
+    /// 失败时通过事件通知，选中的文字保持不变。
+    async fn finish_transform(
+        &mut self,
+        session_gen: u64,
+        trace_id: Option<String>,
+        instruction: String,
+        session: &SessionOverrides,
+    ) {
+        if instruction.is_empty() {
+            info!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                "没有识别到改写指令 | No rewrite instruction recognized"
+            );
+            self.events.send(PipelineEvent::CorrectionSkipped { trace_id });
+            return;
+        }
+        self.events.send(PipelineEvent::Partial {
+            trace_id: trace_id.clone(),
+            text: instruction.clone(),
+        });
+
+        let selection = match self.injector.copy_selection(trace_id.clone()).await {
+            Ok(selection) if selection.trim().is_empty() => {
+                let error = AppError::new(ErrorCode::NoSelection, "请先选中要改写的文字");
+                self.events.send(PipelineEvent::Failed { trace_id, error });
+                return;
+            }
+            Ok(selection) => selection,
+            Err(err) => {
+                let error = AppError::new(ErrorCode::InjectionFailed, format!("{err:#}"));
+                self.events.send(PipelineEvent::Failed { trace_id, error });
+                return;
+            }
+        };
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            gen = session_gen,
+            instruction_len = instruction.chars().count(),
+            selection_len = selection.chars().count(),
+            "开始改写选中的文字 | Rewriting the selection"
+        );
+        self.events.send(PipelineEvent::Correcting {
+            trace_id: trace_id.clone(),
+        });
+
+        let llm_span = info_span!(
+            "llm",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            error = field::Empty
+        );
+        let rewritten = self
+            .llm
+            .rewrite(&instruction, &selection)
+            .instrument(llm_span.clone())
+            .await;
+        if let Err(err) = &rewritten {
+            llm_span.record("error", format!("{err:#}").as_str());
+        }
+        drop(llm_span);
+        if self.generation.load(Ordering::SeqCst) != session_gen {
+            info!(
+                target: "pipeline",
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                gen = session_gen,
+                "改写已取消 | Rewrite cancelled"
+            );
+            self.events.send(PipelineEvent::CorrectionSkipped { trace_id });
+            return;
+        }
+        let rewritten = match rewritten {
+            Ok(rewritten) => rewritten,
+            Err(err) => {
+                warn!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    gen = session_gen,
+                    error = %format!("{err:#}"),
+                    "改写失败 | Rewrite failed"
+                );
+                let error = AppError::new(ErrorCode::LlmFailed, format!("{err:#}"));
+                self.events.send(PipelineEvent::Failed { trace_id, error });
+                return;
+            }
+        };
+
+        // 选中的内容仍处于选中状态，直接输入即可替换
+        let output = self.rules.apply(&self.dictionary.apply(&rewritten.corrected));
+        let output = fix_punctuation(self.session_punctuation(session), output);
+        let output = self.plugins.apply(PluginStage::Output, &output);
+        let output = filter_content(self.session_content_filter(session), output, trace_id.as_deref());
+        let command = InjectCommand::text(session.injection, trace_id.clone(), output.clone());
+        if self.injector.tx.send(command).await.is_err() {
+            let error = AppError::new(ErrorCode::InjectionFailed, "注入通道已关闭");
+            self.events.send(PipelineEvent::Failed { trace_id, error });
+            return;
+        }
+        self.injection_history
+            .lock()
+            .expect("injection history lock")
+            .record(&self.app_name, trace_id.clone(), &output);
+        audit::record(trace_id.as_deref(), &self.app_name, AuditKind::Rewrite, true, &output);
+        *self.last_output.lock().expect("last output lock") = Some(output.clone());
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            gen = session_gen,
+            len = output.chars().count(),
+            latency_ms = rewritten.latency_ms,
+            "选中的文字已改写 | Selection rewritten"
+        );
+        self.events.send(PipelineEvent::TransformApplied {
+            trace_id,
+            instruction,
+            text: output,
+            latency_ms: rewritten.latency_ms,
+        });
+    }
+
+    /// 展开语音片段；剪贴板读取失败时 `{clipboard}` 按空串处理
+    async fn expand_snippet(&self, snippet: &Snippet, trace_id: Option<String>) -> String {
+        let clipboard = if snippet.needs_clipboard() {
+            self.injector.read_clipboard(trace_id.clone()).await.unwrap_or_else(|err| {
+                warn!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %format!("{err:#}"),
+                    "读取剪贴板失败，片段中的剪贴板内容留空 | Clipboard read failed, leaving it out of the snippet"
+                );
+                String::new()
+            })
+        } else {
+            String::new()
+        };
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            snippet = %snippet.name,
+            "插入语音片段 | Inserting snippet"
+        );
+        snippet.expand(chrono::Local::now(), &clipboard)
+    }
+
+    /// 执行编辑命令：按当前应用的输入记录回删（可一次回删多次输入）、选中，或输入换行
+    async fn run_command(&mut self, command: Command, trace_id: Option<String>) {
+        let Command { action, count } = command;
+        if action == EditAction::Teach {
+            self.teach(trace_id).await;
+            return;
+        }
+        let command = {
+            let mut history = self.injection_history.lock().expect("injection history lock");
+            match action {
+                EditAction::DeleteLast => history.undo(&self.app_name, count).map(|undo| InjectCommand::Backspace {
+                    trace_id: trace_id.clone(),
+                    count: undo.backspaces,
+                }),
+                EditAction::SelectLast => history.select(&self.app_name).map(|count| InjectCommand::SelectBack {
+                    trace_id: trace_id.clone(),
+                    count,
+                }),
+                EditAction::Newline => {
+                    history.record(&self.app_name, trace_id.clone(), "\n");
+                    Some(InjectCommand::Newline {
+                        trace_id: trace_id.clone(),
+                    })
+                }
+                // 已在上面单独处理
+                EditAction::Teach => None,
+            }
+        };
+        let applied = match command {
+            Some(command) => match self.injector.tx.send(command).await {
+                Ok(()) => true,
+                Err(_) => {
+                    error!(
+                        target: "pipeline",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        "注入通道已关闭 | Injection channel closed"
+                    );
+                    false
+                }
+            },
+            None => false,
+        };
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            action = ?action,
+            count = count,
+            applied = applied,
+            "执行语音编辑命令 | Voice command executed"
+        );
+        self.events.send(PipelineEvent::VoiceCommand {
+            trace_id,
+            action,
+            applied,
+        });
+    }
+
+    /// "记住这个"：复制选中的（用户改好的）文字，与上一次输出比较，把改动记入个人词典
+    async fn teach(&mut self, trace_id: Option<String>) {
+        let before = self.last_output.lock().expect("last output lock").clone();
+        let learned = match before {
+            _ if !self.dictionary.is_enabled() => Err(anyhow::anyhow!("个人词典未启用")),
+            None => Err(anyhow::anyhow!("还没有输出过文字")),
+            Some(before) => match self.injector.copy_selection(trace_id.clone()).await {
+                Ok(after) if after.trim().is_empty() => Err(anyhow::anyhow!("没有选中改好的文字")),
+                Ok(after) => self.dictionary.learn(&before, &after).map(|term| (term, after)),
+                Err(err) => Err(err),
+            },
+        };
+        match learned {
+            Ok((term, after)) => {
+                info!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    from = %privacy::redact(&term.from),
+                    to = %privacy::redact(&term.to),
+                    "已记入个人词典 | Term learned"
+                );
+                // 改好的文字才是这次的最终结果
+                *self.last_output.lock().expect("last output lock") = Some(after);
+                self.events.send(PipelineEvent::TermLearned {
+                    trace_id,
+                    from: term.from,
+                    to: term.to,
+                });
+            }
+            Err(err) => {
+                warn!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %format!("{err:#}"),
+                    "没有学到新的说法 | Nothing learned"
+                );
+                self.events.send(PipelineEvent::VoiceCommand {
+                    trace_id,
+                    action: EditAction::Teach,
+                    applied: false,
+                });
+            }
+        }
+    }
+
+    /// 会话处于代码听写时返回格式化器
+    fn session_code_formatter(&self, session: &SessionOverrides) -> Option<Arc<CodeFormatter>> {
+        (session.code_mode == Some(true)).then(|| self.code_formatter.clone())
+    }
+
+    /// 会话启用敏感词过滤时返回过滤器
+    fn session_content_filter(&self, session: &SessionOverrides) -> Option<Arc<ContentFilter>> {
+        (session.content_filter == Some(true)).then(|| self.content_filter.clone())
+    }
+
+    /// 启用数字规范化且不是代码听写时返回规范化器
+    fn session_numbers(&self, session: &SessionOverrides) -> Option<Arc<NumberFormatter>> {
+        (self.numbers.enabled() && session.code_mode != Some(true)).then(|| self.numbers.clone())
+    }
+
+    /// 启用中英混排标点整理且不是代码听写时返回规则
+    fn session_punctuation(&self, session: &SessionOverrides) -> Option<PunctuationConfig> {
+        (self.punctuation.enabled && session.code_mode != Some(true)).then_some(self.punctuation)
+    }
+
+    /// 识别结果的语言：ASR 返回的优先，其次是会话指定的识别语言，最后按文字判断；
+    /// 未启用多语言或处于代码听写时返回 `None`
+    fn resolve_language(&self, session: &SessionOverrides, reported: Option<String>, text: &str) -> Option<Language> {
+        if !self.language_config.enabled || session.code_mode == Some(true) {
+            return None;
+        }
+        reported
+            .as_deref()
+            .and_then(Language::from_code)
+            .or_else(|| self.context.language.as_deref().and_then(Language::from_code))
+            .or_else(|| language::detect(text))
+    }
+
+    /// 服务器不可达：会话转入离线队列，连接恢复后补交
+    fn queue_deferred(&mut self, deferred: Box<dyn DeferredSession>, err: anyhow::Error) -> anyhow::Result<()> {
+        let session = self.session.clone();
+        let trace_id = self.trace_id.clone();
+        self.injected_len = 0;
+
+        let pending = self.deferred.pending.load(Ordering::SeqCst);
+        if pending >= MAX_DEFERRED_SESSIONS {
+            return Err(err.context(format!("离线暂存已满（{MAX_DEFERRED_SESSIONS} 条）| Offline queue is full")));
+        }
+        let pending = self.deferred.pending.fetch_add(1, Ordering::SeqCst) + 1;
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.as_deref().unwrap_or(""),
+            pending = pending,
+            error = %format!("{err:#}"),
+            "会话已加入离线队列 | Session queued for deferred submission"
+        );
+        let _ = self.deferred.tx.send(QueuedSession {
+            session: deferred,
+            context: SinkContext {
+                trace_id: trace_id.clone(),
+                app_name: self.app_name.clone(),
+                injection: session.injection,
+            },
+            sinks: self.sinks.clone(),
+            auto_punctuation: session.auto_punctuation,
+            code_formatter: self.session_code_formatter(&session),
+            numbers: self.session_numbers(&session),
+            language_config: (self.language_config.enabled && session.code_mode != Some(true))
+                .then_some(self.language_config),
+            punctuation: self.session_punctuation(&session),
+            rules: self.rules.clone(),
+            dictionary: self.dictionary.clone(),
+            plugins: self.plugins.clone(),
+            content_filter: self.session_content_filter(&session),
+            audio_ms: self.audio_ms(),
+            queued_at: Instant::now(),
+        });
+        self.events.send(PipelineEvent::SessionQueued { trace_id, pending });
+        Ok(())
+    }
+
+    /// 把上一次的最终结果重新输入到当前焦点（按当前应用的注入方式）
+    pub async fn retype_last(&self, app_name: &str) -> anyhow::Result<String> {
+        let Some(text) = self.last_output.lock().expect("last output lock").clone() else {
+            return Err(AppError::new(ErrorCode::NotFound, "还没有可重新输入的结果").into());
+        };
+        let strategy = self.resolve_overrides(app_name).injection;
+        self.injector
+            .tx
+            .send(InjectCommand::text(strategy, None, text.clone()))
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))?;
+        Ok(text)
+    }
+
+    /// 回删目标应用中最近 `count` 次输入（校正替换过的按替换后的文字计），返回被删掉的文字。
+    /// 还在等待的校正一并取消，免得随后又替换已删掉的文字
+    pub async fn undo_last(&self, app_name: &str, count: usize) -> anyhow::Result<String> {
+        if self.active {
+            return Err(AppError::new(ErrorCode::Busy, "录音中，无法撤销").into());
+        }
+        let undo = self
+            .injection_history
+            .lock()
+            .expect("injection history lock")
+            .undo(app_name, count.max(1));
+        let Some(undo) = undo else {
+            return Err(AppError::new(ErrorCode::NotFound, "当前应用中没有可撤销的输入").into());
+        };
+        let gen = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let _ = self.cancel_tx.send(gen);
+        self.injector
+            .tx
+            .send(InjectCommand::Backspace {
+                trace_id: None,
+                count: undo.backspaces,
+            })
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))?;
+        info!(
+            target: "pipeline",
+            app = app_name,
+            entries = undo.entries,
+            chars = undo.backspaces,
+            "已撤销最近的输入 | Recent input undone"
+        );
+        Ok(undo.text)
+    }
+
+    /// 应用超出替换时限的校正：只有该会话仍是目标应用中最后一次输入时才回删替换
+    pub async fn apply_late_correction(&self, late: &LateCorrection) -> anyhow::Result<()> {
+        let updated = self.injection_history.lock().expect("injection history lock").update(
+            &late.app_name,
+            late.trace_id.as_deref(),
+            &late.text,
+        );
+        if !updated {
+            return Err(AppError::new(ErrorCode::NotFound, "之后已有新的输入，无法替换").into());
+        }
+        let context = SinkContext {
+            trace_id: late.trace_id.clone(),
+            app_name: late.app_name.clone(),
+            injection: self.resolve_overrides(&late.app_name).injection,
+        };
+        self.sinks.replace(&context, late.replace_chars, &late.replacement).await?;
+        *self.last_output.lock().expect("last output lock") = Some(late.text.clone());
+        audit::record(late.trace_id.as_deref(), &late.app_name, AuditKind::Dictation, true, &late.text);
+        info!(
+            target: "pipeline",
+            trace_id = late.trace_id.as_deref().unwrap_or(""),
+            "已按用户选择应用迟到的校正 | Late correction applied at the user's request"
+        );
+        Ok(())
+    }
+}
+
+/// 抢占：让识别中的会话在拿到结果后直接丢弃，进行中的 LLM 校正同样取消
+#[derive(Clone)]
+pub struct PreemptHandle(Arc<AtomicU64>);
+
+impl PreemptHandle {
+    pub fn preempt(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// 一次会话的链路 span（见 `telemetry`）：`audio_capture` 到录音结束，`asr` 到得到识别结果，
+/// `session` 到会话收尾
+struct SessionSpans {
+    session: Span,
+    capture: Span,
+    asr: Span,
+}
+
+impl SessionSpans {
+    fn none() -> Self {
+        Self {
+            session: Span::none(),
+            capture: Span::none(),
+            asr: Span::none(),
+        }
+    }
+
+    fn start(trace_id: &str, app_name: &str) -> Self {
+        let session = info_span!("session", trace_id = trace_id, app = app_name);
+        Self {
+            capture: info_span!(parent: &session, "audio_capture", trace_id = trace_id),
+            asr: info_span!(parent: &session, "asr", trace_id = trace_id, error = field::Empty),
+            session,
+        }
+    }
+
+    fn fail(self, err: &anyhow::Error) {
+        self.asr.record("error", format!("{err:#}").as_str());
+    }
+}
+
+/// 会话收尾：在会话结束的任一分支离开作用域时输出一次汇总，并把最终结果交给各输出端
+struct SummaryReport {
+    events: EventSender,
+    sinks: Sinks,
+    app_name: String,
+    /// 最终结果，为空时不交给输出端
+    text: String,
+    summary: SessionSummary,
+}
+
+impl SummaryReport {
+    fn new(events: EventSender, sinks: Sinks, context: &SinkContext, summary: SessionSummary) -> Self {
+        Self {
+            events,
+            sinks,
+            app_name: context.app_name.clone(),
+            text: String::new(),
+            summary,
+        }
+    }
+}
+
+impl Drop for SummaryReport {
+    fn drop(&mut self) {
+        let summary = std::mem::take(&mut self.summary);
+        info!(
+            target: "pipeline",
+            trace_id = summary.trace_id.as_deref().unwrap_or(""),
+            audio_ms = summary.audio_ms,
+            frames_sent = summary.frames_sent,
+            asr_ms = summary.asr_ms,
+            llm_ms = ?summary.llm_ms,
+            chars_injected = summary.chars_injected,
+            corrected = summary.corrected,
+            "会话汇总 | Session summary"
+        );
+        if !self.text.trim().is_empty() {
+            audit::record(
+                summary.trace_id.as_deref(),
+                &self.app_name,
+                AuditKind::Dictation,
+                summary.corrected,
+                &self.text,
+            );
+            self.sinks.commit(Transcript {
+                trace_id: summary.trace_id.clone(),
+                app: std::mem::take(&mut self.app_name),
+                text: std::mem::take(&mut self.text),
+                corrected: summary.corrected,
+            });
+        }
+        self.events.send(PipelineEvent::SessionSummary(summary));
+    }
+}
+
+struct QueuedSession {
+    session: Box<dyn DeferredSession>,
+    context: SinkContext,
+    sinks: Sinks,
+    auto_punctuation: bool,
+    code_formatter: Option<Arc<CodeFormatter>>,
+    numbers: Option<Arc<NumberFormatter>>,
+    /// 启用多语言且不是代码听写时按文字判断语言
+    language_config: Option<LanguageConfig>,
+    punctuation: Option<PunctuationConfig>,
+    rules: Arc<RuleSet>,
+    dictionary: Arc<LearnedDictionary>,
+    plugins: Arc<PluginSet>,
+    content_filter: Option<Arc<ContentFilter>>,
+    audio_ms: u64,
+    queued_at: Instant,
+}
+
+/// 离线会话队列：连接恢复后按录音顺序逐个补交，结果输出到当前焦点（不做 LLM 校正）
+struct DeferredQueue {
+    tx: mpsc::UnboundedSender<QueuedSession>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl DeferredQueue {
+    fn spawn(
+        events: EventSender,
+        last_output: Arc<Mutex<Option<String>>>,
+        injection_history: Arc<Mutex<InjectionHistory>>,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<QueuedSession>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let pending_for_task = pending.clone();
+
+        tauri::async_runtime::spawn(async move {
+            while let Some(queued) = rx.recv().await {
+                let trace_id = Some(queued.session.trace_id().to_string());
+                let remaining = DEFERRED_MAX_AGE.saturating_sub(queued.queued_at.elapsed());
+                let submitted_at = Instant::now();
+                let outcome = tokio::time::timeout(remaining, queued.session.submit()).await;
+                let pending = pending_for_task.fetch_sub(1, Ordering::SeqCst) - 1;
+
+                let Ok((result, traffic)) = outcome else {
+                    warn!(
+                        target: "pipeline",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        "离线会话已过期，放弃补交 | Deferred session expired"
+                    );
+                    events.send(PipelineEvent::Failed {
+                        trace_id,
+                        error: AppError::new(
+                            ErrorCode::ServerUnreachable,
+                            format!("离线暂存的录音在 {} 分钟内未能提交", DEFERRED_MAX_AGE.as_secs() / 60),
+                        ),
+                    });
+                    continue;
+                };
+                report_traffic(&events, trace_id.clone(), traffic);
+
+                let text = match result {
+                    Ok(text) => text,
+                    Err(err) => {
+                        warn!(
+                            target: "pipeline",
+                            trace_id = trace_id.as_deref().unwrap_or(""),
+                            error = %format!("{err:#}"),
+                            "离线会话识别失败 | Deferred session failed"
+                        );
+                        events.send(PipelineEvent::Failed {
+                            trace_id,
+                            error: AppError::new(ErrorCode::AsrFailed, format!("{err:#}")),
+                        });
+                        continue;
+                    }
+                };
+
+                let mut text = text.trim().to_string();
+                if !queued.auto_punctuation {
+                    text = strip_trailing_punctuation(&text).to_string();
+                }
+                if let Some(formatter) = &queued.code_formatter {
+                    text = formatter.format(&text);
+                }
+                if let Some(numbers) = &queued.numbers {
+                    text = numbers.format(&text);
+                }
+                if let Some((config, language)) = queued.language_config.zip(language::detect(&text)) {
+                    text = config.polish(&text, language);
+                }
+                let text = queued.plugins.apply(PluginStage::Asr, &text);
+                let text = queued.rules.apply(&queued.dictionary.apply(&text));
+                let text = fix_punctuation(queued.punctuation, text);
+                let text = queued.plugins.apply(PluginStage::Output, &text);
+                let text = filter_content(queued.content_filter.clone(), text, trace_id.as_deref());
+                if text.trim().is_empty() {
+                    events.send(PipelineEvent::CorrectionSkipped { trace_id });
+                    continue;
+                }
+
+                if let Err(err) = queued.sinks.draft(&queued.context, &text).await {
+                    warn!(
+                        target: "pipeline",
+                        trace_id = trace_id.as_deref().unwrap_or(""),
+                        error = %format!("{err:#}"),
+                        "文字输出失败 | Output failed"
+                    );
+                    events.send(PipelineEvent::Failed {
+                        trace_id,
+                        error: AppError::new(ErrorCode::InjectionFailed, format!("{err:#}")),
+                    });
+                    continue;
+                }
+                *last_output.lock().expect("last output lock") = Some(text.clone());
+                if queued.sinks.types_into_focus() {
+                    injection_history.lock().expect("injection history lock").record(
+                        &queued.context.app_name,
+                        trace_id.clone(),
+                        &text,
+                    );
+                }
+                queued.sinks.commit(Transcript {
+                    trace_id: trace_id.clone(),
+                    app: queued.context.app_name.clone(),
+                    text: text.clone(),
+                    corrected: false,
+                });
+                audit::record(
+                    trace_id.as_deref(),
+                    &queued.context.app_name,
+                    AuditKind::Deferred,
+                    false,
+                    &text,
+                );
+
+                info!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    len = text.chars().count(),
+                    waited_ms = queued.queued_at.elapsed().as_millis() as u64,
+                    pending = pending,
+                    "离线会话已补交并输出 | Deferred session delivered"
+                );
+                events.send(PipelineEvent::AsrFinal {
+                    trace_id: trace_id.clone(),
+                    text: text.clone(),
+                    asr_ms: submitted_at.elapsed().as_millis() as u64,
+                    audio_ms: queued.audio_ms,
+                });
+                events.send(PipelineEvent::DeferredDelivered {
+                    trace_id: trace_id.clone(),
+                    text,
+                    pending,
+                });
+                events.send(PipelineEvent::CorrectionSkipped { trace_id });
+            }
+        });
+
+        Self { tx, pending }
+    }
+}
+
+/// 把引擎已产生的中间结果转发给订阅方（不支持流式的引擎不会产生 Partial）
+pub(crate) fn forward_partials(
+    asr_events: &mut mpsc::Receiver<asr::AsrEvent>,
+    events: &EventSender,
+    trace_id: Option<&str>,
+) {
+    while let Ok(event) = asr_events.try_recv() {
+        if let asr::AsrEvent::Partial { text } = event {
+            events.send(PipelineEvent::Partial {
+                trace_id: trace_id.map(str::to_string),
+                text,
+            });
+        }
+    }
+}
+
+/// 记录单次会话的网络用量摘要，并交给统计
+fn report_traffic(events: &EventSender, trace_id: Option<String>, traffic: TrafficStats) {
+    if traffic == TrafficStats::default() {
+        return;
+    }
+    info!(
+        target: "network",
+        trace_id = trace_id.as_deref().unwrap_or(""),
+        bytes_sent = traffic.bytes_sent,
+        bytes_received = traffic.bytes_received,
+        audio_packets = traffic.audio_packets,
+        avg_packet_bytes = traffic.avg_packet_bytes(),
+        retransmitted = traffic.retransmitted_packets,
+        dropped = traffic.dropped_packets,
+        "会话网络用量 | Session network usage"
+    );
+    events.send(PipelineEvent::NetworkUsage { trace_id, traffic });
+}
+
+/// 服务器负责校正（或开启了收听窗口）时等它发来的校正结果，否则交给本地 LLM
+async fn correct(
+    llm: Arc<dyn LlmEngine>,
+    server_correction: Option<Box<dyn asr::ServerCorrection>>,
+    original: &str,
+    hints: llm::CorrectionHints,
+) -> anyhow::Result<llm::CorrectionResult> {
+    let Some(server_correction) = server_correction else {
+        return llm.correct(original, hints).await;
+    };
+    let started = Instant::now();
+    let Some(corrected) = server_correction.wait().await? else {
+        // 收听窗口内服务器没有发来校正，改用本地 LLM
+        return llm.correct(original, hints).await;
+    };
+    Ok(llm::CorrectionResult {
+        original: original.to_string(),
+        changed: corrected.trim() != original.trim(),
+        corrected,
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// 快慢两路识别时等质量 ASR 的结果（失败时保留快速结果），再按需 LLM 校正；没有质量结果时即 `correct`
+async fn refine(
+    llm: Option<Arc<dyn LlmEngine>>,
+    quality: Option<Box<dyn asr::QualityResult>>,
+    server_correction: Option<Box<dyn asr::ServerCorrection>>,
+    prepare: impl FnOnce(String) -> String,
+    original: &str,
+    hints: llm::CorrectionHints,
+) -> anyhow::Result<llm::CorrectionResult> {
+    let started = Instant::now();
+    let trace_id = hints.trace_id.clone();
+    let source = match quality {
+        Some(quality) => match quality.wait().await {
+            Ok(text) => Some(prepare(text)),
+            Err(err) => {
+                warn!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %format!("{err:#}"),
+                    "质量 ASR 失败，保留快速结果 | Quality ASR failed, keeping the fast result"
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let text = match (llm, source) {
+        (Some(llm), None) => return correct(llm, server_correction, original, hints).await,
+        (None, None) => original.to_string(),
+        (None, Some(source)) => source,
+        // 服务器校正的是快速结果，已被质量结果取代
+        (Some(llm), Some(source)) => match llm.correct(&source, hints).await {
+            Ok(correction) if correction.changed => correction.corrected,
+            Ok(_) => source,
+            Err(err) => {
+                warn!(
+                    target: "pipeline",
+                    trace_id = trace_id.as_deref().unwrap_or(""),
+                    error = %format!("{err:#}"),
+                    "质量结果的 LLM 校正失败，直接使用 | LLM correction of the quality result failed, using it as is"
+                );
+                source
+            }
+        },
+    };
+    Ok(llm::CorrectionResult {
+        original: original.to_string(),
+        changed: text.trim() != original.trim(),
+        corrected: text,
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// 遮盖或删掉词表中的词；日志只记命中次数，不记录词本身
+fn filter_content(filter: Option<Arc<ContentFilter>>, text: String, trace_id: Option<&str>) -> String {
+    let Some(filter) = filter else {
+        return text;
+    };
+    let (filtered, hits) = filter.apply(&text);
+    if hits > 0 {
+        info!(
+            target: "pipeline",
+            trace_id = trace_id.unwrap_or(""),
+            hits = hits,
+            "已过滤敏感词 | Filtered listed words"
+        );
+    }
+    filtered
+}
+
+/// 统一中英混排的标点宽度与空格；未启用或代码听写时原样返回
+fn fix_punctuation(punctuation: Option<PunctuationConfig>, text: String) -> String {
+    match punctuation {
+        Some(punctuation) => punctuation.fix(&text),
+        None => text,
+    }
+}
+
+/// 去掉句末标点（部分应用如聊天框/IDE 不需要自动补全的句号）
+fn strip_trailing_punctuation(text: &str) -> &str {
+    text.trim_end_matches(|c: char| matches!(c, '。' | '.' | '，' | ',' | '！' | '!' | '？' | '?' | '；' | ';'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct MockAsrEngine {
+        final_text: String,
+        /// 模拟服务器不可达：stop 失败并留下待补交的会话
+        offline: bool,
+        confidence: Option<f32>,
+        deferred: Option<Box<dyn DeferredSession>>,
+        /// 快慢两路识别中质量引擎的结果，会话请求了第二路识别时才返回
+        quality: Option<String>,
+        racing: bool,
         rx: mpsc::Receiver<asr::AsrEvent>,
     }
 
-    impl MockAsrEngine {
-        fn new(final_text: impl Into<String>) -> Self {
-            let (_tx, rx) = mpsc::channel(8);
-            Self {
-                final_text: final_text.into(),
-                rx,
+    impl MockAsrEngine {
+        fn new(final_text: impl Into<String>) -> Self {
+            let (_tx, rx) = mpsc::channel(8);
+            Self {
+                final_text: final_text.into(),
+                offline: false,
+                confidence: None,
+                deferred: None,
+                quality: None,
+                racing: false,
+                rx,
+            }
+        }
+    }
+
+    struct MockDeferredSession {
+        text: String,
+    }
+
+    #[async_trait]
+    impl DeferredSession for MockDeferredSession {
+        fn trace_id(&self) -> &str {
+            "t-offline"
+        }
+
+        async fn submit(self: Box<Self>) -> (anyhow::Result<String>, TrafficStats) {
+            (Ok(self.text), TrafficStats::default())
+        }
+    }
+
+    struct MockQualityResult {
+        text: String,
+    }
+
+    #[async_trait]
+    impl asr::QualityResult for MockQualityResult {
+        async fn wait(self: Box<Self>) -> anyhow::Result<String> {
+            Ok(self.text)
+        }
+    }
+
+    #[async_trait]
+    impl AsrEngine for MockAsrEngine {
+        async fn start(&mut self, _trace_id: String, _sample_rate: u32, context: AsrContext) -> anyhow::Result<()> {
+            self.racing = context.quality_pass;
+            Ok(())
+        }
+
+        async fn feed_audio(&mut self, _pcm: &[i16]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn cancel(&mut self) {
+            self.deferred = None;
+        }
+
+        async fn stop(&mut self) -> anyhow::Result<String> {
+            if self.offline {
+                self.deferred = Some(Box::new(MockDeferredSession {
+                    text: self.final_text.clone(),
+                }));
+                anyhow::bail!("websocket not connected");
+            }
+            Ok(self.final_text.clone())
+        }
+
+        fn events(&mut self) -> &mut mpsc::Receiver<asr::AsrEvent> {
+            &mut self.rx
+        }
+
+        fn take_deferred(&mut self) -> Option<Box<dyn DeferredSession>> {
+            self.deferred.take()
+        }
+
+        fn take_confidence(&mut self) -> Option<f32> {
+            self.confidence
+        }
+
+        fn take_quality_result(&mut self) -> Option<Box<dyn asr::QualityResult>> {
+            let text = self.quality.clone().filter(|_| std::mem::take(&mut self.racing))?;
+            Some(Box::new(MockQualityResult { text }))
+        }
+    }
+
+    struct MockLlmEngine {
+        corrected: String,
+        changed: bool,
+        delay: Duration,
+    }
+
+    impl MockLlmEngine {
+        fn new(corrected: impl Into<String>, changed: bool) -> Self {
+            Self {
+                corrected: corrected.into(),
+                changed,
+                delay: Duration::ZERO,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmEngine for MockLlmEngine {
+        async fn correct(&self, text: &str, _hints: llm::CorrectionHints) -> anyhow::Result<llm::CorrectionResult> {
+            tokio::time::sleep(self.delay).await;
+            Ok(llm::CorrectionResult {
+                original: text.to_string(),
+                corrected: self.corrected.clone(),
+                changed: self.changed,
+                latency_ms: 0,
+            })
+        }
+
+        /// 改写结果带上指令，便于确认指令与选中的文字都交给了 LLM
+        async fn rewrite(&self, instruction: &str, text: &str) -> anyhow::Result<llm::CorrectionResult> {
+            Ok(llm::CorrectionResult {
+                original: text.to_string(),
+                corrected: format!("{}（{instruction}）", self.corrected),
+                changed: true,
+                latency_ms: 0,
+            })
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+    }
+
+    fn test_pipeline(asr_text: &str, corrected: &str, changed: bool) -> (Pipeline, mpsc::Receiver<InjectCommand>) {
+        let (tx, rx) = mpsc::channel(16);
+        let injector = Injector { tx };
+        let (events, _events_rx) = crate::events::channel();
+
+        let asr: Box<dyn AsrEngine> = Box::new(MockAsrEngine::new(asr_text));
+        let llm: Arc<dyn LlmEngine> = Arc::new(MockLlmEngine::new(corrected, changed));
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel::<u64>(0);
+        let last_output = Arc::new(Mutex::new(None));
+        let injection_history = Arc::new(Mutex::new(InjectionHistory::default()));
+        let deferred = DeferredQueue::spawn(events.clone(), last_output.clone(), injection_history.clone());
+
+        (
+            Pipeline {
+                asr,
+                asr_config: asr::AsrConfig::default(),
+                llm,
+                llm_config: llm::LlmConfig::default(),
+                sinks: Sinks::keyboard(injector.clone()),
+                injector,
+                events,
+                generation: Arc::new(AtomicU64::new(0)),
+                cancel_tx,
+                _cancel_rx: cancel_rx,
+                trace_id: None,
+                app_name: String::new(),
+                injected_len: 0,
+                fed_samples: 0,
+                fed_frames: 0,
+                sample_rate: 16000,
+                level_meter: LevelMeter::new(16000),
+                last_output,
+                app_overrides: BTreeMap::new(),
+                rules: Arc::new(RuleSet::default()),
+                content_filter: Arc::new(ContentFilter::default()),
+                commands: CommandSet::default(),
+                snippets: SnippetLibrary::default(),
+                plugins: Arc::new(PluginSet::default()),
+                plugin_config: PluginConfig::default(),
+                code_formatter: Arc::new(CodeFormatter::default()),
+                numbers: Arc::new(NumberFormatter::default()),
+                language_config: LanguageConfig::default(),
+                punctuation: PunctuationConfig::default(),
+                dictionary: Arc::new(LearnedDictionary::in_memory()),
+                injection_history,
+                reliability: Arc::new(InjectionReliability::in_memory()),
+                session: SessionOverrides::default(),
+                deferred,
+                active: false,
+                pending_config: None,
+                segment_config: SegmentConfig::default(),
+                server_correction: false,
+                correction_gate: llm::CorrectionGate::default(),
+                correction_timing: llm::CorrectionTiming::default(),
+                throttle: ThrottleConfig::default(),
+                asr_fallback: None,
+                llm_fallback: None,
+                segmenter: None,
+                context: AsrContext::default(),
+                committed: String::new(),
+                segment_index: 0,
+                transform: false,
+                spans: SessionSpans::none(),
+            },
+            rx,
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_replaces_after_500ms_delay() {
+        let (mut pipeline, mut rx) = test_pipeline("你好", "您好", true);
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+
+        let cmd1 = rx.recv().await.expect("cmd1");
+        match cmd1 {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "你好"),
+            other => panic!("unexpected cmd1: {other:?}"),
+        }
+
+        assert!(rx.try_recv().is_err(), "不应在 500ms 内替换");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+
+        let cmd2 = rx.recv().await.expect("cmd2");
+        match cmd2 {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 2),
+            other => panic!("unexpected cmd2: {other:?}"),
+        }
+
+        let cmd3 = rx.recv().await.expect("cmd3");
+        match cmd3 {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "您好"),
+            other => panic!("unexpected cmd3: {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_replaces_fast_result_with_quality_result() {
+        let (mut pipeline, mut rx) = test_pipeline("今天天汽不错", "", false);
+        pipeline.asr = Box::new(MockAsrEngine {
+            quality: Some("今天天气不错".to_string()),
+            ..MockAsrEngine::new("今天天汽不错")
+        });
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("fast text") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "今天天汽不错"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        // LLM 没有改动质量结果时仍用它替换快速结果
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        match rx.recv().await.expect("backspace") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 6),
+            other => panic!("unexpected command: {other:?}"),
+        }
+        match rx.recv().await.expect("quality text") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "今天天气不错"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_honors_correction_timing() {
+        let (mut pipeline, mut rx) = test_pipeline("你好", "您好", true);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+        pipeline.set_correction_timing(llm::CorrectionTiming {
+            min_delay_ms: 100,
+            replace_window_ms: 2000,
+        });
+
+        // 缩短最短等待后，100ms 即替换
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        let _ = rx.recv().await.expect("asr cmd");
+        tokio::time::advance(Duration::from_millis(100)).await;
+        tokio::task::yield_now().await;
+        match rx.recv().await.expect("backspace") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 2),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        let _ = rx.recv().await.expect("corrected");
+
+        // 超出替换时限才返回的校正不自动替换，交给用户决定
+        pipeline.llm = Arc::new(MockLlmEngine {
+            delay: Duration::from_secs(3),
+            ..MockLlmEngine::new("您好", true)
+        });
+        let gen = pipeline
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        let _ = rx.recv().await.expect("asr cmd");
+        let mut late = None;
+        loop {
+            match events_rx.recv().await.expect("event") {
+                PipelineEvent::CorrectionLate(correction) => late = Some(correction),
+                PipelineEvent::CorrectionSkipped { trace_id } if trace_id.as_deref() == Some("t2") => break,
+                PipelineEvent::CorrectionApplied { trace_id, .. } if trace_id.as_deref() == Some("t2") => {
+                    panic!("超时的校正不应替换")
+                }
+                _ => continue,
+            }
+        }
+        assert!(rx.try_recv().is_err(), "超时的校正不应替换");
+
+        // 用户选择应用：仍是最后一次输入时回删替换
+        let late = late.expect("late correction");
+        assert_eq!((late.replace_chars, late.text.as_str()), (2, "您好"));
+        pipeline.apply_late_correction(&late).await.expect("apply");
+        match rx.recv().await.expect("backspace") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 2),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        match rx.recv().await.expect("corrected") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "您好"),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        let stale = LateCorrection {
+            trace_id: Some("t1".to_string()),
+            ..late
+        };
+        assert!(pipeline.apply_late_correction(&stale).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_reports_session_summary() {
+        let (mut pipeline, _rx) = test_pipeline("你好", "您好啊", true);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.feed_audio(&[0; 320]).await.expect("feed");
+        pipeline.feed_audio(&[0; 320]).await.expect("feed");
+        pipeline.stop(gen).await.expect("stop");
+
+        let summary = loop {
+            match events_rx.recv().await.expect("event") {
+                PipelineEvent::SessionSummary(summary) => break summary,
+                _ => continue,
+            }
+        };
+        assert_eq!(summary.trace_id.as_deref(), Some("t1"));
+        assert_eq!(summary.frames_sent, 2);
+        assert_eq!(summary.audio_ms, 40);
+        assert!(summary.llm_ms.is_some());
+        assert_eq!(summary.chars_injected, 3);
+        assert!(summary.corrected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_injects_segments_during_long_dictation() {
+        let (mut pipeline, mut rx) = test_pipeline("hello.", "hello.", false);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+        pipeline.set_segment_config(SegmentConfig {
+            enabled: true,
+            max_segment_ms: 60_000,
+            pause_ms: 200,
+            min_segment_ms: 0,
+        });
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        for _ in 0..10 {
+            pipeline.feed_audio(&[8000; 320]).await.expect("feed");
+        }
+        // 停顿 200ms 后第一段在录音中输出
+        for _ in 0..10 {
+            pipeline.feed_audio(&[0; 320]).await.expect("feed");
+        }
+        match rx.try_recv().expect("segment cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "hello."),
+            other => panic!("unexpected segment cmd: {other:?}"),
+        }
+
+        for _ in 0..5 {
+            pipeline.feed_audio(&[8000; 320]).await.expect("feed");
+        }
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("final cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, " hello."),
+            other => panic!("unexpected final cmd: {other:?}"),
+        }
+
+        let mut segments = 0;
+        let final_text = loop {
+            match events_rx.recv().await.expect("event") {
+                PipelineEvent::SegmentFinal { index, text, .. } => {
+                    segments += 1;
+                    assert_eq!((index, text.as_str()), (1, "hello."));
+                }
+                PipelineEvent::AsrFinal { text, .. } => break text,
+                _ => continue,
+            }
+        };
+        assert_eq!(segments, 1);
+        assert_eq!(final_text, "hello. hello.");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_skips_replace_when_new_session_started() {
+        let (mut pipeline, mut rx) = test_pipeline("hello", "fixed", true);
+
+        let gen1 = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start 1");
+        pipeline.stop(gen1).await.expect("stop 1");
+
+        let _ = rx.recv().await.expect("cmd1");
+
+        let _gen2 = pipeline
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start 2");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+
+        assert!(rx.try_recv().is_err(), "新会话开始后不应替换旧结果");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_discards_preempted_session() {
+        let (mut pipeline, mut rx) = test_pipeline("hello", "fixed", true);
+        let preempt = pipeline.preempt_handle();
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        preempt.preempt();
+        pipeline.stop(gen).await.expect("stop");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+
+        assert!(rx.try_recv().is_err(), "被抢占的会话不应输出");
+        assert!(pipeline.retype_last("").await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_cancel_discards_session_without_output() {
+        let (mut pipeline, mut rx) = test_pipeline("hello", "fixed", true);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+
+        pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.feed_audio(&[0; 320]).await.expect("feed");
+        pipeline.cancel().await;
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+
+        assert!(rx.try_recv().is_err(), "取消的会话不应输出");
+        assert!(pipeline.trace_id().is_none());
+        assert!(pipeline.retype_last("").await.is_err());
+        let mut cancelled = false;
+        while let Ok(event) = events_rx.try_recv() {
+            match event {
+                PipelineEvent::SessionCancelled { trace_id } => cancelled = trace_id.as_deref() == Some("t1"),
+                PipelineEvent::AsrFinal { .. } | PipelineEvent::SessionSummary(_) => {
+                    panic!("取消的会话不应有识别结果: {event:?}")
+                }
+                _ => {}
             }
         }
+        assert!(cancelled);
     }
 
-    #[async_trait]
-    impl AsrEngine for MockAsrEngine {
-        async fn start(&mut self, _trace_id: String, _sample_rate: u32, _context: AsrContext) -> anyhow::Result<()> {
-            Ok(())
-        }
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_no_replace_when_llm_unchanged() {
+        let (mut pipeline, mut rx) = test_pipeline("hello", "hello", false);
 
-        async fn feed_audio(&mut self, _pcm: &[i16]) -> anyhow::Result<()> {
-            Ok(())
-        }
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
 
-        async fn stop(&mut self) -> anyhow::Result<String> {
-            Ok(self.final_text.clone())
-        }
+        let _ = rx.recv().await.expect("cmd1");
 
-        fn events(&mut self) -> &mut mpsc::Receiver<asr::AsrEvent> {
-            &mut self.rx
-        }
-    }
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
 
-    struct MockLlmEngine {
-        corrected: String,
-        changed: bool,
+        assert!(rx.try_recv().is_err(), "LLM 无变化不应替换");
     }
 
-    impl MockLlmEngine {
-        fn new(corrected: impl Into<String>, changed: bool) -> Self {
-            Self {
-                corrected: corrected.into(),
-                changed,
-            }
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_applies_per_app_overrides() {
+        let (mut pipeline, mut rx) = test_pipeline("hello.", "Hello.", true);
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "Terminal".to_string(),
+            AppOverride {
+                llm_enabled: Some(false),
+                auto_punctuation: Some(false),
+                injection: Some(crate::input::InjectionStrategy::Clipboard),
+                ..AppOverride::default()
+            },
+        );
+        pipeline.set_app_overrides(overrides);
+
+        let context = AsrContext {
+            app_name: "terminal".to_string(),
+            ..AsrContext::default()
+        };
+        let gen = pipeline.start("t1".to_string(), 16000, context).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+
+        match rx.recv().await.expect("cmd1") {
+            InjectCommand::PasteText { text, .. } => assert_eq!(text, "hello"),
+            other => panic!("unexpected cmd1: {other:?}"),
         }
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+
+        assert!(rx.try_recv().is_err(), "禁用 LLM 的应用不应替换");
     }
 
-    #[async_trait]
-    impl LlmEngine for MockLlmEngine {
-        async fn correct(&self, text: &str) -> anyhow::Result<llm::CorrectionResult> {
-            Ok(llm::CorrectionResult {
-                original: text.to_string(),
-                corrected: self.corrected.clone(),
-                changed: self.changed,
-                latency_ms: 0,
-            })
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_formats_code_dictation_in_ide() {
+        let (mut pipeline, mut rx) = test_pipeline("user underscore name equals one.", "user_name = 1", true);
+        pipeline.set_code_mode(CodeFormatter::new(&crate::code_mode::CodeModeConfig {
+            enabled: true,
+            ..crate::code_mode::CodeModeConfig::default()
+        }));
+
+        let context = AsrContext {
+            app_name: "Code".to_string(),
+            ..AsrContext::default()
+        };
+        let gen = pipeline.start("t1".to_string(), 16000, context).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("cmd1") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "user_name = one"),
+            other => panic!("unexpected cmd1: {other:?}"),
+        }
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        match rx.recv().await.expect("backspace") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 15),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        match rx.recv().await.expect("cmd2") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "user_name = 1"),
+            other => panic!("unexpected cmd2: {other:?}"),
         }
 
-        async fn health_check(&self) -> bool {
-            true
+        // 按应用覆盖可以关闭自动进入的代码听写
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "Code".to_string(),
+            AppOverride {
+                llm_enabled: Some(false),
+                code_mode: Some(false),
+                ..AppOverride::default()
+            },
+        );
+        pipeline.set_app_overrides(overrides);
+        let context = AsrContext {
+            app_name: "Code".to_string(),
+            ..AsrContext::default()
+        };
+        let gen = pipeline.start("t2".to_string(), 16000, context).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("cmd3") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "user underscore name equals one."),
+            other => panic!("unexpected cmd3: {other:?}"),
         }
     }
 
-    fn test_pipeline(asr_text: &str, corrected: &str, changed: bool) -> (Pipeline, mpsc::Receiver<InjectCommand>) {
-        let (tx, rx) = mpsc::channel(16);
-        let injector = Injector { tx };
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_gates_correction_by_asr_confidence() {
+        let (mut pipeline, mut rx) = test_pipeline("好的", "好的。", true);
+        pipeline.set_correction_gate(llm::CorrectionGate {
+            enabled: true,
+            ..llm::CorrectionGate::default()
+        });
 
-        let asr: Box<dyn AsrEngine> = Box::new(MockAsrEngine::new(asr_text));
-        let llm: Arc<dyn LlmEngine> = Arc::new(MockLlmEngine::new(corrected, changed));
-        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel::<u64>(0);
+        // 高置信度的短句直接输出，不校正
+        pipeline.asr = Box::new(MockAsrEngine {
+            confidence: Some(0.95),
+            ..MockAsrEngine::new("好的")
+        });
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("cmd1") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "好的"),
+            other => panic!("unexpected cmd1: {other:?}"),
+        }
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err(), "高置信度短句不应校正");
 
-        (
-            Pipeline {
-                asr,
-                llm,
-                injector,
-                generation: Arc::new(AtomicU64::new(0)),
-                cancel_tx,
-                _cancel_rx: cancel_rx,
-                trace_id: None,
-                injected_len: 0,
+        // 低置信度时即使当前应用关闭了校正也要校正
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "Notes".to_string(),
+            AppOverride {
+                llm_enabled: Some(false),
+                ..AppOverride::default()
             },
-            rx,
-        )
+        );
+        pipeline.set_app_overrides(overrides);
+        pipeline.asr = Box::new(MockAsrEngine {
+            confidence: Some(0.3),
+            ..MockAsrEngine::new("好的")
+        });
+        let context = AsrContext {
+            app_name: "Notes".to_string(),
+            ..AsrContext::default()
+        };
+        let gen = pipeline.start("t2".to_string(), 16000, context).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        let _ = rx.recv().await.expect("asr cmd");
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        match rx.recv().await.expect("backspace") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 2),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        match rx.recv().await.expect("corrected") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "好的。"),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+
+        let gate = llm::CorrectionGate {
+            enabled: true,
+            ..llm::CorrectionGate::default()
+        };
+        assert_eq!(gate.decide(None, "好的"), llm::GateDecision::Default);
+        assert_eq!(gate.decide(Some(0.95), &"长".repeat(40)), llm::GateDecision::Default);
+        assert_eq!(llm::CorrectionGate::default().decide(Some(0.1), "好的"), llm::GateDecision::Default);
     }
 
     #[tokio::test(start_paused = true)]
-    async fn pipeline_replaces_after_500ms_delay() {
+    async fn pipeline_retypes_last_corrected_text() {
         let (mut pipeline, mut rx) = test_pipeline("你好", "您好", true);
+        assert!(pipeline.retype_last("").await.is_err(), "没有结果时不应输入");
 
         let gen = pipeline
             .start("t1".to_string(), 16000, AsrContext::default())
@@ -354,68 +2709,354 @@ mod tests {
             .expect("start");
         pipeline.stop(gen).await.expect("stop");
 
-        let cmd1 = rx.recv().await.expect("cmd1");
-        match cmd1 {
-            InjectCommand::TypeText { text, .. } => assert_eq!(text, "你好"),
-            other => panic!("unexpected cmd1: {other:?}"),
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        for _ in 0..3 {
+            let _ = rx.recv().await.expect("session cmd");
         }
 
-        assert!(rx.try_recv().is_err(), "不应在 500ms 内替换");
+        assert_eq!(pipeline.retype_last("").await.expect("retype"), "您好");
+        match rx.recv().await.expect("retype cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "您好"),
+            other => panic!("unexpected retype cmd: {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_applies_rules_to_asr_and_llm_output() {
+        let (mut pipeline, mut rx) = test_pipeline("open github", "Open github.", true);
+        let rules: Vec<crate::rules::TextRule> =
+            serde_json::from_str(r#"[{ "type": "exact", "from": "github", "to": "GitHub" }]"#).expect("rules");
+        pipeline.set_rules(RuleSet::compile(&rules).expect("compile"));
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+
+        match rx.recv().await.expect("cmd1") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "open GitHub"),
+            other => panic!("unexpected cmd1: {other:?}"),
+        }
 
         tokio::time::advance(Duration::from_millis(500)).await;
         tokio::task::yield_now().await;
 
-        let cmd2 = rx.recv().await.expect("cmd2");
-        match cmd2 {
-            InjectCommand::Backspace { count, .. } => assert_eq!(count, 2),
+        match rx.recv().await.expect("cmd2") {
+            InjectCommand::Backspace { count, .. } => assert_eq!(count, 11),
             other => panic!("unexpected cmd2: {other:?}"),
         }
-
-        let cmd3 = rx.recv().await.expect("cmd3");
-        match cmd3 {
-            InjectCommand::TypeText { text, .. } => assert_eq!(text, "您好"),
+        match rx.recv().await.expect("cmd3") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "Open GitHub."),
             other => panic!("unexpected cmd3: {other:?}"),
         }
     }
 
+    #[tokio::test]
+    async fn pipeline_rewrites_selection_with_spoken_instruction() {
+        let (mut pipeline, mut rx) = test_pipeline("改得正式一些。", "您好", true);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+
+        for selection in ["你好", " "] {
+            let gen = pipeline
+                .start_transform("t1".to_string(), 16000, AsrContext::default())
+                .await
+                .expect("start");
+            let reply_with_selection = async {
+                match rx.recv().await {
+                    Some(InjectCommand::CopySelection { reply, .. }) => {
+                        let _ = reply.send(Ok(selection.to_string()));
+                    }
+                    other => panic!("unexpected cmd: {other:?}"),
+                }
+            };
+            let (stopped, ()) = tokio::join!(pipeline.stop(gen), reply_with_selection);
+            stopped.expect("stop");
+        }
+
+        // 结果直接输入，替换仍处于选中状态的文字；没有选中内容时不输入
+        match rx.recv().await.expect("rewrite cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "您好（改得正式一些。）"),
+            other => panic!("unexpected rewrite cmd: {other:?}"),
+        }
+        assert!(rx.try_recv().is_err());
+
+        let mut applied = None;
+        let mut failed = None;
+        while let Ok(event) = events_rx.try_recv() {
+            match event {
+                PipelineEvent::TransformApplied { text, .. } => applied = Some(text),
+                PipelineEvent::Failed { error, .. } => failed = Some(error.code),
+                PipelineEvent::AsrFinal { .. } => panic!("改写指令不应作为听写结果输出"),
+                _ => {}
+            }
+        }
+        assert_eq!(applied.as_deref(), Some("您好（改得正式一些。）"));
+        assert_eq!(failed, Some(ErrorCode::NoSelection));
+    }
+
     #[tokio::test(start_paused = true)]
-    async fn pipeline_skips_replace_when_new_session_started() {
-        let (mut pipeline, mut rx) = test_pipeline("hello", "fixed", true);
+    async fn pipeline_learns_terms_from_corrected_selection() {
+        let (mut pipeline, mut rx) = test_pipeline("push to get hub", "push to get hub", false);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+        let config = crate::voice_command::VoiceCommandConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        pipeline.set_voice_commands(CommandSet::compile(&config).expect("compile"));
+        pipeline.dictionary.configure(&crate::dictionary::DictionaryConfig {
+            enabled: true,
+            ..Default::default()
+        });
 
-        let gen1 = pipeline
+        let gen = pipeline
             .start("t1".to_string(), 16000, AsrContext::default())
             .await
-            .expect("start 1");
-        pipeline.stop(gen1).await.expect("stop 1");
-
-        let _ = rx.recv().await.expect("cmd1");
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        let _ = rx.recv().await.expect("asr cmd");
 
-        let _gen2 = pipeline
+        // 用户把输出改好、选中后说 "记住这个"
+        pipeline.asr = Box::new(MockAsrEngine::new("记住这个。"));
+        let gen = pipeline
             .start("t2".to_string(), 16000, AsrContext::default())
             .await
-            .expect("start 2");
+            .expect("start");
+        let reply_with_selection = async {
+            match rx.recv().await {
+                Some(InjectCommand::CopySelection { reply, .. }) => {
+                    let _ = reply.send(Ok("push to GitHub".to_string()));
+                }
+                other => panic!("unexpected cmd: {other:?}"),
+            }
+        };
+        let (stopped, ()) = tokio::join!(pipeline.stop(gen), reply_with_selection);
+        stopped.expect("stop");
+
+        // 之后的输出按学到的说法替换
+        pipeline.asr = Box::new(MockAsrEngine::new("open get hub"));
+        let gen = pipeline
+            .start("t3".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        match rx.recv().await.expect("cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "open GitHub"),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+
+        let mut learned = None;
+        while let Ok(event) = events_rx.try_recv() {
+            if let PipelineEvent::TermLearned { from, to, .. } = event {
+                learned = Some((from, to));
+            }
+        }
+        assert_eq!(learned, Some(("get hub".to_string(), "GitHub".to_string())));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_runs_voice_commands_against_injection_history() {
+        let (mut pipeline, mut rx) = test_pipeline("hi", "Hello.", true);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.events = events;
+        let config = crate::voice_command::VoiceCommandConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        pipeline.set_voice_commands(CommandSet::compile(&config).expect("compile"));
+        let context = || AsrContext {
+            app_name: "Notes".to_string(),
+            ..AsrContext::default()
+        };
 
+        let gen = pipeline.start("t1".to_string(), 16000, context()).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
         tokio::time::advance(Duration::from_millis(500)).await;
         tokio::task::yield_now().await;
+        for _ in 0..3 {
+            let _ = rx.recv().await.expect("session cmd");
+        }
 
-        assert!(rx.try_recv().is_err(), "新会话开始后不应替换旧结果");
+        // 回删校正后的整句，不输入命令本身，也不做校正
+        pipeline.asr = Box::new(MockAsrEngine::new("Scratch that."));
+        let gen = pipeline.start("t2".to_string(), 16000, context()).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        assert!(matches!(rx.recv().await, Some(InjectCommand::Backspace { count: 6, .. })));
+
+        // 没有更早的输入时不做任何操作
+        let gen = pipeline.start("t3".to_string(), 16000, context()).await.expect("start");
+        pipeline.stop(gen).await.expect("stop");
+        tokio::time::advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+
+        let mut commands = Vec::new();
+        while let Ok(event) = events_rx.try_recv() {
+            if let PipelineEvent::VoiceCommand { action, applied, .. } = event {
+                commands.push((action, applied));
+            }
+        }
+        assert_eq!(commands, vec![(EditAction::DeleteLast, true), (EditAction::DeleteLast, false)]);
     }
 
     #[tokio::test(start_paused = true)]
-    async fn pipeline_no_replace_when_llm_unchanged() {
-        let (mut pipeline, mut rx) = test_pipeline("hello", "hello", false);
+    async fn undo_last_removes_several_sessions_after_correction() {
+        let (mut pipeline, mut rx) = test_pipeline("hi", "Hello.", true);
+        let context = || AsrContext {
+            app_name: "Notes".to_string(),
+            ..AsrContext::default()
+        };
+        for trace_id in ["t1", "t2"] {
+            let gen = pipeline.start(trace_id.to_string(), 16000, context()).await.expect("start");
+            pipeline.stop(gen).await.expect("stop");
+            tokio::time::advance(Duration::from_millis(500)).await;
+            tokio::task::yield_now().await;
+            for _ in 0..3 {
+                let _ = rx.recv().await.expect("session cmd");
+            }
+        }
+
+        // 两次输入都已被校正替换，按替换后的文字回删
+        let removed = pipeline.undo_last("Notes", 5).await.expect("undo");
+        assert_eq!(removed, "Hello.Hello.");
+        assert!(matches!(rx.recv().await, Some(InjectCommand::Backspace { count: 12, .. })));
+        assert!(pipeline.undo_last("Notes", 1).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pipeline_inserts_expanded_snippet_without_correction() {
+        let (mut pipeline, mut rx) = test_pipeline("Insert quote.", "Insert a quote.", true);
+        let config = crate::snippet::SnippetConfig {
+            enabled: true,
+            snippets: vec![Snippet {
+                name: "quote".to_string(),
+                text: "> {clipboard}".to_string(),
+            }],
+            ..Default::default()
+        };
+        pipeline.set_snippets(SnippetLibrary::compile(&config).expect("compile"));
 
         let gen = pipeline
             .start("t1".to_string(), 16000, AsrContext::default())
             .await
             .expect("start");
-        pipeline.stop(gen).await.expect("stop");
-
-        let _ = rx.recv().await.expect("cmd1");
+        let reply_with_clipboard = async {
+            match rx.recv().await {
+                Some(InjectCommand::ReadClipboard { reply, .. }) => {
+                    let _ = reply.send(Ok("copied".to_string()));
+                }
+                other => panic!("unexpected cmd: {other:?}"),
+            }
+        };
+        let (stopped, ()) = tokio::join!(pipeline.stop(gen), reply_with_clipboard);
+        stopped.expect("stop");
+        match rx.recv().await.expect("cmd") {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "> copied"),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
 
+        // 不做 LLM 校正，不会回删替换
         tokio::time::advance(Duration::from_millis(500)).await;
         tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+    }
 
-        assert!(rx.try_recv().is_err(), "LLM 无变化不应替换");
+    #[tokio::test]
+    async fn pipeline_queues_and_delivers_deferred_session() {
+        let (mut pipeline, mut rx) = test_pipeline("离线结果。", "", false);
+        let mut engine = MockAsrEngine::new("离线结果。");
+        engine.offline = true;
+        pipeline.asr = Box::new(engine);
+
+        let gen = pipeline
+            .start("t-offline".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop should queue instead of failing");
+
+        let cmd = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("deferred delivery")
+            .expect("cmd");
+        match cmd {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "离线结果。"),
+            other => panic!("unexpected deferred cmd: {other:?}"),
+        }
+        assert_eq!(pipeline.retype_last("").await.expect("retype"), "离线结果。");
+    }
+
+    #[tokio::test]
+    async fn pipeline_reports_deferred_session_it_could_not_type() {
+        let (mut pipeline, rx) = test_pipeline("离线结果。", "", false);
+        let mut engine = MockAsrEngine::new("离线结果。");
+        engine.offline = true;
+        pipeline.asr = Box::new(engine);
+        let (events, mut events_rx) = crate::events::channel();
+        pipeline.deferred =
+            DeferredQueue::spawn(events, pipeline.last_output.clone(), pipeline.injection_history.clone());
+        // 注入通道已关闭：补交的结果无法输出
+        drop(rx);
+
+        let gen = pipeline
+            .start("t-offline".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.stop(gen).await.expect("stop should queue instead of failing");
+
+        let failed = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let PipelineEvent::Failed { trace_id, error } = events_rx.recv().await.expect("event") {
+                    break (trace_id, error.code);
+                }
+            }
+        })
+        .await
+        .expect("failure event");
+        assert_eq!(failed, (Some("t-offline".to_string()), ErrorCode::InjectionFailed));
+        assert!(pipeline.last_output.lock().expect("last output lock").is_none());
+    }
+
+    #[tokio::test]
+    async fn pipeline_types_text_from_mock_asr_server() {
+        use crate::asr::mock_server::{MockAsrServer, MockReply};
+
+        let server = MockAsrServer::spawn(&[], MockReply::text("你好世界")).await;
+        let (mut pipeline, mut rx) = test_pipeline("", "", false);
+        pipeline
+            .replace_asr(&asr::AsrConfig::websocket(server.endpoint.clone()))
+            .expect("websocket engine");
+
+        let gen = pipeline
+            .start("t1".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        for _ in 0..5 {
+            pipeline.feed_audio(&[0i16; 640]).await.expect("feed");
+        }
+        pipeline.stop(gen).await.expect("stop");
+        let cmd = tokio::time::timeout(Duration::from_secs(3), rx.recv())
+            .await
+            .expect("typed")
+            .expect("cmd");
+        match cmd {
+            InjectCommand::TypeText { text, .. } => assert_eq!(text, "你好世界"),
+            other => panic!("unexpected cmd: {other:?}"),
+        }
+        assert_eq!(server.received().starts[0]["trace_id"], "t1");
+        assert!(server.received().frames[0] > 0);
+
+        // 服务端报错：本次会话失败，不输入文字
+        server.set_reply(MockReply::Error("model busy".to_string()));
+        let gen = pipeline
+            .start("t2".to_string(), 16000, AsrContext::default())
+            .await
+            .expect("start");
+        pipeline.feed_audio(&[0i16; 640]).await.expect("feed");
+        let err = pipeline.stop(gen).await.expect_err("asr error");
+        assert!(format!("{err:#}").contains("model busy"));
+        assert!(rx.try_recv().is_err());
     }
 }
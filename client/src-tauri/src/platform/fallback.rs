@@ -1,9 +1,24 @@
+#[allow(dead_code)]
+pub fn accessibility_required() -> bool {
+    false
+}
+
 #[allow(dead_code)]
 pub fn ensure_accessibility(prompt: bool) -> bool {
     let _ = prompt;
     true
 }
 
+#[allow(dead_code)]
+pub fn input_monitoring_granted() -> bool {
+    true
+}
+
+#[allow(dead_code)]
+pub fn open_input_monitoring_settings() -> Result<(), String> {
+    Err("当前平台不需要输入监控权限".to_string())
+}
+
 #[allow(dead_code)]
 pub fn open_accessibility_settings() -> Result<(), String> {
     Err("当前平台不需要辅助功能权限".to_string())
@@ -11,30 +26,118 @@ pub fn open_accessibility_settings() -> Result<(), String> {
 
 #[allow(dead_code)]
 pub fn open_microphone_settings() -> Result<(), String> {
-    #[cfg(windows)]
-    {
-        return open_sound_settings();
-    }
     Err("请在系统设置中检查麦克风权限/设备".to_string())
 }
 
 #[allow(dead_code)]
 pub fn open_sound_settings() -> Result<(), String> {
-    #[cfg(windows)]
-    {
-        use std::process::Command;
-        let status = Command::new("cmd")
-            .args(["/C", "start", "", "ms-settings:sound"])
-            .status()
-            .map_err(|err| err.to_string())?;
-        if status.success() {
-            return Ok(());
+    Err("当前平台不支持自动打开声音设置".to_string())
+}
+
+pub fn open_path(path: &std::path::Path) -> Result<(), String> {
+    let status = std::process::Command::new("xdg-open")
+        .arg(path)
+        .status()
+        .map_err(|err| err.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(format!("xdg-open failed: status={status}"))
+}
+
+fn autostart_entry_path() -> Result<std::path::PathBuf, String> {
+    use std::path::PathBuf;
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(|| "无法确定用户配置目录".to_string())?;
+    Ok(config_dir.join("autostart").join("ghosttype.desktop"))
+}
+
+pub fn autostart_enabled() -> Result<bool, String> {
+    Ok(autostart_entry_path()?.exists())
+}
+
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    let path = autostart_entry_path()?;
+    if !enabled {
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|err| err.to_string())?;
         }
-        return Err(format!("打开声音设置失败: status={status}"));
+        return Ok(());
     }
 
-    #[cfg(not(windows))]
-    {
-        Err("当前平台不支持自动打开声音设置".to_string())
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=GhostType\nExec=\"{}\"\nX-GNOME-Autostart-enabled=true\n",
+        exe.display()
+    );
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
     }
+    std::fs::write(&path, entry).map_err(|err| err.to_string())
+}
+
+pub fn taskbar_uses_light_theme() -> Option<bool> {
+    None
+}
+
+pub fn attach_console() {}
+
+pub fn caret_position() -> Option<super::CaretRect> {
+    None
+}
+
+pub fn focused_text() -> Option<String> {
+    None
+}
+
+pub fn focused_field() -> Option<super::FocusedField> {
+    None
+}
+
+#[allow(dead_code)]
+pub fn watch_session(on_event: std::sync::Arc<dyn Fn(super::SessionEvent) + Send + Sync>) -> bool {
+    let _ = on_event;
+    false
+}
+
+pub fn action_toast(app_id: Option<&str>, xml: &str, timeout: std::time::Duration) -> Option<String> {
+    let _ = (app_id, xml, timeout);
+    None
+}
+
+#[allow(dead_code)]
+pub fn power_supply() -> super::PowerSupply {
+    super::PowerSupply::default()
+}
+
+#[allow(dead_code)]
+pub fn hardware() -> super::Hardware {
+    let nvidia = super::nvidia::gpu();
+    super::Hardware {
+        cuda: nvidia.is_some(),
+        gpu_name: nvidia.as_ref().map(|(name, _)| name.clone()),
+        gpu_memory_mb: nvidia.and_then(|(_, memory)| memory),
+        ..super::Hardware::default()
+    }
+}
+
+pub fn is_elevated() -> bool {
+    false
+}
+
+pub fn has_ui_access() -> bool {
+    false
+}
+
+pub fn process_elevated(pid: u64) -> Option<bool> {
+    let _ = pid;
+    None
+}
+
+pub fn relaunch_elevated() -> Result<(), String> {
+    Err("只有 Windows 支持以管理员权限重新启动".to_string())
 }
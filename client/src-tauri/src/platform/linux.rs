@@ -0,0 +1,411 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+// 打开目录、开机启动等与其他类 Unix 平台相同
+pub use super::fallback::{
+    action_toast, attach_console, autostart_enabled, caret_position, focused_field, focused_text, has_ui_access,
+    input_monitoring_granted, is_elevated, open_input_monitoring_settings, open_path, process_elevated,
+    relaunch_elevated, set_autostart, taskbar_uses_light_theme,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisplayServer {
+    X11,
+    Wayland,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Desktop {
+    Gnome,
+    Kde,
+    Other,
+}
+
+fn display_server() -> DisplayServer {
+    parse_display_server(
+        std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        std::env::var_os("DISPLAY").is_some(),
+    )
+}
+
+/// 优先看 `XDG_SESSION_TYPE`；未设置时（如从终端或 systemd 服务启动）按显示相关的环境变量推断
+fn parse_display_server(session_type: Option<&str>, wayland_display: bool, x_display: bool) -> DisplayServer {
+    match session_type.map(|value| value.trim().to_ascii_lowercase()).as_deref() {
+        Some("wayland") => DisplayServer::Wayland,
+        Some("x11") => DisplayServer::X11,
+        _ if wayland_display => DisplayServer::Wayland,
+        _ if x_display => DisplayServer::X11,
+        _ => DisplayServer::Unknown,
+    }
+}
+
+fn desktop() -> Desktop {
+    parse_desktop(&std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default())
+}
+
+/// `XDG_CURRENT_DESKTOP` 是冒号分隔的列表，例如 `ubuntu:GNOME`
+fn parse_desktop(current: &str) -> Desktop {
+    for name in current.split(':').map(|name| name.trim().to_ascii_lowercase()) {
+        match name.as_str() {
+            "gnome" | "gnome-classic" | "gnome-flashback" | "unity" | "budgie" => return Desktop::Gnome,
+            "kde" | "plasma" => return Desktop::Kde,
+            _ => {}
+        }
+    }
+    Desktop::Other
+}
+
+/// 当前用户是否在 `input` 组中（可直接读写 `/dev/input` 与 `/dev/uinput`）
+fn in_input_group() -> bool {
+    Command::new("id")
+        .arg("-Gn")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| has_group(&String::from_utf8_lossy(&output.stdout), "input"))
+}
+
+fn has_group(groups: &str, name: &str) -> bool {
+    groups.split_whitespace().any(|group| group == name)
+}
+
+/// 桌面门户是否提供 RemoteDesktop 接口（Wayland 下模拟键盘输入的标准途径）
+fn remote_desktop_portal_available() -> bool {
+    Command::new("gdbus")
+        .args([
+            "introspect",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| String::from_utf8_lossy(&output.stdout).contains("org.freedesktop.portal.RemoteDesktop"))
+}
+
+/// Wayland 不允许普通程序监听全局按键和模拟输入，需要 `input` 组权限或 RemoteDesktop 门户；
+/// X11 没有这种限制
+pub fn accessibility_required() -> bool {
+    display_server() == DisplayServer::Wayland
+}
+
+/// Linux 没有可以触发的授权弹窗，`prompt` 不起作用
+pub fn ensure_accessibility(prompt: bool) -> bool {
+    let _ = prompt;
+    if !accessibility_required() {
+        return true;
+    }
+    in_input_group() || remote_desktop_portal_available()
+}
+
+pub fn open_accessibility_settings() -> Result<(), String> {
+    match desktop() {
+        Desktop::Gnome => open_settings_panel("gnome-control-center", "universal-access"),
+        Desktop::Kde => open_settings_panel("systemsettings", "kcm_access"),
+        Desktop::Other => Err("请将当前用户加入 input 组（sudo usermod -aG input $USER）后重新登录".to_string()),
+    }
+}
+
+pub fn open_microphone_settings() -> Result<(), String> {
+    match desktop() {
+        // GNOME 的麦克风开关在“隐私”面板中
+        Desktop::Gnome => open_settings_panel("gnome-control-center", "privacy"),
+        Desktop::Kde => open_settings_panel("systemsettings", "kcm_pulseaudio"),
+        Desktop::Other => Err("请在系统设置中检查麦克风权限/设备".to_string()),
+    }
+}
+
+pub fn open_sound_settings() -> Result<(), String> {
+    match desktop() {
+        Desktop::Gnome => open_settings_panel("gnome-control-center", "sound"),
+        Desktop::Kde => open_settings_panel("systemsettings", "kcm_pulseaudio"),
+        Desktop::Other => Err("当前桌面环境不支持自动打开声音设置".to_string()),
+    }
+}
+
+/// 电源与温区信息都来自 sysfs，读取开销很小
+pub fn power_supply() -> super::PowerSupply {
+    super::PowerSupply {
+        on_battery: on_battery(Path::new("/sys/class/power_supply")),
+        thermal_pressure: thermal_pressure(Path::new("/sys/class/thermal")),
+    }
+}
+
+const LOGIND_MANAGER_PATH: &str = "/org/freedesktop/login1";
+
+/// 由常驻的 `gdbus monitor` 转发 logind 的 D-Bus 信号：`PrepareForSleep` 表示休眠与唤醒，
+/// 当前会话的 `Lock`/`Unlock` 与 `LockedHint` 属性表示锁屏
+pub fn watch_session(on_event: Arc<dyn Fn(super::SessionEvent) + Send + Sync>) -> bool {
+    let Ok(mut monitor) = Command::new("gdbus")
+        .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    let Some(stdout) = monitor.stdout.take() else {
+        return false;
+    };
+    // 不知道当前会话时只处理休眠，不把别的会话锁屏当成自己的
+    let session = std::env::var("XDG_SESSION_ID")
+        .ok()
+        .map(|id| format!("{LOGIND_MANAGER_PATH}/session/{}", escape_object_path(&id)));
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(event) = parse_logind_signal(&line, session.as_deref()) {
+                on_event(event);
+            }
+        }
+        let _ = monitor.wait();
+    });
+    true
+}
+
+/// logind 对象路径中的会话 ID 按 sd-bus 的规则转义：字母数字以外的字符与开头的数字写成 `_xx`
+fn escape_object_path(label: &str) -> String {
+    if label.is_empty() {
+        return "_".to_string();
+    }
+    label
+        .bytes()
+        .enumerate()
+        .map(|(index, byte)| {
+            if byte.is_ascii_alphabetic() || (byte.is_ascii_digit() && index > 0) {
+                (byte as char).to_string()
+            } else {
+                format!("_{byte:02x}")
+            }
+        })
+        .collect()
+}
+
+/// `gdbus monitor` 每个信号一行：`<对象路径>: <接口>.<成员> (<参数>)`
+fn parse_logind_signal(line: &str, session: Option<&str>) -> Option<super::SessionEvent> {
+    use super::SessionEvent;
+
+    let (path, signal) = line.split_once(": ")?;
+    let (member, args) = signal.trim().split_once(' ')?;
+    if path == LOGIND_MANAGER_PATH {
+        return match (member, args) {
+            ("org.freedesktop.login1.Manager.PrepareForSleep", "(true,)") => Some(SessionEvent::Suspending),
+            ("org.freedesktop.login1.Manager.PrepareForSleep", "(false,)") => Some(SessionEvent::Resumed),
+            _ => None,
+        };
+    }
+    if Some(path) != session {
+        return None;
+    }
+    match member {
+        "org.freedesktop.login1.Session.Lock" => Some(SessionEvent::Locked),
+        "org.freedesktop.login1.Session.Unlock" => Some(SessionEvent::Unlocked),
+        "org.freedesktop.DBus.Properties.PropertiesChanged" if args.contains("'LockedHint': <true>") => {
+            Some(SessionEvent::Locked)
+        }
+        "org.freedesktop.DBus.Properties.PropertiesChanged" if args.contains("'LockedHint': <false>") => {
+            Some(SessionEvent::Unlocked)
+        }
+        _ => None,
+    }
+}
+
+/// 内存来自 `/proc/meminfo`；NVIDIA 显卡由 `nvidia-smi` 报告（没有 AMD/Intel 的本地推理加速检测）
+pub fn hardware() -> super::Hardware {
+    let nvidia = super::nvidia::gpu();
+    super::Hardware {
+        memory_mb: std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|meminfo| mem_total_mb(&meminfo)),
+        cuda: nvidia.is_some(),
+        gpu_name: nvidia.as_ref().map(|(name, _)| name.clone()),
+        gpu_memory_mb: nvidia.and_then(|(_, memory)| memory),
+        ..super::Hardware::default()
+    }
+}
+
+/// `MemTotal:       16318440 kB`
+fn mem_total_mb(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kb = line.trim().trim_end_matches("kB").trim().parse::<u64>().ok()?;
+    Some(kb / 1024)
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+/// 有外接电源在线时不算电池供电；否则看是否有电池在放电
+fn on_battery(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let mut discharging = false;
+    for supply in entries.flatten().map(|entry| entry.path()) {
+        match read_trimmed(&supply.join("type")).as_deref() {
+            Some("Mains" | "USB") if read_trimmed(&supply.join("online")).as_deref() == Some("1") => return false,
+            Some("Battery") => discharging |= read_trimmed(&supply.join("status")).as_deref() == Some("Discharging"),
+            _ => {}
+        }
+    }
+    discharging
+}
+
+/// 任一温区达到 passive 触发点（内核开始降频）即视为散热受限
+fn thermal_pressure(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .any(|entry| {
+            let zone = entry.path();
+            let Some(temp) = read_trimmed(&zone.join("temp")).and_then(|temp| temp.parse::<i64>().ok()) else {
+                return false;
+            };
+            (0..)
+                .map_while(|index| {
+                    read_trimmed(&zone.join(format!("trip_point_{index}_type"))).map(|kind| (index, kind))
+                })
+                .filter(|(_, kind)| kind == "passive")
+                .filter_map(|(index, _)| {
+                    read_trimmed(&zone.join(format!("trip_point_{index}_temp")))?
+                        .parse::<i64>()
+                        .ok()
+                })
+                .any(|trip| trip > 0 && temp >= trip)
+        })
+}
+
+fn open_settings_panel(program: &str, panel: &str) -> Result<(), String> {
+    // 设置程序在窗口关闭前不会退出，只确认能启动
+    Command::new(program)
+        .arg(panel)
+        .spawn()
+        .map(drop)
+        .map_err(|err| format!("{program} {panel}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_environment_is_detected() {
+        assert_eq!(
+            parse_display_server(Some("wayland"), false, true),
+            DisplayServer::Wayland
+        );
+        assert_eq!(parse_display_server(Some("X11"), true, true), DisplayServer::X11);
+        assert_eq!(parse_display_server(Some("tty"), true, true), DisplayServer::Wayland);
+        assert_eq!(parse_display_server(None, false, true), DisplayServer::X11);
+        assert_eq!(parse_display_server(None, false, false), DisplayServer::Unknown);
+
+        assert_eq!(parse_desktop("ubuntu:GNOME"), Desktop::Gnome);
+        assert_eq!(parse_desktop("KDE"), Desktop::Kde);
+        assert_eq!(parse_desktop("XFCE"), Desktop::Other);
+        assert_eq!(parse_desktop(""), Desktop::Other);
+
+        assert!(has_group("user adm input audio", "input"));
+        assert!(!has_group("user inputs", "input"));
+    }
+
+    #[test]
+    fn memory_is_read_from_meminfo() {
+        let meminfo = "MemTotal:       16318440 kB\nMemFree:         1021388 kB\n";
+        assert_eq!(mem_total_mb(meminfo), Some(15935));
+        assert_eq!(mem_total_mb("MemFree: 1 kB"), None);
+    }
+
+    #[test]
+    fn logind_signals_are_parsed() {
+        use super::super::SessionEvent;
+
+        assert_eq!(escape_object_path("2"), "_32");
+        assert_eq!(escape_object_path("c1"), "c1");
+        assert_eq!(escape_object_path("a-b"), "a_2db");
+
+        let session = Some("/org/freedesktop/login1/session/_32");
+        let parse = |line: &str| parse_logind_signal(line, session);
+        assert_eq!(
+            parse("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)"),
+            Some(SessionEvent::Suspending)
+        );
+        assert_eq!(
+            parse("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (false,)"),
+            Some(SessionEvent::Resumed)
+        );
+        assert_eq!(
+            parse("/org/freedesktop/login1/session/_32: org.freedesktop.login1.Session.Lock ()"),
+            Some(SessionEvent::Locked)
+        );
+        assert_eq!(
+            parse(
+                "/org/freedesktop/login1/session/_32: org.freedesktop.DBus.Properties.PropertiesChanged \
+                 ('org.freedesktop.login1.Session', {'LockedHint': <false>}, @as [])"
+            ),
+            Some(SessionEvent::Unlocked)
+        );
+        // 其它会话的锁屏、与锁屏无关的属性变化
+        assert_eq!(
+            parse("/org/freedesktop/login1/session/_33: org.freedesktop.login1.Session.Lock ()"),
+            None
+        );
+        assert_eq!(
+            parse(
+                "/org/freedesktop/login1/session/_32: org.freedesktop.DBus.Properties.PropertiesChanged \
+                 ('org.freedesktop.login1.Session', {'IdleHint': <true>}, @as [])"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_logind_signal(
+                "/org/freedesktop/login1/session/_32: org.freedesktop.login1.Session.Lock ()",
+                None
+            ),
+            None
+        );
+        assert_eq!(
+            parse("Monitoring signals from all objects owned by org.freedesktop.login1"),
+            None
+        );
+    }
+
+    #[test]
+    fn power_supply_is_read_from_sysfs() {
+        let root = std::env::temp_dir().join(format!("ghosttype-sysfs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let write = |path: &str, value: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+            std::fs::write(path, format!("{value}\n")).expect("write");
+        };
+        write("supply/BAT0/type", "Battery");
+        write("supply/BAT0/status", "Discharging");
+        write("supply/AC/type", "Mains");
+        write("supply/AC/online", "0");
+        write("thermal/thermal_zone0/temp", "88000");
+        write("thermal/thermal_zone0/trip_point_0_type", "critical");
+        write("thermal/thermal_zone0/trip_point_0_temp", "105000");
+        write("thermal/thermal_zone0/trip_point_1_type", "passive");
+        write("thermal/thermal_zone0/trip_point_1_temp", "95000");
+        write("thermal/cooling_device0/type", "Processor");
+        assert!(on_battery(&root.join("supply")));
+        assert!(!thermal_pressure(&root.join("thermal")));
+
+        write("supply/AC/online", "1");
+        write("thermal/thermal_zone0/temp", "96000");
+        assert!(!on_battery(&root.join("supply")));
+        assert!(thermal_pressure(&root.join("thermal")));
+        assert!(!on_battery(&root.join("missing")));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
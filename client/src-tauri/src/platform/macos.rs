@@ -1,13 +1,78 @@
-use core_foundation::base::TCFType;
+use core_foundation::base::{CFType, CFTypeRef, TCFType};
 use core_foundation::boolean::CFBoolean;
 use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
-use core_foundation::string::CFString;
+use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop, CFRunLoopSource, CFRunLoopSourceRef};
+use core_foundation::string::{CFString, CFStringRef};
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 
 #[link(name = "ApplicationServices", kind = "framework")]
 extern "C" {
     fn AXIsProcessTrusted() -> bool;
     fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+    fn AXUIElementCreateSystemWide() -> CFTypeRef;
+    fn AXUIElementSetMessagingTimeout(element: CFTypeRef, timeout_s: f32) -> i32;
+    fn AXUIElementCopyAttributeValue(element: CFTypeRef, attribute: CFStringRef, value: *mut CFTypeRef) -> i32;
+    fn AXUIElementCopyParameterizedAttributeValue(
+        element: CFTypeRef,
+        attribute: CFStringRef,
+        parameter: CFTypeRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
+    fn AXValueGetValue(value: CFTypeRef, value_type: u32, out: *mut std::ffi::c_void) -> bool;
+}
+
+/// `kAXValueCGRectType`
+const AX_VALUE_CG_RECT: u32 = 3;
+
+/// 查询光标或焦点文字时等待目标程序响应的上限，默认的 6 秒会卡住开始录音
+const AX_TIMEOUT_S: f32 = 0.2;
+
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+struct CGRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOHIDCheckAccess(request_type: u32) -> u32;
+    fn IORegisterForSystemPower(
+        refcon: *mut c_void,
+        port: *mut *mut c_void,
+        callback: extern "C" fn(*mut c_void, u32, u32, *mut c_void),
+        notifier: *mut u32,
+    ) -> u32;
+    fn IONotificationPortGetRunLoopSource(port: *mut c_void) -> CFRunLoopSourceRef;
+    fn IOAllowPowerChange(root_port: u32, notification_id: isize) -> i32;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFNotificationCenterGetDistributedCenter() -> *mut c_void;
+    fn CFNotificationCenterAddObserver(
+        center: *mut c_void,
+        observer: *const c_void,
+        callback: extern "C" fn(*mut c_void, *mut c_void, CFStringRef, *const c_void, CFDictionaryRef),
+        name: CFStringRef,
+        object: *const c_void,
+        suspension_behavior: isize,
+    );
+}
+
+/// `kIOHIDRequestTypeListenEvent`
+const IOHID_REQUEST_LISTEN_EVENT: u32 = 1;
+/// `kIOHIDAccessTypeGranted`；另有 Denied(1) 与 Unknown(2，尚未询问过用户)
+const IOHID_ACCESS_GRANTED: u32 = 0;
+
+pub fn accessibility_required() -> bool {
+    true
 }
 
 pub fn ensure_accessibility(prompt: bool) -> bool {
@@ -35,6 +100,21 @@ pub fn open_accessibility_settings() -> Result<(), String> {
     Err(format!("open failed: status={status}"))
 }
 
+pub fn input_monitoring_granted() -> bool {
+    unsafe { IOHIDCheckAccess(IOHID_REQUEST_LISTEN_EVENT) == IOHID_ACCESS_GRANTED }
+}
+
+pub fn open_input_monitoring_settings() -> Result<(), String> {
+    let status = Command::new("open")
+        .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent")
+        .status()
+        .map_err(|err| err.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(format!("open failed: status={status}"))
+}
+
 pub fn open_microphone_settings() -> Result<(), String> {
     let status = Command::new("open")
         .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone")
@@ -56,3 +136,314 @@ pub fn open_sound_settings() -> Result<(), String> {
     }
     Err(format!("open failed: status={status}"))
 }
+
+pub fn open_path(path: &Path) -> Result<(), String> {
+    let status = Command::new("open").arg(path).status().map_err(|err| err.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(format!("open failed: status={status}"))
+}
+
+const LAUNCH_AGENT_LABEL: &str = "com.ghosttype.client";
+
+fn launch_agent_path() -> Result<PathBuf, String> {
+    let home = std::env::var("HOME").map_err(|_| "无法确定用户目录".to_string())?;
+    Ok(PathBuf::from(home)
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{LAUNCH_AGENT_LABEL}.plist")))
+}
+
+pub fn autostart_enabled() -> Result<bool, String> {
+    Ok(launch_agent_path()?.exists())
+}
+
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    let path = launch_agent_path()?;
+    if !enabled {
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|err| err.to_string())?;
+        }
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{LAUNCH_AGENT_LABEL}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>{}</string>
+  </array>
+  <key>RunAtLoad</key>
+  <true/>
+</dict>
+</plist>
+"#,
+        crate::notify::xml_escape(&exe.display().to_string())
+    );
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(&path, plist).map_err(|err| err.to_string())
+}
+
+pub fn taskbar_uses_light_theme() -> Option<bool> {
+    // 菜单栏外观跟随系统深浅色模式，与窗口主题一致
+    None
+}
+
+fn copy_ax_attribute(element: &CFType, attribute: &str) -> Option<CFType> {
+    let attribute = CFString::new(attribute);
+    let mut value: CFTypeRef = std::ptr::null();
+    unsafe {
+        let err = AXUIElementCopyAttributeValue(element.as_CFTypeRef(), attribute.as_concrete_TypeRef(), &mut value);
+        (err == 0 && !value.is_null()).then(|| CFType::wrap_under_create_rule(value))
+    }
+}
+
+/// 焦点元素的选区（`AXSelectedTextRange`）经 `AXBoundsForRange` 换算成屏幕坐标；需要辅助功能权限
+pub fn caret_position() -> Option<super::CaretRect> {
+    unsafe {
+        let system = CFType::wrap_under_create_rule(AXUIElementCreateSystemWide());
+        AXUIElementSetMessagingTimeout(system.as_CFTypeRef(), AX_TIMEOUT_S);
+        let focused = copy_ax_attribute(&system, "AXFocusedUIElement")?;
+        let range = copy_ax_attribute(&focused, "AXSelectedTextRange")?;
+
+        let attribute = CFString::new("AXBoundsForRange");
+        let mut bounds: CFTypeRef = std::ptr::null();
+        let err = AXUIElementCopyParameterizedAttributeValue(
+            focused.as_CFTypeRef(),
+            attribute.as_concrete_TypeRef(),
+            range.as_CFTypeRef(),
+            &mut bounds,
+        );
+        if err != 0 || bounds.is_null() {
+            return None;
+        }
+        let bounds = CFType::wrap_under_create_rule(bounds);
+        let mut rect = CGRect::default();
+        if !AXValueGetValue(bounds.as_CFTypeRef(), AX_VALUE_CG_RECT, (&mut rect as *mut CGRect).cast()) {
+            return None;
+        }
+        // 部分程序对空选区返回全零的矩形
+        if rect == CGRect::default() {
+            return None;
+        }
+        Some(super::CaretRect {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        })
+    }
+}
+
+/// 焦点元素的 `AXValue`；需要辅助功能权限，非文本元素（或值不是字符串）返回 `None`
+pub fn focused_text() -> Option<String> {
+    let system = unsafe { CFType::wrap_under_create_rule(AXUIElementCreateSystemWide()) };
+    unsafe { AXUIElementSetMessagingTimeout(system.as_CFTypeRef(), AX_TIMEOUT_S) };
+    let focused = copy_ax_attribute(&system, "AXFocusedUIElement")?;
+    // 密码框不提供 AXValue
+    let value = copy_ax_attribute(&focused, "AXValue")?;
+    value.downcast::<CFString>().map(|text| text.to_string())
+}
+
+/// 焦点元素的 `AXRole`、`AXSubrole`、`AXPlaceholderValue` 与 `AXDescription`（或 `AXTitle`）；需要辅助功能权限
+pub fn focused_field() -> Option<super::FocusedField> {
+    let system = unsafe { CFType::wrap_under_create_rule(AXUIElementCreateSystemWide()) };
+    unsafe { AXUIElementSetMessagingTimeout(system.as_CFTypeRef(), AX_TIMEOUT_S) };
+    let focused = copy_ax_attribute(&system, "AXFocusedUIElement")?;
+    let string = |attribute: &str| {
+        copy_ax_attribute(&focused, attribute)
+            .and_then(|value| value.downcast::<CFString>())
+            .map(|value| value.to_string())
+            .filter(|value| !value.trim().is_empty())
+    };
+    let role = string("AXRole")?;
+    let subrole = string("AXSubrole");
+    Some(super::FocusedField {
+        multi_line: role == "AXTextArea",
+        password: subrole.as_deref() == Some("AXSecureTextField"),
+        search: subrole.as_deref() == Some("AXSearchField"),
+        role: match subrole {
+            Some(subrole) => format!("{role}/{subrole}"),
+            None => role,
+        },
+        placeholder: string("AXPlaceholderValue"),
+        label: string("AXDescription").or_else(|| string("AXTitle")),
+    })
+}
+
+/// `kIOMessageCanSystemSleep`、`kIOMessageSystemWillSleep`、`kIOMessageSystemHasPoweredOn`
+const IO_MESSAGE_CAN_SYSTEM_SLEEP: u32 = 0xE000_0270;
+const IO_MESSAGE_SYSTEM_WILL_SLEEP: u32 = 0xE000_0280;
+const IO_MESSAGE_SYSTEM_HAS_POWERED_ON: u32 = 0xE000_0300;
+/// `CFNotificationSuspensionBehaviorDeliverImmediately`
+const NOTIFICATION_DELIVER_IMMEDIATELY: isize = 4;
+const SCREEN_LOCKED: &str = "com.apple.screenIsLocked";
+const SCREEN_UNLOCKED: &str = "com.apple.screenIsUnlocked";
+
+/// 通知回调的上下文，随监听线程一直存在
+struct SessionWatcher {
+    on_event: Arc<dyn Fn(super::SessionEvent) + Send + Sync>,
+    /// `IORegisterForSystemPower` 返回的连接，休眠前要用它确认
+    root_port: AtomicU32,
+}
+
+/// 休眠前必须调用 `IOAllowPowerChange`，否则系统会等 30 秒才休眠
+extern "C" fn power_notification(refcon: *mut c_void, _service: u32, message: u32, argument: *mut c_void) {
+    let watcher = unsafe { &*(refcon as *const SessionWatcher) };
+    match message {
+        IO_MESSAGE_CAN_SYSTEM_SLEEP => unsafe {
+            IOAllowPowerChange(watcher.root_port.load(Ordering::SeqCst), argument as isize);
+        },
+        IO_MESSAGE_SYSTEM_WILL_SLEEP => {
+            (watcher.on_event)(super::SessionEvent::Suspending);
+            unsafe { IOAllowPowerChange(watcher.root_port.load(Ordering::SeqCst), argument as isize) };
+        }
+        IO_MESSAGE_SYSTEM_HAS_POWERED_ON => (watcher.on_event)(super::SessionEvent::Resumed),
+        _ => {}
+    }
+}
+
+extern "C" fn screen_notification(
+    _center: *mut c_void,
+    observer: *mut c_void,
+    name: CFStringRef,
+    _object: *const c_void,
+    _user_info: CFDictionaryRef,
+) {
+    let watcher = unsafe { &*(observer as *const SessionWatcher) };
+    let name = unsafe { CFString::wrap_under_get_rule(name) }.to_string();
+    match name.as_str() {
+        SCREEN_LOCKED => (watcher.on_event)(super::SessionEvent::Locked),
+        SCREEN_UNLOCKED => (watcher.on_event)(super::SessionEvent::Unlocked),
+        _ => {}
+    }
+}
+
+/// 休眠与唤醒来自 IOKit 的电源通知，锁屏与解锁来自分布式通知 `com.apple.screenIsLocked`/`Unlocked`；
+/// 两者都投递到监听线程自己的 run loop
+pub fn watch_session(on_event: Arc<dyn Fn(super::SessionEvent) + Send + Sync>) -> bool {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let watcher: &'static SessionWatcher = Box::leak(Box::new(SessionWatcher {
+            on_event,
+            root_port: AtomicU32::new(0),
+        }));
+        let context = watcher as *const SessionWatcher as *mut c_void;
+        let mut port = std::ptr::null_mut();
+        let mut notifier = 0u32;
+        let root_port = unsafe { IORegisterForSystemPower(context, &mut port, power_notification, &mut notifier) };
+        if root_port == 0 {
+            let _ = ready_tx.send(false);
+            return;
+        }
+        watcher.root_port.store(root_port, Ordering::SeqCst);
+        let run_loop = CFRunLoop::get_current();
+        unsafe {
+            let source = CFRunLoopSource::wrap_under_get_rule(IONotificationPortGetRunLoopSource(port));
+            run_loop.add_source(&source, kCFRunLoopDefaultMode);
+            let center = CFNotificationCenterGetDistributedCenter();
+            for name in [SCREEN_LOCKED, SCREEN_UNLOCKED] {
+                let name = CFString::new(name);
+                CFNotificationCenterAddObserver(
+                    center,
+                    context,
+                    screen_notification,
+                    name.as_concrete_TypeRef(),
+                    std::ptr::null(),
+                    NOTIFICATION_DELIVER_IMMEDIATELY,
+                );
+            }
+        }
+        let _ = ready_tx.send(true);
+        CFRunLoop::run_current();
+    });
+    ready_rx.recv().unwrap_or(false)
+}
+
+/// `pmset -g batt` 的首行说明供电来源；`pmset -g therm` 中 `CPU_Speed_Limit` 低于 100 表示正在降频
+pub fn power_supply() -> super::PowerSupply {
+    let pmset = |arg: &str| {
+        Command::new("pmset")
+            .args(["-g", arg])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            .unwrap_or_default()
+    };
+    super::PowerSupply {
+        on_battery: pmset("batt").contains("'Battery Power'"),
+        thermal_pressure: cpu_speed_limit(&pmset("therm")).is_some_and(|limit| limit < 100),
+    }
+}
+
+fn cpu_speed_limit(therm: &str) -> Option<u32> {
+    therm
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("CPU_Speed_Limit"))
+        .and_then(|rest| rest.trim().trim_start_matches('=').trim().parse().ok())
+}
+
+/// 所有 Mac 都支持 Metal；Apple Silicon 的 GPU 直接使用系统内存，型号即芯片名
+pub fn hardware() -> super::Hardware {
+    let sysctl = |name: &str| {
+        Command::new("sysctl")
+            .args(["-n", name])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let unified_memory = cfg!(target_arch = "aarch64");
+    super::Hardware {
+        memory_mb: sysctl("hw.memsize")
+            .and_then(|bytes| bytes.parse::<u64>().ok())
+            .map(|bytes| bytes / (1024 * 1024)),
+        metal: true,
+        unified_memory,
+        gpu_name: if unified_memory {
+            sysctl("machdep.cpu.brand_string")
+        } else {
+            None
+        },
+        ..super::Hardware::default()
+    }
+}
+
+pub fn is_elevated() -> bool {
+    false
+}
+
+pub fn has_ui_access() -> bool {
+    false
+}
+
+pub fn process_elevated(pid: u64) -> Option<bool> {
+    let _ = pid;
+    None
+}
+
+pub fn relaunch_elevated() -> Result<(), String> {
+    Err("只有 Windows 支持以管理员权限重新启动".to_string())
+}
+
+pub fn action_toast(app_id: Option<&str>, xml: &str, timeout: std::time::Duration) -> Option<String> {
+    // 通知中心的按钮需要签名的通知扩展，这里不支持
+    let _ = (app_id, xml, timeout);
+    None
+}
+
+pub fn attach_console() {
+    // 终端启动时 stdout/stderr 已继承
+}
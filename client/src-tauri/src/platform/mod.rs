@@ -1,22 +1,50 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod fallback;
+#[cfg(target_os = "linux")]
+mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(not(target_os = "macos"))]
-mod fallback;
+mod nvidia;
+#[cfg(windows)]
+mod windows;
 
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+use fallback as imp;
+#[cfg(target_os = "linux")]
+use linux as imp;
 #[cfg(target_os = "macos")]
 use macos as imp;
-#[cfg(not(target_os = "macos"))]
-use fallback as imp;
+#[cfg(windows)]
+use windows as imp;
+
+/// 模拟键盘输入/监听全局快捷键是否需要额外授权（macOS 辅助功能、Linux Wayland 的 input 组或门户）
+pub fn accessibility_required() -> bool {
+    imp::accessibility_required()
+}
 
-/// 检查/请求 macOS 辅助功能权限。
+/// 检查/请求辅助功能权限。
 ///
-/// - `prompt=true`：触发系统弹窗引导（如果尚未授权）
-/// - 非 macOS：直接返回 `true`
-#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+/// - `prompt=true`：触发系统弹窗引导（如果尚未授权；Linux 没有弹窗）
+/// - 不需要授权的平台：直接返回 `true`
 pub fn ensure_accessibility(prompt: bool) -> bool {
     imp::ensure_accessibility(prompt)
 }
 
+/// macOS 10.15+ 监听全局按键（rdev）需要单独的“输入监控”权限，缺失时热键不会触发；
+/// 其他平台直接返回 `true`
+pub fn input_monitoring_granted() -> bool {
+    imp::input_monitoring_granted()
+}
+
+pub fn open_input_monitoring_settings() -> Result<(), String> {
+    imp::open_input_monitoring_settings()
+}
+
 #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
 pub fn open_accessibility_settings() -> Result<(), String> {
     imp::open_accessibility_settings()
@@ -31,3 +59,148 @@ pub fn open_microphone_settings() -> Result<(), String> {
 pub fn open_sound_settings() -> Result<(), String> {
     imp::open_sound_settings()
 }
+
+/// 用系统文件管理器打开目录（或用默认程序打开文件）
+pub fn open_path(path: &Path) -> Result<(), String> {
+    imp::open_path(path)
+}
+
+/// 是否已注册为登录时自动启动
+pub fn autostart_enabled() -> Result<bool, String> {
+    imp::autostart_enabled()
+}
+
+/// 注册/取消登录时自动启动（macOS LaunchAgent、Windows Run 注册表、Linux XDG autostart）
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    imp::set_autostart(enabled)
+}
+
+/// 任务栏/菜单栏是否为浅色；`None` 表示与应用主题一致（无法单独检测）
+pub fn taskbar_uses_light_theme() -> Option<bool> {
+    imp::taskbar_uses_light_theme()
+}
+
+/// 让命令行子命令的输出显示在启动它的终端里（只有 Windows 发布版需要）
+pub fn attach_console() {
+    imp::attach_console()
+}
+
+/// 文本光标在屏幕上的位置：macOS 为逻辑坐标（点），Windows 为物理像素，原点在主屏左上角
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaretRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// 当前焦点中文本光标的位置，用于把悬浮窗放在正在输入的地方；拿不到时返回 `None`
+/// （macOS 需要辅助功能权限；Windows 只支持使用系统光标的程序，Chromium/Electron 等自绘光标的程序拿不到；
+/// Linux 暂不支持）
+pub fn caret_position() -> Option<CaretRect> {
+    imp::caret_position()
+}
+
+/// 焦点文本框的全部内容，用于输入后回读校验；拿不到时返回 `None`
+/// （macOS 读取辅助功能的 `AXValue`；Windows 只支持标准编辑框；Linux 暂不支持）
+pub fn focused_text() -> Option<String> {
+    imp::focused_text()
+}
+
+/// 焦点输入控件的平台信息，由 `input_field` 归类后随 ASR 上下文发出
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedField {
+    /// macOS 为 `AXRole`（有 `AXSubrole` 时为 `AXRole/AXSubrole`），Windows 为控件类名
+    pub role: String,
+    /// 输入框中的提示文字（`AXPlaceholderValue`、编辑框的 cue banner）
+    pub placeholder: Option<String>,
+    /// 控件的说明或标题（`AXDescription`/`AXTitle`）；Windows 暂不提供
+    pub label: Option<String>,
+    pub multi_line: bool,
+    pub password: bool,
+    pub search: bool,
+}
+
+/// 焦点输入控件的角色与提示文字；拿不到时返回 `None`
+/// （macOS 需要辅助功能权限；Windows 只识别标准编辑框；Linux 暂不支持）
+pub fn focused_field() -> Option<FocusedField> {
+    imp::focused_field()
+}
+
+/// 系统的休眠/唤醒与锁屏通知
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// 即将休眠
+    Suspending,
+    /// 从休眠中唤醒
+    Resumed,
+    Locked,
+    Unlocked,
+}
+
+/// 在独立线程上订阅休眠/唤醒与锁屏通知（Windows 为 `WM_POWERBROADCAST` 与会话通知，
+/// macOS 为 IOKit 电源通知与 `com.apple.screenIsLocked`，Linux 为 logind 的 D-Bus 信号）；
+/// 平台不支持时返回 `false`
+pub fn watch_session(on_event: Arc<dyn Fn(SessionEvent) + Send + Sync>) -> bool {
+    imp::watch_session(on_event)
+}
+
+/// 电源状态；无法判断的项按不受限处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerSupply {
+    /// 正在使用电池供电
+    pub on_battery: bool,
+    /// 系统因温度过高正在降频
+    pub thermal_pressure: bool,
+}
+
+/// 读取当前电源状态（可能需要启动外部命令，不要在热路径上调用）
+pub fn power_supply() -> PowerSupply {
+    imp::power_supply()
+}
+
+/// 内存与可用的 GPU 加速；检测不到的项为 `None`/`false`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hardware {
+    pub memory_mb: Option<u64>,
+    pub metal: bool,
+    /// GPU 与 CPU 共用内存（Apple Silicon），显存即系统内存
+    pub unified_memory: bool,
+    /// 已安装 NVIDIA 驱动（`nvidia-smi` 可用）
+    pub cuda: bool,
+    /// 系统自带 DirectML（Windows 10 1903 起）
+    pub directml: bool,
+    pub gpu_name: Option<String>,
+    pub gpu_memory_mb: Option<u64>,
+}
+
+/// 探测硬件（需要启动外部命令，不要在热路径上调用）
+pub fn hardware() -> Hardware {
+    imp::hardware()
+}
+
+/// 当前进程是否以管理员权限运行（仅 Windows）
+pub fn is_elevated() -> bool {
+    imp::is_elevated()
+}
+
+/// 当前进程是否具备 UIAccess（仅 Windows，需要签名并安装在受信任目录的 uiAccess 版本）
+pub fn has_ui_access() -> bool {
+    imp::has_ui_access()
+}
+
+/// 指定进程是否以管理员权限运行；`None` 表示无法判断（非 Windows 或进程已退出）
+pub fn process_elevated(pid: u64) -> Option<bool> {
+    imp::process_elevated(pid)
+}
+
+/// 通过 UAC 以管理员权限重新启动自身，成功后调用方应退出
+pub fn relaunch_elevated() -> Result<(), String> {
+    imp::relaunch_elevated()
+}
+
+/// 显示带按钮的系统通知并等待用户选择，返回所点按钮的标识；不支持（仅 Windows 支持）、关闭或超时返回 `None`。
+/// `app_id` 为空时使用 PowerShell 的身份显示
+pub fn action_toast(app_id: Option<&str>, xml: &str, timeout: Duration) -> Option<String> {
+    imp::action_toast(app_id, xml, timeout)
+}
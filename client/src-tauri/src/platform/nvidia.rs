@@ -0,0 +1,39 @@
+/// 第一块 NVIDIA 显卡的名称与显存（MB）；没有驱动时 `nvidia-smi` 不存在
+pub fn gpu() -> Option<(String, Option<u64>)> {
+    let mut command = std::process::Command::new("nvidia-smi");
+    command.args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"]);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output().ok().filter(|output| output.status.success())?;
+    parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 每块显卡一行：`NVIDIA GeForce RTX 4070, 12282`
+fn parse_nvidia_smi(output: &str) -> Option<(String, Option<u64>)> {
+    let line = output.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let (name, memory) = line.rsplit_once(',').unwrap_or((line, ""));
+    Some((name.trim().to_string(), memory.trim().parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nvidia_smi_output_is_parsed() {
+        let output = "NVIDIA GeForce RTX 4070, 12282\nNVIDIA GeForce GTX 1650, 4096\n";
+        assert_eq!(
+            parse_nvidia_smi(output),
+            Some(("NVIDIA GeForce RTX 4070".to_string(), Some(12282)))
+        );
+        assert_eq!(
+            parse_nvidia_smi("Tesla T4, [N/A]"),
+            Some(("Tesla T4".to_string(), None))
+        );
+        assert_eq!(parse_nvidia_smi(""), None);
+    }
+}
@@ -0,0 +1,728 @@
+pub fn accessibility_required() -> bool {
+    false
+}
+
+pub fn ensure_accessibility(prompt: bool) -> bool {
+    let _ = prompt;
+    true
+}
+
+pub fn input_monitoring_granted() -> bool {
+    true
+}
+
+pub fn open_input_monitoring_settings() -> Result<(), String> {
+    Err("当前平台不需要输入监控权限".to_string())
+}
+
+pub fn open_accessibility_settings() -> Result<(), String> {
+    Err("当前平台不需要辅助功能权限".to_string())
+}
+
+pub fn open_microphone_settings() -> Result<(), String> {
+    open_sound_settings()
+}
+
+pub fn open_sound_settings() -> Result<(), String> {
+    use std::process::Command;
+    let status = Command::new("cmd")
+        .args(["/C", "start", "", "ms-settings:sound"])
+        .status()
+        .map_err(|err| err.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(format!("打开声音设置失败: status={status}"))
+}
+
+pub fn open_path(path: &std::path::Path) -> Result<(), String> {
+    // explorer 的退出码不表示成败，只确认能启动
+    std::process::Command::new("explorer")
+        .arg(path)
+        .spawn()
+        .map(drop)
+        .map_err(|err| err.to_string())
+}
+
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+const RUN_VALUE: &str = "GhostType";
+
+pub fn autostart_enabled() -> Result<bool, String> {
+    use std::process::Command;
+    let output = Command::new("reg")
+        .args(["query", RUN_KEY, "/v", RUN_VALUE])
+        .output()
+        .map_err(|err| err.to_string())?;
+    Ok(output.status.success())
+}
+
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    use std::process::Command;
+    let mut cmd = Command::new("reg");
+    if enabled {
+        let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+        let data = format!("\"{}\"", exe.display());
+        cmd.args(["add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d", &data, "/f"]);
+    } else {
+        if !autostart_enabled()? {
+            return Ok(());
+        }
+        cmd.args(["delete", RUN_KEY, "/v", RUN_VALUE, "/f"]);
+    }
+
+    let status = cmd.status().map_err(|err| err.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(format!("修改启动项失败: status={status}"))
+}
+
+/// Windows 的任务栏主题（SystemUsesLightTheme）可以与应用主题（AppsUseLightTheme）不同
+pub fn taskbar_uses_light_theme() -> Option<bool> {
+    use std::process::Command;
+    let output = Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "SystemUsesLightTheme",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout.split_whitespace().last()?;
+    Some(value == "0x1")
+}
+
+/// Windows 发布版是 GUI 子系统，没有控制台；从终端运行命令行子命令时接到父进程的控制台上
+pub fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // 已有控制台（调试版）或没有父控制台时调用失败，无需处理
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+mod elevation {
+    use std::ffi::c_void;
+
+    pub type Handle = *mut c_void;
+
+    pub const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    pub const TOKEN_QUERY: u32 = 0x0008;
+    pub const TOKEN_ELEVATION: u32 = 20;
+    pub const TOKEN_UI_ACCESS: u32 = 26;
+    pub const ERROR_ACCESS_DENIED: u32 = 5;
+    pub const SW_SHOWNORMAL: i32 = 1;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> Handle;
+        pub fn GetCurrentProcess() -> Handle;
+        pub fn CloseHandle(handle: Handle) -> i32;
+        pub fn GetLastError() -> u32;
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        pub fn OpenProcessToken(process: Handle, desired_access: u32, token: *mut Handle) -> i32;
+        pub fn GetTokenInformation(
+            token: Handle,
+            class: u32,
+            information: *mut c_void,
+            length: u32,
+            return_length: *mut u32,
+        ) -> i32;
+    }
+
+    #[link(name = "shell32")]
+    extern "system" {
+        pub fn ShellExecuteW(
+            hwnd: Handle,
+            operation: *const u16,
+            file: *const u16,
+            parameters: *const u16,
+            directory: *const u16,
+            show: i32,
+        ) -> Handle;
+    }
+
+    /// 读取进程令牌中的 DWORD 标志（TokenElevation、TokenUIAccess）；失败时返回 GetLastError
+    pub fn token_flag(process: Handle, class: u32) -> Result<bool, u32> {
+        unsafe {
+            let mut token: Handle = std::ptr::null_mut();
+            if OpenProcessToken(process, TOKEN_QUERY, &mut token) == 0 {
+                return Err(GetLastError());
+            }
+            let mut value: u32 = 0;
+            let mut length: u32 = 0;
+            let ok = GetTokenInformation(
+                token,
+                class,
+                (&mut value as *mut u32).cast(),
+                std::mem::size_of::<u32>() as u32,
+                &mut length,
+            );
+            let result = if ok == 0 { Err(GetLastError()) } else { Ok(value != 0) };
+            CloseHandle(token);
+            result
+        }
+    }
+
+    pub fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+}
+
+/// 前台线程的焦点控件与光标（GetGUIThreadInfo），光标位置、回读文字与输入框信息共用
+mod gui {
+    use std::ffi::c_void;
+
+    pub type Hwnd = *mut c_void;
+
+    const SMTO_ABORTIFHUNG: u32 = 0x0002;
+    /// 目标程序卡住时最多等待的时间
+    const MESSAGE_TIMEOUT_MS: u32 = 200;
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct Rect {
+        pub left: i32,
+        pub top: i32,
+        pub right: i32,
+        pub bottom: i32,
+    }
+
+    #[repr(C)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[repr(C)]
+    pub struct GuiThreadInfo {
+        size: u32,
+        flags: u32,
+        active: Hwnd,
+        pub focus: Hwnd,
+        capture: Hwnd,
+        menu_owner: Hwnd,
+        move_size: Hwnd,
+        pub caret: Hwnd,
+        pub caret_rect: Rect,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetGUIThreadInfo(thread_id: u32, info: *mut GuiThreadInfo) -> i32;
+        fn GetClassNameW(hwnd: Hwnd, name: *mut u16, max: i32) -> i32;
+        fn SendMessageTimeoutW(
+            hwnd: Hwnd,
+            msg: u32,
+            wparam: usize,
+            lparam: isize,
+            flags: u32,
+            timeout_ms: u32,
+            result: *mut usize,
+        ) -> isize;
+        pub fn ClientToScreen(hwnd: Hwnd, point: *mut Point) -> i32;
+        pub fn GetWindowLongW(hwnd: Hwnd, index: i32) -> i32;
+        fn GetWindowThreadProcessId(hwnd: Hwnd, process_id: *mut u32) -> u32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcessId() -> u32;
+    }
+
+    pub fn foreground_thread() -> Option<GuiThreadInfo> {
+        let mut info = GuiThreadInfo {
+            size: std::mem::size_of::<GuiThreadInfo>() as u32,
+            flags: 0,
+            active: std::ptr::null_mut(),
+            focus: std::ptr::null_mut(),
+            capture: std::ptr::null_mut(),
+            menu_owner: std::ptr::null_mut(),
+            move_size: std::ptr::null_mut(),
+            caret: std::ptr::null_mut(),
+            caret_rect: Rect::default(),
+        };
+        // 线程 ID 为 0 表示前台线程
+        (unsafe { GetGUIThreadInfo(0, &mut info) } != 0).then_some(info)
+    }
+
+    /// 焦点控件及其窗口类名
+    pub fn focused_control() -> Option<(Hwnd, String)> {
+        let focus = foreground_thread()?.focus;
+        if focus.is_null() {
+            return None;
+        }
+        let mut class = [0u16; 64];
+        let len = unsafe { GetClassNameW(focus, class.as_mut_ptr(), class.len() as i32) };
+        Some((focus, String::from_utf16_lossy(&class[..len.max(0) as usize])))
+    }
+
+    /// 标准编辑框（Edit、RichEdit20W、RICHEDIT50W 等）
+    pub fn is_edit_class(class: &str) -> bool {
+        class.to_ascii_lowercase().contains("edit")
+    }
+
+    /// 窗口是否属于本进程；私有消息（WM_USER 之后）的指针参数不会跨进程封送
+    pub fn owned_by_current_process(hwnd: Hwnd) -> bool {
+        let mut process_id = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) != 0 && process_id == GetCurrentProcessId() }
+    }
+
+    /// 带超时地发送消息，返回消息的结果；超时或失败时为 `None`
+    pub fn send_message(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> Option<usize> {
+        let mut result = 0usize;
+        let sent = unsafe {
+            SendMessageTimeoutW(
+                hwnd,
+                msg,
+                wparam,
+                lparam,
+                SMTO_ABORTIFHUNG,
+                MESSAGE_TIMEOUT_MS,
+                &mut result,
+            )
+        };
+        (sent != 0).then_some(result)
+    }
+}
+
+pub fn is_elevated() -> bool {
+    // GetCurrentProcess 返回伪句柄，无需关闭
+    elevation::token_flag(unsafe { elevation::GetCurrentProcess() }, elevation::TOKEN_ELEVATION).unwrap_or(false)
+}
+
+pub fn has_ui_access() -> bool {
+    elevation::token_flag(unsafe { elevation::GetCurrentProcess() }, elevation::TOKEN_UI_ACCESS).unwrap_or(false)
+}
+
+pub fn process_elevated(pid: u64) -> Option<bool> {
+    let pid = u32::try_from(pid).ok().filter(|pid| *pid != 0)?;
+    unsafe {
+        let process = elevation::OpenProcess(elevation::PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let result = match elevation::token_flag(process, elevation::TOKEN_ELEVATION) {
+            Ok(elevated) => Some(elevated),
+            // 普通权限的进程无法读取管理员进程的令牌，拒绝访问本身说明对方权限更高
+            Err(elevation::ERROR_ACCESS_DENIED) => Some(true),
+            Err(_) => None,
+        };
+        elevation::CloseHandle(process);
+        result
+    }
+}
+
+/// 通过 UAC 以管理员权限启动自身（参数不变）；用户取消授权时返回错误
+pub fn relaunch_elevated() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let parameters = std::env::args()
+        .skip(1)
+        .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let operation = elevation::wide("runas");
+    let file = elevation::wide(&exe.display().to_string());
+    let parameters = elevation::wide(&parameters);
+    let result = unsafe {
+        elevation::ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            parameters.as_ptr(),
+            std::ptr::null(),
+            elevation::SW_SHOWNORMAL,
+        )
+    };
+    // 返回值大于 32 表示成功
+    if result as usize > 32 {
+        return Ok(());
+    }
+    Err(format!("ShellExecuteW runas failed: code={}", result as usize))
+}
+
+/// 前台线程的系统光标（GetGUIThreadInfo），坐标从光标所在窗口的客户区换算到屏幕
+pub fn caret_position() -> Option<super::CaretRect> {
+    let info = gui::foreground_thread()?;
+    if info.caret.is_null() {
+        return None;
+    }
+    let rect = &info.caret_rect;
+    let mut origin = gui::Point {
+        x: rect.left,
+        y: rect.top,
+    };
+    if unsafe { gui::ClientToScreen(info.caret, &mut origin) } == 0 {
+        return None;
+    }
+    Some(super::CaretRect {
+        x: origin.x as f64,
+        y: origin.y as f64,
+        width: (rect.right - rect.left) as f64,
+        height: (rect.bottom - rect.top) as f64,
+    })
+}
+
+/// 焦点控件的文字：只读取标准编辑框（Edit/RichEdit 类），其它控件的窗口文字是标题而不是内容；
+/// Chromium/Electron、WPF、UWP 的文本框没有独立的窗口，WM_GETTEXT 读不到，这些应用不做回读校验
+pub fn focused_text() -> Option<String> {
+    const WM_GETTEXT: u32 = 0x000D;
+    const WM_GETTEXTLENGTH: u32 = 0x000E;
+    /// 内容过长的编辑框（例如大文档）不做回读
+    const MAX_CHARS: usize = 1 << 20;
+
+    let (focus, class) = gui::focused_control()?;
+    if !gui::is_edit_class(&class) {
+        return None;
+    }
+    let chars = gui::send_message(focus, WM_GETTEXTLENGTH, 0, 0).filter(|chars| *chars <= MAX_CHARS)?;
+    let mut buffer = vec![0u16; chars + 1];
+    let copied = gui::send_message(focus, WM_GETTEXT, buffer.len(), buffer.as_mut_ptr() as isize)?;
+    Some(String::from_utf16_lossy(&buffer[..copied.min(chars)]))
+}
+
+/// 焦点控件的类名与编辑框样式；本进程内的标准编辑框另外读取 cue banner（占位文字）
+pub fn focused_field() -> Option<super::FocusedField> {
+    const GWL_STYLE: i32 = -16;
+    const ES_MULTILINE: i32 = 0x0004;
+    const ES_PASSWORD: i32 = 0x0020;
+    const EM_GETCUEBANNER: u32 = 0x1502;
+
+    let (focus, class) = gui::focused_control()?;
+    if class.is_empty() {
+        return None;
+    }
+    // 样式位只对标准编辑框有意义，其它控件（如浏览器的渲染窗口）只报告类名
+    if !gui::is_edit_class(&class) {
+        return Some(super::FocusedField {
+            role: class,
+            ..super::FocusedField::default()
+        });
+    }
+    let style = unsafe { gui::GetWindowLongW(focus, GWL_STYLE) };
+    // EM_GETCUEBANNER 的缓冲区指针不会被系统封送，发给其它进程会让对方往它自己的地址空间里写
+    let placeholder = gui::owned_by_current_process(focus)
+        .then(|| {
+            let mut banner = [0u16; 256];
+            let found = gui::send_message(
+                focus,
+                EM_GETCUEBANNER,
+                banner.as_mut_ptr() as usize,
+                banner.len() as isize,
+            )?;
+            let len = banner.iter().position(|&c| c == 0).unwrap_or(banner.len());
+            let placeholder = String::from_utf16_lossy(&banner[..len]);
+            (found != 0 && !placeholder.trim().is_empty()).then_some(placeholder)
+        })
+        .flatten();
+    Some(super::FocusedField {
+        role: class,
+        placeholder,
+        label: None,
+        multi_line: style & ES_MULTILINE != 0,
+        password: style & ES_PASSWORD != 0,
+        search: false,
+    })
+}
+
+/// 接收休眠/唤醒与锁屏通知的隐藏窗口；窗口过程只在创建它的线程上运行
+mod session {
+    use std::cell::RefCell;
+    use std::ffi::c_void;
+    use std::sync::Arc;
+
+    use crate::platform::SessionEvent;
+
+    type Hwnd = *mut c_void;
+    type Callback = Arc<dyn Fn(SessionEvent) + Send + Sync>;
+
+    const WM_POWERBROADCAST: u32 = 0x0218;
+    const WM_WTSSESSION_CHANGE: u32 = 0x02B1;
+    const PBT_APMSUSPEND: usize = 0x0004;
+    /// 每次唤醒都会发送；用户操作唤醒时另有 PBT_APMRESUMESUSPEND，不重复处理
+    const PBT_APMRESUMEAUTOMATIC: usize = 0x0012;
+    const WTS_SESSION_LOCK: usize = 0x7;
+    const WTS_SESSION_UNLOCK: usize = 0x8;
+    const NOTIFY_FOR_THIS_SESSION: u32 = 0;
+
+    #[repr(C)]
+    struct WndClassW {
+        style: u32,
+        window_proc: unsafe extern "system" fn(Hwnd, u32, usize, isize) -> isize,
+        class_extra: i32,
+        window_extra: i32,
+        instance: *mut c_void,
+        icon: *mut c_void,
+        cursor: *mut c_void,
+        background: *mut c_void,
+        menu_name: *const u16,
+        class_name: *const u16,
+    }
+
+    #[repr(C)]
+    struct Msg {
+        hwnd: Hwnd,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+        time: u32,
+        x: i32,
+        y: i32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn RegisterClassW(class: *const WndClassW) -> u16;
+        fn CreateWindowExW(
+            ex_style: u32,
+            class_name: *const u16,
+            window_name: *const u16,
+            style: u32,
+            x: i32,
+            y: i32,
+            width: i32,
+            height: i32,
+            parent: Hwnd,
+            menu: *mut c_void,
+            instance: *mut c_void,
+            param: *mut c_void,
+        ) -> Hwnd;
+        fn DefWindowProcW(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> isize;
+        fn GetMessageW(msg: *mut Msg, hwnd: Hwnd, filter_min: u32, filter_max: u32) -> i32;
+        fn DispatchMessageW(msg: *const Msg) -> isize;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+    }
+
+    #[link(name = "wtsapi32")]
+    extern "system" {
+        fn WTSRegisterSessionNotification(hwnd: Hwnd, flags: u32) -> i32;
+    }
+
+    thread_local! {
+        static CALLBACK: RefCell<Option<Callback>> = const { RefCell::new(None) };
+    }
+
+    unsafe extern "system" fn window_proc(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> isize {
+        let event = match (msg, wparam) {
+            (WM_POWERBROADCAST, PBT_APMSUSPEND) => Some(SessionEvent::Suspending),
+            (WM_POWERBROADCAST, PBT_APMRESUMEAUTOMATIC) => Some(SessionEvent::Resumed),
+            (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => Some(SessionEvent::Locked),
+            (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => Some(SessionEvent::Unlocked),
+            _ => None,
+        };
+        if let Some(event) = event {
+            // 先取出回调再调用，回调里再分发消息时不会重复借用
+            if let Some(callback) = CALLBACK.with(|callback| callback.borrow().clone()) {
+                callback(event);
+            }
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    /// 在当前线程上创建窗口并登记会话通知；失败时返回 `false`
+    pub fn register(callback: Callback) -> bool {
+        let class_name = super::elevation::wide("GhostTypeSessionWatcher");
+        CALLBACK.with(|slot| *slot.borrow_mut() = Some(callback));
+        unsafe {
+            let instance = GetModuleHandleW(std::ptr::null());
+            let class = WndClassW {
+                style: 0,
+                window_proc,
+                class_extra: 0,
+                window_extra: 0,
+                instance,
+                icon: std::ptr::null_mut(),
+                cursor: std::ptr::null_mut(),
+                background: std::ptr::null_mut(),
+                menu_name: std::ptr::null(),
+                class_name: class_name.as_ptr(),
+            };
+            if RegisterClassW(&class) == 0 {
+                return false;
+            }
+            // 消息窗口（HWND_MESSAGE）收不到广播的 WM_POWERBROADCAST，这里用不显示的顶层窗口
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                class_name.as_ptr(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                instance,
+                std::ptr::null_mut(),
+            );
+            if hwnd.is_null() {
+                return false;
+            }
+            // 登记失败（例如远程桌面服务未运行）时仍能收到休眠与唤醒
+            WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION);
+        }
+        true
+    }
+
+    /// 运行消息循环，直到线程收到 WM_QUIT
+    pub fn run() {
+        let mut msg = Msg {
+            hwnd: std::ptr::null_mut(),
+            message: 0,
+            wparam: 0,
+            lparam: 0,
+            time: 0,
+            x: 0,
+            y: 0,
+        };
+        unsafe {
+            while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                DispatchMessageW(&msg);
+            }
+        }
+    }
+}
+
+/// 隐藏窗口接收 WM_POWERBROADCAST（休眠/唤醒）与 WTSRegisterSessionNotification 的锁屏通知，
+/// 消息循环在独立线程上运行
+pub fn watch_session(on_event: std::sync::Arc<dyn Fn(super::SessionEvent) + Send + Sync>) -> bool {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let registered = session::register(on_event);
+        let _ = ready_tx.send(registered);
+        if registered {
+            session::run();
+        }
+    });
+    ready_rx.recv().unwrap_or(false)
+}
+
+/// 未安装（没有注册开始菜单快捷方式）时借用 PowerShell 的 AppUserModelID 显示通知
+const POWERSHELL_APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// 按钮的点击只能由显示通知的进程收到，PowerShell 进程一直等到用户点击、关闭或超时
+const ACTION_TOAST_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
+$xml = New-Object Windows.Data.Xml.Dom.XmlDocument
+$xml.LoadXml($env:GHOSTTYPE_TOAST_XML)
+$toast = New-Object Windows.UI.Notifications.ToastNotification $xml
+Register-ObjectEvent -InputObject $toast -EventName Activated -SourceIdentifier activated | Out-Null
+Register-ObjectEvent -InputObject $toast -EventName Dismissed -SourceIdentifier dismissed | Out-Null
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:GHOSTTYPE_TOAST_APP_ID).Show($toast)
+$event = Wait-Event -Timeout ([int]$env:GHOSTTYPE_TOAST_TIMEOUT)
+if ($event -and $event.SourceIdentifier -eq 'activated') {
+    [Console]::Out.Write(([Windows.UI.Notifications.ToastActivatedEventArgs]$event.SourceArgs[1]).Arguments)
+}
+"#;
+
+/// 显示带按钮的 Toast 通知（`xml` 为完整的 toast 内容），阻塞到用户操作或超时，返回所点按钮的 `arguments`
+pub fn action_toast(app_id: Option<&str>, xml: &str, timeout: std::time::Duration) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-ExecutionPolicy",
+            "Bypass",
+            "-Command",
+            ACTION_TOAST_SCRIPT,
+        ])
+        .env("GHOSTTYPE_TOAST_XML", xml)
+        .env("GHOSTTYPE_TOAST_APP_ID", app_id.unwrap_or(POWERSHELL_APP_ID))
+        .env("GHOSTTYPE_TOAST_TIMEOUT", timeout.as_secs().max(1).to_string())
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    let clicked = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !clicked.is_empty()).then_some(clicked)
+}
+
+/// Windows 不公开降频状态，只判断是否使用电池
+pub fn power_supply() -> super::PowerSupply {
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+    // ACLineStatus：0 为电池，1 为外接电源，255 为未知
+    const AC_OFFLINE: u8 = 0;
+    let mut status = SystemPowerStatus::default();
+    let ok = unsafe { GetSystemPowerStatus(&mut status) } != 0;
+    super::PowerSupply {
+        on_battery: ok && status.ac_line_status == AC_OFFLINE,
+        thermal_pressure: false,
+    }
+}
+
+/// 内存来自 `GlobalMemoryStatusEx`；DirectML 随系统安装在 System32，NVIDIA 显卡由 `nvidia-smi` 报告
+pub fn hardware() -> super::Hardware {
+    #[repr(C)]
+    struct MemoryStatusEx {
+        length: u32,
+        memory_load: u32,
+        total_phys: u64,
+        avail_phys: u64,
+        total_page_file: u64,
+        avail_page_file: u64,
+        total_virtual: u64,
+        avail_virtual: u64,
+        avail_extended_virtual: u64,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalMemoryStatusEx(status: *mut MemoryStatusEx) -> i32;
+    }
+    let mut status = MemoryStatusEx {
+        length: std::mem::size_of::<MemoryStatusEx>() as u32,
+        memory_load: 0,
+        total_phys: 0,
+        avail_phys: 0,
+        total_page_file: 0,
+        avail_page_file: 0,
+        total_virtual: 0,
+        avail_virtual: 0,
+        avail_extended_virtual: 0,
+    };
+    let ok = unsafe { GlobalMemoryStatusEx(&mut status) } != 0;
+    let directml = std::env::var_os("SystemRoot")
+        .map(|root| std::path::Path::new(&root).join("System32").join("DirectML.dll"))
+        .is_some_and(|dll| dll.exists());
+    let nvidia = super::nvidia::gpu();
+    super::Hardware {
+        memory_mb: ok.then_some(status.total_phys / (1024 * 1024)),
+        directml,
+        cuda: nvidia.is_some(),
+        gpu_name: nvidia.as_ref().map(|(name, _)| name.clone()),
+        gpu_memory_mb: nvidia.and_then(|(_, memory)| memory),
+        ..super::Hardware::default()
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(feature = "plugins")]
+mod wasm;
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// 插件：插件目录中的 WASM 模块，在识别、校正与输入之间改写文字（例如医学术语、法律引文的规范化）。
+/// 按文件名顺序依次执行；单个插件出错时跳过它，不影响输出
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PluginConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 插件目录；为空时使用程序目录下的 `plugins`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// 不加载的插件（文件名，不含 `.wasm`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
+}
+
+/// 插件可以处理的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginStage {
+    /// 识别结果：改写后的文字交给个人词典、规则与 LLM 校正
+    Asr,
+    /// 输入前：规则之后、输入到焦点（或交给输出端）之前，LLM 校正结果同样经过这里
+    Output,
+}
+
+impl PluginStage {
+    /// 插件导出的函数名
+    pub fn export_name(self) -> &'static str {
+        match self {
+            PluginStage::Asr => "transform_asr",
+            PluginStage::Output => "transform_output",
+        }
+    }
+}
+
+pub trait Plugin: Send {
+    fn name(&self) -> &str;
+
+    /// 插件是否导出了这个阶段的函数
+    fn handles(&self, stage: PluginStage) -> bool;
+
+    fn transform(&mut self, stage: PluginStage, text: &str) -> anyhow::Result<String>;
+}
+
+/// 已加载的插件，按执行顺序排列
+#[derive(Default)]
+pub struct PluginSet {
+    plugins: Vec<Mutex<Box<dyn Plugin>>>,
+}
+
+impl PluginSet {
+    /// 加载插件目录中的全部插件；无法加载的插件只记录警告
+    pub fn load(config: &PluginConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let dir = resolve_plugin_dir(config);
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wasm")))
+                .collect(),
+            Err(err) => {
+                warn!(
+                    target: "plugin",
+                    dir = %dir.display(),
+                    error = %err,
+                    "插件目录无法读取 | Plugin directory unreadable"
+                );
+                return Self::default();
+            }
+        };
+        paths.sort();
+
+        let mut plugins = Vec::new();
+        for path in paths {
+            let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            if config.disabled.iter().any(|disabled| disabled.trim() == name) {
+                continue;
+            }
+            match load_plugin(&name, &path) {
+                Ok(plugin) => {
+                    info!(
+                        target: "plugin",
+                        plugin = %name,
+                        asr = plugin.handles(PluginStage::Asr),
+                        output = plugin.handles(PluginStage::Output),
+                        "插件已加载 | Plugin loaded"
+                    );
+                    plugins.push(Mutex::new(plugin));
+                }
+                Err(err) => warn!(
+                    target: "plugin",
+                    plugin = %name,
+                    error = %format!("{err:#}"),
+                    "插件加载失败，已跳过 | Plugin failed to load, skipped"
+                ),
+            }
+        }
+        Self { plugins }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// 依次交给处理这个阶段的插件；出错的插件跳过，文字保持不变
+    pub fn apply(&self, stage: PluginStage, text: &str) -> String {
+        let mut text = text.to_string();
+        for plugin in &self.plugins {
+            let mut plugin = plugin.lock().expect("plugin lock");
+            if !plugin.handles(stage) {
+                continue;
+            }
+            match plugin.transform(stage, &text) {
+                Ok(transformed) => text = transformed,
+                Err(err) => warn!(
+                    target: "plugin",
+                    plugin = plugin.name(),
+                    stage = stage.export_name(),
+                    error = %format!("{err:#}"),
+                    "插件处理失败，已跳过 | Plugin failed, skipped"
+                ),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(name: &str, path: &Path) -> anyhow::Result<Box<dyn Plugin>> {
+    Ok(Box::new(wasm::WasmPlugin::load(name, path)?))
+}
+
+#[cfg(not(feature = "plugins"))]
+fn load_plugin(_name: &str, _path: &Path) -> anyhow::Result<Box<dyn Plugin>> {
+    anyhow::bail!("此版本未包含插件支持（需以 --features plugins 构建）")
+}
+
+fn resolve_plugin_dir(config: &PluginConfig) -> PathBuf {
+    if let Some(dir) = config.dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("plugins")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl Plugin for Upper {
+        fn name(&self) -> &str {
+            "upper"
+        }
+
+        fn handles(&self, stage: PluginStage) -> bool {
+            stage == PluginStage::Output
+        }
+
+        fn transform(&mut self, _stage: PluginStage, text: &str) -> anyhow::Result<String> {
+            Ok(text.to_uppercase())
+        }
+    }
+
+    struct Broken;
+
+    impl Plugin for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn handles(&self, _stage: PluginStage) -> bool {
+            true
+        }
+
+        fn transform(&mut self, _stage: PluginStage, _text: &str) -> anyhow::Result<String> {
+            anyhow::bail!("out of fuel")
+        }
+    }
+
+    #[test]
+    fn plugins_run_in_order_and_skip_failures() {
+        let plugins: Vec<Box<dyn Plugin>> = vec![Box::new(Broken), Box::new(Upper)];
+        let set = PluginSet {
+            plugins: plugins.into_iter().map(Mutex::new).collect(),
+        };
+        assert_eq!(set.apply(PluginStage::Output, "bp 120/80"), "BP 120/80");
+        // 没有插件处理 ASR 阶段，出错的插件不改变文字
+        assert_eq!(set.apply(PluginStage::Asr, "bp 120/80"), "bp 120/80");
+
+        assert!(PluginSet::load(&PluginConfig::default()).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::Path;
+
+use anyhow::{bail, Context as _};
+use wasmi::{Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+use super::{Plugin, PluginStage};
+
+/// 每次调用可执行的指令上限，防止插件死循环卡住输出。插件在会话的异步任务里同步执行，
+/// 解释执行这么多指令只需几十毫秒，足够处理一段听写文字
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// 插件返回文字的长度上限
+const MAX_OUTPUT_BYTES: usize = 1 << 20;
+
+/// WASM 插件（核心模块，不提供任何导入）。约定的导出：
+///
+/// - `memory`
+/// - `alloc(len: i32) -> i32`：分配一段内存存放输入
+/// - `transform_asr` / `transform_output`（至少一个）`(ptr: i32, len: i32) -> i64`：
+///   输入、输出均为 UTF-8，返回 `(ptr << 32) | len`；返回负数表示不修改
+/// - `dealloc(ptr: i32, len: i32)`（可选）：用完输入与输出后释放
+pub struct WasmPlugin {
+    name: String,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: Option<TypedFunc<(i32, i32), ()>>,
+    transform_asr: Option<TypedFunc<(i32, i32), i64>>,
+    transform_output: Option<TypedFunc<(i32, i32), i64>>,
+}
+
+impl WasmPlugin {
+    pub fn load(name: &str, path: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("读取 {}", path.display()))?;
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &bytes[..]).context("解析 WASM 模块")?;
+        let mut store = Store::new(&engine, ());
+        let linker = Linker::<()>::new(&engine);
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .context("实例化插件（插件不能有导入）")?;
+
+        let memory = instance.get_memory(&store, "memory").context("插件没有导出 memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").context("插件没有导出 alloc")?;
+        let dealloc = instance.get_typed_func::<(i32, i32), ()>(&store, "dealloc").ok();
+        let transform = |stage: PluginStage| stage_func(&instance, &store, stage);
+        let transform_asr = transform(PluginStage::Asr);
+        let transform_output = transform(PluginStage::Output);
+        if transform_asr.is_none() && transform_output.is_none() {
+            bail!("插件没有导出 transform_asr 或 transform_output");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            store,
+            memory,
+            alloc,
+            dealloc,
+            transform_asr,
+            transform_output,
+        })
+    }
+
+    /// 解出 `(ptr << 32) | len` 并读出结果；负数表示不修改
+    fn read_output(&self, packed: i64) -> anyhow::Result<Option<(i32, Vec<u8>)>> {
+        if packed < 0 {
+            return Ok(None);
+        }
+        let out_ptr = (packed >> 32) as i32;
+        let out_len = packed as u32 as usize;
+        if out_len > MAX_OUTPUT_BYTES {
+            bail!("插件返回的文字过长: {out_len} 字节");
+        }
+        let mut buf = vec![0u8; out_len];
+        self.memory
+            .read(&self.store, out_ptr as u32 as usize, &mut buf)
+            .context("读取插件内存")?;
+        Ok(Some((out_ptr, buf)))
+    }
+
+    fn release(&mut self, ptr: i32, len: i32) {
+        if let Some(dealloc) = &self.dealloc {
+            let _ = dealloc.call(&mut self.store, (ptr, len));
+        }
+    }
+}
+
+fn stage_func(instance: &Instance, store: &Store<()>, stage: PluginStage) -> Option<TypedFunc<(i32, i32), i64>> {
+    instance.get_typed_func::<(i32, i32), i64>(store, stage.export_name()).ok()
+}
+
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handles(&self, stage: PluginStage) -> bool {
+        match stage {
+            PluginStage::Asr => self.transform_asr.is_some(),
+            PluginStage::Output => self.transform_output.is_some(),
+        }
+    }
+
+    fn transform(&mut self, stage: PluginStage, text: &str) -> anyhow::Result<String> {
+        let func = match stage {
+            PluginStage::Asr => self.transform_asr.clone(),
+            PluginStage::Output => self.transform_output.clone(),
+        };
+        let Some(func) = func else {
+            return Ok(text.to_string());
+        };
+        self.store.set_fuel(FUEL_PER_CALL)?;
+
+        let len = i32::try_from(text.len()).context("文字过长")?;
+        let ptr = self.alloc.call(&mut self.store, len).context("alloc")?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, text.as_bytes())
+            .context("写入插件内存")?;
+        let output = func
+            .call(&mut self.store, (ptr, len))
+            .context(stage.export_name())
+            .and_then(|packed| self.read_output(packed));
+        // 插件可能原地改写并返回输入的那段内存，读完结果后再释放
+        if let Ok(Some((out_ptr, buf))) = &output {
+            if *out_ptr != ptr {
+                self.release(*out_ptr, buf.len() as i32);
+            }
+        }
+        self.release(ptr, len);
+        match output? {
+            Some((_, buf)) => String::from_utf8(buf).context("插件返回的不是 UTF-8"),
+            None => Ok(text.to_string()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::*;
+
+    /// 导出 memory 与只增不减的 alloc，`transform_output` 的函数体由各用例提供
+    fn load_wat(name: &str, transform_body: &str) -> WasmPlugin {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "changed")
+                (global $next (mut i32) (i32.const 1024))
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $next))
+                    (global.set $next (i32.add (global.get $next) (local.get $len)))
+                    (local.get $ptr))
+                (func (export "transform_output") (param $ptr i32) (param $len i32) (result i64)
+                    {transform_body}))"#
+        );
+        let path = std::env::temp_dir().join(format!("ghosttype-plugin-{}-{name}.wat", std::process::id()));
+        std::fs::write(&path, wat).expect("write plugin");
+        let plugin = WasmPlugin::load(name, &path);
+        let _ = std::fs::remove_file(&path);
+        plugin.expect("load plugin")
+    }
+
+    #[test]
+    fn plugin_output_is_read_back() {
+        let mut identity = load_wat(
+            "identity",
+            "(i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))",
+        );
+        assert!(identity.handles(PluginStage::Output));
+        assert!(!identity.handles(PluginStage::Asr));
+        assert_eq!(
+            identity
+                .transform(PluginStage::Output, "血压 120/80")
+                .expect("identity"),
+            "血压 120/80"
+        );
+        // 没有导出的阶段原样返回
+        assert_eq!(identity.transform(PluginStage::Asr, "bp").expect("skipped stage"), "bp");
+
+        // 返回数据段中的 "changed"：ptr = 16，len = 7
+        let mut changing = load_wat("changing", "(i64.const 0x10_0000_0007)");
+        assert_eq!(
+            changing.transform(PluginStage::Output, "bp 120/80").expect("changing"),
+            "changed"
+        );
+
+        let mut unchanged = load_wat("negative", "(i64.const -1)");
+        assert_eq!(
+            unchanged.transform(PluginStage::Output, "bp 120/80").expect("negative"),
+            "bp 120/80"
+        );
+    }
+
+    #[test]
+    fn misbehaving_plugins_fail_instead_of_hanging() {
+        // 指向一页内存（64 KiB）之外
+        let mut out_of_bounds = load_wat("out-of-bounds", "(i64.const 0x10_0000_0000_0004)");
+        assert!(out_of_bounds.transform(PluginStage::Output, "bp").is_err());
+
+        let mut endless = load_wat("endless", "(loop $spin (br $spin)) (unreachable)");
+        let started = std::time::Instant::now();
+        assert!(endless.transform(PluginStage::Output, "bp").is_err());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        // 燃料在每次调用前重新装满，出错后插件仍可再次调用（仍然出错而不是卡住）
+        assert!(endless.transform(PluginStage::Output, "bp").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{debug, info, warn};
+
+use crate::platform::{self, PowerSupply, SessionEvent};
+
+/// 没有系统通知可用时检查时间跳变的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 电源状态的刷新间隔（macOS 需要启动 pmset，不必每次检查都查）
+const SUPPLY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 两次检查之间的墙上时间超出间隔这么多，视为系统休眠过（线程在休眠期间不会运行）
+const SUSPEND_GAP: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// 从休眠中唤醒
+    Resumed { slept: Duration },
+    /// 会话锁屏
+    Locked,
+    /// 会话解锁
+    Unlocked,
+}
+
+static SUPPLY: Mutex<PowerSupply> = Mutex::new(PowerSupply {
+    on_battery: false,
+    thermal_pressure: false,
+});
+
+/// 最近一次检测到的电源状态；检测线程未启动时按不受限处理
+pub fn supply() -> PowerSupply {
+    *SUPPLY.lock().expect("power supply lock")
+}
+
+fn refresh_supply() {
+    let current = platform::power_supply();
+    let previous = std::mem::replace(&mut *SUPPLY.lock().expect("power supply lock"), current);
+    if previous != current {
+        info!(
+            target: "power",
+            on_battery = current.on_battery,
+            thermal_pressure = current.thermal_pressure,
+            "电源状态变化 | Power state changed"
+        );
+    }
+}
+
+/// 把系统通知整理成会话事件：唤醒只在收到过休眠通知后报告一次，锁屏状态没有变化的通知忽略
+/// （Linux 的 `Lock` 信号与 `LockedHint` 属性会各来一次）
+#[derive(Debug, Default)]
+struct SessionTracker {
+    suspended_at: Option<SystemTime>,
+    locked: bool,
+}
+
+impl SessionTracker {
+    fn handle(&mut self, event: SessionEvent, now: SystemTime) -> Option<PowerEvent> {
+        match event {
+            SessionEvent::Suspending => {
+                self.suspended_at.get_or_insert(now);
+                None
+            }
+            SessionEvent::Resumed => {
+                let slept = now.duration_since(self.suspended_at.take()?).unwrap_or_default();
+                Some(PowerEvent::Resumed { slept })
+            }
+            SessionEvent::Locked => (!std::mem::replace(&mut self.locked, true)).then_some(PowerEvent::Locked),
+            SessionEvent::Unlocked => std::mem::replace(&mut self.locked, false).then_some(PowerEvent::Unlocked),
+        }
+    }
+}
+
+fn log_event(event: &PowerEvent) {
+    match event {
+        PowerEvent::Resumed { slept } => info!(
+            target: "power",
+            slept_s = slept.as_secs(),
+            "系统已从休眠中唤醒 | System resumed from sleep"
+        ),
+        PowerEvent::Locked => info!(target: "power", "会话已锁定 | Session locked"),
+        PowerEvent::Unlocked => info!(target: "power", "会话已解锁 | Session unlocked"),
+    }
+}
+
+/// 订阅系统的休眠/唤醒与锁屏通知，电源状态在独立线程上定期刷新。平台没有通知可用时
+/// 退回到检查时间跳变：休眠只能在唤醒后发现，锁屏则检测不到。录音与连接仍停留在休眠前的状态，
+/// 由调用方清理
+pub fn spawn_watcher(on_event: impl Fn(PowerEvent) + Send + Sync + 'static) {
+    let on_event = Arc::new(on_event);
+    let tracker = Mutex::new(SessionTracker::default());
+    let notified = platform::watch_session({
+        let on_event = on_event.clone();
+        Arc::new(move |event: SessionEvent| {
+            if event == SessionEvent::Suspending {
+                debug!(target: "power", "系统即将休眠 | System is going to sleep");
+            }
+            let event = tracker
+                .lock()
+                .expect("session tracker lock")
+                .handle(event, SystemTime::now());
+            if let Some(event) = event {
+                log_event(&event);
+                on_event(event);
+            }
+        })
+    });
+    if !notified {
+        warn!(
+            target: "power",
+            "系统休眠/锁屏通知不可用，改为检查时间跳变 | Sleep/lock notifications unavailable, falling back to clock checks"
+        );
+    }
+    std::thread::spawn(move || {
+        let interval = if notified { SUPPLY_INTERVAL } else { POLL_INTERVAL };
+        let mut last_tick = SystemTime::now();
+        refresh_supply();
+        let mut supply_checked = Instant::now();
+        loop {
+            std::thread::sleep(interval);
+            if supply_checked.elapsed() >= SUPPLY_INTERVAL {
+                refresh_supply();
+                supply_checked = Instant::now();
+            }
+            if notified {
+                continue;
+            }
+            let now = SystemTime::now();
+            let elapsed = now.duration_since(last_tick).unwrap_or_default();
+            last_tick = now;
+            if let Some(slept) = suspended_for(elapsed, POLL_INTERVAL) {
+                let event = PowerEvent::Resumed { slept };
+                log_event(&event);
+                on_event(event);
+            }
+        }
+    });
+}
+
+/// 本次检查距上次的墙上时间明显长于预期间隔时，返回估计的休眠时长
+fn suspended_for(elapsed: Duration, interval: Duration) -> Option<Duration> {
+    let slept = elapsed.saturating_sub(interval);
+    (slept >= SUSPEND_GAP).then_some(slept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_large_clock_gaps_count_as_sleep() {
+        let interval = Duration::from_secs(2);
+        assert_eq!(suspended_for(Duration::from_secs(2), interval), None);
+        assert_eq!(suspended_for(Duration::from_secs(10), interval), None);
+        assert_eq!(
+            suspended_for(Duration::from_secs(602), interval),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(suspended_for(Duration::ZERO, interval), None);
+    }
+
+    #[test]
+    fn session_notifications_are_deduplicated() {
+        let mut tracker = SessionTracker::default();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        // 没有休眠通知的唤醒（Windows 会发两次）不报告
+        assert_eq!(tracker.handle(SessionEvent::Resumed, start), None);
+        assert_eq!(tracker.handle(SessionEvent::Suspending, start), None);
+        assert_eq!(
+            tracker.handle(SessionEvent::Resumed, start + Duration::from_secs(600)),
+            Some(PowerEvent::Resumed {
+                slept: Duration::from_secs(600)
+            })
+        );
+        assert_eq!(
+            tracker.handle(SessionEvent::Resumed, start + Duration::from_secs(601)),
+            None
+        );
+
+        assert_eq!(tracker.handle(SessionEvent::Unlocked, start), None);
+        assert_eq!(tracker.handle(SessionEvent::Locked, start), Some(PowerEvent::Locked));
+        assert_eq!(tracker.handle(SessionEvent::Locked, start), None);
+        assert_eq!(
+            tracker.handle(SessionEvent::Unlocked, start),
+            Some(PowerEvent::Unlocked)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 隐私模式：日志与会话历史中不保留识别文字和窗口标题，只记录长度与哈希
+/// （相同内容的哈希相同，便于排查时对照）。输出端（文件、webhook）与界面不受影响
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PrivacyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 启动和保存配置时调用；之后写入的日志与历史按新设置处理
+pub fn install(config: &PrivacyConfig) {
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// 隐私模式下把文字换成 `[redacted len=<字数> sha256=<前 8 位>]`，否则原样返回；空文字保持为空
+pub fn redact(text: &str) -> String {
+    if is_enabled() {
+        redacted(text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn redacted(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let digest = Sha256::digest(text.as_bytes());
+    let hash: String = digest[..4].iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("[redacted len={} sha256={hash}]", text.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_keeps_length_and_hash_only() {
+        assert_eq!(redacted(""), "");
+        let text = redacted("患者血压 120/80");
+        assert!(text.starts_with("[redacted len=11 sha256="), "{text}");
+        assert!(!text.contains("血压"));
+        assert_eq!(text, redacted("患者血压 120/80"));
+        assert_ne!(text, redacted("患者血压 130/80"));
+    }
+}
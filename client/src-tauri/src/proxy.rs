@@ -0,0 +1,433 @@
+use std::net::IpAddr;
+use std::sync::{OnceLock, RwLock};
+
+use anyhow::Context as _;
+use base64::Engine as _;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::debug;
+
+use crate::tls;
+
+/// 出站代理设置，LLM、远程 ASR 与更新检查共用
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProxyConfig {
+    /// 代理地址：`http://host:port`、`socks5://host:port`（`socks5h://` 由代理解析域名），可带 `user:pass@`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// 未填写 `url` 时读取 HTTPS_PROXY / ALL_PROXY / HTTP_PROXY 与 NO_PROXY 环境变量
+    #[serde(default = "default_use_env")]
+    pub use_env: bool,
+    /// 直连的主机：`example.com` 同时匹配其子域名，`*` 表示全部直连；本机地址始终直连
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            use_env: true,
+            no_proxy: Vec::new(),
+        }
+    }
+}
+
+fn default_use_env() -> bool {
+    true
+}
+
+static CURRENT: OnceLock<RwLock<ProxyConfig>> = OnceLock::new();
+
+fn current_lock() -> &'static RwLock<ProxyConfig> {
+    CURRENT.get_or_init(|| RwLock::new(ProxyConfig::default()))
+}
+
+/// 启动和保存配置时调用；之后新建的连接使用新设置
+pub fn install(config: ProxyConfig) {
+    *current_lock().write().expect("proxy config lock") = config;
+}
+
+fn current() -> ProxyConfig {
+    current_lock().read().expect("proxy config lock").clone()
+}
+
+/// 为 `scheme://host` 选择代理地址；返回 `None` 表示直连
+fn select(config: &ProxyConfig, env: impl Fn(&str) -> Option<String>, scheme: &str, host: &str) -> Option<String> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if is_loopback(host) {
+        return None;
+    }
+
+    let configured = config.url.as_deref().map(str::trim).filter(|v| !v.is_empty());
+    let env_lookup = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| env(name).or_else(|| env(&name.to_lowercase())))
+            .filter(|v| !v.trim().is_empty())
+    };
+
+    let mut no_proxy = config.no_proxy.clone();
+    let proxy = match configured {
+        Some(url) => url.to_string(),
+        None if config.use_env => {
+            let names: &[&str] = match scheme {
+                "https" | "wss" => &["HTTPS_PROXY", "ALL_PROXY"],
+                _ => &["HTTP_PROXY", "ALL_PROXY"],
+            };
+            let url = env_lookup(names)?;
+            if let Some(list) = env_lookup(&["NO_PROXY"]) {
+                no_proxy.extend(list.split(',').map(str::to_string));
+            }
+            url
+        }
+        None => return None,
+    };
+
+    if no_proxy.iter().any(|pattern| no_proxy_matches(pattern, host)) {
+        return None;
+    }
+    Some(proxy)
+}
+
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false)
+}
+
+fn no_proxy_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim();
+    if pattern == "*" {
+        return true;
+    }
+    // 去掉前导 `.`/`*.` 与端口，`example.com` 与 `.example.com` 等价
+    let pattern = pattern.trim_start_matches("*.").trim_start_matches('.');
+    let pattern = match pattern.rsplit_once(':') {
+        Some((name, port)) if !name.contains(':') && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => pattern,
+    };
+    if pattern.is_empty() {
+        return false;
+    }
+
+    let host = host.to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+    host == pattern || host.ends_with(&format!(".{pattern}"))
+}
+
+/// reqwest 客户端构造器：按当前代理设置逐个请求选择代理
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    let proxy = reqwest::Proxy::custom(|url| {
+        let host = url.host_str()?;
+        select(&current(), |name| std::env::var(name).ok(), url.scheme(), host)
+    });
+    reqwest::Client::builder().no_proxy().proxy(proxy)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProxyKind {
+    Http,
+    /// `remote_dns` 为 true 时（socks5h）把域名交给代理解析
+    Socks5 { remote_dns: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProxyTarget {
+    kind: ProxyKind,
+    host: String,
+    port: u16,
+    auth: Option<(String, String)>,
+}
+
+fn parse_proxy_url(raw: &str) -> anyhow::Result<ProxyTarget> {
+    // 允许省略 scheme，与 curl 一致按 HTTP 代理处理
+    let raw = raw.trim();
+    let url = if raw.contains("://") {
+        Url::parse(raw)
+    } else {
+        Url::parse(&format!("http://{raw}"))
+    }
+    .with_context(|| format!("invalid proxy url: {raw}"))?;
+
+    let (kind, default_port) = match url.scheme() {
+        "http" => (ProxyKind::Http, 80),
+        "socks5" => (ProxyKind::Socks5 { remote_dns: false }, 1080),
+        "socks5h" => (ProxyKind::Socks5 { remote_dns: true }, 1080),
+        other => anyhow::bail!("unsupported proxy scheme: {other}"),
+    };
+    let host = url
+        .host_str()
+        .context("proxy url missing host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let auth = (!url.username().is_empty()).then(|| {
+        (
+            url.username().to_string(),
+            url.password().unwrap_or_default().to_string(),
+        )
+    });
+
+    Ok(ProxyTarget {
+        kind,
+        host,
+        port: url.port().unwrap_or(default_port),
+        auth,
+    })
+}
+
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 建立 WebSocket 连接，需要时经由 HTTP CONNECT 或 SOCKS5 代理；`wss` 按 `tls.rs` 的设置握手
+pub async fn connect_websocket(endpoint: &str) -> anyhow::Result<WsStream> {
+    let request = endpoint.into_client_request().context("invalid websocket endpoint")?;
+    let uri = request.uri();
+    let scheme = uri.scheme_str().unwrap_or("ws").to_string();
+    let host = uri
+        .host()
+        .context("websocket endpoint missing host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = uri.port_u16().unwrap_or(if scheme == "wss" { 443 } else { 80 });
+
+    let stream = match select(&current(), |name| std::env::var(name).ok(), &scheme, &host) {
+        Some(proxy) => {
+            let proxy = parse_proxy_url(&proxy)?;
+            debug!(
+                target: "network",
+                proxy = %format!("{}:{}", proxy.host, proxy.port),
+                endpoint = %endpoint,
+                "经代理连接 | Connecting via proxy"
+            );
+            connect_via_proxy(&proxy, &host, port).await?
+        }
+        None => TcpStream::connect((host.as_str(), port))
+            .await
+            .with_context(|| format!("connect {host}:{port}"))?,
+    };
+    let _ = stream.set_nodelay(true);
+
+    // 只有 wss 需要 TLS 设置，证书配置有误时不影响 ws 连接
+    let connector = if scheme == "wss" { tls::connector()? } else { None };
+    let (ws, _) = tokio_tungstenite::client_async_tls_with_config(request, stream, None, connector)
+        .await
+        .context("websocket handshake")?;
+    Ok(ws)
+}
+
+async fn connect_via_proxy(proxy: &ProxyTarget, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port))
+        .await
+        .with_context(|| format!("connect proxy {}:{}", proxy.host, proxy.port))?;
+    match proxy.kind {
+        ProxyKind::Http => http_connect(&mut stream, proxy, host, port).await?,
+        ProxyKind::Socks5 { remote_dns } => socks5_connect(&mut stream, proxy, host, port, remote_dns).await?,
+    }
+    Ok(stream)
+}
+
+fn authority(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+async fn http_connect(stream: &mut TcpStream, proxy: &ProxyTarget, host: &str, port: u16) -> anyhow::Result<()> {
+    let authority = authority(host, port);
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some((user, pass)) = &proxy.auth {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{user}:{pass}"));
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await.context("send CONNECT")?;
+
+    // 逐字节读到响应头结束，避免吞掉隧道里的后续数据
+    let mut head = Vec::with_capacity(256);
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= 8 * 1024 {
+            anyhow::bail!("proxy CONNECT response too large");
+        }
+        let n = stream.read(&mut byte).await.context("read CONNECT response")?;
+        if n == 0 {
+            anyhow::bail!("proxy closed connection during CONNECT");
+        }
+        head.push(byte[0]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).and_then(|v| v.parse::<u16>().ok());
+    match status {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        _ => anyhow::bail!("proxy CONNECT failed: {status_line}"),
+    }
+}
+
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    proxy: &ProxyTarget,
+    host: &str,
+    port: u16,
+    remote_dns: bool,
+) -> anyhow::Result<()> {
+    let methods: &[u8] = if proxy.auth.is_some() { &[0x00, 0x02] } else { &[0x00] };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await.context("send SOCKS5 greeting")?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await.context("read SOCKS5 greeting")?;
+    match reply {
+        [0x05, 0x00] => {}
+        [0x05, 0x02] => {
+            let (user, pass) = proxy.auth.as_ref().context("SOCKS5 proxy requires authentication")?;
+            if user.len() > 255 || pass.len() > 255 {
+                anyhow::bail!("SOCKS5 credentials too long");
+            }
+            let mut auth = vec![0x01, user.len() as u8];
+            auth.extend_from_slice(user.as_bytes());
+            auth.push(pass.len() as u8);
+            auth.extend_from_slice(pass.as_bytes());
+            stream.write_all(&auth).await.context("send SOCKS5 auth")?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await.context("read SOCKS5 auth")?;
+            if status[1] != 0x00 {
+                anyhow::bail!("SOCKS5 authentication rejected");
+            }
+        }
+        _ => anyhow::bail!("SOCKS5 proxy rejected auth methods"),
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    let ip = match host.parse::<IpAddr>() {
+        Ok(ip) => Some(ip),
+        Err(_) if remote_dns => None,
+        Err(_) => Some(
+            tokio::net::lookup_host((host, port))
+                .await
+                .with_context(|| format!("resolve {host}"))?
+                .next()
+                .with_context(|| format!("resolve {host}: no address"))?
+                .ip(),
+        ),
+    };
+    match ip {
+        Some(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Some(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        None => {
+            if host.len() > 255 {
+                anyhow::bail!("host name too long for SOCKS5");
+            }
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.context("send SOCKS5 connect")?;
+
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await.context("read SOCKS5 connect")?;
+    if head[1] != 0x00 {
+        anyhow::bail!("SOCKS5 connect failed: {}", socks5_reply_message(head[1]));
+    }
+    // 跳过代理返回的绑定地址与端口
+    let addr_len = match head[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await.context("read SOCKS5 bound address")?;
+            len[0] as usize
+        }
+        other => anyhow::bail!("SOCKS5 invalid address type: {other}"),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await.context("read SOCKS5 bound address")?;
+    Ok(())
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn select_prefers_config_then_env_and_honors_no_proxy() {
+        let env = env_of(&[
+            ("HTTPS_PROXY", "http://corp:3128"),
+            ("all_proxy", "socks5://corp:1080"),
+            ("NO_PROXY", "internal.example.com"),
+        ]);
+        let config = ProxyConfig::default();
+        assert_eq!(select(&config, &env, "wss", "asr.example.com").as_deref(), Some("http://corp:3128"));
+        assert_eq!(select(&config, &env, "ws", "asr.example.com").as_deref(), Some("socks5://corp:1080"));
+        assert_eq!(select(&config, &env, "https", "api.internal.example.com"), None);
+        assert_eq!(select(&config, &env, "https", "127.0.0.1"), None);
+        assert_eq!(select(&config, &env, "ws", "[::1]"), None);
+
+        let config = ProxyConfig {
+            url: Some("socks5h://me:pw@gw:1080".to_string()),
+            use_env: true,
+            no_proxy: vec![".lan:8000".to_string()],
+        };
+        assert_eq!(select(&config, &env, "https", "api.openai.com").as_deref(), Some("socks5h://me:pw@gw:1080"));
+        assert_eq!(select(&config, &env, "ws", "asr.lan"), None);
+
+        let config = ProxyConfig {
+            use_env: false,
+            ..ProxyConfig::default()
+        };
+        assert_eq!(select(&config, &env, "https", "api.openai.com"), None);
+    }
+
+    #[test]
+    fn parse_proxy_url_supports_http_and_socks() {
+        let target = parse_proxy_url("socks5h://me:pw@gw:1081").expect("parse");
+        assert_eq!(target.kind, ProxyKind::Socks5 { remote_dns: true });
+        assert_eq!((target.host.as_str(), target.port), ("gw", 1081));
+        assert_eq!(target.auth, Some(("me".to_string(), "pw".to_string())));
+
+        let target = parse_proxy_url("corp.example.com:3128").expect("parse");
+        assert_eq!(target.kind, ProxyKind::Http);
+        assert_eq!(target.port, 3128);
+        assert_eq!(target.auth, None);
+
+        assert!(parse_proxy_url("ftp://gw:21").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::language::{self, PUNCTUATION_PAIRS};
+
+/// 中英混排的标点与空格：输出前（含 LLM 校正后）按规则统一标点宽度与中英文之间的空格，不依赖语言检测
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PunctuationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub width: PunctuationWidth,
+    #[serde(default)]
+    pub cjk_latin_space: CjkLatinSpace,
+}
+
+/// 逗号、句号、问号、叹号、分号、冒号用全角还是半角
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PunctuationWidth {
+    /// 跟随所在分句：分句中有汉字用全角，否则用半角
+    #[default]
+    Clause,
+    Full,
+    Half,
+}
+
+/// 汉字与英文、数字之间的空格
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CjkLatinSpace {
+    #[default]
+    Add,
+    Remove,
+    Keep,
+}
+
+impl PunctuationConfig {
+    pub fn fix(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let text = normalize_width(text, self.width);
+        match self.cjk_latin_space {
+            CjkLatinSpace::Add => language::space_cjk_latin(&text),
+            CjkLatinSpace::Remove => join_cjk_latin(&text),
+            CjkLatinSpace::Keep => text,
+        }
+    }
+}
+
+fn normalize_width(text: &str, width: PunctuationWidth) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut clause_cjk = false;
+    let mut drop_space = false;
+    for (index, &c) in chars.iter().enumerate() {
+        if drop_space && c == ' ' {
+            continue;
+        }
+        drop_space = false;
+        let Some(&(half, full)) = PUNCTUATION_PAIRS.iter().find(|(half, full)| c == *half || c == *full) else {
+            if c == '\n' {
+                clause_cjk = false;
+            }
+            clause_cjk |= language::is_cjk_letter(c);
+            out.push(c);
+            continue;
+        };
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1).copied();
+        // 3.14、a.b、省略号保持原样，数字间的全角冒号（10：30）改成半角
+        let digits = previous.is_some_and(|p| p.is_ascii_digit()) && next.is_some_and(|n| n.is_ascii_digit());
+        let in_token = c == half
+            && previous.is_some_and(|p| p.is_ascii_alphanumeric())
+            && next.is_some_and(|n| n.is_ascii_alphanumeric());
+        let ellipsis = c == '.' && (previous == Some('.') || next == Some('.'));
+        if digits || in_token || ellipsis {
+            out.push(half);
+            continue;
+        }
+        let wide = match width {
+            PunctuationWidth::Clause => clause_cjk,
+            PunctuationWidth::Full => true,
+            PunctuationWidth::Half => false,
+        };
+        clause_cjk = false;
+        // 标点前不留空格；全角标点后也不留，半角标点后接文字时补一个
+        while out.ends_with(' ') {
+            out.pop();
+        }
+        if wide {
+            out.push(full);
+            drop_space = true;
+        } else {
+            out.push(half);
+            if next.is_some_and(char::is_alphanumeric) {
+                out.push(' ');
+            }
+        }
+    }
+    out
+}
+
+fn join_cjk_latin(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (index, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let previous = out.chars().next_back();
+            let next = chars[index + 1..].iter().copied().find(|n| *n != ' ');
+            let boundary = match (previous, next) {
+                (Some(p), Some(n)) => {
+                    (language::is_cjk_letter(p) && n.is_ascii_alphanumeric())
+                        || (p.is_ascii_alphanumeric() && language::is_cjk_letter(n))
+                }
+                _ => false,
+            };
+            if boundary {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn punctuation_and_spacing_follow_style_rules() {
+        let config = PunctuationConfig {
+            enabled: true,
+            ..PunctuationConfig::default()
+        };
+        assert_eq!(
+            config.fix("我们下周review一下PR, 然后merge."),
+            "我们下周 review 一下 PR，然后 merge。"
+        );
+        assert_eq!(config.fix("好的, thanks. 明天见."), "好的，thanks. 明天见。");
+        assert_eq!(config.fix("Hello，world。It is 10：30！"), "Hello, world. It is 10:30!");
+        assert_eq!(config.fix("版本3.14，等等..."), "版本 3.14，等等...");
+
+        let full = PunctuationConfig {
+            width: PunctuationWidth::Full,
+            cjk_latin_space: CjkLatinSpace::Keep,
+            ..config
+        };
+        assert_eq!(full.fix("OK, 好的"), "OK，好的");
+        let compact = PunctuationConfig {
+            width: PunctuationWidth::Half,
+            cjk_latin_space: CjkLatinSpace::Remove,
+            ..config
+        };
+        assert_eq!(compact.fix("我用 GitHub，好的。"), "我用GitHub, 好的.");
+
+        let disabled = PunctuationConfig::default();
+        assert_eq!(disabled.fix("我用GitHub, 好的."), "我用GitHub, 好的.");
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::input::InjectionStrategy;
+
+/// 模拟键盘输入后回读焦点文本框核对结果（死键、输入法可能让输入的文字走样），
+/// 不一致时删掉并改用剪贴板重新输入；按应用累计模拟输入的可靠度，用于之后自动选择注入方式
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct InjectionCheckConfig {
+    /// Windows 只能回读标准编辑框（Edit/RichEdit 类），浏览器、Electron、WPF、UWP 等的文本框不做校验，
+    /// 也不计入可靠度；Linux 暂不支持
+    #[serde(default)]
+    pub enabled: bool,
+    /// 可靠度低的应用此后直接用剪贴板输入（按应用覆盖中指定了 `injection` 的除外）
+    #[serde(default = "default_true")]
+    pub auto_strategy: bool,
+    /// 累计校验次数达到这个数后才按可靠度切换
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u32,
+    /// 模拟输入的成功率低于这个值（0~1）时改用剪贴板
+    #[serde(default = "default_min_reliability")]
+    pub min_reliability: f32,
+}
+
+impl Default for InjectionCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_strategy: default_true(),
+            min_samples: default_min_samples(),
+            min_reliability: default_min_reliability(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_samples() -> u32 {
+    5
+}
+
+fn default_min_reliability() -> f32 {
+    0.8
+}
+
+/// 一个应用中模拟键盘输入的校验结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppReliability {
+    pub verified: u32,
+    pub mismatched: u32,
+}
+
+impl AppReliability {
+    pub fn samples(&self) -> u32 {
+        self.verified + self.mismatched
+    }
+
+    /// 校验通过的比例；还没有校验过时为空
+    pub fn score(&self) -> Option<f32> {
+        (self.samples() > 0).then(|| self.verified as f32 / self.samples() as f32)
+    }
+}
+
+/// 回读的结果与输入的文字是否一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Insertion {
+    Verified,
+    /// 不一致；`typed` 为文本框中多出的字数，重新输入前按这个数退格
+    Mismatch {
+        typed: usize,
+    },
+}
+
+/// 回读是否已经稳定：文本框长出了整段文字，或者内容有变化且与上一次回读相同。
+/// 应用还在处理按键时读到的只是部分输入，不能拿来判断
+pub fn read_back_settled(before: &str, previous: Option<&str>, current: &str, text: &str) -> bool {
+    current.chars().count() >= before.chars().count() + text.chars().count()
+        || (current != before && previous == Some(current))
+}
+
+/// 比较输入前后的文本框内容：去掉前后相同的部分，剩下的就是这次输入的文字
+/// （有选中内容时会替换掉选区）。光标位置未知，插入点有多种可能时任一种对上即可
+pub fn check_insertion(before: &str, after: &str, text: &str) -> Insertion {
+    let before: Vec<char> = before.chars().collect();
+    let after: Vec<char> = after.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let prefix = before.iter().zip(&after).take_while(|(a, b)| a == b).count();
+    let max_suffix = before.len().min(after.len()) - prefix;
+    let suffix = before
+        .iter()
+        .rev()
+        .zip(after.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if after[prefix..after.len() - suffix] == text[..] {
+        return Insertion::Verified;
+    }
+    // 纯插入：在前后相同部分的重叠范围内找插入点
+    if after.len() == before.len() + text.len() {
+        let full_suffix = before
+            .iter()
+            .rev()
+            .zip(after.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let first = before.len().saturating_sub(full_suffix);
+        if (first..=prefix).any(|index| after[index..index + text.len()] == text[..]) {
+            return Insertion::Verified;
+        }
+    }
+    Insertion::Mismatch {
+        typed: after.len() - prefix - suffix,
+    }
+}
+
+struct Scores {
+    config: InjectionCheckConfig,
+    apps: BTreeMap<String, AppReliability>,
+}
+
+/// 各应用的模拟输入可靠度，保存在本地 JSON 文件中
+pub struct InjectionReliability {
+    path: Option<PathBuf>,
+    inner: Mutex<Scores>,
+}
+
+impl InjectionReliability {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let apps = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).context("parse injection reliability")?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err).context("read injection reliability"),
+        };
+        Ok(Self::with_apps(Some(path.to_path_buf()), apps))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_apps(None, BTreeMap::new())
+    }
+
+    /// 打开默认位置的记录；失败时从空记录开始，保证应用照常运行
+    pub fn open_default() -> Self {
+        let path = resolve_reliability_path();
+        match Self::open(&path) {
+            Ok(reliability) => reliability,
+            Err(err) => {
+                warn!(
+                    target: "input",
+                    path = %path.display(),
+                    error = %format!("{err:#}"),
+                    "注入可靠度读取失败，重新开始统计 | Injection reliability load failed, starting empty"
+                );
+                Self::in_memory()
+            }
+        }
+    }
+
+    fn with_apps(path: Option<PathBuf>, apps: BTreeMap<String, AppReliability>) -> Self {
+        Self {
+            path,
+            inner: Mutex::new(Scores {
+                config: InjectionCheckConfig::default(),
+                apps,
+            }),
+        }
+    }
+
+    pub fn configure(&self, config: &InjectionCheckConfig) {
+        self.inner.lock().expect("reliability lock").config = config.clone();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.lock().expect("reliability lock").config.enabled
+    }
+
+    /// 记录一次校验结果并保存
+    pub fn record(&self, app_name: &str, verified: bool) {
+        let app_name = app_name.trim();
+        if app_name.is_empty() {
+            return;
+        }
+        let apps = {
+            let mut inner = self.inner.lock().expect("reliability lock");
+            let entry = inner.apps.entry(app_name.to_string()).or_default();
+            if verified {
+                entry.verified += 1;
+            } else {
+                entry.mismatched += 1;
+            }
+            info!(
+                target: "input",
+                app = app_name,
+                verified = verified,
+                samples = entry.samples(),
+                score = entry.score().unwrap_or_default(),
+                "注入校验 | Injection verified against read-back"
+            );
+            inner.apps.clone()
+        };
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+        if let Err(err) = save_apps(path, &apps) {
+            warn!(
+                target: "input",
+                path = %path.display(),
+                error = %format!("{err:#}"),
+                "注入可靠度保存失败 | Injection reliability save failed"
+            );
+        }
+    }
+
+    /// 按累计的可靠度选择注入方式：模拟输入常出错的应用改用剪贴板
+    pub fn strategy(&self, app_name: &str, configured: InjectionStrategy) -> InjectionStrategy {
+        let inner = self.inner.lock().expect("reliability lock");
+        let config = &inner.config;
+        if !config.enabled || !config.auto_strategy || configured != InjectionStrategy::Typing {
+            return configured;
+        }
+        match inner.apps.get(app_name.trim()) {
+            Some(app) if app.samples() >= config.min_samples.max(1) => match app.score() {
+                Some(score) if score < config.min_reliability => InjectionStrategy::Clipboard,
+                _ => configured,
+            },
+            _ => configured,
+        }
+    }
+}
+
+fn save_apps(path: &Path, apps: &BTreeMap<String, AppReliability>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).context("create injection reliability dir")?;
+        }
+    }
+    let content = serde_json::to_string_pretty(apps).context("serialize injection reliability")?;
+    std::fs::write(path, content).context("write injection reliability")
+}
+
+fn resolve_reliability_path() -> PathBuf {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    exe_dir.join("data").join("injection_reliability.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_back_is_checked_and_scores_pick_strategy() {
+        assert_eq!(check_insertion("", "你好", "你好"), Insertion::Verified);
+        assert_eq!(check_insertion("ab", "abab", "ba"), Insertion::Verified);
+        assert_eq!(check_insertion("x selected y", "x OK y", "OK"), Insertion::Verified);
+        // 死键吞掉了引号、输入法改写了文字
+        assert_eq!(
+            check_insertion("note: ", "note: ecole", "\"école\""),
+            Insertion::Mismatch { typed: 5 }
+        );
+        assert_eq!(check_insertion("abc", "abc", "d"), Insertion::Mismatch { typed: 0 });
+        // 应用只处理了一部分按键：单看这次回读会误判，要等回读稳定
+        assert_eq!(
+            check_insertion("note: ", "note: hel", "hello"),
+            Insertion::Mismatch { typed: 3 }
+        );
+        assert!(!read_back_settled("note: ", None, "note: hel", "hello"));
+        assert!(read_back_settled("note: ", Some("note: hel"), "note: hello", "hello"));
+        assert!(read_back_settled("x selected y", Some("x OK y"), "x OK y", "OK"));
+        assert!(!read_back_settled("abc", Some("abc"), "abc", "d"));
+
+        let reliability = InjectionReliability::in_memory();
+        reliability.configure(&InjectionCheckConfig {
+            enabled: true,
+            min_samples: 3,
+            ..InjectionCheckConfig::default()
+        });
+        reliability.record("Terminal", false);
+        reliability.record("Terminal", false);
+        assert_eq!(
+            reliability.strategy("Terminal", InjectionStrategy::Typing),
+            InjectionStrategy::Typing
+        );
+        reliability.record("Terminal", true);
+        assert_eq!(
+            reliability.strategy("Terminal", InjectionStrategy::Typing),
+            InjectionStrategy::Clipboard
+        );
+        for _ in 0..3 {
+            reliability.record("Notes", true);
+        }
+        assert_eq!(
+            reliability.strategy("Notes", InjectionStrategy::Typing),
+            InjectionStrategy::Typing
+        );
+
+        reliability.configure(&InjectionCheckConfig {
+            enabled: true,
+            auto_strategy: false,
+            min_samples: 3,
+            ..InjectionCheckConfig::default()
+        });
+        assert_eq!(
+            reliability.strategy("Terminal", InjectionStrategy::Typing),
+            InjectionStrategy::Typing
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::borrow::Cow;
+
+use anyhow::Context as _;
+use regex::{Captures, NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// 正则编译后的大小上限，防止用户规则占用过多内存
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// 后处理规则：在 ASR / LLM 输出之后、输入之前按顺序应用
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextRule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 备注，仅用于界面展示
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    #[serde(flatten)]
+    pub kind: RuleKind,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleKind {
+    /// 用户词典：原样替换；`whole_word` 只匹配完整的单词（对中文无效）
+    Exact {
+        from: String,
+        to: String,
+        #[serde(default)]
+        ignore_case: bool,
+        #[serde(default)]
+        whole_word: bool,
+    },
+    /// 正则替换，`to` 中可用 `$1`、`${name}` 引用捕获组
+    Regex {
+        pattern: String,
+        to: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    /// 改写大小写：只作用于 `pattern` 匹配到的部分，未填写时作用于全文
+    Case {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+        style: CaseStyle,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStyle {
+    Upper,
+    Lower,
+    /// 每个单词首字母大写，其余字母不变
+    Title,
+    /// 每句首字母大写
+    Sentence,
+}
+
+/// 编译后的规则集；规则无效时整组拒绝，不会只生效一部分
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<Compiled>,
+}
+
+#[derive(Debug)]
+enum Compiled {
+    Replace { regex: Regex, to: String, expand: bool },
+    Case { regex: Option<Regex>, style: CaseStyle },
+}
+
+impl RuleSet {
+    pub fn compile(rules: &[TextRule]) -> anyhow::Result<Self> {
+        let compiled = rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.enabled)
+            .map(|(index, rule)| compile_rule(&rule.kind).with_context(|| format!("规则 #{} 无效", index + 1)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 依次应用所有规则，前一条的结果作为后一条的输入
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.rules {
+            let next = match rule {
+                Compiled::Replace { regex, to, expand: true } => regex.replace_all(&text, to.as_str()),
+                Compiled::Replace { regex, to, expand: false } => regex.replace_all(&text, NoExpand(to)),
+                Compiled::Case { regex: None, style } => Cow::Owned(change_case(&text, *style)),
+                Compiled::Case { regex: Some(regex), style } => {
+                    regex.replace_all(&text, |caps: &Captures<'_>| change_case(&caps[0], *style))
+                }
+            };
+            if let Cow::Owned(next) = next {
+                text = Cow::Owned(next);
+            }
+        }
+        text.into_owned()
+    }
+}
+
+fn compile_rule(kind: &RuleKind) -> anyhow::Result<Compiled> {
+    Ok(match kind {
+        RuleKind::Exact {
+            from,
+            to,
+            ignore_case,
+            whole_word,
+        } => {
+            if from.is_empty() {
+                anyhow::bail!("替换的原文不能为空");
+            }
+            let escaped = regex::escape(from);
+            let pattern = if *whole_word { format!(r"\b{escaped}\b") } else { escaped };
+            Compiled::Replace {
+                regex: build_regex(&pattern, *ignore_case)?,
+                to: to.clone(),
+                expand: false,
+            }
+        }
+        RuleKind::Regex {
+            pattern,
+            to,
+            ignore_case,
+        } => {
+            let regex = build_regex(pattern, *ignore_case)?;
+            if regex.is_match("") {
+                // 能匹配空串的正则会在每个字符之间插入替换内容
+                anyhow::bail!("正则不能匹配空字符串: {pattern}");
+            }
+            Compiled::Replace {
+                regex,
+                to: to.clone(),
+                expand: true,
+            }
+        }
+        RuleKind::Case { pattern, style } => Compiled::Case {
+            regex: match pattern.as_deref().filter(|p| !p.is_empty()) {
+                Some(pattern) => Some(build_regex(pattern, false)?),
+                None => None,
+            },
+            style: *style,
+        },
+    })
+}
+
+fn build_regex(pattern: &str, ignore_case: bool) -> anyhow::Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .with_context(|| format!("正则无法解析: {pattern}"))
+}
+
+fn change_case(text: &str, style: CaseStyle) -> String {
+    match style {
+        CaseStyle::Upper => text.to_uppercase(),
+        CaseStyle::Lower => text.to_lowercase(),
+        CaseStyle::Title => capitalize_where(text, |prev| !prev.is_some_and(|c| c.is_alphanumeric() || c == '\'')),
+        CaseStyle::Sentence => {
+            // 英文句号后须有空白才算句末（排除 3.5、example.com）；中文句末标点直接算
+            let mut at_start = true;
+            let mut after_stop = false;
+            let mut out = String::with_capacity(text.len());
+            for c in text.chars() {
+                if at_start && c.is_alphanumeric() {
+                    out.extend(c.to_uppercase());
+                    at_start = false;
+                } else {
+                    out.push(c);
+                }
+                match c {
+                    '.' | '!' | '?' => after_stop = true,
+                    '。' | '！' | '？' => at_start = true,
+                    c if c.is_whitespace() => at_start |= after_stop,
+                    _ => after_stop = false,
+                }
+            }
+            out
+        }
+    }
+}
+
+/// 前一个字符满足 `starts_word` 时把当前字母改为大写
+fn capitalize_where(text: &str, starts_word: impl Fn(Option<char>) -> bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut prev = None;
+    for c in text.chars() {
+        if c.is_alphabetic() && starts_word(prev) {
+            out.extend(c.to_uppercase());
+        } else {
+            out.push(c);
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(raw: &str) -> RuleSet {
+        let rules: Vec<TextRule> = serde_json::from_str(raw).expect("deserialize");
+        RuleSet::compile(&rules).expect("compile")
+    }
+
+    #[test]
+    fn rules_apply_in_order() {
+        let set = rules(
+            r#"[
+              { "type": "exact", "from": "github", "to": "GitHub", "ignore_case": true, "whole_word": true },
+              { "type": "exact", "from": "$1", "to": "一美元" },
+              { "type": "regex", "pattern": "(\\d+) 点 (\\d+)", "to": "$1.$2" },
+              { "type": "regex", "pattern": "(?P<user>\\w+) at (?P<host>\\w+) dot com", "to": "${user}@${host}.com" },
+              { "type": "case", "pattern": "\\bapi\\b", "style": "upper" },
+              { "type": "case", "style": "sentence" },
+              { "type": "exact", "from": "嗯", "to": "", "enabled": false }
+            ]"#,
+        );
+        assert_eq!(
+            set.apply("push to Github and githubber. api costs $1, 3 点 5 嗯"),
+            "Push to GitHub and githubber. API costs 一美元, 3.5 嗯"
+        );
+        assert_eq!(set.apply("mail bob at example dot com"), "Mail bob@example.com");
+        assert_eq!(change_case("it's the o'neil show", CaseStyle::Title), "It's The O'neil Show");
+        assert_eq!(change_case("好的。ok! fine", CaseStyle::Sentence), "好的。Ok! Fine");
+        assert!(RuleSet::default().is_empty());
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected_with_position() {
+        let bad = [
+            TextRule {
+                enabled: true,
+                note: String::new(),
+                kind: RuleKind::Exact {
+                    from: "a".to_string(),
+                    to: "b".to_string(),
+                    ignore_case: false,
+                    whole_word: false,
+                },
+            },
+            TextRule {
+                enabled: true,
+                note: String::new(),
+                kind: RuleKind::Regex {
+                    pattern: "(".to_string(),
+                    to: String::new(),
+                    ignore_case: false,
+                },
+            },
+        ];
+        let err = RuleSet::compile(&bad).expect_err("invalid regex");
+        assert!(format!("{err:#}").contains("#2"));
+
+        let empty: Vec<TextRule> = serde_json::from_str(r#"[{ "type": "regex", "pattern": "x*", "to": "-" }]"#).expect("json");
+        assert!(RuleSet::compile(&empty).is_err());
+        let disabled: Vec<TextRule> =
+            serde_json::from_str(r#"[{ "type": "regex", "pattern": "(", "to": "", "enabled": false }]"#).expect("json");
+        assert!(RuleSet::compile(&disabled).expect("disabled rules are skipped").is_empty());
+    }
+}
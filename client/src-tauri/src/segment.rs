@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+/// 低于这个 RMS（约 -40 dBFS）的帧视为静音
+const SILENCE_RMS: f64 = 0.01;
+
+/// 长时间听写分段输出：录音不停，已说完的部分先识别并输入（中间段不做 LLM 校正）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SegmentConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 一段最长的音频时长，到达后强制分段
+    #[serde(default = "default_max_segment_ms")]
+    pub max_segment_ms: u64,
+    /// 停顿多久视为一句话结束；0 表示只按时长分段
+    #[serde(default = "default_pause_ms")]
+    pub pause_ms: u64,
+    /// 短于这个时长的段不在停顿处切分，避免把一句话切得太碎
+    #[serde(default = "default_min_segment_ms")]
+    pub min_segment_ms: u64,
+}
+
+impl Default for SegmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_segment_ms: default_max_segment_ms(),
+            pause_ms: default_pause_ms(),
+            min_segment_ms: default_min_segment_ms(),
+        }
+    }
+}
+
+fn default_max_segment_ms() -> u64 {
+    30_000
+}
+
+fn default_pause_ms() -> u64 {
+    800
+}
+
+fn default_min_segment_ms() -> u64 {
+    4_000
+}
+
+/// 按音频时长与停顿判断分段位置；没有说话的段不会被切出来
+#[derive(Debug)]
+pub struct Segmenter {
+    max_samples: u64,
+    min_samples: u64,
+    pause_samples: u64,
+    samples: u64,
+    silent_samples: u64,
+    voiced: bool,
+}
+
+impl Segmenter {
+    pub fn new(config: &SegmentConfig, sample_rate: u32) -> Self {
+        let to_samples = |ms: u64| ms * u64::from(sample_rate) / 1000;
+        Self {
+            max_samples: to_samples(config.max_segment_ms.max(1000)),
+            min_samples: to_samples(config.min_segment_ms),
+            pause_samples: to_samples(config.pause_ms),
+            samples: 0,
+            silent_samples: 0,
+            voiced: false,
+        }
+    }
+
+    /// 送入一帧；返回 true 表示当前段在这一帧之后结束
+    pub fn push(&mut self, pcm: &[i16]) -> bool {
+        if pcm.is_empty() {
+            return false;
+        }
+        self.samples += pcm.len() as u64;
+        if is_silent(pcm) {
+            self.silent_samples += pcm.len() as u64;
+        } else {
+            self.silent_samples = 0;
+            self.voiced = true;
+        }
+
+        let at_pause = self.pause_samples > 0
+            && self.voiced
+            && self.samples >= self.min_samples
+            && self.silent_samples >= self.pause_samples;
+        let too_long = self.samples >= self.max_samples;
+        if !at_pause && !too_long {
+            return false;
+        }
+        let split = self.voiced;
+        self.samples = 0;
+        self.silent_samples = 0;
+        self.voiced = false;
+        split
+    }
+}
+
+/// 整帧的 RMS 低于静音阈值
+pub fn is_silent(pcm: &[i16]) -> bool {
+    rms(pcm) < SILENCE_RMS
+}
+
+fn rms(pcm: &[i16]) -> f64 {
+    let sum: f64 = pcm
+        .iter()
+        .map(|&sample| {
+            let value = sample as f64 / i16::MAX as f64;
+            value * value
+        })
+        .sum();
+    (sum / pcm.len() as f64).sqrt()
+}
+
+/// 两段之间的分隔：两侧都是非 CJK 文字时补一个空格
+pub fn separator(previous: &str, next: &str) -> &'static str {
+    match (previous.chars().last(), next.chars().next()) {
+        (Some(last), Some(first))
+            if !last.is_whitespace() && !first.is_whitespace() && !is_cjk(last) && !is_cjk(first) =>
+        {
+            " "
+        }
+        _ => "",
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3000}'..='\u{303f}'
+            | '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{ff00}'..='\u{ffef}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmenter_splits_at_pauses_and_max_length() {
+        let config = SegmentConfig {
+            enabled: true,
+            max_segment_ms: 3000,
+            pause_ms: 400,
+            min_segment_ms: 2000,
+        };
+        let mut segmenter = Segmenter::new(&config, 16000);
+        let speech = [8000i16; 320];
+        let silence = [0i16; 320];
+
+        // 20ms 一帧：0.6s 语音后停顿，未到最短时长不切分
+        assert!((0..30).all(|_| !segmenter.push(&speech)));
+        assert!((0..30).all(|_| !segmenter.push(&silence)));
+        // 再说 0.4s，停顿 0.4s 后切分
+        assert!((0..20).all(|_| !segmenter.push(&speech)));
+        assert!((0..19).all(|_| !segmenter.push(&silence)));
+        assert!(segmenter.push(&silence));
+
+        // 一直说话时按最长时长切分
+        assert!((0..149).all(|_| !segmenter.push(&speech)));
+        assert!(segmenter.push(&speech));
+
+        // 全是静音时不切出空段
+        assert!((0..200).all(|_| !segmenter.push(&silence)));
+
+        assert_eq!(separator("Hello world.", "Next"), " ");
+        assert_eq!(separator("你好。", "Next"), "");
+        assert_eq!(separator("done ", "next"), "");
+        assert_eq!(separator("", "next"), "");
+    }
+}
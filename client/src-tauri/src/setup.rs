@@ -0,0 +1,368 @@
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::{asr, audio, config, platform, proxy};
+
+/// 首次运行向导的整体状态（前端据此决定是否进入向导）
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupStatus {
+    pub first_run: bool,
+    pub config_path: Option<String>,
+    pub os: String,
+    pub arch: String,
+    pub default_hotkey: String,
+    pub default_endpoint: String,
+    pub needs_accessibility: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerProbe {
+    pub endpoint: String,
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MicrophoneTest {
+    pub ok: bool,
+    pub sample_rate: u32,
+    pub frames: u64,
+    pub peak: f32,
+    pub rms: f32,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupPermission {
+    Microphone,
+    Accessibility,
+}
+
+/// 单项权限的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    /// 已触发系统弹窗/提示，等待用户在系统设置中授权
+    Pending,
+    Denied,
+    /// 前一步尚未完成，本步骤暂未请求
+    NotRequested,
+    /// 当前平台不需要此权限
+    NotRequired,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionStep {
+    pub permission: SetupPermission,
+    pub state: PermissionState,
+    pub detail: Option<String>,
+}
+
+/// 引导式权限流程的结果：按顺序列出每一步，`next` 为需要用户处理的下一步
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionFlow {
+    pub steps: Vec<PermissionStep>,
+    pub next: Option<SetupPermission>,
+    pub all_granted: bool,
+}
+
+pub fn status() -> SetupStatus {
+    let (config, path) = config::load_with_path();
+    SetupStatus {
+        first_run: path.is_none(),
+        config_path: path.map(|p| p.display().to_string()),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        default_hotkey: config.hotkey,
+        default_endpoint: asr::default_websocket_endpoint(),
+        needs_accessibility: platform::accessibility_required(),
+    }
+}
+
+/// 发送 ping 并等待 pong，用于连接测试与向导探测。
+pub async fn ping_server(endpoint: &str, timeout: Duration) -> Result<bool, String> {
+    let endpoint = endpoint.trim();
+    if endpoint.is_empty() {
+        return Err("服务器地址为空 | Endpoint is empty".to_string());
+    }
+
+    let connect_result = tokio::time::timeout(timeout, proxy::connect_websocket(endpoint))
+        .await
+        .map_err(|_| "连接超时 | Connect timeout".to_string())?;
+
+    let ws = connect_result.map_err(|err| format!("{err:#}"))?;
+    let (mut write, mut read) = ws.split();
+
+    let payload = serde_json::json!({ "type": "ping" }).to_string();
+    write
+        .send(Message::Text(payload))
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let incoming = tokio::time::timeout(timeout, read.next())
+        .await
+        .map_err(|_| "等待响应超时 | Wait timeout".to_string())?;
+
+    let Some(Ok(Message::Text(text))) = incoming else {
+        return Ok(false);
+    };
+
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Ok(false);
+    };
+    Ok(value.get("type").and_then(|v| v.as_str()) == Some("pong"))
+}
+
+pub async fn probe_server(endpoint: Option<String>) -> ServerProbe {
+    let endpoint = endpoint
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(asr::default_websocket_endpoint);
+
+    let started = Instant::now();
+    let result = ping_server(&endpoint, Duration::from_secs(3)).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let probe = match result {
+        Ok(true) => ServerProbe {
+            endpoint,
+            reachable: true,
+            latency_ms: Some(latency_ms),
+            error: None,
+        },
+        Ok(false) => ServerProbe {
+            endpoint,
+            reachable: false,
+            latency_ms: None,
+            error: Some("服务器未响应 pong | Server did not answer pong".to_string()),
+        },
+        Err(err) => ServerProbe {
+            endpoint,
+            reachable: false,
+            latency_ms: None,
+            error: Some(err),
+        },
+    };
+
+    info!(
+        target: "setup",
+        endpoint = %probe.endpoint,
+        reachable = probe.reachable,
+        latency_ms = probe.latency_ms.unwrap_or(0),
+        "服务器探测完成 | Server probe finished"
+    );
+    probe
+}
+
+/// 逐项请求权限：麦克风通过一次短暂采集触发系统弹窗，辅助功能使用系统提示框。
+pub async fn request_permission(permission: SetupPermission, device: Option<String>) -> bool {
+    match permission {
+        SetupPermission::Microphone => test_microphone(device, 300).await.ok,
+        SetupPermission::Accessibility => platform::ensure_accessibility(true),
+    }
+}
+
+/// 按顺序检查/请求权限：先麦克风（短暂采集以触发系统弹窗），再辅助功能。
+///
+/// - `prompt=false`：只检查当前状态，不触发任何系统弹窗
+/// - `prompt=true`：前一步未授权时停下，后续步骤标记为 `NotRequested`
+pub async fn permission_flow(device: Option<String>, prompt: bool) -> PermissionFlow {
+    let mut steps = Vec::with_capacity(2);
+
+    let microphone = if prompt {
+        let test = test_microphone(device, 500).await;
+        PermissionStep {
+            permission: SetupPermission::Microphone,
+            state: classify_microphone(&test),
+            detail: test.error,
+        }
+    } else {
+        let ok = audio::check_microphone_access(device.as_deref());
+        PermissionStep {
+            permission: SetupPermission::Microphone,
+            state: if ok { PermissionState::Granted } else { PermissionState::Denied },
+            detail: None,
+        }
+    };
+    let microphone_ok = microphone.state == PermissionState::Granted;
+    steps.push(microphone);
+
+    let accessibility = if !platform::accessibility_required() {
+        PermissionState::NotRequired
+    } else if prompt && !microphone_ok {
+        PermissionState::NotRequested
+    } else if platform::ensure_accessibility(false) {
+        PermissionState::Granted
+    } else if prompt {
+        platform::ensure_accessibility(true);
+        PermissionState::Pending
+    } else {
+        PermissionState::Denied
+    };
+    steps.push(PermissionStep {
+        permission: SetupPermission::Accessibility,
+        state: accessibility,
+        detail: None,
+    });
+
+    let next = next_step(&steps);
+    info!(
+        target: "setup",
+        prompt = prompt,
+        next = ?next,
+        "权限流程状态 | Permission flow status"
+    );
+    PermissionFlow {
+        all_granted: next.is_none(),
+        next,
+        steps,
+    }
+}
+
+/// macOS 未授权时采集不会报错，但只会拿到全零的静音数据
+fn classify_microphone(test: &MicrophoneTest) -> PermissionState {
+    if !test.ok {
+        return PermissionState::Denied;
+    }
+    if cfg!(target_os = "macos") && test.peak == 0.0 {
+        return PermissionState::Pending;
+    }
+    PermissionState::Granted
+}
+
+fn next_step(steps: &[PermissionStep]) -> Option<SetupPermission> {
+    steps
+        .iter()
+        .find(|step| !matches!(step.state, PermissionState::Granted | PermissionState::NotRequired))
+        .map(|step| step.permission)
+}
+
+/// 录制一小段音频并计算电平，确认设备可用且确实有声音输入。
+pub async fn test_microphone(device: Option<String>, duration_ms: u64) -> MicrophoneTest {
+    let duration = Duration::from_millis(duration_ms.clamp(200, 5000));
+    let (recorder, mut pcm_rx) = match audio::start_audio("setup".to_string(), device) {
+        Ok(parts) => parts,
+        Err(err) => {
+            warn!(
+                target: "setup",
+                error = %err,
+                "麦克风测试失败 | Microphone test failed"
+            );
+            return MicrophoneTest {
+                ok: false,
+                sample_rate: 0,
+                frames: 0,
+                peak: 0.0,
+                rms: 0.0,
+                error: Some(err.to_string()),
+            };
+        }
+    };
+
+    let sample_rate = recorder.sample_rate;
+    let deadline = tokio::time::Instant::now() + duration;
+    let mut frames: u64 = 0;
+    let mut peak: f32 = 0.0;
+    let mut sum_sq: f64 = 0.0;
+    let mut samples: u64 = 0;
+
+    while let Ok(Some(frame)) = tokio::time::timeout_at(deadline, pcm_rx.recv()).await {
+        frames += 1;
+        for sample in frame {
+            let value = sample as f32 / i16::MAX as f32;
+            peak = peak.max(value.abs());
+            sum_sq += (value as f64) * (value as f64);
+            samples += 1;
+        }
+    }
+
+    let _ = tokio::task::spawn_blocking(move || recorder.stop()).await;
+
+    let rms = if samples > 0 {
+        (sum_sq / samples as f64).sqrt() as f32
+    } else {
+        0.0
+    };
+
+    info!(
+        target: "setup",
+        sample_rate = sample_rate,
+        frames = frames,
+        peak = peak as f64,
+        rms = rms as f64,
+        "麦克风测试完成 | Microphone test finished"
+    );
+
+    MicrophoneTest {
+        ok: frames > 0,
+        sample_rate,
+        frames,
+        peak,
+        rms,
+        error: if frames > 0 {
+            None
+        } else {
+            Some("未采集到音频 | No audio captured".to_string())
+        },
+    }
+}
+
+/// 写入向导生成的初始配置；已存在配置时写回原路径。
+pub fn complete(config: &config::ClientConfig) -> anyhow::Result<std::path::PathBuf> {
+    let (_, path) = config::load_with_path();
+    let saved = config::save_to_path(config, path)?;
+    info!(
+        target: "setup",
+        path = %saved.display(),
+        "首次设置完成 | Initial setup completed"
+    );
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(permission: SetupPermission, state: PermissionState) -> PermissionStep {
+        PermissionStep {
+            permission,
+            state,
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn next_step_skips_granted_and_not_required() {
+        let steps = vec![
+            step(SetupPermission::Microphone, PermissionState::Granted),
+            step(SetupPermission::Accessibility, PermissionState::NotRequired),
+        ];
+        assert_eq!(next_step(&steps), None);
+
+        let steps = vec![
+            step(SetupPermission::Microphone, PermissionState::Pending),
+            step(SetupPermission::Accessibility, PermissionState::NotRequested),
+        ];
+        assert_eq!(next_step(&steps), Some(SetupPermission::Microphone));
+    }
+
+    #[test]
+    fn microphone_without_capture_is_denied() {
+        let test = MicrophoneTest {
+            ok: false,
+            sample_rate: 0,
+            frames: 0,
+            peak: 0.0,
+            rms: 0.0,
+            error: Some("no device".to_string()),
+        };
+        assert_eq!(classify_microphone(&test), PermissionState::Denied);
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rdev::Key;
+use tauri::Manager;
+use tracing::{info, warn};
+
+use crate::app_state::AppState;
+use crate::input::Injector;
+use crate::{logging, network};
+
+/// 整个退出流程的时限，超时后直接退出（例如 Pipeline 卡在无响应的服务器上）
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 等待剩余事件写入历史记录与统计的时限
+const EVENTS_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 正按住 CapsLock 热键：按下时系统已切换了大写锁定，松开前退出需要切换回来
+static CAPS_LOCK_HELD: AtomicBool = AtomicBool::new(false);
+
+/// 热键监听线程在热键按下、松开时调用
+pub fn track_hotkey(key: Key, pressed: bool) {
+    if key == Key::CapsLock {
+        CAPS_LOCK_HELD.store(pressed, Ordering::SeqCst);
+    }
+}
+
+/// 退出应用：先结束录音与会话、关闭服务器连接、写完历史与日志、恢复按键状态，再退出进程。
+/// 重复触发时忽略
+pub fn quit(app: &tauri::AppHandle) {
+    static QUITTING: AtomicBool = AtomicBool::new(false);
+    if QUITTING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        info!(target: "app", "正在退出 | Shutting down");
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown(&app)).await.is_err() {
+            warn!(
+                target: "app",
+                timeout_ms = SHUTDOWN_TIMEOUT.as_millis() as u64,
+                "退出清理超时，直接退出 | Shutdown cleanup timed out, exiting anyway"
+            );
+        }
+        logging::flush();
+        app.exit(0);
+    });
+}
+
+async fn shutdown(app: &tauri::AppHandle) {
+    let state = app.try_state::<Arc<AppState>>().map(|state| state.inner().clone());
+    if let Some(state) = &state {
+        state.shutdown().await;
+    }
+    network::close_all().await;
+    if let Some(injector) = app.try_state::<Injector>() {
+        let caps_lock = CAPS_LOCK_HELD.load(Ordering::SeqCst);
+        if let Err(err) = injector.restore_keys(caps_lock).await {
+            warn!(
+                target: "input",
+                error = %format!("{err:#}"),
+                "退出时恢复按键状态失败 | Failed to restore key state on exit"
+            );
+        }
+    }
+    if let Some(state) = &state {
+        state.events.drained(EVENTS_DRAIN_TIMEOUT).await;
+    }
+    info!(target: "app", "退出清理完成 | Shutdown cleanup finished");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_caps_lock_hotkey_is_tracked() {
+        track_hotkey(Key::F8, true);
+        assert!(!CAPS_LOCK_HELD.load(Ordering::SeqCst));
+        track_hotkey(Key::CapsLock, true);
+        assert!(CAPS_LOCK_HELD.load(Ordering::SeqCst));
+        track_hotkey(Key::F8, false);
+        assert!(CAPS_LOCK_HELD.load(Ordering::SeqCst));
+        track_hotkey(Key::CapsLock, false);
+        assert!(!CAPS_LOCK_HELD.load(Ordering::SeqCst));
+    }
+}
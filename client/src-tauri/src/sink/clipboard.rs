@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+
+use super::{Sink, Transcript};
+use crate::input::{InjectCommand, Injector};
+
+/// 把最终结果复制到剪贴板。经由注入通道执行，排在粘贴注入恢复原剪贴板之后，不会被覆盖。
+pub struct ClipboardSink {
+    injector: Injector,
+}
+
+impl ClipboardSink {
+    pub fn new(injector: Injector) -> Self {
+        Self { injector }
+    }
+}
+
+#[async_trait]
+impl Sink for ClipboardSink {
+    fn name(&self) -> &'static str {
+        "clipboard"
+    }
+
+    async fn commit(&self, transcript: &Transcript) -> anyhow::Result<()> {
+        self.injector
+            .tx
+            .send(InjectCommand::CopyText {
+                trace_id: transcript.trace_id.clone(),
+                text: transcript.text.clone(),
+            })
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))
+    }
+}
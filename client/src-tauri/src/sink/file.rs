@@ -0,0 +1,96 @@
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+
+use super::{Sink, Transcript};
+
+/// 把最终结果追加到文本文件（语音日记）。`.md` 文件每条写成列表项，其它文件每条一行 `[时间] 文字`。
+pub struct FileSink {
+    path: PathBuf,
+    markdown: bool,
+}
+
+impl FileSink {
+    pub fn new(path: &str) -> anyhow::Result<Self> {
+        let path = PathBuf::from(path.trim());
+        if !path.is_absolute() {
+            anyhow::bail!("文件输出端需要绝对路径: {}", path.display());
+        }
+        let markdown = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"));
+        Ok(Self { path, markdown })
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn commit(&self, transcript: &Transcript) -> anyhow::Result<()> {
+        let entry = format_entry(&transcript.text, Local::now(), self.markdown);
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || append(&path, &entry))
+            .await
+            .context("file sink task")?
+    }
+}
+
+fn append(path: &Path, entry: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    // 整条一次写入，避免与下一条交错
+    file.write_all(entry.as_bytes())
+        .with_context(|| format!("write {}", path.display()))
+}
+
+fn format_entry(text: &str, time: DateTime<Local>, markdown: bool) -> String {
+    let text = text.trim();
+    if markdown {
+        // 多行文字缩进到同一个列表项下
+        let body = text.lines().collect::<Vec<_>>().join("\n  ");
+        format!("- **{}** {body}\n", time.format("%Y-%m-%d %H:%M"))
+    } else {
+        format!("[{}] {text}\n", time.format("%Y-%m-%d %H:%M:%S"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn entries_are_timestamped_per_format() {
+        let time = Local.with_ymd_and_hms(2024, 5, 1, 9, 30, 5).single().expect("time");
+        assert_eq!(format_entry(" 买牛奶 ", time, false), "[2024-05-01 09:30:05] 买牛奶\n");
+        assert_eq!(
+            format_entry("第一行\n第二行", time, true),
+            "- **2024-05-01 09:30** 第一行\n  第二行\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("ghosttype-file-sink-{}", std::process::id()));
+        let path = dir.join("journal.md");
+        let sink = FileSink::new(path.to_str().expect("utf-8 path")).expect("sink");
+        assert!(sink.markdown);
+        append(&sink.path, "- a\n").expect("append");
+        append(&sink.path, "- b\n").expect("append");
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), "- a\n- b\n");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(FileSink::new("notes.txt").is_err());
+    }
+}
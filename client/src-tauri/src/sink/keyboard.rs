@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+
+use super::{Sink, SinkContext};
+use crate::input::{InjectCommand, Injector};
+
+/// 输入到当前焦点；校正结果通过退格后重新输入来替换
+pub struct KeyboardSink {
+    injector: Injector,
+}
+
+impl KeyboardSink {
+    pub fn new(injector: Injector) -> Self {
+        Self { injector }
+    }
+
+    async fn send(&self, command: InjectCommand) -> anyhow::Result<()> {
+        self.injector
+            .tx
+            .send(command)
+            .await
+            .map_err(|_| anyhow::anyhow!("注入通道已关闭"))
+    }
+}
+
+#[async_trait]
+impl Sink for KeyboardSink {
+    fn name(&self) -> &'static str {
+        "keyboard"
+    }
+
+    async fn draft(&self, context: &SinkContext, text: &str) -> anyhow::Result<()> {
+        self.send(InjectCommand::text(context.injection, context.trace_id.clone(), text.to_string()))
+            .await
+    }
+
+    async fn replace(&self, context: &SinkContext, previous_chars: usize, text: &str) -> anyhow::Result<()> {
+        self.send(InjectCommand::Backspace {
+            trace_id: context.trace_id.clone(),
+            count: previous_chars,
+        })
+        .await?;
+        self.send(InjectCommand::text(context.injection, context.trace_id.clone(), text.to_string()))
+            .await
+    }
+}
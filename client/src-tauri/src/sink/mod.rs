@@ -0,0 +1,207 @@
+mod clipboard;
+mod file;
+mod keyboard;
+mod webhook;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::input::{InjectionStrategy, Injector};
+
+/// 输出端配置：可同时启用多个，识别结果按顺序交给每一个
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// 输入到当前焦点（键盘或粘贴，见按应用覆盖的 `injection`），LLM 校正后原地替换
+    Keyboard,
+    /// 把最终结果复制到剪贴板（不粘贴）
+    Clipboard,
+    /// 把最终结果连同时间追加到文件（`.md` 写成列表项，其它按行写入）
+    File { path: String },
+    /// 把最终结果 POST 到指定地址（JSON），失败时按指数退避重试
+    Webhook {
+        url: String,
+        /// 附加的请求头，例如 `Authorization`
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+        #[serde(default = "default_webhook_retries")]
+        max_retries: u32,
+        #[serde(default = "default_webhook_timeout_ms")]
+        timeout_ms: u64,
+    },
+}
+
+fn default_webhook_retries() -> u32 {
+    3
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+pub fn default_sinks() -> Vec<SinkConfig> {
+    vec![SinkConfig::Keyboard]
+}
+
+/// 一次会话输出时的上下文
+#[derive(Debug, Clone, Default)]
+pub struct SinkContext {
+    pub trace_id: Option<String>,
+    pub app_name: String,
+    pub injection: InjectionStrategy,
+}
+
+/// 会话的最终结果（校正后以校正结果为准）
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub trace_id: Option<String>,
+    pub app: String,
+    pub text: String,
+    pub corrected: bool,
+}
+
+/// 识别结果的去处。`draft`/`replace` 面向需要尽快看到文字的输出端（键盘），
+/// 只关心最终结果的输出端实现 `commit` 即可。
+#[async_trait]
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// ASR 结果，LLM 校正可能随后替换它
+    async fn draft(&self, _context: &SinkContext, _text: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 用校正结果替换刚输出的 `previous_chars` 个字
+    async fn replace(&self, _context: &SinkContext, _previous_chars: usize, _text: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 会话结束，结果不会再变
+    async fn commit(&self, _transcript: &Transcript) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// 当前启用的全部输出端
+#[derive(Clone, Default)]
+pub struct Sinks {
+    sinks: Vec<Arc<dyn Sink>>,
+}
+
+impl Sinks {
+    /// 只输入到当前焦点（默认配置）
+    pub fn keyboard(injector: Injector) -> Self {
+        Self {
+            sinks: vec![Arc::new(keyboard::KeyboardSink::new(injector))],
+        }
+    }
+
+    /// 是否会把文字输入到当前焦点（语音编辑命令只对这样输入的文字生效）
+    pub fn types_into_focus(&self) -> bool {
+        self.sinks.iter().any(|sink| sink.name() == "keyboard")
+    }
+
+    /// 依次交给每个输出端；有失败时仍会交给其余输出端，返回第一个错误
+    pub async fn draft(&self, context: &SinkContext, text: &str) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Err(err) = sink.draft(context, text).await {
+                result = result.and(Err(err.context(sink.name())));
+            }
+        }
+        result
+    }
+
+    pub async fn replace(&self, context: &SinkContext, previous_chars: usize, text: &str) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Err(err) = sink.replace(context, previous_chars, text).await {
+                result = result.and(Err(err.context(sink.name())));
+            }
+        }
+        result
+    }
+
+    /// 在后台交给各输出端，互不等待（webhook 重试不会拖慢其它输出端）；失败只记录日志，不影响会话
+    pub fn commit(&self, transcript: Transcript) {
+        let transcript = Arc::new(transcript);
+        for sink in &self.sinks {
+            let sink = sink.clone();
+            let transcript = transcript.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = sink.commit(&transcript).await {
+                    warn!(
+                        target: "sink",
+                        trace_id = transcript.trace_id.as_deref().unwrap_or(""),
+                        sink = sink.name(),
+                        error = %format!("{err:#}"),
+                        "输出端写入失败 | Sink delivery failed"
+                    );
+                }
+            });
+        }
+    }
+}
+
+pub fn create_sinks(configs: &[SinkConfig], injector: &Injector) -> anyhow::Result<Sinks> {
+    let mut sinks: Vec<Arc<dyn Sink>> = Vec::with_capacity(configs.len());
+    for (index, config) in configs.iter().enumerate() {
+        if configs[..index].contains(config) {
+            anyhow::bail!("输出端重复: {config:?}");
+        }
+        let sink: Arc<dyn Sink> = match config {
+            SinkConfig::Keyboard => Arc::new(keyboard::KeyboardSink::new(injector.clone())),
+            SinkConfig::Clipboard => Arc::new(clipboard::ClipboardSink::new(injector.clone())),
+            SinkConfig::File { path } => Arc::new(file::FileSink::new(path)?),
+            SinkConfig::Webhook {
+                url,
+                headers,
+                max_retries,
+                timeout_ms,
+            } => Arc::new(webhook::WebhookSink::new(url, headers, *max_retries, *timeout_ms)?),
+        };
+        sinks.push(sink);
+    }
+    Ok(Sinks { sinks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InjectCommand;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn sinks_follow_config_order() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let injector = Injector { tx };
+        let configs: Vec<SinkConfig> =
+            serde_json::from_str(r#"[{ "type": "keyboard" }, { "type": "clipboard" }]"#).expect("config");
+        let sinks = create_sinks(&configs, &injector).expect("sinks");
+        let context = SinkContext {
+            trace_id: Some("t1".to_string()),
+            ..SinkContext::default()
+        };
+
+        sinks.draft(&context, "hello").await.expect("draft");
+        sinks.replace(&context, 5, "Hello.").await.expect("replace");
+        sinks.commit(Transcript {
+            trace_id: Some("t1".to_string()),
+            app: String::new(),
+            text: "Hello.".to_string(),
+            corrected: true,
+        });
+
+        assert!(matches!(rx.recv().await, Some(InjectCommand::TypeText { text, .. }) if text == "hello"));
+        assert!(matches!(rx.recv().await, Some(InjectCommand::Backspace { count: 5, .. })));
+        assert!(matches!(rx.recv().await, Some(InjectCommand::TypeText { text, .. }) if text == "Hello."));
+        assert!(matches!(rx.recv().await, Some(InjectCommand::CopyText { text, .. }) if text == "Hello."));
+
+        let duplicated = [SinkConfig::Clipboard, SinkConfig::Clipboard];
+        assert!(create_sinks(&duplicated, &injector).is_err());
+    }
+}
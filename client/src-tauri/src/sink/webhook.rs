@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, StatusCode, Url};
+use tracing::{debug, warn};
+
+use super::{Sink, Transcript};
+use crate::proxy;
+
+/// 第一次重试前的等待，之后每次翻倍
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// 把最终结果以 JSON（`trace_id`、`app`、`text`、`corrected`）POST 到指定地址，供自动化工具使用
+pub struct WebhookSink {
+    client: Client,
+    url: Url,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl WebhookSink {
+    pub fn new(
+        url: &str,
+        headers: &BTreeMap<String, String>,
+        max_retries: u32,
+        timeout_ms: u64,
+    ) -> anyhow::Result<Self> {
+        let url = Url::parse(url.trim()).with_context(|| format!("webhook 地址无效: {url}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("webhook 地址需以 http:// 或 https:// 开头: {url}");
+        }
+
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .with_context(|| format!("invalid header {name}"))?;
+            let value =
+                HeaderValue::from_str(value.trim()).with_context(|| format!("invalid header value for {name}"))?;
+            header_map.insert(name, value);
+        }
+        let client = proxy::http_client_builder()
+            .default_headers(header_map)
+            .timeout(Duration::from_millis(timeout_ms.max(200)))
+            .build()
+            .context("build reqwest client")?;
+
+        Ok(Self {
+            client,
+            url,
+            max_retries,
+            retry_delay: INITIAL_RETRY_DELAY,
+        })
+    }
+
+    async fn post(&self, transcript: &Transcript) -> Result<(), Attempt> {
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(transcript)
+            .send()
+            .await
+            .map_err(|err| Attempt::Retry(anyhow::Error::new(err).context("webhook request")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let err = anyhow::anyhow!("webhook responded {status}");
+        if should_retry(status) {
+            Err(Attempt::Retry(err))
+        } else {
+            Err(Attempt::Fatal(err))
+        }
+    }
+}
+
+/// 单次请求的失败类型：网络错误、超时与 5xx/429 可以重试，其它 4xx 重试也不会成功
+enum Attempt {
+    Retry(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+fn should_retry(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn commit(&self, transcript: &Transcript) -> anyhow::Result<()> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let err = match self.post(transcript).await {
+                Ok(()) => {
+                    debug!(
+                        target: "sink",
+                        trace_id = transcript.trace_id.as_deref().unwrap_or(""),
+                        attempt = attempt + 1,
+                        "webhook 已送达 | Webhook delivered"
+                    );
+                    return Ok(());
+                }
+                Err(Attempt::Fatal(err)) => return Err(err),
+                Err(Attempt::Retry(err)) if attempt >= self.max_retries => {
+                    return Err(err.context(format!("已重试 {attempt} 次")));
+                }
+                Err(Attempt::Retry(err)) => err,
+            };
+            attempt += 1;
+            warn!(
+                target: "sink",
+                trace_id = transcript.trace_id.as_deref().unwrap_or(""),
+                attempt = attempt,
+                delay_ms = delay.as_millis() as u64,
+                error = %format!("{err:#}"),
+                "webhook 发送失败，稍后重试 | Webhook failed, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 依次返回给定的状态码，记录收到的请求体
+    async fn serve(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>, tokio::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("addr"));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let hits_for_task = hits.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.expect("accept");
+                let mut buf = vec![0u8; 4096];
+                let mut len = 0;
+                // 读到请求体完整为止
+                loop {
+                    let n = stream.read(&mut buf[len..]).await.expect("read");
+                    len += n;
+                    let raw = String::from_utf8_lossy(&buf[..len]).to_string();
+                    if let Some((head, body)) = raw.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                line.to_ascii_lowercase().strip_prefix("content-length:").map(str::to_string)
+                            })
+                            .and_then(|value| value.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            let _ = tx.send(body.to_string()).await;
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                hits_for_task.fetch_add(1, Ordering::SeqCst);
+                let response = format!("HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits, rx)
+    }
+
+    fn transcript() -> Transcript {
+        Transcript {
+            trace_id: Some("t1".to_string()),
+            app: "Notes".to_string(),
+            text: "买牛奶".to_string(),
+            corrected: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn webhook_retries_server_errors_then_delivers() {
+        let (url, hits, mut bodies) = serve(vec![503, 200]).await;
+        let mut sink = WebhookSink::new(&url, &BTreeMap::new(), 3, 2000).expect("sink");
+        sink.retry_delay = Duration::from_millis(10);
+
+        sink.commit(&transcript()).await.expect("delivered");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        let body: serde_json::Value = serde_json::from_str(&bodies.recv().await.expect("body")).expect("json");
+        assert_eq!(body["trace_id"], "t1");
+        assert_eq!(body["app"], "Notes");
+        assert_eq!(body["text"], "买牛奶");
+        assert_eq!(body["corrected"], true);
+    }
+
+    #[tokio::test]
+    async fn webhook_gives_up_on_client_errors() {
+        let (url, hits, _bodies) = serve(vec![404, 200]).await;
+        let mut sink = WebhookSink::new(&url, &BTreeMap::new(), 3, 2000).expect("sink");
+        sink.retry_delay = Duration::from_millis(10);
+
+        assert!(sink.commit(&transcript()).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        assert!(WebhookSink::new("ftp://x", &BTreeMap::new(), 3, 2000).is_err());
+    }
+}
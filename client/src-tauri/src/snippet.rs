@@ -0,0 +1,204 @@
+use anyhow::bail;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::voice_command;
+
+/// 语音片段：整句是"插入 <片段名>"时输入展开后的片段，而不是说出的文字；不做 LLM 校正
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnippetConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 触发词，后面跟片段名
+    #[serde(default = "default_triggers")]
+    pub triggers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+}
+
+impl Default for SnippetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            triggers: default_triggers(),
+            snippets: Vec::new(),
+        }
+    }
+}
+
+fn default_triggers() -> Vec<String> {
+    vec!["insert".to_string(), "插入".to_string()]
+}
+
+/// 模板中可用 `{date}`、`{time}`、`{datetime}`、`{clipboard}`，
+/// 以及 `{date:%Y年%m月%d日}` 这样的自定义日期格式；其他花括号原样保留
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Snippet {
+    pub name: String,
+    pub text: String,
+}
+
+impl Snippet {
+    /// 需要读取剪贴板时才去读，避免无谓地访问剪贴板
+    pub fn needs_clipboard(&self) -> bool {
+        self.text.contains("{clipboard}")
+    }
+
+    pub fn expand(&self, now: DateTime<Local>, clipboard: &str) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            let key = &rest[start + 1..start + len];
+            match placeholder(key, now, clipboard) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn placeholder(key: &str, now: DateTime<Local>, clipboard: &str) -> Option<String> {
+    let format = match key {
+        "clipboard" => return Some(clipboard.to_string()),
+        "date" => "%Y-%m-%d",
+        "time" => "%H:%M",
+        "datetime" => "%Y-%m-%d %H:%M",
+        key => key.strip_prefix("date:").filter(|format| valid_date_format(format))?,
+    };
+    Some(now.format(format).to_string())
+}
+
+/// chrono 遇到无效的格式说明会在格式化时 panic，使用前先检查
+fn valid_date_format(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// 编译后的片段库；未启用时不识别任何片段
+#[derive(Debug, Default)]
+pub struct SnippetLibrary {
+    enabled: bool,
+    triggers: Vec<String>,
+    /// 去掉空白与标点后的片段名
+    snippets: Vec<(String, Snippet)>,
+}
+
+impl SnippetLibrary {
+    pub fn compile(config: &SnippetConfig) -> anyhow::Result<Self> {
+        let mut snippets: Vec<(String, Snippet)> = Vec::with_capacity(config.snippets.len());
+        for (index, snippet) in config.snippets.iter().enumerate() {
+            let key = compact(&snippet.name);
+            if key.is_empty() {
+                bail!("片段 #{} 没有名称", index + 1);
+            }
+            if snippets.iter().any(|(existing, _)| *existing == key) {
+                bail!("片段名重复: {}", snippet.name);
+            }
+            let mut rest = snippet.text.as_str();
+            while let Some(start) = rest.find("{date:") {
+                let format = &rest[start + "{date:".len()..];
+                let format = &format[..format.find('}').unwrap_or(format.len())];
+                if !valid_date_format(format) {
+                    bail!("片段 {} 的日期格式无效: {format}", snippet.name);
+                }
+                rest = &rest[start + 1..];
+            }
+            snippets.push((key, snippet.clone()));
+        }
+        let triggers = config
+            .triggers
+            .iter()
+            .map(|trigger| voice_command::normalize(trigger))
+            .filter(|trigger| !trigger.is_empty())
+            .collect();
+        Ok(Self {
+            enabled: config.enabled,
+            triggers,
+            snippets,
+        })
+    }
+
+    /// 整句是"触发词 + 片段名"时返回对应片段
+    pub fn recognize(&self, text: &str) -> Option<&Snippet> {
+        if !self.enabled {
+            return None;
+        }
+        let normalized = voice_command::normalize(text);
+        let name = self.triggers.iter().find_map(|trigger| {
+            let rest = normalized.strip_prefix(trigger.as_str())?;
+            // 英文触发词后必须断词，"inserted" 不算 "insert"
+            let needs_break = trigger.chars().last().is_some_and(|c| c.is_ascii_alphanumeric());
+            if needs_break && !rest.starts_with(|c: char| !c.is_alphanumeric()) {
+                return None;
+            }
+            Some(compact(rest))
+        })?;
+        self.snippets
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, snippet)| snippet)
+    }
+}
+
+/// 比较片段名时忽略大小写、空白与标点（识别结果里的空格、标点不稳定）
+fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn snippets_match_trigger_and_expand_placeholders() {
+        let config: SnippetConfig = serde_json::from_str(
+            r#"{
+              "enabled": true,
+              "snippets": [
+                { "name": "my address", "text": "1 Infinite Loop" },
+                { "name": "签名", "text": "此致\n{date:%Y年%m月%d日} {time}" },
+                { "name": "quote", "text": "> {clipboard} ({weekday})" }
+              ]
+            }"#,
+        )
+        .expect("config");
+        let library = SnippetLibrary::compile(&config).expect("compile");
+
+        assert_eq!(library.recognize("Insert my address.").map(|s| s.text.as_str()), Some("1 Infinite Loop"));
+        assert_eq!(library.recognize("insert My-Address").map(|s| s.name.as_str()), Some("my address"));
+        assert_eq!(library.recognize("插入，签名。").map(|s| s.name.as_str()), Some("签名"));
+        assert!(library.recognize("inserted my address").is_none());
+        assert!(library.recognize("please insert my address").is_none());
+        assert!(SnippetLibrary::default().recognize("insert my address").is_none());
+
+        let now = Local.with_ymd_and_hms(2026, 3, 9, 14, 5, 0).unwrap();
+        let signature = library.recognize("插入签名").expect("signature");
+        assert!(!signature.needs_clipboard());
+        assert_eq!(signature.expand(now, ""), "此致\n2026年03月09日 14:05");
+        let quote = library.recognize("insert quote").expect("quote");
+        assert!(quote.needs_clipboard());
+        assert_eq!(quote.expand(now, "copied"), "> copied ({weekday})");
+
+        let invalid = SnippetConfig {
+            enabled: true,
+            triggers: default_triggers(),
+            snippets: vec![Snippet {
+                name: "bad".to_string(),
+                text: "{date:%Q}".to_string(),
+            }],
+        };
+        assert!(SnippetLibrary::compile(&invalid).is_err());
+    }
+}
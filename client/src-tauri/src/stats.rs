@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::network::TrafficStats;
+
+/// 使用统计快照（自启动或上次重置以来）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatsSnapshot {
+    pub since: String,
+    pub sessions: u64,
+    pub corrections: u64,
+    pub failures: u64,
+    pub total_audio_ms: u64,
+    pub total_asr_ms: u64,
+    pub total_llm_ms: u64,
+    pub avg_asr_ms: u64,
+    pub avg_llm_ms: u64,
+    pub chars_injected: u64,
+    pub per_app: BTreeMap<String, u64>,
+    pub network: NetworkTotals,
+}
+
+/// 与 ASR 服务器之间的网络用量累计（按流量计费的网络可据此估算开销）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkTotals {
+    /// 产生网络流量的会话数（离线补交单独计一次）
+    pub sessions: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub audio_packets: u64,
+    pub retransmitted_packets: u64,
+    /// 服务器确认缺失的音频包
+    pub dropped_packets: u64,
+    /// 平均每个 Opus 包的字节数
+    pub avg_packet_bytes: u64,
+    pub avg_bytes_per_session: u64,
+    pub audio_bytes: u64,
+}
+
+/// 取不到前台应用名时的归类
+pub const UNKNOWN_APP: &str = "(unknown)";
+
+#[derive(Debug, Default)]
+struct StatsInner {
+    snapshot: StatsSnapshot,
+    current_app: Option<String>,
+    /// 上一次识别结果的字数，校正替换它时从累计中扣除
+    last_final_chars: usize,
+}
+
+/// 会话延迟与用量统计，数据只保存在内存中
+pub struct StatsCollector {
+    inner: Mutex<StatsInner>,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(StatsInner {
+                snapshot: empty_snapshot(),
+                current_app: None,
+                last_final_chars: 0,
+            }),
+        }
+    }
+
+    pub fn record_start(&self, app_name: &str) {
+        let mut inner = self.inner.lock().expect("stats lock");
+        let app = match app_name.trim() {
+            "" => UNKNOWN_APP,
+            app => app,
+        };
+        inner.current_app = Some(app.to_string());
+    }
+
+    pub fn record_final(&self, audio_ms: u64, asr_ms: u64, chars: usize) {
+        let mut inner = self.inner.lock().expect("stats lock");
+        let app = inner.current_app.take().unwrap_or_else(|| UNKNOWN_APP.to_string());
+        inner.last_final_chars = chars;
+        let snapshot = &mut inner.snapshot;
+        snapshot.sessions += 1;
+        snapshot.total_audio_ms += audio_ms;
+        snapshot.total_asr_ms += asr_ms;
+        snapshot.chars_injected += chars as u64;
+        *snapshot.per_app.entry(app).or_insert(0) += 1;
+        snapshot.avg_asr_ms = snapshot.total_asr_ms / snapshot.sessions;
+    }
+
+    /// 校正结果替换上一次识别结果，字数只按替换后的文字计
+    pub fn record_correction(&self, llm_ms: u64, chars: usize) {
+        let mut inner = self.inner.lock().expect("stats lock");
+        let replaced = std::mem::replace(&mut inner.last_final_chars, chars);
+        let snapshot = &mut inner.snapshot;
+        snapshot.corrections += 1;
+        snapshot.total_llm_ms += llm_ms;
+        snapshot.chars_injected = (snapshot.chars_injected + chars as u64).saturating_sub(replaced as u64);
+        snapshot.avg_llm_ms = snapshot.total_llm_ms / snapshot.corrections;
+    }
+
+    pub fn record_traffic(&self, traffic: &TrafficStats) {
+        let mut inner = self.inner.lock().expect("stats lock");
+        let network = &mut inner.snapshot.network;
+        network.sessions += 1;
+        network.bytes_sent += traffic.bytes_sent;
+        network.bytes_received += traffic.bytes_received;
+        network.audio_packets += traffic.audio_packets;
+        network.audio_bytes += traffic.audio_bytes;
+        network.retransmitted_packets += traffic.retransmitted_packets;
+        network.dropped_packets += traffic.dropped_packets;
+        network.avg_packet_bytes = network.audio_bytes.checked_div(network.audio_packets).unwrap_or(0);
+        network.avg_bytes_per_session = (network.bytes_sent + network.bytes_received) / network.sessions;
+    }
+
+    pub fn record_failure(&self) {
+        self.inner.lock().expect("stats lock").snapshot.failures += 1;
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.inner.lock().expect("stats lock").snapshot.clone()
+    }
+
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().expect("stats lock");
+        inner.snapshot = empty_snapshot();
+        inner.last_final_chars = 0;
+    }
+}
+
+impl Default for StatsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn empty_snapshot() -> StatsSnapshot {
+    StatsSnapshot {
+        since: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        ..StatsSnapshot::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_aggregate_sessions_and_apps() {
+        let stats = StatsCollector::new();
+        stats.record_start("Slack");
+        stats.record_final(2000, 300, 10);
+        stats.record_correction(500, 11);
+        stats.record_start("Slack");
+        stats.record_final(1000, 100, 5);
+        stats.record_start("");
+        stats.record_final(1000, 200, 5);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.sessions, 3);
+        assert_eq!(snapshot.total_audio_ms, 4000);
+        assert_eq!(snapshot.avg_asr_ms, 200);
+        assert_eq!(snapshot.avg_llm_ms, 500);
+        // 校正后的 11 个字替换了识别结果的 10 个字
+        assert_eq!(snapshot.chars_injected, 21);
+        assert_eq!(snapshot.per_app.get("Slack"), Some(&2));
+        assert_eq!(snapshot.per_app.get("(unknown)"), Some(&1));
+
+        stats.reset();
+        assert_eq!(stats.snapshot().sessions, 0);
+    }
+
+    #[test]
+    fn stats_accumulate_network_usage() {
+        let stats = StatsCollector::new();
+        let mut traffic = TrafficStats::default();
+        traffic.record_text(100);
+        for _ in 0..10 {
+            traffic.record_audio(60, false);
+        }
+        traffic.record_received(50);
+        stats.record_traffic(&traffic);
+
+        let mut replay = TrafficStats::default();
+        replay.record_audio(80, true);
+        replay.record_audio(80, false);
+        replay.dropped_packets = 2;
+        stats.record_traffic(&replay);
+
+        let network = stats.snapshot().network;
+        assert_eq!(network.sessions, 2);
+        assert_eq!(network.bytes_sent, 100 + 600 + 160);
+        assert_eq!(network.bytes_received, 50);
+        assert_eq!(network.audio_packets, 12);
+        assert_eq!(network.retransmitted_packets, 1);
+        assert_eq!(network.dropped_packets, 2);
+        assert_eq!(network.avg_packet_bytes, 63);
+        assert_eq!(network.avg_bytes_per_session, 455);
+    }
+}
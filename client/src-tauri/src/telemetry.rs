@@ -0,0 +1,413 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::Notify;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{debug, warn, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::proxy;
+
+/// 未发送的 span 上限，collector 不可达时丢弃最旧的
+const MAX_QUEUED_SPANS: usize = 2048;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 每次听写的根 span，其它带 `trace_id` 的 span 都挂在它下面
+pub const SESSION_SPAN: &str = "session";
+
+/// OpenTelemetry 链路导出：每次听写是一条链路（`session` 下有 `audio_capture`、`asr`、`llm`、`inject`），
+/// 以 OTLP/HTTP（JSON）发送到 collector。span 为 INFO 级别，`GHOSTTYPE_LOG` 需允许 info。
+///
+/// 链路 ID 是会话 trace_id 的 SHA-256 前 16 字节，根 span ID 是其后 8 字节（均为小写十六进制）；
+/// 服务端按同样方式计算即可把自己的 span 挂到同一条链路上
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TelemetryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/HTTP 的 traces 地址
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// 附加的请求头，例如鉴权
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_endpoint(),
+            headers: BTreeMap::new(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
+fn default_endpoint() -> String {
+    "http://127.0.0.1:4318/v1/traces".to_string()
+}
+
+fn default_service_name() -> String {
+    "ghosttype-client".to_string()
+}
+
+/// 已结束、等待发送的 span
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    name: &'static str,
+    trace_id: String,
+    span_id: String,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+    error: Option<String>,
+}
+
+/// 进行中的 span，保存在 span 扩展里
+struct OpenSpan {
+    trace_id: Option<String>,
+    start: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+    error: Option<String>,
+}
+
+impl OpenSpan {
+    fn set(&mut self, field: &Field, value: Value) {
+        match field.name() {
+            "trace_id" => self.trace_id = value.as_str().map(str::to_string),
+            "error" => self.error = Some(value.as_str().map_or_else(|| value.to_string(), str::to_string)),
+            name => {
+                self.attributes.retain(|(key, _)| *key != name);
+                self.attributes.push((name, value));
+            }
+        }
+    }
+}
+
+impl Visit for OpenSpan {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, Value::from(format!("{value:?}")));
+    }
+}
+
+struct Exporter {
+    enabled: AtomicBool,
+    config: Mutex<TelemetryConfig>,
+    queue: Mutex<VecDeque<SpanRecord>>,
+    /// 会话结束时提前发送
+    flush: Notify,
+}
+
+impl Exporter {
+    fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            config: Mutex::new(TelemetryConfig::default()),
+            queue: Mutex::new(VecDeque::new()),
+            flush: Notify::new(),
+        }
+    }
+
+    fn push(&self, span: SpanRecord) {
+        let mut queue = self.queue.lock().expect("telemetry queue lock");
+        if queue.len() >= MAX_QUEUED_SPANS {
+            queue.pop_front();
+        }
+        queue.push_back(span);
+    }
+
+    fn take(&self) -> Vec<SpanRecord> {
+        self.queue.lock().expect("telemetry queue lock").drain(..).collect()
+    }
+}
+
+fn exporter() -> &'static Arc<Exporter> {
+    static EXPORTER: OnceLock<Arc<Exporter>> = OnceLock::new();
+    EXPORTER.get_or_init(|| Arc::new(Exporter::new()))
+}
+
+/// 收集 span 的 tracing 层，在日志初始化时加入；未启用时不记录任何内容
+pub fn layer() -> TelemetryLayer {
+    TelemetryLayer {
+        exporter: exporter().clone(),
+    }
+}
+
+/// 启动和保存配置时调用；第一次启用时开始后台发送
+pub fn install(config: TelemetryConfig) {
+    static FLUSHER: Once = Once::new();
+    let exporter = exporter();
+    let enabled = config.enabled;
+    *exporter.config.lock().expect("telemetry config lock") = config;
+    exporter.enabled.store(enabled, Ordering::SeqCst);
+    if !enabled {
+        exporter.take();
+        return;
+    }
+    FLUSHER.call_once(|| {
+        tauri::async_runtime::spawn(flush_loop(exporter.clone()));
+    });
+}
+
+async fn flush_loop(exporter: Arc<Exporter>) {
+    let client = match proxy::http_client_builder().timeout(EXPORT_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            warn!(target: "telemetry", error = %err, "链路导出初始化失败 | Trace exporter init failed");
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            _ = exporter.flush.notified() => {}
+            _ = tokio::time::sleep(FLUSH_INTERVAL) => {}
+        }
+        let spans = exporter.take();
+        let config = exporter.config.lock().expect("telemetry config lock").clone();
+        if spans.is_empty() || !config.enabled {
+            continue;
+        }
+        let mut request = client
+            .post(&config.endpoint)
+            .json(&encode(&config.service_name, &spans));
+        for (name, value) in &config.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => debug!(target: "telemetry", spans = spans.len(), "链路已发送 | Spans exported"),
+            Err(err) => warn!(
+                target: "telemetry",
+                spans = spans.len(),
+                error = %err,
+                "链路发送失败，已丢弃 | Failed to export spans, dropped"
+            ),
+        }
+    }
+}
+
+pub struct TelemetryLayer {
+    exporter: Arc<Exporter>,
+}
+
+impl<S> Layer<S> for TelemetryLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !self.exporter.enabled.load(Ordering::Relaxed) || attrs.metadata().fields().field("trace_id").is_none() {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut open = OpenSpan {
+            trace_id: None,
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+            error: None,
+        };
+        attrs.record(&mut open);
+        span.extensions_mut().insert(open);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                values.record(open);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+        let Some(trace_id) = open.trace_id.filter(|trace_id| !trace_id.is_empty()) else {
+            return;
+        };
+        let name = span.metadata().name();
+        let span_id = if name == SESSION_SPAN {
+            session_ids(&trace_id).1
+        } else {
+            child_span_id(&trace_id, name, open.start)
+        };
+        self.exporter.push(SpanRecord {
+            name,
+            trace_id,
+            span_id,
+            start: open.start,
+            end: SystemTime::now(),
+            attributes: open.attributes,
+            error: open.error,
+        });
+        if name == SESSION_SPAN {
+            self.exporter.flush.notify_one();
+        }
+    }
+}
+
+/// 会话 trace_id 对应的（OTLP 链路 ID，根 span ID）
+fn session_ids(trace_id: &str) -> (String, String) {
+    let digest = Sha256::digest(trace_id.as_bytes());
+    (hex(&digest[..16]), hex(&digest[16..24]))
+}
+
+fn child_span_id(trace_id: &str, name: &str, start: SystemTime) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let digest = Sha256::new()
+        .chain_update(trace_id)
+        .chain_update(name)
+        .chain_update(unix_nanos(start).to_le_bytes())
+        .chain_update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes())
+        .finalize();
+    hex(&digest[..8])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// OTLP/HTTP JSON 请求体（ExportTraceServiceRequest）
+fn encode(service_name: &str, spans: &[SpanRecord]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let (trace_id, root_id) = session_ids(&span.trace_id);
+            let mut attributes = vec![attribute("ghosttype.trace_id", Value::from(span.trace_id.as_str()))];
+            attributes.extend(span.attributes.iter().map(|(key, value)| attribute(key, value.clone())));
+            let mut encoded = json!({
+                "traceId": trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": 1,
+                "startTimeUnixNano": unix_nanos(span.start).to_string(),
+                "endTimeUnixNano": unix_nanos(span.end).to_string(),
+                "attributes": attributes,
+                "status": match &span.error {
+                    Some(message) => json!({ "code": 2, "message": message }),
+                    None => json!({ "code": 0 }),
+                },
+            });
+            if span.span_id != root_id {
+                encoded["parentSpanId"] = Value::from(root_id);
+            }
+            encoded
+        })
+        .collect();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", Value::from(service_name)),
+                    attribute("service.version", Value::from(env!("CARGO_PKG_VERSION"))),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "ghosttype" },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    let value = match value {
+        Value::Bool(value) => json!({ "boolValue": value }),
+        // OTLP JSON 中 int64 以字符串表示
+        Value::Number(number) if number.is_f64() => json!({ "doubleValue": number }),
+        Value::Number(number) => json!({ "intValue": number.to_string() }),
+        Value::String(value) => json!({ "stringValue": value }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn session_spans_share_a_trace_derived_from_trace_id() {
+        let exporter = Arc::new(Exporter::new());
+        exporter.enabled.store(true, Ordering::SeqCst);
+        let subscriber = tracing_subscriber::registry().with(TelemetryLayer {
+            exporter: exporter.clone(),
+        });
+        tracing::subscriber::with_default(subscriber, || {
+            let session = tracing::info_span!("session", trace_id = "abc123", app = "Notes");
+            let asr = tracing::info_span!("asr", trace_id = "abc123", error = tracing::field::Empty);
+            asr.record("error", "timeout");
+            drop(asr);
+            // 没有 trace_id 的 span 不导出
+            drop(tracing::info_span!("other", len = 3));
+            drop(session);
+        });
+
+        let spans = exporter.take();
+        let body = encode("ghosttype-client", &spans);
+        let spans = body["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        let (asr, session) = (&spans[0], &spans[1]);
+        let (trace_id, root_id) = session_ids("abc123");
+        assert_eq!(trace_id.len(), 32);
+        assert_eq!(session["traceId"], trace_id.as_str());
+        assert_eq!(session["spanId"], root_id.as_str());
+        assert!(session.get("parentSpanId").is_none());
+        assert_eq!(
+            session["attributes"][1],
+            json!({ "key": "app", "value": { "stringValue": "Notes" } })
+        );
+
+        assert_eq!(asr["traceId"], trace_id.as_str());
+        assert_eq!(asr["parentSpanId"], root_id.as_str());
+        assert_ne!(asr["spanId"], root_id.as_str());
+        assert_eq!(asr["status"], json!({ "code": 2, "message": "timeout" }));
+        assert_eq!(
+            attribute("len", Value::from(3)),
+            json!({ "key": "len", "value": { "intValue": "3" } })
+        );
+    }
+}
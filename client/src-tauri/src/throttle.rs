@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::asr::AsrConfig;
+use crate::llm::LlmConfig;
+use crate::platform::PowerSupply;
+
+/// 使用电池或散热受限时改用更轻的后端（例如用云端代替本地模型），恢复后换回配置的后端。
+/// 在每次会话开始时按当前电源状态选择
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ThrottleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 使用电池时切换
+    #[serde(default = "default_true")]
+    pub on_battery: bool,
+    /// 系统因温度过高降频时切换
+    #[serde(default = "default_true")]
+    pub on_thermal_pressure: bool,
+    /// 受限时依次尝试的 ASR 后端，使用第一个可以创建的；为空时保持配置的后端
+    #[serde(default)]
+    pub asr: Vec<AsrConfig>,
+    /// 受限时依次尝试的 LLM 后端（可以是 `disabled`）；为空时保持配置的后端
+    #[serde(default)]
+    pub llm: Vec<LlmConfig>,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            on_battery: default_true(),
+            on_thermal_pressure: default_true(),
+            asr: Vec::new(),
+            llm: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 切换到轻量后端的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleReason {
+    Battery,
+    Thermal,
+}
+
+impl ThrottleConfig {
+    /// 按电源状态判断本次会话是否需要切换；散热受限优先
+    pub fn reason(&self, supply: PowerSupply) -> Option<ThrottleReason> {
+        if !self.enabled {
+            return None;
+        }
+        if self.on_thermal_pressure && supply.thermal_pressure {
+            return Some(ThrottleReason::Thermal);
+        }
+        if self.on_battery && supply.on_battery {
+            return Some(ThrottleReason::Battery);
+        }
+        None
+    }
+}
+
+/// 受限时依次尝试的后端，跳过与配置的后端相同的项
+pub fn candidates<'a, T: PartialEq>(chain: &'a [T], configured: &'a T) -> impl Iterator<Item = &'a T> {
+    chain.iter().filter(move |candidate| *candidate != configured)
+}
+
+/// 日志中使用的 ASR 后端名称（不含密钥等配置）
+pub fn asr_name(config: &AsrConfig) -> &'static str {
+    match config {
+        AsrConfig::Native => "native",
+        AsrConfig::Cloud { .. } => "cloud",
+        AsrConfig::WebSocket { .. } => "websocket",
+        AsrConfig::Race { .. } => "race",
+    }
+}
+
+/// 日志中使用的 LLM 后端名称
+pub fn llm_name(config: &LlmConfig) -> &'static str {
+    match config {
+        LlmConfig::Disabled => "disabled",
+        LlmConfig::OpenAiCompat { .. } => "openai_compat",
+        LlmConfig::Ollama { .. } => "ollama",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_follows_power_state_and_policy() {
+        let supply = |on_battery, thermal_pressure| PowerSupply {
+            on_battery,
+            thermal_pressure,
+        };
+        let mut config = ThrottleConfig::default();
+        assert_eq!(config.reason(supply(true, true)), None);
+
+        config.enabled = true;
+        assert_eq!(config.reason(supply(false, false)), None);
+        assert_eq!(config.reason(supply(true, false)), Some(ThrottleReason::Battery));
+        assert_eq!(config.reason(supply(true, true)), Some(ThrottleReason::Thermal));
+
+        config.on_thermal_pressure = false;
+        assert_eq!(config.reason(supply(false, true)), None);
+        config.on_battery = false;
+        assert_eq!(config.reason(supply(true, true)), None);
+
+        let configured = LlmConfig::Disabled;
+        let ollama = LlmConfig::Ollama {
+            endpoint: "http://127.0.0.1:11434".to_string(),
+            model: "qwen2.5:0.5b".to_string(),
+            timeout_ms: 3000,
+        };
+        let chain = [LlmConfig::Disabled, ollama.clone()];
+        assert_eq!(candidates(&chain, &configured).collect::<Vec<_>>(), vec![&ollama]);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use serde::Serialize;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::{LookupSpan, Scope};
+use tracing_subscriber::Layer;
+
+/// 保留时间线的最近会话数
+const MAX_SESSIONS: usize = 50;
+
+/// 每个会话最多保留的事件数，超出后丢弃最早的
+const MAX_EVENTS_PER_SESSION: usize = 500;
+
+/// 一条带 trace_id 的日志事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    pub timestamp: String,
+    /// 距本会话第一条事件的毫秒数
+    pub offset_ms: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// span 上记录的会话 trace_id（保存在 span 扩展里），span 内未带 trace_id 的日志按它归属会话
+pub struct SpanTraceId(pub String);
+
+struct Session {
+    trace_id: String,
+    started: Instant,
+    events: VecDeque<TimelineEvent>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    sessions: VecDeque<Session>,
+}
+
+impl Sessions {
+    fn push(&mut self, trace_id: String, at: Instant, mut event: TimelineEvent) {
+        let index = match self.sessions.iter().rposition(|session| session.trace_id == trace_id) {
+            Some(index) => index,
+            None => {
+                if self.sessions.len() >= MAX_SESSIONS {
+                    self.sessions.pop_front();
+                }
+                self.sessions.push_back(Session {
+                    trace_id,
+                    started: at,
+                    events: VecDeque::new(),
+                });
+                self.sessions.len() - 1
+            }
+        };
+        let session = &mut self.sessions[index];
+        event.offset_ms = at.saturating_duration_since(session.started).as_millis() as u64;
+        if session.events.len() >= MAX_EVENTS_PER_SESSION {
+            session.events.pop_front();
+        }
+        session.events.push_back(event);
+    }
+
+    fn get(&self, trace_id: &str) -> Vec<TimelineEvent> {
+        self.sessions
+            .iter()
+            .rfind(|session| session.trace_id == trace_id)
+            .map(|session| session.events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn sessions() -> &'static Mutex<Sessions> {
+    static SESSIONS: OnceLock<Mutex<Sessions>> = OnceLock::new();
+    SESSIONS.get_or_init(Mutex::default)
+}
+
+/// 某次听写按发生顺序排列的日志事件；会话太早或 trace_id 不存在时为空
+pub fn session(trace_id: &str) -> Vec<TimelineEvent> {
+    sessions().lock().expect("timeline lock").get(trace_id.trim())
+}
+
+/// 事件所在的 span 链上最近的会话 trace_id
+pub fn scope_trace_id<S>(scope: Option<Scope<'_, S>>) -> Option<String>
+where
+    S: for<'a> LookupSpan<'a>,
+{
+    scope.into_iter().flatten().find_map(|span| {
+        span.extensions()
+            .get::<SpanTraceId>()
+            .map(|trace_id| trace_id.0.clone())
+    })
+}
+
+/// 收集带 trace_id 的日志事件的 tracing 层，在日志初始化时加入
+pub fn layer() -> TimelineLayer {
+    TimelineLayer
+}
+
+pub struct TimelineLayer;
+
+impl<S> Layer<S> for TimelineLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().fields().field("trace_id").is_none() {
+            return;
+        }
+        let mut fields = EventFields::default();
+        attrs.record(&mut fields);
+        if let (Some(trace_id), Some(span)) = (fields.trace_id, ctx.span(id)) {
+            span.extensions_mut().insert(SpanTraceId(trace_id));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let mut fields = EventFields::default();
+        values.record(&mut fields);
+        if let (Some(trace_id), Some(span)) = (fields.trace_id, ctx.span(id)) {
+            span.extensions_mut().replace(SpanTraceId(trace_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let at = Instant::now();
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+        let Some(trace_id) = fields
+            .trace_id
+            .take()
+            .or_else(|| scope_trace_id(ctx.event_scope(event)))
+        else {
+            return;
+        };
+        let metadata = event.metadata();
+        let event = TimelineEvent {
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            offset_ms: 0,
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: fields.message.unwrap_or_default(),
+            fields: fields.fields,
+        };
+        sessions().lock().expect("timeline lock").push(trace_id, at, event);
+    }
+}
+
+#[derive(Default)]
+struct EventFields {
+    trace_id: Option<String>,
+    message: Option<String>,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for EventFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = Some(value.to_string()),
+            // 空的 trace_id 表示不属于任何会话
+            "trace_id" => self.trace_id = Some(value.trim().to_string()).filter(|v| !v.is_empty()),
+            name => {
+                self.fields.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::info;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn events_are_grouped_by_event_or_span_trace_id() {
+        let subscriber = tracing_subscriber::registry().with(layer());
+        tracing::subscriber::with_default(subscriber, || {
+            info!(target: "pipeline", trace_id = "timeline-a", "开始录音 | Recording started");
+            info!(target: "pipeline", trace_id = "", "无会话 | No session");
+            let span = tracing::info_span!("inject", trace_id = "timeline-a").entered();
+            info!(target: "input", chars = 2, "注入完成 | Injected");
+            drop(span);
+            info!(target: "pipeline", trace_id = "timeline-b", "开始录音 | Recording started");
+        });
+
+        let timeline = session("timeline-a");
+        let messages: Vec<&str> = timeline.iter().map(|event| event.message.as_str()).collect();
+        assert_eq!(messages, ["开始录音 | Recording started", "注入完成 | Injected"]);
+        assert_eq!(timeline[1].target, "input");
+        assert_eq!(timeline[1].fields.get("chars").map(String::as_str), Some("2"));
+        assert!(timeline[0].offset_ms <= timeline[1].offset_ms);
+        assert_eq!(session("timeline-b").len(), 1);
+        assert!(session("missing").is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::BufReader;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::Connector;
+
+/// `wss://` 连接的 TLS 设置：客户端证书（mTLS）与额外信任的 CA
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TlsConfig {
+    /// 客户端证书路径：PEM（可含证书链）或 PKCS#12（.p12 / .pfx）
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM 私钥路径；证书为 PKCS#12 时不需要
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// PKCS#12 文件的密码
+    #[serde(default)]
+    pub pkcs12_password: Option<String>,
+    /// 额外信任的 CA 证书（PEM），用于自签名的服务端
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+}
+
+impl TlsConfig {
+    fn is_empty(&self) -> bool {
+        non_empty(&self.client_cert).is_none() && non_empty(&self.ca_cert).is_none()
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+static CURRENT: OnceLock<RwLock<TlsConfig>> = OnceLock::new();
+
+fn current_lock() -> &'static RwLock<TlsConfig> {
+    CURRENT.get_or_init(|| RwLock::new(TlsConfig::default()))
+}
+
+/// 启动和保存配置时调用；之后新建的连接使用新设置
+pub fn install(config: TlsConfig) {
+    *current_lock().write().expect("tls config lock") = config;
+}
+
+/// 按当前设置构造 TLS 连接器；未配置证书时返回 `None`（使用内置根证书）。
+/// 每次连接都重新读取文件，证书轮换后重连即可生效。
+pub fn connector() -> anyhow::Result<Option<Connector>> {
+    let config = current_lock().read().expect("tls config lock").clone();
+    if config.is_empty() {
+        return Ok(None);
+    }
+    let client_config = build_client_config(&config)?;
+    Ok(Some(Connector::Rustls(Arc::new(client_config))))
+}
+
+fn build_client_config(config: &TlsConfig) -> anyhow::Result<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    if let Some(path) = non_empty(&config.ca_cert) {
+        let pem = std::fs::read(path).with_context(|| format!("read CA certificate {path}"))?;
+        for cert in pem_certs(&pem).with_context(|| format!("parse CA certificate {path}"))? {
+            roots
+                .add(&cert)
+                .with_context(|| format!("add CA certificate {path}"))?;
+        }
+    }
+
+    let builder = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+
+    let Some(cert_path) = non_empty(&config.client_cert) else {
+        return Ok(builder.with_no_client_auth());
+    };
+    let data = std::fs::read(cert_path).with_context(|| format!("read client certificate {cert_path}"))?;
+    let (certs, key) = if is_pem(&data) {
+        let certs = pem_certs(&data).with_context(|| format!("parse client certificate {cert_path}"))?;
+        // 私钥可以与证书放在同一个 PEM 文件里
+        let key = match non_empty(&config.client_key) {
+            Some(key_path) => {
+                let pem = std::fs::read(key_path).with_context(|| format!("read client key {key_path}"))?;
+                pem_private_key(&pem).with_context(|| format!("parse client key {key_path}"))?
+            }
+            None => pem_private_key(&data).with_context(|| format!("parse client key in {cert_path}"))?,
+        };
+        (certs, key)
+    } else {
+        let password = config.pkcs12_password.as_deref().unwrap_or_default();
+        pkcs12_identity(&data, password).with_context(|| format!("parse PKCS#12 {cert_path}"))?
+    };
+
+    builder
+        .with_client_auth_cert(certs, key)
+        .context("invalid client certificate or key")
+}
+
+fn is_pem(data: &[u8]) -> bool {
+    data.windows(11).any(|w| w == b"-----BEGIN ")
+}
+
+fn pem_certs(pem: &[u8]) -> anyhow::Result<Vec<rustls::Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(pem))?;
+    if certs.is_empty() {
+        anyhow::bail!("no certificate found");
+    }
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+fn pem_private_key(pem: &[u8]) -> anyhow::Result<rustls::PrivateKey> {
+    for item in rustls_pemfile::read_all(&mut BufReader::new(pem))? {
+        match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(rustls::PrivateKey(key)),
+            _ => {}
+        }
+    }
+    anyhow::bail!("no private key found")
+}
+
+fn pkcs12_identity(der: &[u8], password: &str) -> anyhow::Result<(Vec<rustls::Certificate>, rustls::PrivateKey)> {
+    let pfx = p12::PFX::parse(der).map_err(|err| anyhow::anyhow!("{err:?}"))?;
+    let certs = pfx
+        .cert_x509_bags(password)
+        .map_err(|err| anyhow::anyhow!("decrypt certificates (wrong password?): {err:?}"))?;
+    let key = pfx
+        .key_bags(password)
+        .map_err(|err| anyhow::anyhow!("decrypt private key (wrong password?): {err:?}"))?
+        .into_iter()
+        .next()
+        .context("no private key found")?;
+    if certs.is_empty() {
+        anyhow::bail!("no certificate found");
+    }
+    Ok((
+        certs.into_iter().map(rustls::Certificate).collect(),
+        rustls::PrivateKey(key),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config_uses_default_connector() {
+        let config = TlsConfig {
+            client_cert: Some("  ".to_string()),
+            pkcs12_password: Some("secret".to_string()),
+            ..TlsConfig::default()
+        };
+        assert!(config.is_empty());
+        assert!(is_pem(b"junk\n-----BEGIN CERTIFICATE-----\n"));
+        assert!(!is_pem(&[0x30, 0x82, 0x01, 0x00]));
+    }
+
+    #[test]
+    fn missing_files_are_reported_with_path() {
+        let config = TlsConfig {
+            client_cert: Some("/nonexistent/ghosttype-client.pem".to_string()),
+            ..TlsConfig::default()
+        };
+        let err = build_client_config(&config).expect_err("missing cert");
+        assert!(format!("{err:#}").contains("/nonexistent/ghosttype-client.pem"));
+    }
+}
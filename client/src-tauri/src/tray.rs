@@ -0,0 +1,679 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::async_runtime::JoinHandle;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::audio_file;
+use crate::caption::CaptionController;
+use crate::config::{self, ClientConfig};
+use crate::connection::ConnectionMonitor;
+use crate::crash;
+use crate::error::{AppError, ErrorCode};
+use crate::events::PipelineEvent;
+use crate::notify::Notifier;
+use crate::platform;
+use crate::shutdown;
+use crate::warmup::Warmup;
+
+const TRAY_ID: &str = "ghosttype-tray";
+
+/// 托盘提示中展示的上次识别结果最大字符数
+const LAST_TRANSCRIPT_CHARS: usize = 40;
+
+// 图标命名：`_light` 为浅色图形（深色菜单栏/任务栏用），`_dark` 为深色图形（浅色主题用）。
+#[cfg(target_os = "macos")]
+macro_rules! tray_icon {
+    ($name:literal) => {
+        include_bytes!(concat!("../icons/", $name, "@2x.png"))
+    };
+}
+#[cfg(not(target_os = "macos"))]
+macro_rules! tray_icon {
+    ($name:literal) => {
+        include_bytes!(concat!("../icons/", $name, ".png"))
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayIcon {
+    Idle,
+    Recording,
+    Processing,
+    Error,
+    Paused,
+}
+
+impl TrayIcon {
+    /// 带颜色提示的状态不能用模板图（macOS 模板图只保留透明度）
+    fn is_template(self) -> bool {
+        cfg!(target_os = "macos") && !matches!(self, TrayIcon::Recording | TrayIcon::Error)
+    }
+
+    fn bytes(self, theme: tauri::Theme) -> &'static [u8] {
+        // macOS 模板图由系统着色，统一使用深色图形
+        let dark_glyph = self.is_template() || !matches!(theme, tauri::Theme::Dark);
+        match (self, dark_glyph) {
+            (TrayIcon::Idle, true) => tray_icon!("tray_idle_dark"),
+            (TrayIcon::Idle, false) => tray_icon!("tray_idle_light"),
+            (TrayIcon::Recording, true) => tray_icon!("tray_recording_dark"),
+            (TrayIcon::Recording, false) => tray_icon!("tray_recording_light"),
+            (TrayIcon::Processing, true) => tray_icon!("tray_processing_dark"),
+            (TrayIcon::Processing, false) => tray_icon!("tray_processing_light"),
+            (TrayIcon::Error, true) => tray_icon!("tray_error_dark"),
+            (TrayIcon::Error, false) => tray_icon!("tray_error_light"),
+            (TrayIcon::Paused, true) => tray_icon!("tray_paused_dark"),
+            (TrayIcon::Paused, false) => tray_icon!("tray_paused_light"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TrayMode {
+    Idle,
+    Recording,
+    Processing,
+}
+
+#[derive(Debug)]
+struct TrayControllerState {
+    mode: TrayMode,
+    error: bool,
+    paused: bool,
+    theme: tauri::Theme,
+    status_text: Option<String>,
+    last_transcript: Option<String>,
+    /// 预热发现不可用的服务，空闲时显示在提示中
+    degraded: Option<String>,
+    recording_started: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct TrayController {
+    app: tauri::AppHandle,
+    state: Mutex<TrayControllerState>,
+    /// 录音计时任务：每秒刷新一次录音时长
+    timer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TrayController {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self {
+            app,
+            state: Mutex::new(TrayControllerState {
+                mode: TrayMode::Idle,
+                error: false,
+                paused: false,
+                theme: tauri::Theme::Dark,
+                status_text: None,
+                last_transcript: None,
+                degraded: None,
+                recording_started: None,
+            }),
+            timer: Mutex::new(None),
+        }
+    }
+
+    pub fn set_idle(&self) {
+        self.set_mode(TrayMode::Idle);
+        self.clear_error();
+    }
+
+    pub fn set_recording(&self) {
+        self.set_mode(TrayMode::Recording);
+        self.clear_error();
+    }
+
+    pub fn set_processing(&self) {
+        self.set_mode(TrayMode::Processing);
+        self.clear_error();
+    }
+
+    pub fn set_error(&self) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.error = true;
+        drop(guard);
+        self.apply();
+    }
+
+    pub fn clear_error(&self) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.error = false;
+        drop(guard);
+        self.apply();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.paused = paused;
+        drop(guard);
+        self.apply();
+    }
+
+    /// 系统主题变化时切换图标配色
+    pub fn set_theme(&self, theme: tauri::Theme) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.theme = theme;
+        drop(guard);
+        self.apply();
+    }
+
+    /// 覆盖默认的状态文字（例如 "Correcting…"），传 `None` 恢复默认。
+    pub fn set_status_text(&self, text: Option<&str>) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.status_text = text.map(str::to_string);
+        drop(guard);
+        self.apply();
+    }
+
+    /// 在禁止听写的应用中按下热键：不报错，只在提示中说明，下次录音时恢复
+    pub fn set_blocked(&self, app_name: &str) {
+        self.set_status_text(Some(&format!("已在 {app_name} 中禁用听写 | Dictation disabled in {app_name}")));
+    }
+
+    /// 记录上次输出的文字，空闲时显示在提示中。
+    pub fn set_last_transcript(&self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.last_transcript = Some(tail_chars(text, LAST_TRANSCRIPT_CHARS));
+        drop(guard);
+        self.apply();
+    }
+
+    /// 预热发现 ASR 服务器或 LLM 不可用时在提示中说明，传 `None` 清除
+    pub fn set_degraded(&self, detail: Option<&str>) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        guard.degraded = detail.map(str::to_string);
+        drop(guard);
+        self.apply();
+    }
+
+    fn set_mode(&self, mode: TrayMode) {
+        let mut guard = self.state.lock().expect("tray state lock");
+        let was_recording = matches!(guard.mode, TrayMode::Recording);
+        guard.mode = mode;
+        if !matches!(mode, TrayMode::Idle) {
+            guard.status_text = None;
+        }
+        let is_recording = matches!(mode, TrayMode::Recording);
+        if is_recording && !was_recording {
+            guard.recording_started = Some(Instant::now());
+        } else if !is_recording {
+            guard.recording_started = None;
+        }
+        let started = guard.recording_started;
+        drop(guard);
+
+        match (was_recording, started) {
+            (false, Some(started)) => self.start_timer(started),
+            (true, None) => self.stop_timer(),
+            _ => {}
+        }
+        self.apply();
+    }
+
+    fn start_timer(&self, started: Instant) {
+        let app = self.app.clone();
+        let task = tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(tray) = app.tray_by_id(TRAY_ID) else {
+                    continue;
+                };
+                let elapsed = format_elapsed(started.elapsed());
+                if cfg!(target_os = "macos") {
+                    let _ = tray.set_title(Some(elapsed.as_str()));
+                }
+                let _ = tray.set_tooltip(Some(format!("GhostType · 录音中 | Recording {elapsed}")));
+            }
+        });
+
+        let previous = self.timer.lock().expect("tray timer lock").replace(task);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
+    fn stop_timer(&self) {
+        if let Some(task) = self.timer.lock().expect("tray timer lock").take() {
+            task.abort();
+        }
+        if cfg!(target_os = "macos") {
+            if let Some(tray) = self.app.tray_by_id(TRAY_ID) {
+                let _ = tray.set_title(None::<&str>);
+            }
+        }
+    }
+
+    fn apply(&self) {
+        let guard = self.state.lock().expect("tray state lock");
+        let icon = match (guard.mode, guard.error) {
+            (TrayMode::Recording, _) => TrayIcon::Recording,
+            (TrayMode::Idle, false) if guard.paused => TrayIcon::Paused,
+            (_, true) => TrayIcon::Error,
+            (TrayMode::Processing, false) => TrayIcon::Processing,
+            (TrayMode::Idle, false) => TrayIcon::Idle,
+        };
+        let theme = guard.theme;
+        let tooltip = tooltip_text(&guard);
+        drop(guard);
+
+        let Some(tray) = self.app.tray_by_id(TRAY_ID) else {
+            return;
+        };
+
+        let image = tauri::image::Image::from_bytes(icon.bytes(theme)).expect("tray icon");
+        if let Err(err) = tray.set_icon(Some(image)) {
+            tracing::warn!(target: "tray", error = %err, "tray icon set failed");
+        }
+        if cfg!(target_os = "macos") {
+            let _ = tray.set_icon_as_template(icon.is_template());
+        }
+        if let Err(err) = tray.set_tooltip(Some(tooltip)) {
+            tracing::warn!(target: "tray", error = %err, "tray tooltip set failed");
+        }
+    }
+}
+
+fn tooltip_text(state: &TrayControllerState) -> String {
+    if let Some(text) = state.status_text.as_deref() {
+        return format!("GhostType · {text}");
+    }
+
+    if let (TrayMode::Recording, Some(started)) = (state.mode, state.recording_started) {
+        return format!("GhostType · 录音中 | Recording {}", format_elapsed(started.elapsed()));
+    }
+
+    let status = match (state.mode, state.error) {
+        (TrayMode::Recording, _) => "录音中 | Recording",
+        (TrayMode::Idle, false) if state.paused => "已暂停 | Paused",
+        (_, true) => "出错 | Error",
+        (TrayMode::Processing, false) => "识别中… | Recognizing…",
+        (TrayMode::Idle, false) if state.degraded.is_some() => "部分服务不可用 | Degraded",
+        (TrayMode::Idle, false) => "就绪 | Ready",
+    };
+
+    let mut tooltip = format!("GhostType · {status}");
+    if matches!(state.mode, TrayMode::Idle) {
+        for line in [state.degraded.as_deref(), state.last_transcript.as_deref()].into_iter().flatten() {
+            tooltip.push('\n');
+            tooltip.push_str(line);
+        }
+    }
+    tooltip
+}
+
+/// 录音时长格式化为 `mm:ss`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// 取末尾 `max` 个字符，被截断时在前面加省略号
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(count - max).collect();
+    format!("…{tail}")
+}
+
+/// 按系统主题选择托盘图标配色，并在主题变化时实时切换
+pub fn watch_theme(app: &tauri::App, tray: Arc<TrayController>) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if let Ok(theme) = window.theme() {
+        tray.set_theme(tray_theme(theme));
+    }
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::ThemeChanged(theme) = event {
+            tray.set_theme(tray_theme(*theme));
+        }
+    });
+}
+
+fn tray_theme(window_theme: tauri::Theme) -> tauri::Theme {
+    match platform::taskbar_uses_light_theme() {
+        Some(true) => tauri::Theme::Light,
+        Some(false) => tauri::Theme::Dark,
+        None => window_theme,
+    }
+}
+
+/// 托盘菜单中 ASR 配置项的 id 前缀
+const ASR_PROFILE_PREFIX: &str = "asr-profile:";
+
+fn build_menu<M: Manager<tauri::Wry>>(
+    manager: &M,
+    config: &ClientConfig,
+    paused: bool,
+    meeting: bool,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(manager)?;
+
+    let pause = CheckMenuItem::with_id(manager, "pause", "Pause GhostType", true, paused, None::<&str>)?;
+    menu.append(&pause)?;
+    let meeting = CheckMenuItem::with_id(manager, "meeting", "Transcribe meeting", true, meeting, None::<&str>)?;
+    menu.append(&meeting)?;
+    let captions = CheckMenuItem::with_id(
+        manager,
+        "captions",
+        "Show live captions",
+        true,
+        config.captions.enabled,
+        None::<&str>,
+    )?;
+    menu.append(&captions)?;
+    menu.append(&PredefinedMenuItem::separator(manager)?)?;
+
+    if !config.asr_profiles.is_empty() {
+        let submenu = Submenu::new(manager, "ASR Backend", true)?;
+        for name in config.asr_profiles.keys() {
+            let checked = config.active_asr_profile.as_deref() == Some(name.as_str());
+            let item = CheckMenuItem::with_id(
+                manager,
+                format!("{ASR_PROFILE_PREFIX}{name}"),
+                name,
+                true,
+                checked,
+                None::<&str>,
+            )?;
+            submenu.append(&item)?;
+        }
+        menu.append(&submenu)?;
+        menu.append(&PredefinedMenuItem::separator(manager)?)?;
+    }
+
+    let retype = MenuItem::with_id(manager, "retype", "Type last result again", true, None::<&str>)?;
+    menu.append(&retype)?;
+    let undo = MenuItem::with_id(manager, "undo", "Undo last input", true, None::<&str>)?;
+    menu.append(&undo)?;
+    let transcribe = MenuItem::with_id(manager, "transcribe_file", "Transcribe audio file…", true, None::<&str>)?;
+    menu.append(&transcribe)?;
+    if crash::has_reports() {
+        let crashes = MenuItem::with_id(manager, "crashes", "Open crash reports", true, None::<&str>)?;
+        menu.append(&crashes)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(manager)?)?;
+
+    let show = MenuItem::with_id(manager, "show", "Show", true, None::<&str>)?;
+    let hide = MenuItem::with_id(manager, "hide", "Hide", true, None::<&str>)?;
+    let quit = MenuItem::with_id(manager, "quit", "Quit", true, None::<&str>)?;
+    menu.append_items(&[&show, &hide, &quit])?;
+
+    Ok(menu)
+}
+
+/// 配置变化后重建托盘菜单（ASR 配置列表/勾选状态）
+pub fn refresh_menu(app: &tauri::AppHandle, config: &ClientConfig) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app.try_state::<Arc<AppState>>();
+    let paused = state.as_ref().is_some_and(|state| state.is_paused());
+    let meeting = state.as_ref().is_some_and(|state| state.is_meeting_active());
+    match build_menu(app, config, paused, meeting) {
+        Ok(menu) => {
+            if let Err(err) = tray.set_menu(Some(menu)) {
+                tracing::warn!(target: "tray", error = %err, "tray menu set failed");
+            }
+        }
+        Err(err) => tracing::warn!(target: "tray", error = %err, "tray menu build failed"),
+    }
+}
+
+/// 切换 ASR 配置；无论成功与否都按配置文件刷新菜单勾选状态
+pub async fn switch_asr_profile(app: &tauri::AppHandle, name: &str) -> Result<(), AppError> {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return Err(AppError::new(ErrorCode::Internal, "应用尚未初始化"));
+    };
+    let state = state.inner().clone();
+
+    let result = state.switch_asr_profile(name).await;
+    match &result {
+        Ok(config) => {
+            tracing::info!(target: "tray", profile = name, "ASR 配置已切换 | ASR profile switched");
+            refresh_menu(app, config);
+            if let Some(monitor) = app.try_state::<Arc<ConnectionMonitor>>() {
+                monitor.attach(state.pipeline.lock().await.asr_connection());
+            }
+            if let Some(warmup) = app.try_state::<Arc<Warmup>>() {
+                warmup.refresh(state.clone());
+            }
+        }
+        Err(err) => {
+            tracing::warn!(
+                target: "tray",
+                profile = name,
+                error = %err,
+                "ASR 配置切换失败 | ASR profile switch failed"
+            );
+            refresh_menu(app, &config::load_with_path().0);
+        }
+    }
+    result.map(|_| ()).map_err(AppError::from)
+}
+
+/// 托盘菜单关闭后焦点回到原窗口需要一点时间
+const RETYPE_DELAY: Duration = Duration::from_millis(300);
+
+/// 重新输入上一次的结果（上次注入落到了错误窗口时使用）
+fn retype_last(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(RETYPE_DELAY).await;
+        if let Err(err) = state.retype_last().await {
+            tracing::warn!(target: "tray", error = %err, "重新输入失败 | Retype failed");
+        }
+    });
+}
+
+/// 回删上一次输入到当前应用的文字
+fn undo_last(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(RETYPE_DELAY).await;
+        if let Err(err) = state.undo_last(1).await {
+            tracing::warn!(target: "tray", error = %err, "撤销失败 | Undo failed");
+        }
+    });
+}
+
+/// 暂停/恢复听写：暂停期间热键被忽略
+fn toggle_pause(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let paused = !state.is_paused();
+    state.set_paused(paused);
+    if let Some(tray) = app.try_state::<Arc<TrayController>>() {
+        tray.set_paused(paused);
+    }
+    tracing::info!(
+        target: "tray",
+        paused = paused,
+        "听写暂停状态已切换 | Dictation pause toggled"
+    );
+    refresh_menu(app, &config::load_with_path().0);
+}
+
+/// 开关实时字幕并写回配置
+fn toggle_captions(app: &tauri::AppHandle) {
+    let (mut config, path) = config::load_with_path();
+    config.captions.enabled = !config.captions.enabled;
+    match config::save_to_path(&config, path) {
+        Ok(_) => {
+            if let Some(captions) = app.try_state::<Arc<CaptionController>>() {
+                captions.set_config(&config.captions);
+            }
+            tracing::info!(
+                target: "tray",
+                enabled = config.captions.enabled,
+                "实时字幕已切换 | Live captions toggled"
+            );
+        }
+        Err(err) => {
+            tracing::warn!(target: "tray", error = %err, "实时字幕设置保存失败 | Saving caption setting failed");
+            config.captions.enabled = !config.captions.enabled;
+        }
+    }
+    refresh_menu(app, &config);
+}
+
+/// 开始/结束会议转写；结束后的保存通知由 `MeetingSaved` 事件发出
+fn toggle_meeting(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let config = config::load_with_path().0;
+        match state.toggle_meeting(&config).await {
+            Ok(Some(path)) => tracing::info!(
+                target: "tray",
+                path = %path.display(),
+                "会议转写已开始 | Meeting transcription started"
+            ),
+            Ok(None) => tracing::info!(target: "tray", "会议转写已结束 | Meeting transcription stopped"),
+            Err(err) => {
+                tracing::warn!(
+                    target: "tray",
+                    error = %format!("{err:#}"),
+                    "会议转写无法开始 | Meeting transcription failed to start"
+                );
+                state.events.send(PipelineEvent::Failed {
+                    trace_id: None,
+                    error: AppError::with_code(ErrorCode::ServerUnreachable)(err),
+                });
+            }
+        }
+        refresh_menu(&app, &config);
+    });
+}
+
+pub fn setup_tray(app: &tauri::App, config: &ClientConfig) -> tauri::Result<()> {
+    let menu = build_menu(app, config, false, false)?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(tauri::image::Image::from_bytes(TrayIcon::Idle.bytes(tauri::Theme::Dark)).expect("tray icon"))
+        .icon_as_template(TrayIcon::Idle.is_template())
+        .tooltip("GhostType")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "quit" => shutdown::quit(app),
+            "pause" => toggle_pause(app),
+            "meeting" => toggle_meeting(app),
+            "captions" => toggle_captions(app),
+            "retype" => retype_last(app),
+            "undo" => undo_last(app),
+            "transcribe_file" => audio_file::pick_and_transcribe(app),
+            "crashes" => {
+                if let Err(err) = crash::open_folder() {
+                    tracing::warn!(
+                        target: "tray",
+                        error = %err,
+                        "崩溃报告目录打开失败 | Failed to open crash reports folder"
+                    );
+                }
+            }
+            "show" => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+                if let Some(notifier) = app.try_state::<Arc<Notifier>>() {
+                    notifier.open_pending_settings();
+                }
+            }
+            "hide" => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+            id => {
+                if let Some(name) = id.strip_prefix(ASR_PROFILE_PREFIX) {
+                    let app = app.clone();
+                    let name = name.to_string();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = switch_asr_profile(&app, &name).await;
+                    });
+                }
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_chars_keeps_short_text_and_truncates_long_text() {
+        assert_eq!(tail_chars("你好", 40), "你好");
+        assert_eq!(tail_chars("abcdef", 3), "…def");
+    }
+
+    #[test]
+    fn format_elapsed_renders_minutes_and_seconds() {
+        assert_eq!(format_elapsed(Duration::from_secs(7)), "00:07");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "02:05");
+    }
+
+    #[test]
+    fn tooltip_shows_paused_only_when_idle() {
+        let mut state = TrayControllerState {
+            mode: TrayMode::Idle,
+            error: false,
+            paused: true,
+            theme: tauri::Theme::Dark,
+            status_text: None,
+            last_transcript: None,
+            degraded: None,
+            recording_started: None,
+        };
+        assert_eq!(tooltip_text(&state), "GhostType · 已暂停 | Paused");
+
+        state.mode = TrayMode::Processing;
+        assert_eq!(tooltip_text(&state), "GhostType · 识别中… | Recognizing…");
+    }
+
+    #[test]
+    fn tooltip_lists_degraded_services_when_idle() {
+        let mut state = TrayControllerState {
+            mode: TrayMode::Idle,
+            error: false,
+            paused: false,
+            theme: tauri::Theme::Dark,
+            status_text: None,
+            last_transcript: Some("你好".to_string()),
+            degraded: Some("ASR 服务器不可用 | ASR server unreachable".to_string()),
+            recording_started: None,
+        };
+        assert_eq!(
+            tooltip_text(&state),
+            "GhostType · 部分服务不可用 | Degraded\nASR 服务器不可用 | ASR server unreachable\n你好"
+        );
+
+        state.mode = TrayMode::Processing;
+        assert_eq!(tooltip_text(&state), "GhostType · 识别中… | Recognizing…");
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::notify::Notifier;
+use crate::proxy;
+
+/// 更新检查设置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UpdateConfig {
+    /// 启动后在后台定期检查更新，发现新版本时发送系统通知
+    #[serde(default)]
+    pub auto_check: bool,
+    #[serde(default = "default_feed_url")]
+    pub feed_url: String,
+    #[serde(default = "default_interval_hours")]
+    pub interval_hours: u64,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            auto_check: false,
+            feed_url: default_feed_url(),
+            interval_hours: default_interval_hours(),
+        }
+    }
+}
+
+fn default_feed_url() -> String {
+    "https://api.github.com/repos/Yida-git/GhostType/releases/latest".to_string()
+}
+
+fn default_interval_hours() -> u64 {
+    24
+}
+
+/// 更新检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    pub release_notes: String,
+    pub download_url: String,
+    pub published_at: Option<String>,
+}
+
+/// GitHub Releases API 格式（只取用到的字段）
+#[derive(Debug, Deserialize)]
+struct ReleaseResponse {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    html_url: String,
+    #[serde(default)]
+    published_at: Option<String>,
+}
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn check(feed_url: &str) -> anyhow::Result<UpdateInfo> {
+    let client = proxy::http_client_builder()
+        .user_agent(concat!("GhostType/", env!("CARGO_PKG_VERSION")))
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("build http client")?;
+
+    let resp = client
+        .get(feed_url)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .context("send update request")?;
+
+    let status = resp.status();
+    let body = resp.text().await.context("read update response")?;
+    if !status.is_success() {
+        anyhow::bail!("update feed http error: status={status}");
+    }
+
+    let release = serde_json::from_str::<ReleaseResponse>(&body).context("parse update feed json")?;
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim().trim_start_matches('v').to_string();
+
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        update_available: is_newer(&latest, current),
+        latest_version: latest,
+        release_notes: release.body.unwrap_or_default(),
+        download_url: release.html_url,
+        published_at: release.published_at,
+    })
+}
+
+/// 后台定期检查；同一版本只通知一次
+pub fn spawn_background_check(config: UpdateConfig, notifier: Arc<Notifier>) {
+    let interval = Duration::from_secs(config.interval_hours.max(1) * 3600);
+    tauri::async_runtime::spawn(async move {
+        let mut notified: Option<String> = None;
+        loop {
+            match check(&config.feed_url).await {
+                Ok(info) if info.update_available => {
+                    if notified.as_deref() != Some(info.latest_version.as_str()) {
+                        info!(
+                            target: "update",
+                            current = info.current_version.as_str(),
+                            latest = info.latest_version.as_str(),
+                            "发现新版本 | Update available"
+                        );
+                        notifier.update_available(&info);
+                        notified = Some(info.latest_version);
+                    }
+                }
+                Ok(info) => {
+                    debug!(target: "update", latest = info.latest_version.as_str(), "已是最新版本 | Up to date");
+                }
+                Err(err) => {
+                    warn!(target: "update", error = %err, "检查更新失败 | Update check failed");
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// 比较 `major.minor.patch[-pre]`：数字逐段比较；同版本号时正式版高于预发布版
+fn is_newer(latest: &str, current: &str) -> bool {
+    let (latest_core, latest_pre) = split_version(latest);
+    let (current_core, current_pre) = split_version(current);
+
+    match latest_core.cmp(&current_core) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => match (latest_pre, current_pre) {
+            (None, Some(_)) => true,
+            (Some(latest), Some(current)) => latest > current,
+            _ => false,
+        },
+    }
+}
+
+fn split_version(raw: &str) -> (Vec<u64>, Option<&str>) {
+    let raw = raw.trim().trim_start_matches('v');
+    let (core, pre) = match raw.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (raw, None),
+    };
+    let mut parts: Vec<u64> = core
+        .split('.')
+        .map(|part| part.trim().parse::<u64>().unwrap_or(0))
+        .collect();
+    parts.resize(3.max(parts.len()), 0);
+    (parts, pre)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_newer_compares_numeric_parts_and_prereleases() {
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("v0.10.0", "0.9.3"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(is_newer("0.2.0", "0.2.0-beta.1"));
+        assert!(!is_newer("0.2.0-beta.1", "0.2.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0", "0.1.1"));
+    }
+}
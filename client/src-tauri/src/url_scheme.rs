@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use crate::control::ControlAction;
+
+/// macOS 上注册的 URL scheme（见 Info.plist），可在快捷指令、Raycast、AppleScript 与 `open` 命令中使用
+pub const SCHEME: &str = "ghosttype";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlAction {
+    /// `ghosttype://start`、`stop`、`toggle`、`cancel`：与本地控制接口相同
+    Control(ControlAction),
+    /// `ghosttype://transcribe-clipboard`：识别剪贴板中路径指向的 WAV 文件，结果写回剪贴板
+    TranscribeClipboard,
+}
+
+/// 解析 `ghosttype://<动作>`；也接受 `ghosttype:<动作>` 与 `ghosttype:///<动作>`，忽略查询参数与大小写
+pub fn parse(url: &str) -> Result<UrlAction, String> {
+    let url = url.trim();
+    let rest = url
+        .split_once(':')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| format!("不是 {SCHEME}:// 链接 | Not a {SCHEME}:// URL: {url}"))?;
+    let action = rest
+        .trim_start_matches('/')
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_ascii_lowercase();
+    match action.as_str() {
+        "start" => Ok(UrlAction::Control(ControlAction::Start)),
+        "stop" => Ok(UrlAction::Control(ControlAction::Stop)),
+        "toggle" => Ok(UrlAction::Control(ControlAction::Toggle)),
+        "cancel" => Ok(UrlAction::Control(ControlAction::Cancel)),
+        "transcribe-clipboard" => Ok(UrlAction::TranscribeClipboard),
+        _ => Err(format!("未知动作 | Unknown action: {action}")),
+    }
+}
+
+/// 剪贴板中的文字作为音频文件路径：普通路径或 `file://` URL（解码 `%XX`）
+pub fn clipboard_audio_path(text: &str) -> Option<PathBuf> {
+    let text = text.trim().trim_matches('"');
+    if text.is_empty() || text.contains('\n') {
+        return None;
+    }
+    match text.strip_prefix("file://") {
+        Some(path) => {
+            // file://localhost/Users/... 与 file:///Users/... 等价
+            let path = path.strip_prefix("localhost").unwrap_or(path);
+            Some(PathBuf::from(percent_decode(path)?))
+        }
+        None => Some(PathBuf::from(text)),
+    }
+}
+
+fn percent_decode(raw: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut iter = raw.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let hex = [iter.next()?, iter.next()?];
+        let hex = std::str::from_utf8(&hex).ok()?;
+        bytes.push(u8::from_str_radix(hex, 16).ok()?);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_and_clipboard_paths_are_parsed() {
+        assert_eq!(
+            parse("ghosttype://toggle"),
+            Ok(UrlAction::Control(ControlAction::Toggle))
+        );
+        assert_eq!(
+            parse("GhostType:///Start/?source=shortcuts"),
+            Ok(UrlAction::Control(ControlAction::Start))
+        );
+        assert_eq!(
+            parse("ghosttype:transcribe-clipboard"),
+            Ok(UrlAction::TranscribeClipboard)
+        );
+        assert!(parse("ghosttype://status").is_err());
+        assert!(parse("https://example.com/start").is_err());
+
+        assert_eq!(
+            clipboard_audio_path("file:///Users/me/Voice%20Memos/%E4%BC%9A%E8%AE%AE.wav"),
+            Some(PathBuf::from("/Users/me/Voice Memos/会议.wav"))
+        );
+        assert_eq!(
+            clipboard_audio_path(" \"/tmp/a b.wav\"\n"),
+            Some(PathBuf::from("/tmp/a b.wav"))
+        );
+        assert_eq!(clipboard_audio_path("first line\nsecond line"), None);
+        assert_eq!(clipboard_audio_path("file:///bad%zz"), None);
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// 正则编译后的大小上限，与后处理规则一致
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// 每个应用保留的输入记录条数
+const HISTORY_PER_APP: usize = 20;
+
+/// 内置说法，比较前去掉首尾标点与空白并转小写
+const BUILTIN_PHRASES: &[(&str, EditAction)] = &[
+    ("delete that", EditAction::DeleteLast),
+    ("scratch that", EditAction::DeleteLast),
+    ("删掉", EditAction::DeleteLast),
+    ("删除", EditAction::DeleteLast),
+    ("删掉刚才那句", EditAction::DeleteLast),
+    ("删除上一句", EditAction::DeleteLast),
+    ("undo", EditAction::DeleteLast),
+    ("undo that", EditAction::DeleteLast),
+    ("撤销", EditAction::DeleteLast),
+    ("撤销上一句", EditAction::DeleteLast),
+    ("select that", EditAction::SelectLast),
+    ("选中", EditAction::SelectLast),
+    ("选中刚才那句", EditAction::SelectLast),
+    ("new line", EditAction::Newline),
+    ("换行", EditAction::Newline),
+    ("另起一行", EditAction::Newline),
+    ("teach that", EditAction::Teach),
+    ("learn that", EditAction::Teach),
+    ("记住这个", EditAction::Teach),
+    ("记住修改", EditAction::Teach),
+];
+
+/// 内置的带次数的回删说法（"undo last 3"、"删掉最后两句"），次数在 `count` 组中，省略时为 1
+const COUNTED_PATTERNS: &[&str] = &[
+    "(?:undo|delete|scratch|remove)(?: the)? last(?: (?P<count>\\d+|one|two|three|four|five|six|seven|eight|nine|ten))?\
+     (?: (?:ones|times|entries|sentences|segments))?",
+    r"(?:撤销|删掉|删除)(?:最后|最近|前面)?(?P<count>[0-9一二两三四五六七八九十]+)(?:句|段|次)",
+];
+
+/// 语音编辑命令：整句识别结果是命令说法时执行编辑操作，不输入文字、不做 LLM 校正
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct VoiceCommandConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 额外的说法：正则需匹配整句（大小写不敏感），在内置说法之后检查
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phrases: Vec<CommandPhrase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CommandPhrase {
+    pub pattern: String,
+    pub action: EditAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditAction {
+    /// 删除当前应用中上一次（或最近几次）输入的内容
+    DeleteLast,
+    /// 选中当前应用中上一次输入的内容
+    SelectLast,
+    /// 输入换行（回车）
+    Newline,
+    /// 把选中的（用户改过的）文字与上一次输出比较，记入个人词典
+    Teach,
+}
+
+/// 识别出的编辑命令；`count` 为回删的输入次数，其它命令为 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub action: EditAction,
+    pub count: usize,
+}
+
+/// 编译后的命令说法；未启用时不识别任何命令
+#[derive(Debug, Default)]
+pub struct CommandSet {
+    enabled: bool,
+    counted: Vec<Regex>,
+    custom: Vec<(Regex, EditAction)>,
+}
+
+impl CommandSet {
+    pub fn compile(config: &VoiceCommandConfig) -> anyhow::Result<Self> {
+        let custom = config
+            .phrases
+            .iter()
+            .enumerate()
+            .map(|(index, phrase)| {
+                RegexBuilder::new(&format!("^(?:{})$", phrase.pattern))
+                    .case_insensitive(true)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .map(|regex| (regex, phrase.action))
+                    .with_context(|| format!("命令说法 #{} 无法解析: {}", index + 1, phrase.pattern))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let counted = COUNTED_PATTERNS
+            .iter()
+            .map(|pattern| Regex::new(&format!("^(?:{pattern})$")).context("内置回删说法无法解析"))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            enabled: config.enabled,
+            counted,
+            custom,
+        })
+    }
+
+    /// 整句是命令说法时返回对应的操作；自定义说法可用 `count` 组指定回删次数
+    pub fn recognize(&self, text: &str) -> Option<Command> {
+        if !self.enabled {
+            return None;
+        }
+        let normalized = normalize(text);
+        if normalized.is_empty() {
+            return None;
+        }
+        if let Some((_, action)) = BUILTIN_PHRASES.iter().find(|(phrase, _)| *phrase == normalized) {
+            return Some(Command {
+                action: *action,
+                count: 1,
+            });
+        }
+        let counted = self.counted.iter().map(|regex| (regex, EditAction::DeleteLast));
+        let custom = self.custom.iter().map(|(regex, action)| (regex, *action));
+        counted.chain(custom).find_map(|(regex, action)| {
+            let captures = regex.captures(&normalized)?;
+            let count = match captures.name("count") {
+                Some(count) => parse_count(count.as_str())?,
+                None => 1,
+            };
+            Some(Command { action, count })
+        })
+    }
+}
+
+/// 解析说法中的次数（阿拉伯数字、英文或中文数字），限制在输入记录条数以内
+fn parse_count(text: &str) -> Option<usize> {
+    const WORDS: &[&str] = &["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"];
+    const DIGITS: &str = "一二三四五六七八九";
+    let count = if let Ok(count) = text.parse::<usize>() {
+        count
+    } else if let Some(index) = WORDS.iter().position(|word| *word == text) {
+        index + 1
+    } else {
+        // 中文数字只支持到九十九（"两"同"二"）
+        let digit = |c: char| match c {
+            '两' => Some(2),
+            c => DIGITS.chars().position(|d| d == c).map(|index| index + 1),
+        };
+        let chars: Vec<char> = text.chars().collect();
+        match chars.as_slice() {
+            ['十'] => 10,
+            ['十', ones] => 10 + digit(*ones)?,
+            [tens, '十'] => digit(*tens)? * 10,
+            [tens, '十', ones] => digit(*tens)? * 10 + digit(*ones)?,
+            [ones] => digit(*ones)?,
+            _ => return None,
+        }
+    };
+    (count > 0).then_some(count.min(HISTORY_PER_APP))
+}
+
+/// 去掉首尾的标点与空白，中间的连续空白合并为一个，英文转小写
+pub fn normalize(text: &str) -> String {
+    text.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation() || "。，、！？；：…".contains(c))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[derive(Debug, Clone)]
+struct Injected {
+    trace_id: Option<String>,
+    text: String,
+    chars: usize,
+    /// 已被"选中"命令选中，再次输入会替换掉它
+    selected: bool,
+}
+
+/// 一次撤销：需要退格的次数与被删掉的文字（按输入顺序拼接）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Undo {
+    pub entries: usize,
+    pub backspaces: usize,
+    pub text: String,
+}
+
+/// 按目标应用记录最近输入的文字，供编辑命令回删或选中
+#[derive(Debug, Default)]
+pub struct InjectionHistory {
+    apps: BTreeMap<String, Vec<Injected>>,
+}
+
+impl InjectionHistory {
+    /// 记录一次输入；上一条处于选中状态时已被这次输入替换，一并移除
+    pub fn record(&mut self, app_name: &str, trace_id: Option<String>, text: &str) {
+        let entries = self.apps.entry(app_name.to_string()).or_default();
+        if entries.last().is_some_and(|entry| entry.selected) {
+            entries.pop();
+        }
+        entries.push(Injected {
+            trace_id,
+            text: text.to_string(),
+            chars: text.chars().count(),
+            selected: false,
+        });
+        if entries.len() > HISTORY_PER_APP {
+            entries.remove(0);
+        }
+    }
+
+    /// 校正替换后更新文字；只更新仍是最后一条的同一会话，返回是否已更新
+    pub fn update(&mut self, app_name: &str, trace_id: Option<&str>, text: &str) -> bool {
+        let last = self.apps.get_mut(app_name).and_then(|entries| entries.last_mut());
+        match last.filter(|entry| entry.trace_id.as_deref() == trace_id && !entry.selected) {
+            Some(entry) => {
+                entry.text = text.to_string();
+                entry.chars = text.chars().count();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 从最后一条起移除至多 `count` 条；没有记录时返回 `None`
+    pub fn undo(&mut self, app_name: &str, count: usize) -> Option<Undo> {
+        let entries = self.apps.get_mut(app_name)?;
+        let count = count.min(entries.len());
+        if count == 0 {
+            return None;
+        }
+        let removed: Vec<Injected> = entries.drain(entries.len() - count..).collect();
+        Some(Undo {
+            entries: count,
+            // 只有最后一条可能处于选中状态，一次退格删掉选中的内容
+            backspaces: removed
+                .iter()
+                .map(|entry| if entry.selected { 1 } else { entry.chars })
+                .sum(),
+            text: removed.iter().map(|entry| entry.text.as_str()).collect(),
+        })
+    }
+
+    /// 标记最后一条为已选中，返回需要选中的字数；已选中时返回 `None`
+    pub fn select(&mut self, app_name: &str) -> Option<usize> {
+        let entry = self.apps.get_mut(app_name)?.last_mut().filter(|entry| !entry.selected)?;
+        entry.selected = true;
+        Some(entry.chars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_match_whole_utterance_only() {
+        let config: VoiceCommandConfig = serde_json::from_str(
+            r#"{ "enabled": true, "phrases": [{ "pattern": "(never ?mind|算了)", "action": "delete_last" }] }"#,
+        )
+        .expect("config");
+        let commands = CommandSet::compile(&config).expect("compile");
+        let action = |text: &str| commands.recognize(text).map(|command| command.action);
+        assert_eq!(action("Scratch that."), Some(EditAction::DeleteLast));
+        assert_eq!(action(" 换行。"), Some(EditAction::Newline));
+        assert_eq!(action("Select  that!"), Some(EditAction::SelectLast));
+        assert_eq!(action("Never mind"), Some(EditAction::DeleteLast));
+        assert_eq!(action("please delete that file"), None);
+        assert_eq!(CommandSet::default().recognize("delete that"), None);
+
+        let invalid = VoiceCommandConfig {
+            enabled: true,
+            phrases: vec![CommandPhrase {
+                pattern: "(".to_string(),
+                action: EditAction::Newline,
+            }],
+        };
+        assert!(CommandSet::compile(&invalid).is_err());
+
+        let mut history = InjectionHistory::default();
+        history.record("Notes", Some("t1".to_string()), "Hello");
+        history.record("Notes", Some("t2".to_string()), " wrold.");
+        assert!(history.update("Notes", Some("t2"), " world!!!"));
+        assert!(!history.update("Notes", Some("t1"), "ignored"));
+        assert_eq!(history.select("Notes"), Some(9));
+        assert_eq!(history.select("Notes"), None);
+        let mut pop = |app: &str| history.undo(app, 1).map(|undo| undo.backspaces);
+        assert_eq!(pop("Notes"), Some(1));
+        assert_eq!(pop("Notes"), Some(5));
+        assert_eq!(pop("Notes"), None);
+        assert_eq!(pop("Mail"), None);
+    }
+
+    #[test]
+    fn undo_counts_are_parsed_and_remove_several_entries() {
+        let config: VoiceCommandConfig = serde_json::from_str(
+            r#"{ "enabled": true, "phrases": [{ "pattern": "back (?P<count>\\d+)", "action": "delete_last" }] }"#,
+        )
+        .expect("config");
+        let commands = CommandSet::compile(&config).expect("compile");
+        let count = |text: &str| commands.recognize(text).map(|command| command.count);
+        assert_eq!(count("Undo."), Some(1));
+        assert_eq!(count("Undo last"), Some(1));
+        assert_eq!(count("undo last 3"), Some(3));
+        assert_eq!(count("Delete the last two sentences."), Some(2));
+        assert_eq!(count("删掉最后两句。"), Some(2));
+        assert_eq!(count("撤销十二次"), Some(12));
+        assert_eq!(count("undo last 500"), Some(HISTORY_PER_APP));
+        assert_eq!(count("back 4"), Some(4));
+        assert_eq!(count("undo last 0"), None);
+
+        let mut history = InjectionHistory::default();
+        history.record("Notes", Some("t1".to_string()), "One.");
+        history.record("Notes", Some("t2".to_string()), " Two.");
+        history.record("Notes", Some("t3".to_string()), "\n");
+        assert_eq!(history.select("Notes"), Some(1));
+        assert_eq!(
+            history.undo("Notes", 2),
+            Some(Undo {
+                entries: 2,
+                backspaces: 6,
+                text: " Two.\n".to_string(),
+            })
+        );
+        assert_eq!(
+            history.undo("Notes", 5).map(|undo| (undo.entries, undo.backspaces)),
+            Some((1, 4))
+        );
+        assert_eq!(history.undo("Notes", 1), None);
+    }
+}
@@ -0,0 +1,303 @@
+#[cfg(feature = "wake-word")]
+mod openwakeword;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::audio;
+use crate::segment;
+
+/// 检测器使用的采样率
+const DETECTOR_SAMPLE_RATE: u32 = 16000;
+
+/// 唤醒后迟迟没有开口，按这个时长结束
+const NO_SPEECH_MS: u64 = 5000;
+
+/// 唤醒词：常驻监听麦克风，听到唤醒词后免按键开始一次听写，说完停顿后自动结束。
+///
+/// 只在本机处理：检测在本进程内完成，不联网、不写盘；唤醒前的音频检测完即丢弃，
+/// 不会发送给 ASR 服务，也不会进入历史记录。模型只从本地路径加载，不会自动下载。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WakeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 唤醒词模型（openWakeWord `.onnx`，例如 `hey_ghost.onnx`）的本地路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// `melspectrogram.onnx` 与 `embedding_model.onnx` 所在目录；为空时与唤醒词模型同目录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub models_dir: Option<String>,
+    /// 0~1，越高越不容易误触发
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+    /// 说完后停顿多久结束录音
+    #[serde(default = "default_end_silence_ms")]
+    pub end_silence_ms: u64,
+    /// 唤醒后最长录音时长
+    #[serde(default = "default_max_session_ms")]
+    pub max_session_ms: u64,
+}
+
+impl Default for WakeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            models_dir: None,
+            threshold: default_threshold(),
+            end_silence_ms: default_end_silence_ms(),
+            max_session_ms: default_max_session_ms(),
+        }
+    }
+}
+
+fn default_threshold() -> f32 {
+    0.5
+}
+
+fn default_end_silence_ms() -> u64 {
+    1200
+}
+
+fn default_max_session_ms() -> u64 {
+    30_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeEvent {
+    /// 听到唤醒词，开始听写
+    Detected,
+    /// 说完停顿（或超时），结束听写
+    Ended,
+}
+
+/// 唤醒词检测器：输入 16kHz PCM，每攒够一个分析窗口返回一次得分（0~1）
+pub trait WakeDetector: Send {
+    fn process(&mut self, pcm: &[i16]) -> anyhow::Result<Option<f32>>;
+
+    /// 清空内部缓冲，避免结束后被残留的特征再次触发
+    fn reset(&mut self);
+}
+
+/// 创建检测器；构建时未启用 `wake-word` 特性时返回错误
+pub fn create_detector(config: &WakeConfig) -> anyhow::Result<Box<dyn WakeDetector>> {
+    let Some(model) = config.model.as_deref().map(str::trim).filter(|m| !m.is_empty()) else {
+        anyhow::bail!("未设置唤醒词模型（wake.model）");
+    };
+    let model = PathBuf::from(model);
+    if !model.is_file() {
+        anyhow::bail!("唤醒词模型不存在: {}", model.display());
+    }
+    let models_dir = match config.models_dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => model.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+    build_detector(&model, &models_dir)
+}
+
+#[cfg(feature = "wake-word")]
+fn build_detector(model: &Path, models_dir: &Path) -> anyhow::Result<Box<dyn WakeDetector>> {
+    Ok(Box::new(openwakeword::OpenWakeWord::load(model, models_dir)?))
+}
+
+#[cfg(not(feature = "wake-word"))]
+fn build_detector(_model: &Path, _models_dir: &Path) -> anyhow::Result<Box<dyn WakeDetector>> {
+    anyhow::bail!("此版本未包含唤醒词支持（需以 --features wake-word 构建）")
+}
+
+/// 在独立线程上常驻录音与检测；检测器无法创建或麦克风不可用时只记录警告
+pub fn spawn_listener(
+    config: WakeConfig,
+    audio_device: Option<String>,
+    on_event: impl Fn(WakeEvent) + Send + 'static,
+) {
+    let detector = match create_detector(&config) {
+        Ok(detector) => detector,
+        Err(err) => {
+            warn!(
+                target: "wake",
+                error = %format!("{err:#}"),
+                "唤醒词未启用 | Wake word disabled"
+            );
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        let (recorder, mut frames) = match audio::start_audio("wake".to_string(), audio_device) {
+            Ok(parts) => parts,
+            Err(err) => {
+                warn!(
+                    target: "wake",
+                    error = %err,
+                    "唤醒词监听无法打开麦克风 | Wake word listener could not open the microphone"
+                );
+                return;
+            }
+        };
+        info!(target: "wake", "唤醒词监听已开启 | Wake word listener started");
+        let mut listener = Listener::new(&config, detector, recorder.sample_rate);
+        while let Some(frame) = frames.blocking_recv() {
+            if let Some(event) = listener.push(&frame) {
+                on_event(event);
+            }
+        }
+        recorder.stop();
+    });
+}
+
+#[derive(Debug)]
+enum ListenState {
+    Waiting,
+    Dictating { samples: u64, silent: u64, voiced: bool },
+}
+
+/// 等待唤醒 → 听写 → 停顿后结束，再回到等待
+struct Listener {
+    detector: Box<dyn WakeDetector>,
+    threshold: f32,
+    sample_rate: u32,
+    end_silence: u64,
+    max_session: u64,
+    no_speech: u64,
+    state: ListenState,
+}
+
+impl Listener {
+    fn new(config: &WakeConfig, detector: Box<dyn WakeDetector>, sample_rate: u32) -> Self {
+        let to_samples = |ms: u64| ms * u64::from(sample_rate) / 1000;
+        Self {
+            detector,
+            threshold: config.threshold.clamp(0.05, 0.99),
+            sample_rate,
+            end_silence: to_samples(config.end_silence_ms.max(300)),
+            max_session: to_samples(config.max_session_ms.max(1000)),
+            no_speech: to_samples(NO_SPEECH_MS),
+            state: ListenState::Waiting,
+        }
+    }
+
+    fn push(&mut self, pcm: &[i16]) -> Option<WakeEvent> {
+        match &mut self.state {
+            ListenState::Waiting => {
+                let pcm = resample_to_16k(pcm, self.sample_rate);
+                let score = match self.detector.process(&pcm) {
+                    Ok(score) => score?,
+                    Err(err) => {
+                        debug!(target: "wake", error = %err, "唤醒词检测失败 | Wake word detection failed");
+                        return None;
+                    }
+                };
+                if score < self.threshold {
+                    return None;
+                }
+                info!(target: "wake", score = score, "听到唤醒词 | Wake word detected");
+                self.state = ListenState::Dictating {
+                    samples: 0,
+                    silent: 0,
+                    voiced: false,
+                };
+                Some(WakeEvent::Detected)
+            }
+            ListenState::Dictating { samples, silent, voiced } => {
+                *samples += pcm.len() as u64;
+                if segment::is_silent(pcm) {
+                    *silent += pcm.len() as u64;
+                } else {
+                    *silent = 0;
+                    *voiced = true;
+                }
+                let done = (*voiced && *silent >= self.end_silence)
+                    || (!*voiced && *samples >= self.no_speech)
+                    || *samples >= self.max_session;
+                if !done {
+                    return None;
+                }
+                self.detector.reset();
+                self.state = ListenState::Waiting;
+                Some(WakeEvent::Ended)
+            }
+        }
+    }
+}
+
+/// 录音设备的采样率转换到 16kHz（线性插值；48kHz 时按 3 点平均抽取）
+fn resample_to_16k(pcm: &[i16], sample_rate: u32) -> Vec<i16> {
+    match sample_rate {
+        DETECTOR_SAMPLE_RATE => pcm.to_vec(),
+        48000 => pcm
+            .chunks(3)
+            .map(|chunk| (chunk.iter().map(|&s| i32::from(s)).sum::<i32>() / chunk.len() as i32) as i16)
+            .collect(),
+        rate if rate > 0 => {
+            let out_len = pcm.len() * DETECTOR_SAMPLE_RATE as usize / rate as usize;
+            let step = rate as f64 / DETECTOR_SAMPLE_RATE as f64;
+            (0..out_len)
+                .map(|i| {
+                    let pos = i as f64 * step;
+                    let index = pos as usize;
+                    let frac = pos - index as f64;
+                    let a = f64::from(pcm[index.min(pcm.len() - 1)]);
+                    let b = f64::from(pcm[(index + 1).min(pcm.len() - 1)]);
+                    (a + (b - a) * frac) as i16
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 收到的第 `fire_at` 帧返回满分
+    struct ScriptedDetector {
+        calls: usize,
+        fire_at: usize,
+        resets: usize,
+    }
+
+    impl WakeDetector for ScriptedDetector {
+        fn process(&mut self, pcm: &[i16]) -> anyhow::Result<Option<f32>> {
+            assert_eq!(pcm.len(), 320, "48kHz 帧应转换为 16kHz");
+            self.calls += 1;
+            Ok(Some(if self.calls == self.fire_at { 0.9 } else { 0.1 }))
+        }
+
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+    }
+
+    #[test]
+    fn listener_starts_on_wake_word_and_ends_after_pause() {
+        let config = WakeConfig {
+            enabled: true,
+            end_silence_ms: 400,
+            ..WakeConfig::default()
+        };
+        let detector = ScriptedDetector {
+            calls: 0,
+            fire_at: 3,
+            resets: 0,
+        };
+        let mut listener = Listener::new(&config, Box::new(detector), 48000);
+        let speech = [8000i16; 960];
+        let silence = [0i16; 960];
+
+        assert_eq!(listener.push(&speech), None);
+        assert_eq!(listener.push(&speech), None);
+        assert_eq!(listener.push(&speech), Some(WakeEvent::Detected));
+        // 听写中不再检测唤醒词，说话后停顿 400ms 结束
+        assert!((0..10).all(|_| listener.push(&speech).is_none()));
+        assert!((0..19).all(|_| listener.push(&silence).is_none()));
+        assert_eq!(listener.push(&silence), Some(WakeEvent::Ended));
+        assert_eq!(listener.push(&speech), None);
+
+        assert_eq!(resample_to_16k(&[3, 6, 9, 30, 30, 30], 48000), vec![6, 30]);
+        assert_eq!(resample_to_16k(&[0; 480], 24000).len(), 320);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use anyhow::Context as _;
+use ort::session::Session;
+use ort::value::Tensor;
+
+use super::WakeDetector;
+
+/// 每次送入特征模型的新音频（80ms）
+const CHUNK_SAMPLES: usize = 1280;
+/// 梅尔谱窗口需要的上文（3 个 10ms 帧移）
+const CONTEXT_SAMPLES: usize = 480;
+const MEL_BINS: usize = 32;
+/// 一个 embedding 覆盖的梅尔帧数
+const EMBEDDING_WINDOW: usize = 76;
+const EMBEDDING_DIM: usize = 96;
+/// 唤醒词模型默认输入的 embedding 数（约 1.3 秒）
+const DEFAULT_KEYWORD_FRAMES: usize = 16;
+
+/// openWakeWord：梅尔谱 → 语音 embedding → 唤醒词分类，三个模型都在本地 ONNX Runtime 中运行
+pub struct OpenWakeWord {
+    melspectrogram: Session,
+    embedding: Session,
+    keyword: Session,
+    keyword_frames: usize,
+    /// 尚未处理的音频（int16 数值的 f32）
+    pending: Vec<f32>,
+    context: Vec<f32>,
+    mels: VecDeque<[f32; MEL_BINS]>,
+    embeddings: VecDeque<Vec<f32>>,
+}
+
+impl OpenWakeWord {
+    pub fn load(model: &Path, models_dir: &Path) -> anyhow::Result<Self> {
+        let keyword = load_session(model)?;
+        // 模型输入为 [1, frames, 96]，frames 因模型而异
+        let keyword_frames = keyword
+            .inputs
+            .first()
+            .and_then(|input| input.input_type.tensor_dimensions())
+            .and_then(|dims| dims.get(1).copied())
+            .filter(|frames| *frames > 0)
+            .map(|frames| frames as usize)
+            .unwrap_or(DEFAULT_KEYWORD_FRAMES);
+        Ok(Self {
+            melspectrogram: load_session(&models_dir.join("melspectrogram.onnx"))?,
+            embedding: load_session(&models_dir.join("embedding_model.onnx"))?,
+            keyword,
+            keyword_frames,
+            pending: Vec::with_capacity(CHUNK_SAMPLES * 2),
+            context: vec![0.0; CONTEXT_SAMPLES],
+            mels: VecDeque::with_capacity(EMBEDDING_WINDOW + 8),
+            embeddings: VecDeque::with_capacity(keyword_frames + 1),
+        })
+    }
+
+    fn process_chunk(&mut self, chunk: &[f32]) -> anyhow::Result<Option<f32>> {
+        let mut audio = Vec::with_capacity(CONTEXT_SAMPLES + chunk.len());
+        audio.extend_from_slice(&self.context);
+        audio.extend_from_slice(chunk);
+        self.context = audio[audio.len() - CONTEXT_SAMPLES..].to_vec();
+
+        let input = Tensor::from_array(([1usize, audio.len()], audio))?;
+        let outputs = self.melspectrogram.run(ort::inputs![input]?)?;
+        let (_, mel) = outputs[0].try_extract_raw_tensor::<f32>()?;
+        for frame in mel.chunks_exact(MEL_BINS) {
+            let mut bins = [0.0; MEL_BINS];
+            // 与 openWakeWord 训练时的缩放一致
+            for (bin, value) in bins.iter_mut().zip(frame) {
+                *bin = value / 10.0 + 2.0;
+            }
+            self.mels.push_back(bins);
+        }
+        while self.mels.len() > EMBEDDING_WINDOW {
+            self.mels.pop_front();
+        }
+        if self.mels.len() < EMBEDDING_WINDOW {
+            return Ok(None);
+        }
+
+        let window: Vec<f32> = self.mels.iter().flatten().copied().collect();
+        let input = Tensor::from_array(([1usize, EMBEDDING_WINDOW, MEL_BINS, 1], window))?;
+        let outputs = self.embedding.run(ort::inputs![input]?)?;
+        let (_, embedding) = outputs[0].try_extract_raw_tensor::<f32>()?;
+        if embedding.len() != EMBEDDING_DIM {
+            anyhow::bail!("unexpected embedding size {}", embedding.len());
+        }
+        self.embeddings.push_back(embedding.to_vec());
+        while self.embeddings.len() > self.keyword_frames {
+            self.embeddings.pop_front();
+        }
+        if self.embeddings.len() < self.keyword_frames {
+            return Ok(None);
+        }
+
+        let features: Vec<f32> = self.embeddings.iter().flatten().copied().collect();
+        let input = Tensor::from_array(([1usize, self.keyword_frames, EMBEDDING_DIM], features))?;
+        let outputs = self.keyword.run(ort::inputs![input]?)?;
+        let (_, score) = outputs[0].try_extract_raw_tensor::<f32>()?;
+        Ok(score.first().copied())
+    }
+}
+
+impl WakeDetector for OpenWakeWord {
+    fn process(&mut self, pcm: &[i16]) -> anyhow::Result<Option<f32>> {
+        self.pending.extend(pcm.iter().map(|&sample| f32::from(sample)));
+        let mut best: Option<f32> = None;
+        while self.pending.len() >= CHUNK_SAMPLES {
+            let chunk: Vec<f32> = self.pending.drain(..CHUNK_SAMPLES).collect();
+            if let Some(score) = self.process_chunk(&chunk)? {
+                best = Some(best.map_or(score, |best| best.max(score)));
+            }
+        }
+        Ok(best)
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.context = vec![0.0; CONTEXT_SAMPLES];
+        self.mels.clear();
+        self.embeddings.clear();
+    }
+}
+
+fn load_session(path: &Path) -> anyhow::Result<Session> {
+    Session::builder()
+        .and_then(|builder| builder.with_intra_threads(1))
+        .and_then(|builder| builder.commit_from_file(path))
+        .with_context(|| format!("load wake word model {}", path.display()))
+}
@@ -0,0 +1,226 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::app_state::AppState;
+use crate::network::{ConnectionManager, LinkState};
+use crate::tray::TrayController;
+
+/// 等待 ASR 长连接建立的时限
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 最近一次预热的结果，每次更新都发送 `warmup-status` 事件
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WarmupStatus {
+    pub checked_at: Option<String>,
+    /// 当前 ASR 为 WebSocket 时的服务器地址；其它后端为空
+    pub asr_endpoint: Option<String>,
+    /// ASR 服务器是否可用；不使用长连接的后端为空
+    pub asr_ready: Option<bool>,
+    pub asr_rtt_ms: Option<u64>,
+    pub asr_error: Option<String>,
+    /// LLM 健康检查是否通过（未启用 LLM 时总是通过）
+    pub llm_ready: bool,
+    pub duration_ms: u64,
+}
+
+impl WarmupStatus {
+    /// 托盘提示中的降级说明；尚未预热或全部可用时为空
+    pub fn degraded(&self) -> Option<String> {
+        self.checked_at.as_ref()?;
+        let mut problems = Vec::new();
+        if self.asr_ready == Some(false) {
+            problems.push("ASR 服务器不可用 | ASR server unreachable");
+        }
+        if !self.llm_ready {
+            problems.push("LLM 不可用，只输出识别结果 | LLM unavailable, raw transcripts only");
+        }
+        (!problems.is_empty()).then(|| problems.join("\n"))
+    }
+
+    fn apply_asr(&mut self, endpoint: Option<&str>, result: Option<Result<u64, String>>) {
+        self.asr_endpoint = endpoint.map(str::to_string);
+        self.asr_ready = result.as_ref().map(Result::is_ok);
+        self.asr_rtt_ms = result.as_ref().and_then(|result| result.as_ref().ok().copied());
+        self.asr_error = result.and_then(Result::err);
+    }
+}
+
+/// 在会话之外预热 ASR 连接与 LLM，首次听写不必等连接建立，托盘也能提前提示不可用的服务
+pub struct Warmup {
+    app: tauri::AppHandle,
+    tray: Arc<TrayController>,
+    status: Mutex<WarmupStatus>,
+    /// 每次预热加一；新的预热开始后，旧的结果不再写入
+    generation: watch::Sender<u64>,
+}
+
+impl Warmup {
+    pub fn new(app: tauri::AppHandle, tray: Arc<TrayController>) -> Self {
+        let (generation, _) = watch::channel(0);
+        Self {
+            app,
+            tray,
+            status: Mutex::new(WarmupStatus::default()),
+            generation,
+        }
+    }
+
+    pub fn snapshot(&self) -> WarmupStatus {
+        self.status.lock().expect("warmup status lock").clone()
+    }
+
+    /// 在后台连上当前的 ASR 服务器并 ping 一次，同时做 LLM 健康检查。
+    /// 启动、切换 ASR 配置和应用新配置后调用
+    pub fn refresh(self: &Arc<Self>, state: Arc<AppState>) {
+        self.generation.send_modify(|generation| *generation += 1);
+        let generation = *self.generation.borrow();
+        let warmup = self.clone();
+        tauri::async_runtime::spawn(async move { warmup.run(state, generation).await });
+    }
+
+    /// 校正成功说明 LLM 已可用，清除预热时的失败提示
+    pub fn record_llm_ok(&self) {
+        let status = self.snapshot();
+        if status.checked_at.is_none() || status.llm_ready {
+            return;
+        }
+        let generation = *self.generation.borrow();
+        self.store(generation, |status, _| status.llm_ready = true);
+    }
+
+    async fn run(&self, state: Arc<AppState>, generation: u64) {
+        let mut generations = self.generation.subscribe();
+        let started = Instant::now();
+        let (connection, llm) = {
+            let pipeline = state.pipeline.lock().await;
+            (pipeline.asr_connection(), pipeline.llm_engine())
+        };
+        let endpoint = connection.as_ref().map(|connection| connection.endpoint().to_string());
+        let (asr, llm_ready) = tokio::join!(check_asr(connection.as_deref()), llm.health_check());
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let stored = self.store(generation, |status, now| {
+            *status = WarmupStatus {
+                checked_at: Some(now.to_string()),
+                llm_ready,
+                duration_ms,
+                ..WarmupStatus::default()
+            };
+            status.apply_asr(endpoint.as_deref(), asr);
+        });
+        let Some(status) = stored else {
+            return;
+        };
+        if status.degraded().is_some() {
+            warn!(
+                target: "warmup",
+                endpoint = endpoint.as_deref().unwrap_or(""),
+                asr_ready = ?status.asr_ready,
+                llm_ready = status.llm_ready,
+                error = status.asr_error.as_deref().unwrap_or(""),
+                duration_ms = duration_ms,
+                "预热完成，部分服务不可用 | Warm-up finished, some services unavailable"
+            );
+        } else {
+            info!(
+                target: "warmup",
+                endpoint = endpoint.as_deref().unwrap_or(""),
+                rtt_ms = ?status.asr_rtt_ms,
+                duration_ms = duration_ms,
+                "预热完成 | Warm-up finished"
+            );
+        }
+
+        // ASR 服务器暂时不可用：连接恢复后再确认一次，托盘提示随之清除
+        let Some(connection) = connection.filter(|_| status.asr_ready == Some(false)) else {
+            return;
+        };
+        let mut link = connection.watch();
+        let reconnected = async { link.wait_for(|link| link.state == LinkState::Connected).await.is_ok() };
+        let recovered = tokio::select! {
+            _ = generations.changed() => false,
+            connected = reconnected => connected,
+        };
+        if !recovered {
+            return;
+        }
+        let asr = check_asr(Some(&connection)).await;
+        if let Some(status) = self.store(generation, |status, _| status.apply_asr(endpoint.as_deref(), asr)) {
+            info!(
+                target: "warmup",
+                endpoint = endpoint.as_deref().unwrap_or(""),
+                asr_ready = ?status.asr_ready,
+                "ASR 服务器已恢复 | ASR server recovered"
+            );
+        }
+    }
+
+    /// 只在 `generation` 仍是最新一次预热时写入结果，并同步托盘提示
+    fn store(&self, generation: u64, apply: impl FnOnce(&mut WarmupStatus, &str)) -> Option<WarmupStatus> {
+        if *self.generation.borrow() != generation {
+            return None;
+        }
+        let now = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        let snapshot = {
+            let mut status = self.status.lock().expect("warmup status lock");
+            apply(&mut status, &now);
+            status.clone()
+        };
+        self.tray.set_degraded(snapshot.degraded().as_deref());
+        let _ = self.app.emit("warmup-status", &snapshot);
+        Some(snapshot)
+    }
+}
+
+/// 等长连接建立后 ping 一次，返回往返耗时；不使用长连接的后端返回 `None`
+async fn check_asr(connection: Option<&ConnectionManager>) -> Option<Result<u64, String>> {
+    let connection = connection?;
+    let mut link = connection.watch();
+    let connected = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        link.wait_for(|link| link.state == LinkState::Connected),
+    )
+    .await
+    .is_ok_and(|changed| changed.is_ok());
+    if !connected {
+        let error = connection.status().last_error;
+        return Some(Err(error.unwrap_or_else(|| "连接超时 | Connect timed out".to_string())));
+    }
+    Some(connection.ping(PING_TIMEOUT).await.map_err(|err| format!("{err:#}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degraded_lists_unavailable_services_after_a_check() {
+        let mut status = WarmupStatus::default();
+        assert_eq!(status.degraded(), None);
+
+        status.checked_at = Some("t1".to_string());
+        status.llm_ready = true;
+        status.apply_asr(None, None);
+        assert_eq!(status.degraded(), None);
+
+        status.apply_asr(Some("ws://a/ws"), Some(Err("refused".to_string())));
+        status.llm_ready = false;
+        assert_eq!(status.asr_error.as_deref(), Some("refused"));
+        assert_eq!(
+            status.degraded().as_deref(),
+            Some("ASR 服务器不可用 | ASR server unreachable\nLLM 不可用，只输出识别结果 | LLM unavailable, raw transcripts only")
+        );
+
+        status.apply_asr(Some("ws://a/ws"), Some(Ok(12)));
+        status.llm_ready = true;
+        assert_eq!(status.asr_rtt_ms, Some(12));
+        assert_eq!(status.asr_error, None);
+        assert_eq!(status.degraded(), None);
+    }
+}
@@ -0,0 +1,29 @@
+import "./captions.css";
+import { listen } from "@tauri-apps/api/event";
+
+const doneNode = document.getElementById("captionDone");
+const liveNode = document.getElementById("captionLive");
+
+function setLine(node, text) {
+  // direction: rtl 让超长文本显示末尾；用 LRM 包裹避免标点被挪到开头
+  node.textContent = text ? `\u200e${text}\u200e` : "";
+}
+
+// 上一行：已识别完成的文字；下一行：正在识别的文字
+function commit(text) {
+  if (text) setLine(doneNode, text);
+  setLine(liveNode, "");
+}
+
+listen("session-started", () => setLine(liveNode, ""));
+
+listen("partial", (event) => {
+  const text = event.payload?.text ?? "";
+  if (text) setLine(liveNode, text);
+});
+
+listen("asr-segment", (event) => commit(event.payload?.text ?? ""));
+listen("asr-final", (event) => commit(event.payload?.text ?? ""));
+listen("correction-applied", (event) => commit(event.payload?.text ?? ""));
+listen("meeting-segment", (event) => commit(event.payload?.text ?? ""));
+listen("session-cancelled", () => setLine(liveNode, ""));
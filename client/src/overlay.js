@@ -0,0 +1,95 @@
+import "./overlay.css";
+import { listen } from "@tauri-apps/api/event";
+
+const overlay = document.getElementById("overlay");
+const textNode = document.getElementById("overlayText");
+const waveNode = document.getElementById("overlayWave");
+
+const WAVE_BARS = 20;
+const bars = Array.from({ length: WAVE_BARS }, () => {
+  const bar = document.createElement("span");
+  waveNode.appendChild(bar);
+  return bar;
+});
+let levels = new Array(WAVE_BARS).fill(0);
+
+function renderWave() {
+  bars.forEach((bar, i) => {
+    // RMS 通常很小，开方放大后更直观
+    const height = Math.min(1, Math.sqrt(levels[i]) * 1.6);
+    bar.style.height = `${Math.round(2 + height * 22)}px`;
+  });
+}
+
+function setText(text, placeholder = false) {
+  // direction: rtl 让超长文本显示末尾；用 LRM 包裹避免标点被挪到开头
+  textNode.textContent = `\u200e${text}\u200e`;
+  textNode.classList.toggle("placeholder", placeholder);
+}
+
+function reset() {
+  overlay.classList.remove("processing");
+  levels = new Array(WAVE_BARS).fill(0);
+  renderWave();
+  setText("正在聆听…", true);
+}
+
+reset();
+
+listen("session-started", () => reset());
+
+listen("audio-level", (event) => {
+  const incoming = event.payload?.levels ?? [];
+  if (!incoming.length) return;
+  levels = levels.concat(incoming).slice(-WAVE_BARS);
+  renderWave();
+});
+
+listen("partial", (event) => {
+  const text = event.payload?.text ?? "";
+  if (text) setText(text);
+});
+
+listen("asr-segment", (event) => {
+  const text = event.payload?.text ?? "";
+  if (text) setText(text);
+});
+
+listen("asr-final", (event) => {
+  overlay.classList.add("processing");
+  setText(event.payload?.text ?? "");
+});
+
+listen("correcting", () => overlay.classList.add("processing"));
+
+listen("session-cancelled", () => {
+  overlay.classList.remove("processing");
+  setText("已取消", true);
+});
+
+const COMMAND_LABELS = {
+  delete_last: ["已删除", "没有可删除的内容"],
+  select_last: ["已选中", "没有可选中的内容"],
+  newline: ["已换行", "已换行"],
+  teach: ["已记住", "没有可学习的修改"],
+};
+
+listen("voice-command", (event) => {
+  const [done, nothing] = COMMAND_LABELS[event.payload?.action] ?? ["", ""];
+  overlay.classList.remove("processing");
+  setText(event.payload?.applied ? done : nothing, true);
+});
+
+listen("term-learned", (event) => {
+  overlay.classList.remove("processing");
+  setText(`已记住：${event.payload?.from ?? ""} → ${event.payload?.to ?? ""}`, true);
+});
+
+listen("correction-applied", (event) => {
+  setText(event.payload?.text ?? "");
+});
+
+listen("transform-applied", (event) => {
+  overlay.classList.remove("processing");
+  setText(event.payload?.text ?? "");
+});
@@ -1,7 +1,17 @@
+import { resolve } from "node:path";
 import { defineConfig } from "vite";
 
 export default defineConfig({
   clearScreen: false,
+  build: {
+    rollupOptions: {
+      input: {
+        main: resolve(__dirname, "index.html"),
+        overlay: resolve(__dirname, "overlay.html"),
+        captions: resolve(__dirname, "captions.html"),
+      },
+    },
+  },
   server: {
     port: 1420,
     strictPort: true,
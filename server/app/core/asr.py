@@ -26,13 +26,24 @@ except Exception:  # noqa: BLE001 - optional dependency at import time
 
 
 class AsrEngine(Protocol):
-    async def transcribe(self, audio_pcm: bytes, sample_rate: int) -> str: ...
+    async def transcribe(
+        self, audio_pcm: bytes, sample_rate: int, language: Optional[str] = None
+    ) -> str: ...
+
+    def languages(self) -> List[str]:
+        """可在 start 中指定的识别语言；空列表表示不支持按会话指定"""
+        ...
 
 
 class StubAsrEngine:
-    async def transcribe(self, audio_pcm: bytes, sample_rate: int) -> str:
+    async def transcribe(
+        self, audio_pcm: bytes, sample_rate: int, language: Optional[str] = None
+    ) -> str:
         return f"[pcm_bytes={len(audio_pcm)} sr={sample_rate}]"
 
+    def languages(self) -> List[str]:
+        return []
+
 
 @dataclass(frozen=True)
 class SenseVoiceConfig:
@@ -101,8 +112,16 @@ class SenseVoiceEngine:
         )
         self.providers = self.session.get_providers()
 
-    async def transcribe(self, audio_pcm: bytes, sample_rate: int) -> str:
-        return await asyncio.to_thread(self._transcribe_sync, audio_pcm, sample_rate)
+    async def transcribe(
+        self, audio_pcm: bytes, sample_rate: int, language: Optional[str] = None
+    ) -> str:
+        return await asyncio.to_thread(self._transcribe_sync, audio_pcm, sample_rate, language)
+
+    def languages(self) -> List[str]:
+        """模型元数据中的 `lang_*` 键，如 auto/zh/en/yue/ja/ko"""
+        if self._mode != "sense_voice_ctc":
+            return []
+        return sorted(k[len("lang_"):] for k in self._meta if k.startswith("lang_"))
 
     def _read_custom_metadata(self, session: Any) -> Dict[str, str]:
         try:
@@ -184,8 +203,8 @@ class SenseVoiceEngine:
         arr = np.array([float(p) for p in parts], dtype=np.float32)
         return np.ascontiguousarray(arr)
 
-    def _resolve_language_id(self) -> int:
-        lang = (self.config.language or "auto").strip().lower()
+    def _resolve_language_id(self, language: Optional[str] = None) -> int:
+        lang = (language or self.config.language or "auto").strip().lower()
         key = f"lang_{lang}"
         raw = self._meta.get(key) or self._meta.get("lang_auto") or "0"
         try:
@@ -330,7 +349,9 @@ class SenseVoiceEngine:
                 out[i] = tok
         return out
 
-    def _transcribe_sync(self, audio_pcm: bytes, sample_rate: int) -> str:
+    def _transcribe_sync(
+        self, audio_pcm: bytes, sample_rate: int, language: Optional[str] = None
+    ) -> str:
         if np is None:
             raise RuntimeError("numpy not installed")
 
@@ -359,7 +380,14 @@ class SenseVoiceEngine:
             inputs: Dict[str, Any] = {
                 "x": input_x,
                 "x_length": np.array([x_len], dtype=np.int32),
-                "language": np.array([self._sensevoice_language_id], dtype=np.int32),
+                "language": np.array(
+                    [
+                        self._resolve_language_id(language)
+                        if language
+                        else self._sensevoice_language_id
+                    ],
+                    dtype=np.int32,
+                ),
                 "text_norm": np.array([self._sensevoice_text_norm_id], dtype=np.int32),
             }
             outputs = self.session.run(None, inputs)
@@ -0,0 +1,105 @@
+from __future__ import annotations
+
+import os
+import socket
+from typing import Any, Optional
+
+try:
+    from zeroconf import IPVersion, ServiceInfo  # type: ignore[import-not-found]
+    from zeroconf.asyncio import AsyncZeroconf  # type: ignore[import-not-found]
+except Exception:  # noqa: BLE001 - optional; without it the server is simply not discoverable
+    AsyncZeroconf = None  # type: ignore[assignment,misc]
+
+from app.logging_config import get_logger
+
+log_mdns = get_logger("mdns")
+
+# 客户端 `discover_servers` 浏览的服务类型
+SERVICE_TYPE = "_ghosttype._tcp.local."
+
+WS_PATH = "/ws"
+
+
+def _enabled() -> bool:
+    return (os.environ.get("GHOSTTYPE_MDNS") or "1").strip().lower() not in ("0", "false", "off", "no")
+
+
+def _port() -> int:
+    """uvicorn 不把端口告诉应用：入口脚本通过 GHOSTTYPE_PORT 传入，直接运行 uvicorn 时按默认 8000。"""
+
+    try:
+        return int(os.environ.get("GHOSTTYPE_PORT") or 8000)
+    except ValueError:
+        return 8000
+
+
+def _lan_address() -> Optional[str]:
+    """本机用于访问局域网的 IPv4 地址（不实际发送数据）。"""
+
+    sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+    try:
+        sock.connect(("224.0.0.251", 5353))
+        address = sock.getsockname()[0]
+    except OSError:
+        return None
+    finally:
+        sock.close()
+    return None if address.startswith("127.") else address
+
+
+class MdnsAdvertiser:
+    """在局域网内广播本服务，客户端可直接搜索到地址。"""
+
+    def __init__(self) -> None:
+        self._zeroconf: Any = None
+        self._info: Any = None
+
+    async def start(self) -> None:
+        if not _enabled():
+            return
+        if AsyncZeroconf is None:
+            log_mdns.info("未安装 zeroconf，跳过局域网广播 | zeroconf not installed; mDNS disabled")
+            return
+        address = _lan_address()
+        if address is None:
+            log_mdns.warning("未找到局域网地址，跳过局域网广播 | No LAN address; mDNS disabled")
+            return
+
+        hostname = socket.gethostname().split(".")[0] or "ghosttype"
+        port = _port()
+        info = ServiceInfo(
+            SERVICE_TYPE,
+            f"{hostname}.{SERVICE_TYPE}",
+            parsed_addresses=[address],
+            port=port,
+            properties={"path": WS_PATH},
+            server=f"{hostname}.local.",
+        )
+        zeroconf = AsyncZeroconf(ip_version=IPVersion.V4Only)
+        try:
+            # 同名实例已存在时自动改名
+            await zeroconf.async_register_service(info, allow_name_change=True)
+        except Exception as exc:  # noqa: BLE001 - discovery is best effort
+            log_mdns.warning("局域网广播失败 | mDNS registration failed | error={error}", error=str(exc))
+            await zeroconf.async_close()
+            return
+
+        self._zeroconf = zeroconf
+        self._info = info
+        log_mdns.info(
+            "局域网广播已开启 | mDNS advertising | name={name} endpoint=ws://{address}:{port}{path}",
+            name=info.name,
+            address=address,
+            port=port,
+            path=WS_PATH,
+        )
+
+    async def stop(self) -> None:
+        if self._zeroconf is None:
+            return
+        try:
+            await self._zeroconf.async_unregister_service(self._info)
+        finally:
+            await self._zeroconf.async_close()
+            self._zeroconf = None
+            self._info = None
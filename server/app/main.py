@@ -1,4 +1,5 @@
 import asyncio
+import base64
 import json
 import os
 import tempfile
@@ -10,6 +11,11 @@ from typing import Any, Dict, Optional
 
 from fastapi import FastAPI, WebSocket, WebSocketDisconnect
 
+try:
+    import msgpack  # type: ignore[import-not-found]
+except Exception:  # noqa: BLE001 - optional; without it events are always JSON
+    msgpack = None  # type: ignore[assignment]
+
 from app.logging_config import get_logger, setup_logging, with_trace
 
 setup_logging()
@@ -19,10 +25,33 @@ log_audio = get_logger("audio")
 log_asr = get_logger("asr")
 
 from app.core.asr import AsrEngine, SenseVoiceEngine, StubAsrEngine
-from app.utils.audio import decode_opus_packets_to_pcm_s16le, write_wav_s16le
+from app.discovery import MdnsAdvertiser
+from app.utils import e2e
+from app.utils.audio import (
+    decode_opus_packets_to_pcm_s16le,
+    parse_pcm_frame,
+    pcm_frames_to_pcm_s16le,
+    write_wav_s16le,
+)
 
 app = FastAPI()
 
+# 随 pong 返回给客户端的协议扩展（`partials`、`llm` 等实现后再加入）
+SERVER_FEATURES = (
+    ["seq_frames", "resume", "cancel"]
+    + (["msgpack"] if msgpack is not None else [])
+    + (["e2e"] if e2e.AVAILABLE else [])
+)
+
+# 支持的音频编码；`pcm_s16le` 为带 4 字节帧头的原始 PCM，供局域网部署省去编码
+SERVER_CODECS = ["opus", "pcm_s16le"]
+
+# 启用帧序号时每收到多少个音频包回一次 ack
+ACK_INTERVAL = 25
+
+# 录音中断线的会话保留多久等待客户端续传（秒）
+RESUME_TTL_S = 60.0
+
 
 def _resolve_model_path() -> Path:
     """
@@ -41,6 +70,9 @@ def _resolve_model_path() -> Path:
 
 MODEL_PATH = _resolve_model_path()
 asr_engine: AsrEngine = StubAsrEngine()
+mdns = MdnsAdvertiser()
+# 端到端加密的服务器密钥（未安装 cryptography 时为 None）
+e2e_key: Optional[e2e.ServerKey] = e2e.ServerKey(e2e.default_key_path()) if e2e.AVAILABLE else None
 
 
 @app.on_event("startup")
@@ -93,24 +125,75 @@ async def startup() -> None:
             model=str(MODEL_PATH),
         )
 
+    if e2e_key is not None:
+        # 客户端可把它填到 asr.e2e_server_key 固定下来，防止代理替换公钥
+        log_server.info("端到端加密公钥 | E2E public key | key={key}", key=e2e_key.public_key())
+
+    await mdns.start()
+
     startup_ms = (time.perf_counter() - t_start) * 1000.0
     log_server.info("服务器就绪 | Server ready | startup_time_ms={ms:.0f}", ms=startup_ms)
 
 
+@app.on_event("shutdown")
+async def shutdown() -> None:
+    await mdns.stop()
+
+
 @dataclass
 class SessionState:
     trace_id: Optional[str] = None
     sample_rate: Optional[int] = None
     context: Dict[str, Any] = field(default_factory=dict)
     use_cloud_api: bool = False
-    opus_packets: list[bytes] = field(default_factory=list)
+    language: Optional[str] = None
+    # 音频编码（start 中 `codec`，默认 Opus）；原始 PCM 帧存入时已去掉帧头
+    codec: str = "opus"
+    audio_packets: list[bytes] = field(default_factory=list)
     packet_count: int = 0
     total_bytes: int = 0
+    # 音频帧带 4 字节大端序号（start 中 `seq_frames: true`）
+    seq_frames: bool = False
+    next_seq: int = 0
+    missing_frames: int = 0
+    # 会话事件以 MessagePack 二进制帧发送（start 中 `wire_format: "msgpack"`）
+    msgpack_wire: bool = False
+    # 端到端加密（start 中带 `e2e`）：音频帧与会话事件均为加密帧
+    e2e_channel: Optional[e2e.E2eChannel] = None
+    # 已 start 尚未 stop：此时断线会保留会话等待续传
+    active: bool = False
 
     def reset_audio(self) -> None:
-        self.opus_packets.clear()
+        self.audio_packets.clear()
         self.packet_count = 0
         self.total_bytes = 0
+        self.next_seq = 0
+        self.missing_frames = 0
+
+
+# 录音中断线的会话：trace_id -> (断线时间, 会话状态)
+_suspended_sessions: Dict[str, tuple[float, SessionState]] = {}
+
+
+def _suspend_session(state: SessionState) -> None:
+    if not state.active or not state.trace_id:
+        return
+    now = time.monotonic()
+    for trace_id, (since, _) in list(_suspended_sessions.items()):
+        if now - since > RESUME_TTL_S:
+            del _suspended_sessions[trace_id]
+    _suspended_sessions[state.trace_id] = (now, state)
+    with_trace(log_ws, state.trace_id).info(
+        "录音中断线，会话保留等待续传 | Disconnected mid-session; kept for resume | packets={packets}",
+        packets=state.packet_count,
+    )
+
+
+def _take_suspended(trace_id: str) -> Optional[SessionState]:
+    entry = _suspended_sessions.pop(trace_id, None)
+    if entry is None or time.monotonic() - entry[0] > RESUME_TTL_S:
+        return None
+    return entry[1]
 
 
 def _json_dumps(obj: Any) -> str:
@@ -128,11 +211,75 @@ def _generate_trace_id() -> str:
     return "".join(reversed(out))
 
 
-async def _send_error(ws: WebSocket, message: str, *, trace_id: Optional[str] = None) -> None:
+def _pong_payload() -> Dict[str, Any]:
+    """pong 同时声明服务器能力，客户端据此调整请求"""
+    payload: Dict[str, Any] = {
+        "type": "pong",
+        "features": SERVER_FEATURES,
+        "codecs": SERVER_CODECS,
+        "languages": asr_engine.languages(),
+    }
+    if e2e_key is not None:
+        payload["e2e_key"] = e2e_key.public_key()
+    return payload
+
+
+async def _send_event(
+    ws: WebSocket,
+    payload: Dict[str, Any],
+    *,
+    msgpack_wire: bool = False,
+    e2e_channel: Optional[e2e.E2eChannel] = None,
+) -> None:
+    if msgpack_wire and msgpack is not None:
+        data = msgpack.packb(payload)
+    elif e2e_channel is not None:
+        data = _json_dumps(payload).encode("utf-8")
+    else:
+        await ws.send_text(_json_dumps(payload))
+        return
+    await ws.send_bytes(e2e_channel.seal(data) if e2e_channel is not None else data)
+
+
+async def _send_ack(ws: WebSocket, state: SessionState) -> None:
+    await _send_event(
+        ws,
+        {
+            "type": "ack",
+            "trace_id": state.trace_id,
+            "seq": state.next_seq - 1,
+            "received": state.packet_count,
+            "missing": state.missing_frames,
+        },
+        msgpack_wire=state.msgpack_wire,
+        e2e_channel=state.e2e_channel,
+    )
+
+
+async def _send_error(
+    ws: WebSocket,
+    message: str,
+    *,
+    trace_id: Optional[str] = None,
+    msgpack_wire: bool = False,
+    e2e_channel: Optional[e2e.E2eChannel] = None,
+) -> None:
     payload: Dict[str, Any] = {"type": "error", "message": message}
     if trace_id:
         payload["trace_id"] = trace_id
-    await ws.send_text(_json_dumps(payload))
+    await _send_event(ws, payload, msgpack_wire=msgpack_wire, e2e_channel=e2e_channel)
+
+
+def _accept_e2e(sealed: Dict[str, Any], trace_id: str) -> tuple[e2e.E2eChannel, Dict[str, Any]]:
+    """完成密钥交换并解密 start 中的上下文"""
+    if e2e_key is None:
+        raise ValueError("e2e not supported")
+    channel = e2e_key.accept(str(sealed.get("public_key") or ""), trace_id)
+    context: Dict[str, Any] = {}
+    raw = sealed.get("context")
+    if raw:
+        context = dict(json.loads(channel.open(base64.b64decode(raw))) or {})
+    return channel, context
 
 
 @app.get("/")
@@ -150,11 +297,12 @@ async def websocket_endpoint(ws: WebSocket) -> None:
     state = SessionState()
     stop_lock = asyncio.Lock()
 
-    async def handle_stop() -> None:
+    async def handle_stop(frames: Optional[int]) -> None:
         async with stop_lock:
+            state.active = False
             if state.sample_rate is None:
                 log_ws.warning("收到stop但未start | Stop before start")
-                await _send_error(ws, "stop before start", trace_id=state.trace_id)
+                await _send_error(ws, "stop before start", trace_id=state.trace_id, msgpack_wire=state.msgpack_wire, e2e_channel=state.e2e_channel)
                 state.reset_audio()
                 return
 
@@ -162,13 +310,26 @@ async def websocket_endpoint(ws: WebSocket) -> None:
             tlog_audio = with_trace(log_audio, trace_id)
             tlog_asr = with_trace(log_asr, trace_id)
 
+            if state.seq_frames:
+                # 末尾丢失的帧只能靠客户端报告的总帧数发现
+                if frames is not None and frames > state.next_seq:
+                    state.missing_frames += frames - state.next_seq
+                    state.next_seq = frames
+                if state.missing_frames:
+                    tlog_audio.warning(
+                        "会话音频丢帧 | Session audio frames missing | missing={missing} received={received}",
+                        missing=state.missing_frames,
+                        received=state.packet_count,
+                    )
+                await _send_ack(ws, state)
+
             try:
                 t0 = time.perf_counter()
                 packets = state.packet_count
                 total_bytes = state.total_bytes
                 pcm = await asyncio.to_thread(
-                    decode_opus_packets_to_pcm_s16le,
-                    state.opus_packets,
+                    pcm_frames_to_pcm_s16le if state.codec == "pcm_s16le" else decode_opus_packets_to_pcm_s16le,
+                    state.audio_packets,
                     input_sample_rate=state.sample_rate,
                 )
                 t1 = time.perf_counter()
@@ -211,10 +372,12 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                         "ASR推理开始 | ASR inference started | pcm_duration_ms={dur:.0f}",
                         dur=pcm_duration_ms,
                     )
-                    text = await asr_engine.transcribe(pcm.pcm_s16le, pcm.sample_rate)
+                    text = await asr_engine.transcribe(
+                        pcm.pcm_s16le, pcm.sample_rate, state.language
+                    )
                     t_asr1 = time.perf_counter()
                 except Exception as exc:
-                    await _send_error(ws, f"asr failed: {exc}", trace_id=state.trace_id)
+                    await _send_error(ws, f"asr failed: {exc}", trace_id=state.trace_id, msgpack_wire=state.msgpack_wire, e2e_channel=state.e2e_channel)
                     text = f"[asr_error: {exc}]"
                     t_asr1 = time.perf_counter()
 
@@ -237,15 +400,16 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                         sr=pcm.sample_rate,
                     )
 
-                await ws.send_text(
-                    _json_dumps(
-                        {
-                            "type": "fast_text",
-                            "trace_id": state.trace_id,
-                            "content": text,
-                            "is_final": True,
-                        }
-                    )
+                await _send_event(
+                    ws,
+                    {
+                        "type": "fast_text",
+                        "trace_id": state.trace_id,
+                        "content": text,
+                        "is_final": True,
+                    },
+                    msgpack_wire=state.msgpack_wire,
+                    e2e_channel=state.e2e_channel,
                 )
                 with_trace(log_ws, trace_id).debug(
                     "发送识别结果 | Sending recognition result | text_len={len}",
@@ -255,7 +419,7 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                 with_trace(log_audio, state.trace_id or "").exception(
                     "音频解码失败 | Audio decode failed | error={error}", error=str(exc)
                 )
-                await _send_error(ws, f"audio decode failed: {exc}", trace_id=state.trace_id)
+                await _send_error(ws, f"audio decode failed: {exc}", trace_id=state.trace_id, msgpack_wire=state.msgpack_wire, e2e_channel=state.e2e_channel)
             finally:
                 state.reset_audio()
 
@@ -268,12 +432,12 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                 try:
                     payload = json.loads(text)
                 except json.JSONDecodeError:
-                    await _send_error(ws, "invalid json", trace_id=state.trace_id)
+                    await _send_error(ws, "invalid json", trace_id=state.trace_id, msgpack_wire=state.msgpack_wire, e2e_channel=state.e2e_channel)
                     continue
 
                 msg_type = payload.get("type")
                 if msg_type == "ping":
-                    await ws.send_text(_json_dumps({"type": "pong"}))
+                    await ws.send_text(_json_dumps(_pong_payload()))
                     continue
 
                 if msg_type == "start":
@@ -285,26 +449,132 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                     state.sample_rate = int(payload.get("sample_rate", 48000))
                     state.context = dict(payload.get("context") or {})
                     state.use_cloud_api = bool(payload.get("use_cloud_api", False))
+                    state.seq_frames = bool(payload.get("seq_frames", False))
+                    state.msgpack_wire = (
+                        payload.get("wire_format") == "msgpack" and msgpack is not None
+                    )
+                    codec = payload.get("codec") or "opus"
+                    if codec not in SERVER_CODECS:
+                        with_trace(log_ws, state.trace_id).warning(
+                            "不支持的音频编码 | Unsupported codec | codec={codec}", codec=str(codec)
+                        )
+                        state.sample_rate = None
+                        await _send_error(ws, f"unsupported codec: {codec}", trace_id=state.trace_id)
+                        continue
+                    state.codec = codec
+                    language = payload.get("language")
+                    state.language = (
+                        language.strip().lower()
+                        if isinstance(language, str) and language.strip()
+                        else None
+                    )
+                    state.e2e_channel = None
+                    sealed = payload.get("e2e")
+                    if isinstance(sealed, dict):
+                        try:
+                            state.e2e_channel, state.context = _accept_e2e(sealed, state.trace_id)
+                        except Exception as exc:  # noqa: BLE001
+                            # 握手失败不能退回明文：拒绝本次会话
+                            with_trace(log_ws, state.trace_id).warning(
+                                "端到端加密握手失败 | E2E handshake failed | error={error}", error=str(exc)
+                            )
+                            state.sample_rate = None
+                            await _send_error(ws, f"e2e handshake failed: {exc}", trace_id=state.trace_id)
+                            continue
                     state.reset_audio()
+                    state.active = True
+                    # 同一 trace_id 的断线会话：续传时接上已收到的音频，否则丢弃
+                    parked = _take_suspended(state.trace_id)
+                    if payload.get("resume"):
+                        if parked is not None:
+                            state.audio_packets = parked.audio_packets
+                            state.packet_count = parked.packet_count
+                            state.total_bytes = parked.total_bytes
+                            state.next_seq = parked.next_seq if parked.seq_frames else parked.packet_count
+                            state.missing_frames = parked.missing_frames
+                        with_trace(log_ws, state.trace_id).info(
+                            "会话续传 | Session resumed | found={found} received={received}",
+                            found=parked is not None,
+                            received=state.next_seq if parked is not None else 0,
+                        )
+                        await _send_event(
+                            ws,
+                            {
+                                "type": "resumed",
+                                "trace_id": state.trace_id,
+                                "received": state.next_seq if parked is not None else 0,
+                            },
+                            msgpack_wire=state.msgpack_wire,
+                            e2e_channel=state.e2e_channel,
+                        )
                     with_trace(log_ws, state.trace_id).debug(
                         "收到控制消息 | Control message received | type=start sample_rate={sr}",
                         sr=state.sample_rate,
                     )
                     continue
 
+                if msg_type == "cancel":
+                    # 客户端放弃本次会话：丢弃音频，不识别、不回结果，断线后也不保留
+                    with_trace(log_ws, state.trace_id or "").info(
+                        "会话已取消 | Session cancelled | packets={packets}",
+                        packets=state.packet_count,
+                    )
+                    async with stop_lock:
+                        state.active = False
+                        state.sample_rate = None
+                        state.reset_audio()
+                    continue
+
                 if msg_type == "stop":
                     with_trace(log_ws, state.trace_id or "").debug(
                         "收到控制消息 | Control message received | type=stop"
                     )
-                    await handle_stop()
+                    frames = payload.get("frames")
+                    await handle_stop(frames if isinstance(frames, int) else None)
                     continue
 
-                await _send_error(ws, f"unknown type: {msg_type}", trace_id=state.trace_id)
+                await _send_error(ws, f"unknown type: {msg_type}", trace_id=state.trace_id, msgpack_wire=state.msgpack_wire, e2e_channel=state.e2e_channel)
                 continue
 
             audio = msg.get("bytes")
             if audio is not None:
-                state.opus_packets.append(audio)
+                if state.e2e_channel is not None:
+                    try:
+                        audio = state.e2e_channel.open(audio)
+                    except Exception as exc:  # noqa: BLE001
+                        # 无法解密的帧丢弃；启用帧序号时会在 ack 中计为缺失
+                        with_trace(log_audio, state.trace_id or "").warning(
+                            "音频帧解密失败 | Audio frame decryption failed | error={error}", error=str(exc)
+                        )
+                        continue
+                if state.seq_frames:
+                    if len(audio) < 4:
+                        continue
+                    seq = int.from_bytes(audio[:4], "big")
+                    audio = audio[4:]
+                    if seq < state.next_seq:
+                        # 重复帧（客户端重发）直接丢弃
+                        continue
+                    if seq > state.next_seq:
+                        with_trace(log_audio, state.trace_id or "").warning(
+                            "音频帧序号不连续 | Audio frame gap | expected={expected} got={got}",
+                            expected=state.next_seq,
+                            got=seq,
+                        )
+                        state.missing_frames += seq - state.next_seq
+                    state.next_seq = seq + 1
+                if state.codec == "pcm_s16le":
+                    try:
+                        audio = parse_pcm_frame(audio)
+                    except ValueError as exc:
+                        # 帧头不合法的帧丢弃；启用帧序号时计为缺失
+                        with_trace(log_audio, state.trace_id or "").warning(
+                            "PCM 帧无效 | Invalid PCM frame | error={error}", error=str(exc)
+                        )
+                        if state.seq_frames:
+                            state.missing_frames += 1
+                        continue
+                state.audio_packets.append(audio)
                 state.packet_count += 1
                 state.total_bytes += len(audio)
                 with_trace(log_audio, state.trace_id or "").debug(
@@ -312,6 +582,8 @@ async def websocket_endpoint(ws: WebSocket) -> None:
                     bytes=len(audio),
                     packets=state.packet_count,
                 )
+                if state.seq_frames and state.packet_count % ACK_INTERVAL == 0:
+                    await _send_ack(ws, state)
                 continue
 
     except WebSocketDisconnect:
@@ -323,3 +595,5 @@ async def websocket_endpoint(ws: WebSocket) -> None:
         if 'disconnect message has been received' in str(exc):
             return
         raise
+    finally:
+        _suspend_session(state)
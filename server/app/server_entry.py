@@ -79,6 +79,8 @@ def _maybe_chdir_base(base_path: Path) -> None:
 def _run_uvicorn(*, host: str, port: int) -> int:
     import uvicorn
 
+    # 局域网广播需要知道实际端口
+    os.environ["GHOSTTYPE_PORT"] = str(port)
     uvicorn.run(
         "app.main:app",
         host=host,
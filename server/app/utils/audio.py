@@ -18,6 +18,9 @@ except Exception:  # noqa: BLE001 - optional at import time; raised on decode if
 _OGG_CRC_POLY = 0x04C11DB7
 _OGG_OPUS_GRANULE_RATE = 48000
 _SUPPORTED_OPUS_RATES = (8000, 12000, 16000, 24000, 48000)
+# 原始 PCM 帧头：1 字节版本、1 字节声道数、2 字节大端采样数
+_PCM_FRAME_VERSION = 1
+_PCM_FRAME_HEADER = struct.Struct(">BBH")
 
 
 def _make_ogg_crc_table() -> List[int]:
@@ -216,3 +219,53 @@ def decode_opus_packets_to_pcm_s16le(
         sample_rate=output_sample_rate,
         channels=1,
     )
+
+
+def parse_pcm_frame(frame: bytes) -> bytes:
+    """校验原始 PCM 帧头，返回其后的 16 位小端采样"""
+    if len(frame) < _PCM_FRAME_HEADER.size:
+        raise ValueError(f"PCM 帧过短 | PCM frame too short: {len(frame)} bytes")
+    version, channels, samples = _PCM_FRAME_HEADER.unpack_from(frame)
+    if version != _PCM_FRAME_VERSION or channels != 1:
+        raise ValueError(
+            f"不支持的 PCM 帧 | Unsupported PCM frame: version={version} channels={channels}"
+        )
+    payload = frame[_PCM_FRAME_HEADER.size :]
+    if len(payload) != samples * 2:
+        raise ValueError(
+            f"PCM 帧长度不符 | PCM frame length mismatch: {len(payload)} bytes for {samples} samples"
+        )
+    return payload
+
+
+def pcm_frames_to_pcm_s16le(
+    pcm_frames: Iterable[bytes],
+    *,
+    input_sample_rate: int,
+    output_sample_rate: int = 16000,
+) -> PcmAudio:
+    """拼接已去掉帧头的原始 PCM，并重采样到模型需要的采样率"""
+    pcm = b"".join(pcm_frames)
+    if not pcm or input_sample_rate == output_sample_rate:
+        return PcmAudio(pcm_s16le=pcm, sample_rate=output_sample_rate, channels=1)
+    if av is None or np is None:
+        raise RuntimeError("PyAV not installed; add `av` + `numpy` to your environment")
+
+    frame = av.AudioFrame.from_ndarray(
+        np.frombuffer(pcm, dtype="<i2").reshape(1, -1), format="s16", layout="mono"
+    )
+    frame.sample_rate = input_sample_rate
+    resampler = av.AudioResampler(format="s16", layout="mono", rate=output_sample_rate)
+    pcm_chunks: List[bytes] = []
+    for resampled_frames in (resampler.resample(frame), resampler.resample(None)):
+        if resampled_frames is None:
+            continue
+        if not isinstance(resampled_frames, (list, tuple)):
+            resampled_frames = [resampled_frames]
+        for r in resampled_frames:
+            pcm_chunks.append(r.to_ndarray().tobytes())
+    return PcmAudio(
+        pcm_s16le=b"".join(pcm_chunks),
+        sample_rate=output_sample_rate,
+        channels=1,
+    )
@@ -0,0 +1,115 @@
+"""
+应用层端到端加密（与 TLS 无关，经不可信的反向代理中转时使用）。
+
+- 服务器持有长期 X25519 密钥，公钥随 pong 的 `e2e_key` 下发
+- 客户端每个会话生成临时密钥，公钥放在 start 的 `e2e.public_key`
+- HKDF-SHA256(shared, salt=trace_id, info="ghosttype-e2e-v1") 派生 64 字节：
+  前 32 字节加密客户端→服务器，后 32 字节加密服务器→客户端
+- 加密帧 = 8 字节大端计数器 + ChaCha20-Poly1305 密文；nonce = 4 字节 0 + 计数器
+"""
+
+from __future__ import annotations
+
+import base64
+import os
+from pathlib import Path
+from typing import Optional
+
+try:
+    from cryptography.hazmat.primitives import serialization
+    from cryptography.hazmat.primitives.asymmetric.x25519 import X25519PrivateKey, X25519PublicKey
+    from cryptography.hazmat.primitives.ciphers.aead import ChaCha20Poly1305
+    from cryptography.hazmat.primitives.hashes import SHA256
+    from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+except Exception:  # noqa: BLE001 - optional; without it the server does not offer e2e
+    X25519PrivateKey = None  # type: ignore[assignment,misc]
+
+AVAILABLE = X25519PrivateKey is not None
+
+HKDF_INFO = b"ghosttype-e2e-v1"
+COUNTER_LEN = 8
+KEY_FILE_NAME = "e2e_key.bin"
+
+
+def _nonce(counter: int) -> bytes:
+    return b"\x00" * 4 + counter.to_bytes(COUNTER_LEN, "big")
+
+
+class E2eChannel:
+    """一个会话的两个方向：open 解密客户端帧（计数器必须递增），seal 加密发给客户端的事件。"""
+
+    def __init__(self, recv_key: bytes, send_key: bytes) -> None:
+        self._recv = ChaCha20Poly1305(recv_key)
+        self._send = ChaCha20Poly1305(send_key)
+        self._recv_last: Optional[int] = None
+        self._send_counter = 0
+
+    def open(self, frame: bytes) -> bytes:
+        if len(frame) < COUNTER_LEN:
+            raise ValueError("e2e frame too short")
+        counter = int.from_bytes(frame[:COUNTER_LEN], "big")
+        if self._recv_last is not None and counter <= self._recv_last:
+            raise ValueError(f"e2e frame replayed (counter {counter})")
+        plaintext = self._recv.decrypt(_nonce(counter), bytes(frame[COUNTER_LEN:]), None)
+        self._recv_last = counter
+        return plaintext
+
+    def seal(self, plaintext: bytes) -> bytes:
+        counter = self._send_counter
+        self._send_counter += 1
+        return counter.to_bytes(COUNTER_LEN, "big") + self._send.encrypt(_nonce(counter), plaintext, None)
+
+
+class ServerKey:
+    """服务器长期密钥：保存在 base 目录下，重启后公钥不变，客户端可以固定它。"""
+
+    def __init__(self, path: Optional[Path] = None) -> None:
+        self._path = path
+        self._private: Optional["X25519PrivateKey"] = None
+
+    def _load(self) -> "X25519PrivateKey":
+        if self._private is not None:
+            return self._private
+        raw: Optional[bytes] = None
+        if self._path is not None and self._path.exists():
+            data = self._path.read_bytes()
+            if len(data) == 32:
+                raw = data
+        if raw is None:
+            private = X25519PrivateKey.generate()
+            raw = private.private_bytes(
+                serialization.Encoding.Raw,
+                serialization.PrivateFormat.Raw,
+                serialization.NoEncryption(),
+            )
+            if self._path is not None:
+                try:
+                    self._path.parent.mkdir(parents=True, exist_ok=True)
+                    self._path.write_bytes(raw)
+                    os.chmod(self._path, 0o600)
+                except OSError:
+                    # 写不进去就只在本次运行有效
+                    pass
+        self._private = X25519PrivateKey.from_private_bytes(raw)
+        return self._private
+
+    def public_key(self) -> str:
+        public = self._load().public_key().public_bytes(serialization.Encoding.Raw, serialization.PublicFormat.Raw)
+        return base64.b64encode(public).decode("ascii")
+
+    def accept(self, client_public_key: str, trace_id: str) -> E2eChannel:
+        """用客户端的临时公钥完成交换；公钥无效时抛出 ValueError。"""
+
+        try:
+            peer = X25519PublicKey.from_public_bytes(base64.b64decode(client_public_key, validate=True))
+        except Exception as exc:  # noqa: BLE001
+            raise ValueError(f"invalid e2e public key: {exc}") from exc
+        shared = self._load().exchange(peer)
+        okm = HKDF(algorithm=SHA256(), length=64, salt=trace_id.encode("utf-8"), info=HKDF_INFO).derive(shared)
+        return E2eChannel(recv_key=okm[:32], send_key=okm[32:])
+
+
+def default_key_path() -> Path:
+    base = (os.environ.get("GHOSTTYPE_BASE_PATH") or "").strip()
+    root = Path(base).expanduser() if base else Path(__file__).resolve().parents[2]
+    return root / KEY_FILE_NAME
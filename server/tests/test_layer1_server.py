@@ -155,6 +155,276 @@ async def test_start_stop_flow(result: TestResult) -> None:
         result.fail("T1.4 Start/Stop", str(exc))
 
 
+async def test_seq_frames_ack(result: TestResult) -> None:
+    """T1.4b: 帧序号与 ack"""
+    print("\nT1.4b: 帧序号与 ack")
+    trace_id = "s1q2e3"
+    try:
+        import websockets
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "seq_frames" not in (pong.get("features") or []):
+                result.warn("T1.4b 帧序号", "server does not advertise seq_frames")
+                return
+
+            await ws.send(
+                json.dumps(
+                    {
+                        "type": "start",
+                        "trace_id": trace_id,
+                        "sample_rate": 48000,
+                        "context": {"app_name": "TestScript", "window_title": "test"},
+                        "use_cloud_api": False,
+                        "seq_frames": True,
+                    }
+                )
+            )
+            # 故意跳过序号 1，并声明共 4 帧（末尾的 3 也缺失）
+            for seq in (0, 2):
+                await ws.send(seq.to_bytes(4, "big") + b"\x00")
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id, "frames": 4}))
+
+            while True:
+                data = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+                if data.get("type") == "ack":
+                    break
+            if data.get("received") == 2 and data.get("missing") == 2:
+                result.ok("T1.4b 帧序号", f"ack={data}")
+            else:
+                result.fail("T1.4b 帧序号", f"unexpected ack: {data}")
+    except Exception as exc:
+        result.fail("T1.4b 帧序号", str(exc))
+
+
+async def test_msgpack_events(result: TestResult) -> None:
+    """T1.4c: MessagePack 事件编码"""
+    print("\nT1.4c: MessagePack 事件编码")
+    trace_id = "m1p2k3"
+    try:
+        import msgpack
+        import websockets
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "msgpack" not in (pong.get("features") or []):
+                result.warn("T1.4c MessagePack", "server does not advertise msgpack")
+                return
+
+            await ws.send(
+                json.dumps(
+                    {
+                        "type": "start",
+                        "trace_id": trace_id,
+                        "sample_rate": 48000,
+                        "context": {"app_name": "TestScript", "window_title": "test"},
+                        "use_cloud_api": False,
+                        "wire_format": "msgpack",
+                    }
+                )
+            )
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id}))
+            response = await asyncio.wait_for(ws.recv(), timeout=FIRST_INFERENCE_TIMEOUT)
+            if not isinstance(response, bytes):
+                result.fail("T1.4c MessagePack", f"expected binary frame, got: {response!r}")
+                return
+            data = msgpack.unpackb(response)
+            if data.get("type") in ("fast_text", "error") and data.get("trace_id") == trace_id:
+                result.ok("T1.4c MessagePack", f"type={data.get('type')} bytes={len(response)}")
+            else:
+                result.fail("T1.4c MessagePack", f"unexpected: {data}")
+    except Exception as exc:
+        result.fail("T1.4c MessagePack", str(exc))
+
+
+async def test_e2e_session(result: TestResult) -> None:
+    """T1.4d: 端到端加密会话"""
+    print("\nT1.4d: 端到端加密会话")
+    trace_id = "e2e9k1"
+    try:
+        import base64
+
+        import websockets
+        from cryptography.hazmat.primitives import serialization
+        from cryptography.hazmat.primitives.asymmetric.x25519 import X25519PrivateKey, X25519PublicKey
+        from cryptography.hazmat.primitives.hashes import SHA256
+        from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+
+        from app.utils.e2e import HKDF_INFO, E2eChannel
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "e2e" not in (pong.get("features") or []) or not pong.get("e2e_key"):
+                result.warn("T1.4d E2E", "server does not advertise e2e")
+                return
+
+            # 客户端一侧：临时密钥交换，方向与服务器相反
+            secret = X25519PrivateKey.generate()
+            server_public = X25519PublicKey.from_public_bytes(base64.b64decode(pong["e2e_key"]))
+            okm = HKDF(algorithm=SHA256(), length=64, salt=trace_id.encode(), info=HKDF_INFO).derive(
+                secret.exchange(server_public)
+            )
+            channel = E2eChannel(recv_key=okm[32:], send_key=okm[:32])
+            public = secret.public_key().public_bytes(serialization.Encoding.Raw, serialization.PublicFormat.Raw)
+            context = channel.seal(json.dumps({"app_name": "TestScript", "window_title": "secret"}).encode())
+
+            await ws.send(
+                json.dumps(
+                    {
+                        "type": "start",
+                        "trace_id": trace_id,
+                        "sample_rate": 48000,
+                        "context": {"app_name": "", "window_title": ""},
+                        "use_cloud_api": False,
+                        "e2e": {
+                            "public_key": base64.b64encode(public).decode(),
+                            "context": base64.b64encode(context).decode(),
+                        },
+                    }
+                )
+            )
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id}))
+            response = await asyncio.wait_for(ws.recv(), timeout=FIRST_INFERENCE_TIMEOUT)
+            if not isinstance(response, bytes):
+                result.fail("T1.4d E2E", f"expected encrypted frame, got: {response!r}")
+                return
+            data = json.loads(channel.open(response))
+            if data.get("type") in ("fast_text", "error") and data.get("trace_id") == trace_id:
+                result.ok("T1.4d E2E", f"type={data.get('type')} bytes={len(response)}")
+            else:
+                result.fail("T1.4d E2E", f"unexpected: {data}")
+    except Exception as exc:
+        result.fail("T1.4d E2E", str(exc))
+
+
+async def test_resume_session(result: TestResult) -> None:
+    """T1.4e: 断线后续传会话"""
+    print("\nT1.4e: 断线后续传会话")
+    trace_id = "r3s7m1"
+    start = {
+        "type": "start",
+        "trace_id": trace_id,
+        "sample_rate": 48000,
+        "context": {"app_name": "TestScript", "window_title": "test"},
+        "use_cloud_api": False,
+        "seq_frames": True,
+    }
+    try:
+        import websockets
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "resume" not in (pong.get("features") or []):
+                result.warn("T1.4e 续传", "server does not advertise resume")
+                return
+            await ws.send(json.dumps(start))
+            for seq in (0, 1, 2):
+                await ws.send(seq.to_bytes(4, "big") + b"\x00")
+        # 不发 stop 直接断开，服务器应保留会话
+        await asyncio.sleep(0.2)
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({**start, "resume": True}))
+            data = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if data.get("type") != "resumed" or data.get("received") != 3:
+                result.fail("T1.4e 续传", f"unexpected: {data}")
+                return
+            await ws.send((3).to_bytes(4, "big") + b"\x00")
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id, "frames": 4}))
+            while True:
+                data = json.loads(await asyncio.wait_for(ws.recv(), timeout=FIRST_INFERENCE_TIMEOUT))
+                if data.get("type") == "ack":
+                    break
+            if data.get("received") == 4 and data.get("missing") == 0:
+                result.ok("T1.4e 续传", f"ack={data}")
+            else:
+                result.fail("T1.4e 续传", f"unexpected ack: {data}")
+    except Exception as exc:
+        result.fail("T1.4e 续传", str(exc))
+
+
+async def test_cancel_session(result: TestResult) -> None:
+    """T1.4f: 取消会话后不回结果"""
+    print("\nT1.4f: 取消会话")
+    trace_id = "c4nc31"
+    try:
+        import websockets
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "cancel" not in (pong.get("features") or []):
+                result.warn("T1.4f 取消", "server does not advertise cancel")
+                return
+            await ws.send(
+                json.dumps(
+                    {
+                        "type": "start",
+                        "trace_id": trace_id,
+                        "sample_rate": 48000,
+                        "context": {"app_name": "TestScript", "window_title": "test"},
+                        "use_cloud_api": False,
+                    }
+                )
+            )
+            await ws.send(b"\x00")
+            await ws.send(json.dumps({"type": "cancel", "trace_id": trace_id}))
+            # 取消后的 stop 应被当作未 start
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id}))
+            data = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if data.get("type") == "error" and "stop before start" in str(data.get("message")):
+                result.ok("T1.4f 取消")
+            else:
+                result.fail("T1.4f 取消", f"unexpected: {data}")
+    except Exception as exc:
+        result.fail("T1.4f 取消", str(exc))
+
+
+async def test_pcm_session(result: TestResult) -> None:
+    """T1.4g: 原始 PCM 会话"""
+    print("\nT1.4g: 原始 PCM 会话")
+    trace_id = "p3m4c5"
+    try:
+        import websockets
+
+        async with websockets.connect(WS_URL) as ws:
+            await ws.send(json.dumps({"type": "ping"}))
+            pong = json.loads(await asyncio.wait_for(ws.recv(), timeout=NORMAL_TIMEOUT))
+            if "pcm_s16le" not in (pong.get("codecs") or []):
+                result.warn("T1.4g 原始 PCM", "server does not advertise pcm_s16le")
+                return
+            await ws.send(
+                json.dumps(
+                    {
+                        "type": "start",
+                        "trace_id": trace_id,
+                        "sample_rate": 16000,
+                        "context": {"app_name": "TestScript", "window_title": "test"},
+                        "use_cloud_api": False,
+                        "codec": "pcm_s16le",
+                    }
+                )
+            )
+            # 两个 20ms 帧；帧头声明的采样数与实际不符的帧被丢弃
+            for _ in range(2):
+                await ws.send(bytes([1, 1]) + (320).to_bytes(2, "big") + b"\x00\x00" * 320)
+            await ws.send(bytes([1, 1]) + (320).to_bytes(2, "big") + b"\x00\x00")
+            await ws.send(json.dumps({"type": "stop", "trace_id": trace_id}))
+            data = json.loads(await asyncio.wait_for(ws.recv(), timeout=FIRST_INFERENCE_TIMEOUT))
+            if data.get("type") != "fast_text":
+                result.fail("T1.4g 原始 PCM", f"unexpected: {data}")
+            elif "pcm_bytes=" in data.get("content", "") and "pcm_bytes=1280 " not in data["content"]:
+                result.fail("T1.4g 原始 PCM", f"unexpected audio length: {data['content']}")
+            else:
+                result.ok("T1.4g 原始 PCM", f"content={data.get('content')}")
+    except Exception as exc:
+        result.fail("T1.4g 原始 PCM", str(exc))
+
+
 async def test_audio_decode_pipeline(result: TestResult) -> None:
     """T1.5: 音频解码管道测试"""
     print("\nT1.5: 音频解码管道")
@@ -263,6 +533,12 @@ async def main() -> None:
     await test_websocket_connect(result)
     await test_ping_pong(result)
     await test_start_stop_flow(result)
+    await test_seq_frames_ack(result)
+    await test_msgpack_events(result)
+    await test_e2e_session(result)
+    await test_resume_session(result)
+    await test_cancel_session(result)
+    await test_pcm_session(result)
     await test_audio_decode_pipeline(result)
 
     engine = await test_asr_engine_load(result)